@@ -22,10 +22,10 @@ use std::{any::Any, iter::FromIterator};
 
 use super::{
     array::print_long_array, raw_pointer::as_aligned_pointer, raw_pointer::RawPtrBox,
-    Array, ArrayData, ArrayDataRef, FixedSizeListArray, GenericBinaryIter,
-    GenericListArray, LargeListArray, ListArray, OffsetSizeTrait,
+    Array, ArrayData, ArrayDataRef, DecimalBuilder, FixedSizeListArray,
+    GenericBinaryIter, GenericListArray, LargeListArray, ListArray, OffsetSizeTrait,
 };
-use crate::util::bit_util;
+use crate::util::{bit_util, decimal};
 use crate::{buffer::Buffer, datatypes::ToByteSlice};
 use crate::{buffer::MutableBuffer, datatypes::DataType};
 
@@ -475,9 +475,38 @@ impl DecimalArray {
         i128::from_be_bytes(result)
     }
 
-    /// Returns the byte size per value for Decimal arrays with a given precision
+    /// Returns the element at index `i` formatted with its scale, e.g. `-123.45`.
+    pub fn value_as_string(&self, i: usize) -> String {
+        decimal::format_decimal(self.value(i), self.scale)
+    }
+
+    /// Returns the precision of the decimals of this array
+    pub fn precision(&self) -> usize {
+        self.precision
+    }
+
+    /// Returns the scale of the decimals of this array
+    pub fn scale(&self) -> usize {
+        self.scale
+    }
+
+    /// Creates a `DecimalArray` of the given precision and scale from a vector of
+    /// optional values.
+    pub fn from_opt_vec(data: Vec<Option<i128>>, precision: usize, scale: usize) -> Self {
+        let mut builder = DecimalBuilder::new(data.len(), precision, scale);
+        for value in data {
+            match value {
+                Some(v) => builder.append_value(v).unwrap(),
+                None => builder.append_null().unwrap(),
+            }
+        }
+        builder.finish()
+    }
+
+    /// Returns the byte size per value for Decimal arrays with a given precision, which
+    /// holds the digits of the precision and a sign bit
     pub fn calc_fixed_byte_size(precision: usize) -> i32 {
-        (10.0_f64.powi(precision as i32).log2() / 8.0).ceil() as i32
+        ((10.0_f64.powi(precision as i32).log2() + 1.0) / 8.0).ceil() as i32
     }
 
     /// Returns the offset for the element at index `i`.
@@ -981,4 +1010,22 @@ mod tests {
             format!("{:?}", arr)
         );
     }
+
+    #[test]
+    fn test_decimal_array_from_opt_vec() {
+        let arr = DecimalArray::from_opt_vec(vec![Some(12345), None, Some(-5)], 5, 2);
+        assert_eq!(&DataType::Decimal(5, 2), arr.data_type());
+        assert_eq!((5, 2), (arr.precision(), arr.scale()));
+        assert_eq!(3, arr.len());
+        assert!(arr.is_null(1));
+        assert_eq!("123.45", arr.value_as_string(0));
+        assert_eq!("-0.05", arr.value_as_string(2));
+
+        // the largest values of a precision keep their sign
+        let arr =
+            DecimalArray::from_opt_vec(vec![Some(9_999_999), Some(-9_999_999)], 7, 0);
+        assert_eq!(4, arr.value_length());
+        assert_eq!(9_999_999, arr.value(0));
+        assert_eq!(-9_999_999, arr.value(1));
+    }
 }
@@ -78,6 +78,12 @@ where
     Box::new(move |i, j| left.value(i).cmp(&right.value(j)))
 }
 
+fn compare_decimal<'a>(left: &'a Array, right: &'a Array) -> DynComparator<'a> {
+    let left = left.as_any().downcast_ref::<DecimalArray>().unwrap();
+    let right = right.as_any().downcast_ref::<DecimalArray>().unwrap();
+    Box::new(move |i, j| left.value(i).cmp(&right.value(j)))
+}
+
 fn compare_dict_string<'a, T>(left: &'a Array, right: &'a Array) -> DynComparator<'a>
 where
     T: ArrowDictionaryKeyType,
@@ -186,6 +192,11 @@ pub fn build_compare<'a>(left: &'a Array, right: &'a Array) -> Result<DynCompara
         }
         (Utf8, Utf8) => compare_string::<i32>(left, right),
         (LargeUtf8, LargeUtf8) => compare_string::<i64>(left, right),
+        (Decimal(_, left_scale), Decimal(_, right_scale))
+            if left_scale == right_scale =>
+        {
+            compare_decimal(left, right)
+        }
         (
             Dictionary(key_type_lhs, value_type_lhs),
             Dictionary(key_type_rhs, value_type_rhs),
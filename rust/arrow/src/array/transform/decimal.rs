@@ -0,0 +1,49 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::array::{ArrayData, DecimalArray};
+use crate::datatypes::DataType;
+
+use super::{Extend, ExtendNulls, _MutableArrayData};
+
+/// The number of bytes of every value of a decimal type
+pub(super) fn byte_width(data_type: &DataType) -> usize {
+    match data_type {
+        DataType::Decimal(precision, _) => {
+            DecimalArray::calc_fixed_byte_size(*precision) as usize
+        }
+        _ => unreachable!(),
+    }
+}
+
+pub(super) fn build_extend(array: &ArrayData) -> Extend {
+    let size = byte_width(array.data_type());
+    let values = &array.buffers()[0].data()[array.offset() * size..];
+    Box::new(
+        move |mutable: &mut _MutableArrayData, _, start: usize, len: usize| {
+            let bytes = &values[start * size..(start + len) * size];
+            mutable.buffers[0].extend_from_slice(bytes);
+        },
+    )
+}
+
+pub(super) fn build_extend_nulls(data_type: &DataType) -> ExtendNulls {
+    let size = byte_width(data_type);
+    Box::new(move |mutable: &mut _MutableArrayData, len: usize| {
+        mutable.buffers[0].extend_from_slice(&vec![0u8; len * size]);
+    })
+}
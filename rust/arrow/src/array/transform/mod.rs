@@ -22,6 +22,7 @@ use crate::{buffer::MutableBuffer, datatypes::DataType, util::bit_util};
 use super::{ArrayData, ArrayDataRef};
 
 mod boolean;
+mod decimal;
 mod list;
 mod primitive;
 mod utils;
@@ -204,6 +205,7 @@ fn build_extend(array: &ArrayData) -> Extend {
         DataType::LargeUtf8 | DataType::LargeBinary => {
             variable_size::build_extend::<i64>(array)
         }
+        DataType::Decimal(_, _) => decimal::build_extend(array),
         DataType::List(_) => list::build_extend::<i32>(array),
         DataType::LargeList(_) => list::build_extend::<i64>(array),
         DataType::Dictionary(child_data_type, _) => match child_data_type.as_ref() {
@@ -231,6 +233,9 @@ fn build_extend(array: &ArrayData) -> Extend {
 
 fn build_extend_nulls(data_type: &DataType) -> ExtendNulls {
     use crate::datatypes::*;
+    if let DataType::Decimal(_, _) = data_type {
+        return decimal::build_extend_nulls(data_type);
+    }
     Box::new(match data_type {
         DataType::Boolean => boolean::extend_nulls,
         DataType::UInt8 => primitive::extend_nulls::<u8>,
@@ -322,6 +327,11 @@ impl<'a> MutableArrayData<'a> {
             DataType::Interval(IntervalUnit::DayTime) => {
                 vec![MutableBuffer::new(capacity * size_of::<i64>())]
             }
+            DataType::Decimal(_, _) => {
+                vec![MutableBuffer::new(
+                    capacity * decimal::byte_width(data_type),
+                )]
+            }
             DataType::Utf8 | DataType::Binary => {
                 let mut buffer = MutableBuffer::new((1 + capacity) * size_of::<i32>());
                 buffer.extend_from_slice(&[0i32].to_byte_slice());
@@ -385,6 +395,7 @@ impl<'a> MutableArrayData<'a> {
             | DataType::LargeUtf8
             | DataType::LargeBinary
             | DataType::Interval(_)
+            | DataType::Decimal(_, _)
             | DataType::FixedSizeBinary(_) => vec![],
             DataType::List(_) | DataType::LargeList(_) => {
                 let childs = arrays
@@ -465,8 +476,8 @@ mod tests {
     use super::*;
 
     use crate::array::{
-        Array, ArrayDataRef, BooleanArray, DictionaryArray, Int16Array, Int16Type,
-        Int64Builder, ListBuilder, PrimitiveBuilder, StringArray,
+        Array, ArrayDataRef, BooleanArray, DecimalArray, DictionaryArray, Int16Array,
+        Int16Type, Int64Builder, ListBuilder, PrimitiveBuilder, StringArray,
         StringDictionaryBuilder, UInt8Array,
     };
     use crate::{array::ListArray, error::Result};
@@ -653,6 +664,27 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_decimal() {
+        let array =
+            DecimalArray::from_opt_vec(vec![Some(1), Some(-2), None, Some(4)], 9, 2);
+        let array = array.slice(1, 3).data();
+        let arrays = vec![array.as_ref()];
+
+        let mut mutable = MutableArrayData::new(arrays, true, 0);
+
+        mutable.extend(0, 0, 2);
+        mutable.extend_nulls(1);
+        mutable.extend(0, 2, 3);
+
+        let result = mutable.freeze();
+        let result = DecimalArray::from(Arc::new(result));
+
+        let expected =
+            DecimalArray::from_opt_vec(vec![Some(-2), None, None, Some(4)], 9, 2);
+        assert_eq!(result, expected);
+    }
+
     fn create_dictionary_array(values: &[&str], keys: &[Option<&str>]) -> ArrayDataRef {
         let values = StringArray::from(values.to_vec());
         let mut builder = StringDictionaryBuilder::new_with_dictionary(
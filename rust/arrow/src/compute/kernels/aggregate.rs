@@ -19,8 +19,11 @@
 
 use std::ops::Add;
 
-use crate::array::{Array, GenericStringArray, PrimitiveArray, StringOffsetSizeTrait};
+use crate::array::{
+    Array, DecimalArray, GenericStringArray, PrimitiveArray, StringOffsetSizeTrait,
+};
 use crate::datatypes::ArrowNumericType;
+use crate::error::{ArrowError, Result};
 
 /// Helper macro to perform min/max of strings
 fn min_max_string<T: StringOffsetSizeTrait, F: Fn(&str, &str) -> bool>(
@@ -87,6 +90,44 @@ pub fn min_string<T: StringOffsetSizeTrait>(
     min_max_string(array, |a, b| a > b)
 }
 
+/// Returns the minimum value in the decimal array, in the array's scale.
+pub fn min_decimal(array: &DecimalArray) -> Option<i128> {
+    (0..array.len())
+        .filter(|i| array.is_valid(*i))
+        .map(|i| array.value(i))
+        .min()
+}
+
+/// Returns the maximum value in the decimal array, in the array's scale.
+pub fn max_decimal(array: &DecimalArray) -> Option<i128> {
+    (0..array.len())
+        .filter(|i| array.is_valid(*i))
+        .map(|i| array.value(i))
+        .max()
+}
+
+/// Returns the sum of values in the decimal array, in the array's scale.
+///
+/// Returns `Ok(None)` if the array is empty or only contains null values, and an error
+/// if the sum overflows an `i128`.
+pub fn sum_decimal(array: &DecimalArray) -> Result<Option<i128>> {
+    let mut sum: Option<i128> = None;
+    for i in 0..array.len() {
+        if array.is_valid(i) {
+            let value =
+                sum.unwrap_or(0)
+                    .checked_add(array.value(i))
+                    .ok_or_else(|| {
+                        ArrowError::ComputeError(
+                            "Overflow in sum of decimals".to_string(),
+                        )
+                    })?;
+            sum = Some(value);
+        }
+    }
+    Ok(sum)
+}
+
 /// Helper function to perform min/max lambda function on values from a numeric array.
 fn min_max_helper<T, F>(array: &PrimitiveArray<T>, cmp: F) -> Option<T::Native>
 where
@@ -270,6 +311,23 @@ mod tests {
         assert_eq!(15, sum(&a).unwrap());
     }
 
+    #[test]
+    fn test_decimal_array_aggregates() -> Result<()> {
+        let a =
+            DecimalArray::from_opt_vec(vec![Some(150), None, Some(-225), Some(5)], 5, 2);
+        assert_eq!(Some(-70), sum_decimal(&a)?);
+        assert_eq!(Some(-225), min_decimal(&a));
+        assert_eq!(Some(150), max_decimal(&a));
+
+        let a = DecimalArray::from_opt_vec(vec![None, None], 5, 2);
+        assert_eq!(None, sum_decimal(&a)?);
+        assert_eq!(None, min_decimal(&a));
+
+        let a = DecimalArray::from_opt_vec(vec![Some(i128::MAX), Some(1)], 38, 0);
+        assert!(sum_decimal(&a).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_primitive_array_float_sum() {
         let a = Float64Array::from(vec![1.1, 2.2, 3.3, 4.4, 5.5]);
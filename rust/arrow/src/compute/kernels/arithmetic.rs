@@ -40,8 +40,9 @@ use crate::compute::util::combine_option_bitmap;
 #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "simd"))]
 use crate::compute::util::simd_load_set_invalid;
 use crate::datatypes;
-use crate::datatypes::ToByteSlice;
+use crate::datatypes::{DataType, ToByteSlice};
 use crate::error::{ArrowError, Result};
+use crate::util::decimal;
 use crate::{array::*, util::bit_util};
 
 /// Helper function to perform math lambda function on values from two arrays. If either
@@ -348,6 +349,168 @@ where
     math_divide(&left, &right)
 }
 
+/// Returns the precision and scale of a decimal data type
+fn decimal_precision_scale(data_type: &DataType) -> Result<(usize, usize)> {
+    match data_type {
+        DataType::Decimal(precision, scale) => Ok((*precision, *scale)),
+        other => Err(ArrowError::ComputeError(format!(
+            "Expected a decimal data type, got {:?}",
+            other
+        ))),
+    }
+}
+
+/// Limits a decimal result type to [decimal::MAX_DECIMAL_PRECISION], giving up fractional
+/// digits for integer digits while keeping a scale of at least 6 (or of the original
+/// scale when that is smaller), which is the rule Hive and Spark use.
+fn bounded_decimal_type(precision: usize, scale: usize) -> DataType {
+    if precision <= decimal::MAX_DECIMAL_PRECISION {
+        DataType::Decimal(precision, scale)
+    } else {
+        let integer_digits = precision - scale;
+        let min_scale = scale.min(6);
+        let scale = decimal::MAX_DECIMAL_PRECISION
+            .saturating_sub(integer_digits)
+            .max(min_scale);
+        DataType::Decimal(decimal::MAX_DECIMAL_PRECISION, scale)
+    }
+}
+
+/// Returns the decimal type of `left + right` and `left - right`, which has the larger
+/// of both scales and one more integer digit than the larger of both.
+pub fn add_decimal_type(left: &DataType, right: &DataType) -> Result<DataType> {
+    let (p1, s1) = decimal_precision_scale(left)?;
+    let (p2, s2) = decimal_precision_scale(right)?;
+    let scale = s1.max(s2);
+    let precision = (p1 - s1).max(p2 - s2) + scale + 1;
+    Ok(bounded_decimal_type(precision, scale))
+}
+
+/// Returns the decimal type of `left * right`, which has the sum of both scales and
+/// both precisions.
+pub fn multiply_decimal_type(left: &DataType, right: &DataType) -> Result<DataType> {
+    let (p1, s1) = decimal_precision_scale(left)?;
+    let (p2, s2) = decimal_precision_scale(right)?;
+    Ok(bounded_decimal_type(p1 + p2 + 1, s1 + s2))
+}
+
+/// Returns the decimal type of `left / right`, which has a scale of at least 6.
+pub fn divide_decimal_type(left: &DataType, right: &DataType) -> Result<DataType> {
+    let (p1, s1) = decimal_precision_scale(left)?;
+    let (p2, s2) = decimal_precision_scale(right)?;
+    let scale = (s1 + p2 + 1).max(6);
+    let precision = p1 - s1 + s2 + scale;
+    Ok(bounded_decimal_type(precision, scale))
+}
+
+/// Helper function to perform a decimal math operation that returns a value of the
+/// precision and scale of `result_type`, or an error when it overflows.
+fn decimal_math_op<F>(
+    left: &DecimalArray,
+    right: &DecimalArray,
+    result_type: DataType,
+    op: F,
+) -> Result<DecimalArray>
+where
+    F: Fn(i128, i128, usize) -> Result<Option<i128>>,
+{
+    if left.len() != right.len() {
+        return Err(ArrowError::ComputeError(
+            "Cannot perform math operation on arrays of different length".to_string(),
+        ));
+    }
+    let (precision, scale) = decimal_precision_scale(&result_type)?;
+    let mut builder = DecimalBuilder::new(left.len(), precision, scale);
+    for i in 0..left.len() {
+        if left.is_null(i) || right.is_null(i) {
+            builder.append_null()?;
+            continue;
+        }
+        let value = op(left.value(i), right.value(i), scale)?
+            .filter(|v| decimal::fits_precision(*v, precision))
+            .ok_or_else(|| {
+                ArrowError::ComputeError(format!(
+                    "Overflow in decimal operation on {} and {} with result type {:?}",
+                    left.value_as_string(i),
+                    right.value_as_string(i),
+                    result_type
+                ))
+            })?;
+        builder.append_value(value)?;
+    }
+    Ok(builder.finish())
+}
+
+/// Perform `left + right` operation on two decimal arrays, returning an array of the type
+/// given by [add_decimal_type]. If either left or right value is null then the result
+/// is also null. Values that overflow the result type return an error.
+pub fn add_decimal(left: &DecimalArray, right: &DecimalArray) -> Result<DecimalArray> {
+    let (s1, s2) = (left.scale(), right.scale());
+    let result_type = add_decimal_type(left.data_type(), right.data_type())?;
+    decimal_math_op(left, right, result_type, |a, b, scale| {
+        let common = s1.max(s2);
+        Ok(decimal::rescale(a, s1, common)
+            .zip(decimal::rescale(b, s2, common))
+            .and_then(|(a, b)| a.checked_add(b))
+            .and_then(|v| decimal::rescale(v, common, scale)))
+    })
+}
+
+/// Perform `left - right` operation on two decimal arrays, returning an array of the type
+/// given by [add_decimal_type]. If either left or right value is null then the result
+/// is also null. Values that overflow the result type return an error.
+pub fn subtract_decimal(
+    left: &DecimalArray,
+    right: &DecimalArray,
+) -> Result<DecimalArray> {
+    let (s1, s2) = (left.scale(), right.scale());
+    let result_type = add_decimal_type(left.data_type(), right.data_type())?;
+    decimal_math_op(left, right, result_type, |a, b, scale| {
+        let common = s1.max(s2);
+        Ok(decimal::rescale(a, s1, common)
+            .zip(decimal::rescale(b, s2, common))
+            .and_then(|(a, b)| a.checked_sub(b))
+            .and_then(|v| decimal::rescale(v, common, scale)))
+    })
+}
+
+/// Perform `left * right` operation on two decimal arrays, returning an array of the type
+/// given by [multiply_decimal_type]. If either left or right value is null then the
+/// result is also null. Values that overflow the result type return an error.
+pub fn multiply_decimal(
+    left: &DecimalArray,
+    right: &DecimalArray,
+) -> Result<DecimalArray> {
+    let (s1, s2) = (left.scale(), right.scale());
+    let result_type = multiply_decimal_type(left.data_type(), right.data_type())?;
+    decimal_math_op(left, right, result_type, |a, b, scale| {
+        Ok(a.checked_mul(b)
+            .and_then(|v| decimal::rescale(v, s1 + s2, scale)))
+    })
+}
+
+/// Perform `left / right` operation on two decimal arrays, returning an array of the type
+/// given by [divide_decimal_type], rounded half away from zero. If either left or right
+/// value is null then the result is also null. If any right hand value is zero then the
+/// result of this operation will be `Err(ArrowError::DivideByZero)`.
+pub fn divide_decimal(left: &DecimalArray, right: &DecimalArray) -> Result<DecimalArray> {
+    let (s1, s2) = (left.scale(), right.scale());
+    let result_type = divide_decimal_type(left.data_type(), right.data_type())?;
+    decimal_math_op(left, right, result_type, |a, b, scale| {
+        if b == 0 {
+            return Err(ArrowError::DivideByZero);
+        }
+        // a / 10^s1 / (b / 10^s2) = a * 10^(s2 + scale - s1) / b / 10^scale
+        Ok(if s2 + scale >= s1 {
+            a.checked_mul(decimal::pow10(s2 + scale - s1))
+                .map(|a| decimal::div_round(a, b))
+        } else {
+            b.checked_mul(decimal::pow10(s1 - s2 - scale))
+                .map(|b| decimal::div_round(a, b))
+        })
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -548,4 +711,58 @@ mod tests {
         assert_eq!(true, c.is_null(3));
         assert_eq!(13, c.value(2));
     }
+
+    #[test]
+    fn test_decimal_array_arithmetic() -> Result<()> {
+        let a = DecimalArray::from_opt_vec(vec![Some(12345), None, Some(-100)], 5, 2);
+        let b = DecimalArray::from_opt_vec(vec![Some(15), Some(20), Some(5)], 4, 1);
+
+        let c = add_decimal(&a, &b)?;
+        assert_eq!(&DataType::Decimal(6, 2), c.data_type());
+        assert_eq!(12495, c.value(0));
+        assert!(c.is_null(1));
+        assert_eq!(-50, c.value(2));
+
+        let c = subtract_decimal(&a, &b)?;
+        assert_eq!(&DataType::Decimal(6, 2), c.data_type());
+        assert_eq!(12195, c.value(0));
+        assert_eq!(-150, c.value(2));
+
+        let c = multiply_decimal(&a, &b)?;
+        assert_eq!(&DataType::Decimal(10, 3), c.data_type());
+        assert_eq!(185175, c.value(0));
+        assert_eq!(-500, c.value(2));
+
+        let c = divide_decimal(&a, &b)?;
+        assert_eq!(&DataType::Decimal(11, 7), c.data_type());
+        assert_eq!(823000000, c.value(0));
+        assert_eq!(-20000000, c.value(2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_decimal_array_divide_rounds() -> Result<()> {
+        let a = DecimalArray::from_opt_vec(vec![Some(1), Some(-2)], 1, 0);
+        let b = DecimalArray::from_opt_vec(vec![Some(3), Some(3)], 1, 0);
+        let c = divide_decimal(&a, &b)?;
+        assert_eq!(&DataType::Decimal(7, 6), c.data_type());
+        assert_eq!(333333, c.value(0));
+        assert_eq!(-666667, c.value(1));
+
+        let b = DecimalArray::from_opt_vec(vec![Some(3), Some(0)], 1, 0);
+        let e = divide_decimal(&a, &b).err().expect("should have failed");
+        assert_eq!("Divide by zero error", e.to_string());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decimal_array_overflow() -> Result<()> {
+        let max = DataType::Decimal(38, 10);
+        assert_eq!(DataType::Decimal(38, 6), multiply_decimal_type(&max, &max)?);
+        assert_eq!(DataType::Decimal(38, 9), add_decimal_type(&max, &max)?);
+
+        let a = DecimalArray::from_opt_vec(vec![Some(9 * decimal::pow10(37))], 38, 0);
+        assert!(add_decimal(&a, &a).is_err());
+        Ok(())
+    }
 }
@@ -42,6 +42,7 @@ use crate::buffer::Buffer;
 use crate::compute::kernels::arithmetic::{divide, multiply};
 use crate::datatypes::*;
 use crate::error::{ArrowError, Result};
+use crate::util::decimal;
 use crate::{array::*, compute::take};
 
 /// Return true if a value of type `from_type` can be cast into a
@@ -68,6 +69,10 @@ pub fn can_cast_types(from_type: &DataType, to_type: &DataType) -> bool {
         (Dictionary(_, value_type), _) => can_cast_types(value_type, to_type),
         (_, Dictionary(_, value_type)) => can_cast_types(from_type, value_type),
 
+        (Decimal(_, _), Decimal(_, _)) => true,
+        (Decimal(_, _), _) => DataType::is_numeric(to_type) || to_type == &Utf8,
+        (_, Decimal(_, _)) => DataType::is_numeric(from_type) || from_type == &Utf8,
+
         (_, Boolean) => DataType::is_numeric(from_type),
         (Boolean, _) => DataType::is_numeric(to_type) || to_type == &Utf8,
         (Utf8, _) => DataType::is_numeric(to_type),
@@ -220,6 +225,9 @@ pub fn can_cast_types(from_type: &DataType, to_type: &DataType) -> bool {
 /// * Time32 and Time64: precision lost when going to higher interval
 /// * Timestamp and Date{32|64}: precision lost when going to higher interval
 /// * Temporal to/from backing primitive: zero-copy with data type change
+/// * Decimal to/from numeric and decimal: rounded half away from zero, values that do
+///   not fit the target type return null
+/// * Utf8 to decimal: strings that can't be parsed or don't fit the precision return null
 ///
 /// Unsupported Casts
 /// * To or from `StructArray`
@@ -316,6 +324,49 @@ pub fn cast(array: &ArrayRef, to_type: &DataType) -> Result<ArrayRef> {
                 from_type, to_type,
             ))),
         },
+        (Decimal(_, _), Decimal(precision, scale)) => {
+            cast_decimal_to_decimal(array, *precision, *scale)
+        }
+        (Decimal(_, _), Utf8) => {
+            let from = array.as_any().downcast_ref::<DecimalArray>().unwrap();
+            let mut b = StringBuilder::new(array.len());
+            for i in 0..array.len() {
+                if array.is_null(i) {
+                    b.append_null()?;
+                } else {
+                    b.append_value(&from.value_as_string(i))?;
+                }
+            }
+
+            Ok(Arc::new(b.finish()) as ArrayRef)
+        }
+        (Decimal(_, _), _) if DataType::is_numeric(to_type) => {
+            cast_decimal_to_numeric(array, to_type)
+        }
+        (Utf8, Decimal(precision, scale)) => {
+            let from = array.as_any().downcast_ref::<StringArray>().unwrap();
+            let values = (0..from.len())
+                .map(|i| {
+                    if from.is_null(i) {
+                        None
+                    } else {
+                        decimal::parse_decimal(from.value(i), *precision, *scale).ok()
+                    }
+                })
+                .collect();
+            Ok(Arc::new(DecimalArray::from_opt_vec(
+                values, *precision, *scale,
+            )))
+        }
+        (_, Decimal(precision, scale)) if DataType::is_numeric(from_type) => {
+            cast_numeric_to_decimal(array, *precision, *scale)
+        }
+        (Decimal(_, _), _) | (_, Decimal(_, _)) => {
+            Err(ArrowError::ComputeError(format!(
+                "Casting from {:?} to {:?} not supported",
+                from_type, to_type,
+            )))
+        }
         (_, Boolean) => match from_type {
             UInt8 => cast_numeric_to_bool::<UInt8Type>(array),
             UInt16 => cast_numeric_to_bool::<UInt16Type>(array),
@@ -910,6 +961,103 @@ where
         .collect()
 }
 
+/// Cast a decimal array to another precision and scale, rounding half away from zero
+/// when the scale decreases. Values that do not fit the precision become null.
+fn cast_decimal_to_decimal(
+    array: &ArrayRef,
+    precision: usize,
+    scale: usize,
+) -> Result<ArrayRef> {
+    let from = array.as_any().downcast_ref::<DecimalArray>().unwrap();
+    let from_scale = from.scale();
+    let values = (0..from.len())
+        .map(|i| {
+            if from.is_null(i) {
+                None
+            } else {
+                decimal::rescale(from.value(i), from_scale, scale)
+                    .filter(|v| decimal::fits_precision(*v, precision))
+            }
+        })
+        .collect();
+    Ok(Arc::new(DecimalArray::from_opt_vec(
+        values, precision, scale,
+    )))
+}
+
+/// Cast a decimal array to a numeric type, through `Float64` for floating point types
+/// and through `Int64` for integer types. Integers are rounded half away from zero,
+/// values that do not fit the target type become null.
+fn cast_decimal_to_numeric(array: &ArrayRef, to_type: &DataType) -> Result<ArrayRef> {
+    let from = array.as_any().downcast_ref::<DecimalArray>().unwrap();
+    let scale = from.scale();
+    let values = (0..from.len()).map(|i| {
+        if from.is_null(i) {
+            None
+        } else {
+            Some(from.value(i))
+        }
+    });
+    let array: ArrayRef = match to_type {
+        DataType::Float32 | DataType::Float64 => {
+            let divisor = decimal::pow10(scale) as f64;
+            Arc::new(
+                values
+                    .map(|v| v.map(|v| v as f64 / divisor))
+                    .collect::<Float64Array>(),
+            )
+        }
+        _ => Arc::new(
+            values
+                .map(|v| {
+                    v.and_then(|v| decimal::rescale(v, scale, 0))
+                        .and_then(num::cast::cast::<i128, i64>)
+                })
+                .collect::<Int64Array>(),
+        ),
+    };
+    cast(&array, to_type)
+}
+
+/// Cast a numeric array to a decimal, through `Float64` for floating point types and
+/// through `Int64` for integer types. Values that do not fit the precision become null.
+fn cast_numeric_to_decimal(
+    array: &ArrayRef,
+    precision: usize,
+    scale: usize,
+) -> Result<ArrayRef> {
+    let multiplier = decimal::pow10(scale);
+    let values: Vec<Option<i128>> = match array.data_type() {
+        DataType::Float32 | DataType::Float64 => {
+            let array = cast(array, &DataType::Float64)?;
+            let array = array.as_any().downcast_ref::<Float64Array>().unwrap();
+            array
+                .iter()
+                .map(|v| {
+                    v.and_then(|v| {
+                        num::cast::cast::<f64, i128>((v * multiplier as f64).round())
+                    })
+                })
+                .collect()
+        }
+        _ => {
+            let array = cast(array, &DataType::Int64)?;
+            let array = array.as_any().downcast_ref::<Int64Array>().unwrap();
+            array
+                .iter()
+                .map(|v| v.and_then(|v| (v as i128).checked_mul(multiplier)))
+                .collect()
+        }
+    };
+    let values = values
+        .into_iter()
+        .map(|v| v.filter(|v| decimal::fits_precision(*v, precision)))
+        .collect();
+    Ok(Arc::new(DecimalArray::from_opt_vec(
+        values, precision, scale,
+    )))
+}
+
 /// Cast numeric types to Boolean
 ///
 /// Any zero value returns `false` while non-zero returns `true`
@@ -1336,6 +1484,63 @@ mod tests {
         assert_eq!(false, c.is_valid(2));
     }
 
+    #[test]
+    fn test_cast_decimal() {
+        let a = DecimalArray::from_opt_vec(vec![Some(12345), Some(-12355), None], 7, 2);
+        let array = Arc::new(a) as ArrayRef;
+
+        let b = cast(&array, &DataType::Decimal(7, 1)).unwrap();
+        let c = b.as_any().downcast_ref::<DecimalArray>().unwrap();
+        assert_eq!(1235, c.value(0));
+        assert_eq!(-1236, c.value(1));
+        assert!(c.is_null(2));
+
+        // 123.45 does not fit in a precision of 4 with a scale of 2
+        let b = cast(&array, &DataType::Decimal(4, 2)).unwrap();
+        assert_eq!(3, b.null_count());
+
+        let b = cast(&array, &DataType::Int32).unwrap();
+        let c = b.as_any().downcast_ref::<Int32Array>().unwrap();
+        assert_eq!(123, c.value(0));
+        assert_eq!(-124, c.value(1));
+
+        let b = cast(&array, &DataType::Float64).unwrap();
+        let c = b.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert!((123.45 - c.value(0)).abs() < f64::EPSILON);
+
+        let b = cast(&array, &DataType::Utf8).unwrap();
+        let c = b.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!("123.45", c.value(0));
+        assert_eq!("-123.55", c.value(1));
+        assert!(c.is_null(2));
+    }
+
+    #[test]
+    fn test_cast_to_decimal() {
+        let a = Int32Array::from(vec![Some(5), None, Some(-100000)]);
+        let array = Arc::new(a) as ArrayRef;
+        let b = cast(&array, &DataType::Decimal(7, 2)).unwrap();
+        let c = b.as_any().downcast_ref::<DecimalArray>().unwrap();
+        assert_eq!(500, c.value(0));
+        assert!(c.is_null(1));
+        assert!(c.is_null(2));
+
+        let a = Float64Array::from(vec![1.005, -2.5]);
+        let array = Arc::new(a) as ArrayRef;
+        let b = cast(&array, &DataType::Decimal(5, 1)).unwrap();
+        let c = b.as_any().downcast_ref::<DecimalArray>().unwrap();
+        assert_eq!(10, c.value(0));
+        assert_eq!(-25, c.value(1));
+
+        let a = StringArray::from(vec!["1.25", "one", "-0.5"]);
+        let array = Arc::new(a) as ArrayRef;
+        let b = cast(&array, &DataType::Decimal(5, 2)).unwrap();
+        let c = b.as_any().downcast_ref::<DecimalArray>().unwrap();
+        assert_eq!(125, c.value(0));
+        assert!(c.is_null(1));
+        assert_eq!(-50, c.value(2));
+    }
+
     #[test]
     fn test_cast_bool_to_i32() {
         let a = BooleanArray::from(vec![Some(true), Some(false), None]);
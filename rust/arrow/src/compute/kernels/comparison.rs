@@ -23,6 +23,7 @@
 //! [here](https://doc.rust-lang.org/stable/core/arch/) for more information.
 
 use regex::Regex;
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::sync::Arc;
 
@@ -31,7 +32,7 @@ use crate::buffer::{Buffer, MutableBuffer};
 use crate::compute::util::combine_option_bitmap;
 use crate::datatypes::{ArrowNumericType, BooleanType, DataType};
 use crate::error::{ArrowError, Result};
-use crate::util::bit_util;
+use crate::util::{bit_util, decimal};
 
 /// Helper function to perform boolean lambda function on values from two arrays, this
 /// version does not attempt to use SIMD.
@@ -345,6 +346,72 @@ pub fn gt_eq_utf8_scalar(left: &StringArray, right: &str) -> Result<BooleanArray
     compare_op_scalar!(left, right, |a, b| a >= b)
 }
 
+/// Compares two decimal values of possibly different scales
+fn cmp_decimal(
+    left: i128,
+    left_scale: usize,
+    right: i128,
+    right_scale: usize,
+) -> Ordering {
+    match left_scale.cmp(&right_scale) {
+        Ordering::Equal => left.cmp(&right),
+        Ordering::Less => match decimal::rescale(left, left_scale, right_scale) {
+            Some(left) => left.cmp(&right),
+            // the rescaled value is larger in magnitude than any `i128`
+            None => left.cmp(&0),
+        },
+        Ordering::Greater => cmp_decimal(right, right_scale, left, left_scale).reverse(),
+    }
+}
+
+macro_rules! compare_decimal_op {
+    ($left: expr, $right:expr, $op:expr) => {{
+        let (left_scale, right_scale) = ($left.scale(), $right.scale());
+        compare_op!($left, $right, |a, b| $op(cmp_decimal(
+            a,
+            left_scale,
+            b,
+            right_scale
+        )))
+    }};
+}
+
+/// Perform `left == right` operation on two decimal arrays, which may have different
+/// scales.
+pub fn eq_decimal(left: &DecimalArray, right: &DecimalArray) -> Result<BooleanArray> {
+    compare_decimal_op!(left, right, |o| o == Ordering::Equal)
+}
+
+/// Perform `left != right` operation on two decimal arrays, which may have different
+/// scales.
+pub fn neq_decimal(left: &DecimalArray, right: &DecimalArray) -> Result<BooleanArray> {
+    compare_decimal_op!(left, right, |o| o != Ordering::Equal)
+}
+
+/// Perform `left < right` operation on two decimal arrays, which may have different
+/// scales.
+pub fn lt_decimal(left: &DecimalArray, right: &DecimalArray) -> Result<BooleanArray> {
+    compare_decimal_op!(left, right, |o| o == Ordering::Less)
+}
+
+/// Perform `left <= right` operation on two decimal arrays, which may have different
+/// scales.
+pub fn lt_eq_decimal(left: &DecimalArray, right: &DecimalArray) -> Result<BooleanArray> {
+    compare_decimal_op!(left, right, |o| o != Ordering::Greater)
+}
+
+/// Perform `left > right` operation on two decimal arrays, which may have different
+/// scales.
+pub fn gt_decimal(left: &DecimalArray, right: &DecimalArray) -> Result<BooleanArray> {
+    compare_decimal_op!(left, right, |o| o == Ordering::Greater)
+}
+
+/// Perform `left >= right` operation on two decimal arrays, which may have different
+/// scales.
+pub fn gt_eq_decimal(left: &DecimalArray, right: &DecimalArray) -> Result<BooleanArray> {
+    compare_decimal_op!(left, right, |o| o != Ordering::Less)
+}
+
 /// Helper function to perform boolean lambda function on values from two arrays using
 /// SIMD.
 #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "simd"))]
@@ -791,6 +858,36 @@ mod tests {
         assert_eq!(false, c.value(4));
     }
 
+    #[test]
+    fn test_decimal_array_comparison() {
+        // [1.5, 2.25, null, -3] and [1.50, 2.2, 1, -3.1]
+        let a = DecimalArray::from_opt_vec(
+            vec![Some(150), Some(225), None, Some(-300)],
+            5,
+            2,
+        );
+        let b = DecimalArray::from_opt_vec(
+            vec![Some(15), Some(22), Some(10), Some(-31)],
+            3,
+            1,
+        );
+        let c = eq_decimal(&a, &b).unwrap();
+        assert_eq!(true, c.value(0));
+        assert_eq!(false, c.value(1));
+        assert_eq!(true, c.is_null(2));
+        assert_eq!(false, c.value(3));
+
+        let c = gt_decimal(&a, &b).unwrap();
+        assert_eq!(false, c.value(0));
+        assert_eq!(true, c.value(1));
+        assert_eq!(true, c.value(3));
+
+        let c = lt_eq_decimal(&b, &a).unwrap();
+        assert_eq!(true, c.value(0));
+        assert_eq!(true, c.value(1));
+        assert_eq!(true, c.value(3));
+    }
+
     #[test]
     fn test_primitive_array_eq_scalar() {
         let a = Int32Array::from(vec![6, 7, 8, 9, 10]);
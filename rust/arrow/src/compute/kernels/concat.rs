@@ -57,6 +57,11 @@ pub fn concat(array_list: &[ArrayRef]) -> Result<ArrayRef> {
             builder.append_data(array_data_list)?;
             Ok(ArrayBuilder::finish(&mut builder))
         }
+        DataType::Decimal(precision, scale) => {
+            let mut builder = DecimalBuilder::new(0, *precision, *scale);
+            builder.append_data(array_data_list)?;
+            Ok(ArrayBuilder::finish(&mut builder))
+        }
         DataType::Boolean => {
             let mut builder = PrimitiveArray::<BooleanType>::builder(0);
             builder.append_data(array_data_list)?;
@@ -225,6 +230,23 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_concat_decimal_arrays() -> Result<()> {
+        let arr = concat(&[
+            Arc::new(DecimalArray::from_opt_vec(vec![Some(1), None], 5, 2)) as ArrayRef,
+            Arc::new(DecimalArray::from_opt_vec(vec![Some(-250)], 5, 2)) as ArrayRef,
+        ])?;
+        let arr = arr.as_any().downcast_ref::<DecimalArray>().unwrap();
+
+        assert_eq!(&DataType::Decimal(5, 2), arr.data_type());
+        assert_eq!(3, arr.len());
+        assert_eq!(1, arr.value(0));
+        assert!(arr.is_null(1));
+        assert_eq!(-250, arr.value(2));
+
+        Ok(())
+    }
+
     #[test]
     fn test_concat_primitive_arrays() -> Result<()> {
         let arr = concat(&[
@@ -472,6 +472,30 @@ impl FilterContext {
                 }
                 Ok(Arc::new(StringArray::from(values)))
             }
+            DataType::Decimal(precision, scale) => {
+                let input_array = array.as_any().downcast_ref::<DecimalArray>().unwrap();
+                let mut values: Vec<Option<i128>> = Vec::with_capacity(self.filtered_count);
+                for i in 0..self.filter_u64.len() {
+                    // foreach u64 batch
+                    let filter_batch = self.filter_u64[i];
+                    if filter_batch == 0 {
+                        // if batch == 0, all items are filtered out, so skip entire batch
+                        continue;
+                    }
+                    for j in 0..64 {
+                        // foreach bit in batch:
+                        if (filter_batch & self.filter_mask[j]) != 0 {
+                            let data_index = (i * 64) + j;
+                            if input_array.is_null(data_index) {
+                                values.push(None)
+                            } else {
+                                values.push(Some(input_array.value(data_index)))
+                            }
+                        }
+                    }
+                }
+                Ok(Arc::new(DecimalArray::from_opt_vec(values, *precision, *scale)))
+            }
             DataType::Dictionary(ref key_type, ref value_type) => match (key_type.as_ref(), value_type.as_ref()) {
                 (key_type, DataType::Utf8) => match key_type {
                     DataType::UInt8 => filter_dictionary_array!(self, array, UInt8DictionaryArray),
@@ -1014,6 +1038,19 @@ mod tests {
         assert_eq!(true, d.is_null(1));
     }
 
+    #[test]
+    fn test_filter_decimal_array_with_null() {
+        let a = DecimalArray::from_opt_vec(vec![Some(12345), None, Some(-5), None], 7, 2);
+        let b = BooleanArray::from(vec![true, false, true, true]);
+        let c = filter(&a, &b).unwrap();
+        let d = c.as_ref().as_any().downcast_ref::<DecimalArray>().unwrap();
+        assert_eq!(3, d.len());
+        assert_eq!(&DataType::Decimal(7, 2), d.data_type());
+        assert_eq!(12345, d.value(0));
+        assert_eq!(-5, d.value(1));
+        assert_eq!(true, d.is_null(2));
+    }
+
     #[test]
     fn test_filter_array_slice_with_null() {
         let a_slice =
@@ -162,6 +162,7 @@ pub fn sort_to_indices(
             sort_primitive::<DurationNanosecondType>(values, v, n, vec![], &options)
         }
         DataType::Utf8 => sort_string(values, v, n, &options),
+        DataType::Decimal(_, _) => sort_decimal(values, v, n, &options),
         DataType::Dictionary(key_type, value_type)
             if *value_type.as_ref() == DataType::Utf8 =>
         {
@@ -342,6 +343,38 @@ fn sort_string(
     )
 }
 
+/// Sort decimals
+fn sort_decimal(
+    values: &ArrayRef,
+    value_indices: Vec<u32>,
+    null_indices: Vec<u32>,
+    options: &SortOptions,
+) -> Result<UInt32Array> {
+    let values = values.as_any().downcast_ref::<DecimalArray>().unwrap();
+
+    let mut valids = value_indices
+        .into_iter()
+        .map(|index| (index, values.value(index as usize)))
+        .collect::<Vec<(u32, i128)>>();
+    let mut nulls = null_indices;
+    if !options.descending {
+        valids.sort_by_key(|a| a.1);
+    } else {
+        valids.sort_by_key(|a| Reverse(a.1));
+        nulls.reverse();
+    }
+    let mut valid_indices: Vec<u32> = valids.iter().map(|tuple| tuple.0).collect();
+
+    if options.nulls_first {
+        nulls.append(&mut valid_indices);
+        return Ok(UInt32Array::from(nulls));
+    }
+
+    valid_indices.append(&mut nulls);
+
+    Ok(UInt32Array::from(valid_indices))
+}
+
 /// Sort dictionary encoded strings
 fn sort_string_dictionary<T: ArrowDictionaryKeyType>(
     values: &ArrayRef,
@@ -1093,6 +1126,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_sort_to_indices_decimal() {
+        let values: ArrayRef = Arc::new(DecimalArray::from_opt_vec(
+            vec![None, Some(150), Some(-25), None, Some(3), Some(150)],
+            5,
+            2,
+        ));
+        let indices = sort_to_indices(&values, None).unwrap();
+        assert_eq!(indices, UInt32Array::from(vec![0, 3, 2, 4, 1, 5]));
+
+        let indices = sort_to_indices(
+            &values,
+            Some(SortOptions {
+                descending: true,
+                nulls_first: false,
+            }),
+        )
+        .unwrap();
+        assert_eq!(indices, UInt32Array::from(vec![1, 5, 4, 2, 3, 0]));
+    }
+
     #[test]
     fn test_sort_strings() {
         test_sort_string_arrays(
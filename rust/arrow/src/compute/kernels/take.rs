@@ -143,6 +143,7 @@ where
         }
         DataType::Utf8 => take_string::<i32, _>(values, indices),
         DataType::LargeUtf8 => take_string::<i64, _>(values, indices),
+        DataType::Decimal(_, _) => take_decimal(values, indices),
         DataType::List(_) => take_list::<_, Int32Type>(values, indices),
         DataType::LargeList(_) => take_list::<_, Int64Type>(values, indices),
         DataType::Struct(fields) => {
@@ -349,6 +350,36 @@ where
     Ok(Arc::new(BooleanArray::from(Arc::new(data))))
 }
 
+/// `take` implementation for decimal arrays
+fn take_decimal<IndexType>(
+    values: &ArrayRef,
+    indices: &PrimitiveArray<IndexType>,
+) -> Result<ArrayRef>
+where
+    IndexType: ArrowNumericType,
+    IndexType::Native: ToPrimitive,
+{
+    let array = values.as_any().downcast_ref::<DecimalArray>().unwrap();
+    let mut builder =
+        DecimalBuilder::new(indices.len(), array.precision(), array.scale());
+    for i in 0..indices.len() {
+        let index = if indices.is_valid(i) {
+            Some(ToPrimitive::to_usize(&indices.value(i)).ok_or_else(|| {
+                ArrowError::ComputeError("Cast to usize failed".to_string())
+            })?)
+        } else {
+            None
+        };
+        match index {
+            Some(index) if array.is_valid(index) => {
+                builder.append_value(array.value(index))?
+            }
+            _ => builder.append_null()?,
+        }
+    }
+    Ok(Arc::new(builder.finish()))
+}
+
 /// `take` implementation for string arrays
 fn take_string<OffsetSize, IndexType>(
     values: &ArrayRef,
@@ -844,6 +875,25 @@ mod tests {
         _test_take_string::<LargeStringArray>()
     }
 
+    #[test]
+    fn test_take_decimal() {
+        let values: ArrayRef = Arc::new(DecimalArray::from_opt_vec(
+            vec![Some(150), None, Some(-2), Some(99999)],
+            5,
+            2,
+        ));
+        let index = UInt32Array::from(vec![Some(3), None, Some(1), Some(0), Some(2)]);
+        let result = take(&values, &index, None).unwrap();
+        let result = result.as_any().downcast_ref::<DecimalArray>().unwrap();
+        assert_eq!(&DataType::Decimal(5, 2), result.data_type());
+        assert_eq!(5, result.len());
+        assert_eq!(99999, result.value(0));
+        assert!(result.is_null(1));
+        assert!(result.is_null(2));
+        assert_eq!(150, result.value(3));
+        assert_eq!(-2, result.value(4));
+    }
+
     macro_rules! test_take_list {
         ($offset_type:ty, $list_data_type:ident, $list_array_type:ident) => {{
             // Construct a value array, [[0,0,0], [-1,-2,-1], [2,3]]
@@ -17,17 +17,41 @@
 
 //! Defines temporal kernels for time and date related functions.
 
-use chrono::Timelike;
+use chrono::{Datelike, NaiveDateTime, NaiveTime, Timelike};
 
 use crate::array::*;
 use crate::datatypes::*;
 use crate::error::Result;
 
-/// Extracts the hours of a given temporal array as an array of integers
-pub fn hour<T>(array: &PrimitiveArray<T>) -> Result<Int32Array>
+/// Applies `op` to the date and time of each value of a temporal array with a date, such as
+/// a date or a timestamp, into an array of integers. Values without a date are null.
+fn extract_date_component<T, F>(array: &PrimitiveArray<T>, op: F) -> Result<Int32Array>
 where
     T: ArrowTemporalType + ArrowNumericType,
     i64: std::convert::From<T::Native>,
+    F: Fn(NaiveDateTime) -> i32,
+{
+    let mut b = Int32Builder::new(array.len());
+    for i in 0..array.len() {
+        if array.is_null(i) {
+            b.append_null()?;
+        } else {
+            match array.value_as_datetime(i) {
+                Some(dt) => b.append_value(op(dt))?,
+                None => b.append_null()?,
+            }
+        }
+    }
+
+    Ok(b.finish())
+}
+
+/// Applies `op` to the time of each value of a temporal array into an array of integers
+fn extract_time_component<T, F>(array: &PrimitiveArray<T>, op: F) -> Result<Int32Array>
+where
+    T: ArrowTemporalType + ArrowNumericType,
+    i64: std::convert::From<T::Native>,
+    F: Fn(NaiveTime) -> i32,
 {
     let mut b = Int32Builder::new(array.len());
     for i in 0..array.len() {
@@ -37,12 +61,12 @@ where
             match array.data_type() {
                 &DataType::Time32(_) | &DataType::Time64(_) => {
                     match array.value_as_time(i) {
-                        Some(time) => b.append_value(time.hour() as i32)?,
+                        Some(time) => b.append_value(op(time))?,
                         None => b.append_null()?,
                     }
                 }
                 _ => match array.value_as_datetime(i) {
-                    Some(dt) => b.append_value(dt.hour() as i32)?,
+                    Some(dt) => b.append_value(op(dt.time()))?,
                     None => b.append_null()?,
                 },
             }
@@ -52,6 +76,100 @@ where
     Ok(b.finish())
 }
 
+/// Extracts the years of a given temporal array as an array of integers
+pub fn year<T>(array: &PrimitiveArray<T>) -> Result<Int32Array>
+where
+    T: ArrowTemporalType + ArrowNumericType,
+    i64: std::convert::From<T::Native>,
+{
+    extract_date_component(array, |dt| dt.year())
+}
+
+/// Extracts the quarters, from 1 to 4, of a given temporal array as an array of integers
+pub fn quarter<T>(array: &PrimitiveArray<T>) -> Result<Int32Array>
+where
+    T: ArrowTemporalType + ArrowNumericType,
+    i64: std::convert::From<T::Native>,
+{
+    extract_date_component(array, |dt| dt.month0() as i32 / 3 + 1)
+}
+
+/// Extracts the months, from 1 to 12, of a given temporal array as an array of integers
+pub fn month<T>(array: &PrimitiveArray<T>) -> Result<Int32Array>
+where
+    T: ArrowTemporalType + ArrowNumericType,
+    i64: std::convert::From<T::Native>,
+{
+    extract_date_component(array, |dt| dt.month() as i32)
+}
+
+/// Extracts the ISO 8601 weeks of the year, from 1 to 53, of a given temporal array as an
+/// array of integers
+pub fn week<T>(array: &PrimitiveArray<T>) -> Result<Int32Array>
+where
+    T: ArrowTemporalType + ArrowNumericType,
+    i64: std::convert::From<T::Native>,
+{
+    extract_date_component(array, |dt| dt.iso_week().week() as i32)
+}
+
+/// Extracts the days of the month, from 1 to 31, of a given temporal array as an array of
+/// integers
+pub fn day<T>(array: &PrimitiveArray<T>) -> Result<Int32Array>
+where
+    T: ArrowTemporalType + ArrowNumericType,
+    i64: std::convert::From<T::Native>,
+{
+    extract_date_component(array, |dt| dt.day() as i32)
+}
+
+/// Extracts the days of the week, from 0 for Sunday to 6 for Saturday, of a given temporal
+/// array as an array of integers
+pub fn day_of_week<T>(array: &PrimitiveArray<T>) -> Result<Int32Array>
+where
+    T: ArrowTemporalType + ArrowNumericType,
+    i64: std::convert::From<T::Native>,
+{
+    extract_date_component(array, |dt| dt.weekday().num_days_from_sunday() as i32)
+}
+
+/// Extracts the days of the year, from 1 to 366, of a given temporal array as an array of
+/// integers
+pub fn day_of_year<T>(array: &PrimitiveArray<T>) -> Result<Int32Array>
+where
+    T: ArrowTemporalType + ArrowNumericType,
+    i64: std::convert::From<T::Native>,
+{
+    extract_date_component(array, |dt| dt.ordinal() as i32)
+}
+
+/// Extracts the hours of a given temporal array as an array of integers
+pub fn hour<T>(array: &PrimitiveArray<T>) -> Result<Int32Array>
+where
+    T: ArrowTemporalType + ArrowNumericType,
+    i64: std::convert::From<T::Native>,
+{
+    extract_time_component(array, |time| time.hour() as i32)
+}
+
+/// Extracts the minutes of a given temporal array as an array of integers
+pub fn minute<T>(array: &PrimitiveArray<T>) -> Result<Int32Array>
+where
+    T: ArrowTemporalType + ArrowNumericType,
+    i64: std::convert::From<T::Native>,
+{
+    extract_time_component(array, |time| time.minute() as i32)
+}
+
+/// Extracts the seconds of a given temporal array as an array of integers
+pub fn second<T>(array: &PrimitiveArray<T>) -> Result<Int32Array>
+where
+    T: ArrowTemporalType + ArrowNumericType,
+    i64: std::convert::From<T::Native>,
+{
+    extract_time_component(array, |time| time.second() as i32)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -68,6 +186,52 @@ mod tests {
         assert_eq!(4, b.value(2));
     }
 
+    #[test]
+    fn test_temporal_array_date64_date_components() {
+        // 2018-01-01T00:00:00, a Monday, and 2019-02-20T04:23:45, a Wednesday
+        let a: PrimitiveArray<Date64Type> =
+            vec![Some(1514764800000), None, Some(1550636625000)].into();
+
+        let b = year(&a).unwrap();
+        assert_eq!(2018, b.value(0));
+        assert_eq!(false, b.is_valid(1));
+        assert_eq!(2019, b.value(2));
+
+        assert_eq!(1, quarter(&a).unwrap().value(2));
+        assert_eq!(2, month(&a).unwrap().value(2));
+        assert_eq!(1, week(&a).unwrap().value(0));
+        assert_eq!(8, week(&a).unwrap().value(2));
+        assert_eq!(20, day(&a).unwrap().value(2));
+        assert_eq!(1, day_of_week(&a).unwrap().value(0));
+        assert_eq!(3, day_of_week(&a).unwrap().value(2));
+        assert_eq!(51, day_of_year(&a).unwrap().value(2));
+    }
+
+    #[test]
+    fn test_temporal_array_timestamp_time_components() {
+        // 2019-02-20T04:23:45
+        let a = TimestampNanosecondArray::from_opt_vec(
+            vec![Some(1550636625000000000), None],
+            None,
+        );
+
+        assert_eq!(4, hour(&a).unwrap().value(0));
+        assert_eq!(23, minute(&a).unwrap().value(0));
+        let b = second(&a).unwrap();
+        assert_eq!(45, b.value(0));
+        assert_eq!(false, b.is_valid(1));
+    }
+
+    #[test]
+    fn test_temporal_array_date32_year() {
+        // 2020-03-01
+        let a: Date32Array = vec![18322].into();
+
+        assert_eq!(2020, year(&a).unwrap().value(0));
+        assert_eq!(3, month(&a).unwrap().value(0));
+        assert_eq!(0, hour(&a).unwrap().value(0));
+    }
+
     #[test]
     fn test_temporal_array_time32_second_hour() {
         let a: PrimitiveArray<Time32SecondType> = vec![37800, 86339].into();
@@ -51,10 +51,11 @@ use std::sync::Arc;
 
 use csv as csv_crate;
 
-use crate::array::{ArrayRef, PrimitiveArray, StringBuilder};
+use crate::array::{ArrayRef, DecimalBuilder, PrimitiveArray, StringBuilder};
 use crate::datatypes::*;
 use crate::error::{ArrowError, Result};
 use crate::record_batch::RecordBatch;
+use crate::util::decimal;
 
 use self::csv_crate::{ByteRecord, StringRecord};
 
@@ -448,6 +449,9 @@ fn parse(
                     }
                     Ok(Arc::new(builder.finish()) as ArrayRef)
                 }
+                &DataType::Decimal(precision, scale) => {
+                    build_decimal_array(line_number, rows, i, precision, scale)
+                }
                 other => Err(ArrowError::ParseError(format!(
                     "Unsupported data type {:?}",
                     other
@@ -548,6 +552,36 @@ fn build_primitive_array<T: ArrowPrimitiveType + Parser>(
         .map(|e| Arc::new(e) as ArrayRef)
 }
 
+// parses a specific column (col_idx) into a decimal Array of the given precision and
+// scale.
+fn build_decimal_array(
+    line_number: usize,
+    rows: &[StringRecord],
+    col_idx: usize,
+    precision: usize,
+    scale: usize,
+) -> Result<ArrayRef> {
+    let mut builder = DecimalBuilder::new(rows.len(), precision, scale);
+    for (row_index, row) in rows.iter().enumerate() {
+        match row.get(col_idx) {
+            Some(s) if !s.is_empty() => {
+                let value =
+                    decimal::parse_decimal(s, precision, scale).map_err(|_| {
+                        ArrowError::ParseError(format!(
+                            "Error while parsing value {} for column {} at line {}",
+                            s,
+                            col_idx,
+                            line_number + row_index
+                        ))
+                    })?;
+                builder.append_value(value)?
+            }
+            _ => builder.append_null()?,
+        }
+    }
+    Ok(Arc::new(builder.finish()))
+}
+
 /// CSV file reader builder
 #[derive(Debug)]
 pub struct ReaderBuilder {
@@ -1056,6 +1090,38 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_decimal() -> Result<()> {
+        let schema = Schema::new(vec![
+            Field::new("price", DataType::Decimal(6, 2), true),
+            Field::new("name", DataType::Utf8, false),
+        ]);
+        let data = "12.5,a\n,b\n-0.125,c\n1234.56,d";
+        let reader = std::io::Cursor::new(data.as_bytes());
+
+        let mut csv =
+            Reader::new(reader, Arc::new(schema), false, None, 1024, None, None);
+
+        let batch = csv.next().unwrap()?;
+        let a = batch.column(0);
+        let a = a.as_any().downcast_ref::<DecimalArray>().unwrap();
+        assert_eq!(4, a.len());
+        assert_eq!("12.50", a.value_as_string(0));
+        assert!(a.is_null(1));
+        assert_eq!("-0.13", a.value_as_string(2));
+        assert_eq!("1234.56", a.value_as_string(3));
+
+        let schema = Schema::new(vec![
+            Field::new("price", DataType::Decimal(3, 2), true),
+            Field::new("name", DataType::Utf8, false),
+        ]);
+        let reader = std::io::Cursor::new(data.as_bytes());
+        let mut csv =
+            Reader::new(reader, Arc::new(schema), false, None, 1024, None, None);
+        assert!(csv.next().unwrap().is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_parsing_bool() {
         // Encode the expected behavior of boolean parsing
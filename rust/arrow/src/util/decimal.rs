@@ -0,0 +1,175 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Functions for the values of decimals, which are `i128` integers of up to
+//! [MAX_DECIMAL_PRECISION] digits, of which the last `scale` digits are the fractional
+//! digits of the decimal.
+
+use crate::error::{ArrowError, Result};
+
+/// The largest precision of a decimal, whose values fit in 16 bytes
+pub const MAX_DECIMAL_PRECISION: usize = 38;
+
+/// Returns `10^exp`, for an `exp` of up to [MAX_DECIMAL_PRECISION]
+pub fn pow10(exp: usize) -> i128 {
+    10_i128.pow(exp as u32)
+}
+
+/// Returns whether `value` has no more than `precision` digits
+pub fn fits_precision(value: i128, precision: usize) -> bool {
+    precision >= MAX_DECIMAL_PRECISION || value.abs() < pow10(precision)
+}
+
+/// Checks that `value` has no more than `precision` digits
+pub fn validate_precision(value: i128, precision: usize) -> Result<i128> {
+    if fits_precision(value, precision) {
+        Ok(value)
+    } else {
+        Err(ArrowError::ComputeError(format!(
+            "Decimal value {} does not fit in a precision of {}",
+            value, precision
+        )))
+    }
+}
+
+/// Divides `value` by `divisor`, rounding half away from zero
+pub fn div_round(value: i128, divisor: i128) -> i128 {
+    let quotient = value / divisor;
+    let remainder = value % divisor;
+    if remainder.abs() * 2 >= divisor.abs() {
+        if (value < 0) == (divisor < 0) {
+            quotient + 1
+        } else {
+            quotient - 1
+        }
+    } else {
+        quotient
+    }
+}
+
+/// Converts `value` from the scale `from_scale` to the scale `to_scale`, rounding half
+/// away from zero when the scale decreases. Returns `None` on overflow.
+pub fn rescale(value: i128, from_scale: usize, to_scale: usize) -> Option<i128> {
+    if to_scale >= from_scale {
+        value.checked_mul(pow10(to_scale - from_scale))
+    } else {
+        Some(div_round(value, pow10(from_scale - to_scale)))
+    }
+}
+
+/// Formats `value` of the scale `scale`, e.g. `-12345` of scale 2 as `-123.45`
+pub fn format_decimal(value: i128, scale: usize) -> String {
+    let digits = value.abs().to_string();
+    let sign = if value < 0 { "-" } else { "" };
+    if scale == 0 {
+        return format!("{}{}", sign, digits);
+    }
+    let digits = format!("{:0>width$}", digits, width = scale + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - scale);
+    format!("{}{}.{}", sign, integer, fraction)
+}
+
+/// Parses a decimal number such as `-123.45` into a value of the scale `scale`, rounding
+/// the digits beyond the scale half away from zero, and checks that it has no more than
+/// `precision` digits.
+pub fn parse_decimal(s: &str, precision: usize, scale: usize) -> Result<i128> {
+    let error = || {
+        ArrowError::ParseError(format!(
+            "Cannot parse \"{}\" as a decimal of precision {} and scale {}",
+            s, precision, scale
+        ))
+    };
+    let s = s.trim();
+    let (negative, unsigned) = match s.as_bytes().first() {
+        Some(b'-') => (true, &s[1..]),
+        Some(b'+') => (false, &s[1..]),
+        _ => (false, s),
+    };
+    let (integer, fraction) = match unsigned.find('.') {
+        Some(dot) => (&unsigned[..dot], &unsigned[dot + 1..]),
+        None => (unsigned, ""),
+    };
+    if (integer.is_empty() && fraction.is_empty())
+        || !integer
+            .bytes()
+            .chain(fraction.bytes())
+            .all(|b| b.is_ascii_digit())
+    {
+        return Err(error());
+    }
+
+    let mut value: i128 = 0;
+    for b in integer.bytes().chain(fraction.bytes().take(scale)) {
+        value = value
+            .checked_mul(10)
+            .and_then(|v| v.checked_add((b - b'0') as i128))
+            .ok_or_else(error)?;
+    }
+    // pad the fraction to the scale, or round it at the scale
+    if fraction.len() < scale {
+        value = value
+            .checked_mul(pow10(scale - fraction.len()))
+            .ok_or_else(error)?;
+    } else if fraction.as_bytes().get(scale).map_or(false, |b| *b >= b'5') {
+        value += 1;
+    }
+    let value = if negative { -value } else { value };
+    if fits_precision(value, precision) {
+        Ok(value)
+    } else {
+        Err(error())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_decimal() {
+        assert_eq!(format_decimal(12345, 2), "123.45");
+        assert_eq!(format_decimal(-12345, 2), "-123.45");
+        assert_eq!(format_decimal(5, 3), "0.005");
+        assert_eq!(format_decimal(-5, 1), "-0.5");
+        assert_eq!(format_decimal(42, 0), "42");
+    }
+
+    #[test]
+    fn test_parse_decimal() -> Result<()> {
+        assert_eq!(parse_decimal("123.45", 5, 2)?, 12345);
+        assert_eq!(parse_decimal("-1.2", 5, 2)?, -120);
+        assert_eq!(parse_decimal("+7", 5, 2)?, 700);
+        assert_eq!(parse_decimal(".5", 5, 2)?, 50);
+        assert_eq!(parse_decimal("1.005", 5, 2)?, 101);
+        assert_eq!(parse_decimal("-1.004", 5, 2)?, -100);
+        assert!(parse_decimal("1234.5", 5, 2).is_err());
+        assert!(parse_decimal("1.2.3", 5, 2).is_err());
+        assert!(parse_decimal("", 5, 2).is_err());
+        assert!(parse_decimal("-", 5, 2).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rescale() {
+        assert_eq!(rescale(125, 2, 4), Some(12500));
+        assert_eq!(rescale(125, 2, 1), Some(13));
+        assert_eq!(rescale(-125, 2, 1), Some(-13));
+        assert_eq!(rescale(124, 2, 0), Some(1));
+        assert_eq!(rescale(i128::MAX, 0, 1), None);
+        assert_eq!(div_round(7, -2), -4);
+    }
+}
@@ -105,6 +105,14 @@ pub fn array_value_to_string(column: &array::ArrayRef, row: usize) -> Result<Str
         DataType::Time64(unit) if *unit == TimeUnit::Nanosecond => {
             make_string!(array::Time64NanosecondArray, column, row)
         }
+        DataType::Decimal(_, _) => {
+            let array = column.as_any().downcast_ref::<array::DecimalArray>().unwrap();
+            if array.is_null(row) {
+                Ok("".to_string())
+            } else {
+                Ok(array.value_as_string(row))
+            }
+        }
         DataType::List(_) => make_string_from_list!(column, row),
         DataType::Dictionary(index_type, _value_type) => match **index_type {
             DataType::Int8 => dict_array_value_to_string::<Int8Type>(column, row),
@@ -17,6 +17,7 @@
 
 pub mod bit_chunk_iterator;
 pub mod bit_util;
+pub mod decimal;
 pub mod display;
 pub mod integration_util;
 #[cfg(feature = "prettyprint")]
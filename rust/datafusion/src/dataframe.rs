@@ -131,6 +131,23 @@ pub trait DataFrame {
     /// ```
     fn limit(&self, n: usize) -> Result<Arc<dyn DataFrame>>;
 
+    /// Calculate the union of this DataFrame with another DataFrame, keeping duplicate rows
+    /// (UNION ALL). Columns are matched by position and the resulting DataFrame has the
+    /// column names of this DataFrame.
+    ///
+    /// ```
+    /// # use datafusion::prelude::*;
+    /// # use datafusion::error::Result;
+    /// # fn main() -> Result<()> {
+    /// let mut ctx = ExecutionContext::new();
+    /// let df = ctx.read_csv("tests/example.csv", CsvReadOptions::new())?;
+    /// let d2 = df.clone();
+    /// let df = df.union(d2)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn union(&self, dataframe: Arc<dyn DataFrame>) -> Result<Arc<dyn DataFrame>>;
+
     /// Sort the DataFrame by the specified sorting expressions. Any expression can be turned into
     /// a sort expression by calling its [sort](../logical_plan/enum.Expr.html#method.sort) method.
     ///
@@ -206,7 +223,8 @@ pub trait DataFrame {
     /// Return the logical plan represented by this DataFrame.
     fn to_logical_plan(&self) -> LogicalPlan;
 
-    /// Return a DataFrame with the explanation of its plan so far.
+    /// Return a DataFrame with the explanation of its plan so far. With `analyze`, the plan
+    /// is executed and the explanation includes the metrics of its execution.
     ///
     /// ```
     /// # use datafusion::prelude::*;
@@ -215,11 +233,11 @@ pub trait DataFrame {
     /// # async fn main() -> Result<()> {
     /// let mut ctx = ExecutionContext::new();
     /// let df = ctx.read_csv("tests/example.csv", CsvReadOptions::new())?;
-    /// let batches = df.limit(100)?.explain(false)?.collect().await?;
+    /// let batches = df.limit(100)?.explain(false, false)?.collect().await?;
     /// # Ok(())
     /// # }
     /// ```
-    fn explain(&self, verbose: bool) -> Result<Arc<dyn DataFrame>>;
+    fn explain(&self, verbose: bool, analyze: bool) -> Result<Arc<dyn DataFrame>>;
 
     /// Return a `FunctionRegistry` used to plan udf's calls
     ///
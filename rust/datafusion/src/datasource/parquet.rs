@@ -24,6 +24,7 @@ use arrow::datatypes::*;
 
 use crate::datasource::TableProvider;
 use crate::error::Result;
+use crate::physical_plan::expressions::PhysicalSortExpr;
 use crate::physical_plan::parquet::ParquetExec;
 use crate::physical_plan::ExecutionPlan;
 
@@ -31,6 +32,7 @@ use crate::physical_plan::ExecutionPlan;
 pub struct ParquetTable {
     path: String,
     schema: SchemaRef,
+    output_ordering: Vec<PhysicalSortExpr>,
 }
 
 impl ParquetTable {
@@ -41,8 +43,20 @@ impl ParquetTable {
         Ok(Self {
             path: path.to_string(),
             schema,
+            output_ordering: vec![],
         })
     }
+
+    /// Declare that the rows of every file are sorted by `output_ordering`, such as when
+    /// the files were written in the order of a key. Operators such as joins can then
+    /// rely on the order of the rows of the scans of this table.
+    pub fn with_output_ordering(
+        mut self,
+        output_ordering: Vec<PhysicalSortExpr>,
+    ) -> Self {
+        self.output_ordering = output_ordering;
+        self
+    }
 }
 
 impl TableProvider for ParquetTable {
@@ -58,11 +72,10 @@ impl TableProvider for ParquetTable {
         projection: &Option<Vec<usize>>,
         batch_size: usize,
     ) -> Result<Arc<dyn ExecutionPlan>> {
-        Ok(Arc::new(ParquetExec::try_new(
-            &self.path,
-            projection.clone(),
-            batch_size,
-        )?))
+        Ok(Arc::new(
+            ParquetExec::try_new(&self.path, projection.clone(), batch_size)?
+                .with_output_ordering(self.output_ordering.clone()),
+        ))
     }
 }
 
@@ -56,6 +56,12 @@ pub enum DataFusionError {
     /// Error returned during execution of the query.
     /// Examples include files not found, errors in parsing certain types.
     Execution(String),
+    /// Error returned when the execution of a query is cancelled, such as when its
+    /// timeout elapses.
+    Cancelled(String),
+    /// Error returned when the memory that the execution of a query needs cannot be
+    /// reserved, as a memory limit would be exceeded.
+    ResourcesExhausted(String),
 }
 
 impl DataFusionError {
@@ -113,6 +119,12 @@ impl Display for DataFusionError {
             DataFusionError::Execution(ref desc) => {
                 write!(f, "Execution error: {}", desc)
             }
+            DataFusionError::Cancelled(ref desc) => {
+                write!(f, "Query cancelled: {}", desc)
+            }
+            DataFusionError::ResourcesExhausted(ref desc) => {
+                write!(f, "Resources exhausted: {}", desc)
+            }
         }
     }
 }
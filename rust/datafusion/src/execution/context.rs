@@ -22,7 +22,9 @@ use std::fs;
 use std::path::Path;
 use std::string::String;
 use std::sync::Arc;
+use std::time::Duration;
 
+use chrono::{DateTime, Utc};
 use futures::{StreamExt, TryStreamExt};
 
 use arrow::csv;
@@ -34,12 +36,18 @@ use crate::datasource::parquet::ParquetTable;
 use crate::datasource::TableProvider;
 use crate::error::{DataFusionError, Result};
 use crate::execution::dataframe_impl::DataFrameImpl;
+use crate::execution::memory_manager::MemoryManager;
 use crate::logical_plan::{
     FunctionRegistry, LogicalPlan, LogicalPlanBuilder, TableSource,
 };
 use crate::optimizer::filter_push_down::FilterPushDown;
 use crate::optimizer::optimizer::OptimizerRule;
 use crate::optimizer::projection_push_down::ProjectionPushDown;
+use crate::physical_optimizer::optimizer::PhysicalOptimizerRule;
+use crate::physical_optimizer::sort_limit_fusion::SortLimitFusion;
+use crate::physical_plan::cancellation::{
+    CancellableStream, Cancellation, CancellationToken,
+};
 use crate::physical_plan::common;
 use crate::physical_plan::csv::CsvReadOptions;
 use crate::physical_plan::merge::MergeExec;
@@ -112,6 +120,8 @@ impl ExecutionContext {
                 scalar_functions: HashMap::new(),
                 var_provider: HashMap::new(),
                 aggregate_functions: HashMap::new(),
+                memory_manager: config.memory_manager.clone(),
+                query_execution_start_time: Utc::now(),
                 config,
             },
         };
@@ -331,36 +341,57 @@ impl ExecutionContext {
         self.state.config.query_planner.rewrite_logical_plan(plan)
     }
 
-    /// Create a physical plan from a logical plan
+    /// Create a physical plan from a logical plan and optimize it by applying physical
+    /// optimizer rules
     pub fn create_physical_plan(
         &self,
         logical_plan: &LogicalPlan,
     ) -> Result<Arc<dyn ExecutionPlan>> {
-        self.state
+        // the operators of each query reserve memory from a manager of its own
+        let mut state = self.state.clone();
+        state.memory_manager = self
+            .state
+            .memory_manager
+            .new_child(self.state.config.query_memory_limit);
+        state.query_execution_start_time = Utc::now();
+        let plan = state
             .config
             .query_planner
-            .create_physical_plan(logical_plan, &self.state)
+            .create_physical_plan(logical_plan, &state)?;
+        SortLimitFusion::new().optimize(plan)
     }
 
-    /// Execute a physical plan and collect the results in memory
+    /// Execute a physical plan and collect the results in memory. The execution fails once
+    /// the query timeout of the config elapses.
     pub async fn collect(
         &self,
         plan: Arc<dyn ExecutionPlan>,
     ) -> Result<Vec<RecordBatch>> {
-        match plan.output_partitioning().partition_count() {
-            0 => Ok(vec![]),
-            1 => {
-                let it = plan.execute(0).await?;
-                common::collect(it).await
-            }
+        self.collect_with_cancellation(plan, CancellationToken::new())
+            .await
+    }
+
+    /// Execute a physical plan and collect the results in memory, unless `token` is
+    /// cancelled or the query timeout of the config elapses first. The execution of the
+    /// plan is then stopped and a [DataFusionError::Cancelled] is returned.
+    pub async fn collect_with_cancellation(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        token: CancellationToken,
+    ) -> Result<Vec<RecordBatch>> {
+        let mut cancellation = Cancellation::new(token, self.state.config.query_timeout);
+        let stream = match plan.output_partitioning().partition_count() {
+            0 => return Ok(vec![]),
+            1 => cancellation.run(plan.execute(0)).await?,
             _ => {
                 // merge into a single partition
                 let plan = MergeExec::new(plan.clone());
                 // MergeExec must produce a single partition
                 assert_eq!(1, plan.output_partitioning().partition_count());
-                common::collect(plan.execute(0).await?).await
+                cancellation.run(plan.execute(0)).await?
             }
-        }
+        };
+        common::collect(Box::pin(CancellableStream::new(stream, cancellation))).await
     }
 
     /// Execute a query and write the results to a partitioned CSV file
@@ -473,6 +504,24 @@ pub struct ExecutionConfig {
     pub concurrency: usize,
     /// Default batch size when reading data sources
     pub batch_size: usize,
+    /// Size in bytes of the input batches that a sort buffers in memory, above which it
+    /// spills sorted runs to disk
+    pub sort_spill_threshold: usize,
+    /// Size in bytes of the groups that a grouped aggregation keeps in memory, above which
+    /// it spills their partial state to disk
+    pub aggregate_memory_budget: usize,
+    /// Estimated size in bytes of the left input of a hash join up to which it is collected
+    /// into a single hash table that all the partitions of the right share, as are the
+    /// left inputs whose size is not estimated. Larger inputs are joined by partitioning
+    /// both sides by their join keys.
+    pub hash_join_collect_left_threshold: usize,
+    /// Time after which the execution of a query is cancelled, if any
+    pub query_timeout: Option<Duration>,
+    /// Size in bytes of the memory that the operators of a query may reserve at once, if
+    /// limited
+    pub query_memory_limit: Option<usize>,
+    /// Manager that the memory of all the queries is reserved from
+    memory_manager: Arc<MemoryManager>,
     /// Responsible for planning `LogicalPlan`s, and `ExecutionPlan`
     query_planner: Arc<dyn QueryPlanner + Send + Sync>,
 }
@@ -483,6 +532,12 @@ impl ExecutionConfig {
         Self {
             concurrency: num_cpus::get(),
             batch_size: 4096,
+            sort_spill_threshold: 512 * 1024 * 1024,
+            aggregate_memory_budget: 512 * 1024 * 1024,
+            hash_join_collect_left_threshold: 64 * 1024 * 1024,
+            query_timeout: None,
+            query_memory_limit: None,
+            memory_manager: Arc::new(MemoryManager::new(None)),
             query_planner: Arc::new(DefaultQueryPlanner {}),
         }
     }
@@ -503,6 +558,50 @@ impl ExecutionConfig {
         self
     }
 
+    /// Customize the memory threshold above which sorts spill to disk
+    pub fn with_sort_spill_threshold(mut self, n: usize) -> Self {
+        self.sort_spill_threshold = n;
+        self
+    }
+
+    /// Customize the memory budget above which grouped aggregations spill to disk
+    pub fn with_aggregate_memory_budget(mut self, n: usize) -> Self {
+        self.aggregate_memory_budget = n;
+        self
+    }
+
+    /// Customize the size threshold up to which the left input of a hash join is collected
+    /// into a single hash table
+    pub fn with_hash_join_collect_left_threshold(mut self, n: usize) -> Self {
+        self.hash_join_collect_left_threshold = n;
+        self
+    }
+
+    /// Cancel the execution of queries that run for longer than `timeout`
+    pub fn with_query_timeout(mut self, timeout: Duration) -> Self {
+        self.query_timeout = Some(timeout);
+        self
+    }
+
+    /// Limit the memory that the operators of a query may reserve at once to `n` bytes
+    pub fn with_query_memory_limit(mut self, n: usize) -> Self {
+        self.query_memory_limit = Some(n);
+        self
+    }
+
+    /// Limit the memory that the operators of all the queries may reserve at once to `n`
+    /// bytes
+    pub fn with_memory_limit(self, n: usize) -> Self {
+        self.with_memory_manager(Arc::new(MemoryManager::new(Some(n))))
+    }
+
+    /// Reserve the memory of all the queries from `memory_manager`. Contexts that share a
+    /// manager share its limit, such as the limit of the memory of a process.
+    pub fn with_memory_manager(mut self, memory_manager: Arc<MemoryManager>) -> Self {
+        self.memory_manager = memory_manager;
+        self
+    }
+
     /// Replace the default query planner
     pub fn with_query_planner(
         mut self,
@@ -526,6 +625,11 @@ pub struct ExecutionContextState {
     pub aggregate_functions: HashMap<String, Arc<AggregateUDF>>,
     /// Context configuration
     pub config: ExecutionConfig,
+    /// Manager that the operators of the queries planned with this state reserve memory
+    /// from
+    pub memory_manager: Arc<MemoryManager>,
+    /// The time that the query planned with this state started, which `now()` returns
+    pub query_execution_start_time: DateTime<Utc>,
 }
 
 impl SchemaProvider for ExecutionContextState {
@@ -818,6 +922,62 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn cancel_query() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let ctx = create_ctx(&tmp_dir, 4)?;
+        let logical_plan =
+            ctx.create_logical_plan("SELECT c1, c2 FROM test ORDER BY c1")?;
+        let logical_plan = ctx.optimize(&logical_plan)?;
+        let physical_plan = ctx.create_physical_plan(&logical_plan)?;
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let e = ctx
+            .collect_with_cancellation(physical_plan, token)
+            .await
+            .unwrap_err();
+        assert!(e.to_string().contains("the query was cancelled"));
+
+        // the queries of other tokens are not cancelled
+        let physical_plan = ctx.create_physical_plan(&logical_plan)?;
+        let results = ctx.collect(physical_plan).await?;
+        assert_eq!(results.iter().map(|b| b.num_rows()).sum::<usize>(), 40);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn query_memory_limit() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new().with_query_memory_limit(1),
+        );
+        let schema = populate_csv_partitions(&tmp_dir, 4, ".csv")?;
+        ctx.register_csv(
+            "test",
+            tmp_dir.path().to_str().unwrap(),
+            CsvReadOptions::new().schema(&schema),
+        )?;
+
+        // the sort spills its input to disk instead of holding it in memory
+        let results = collect(&mut ctx, "SELECT c1, c2 FROM test ORDER BY c1").await?;
+        assert_eq!(results.iter().map(|b| b.num_rows()).sum::<usize>(), 40);
+
+        // the groups of the aggregation are spilled to disk, and merged one at a time
+        let results =
+            collect(&mut ctx, "SELECT c1, SUM(c2) FROM test GROUP BY c1").await?;
+        let mut rows = results
+            .iter()
+            .flat_map(test::format_batch)
+            .collect::<Vec<_>>();
+        rows.sort();
+        assert_eq!(rows, vec!["0,55", "1,55", "2,55", "3,55"]);
+        assert_eq!(ctx.state.memory_manager.reserved(), 0);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn aggregate() -> Result<()> {
         let results = execute("SELECT SUM(c1), SUM(c2) FROM test", 4).await?;
@@ -889,9 +1049,8 @@ mod tests {
     #[tokio::test]
     async fn aggregate_grouped() -> Result<()> {
         let results = execute("SELECT c1, SUM(c2) FROM test GROUP BY c1", 4).await?;
-        assert_eq!(results.len(), 1);
-
-        let batch = &results[0];
+        // the final aggregation produces a batch per partition of the groups
+        let batch = &combine_batches(&results)?;
 
         assert_eq!(field_names(batch), vec!["c1", "SUM(c2)"]);
 
@@ -906,9 +1065,8 @@ mod tests {
     #[tokio::test]
     async fn aggregate_grouped_avg() -> Result<()> {
         let results = execute("SELECT c1, AVG(c2) FROM test GROUP BY c1", 4).await?;
-        assert_eq!(results.len(), 1);
-
-        let batch = &results[0];
+        // the final aggregation produces a batch per partition of the groups
+        let batch = &combine_batches(&results)?;
 
         assert_eq!(field_names(batch), vec!["c1", "AVG(c2)"]);
 
@@ -924,9 +1082,8 @@ mod tests {
     async fn aggregate_grouped_empty() -> Result<()> {
         let results =
             execute("SELECT c1, AVG(c2) FROM test WHERE c1 = 123 GROUP BY c1", 4).await?;
-        assert_eq!(results.len(), 1);
-
-        let batch = &results[0];
+        // the final aggregation produces a batch per partition of the groups
+        let batch = &combine_batches(&results)?;
 
         assert_eq!(field_names(batch), vec!["c1", "AVG(c2)"]);
 
@@ -941,9 +1098,8 @@ mod tests {
     #[tokio::test]
     async fn aggregate_grouped_max() -> Result<()> {
         let results = execute("SELECT c1, MAX(c2) FROM test GROUP BY c1", 4).await?;
-        assert_eq!(results.len(), 1);
-
-        let batch = &results[0];
+        // the final aggregation produces a batch per partition of the groups
+        let batch = &combine_batches(&results)?;
 
         assert_eq!(field_names(batch), vec!["c1", "MAX(c2)"]);
 
@@ -958,9 +1114,8 @@ mod tests {
     #[tokio::test]
     async fn aggregate_grouped_min() -> Result<()> {
         let results = execute("SELECT c1, MIN(c2) FROM test GROUP BY c1", 4).await?;
-        assert_eq!(results.len(), 1);
-
-        let batch = &results[0];
+        // the final aggregation produces a batch per partition of the groups
+        let batch = &combine_batches(&results)?;
 
         assert_eq!(field_names(batch), vec!["c1", "MIN(c2)"]);
 
@@ -1007,9 +1162,8 @@ mod tests {
     #[tokio::test]
     async fn count_aggregated() -> Result<()> {
         let results = execute("SELECT c1, COUNT(c2) FROM test GROUP BY c1", 4).await?;
-        assert_eq!(results.len(), 1);
-
-        let batch = &results[0];
+        // the final aggregation produces a batch per partition of the groups
+        let batch = &combine_batches(&results)?;
 
         assert_eq!(field_names(batch), vec!["c1", "COUNT(c2)"]);
 
@@ -1102,9 +1256,8 @@ mod tests {
         ];
 
         let results = run_count_distinct_integers_aggregated_scenario(partitions).await?;
-        assert_eq!(results.len(), 1);
-
-        let batch = &results[0];
+        // the final aggregation produces a batch per partition of the groups
+        let batch = &combine_batches(&results)?;
         assert_eq!(batch.num_rows(), 3);
         assert_eq!(batch.num_columns(), 10);
         let mut result = test::format_batch(&batch);
@@ -1133,9 +1286,8 @@ mod tests {
         ];
 
         let results = run_count_distinct_integers_aggregated_scenario(partitions).await?;
-        assert_eq!(results.len(), 1);
-
-        let batch = &results[0];
+        // the final aggregation produces a batch per partition of the groups
+        let batch = &combine_batches(&results)?;
         assert_eq!(batch.num_rows(), 3);
         assert_eq!(batch.num_columns(), 10);
         let mut result = test::format_batch(&batch);
@@ -1548,6 +1700,21 @@ mod tests {
             .collect::<Vec<String>>()
     }
 
+    /// Combines the batches of a result into one
+    fn combine_batches(results: &[RecordBatch]) -> Result<RecordBatch> {
+        let schema = results[0].schema();
+        let columns = (0..schema.fields().len())
+            .map(|i| {
+                let arrays = results
+                    .iter()
+                    .map(|batch| batch.column(i).clone())
+                    .collect::<Vec<_>>();
+                arrow::compute::concat(&arrays)
+            })
+            .collect::<arrow::error::Result<Vec<_>>>()?;
+        Ok(RecordBatch::try_new(schema, columns)?)
+    }
+
     /// Execute SQL and return results
     async fn execute(sql: &str, partition_count: usize) -> Result<Vec<RecordBatch>> {
         let tmp_dir = TempDir::new()?;
@@ -98,6 +98,14 @@ impl DataFrame for DataFrameImpl {
         Ok(Arc::new(DataFrameImpl::new(self.ctx_state.clone(), &plan)))
     }
 
+    /// Union with another DataFrame
+    fn union(&self, dataframe: Arc<dyn DataFrame>) -> Result<Arc<dyn DataFrame>> {
+        let plan = LogicalPlanBuilder::from(&self.plan)
+            .union(&dataframe.to_logical_plan())?
+            .build()?;
+        Ok(Arc::new(DataFrameImpl::new(self.ctx_state.clone(), &plan)))
+    }
+
     /// Sort by specified sorting expressions
     fn sort(&self, expr: Vec<Expr>) -> Result<Arc<dyn DataFrame>> {
         let plan = LogicalPlanBuilder::from(&self.plan).sort(expr)?.build()?;
@@ -137,9 +145,9 @@ impl DataFrame for DataFrameImpl {
         self.plan.schema()
     }
 
-    fn explain(&self, verbose: bool) -> Result<Arc<dyn DataFrame>> {
+    fn explain(&self, verbose: bool, analyze: bool) -> Result<Arc<dyn DataFrame>> {
         let plan = LogicalPlanBuilder::from(&self.plan)
-            .explain(verbose)?
+            .explain(verbose, analyze)?
             .build()?;
         Ok(Arc::new(DataFrameImpl::new(self.ctx_state.clone(), &plan)))
     }
@@ -236,6 +244,21 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn union() -> Result<()> {
+        let left = test_table()?.select_columns(vec!["c1", "c2"])?;
+        let right = test_table()?.select_columns(vec!["c1", "c3"])?;
+        let union = left.union(right)?;
+        assert_eq!("c1", union.schema().field(0).name());
+        assert_eq!("c2", union.schema().field(1).name());
+        // c2 is UInt32 and c3 is Int8, which are both coerced to Int64
+        assert_eq!(&DataType::Int64, union.schema().field(1).data_type());
+        let union_rows = union.collect().await?;
+        let count: usize = union_rows.iter().map(|batch| batch.num_rows()).sum();
+        assert_eq!(200, count);
+        Ok(())
+    }
+
     #[test]
     fn limit() -> Result<()> {
         // build query using Table API
@@ -260,7 +283,7 @@ mod tests {
         let df = df
             .select_columns(vec!["c1", "c2", "c11"])?
             .limit(10)?
-            .explain(false)?;
+            .explain(false, false)?;
         let plan = df.to_logical_plan();
 
         // build query using SQL
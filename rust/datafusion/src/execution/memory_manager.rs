@@ -0,0 +1,202 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Accounting of the memory that the operators of queries use while they execute.
+//!
+//! Operators reserve the memory that they buffer from a [MemoryManager], and release it
+//! once they no longer need it. The memory of each query is reserved from a manager of its
+//! own, whose reservations also count against the manager of its context, so that both the
+//! memory of a query and the memory of all the queries of the context, or of all the contexts
+//! that share a manager such as in a process, can be limited.
+
+use std::sync::{Arc, Mutex};
+
+use crate::error::{DataFusionError, Result};
+
+/// Accounts the memory that is reserved from it, up to an optional limit
+#[derive(Debug)]
+pub struct MemoryManager {
+    /// the largest number of bytes that may be reserved at once, if any
+    limit: Option<usize>,
+    /// the number of bytes that are reserved
+    reserved: Mutex<usize>,
+    /// the manager that the reservations from this manager also count against, if any
+    parent: Option<Arc<MemoryManager>>,
+}
+
+impl MemoryManager {
+    /// Create a new manager from which up to `limit` bytes may be reserved at once, or any
+    /// number of bytes without a limit
+    pub fn new(limit: Option<usize>) -> Self {
+        Self {
+            limit,
+            reserved: Mutex::new(0),
+            parent: None,
+        }
+    }
+
+    /// Create a new manager from which up to `limit` bytes may be reserved at once, and whose
+    /// reservations also count against this manager, such as for a query
+    pub fn new_child(self: &Arc<Self>, limit: Option<usize>) -> Arc<Self> {
+        Arc::new(Self {
+            limit,
+            reserved: Mutex::new(0),
+            parent: Some(self.clone()),
+        })
+    }
+
+    /// The largest number of bytes that may be reserved from this manager at once, if any
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// The number of bytes that are reserved from this manager
+    pub fn reserved(&self) -> usize {
+        *self.lock_reserved()
+    }
+
+    /// Create a new reservation from this manager, of no memory yet
+    pub fn new_reservation(self: &Arc<Self>) -> MemoryReservation {
+        MemoryReservation {
+            manager: self.clone(),
+            size: 0,
+        }
+    }
+
+    fn lock_reserved(&self) -> std::sync::MutexGuard<usize> {
+        // the count is always consistent, even if a thread panicked while holding the lock
+        self.reserved
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Reserves `bytes` more from this manager and its parents, unless a limit would be
+    /// exceeded
+    fn try_grow(&self, bytes: usize) -> Result<()> {
+        let mut reserved = self.lock_reserved();
+        if let Some(limit) = self.limit {
+            if *reserved + bytes > limit {
+                return Err(DataFusionError::ResourcesExhausted(format!(
+                    "Cannot reserve {} bytes of memory, as {} of the limit of {} bytes are \
+                     already reserved",
+                    bytes, *reserved, limit
+                )));
+            }
+        }
+        if let Some(parent) = &self.parent {
+            parent.try_grow(bytes)?;
+        }
+        *reserved += bytes;
+        Ok(())
+    }
+
+    /// Releases `bytes` that were reserved from this manager and its parents
+    fn shrink(&self, bytes: usize) {
+        let mut reserved = self.lock_reserved();
+        *reserved = reserved.saturating_sub(bytes);
+        if let Some(parent) = &self.parent {
+            parent.shrink(bytes);
+        }
+    }
+}
+
+/// Memory that an operator reserved from a [MemoryManager], which is released once the
+/// reservation is dropped
+#[derive(Debug)]
+pub struct MemoryReservation {
+    manager: Arc<MemoryManager>,
+    size: usize,
+}
+
+impl MemoryReservation {
+    /// The number of bytes of this reservation
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Grows this reservation by `bytes`, or returns a [DataFusionError::ResourcesExhausted]
+    /// when this would exceed the limit of its manager, or of a parent of its manager
+    pub fn try_grow(&mut self, bytes: usize) -> Result<()> {
+        self.manager.try_grow(bytes)?;
+        self.size += bytes;
+        Ok(())
+    }
+
+    /// Grows or shrinks this reservation to `size` bytes, see [MemoryReservation::try_grow]
+    pub fn try_resize(&mut self, size: usize) -> Result<()> {
+        if size > self.size {
+            self.try_grow(size - self.size)
+        } else {
+            self.shrink(self.size - size);
+            Ok(())
+        }
+    }
+
+    /// Shrinks this reservation by `bytes`
+    pub fn shrink(&mut self, bytes: usize) {
+        let bytes = bytes.min(self.size);
+        self.manager.shrink(bytes);
+        self.size -= bytes;
+    }
+
+    /// Releases all the memory of this reservation
+    pub fn free(&mut self) {
+        self.shrink(self.size)
+    }
+}
+
+impl Drop for MemoryReservation {
+    fn drop(&mut self) {
+        self.free()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reserve_up_to_limits() -> Result<()> {
+        let process = Arc::new(MemoryManager::new(Some(100)));
+        let query1 = process.new_child(Some(60));
+        let query2 = process.new_child(Some(60));
+
+        let mut reservation1 = query1.new_reservation();
+        reservation1.try_grow(50)?;
+        // the limit of the query
+        let e = reservation1.try_grow(20).unwrap_err();
+        assert!(e.to_string().contains("Resources exhausted"));
+        assert_eq!(reservation1.size(), 50);
+
+        // the limit of the process
+        let mut reservation2 = query2.new_reservation();
+        assert!(reservation2.try_grow(60).is_err());
+        reservation2.try_grow(40)?;
+        assert_eq!(process.reserved(), 90);
+        assert_eq!(query2.reserved(), 40);
+
+        reservation1.try_resize(10)?;
+        assert_eq!(query1.reserved(), 10);
+        drop(reservation2);
+        assert_eq!(query2.reserved(), 0);
+        assert_eq!(process.reserved(), 10);
+        reservation1.free();
+        assert_eq!(process.reserved(), 0);
+
+        Ok(())
+    }
+}
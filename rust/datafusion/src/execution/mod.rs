@@ -19,3 +19,4 @@
 
 pub mod context;
 pub mod dataframe_impl;
+pub mod memory_manager;
@@ -117,7 +117,8 @@
 //! 2. The planner [`SqlToRel`](sql::planner::SqlToRel) converts logical expressions on the AST to logical expressions [`Expr`s](logical_plan::Expr).
 //! 3. The planner [`SqlToRel`](sql::planner::SqlToRel) converts logical nodes on the AST to a [`LogicalPlan`](logical_plan::LogicalPlan).
 //! 4. [`OptimizerRules`](optimizer::optimizer::OptimizerRule) are applied to the [`LogicalPlan`](logical_plan::LogicalPlan) to optimize it.
-//! 5. The [`LogicalPlan`](logical_plan::LogicalPlan) is converted to an [`ExecutionPlan`](physical_plan::ExecutionPlan) by a [`PhysicalPlanner`](physical_plan::PhysicalPlanner)
+//! 5. The [`LogicalPlan`](logical_plan::LogicalPlan) is converted to an [`ExecutionPlan`](physical_plan::ExecutionPlan) by a [`PhysicalPlanner`](physical_plan::PhysicalPlanner),
+//!    and [`PhysicalOptimizerRules`](physical_optimizer::optimizer::PhysicalOptimizerRule) are applied to it.
 //! 6. The [`ExecutionPlan`](physical_plan::ExecutionPlan) is executed against data through the [`ExecutionContext`](execution::context::ExecutionContext)
 //!
 //! With a [`DataFrame`](dataframe::DataFrame) API, steps 1-3 are not used as the DataFrame builds the [`LogicalPlan`](logical_plan::LogicalPlan) directly.
@@ -167,12 +168,15 @@
 //! * Filter: [`FilterExec`](physical_plan::filter::FilterExec)
 //! * Hash and Grouped aggregations: [`HashAggregateExec`](physical_plan::hash_aggregate::HashAggregateExec)
 //! * Sort: [`SortExec`](physical_plan::sort::SortExec)
+//! * Sort preserving merge: [`SortPreservingMergeExec`](physical_plan::sort_preserving_merge::SortPreservingMergeExec)
+//! * Top-K (sort with a limit): [`TopKExec`](physical_plan::top_k::TopKExec)
 //! * Merge (partitions): [`MergeExec`](physical_plan::merge::MergeExec)
 //! * Limit: [`LocalLimitExec`](physical_plan::limit::LocalLimitExec) and [`GlobalLimitExec`](physical_plan::limit::GlobalLimitExec)
 //! * Scan a CSV: [`CsvExec`](physical_plan::csv::CsvExec)
 //! * Scan a Parquet: [`ParquetExec`](physical_plan::parquet::ParquetExec)
 //! * Scan from memory: [`MemoryExec`](physical_plan::memory::MemoryExec)
 //! * Explain the plan: [`ExplainExec`](physical_plan::explain::ExplainExec)
+//! * Explain the plan with the metrics of its execution: [`AnalyzeExec`](physical_plan::analyze::AnalyzeExec)
 //!
 //! ## Customize
 //!
@@ -192,6 +196,7 @@ pub mod error;
 pub mod execution;
 pub mod logical_plan;
 pub mod optimizer;
+pub mod physical_optimizer;
 pub mod physical_plan;
 pub mod prelude;
 pub mod scalar;
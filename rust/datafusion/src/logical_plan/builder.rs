@@ -19,7 +19,7 @@
 
 use std::{collections::HashMap, sync::Arc};
 
-use arrow::datatypes::{Schema, SchemaRef};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
 
 use crate::datasource::csv::{CsvFile, CsvReadOptions};
 use crate::datasource::parquet::ParquetTable;
@@ -30,7 +30,7 @@ use super::{
     col, exprlist_to_fields, Expr, JoinType, LogicalPlan, PlanType, StringifiedPlan,
     TableSource,
 };
-use crate::physical_plan::hash_utils;
+use crate::physical_plan::{hash_utils, type_coercion};
 
 /// Builder for logical plans
 pub struct LogicalPlanBuilder {
@@ -159,6 +159,25 @@ impl LogicalPlanBuilder {
         }))
     }
 
+    /// Apply window functions, appending one column per window expression to the
+    /// columns of the input.
+    ///
+    /// # Errors
+    /// This function errors if two or more window expressions have the same name.
+    pub fn window(&self, window_expr: Vec<Expr>) -> Result<Self> {
+        let input_schema = self.plan.schema();
+        validate_unique_names("Windows", &window_expr, input_schema)?;
+
+        let mut window_fields = input_schema.fields().clone();
+        window_fields.extend_from_slice(&exprlist_to_fields(&window_expr, input_schema)?);
+
+        Ok(Self::from(&LogicalPlan::Window {
+            input: Arc::new(self.plan.clone()),
+            window_expr,
+            schema: SchemaRef::new(Schema::new(window_fields)),
+        }))
+    }
+
     /// Apply a filter
     pub fn filter(&self, expr: Expr) -> Result<Self> {
         Ok(Self::from(&LogicalPlan::Filter {
@@ -190,6 +209,20 @@ impl LogicalPlanBuilder {
         join_type: JoinType,
         left_keys: &[&str],
         right_keys: &[&str],
+    ) -> Result<Self> {
+        self.join_with_filter(right, join_type, left_keys, right_keys, None)
+    }
+
+    /// Apply a join whose pairs of rows with equal keys must also satisfy `filter`, a
+    /// predicate over the columns of both plans. On outer joins, a row without a pair that
+    /// satisfies `filter` is padded with nulls.
+    pub fn join_with_filter(
+        &self,
+        right: &LogicalPlan,
+        join_type: JoinType,
+        left_keys: &[&str],
+        right_keys: &[&str],
+        filter: Option<Expr>,
     ) -> Result<Self> {
         if left_keys.len() != right_keys.len() {
             Err(DataFusionError::Plan(
@@ -205,6 +238,9 @@ impl LogicalPlanBuilder {
                 JoinType::Inner => hash_utils::JoinType::Inner,
                 JoinType::Left => hash_utils::JoinType::Left,
                 JoinType::Right => hash_utils::JoinType::Right,
+                JoinType::Full => hash_utils::JoinType::Full,
+                JoinType::Semi => hash_utils::JoinType::Semi,
+                JoinType::Anti => hash_utils::JoinType::Anti,
             };
             let physical_schema = hash_utils::build_join_schema(
                 self.plan.schema(),
@@ -212,20 +248,77 @@ impl LogicalPlanBuilder {
                 &on,
                 &physical_join_type,
             );
+            if let Some(filter) = &filter {
+                // the filter is evaluated on the columns of both sides
+                let filter_schema = hash_utils::build_join_schema(
+                    self.plan.schema(),
+                    right.schema(),
+                    &on,
+                    &hash_utils::JoinType::Inner,
+                );
+                let data_type = filter.get_type(&filter_schema)?;
+                if data_type != DataType::Boolean {
+                    return Err(DataFusionError::Plan(format!(
+                        "The filter of a join must be a boolean expression, but its type is {:?}",
+                        data_type
+                    )));
+                }
+            }
             Ok(Self::from(&LogicalPlan::Join {
                 left: Arc::new(self.plan.clone()),
                 right: Arc::new(right.clone()),
-                on: left_keys
-                    .iter()
-                    .zip(right_keys.iter())
-                    .map(|(l, r)| (l.to_string(), r.to_string()))
-                    .collect(),
+                on,
                 join_type,
+                filter,
                 schema: Arc::new(physical_schema),
             }))
         }
     }
 
+    /// Apply a join on the columns `using_keys`, which both plans must have. The output of
+    /// the join has a single copy of each of these columns.
+    pub fn join_using(
+        &self,
+        right: &LogicalPlan,
+        join_type: JoinType,
+        using_keys: &[&str],
+    ) -> Result<Self> {
+        self.join(right, join_type, using_keys, using_keys)
+    }
+
+    /// Apply a cross join, which combines every row of this plan with every row of `right`
+    pub fn cross_join(&self, right: &LogicalPlan) -> Result<Self> {
+        let schema =
+            hash_utils::build_cross_join_schema(self.plan.schema(), right.schema())?;
+        Ok(Self::from(&LogicalPlan::CrossJoin {
+            left: Arc::new(self.plan.clone()),
+            right: Arc::new(right.clone()),
+            schema: Arc::new(schema),
+        }))
+    }
+
+    /// Apply a union with another plan, keeping duplicate rows (UNION ALL).
+    ///
+    /// Both plans must have the same number of columns. Columns are matched by position,
+    /// coerced to a common type and named after the columns of this plan.
+    pub fn union(&self, plan: &LogicalPlan) -> Result<Self> {
+        let (left, right, schema) = coerce_set_operation_inputs(&self.plan, plan)?;
+
+        // a chain of unions is flattened into a single node
+        let mut inputs = vec![];
+        for input in vec![left, right] {
+            match input {
+                LogicalPlan::Union {
+                    inputs: union_inputs,
+                    ..
+                } => inputs.extend(union_inputs),
+                input => inputs.push(input),
+            }
+        }
+
+        Ok(Self::from(&LogicalPlan::Union { inputs, schema }))
+    }
+
     /// Apply an aggregate
     pub fn aggregate(&self, group_expr: Vec<Expr>, aggr_expr: Vec<Expr>) -> Result<Self> {
         let mut all_expr: Vec<Expr> = group_expr.clone();
@@ -243,8 +336,9 @@ impl LogicalPlanBuilder {
         }))
     }
 
-    /// Create an expression to represent the explanation of the plan
-    pub fn explain(&self, verbose: bool) -> Result<Self> {
+    /// Create an expression to represent the explanation of the plan. With `analyze`, the
+    /// plan is executed and the explanation includes the metrics of its execution.
+    pub fn explain(&self, verbose: bool, analyze: bool) -> Result<Self> {
         let stringified_plans = vec![StringifiedPlan::new(
             PlanType::LogicalPlan,
             format!("{:#?}", self.plan.clone()),
@@ -254,6 +348,7 @@ impl LogicalPlanBuilder {
 
         Ok(Self::from(&LogicalPlan::Explain {
             verbose,
+            analyze,
             plan: Arc::new(self.plan.clone()),
             stringified_plans,
             schema,
@@ -266,6 +361,83 @@ impl LogicalPlanBuilder {
     }
 }
 
+/// Returns `left` and `right` with their columns cast and renamed to a common schema, so
+/// that their rows can be combined by a set operation (e.g. UNION), as well as that schema.
+pub(crate) fn coerce_set_operation_inputs(
+    left: &LogicalPlan,
+    right: &LogicalPlan,
+) -> Result<(LogicalPlan, LogicalPlan, SchemaRef)> {
+    let left_schema = left.schema();
+    let right_schema = right.schema();
+    if left_schema.fields().len() != right_schema.fields().len() {
+        return Err(DataFusionError::Plan(format!(
+            "Set operations require inputs with the same number of columns, \
+             but the inputs have {} and {} columns",
+            left_schema.fields().len(),
+            right_schema.fields().len()
+        )));
+    }
+
+    let fields = left_schema
+        .fields()
+        .iter()
+        .zip(right_schema.fields().iter())
+        .map(|(l, r)| {
+            let data_type = type_coercion::common_type(l.data_type(), r.data_type())
+                .ok_or_else(|| {
+                    DataFusionError::Plan(format!(
+                        "Set operations cannot combine column \"{}\" of type {:?} \
+                         with column \"{}\" of type {:?}",
+                        l.name(),
+                        l.data_type(),
+                        r.name(),
+                        r.data_type()
+                    ))
+                })?;
+            Ok(Field::new(
+                l.name(),
+                data_type,
+                l.is_nullable() || r.is_nullable(),
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let schema = SchemaRef::new(Schema::new(fields));
+
+    Ok((
+        project_to_schema(left, &schema)?,
+        project_to_schema(right, &schema)?,
+        schema,
+    ))
+}
+
+/// Wraps `plan` in a projection that casts and renames its columns to the ones of `schema`,
+/// unless they already have the expected names and types.
+fn project_to_schema(plan: &LogicalPlan, schema: &Schema) -> Result<LogicalPlan> {
+    let fields = plan.schema().fields().iter().zip(schema.fields().iter());
+    if fields.clone().all(|(f, target)| {
+        f.name() == target.name() && f.data_type() == target.data_type()
+    }) {
+        return Ok(plan.clone());
+    }
+
+    let expr = fields
+        .map(|(f, target)| {
+            if f.name() == target.name() && f.data_type() == target.data_type() {
+                col(f.name())
+            } else if f.data_type() == target.data_type() {
+                col(f.name()).alias(target.name())
+            } else {
+                Expr::Cast {
+                    expr: Box::new(col(f.name())),
+                    data_type: target.data_type().clone(),
+                }
+                .alias(target.name())
+            }
+        })
+        .collect();
+    LogicalPlanBuilder::from(plan).project(expr)?.build()
+}
+
 /// Errors if one or more expressions have equal names.
 fn validate_unique_names(
     node_name: &str,
@@ -395,6 +567,143 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn plan_builder_join_using() -> Result<()> {
+        let left = LogicalPlanBuilder::scan(
+            "default",
+            "employee.csv",
+            &employee_schema(),
+            Some(vec![0, 3]),
+        )?
+        .build()?;
+        let right = LogicalPlanBuilder::scan(
+            "default",
+            "employee.csv",
+            &employee_schema(),
+            Some(vec![0, 4]),
+        )?
+        .build()?;
+
+        let column_names = |plan: &LogicalPlan| {
+            plan.schema()
+                .fields()
+                .iter()
+                .map(|f| f.name().clone())
+                .collect::<Vec<_>>()
+        };
+
+        // a single copy of the join column is kept
+        let plan = LogicalPlanBuilder::from(&left)
+            .join_using(&right, JoinType::Inner, &["id"])?
+            .build()?;
+        assert_eq!(column_names(&plan), vec!["id", "state", "salary"]);
+
+        let plan = LogicalPlanBuilder::from(&left)
+            .join_using(&right, JoinType::Right, &["id"])?
+            .build()?;
+        assert_eq!(column_names(&plan), vec!["state", "id", "salary"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn plan_builder_union() -> Result<()> {
+        let plan = LogicalPlanBuilder::scan(
+            "default",
+            "employee.csv",
+            &employee_schema(),
+            Some(vec![0, 3]),
+        )?
+        .project(vec![col("id"), col("state")])?
+        .build()?;
+        let other = LogicalPlanBuilder::scan(
+            "default",
+            "employee.csv",
+            &employee_schema(),
+            Some(vec![1, 4]),
+        )?
+        .project(vec![col("salary"), col("first_name")])?
+        .build()?;
+
+        let plan = LogicalPlanBuilder::from(&plan)
+            .union(&plan)?
+            .union(&other)?
+            .build()?;
+
+        // the union is flattened and the inputs are renamed to the first input's columns
+        let expected = "Union\
+        \n  Projection: #id, #state\
+        \n    TableScan: employee.csv projection=Some([0, 3])\
+        \n  Projection: #id, #state\
+        \n    TableScan: employee.csv projection=Some([0, 3])\
+        \n  Projection: #salary AS id, #first_name AS state\
+        \n    Projection: #salary, #first_name\
+        \n      TableScan: employee.csv projection=Some([1, 4])";
+
+        assert_eq!(expected, format!("{:?}", plan));
+
+        Ok(())
+    }
+
+    #[test]
+    fn plan_builder_union_coerces_types() -> Result<()> {
+        let plan = LogicalPlanBuilder::scan(
+            "default",
+            "employee.csv",
+            &employee_schema(),
+            Some(vec![0]),
+        )?
+        .build()?;
+        let other = LogicalPlanBuilder::empty(true)
+            .project(vec![lit(1i64).alias("id")])?
+            .build()?;
+
+        let plan = LogicalPlanBuilder::from(&plan).union(&other)?.build()?;
+
+        let expected = "Union\
+        \n  Projection: CAST(#id AS Int64) AS id\
+        \n    TableScan: employee.csv projection=Some([0])\
+        \n  Projection: Int64(1) AS id\
+        \n    EmptyRelation";
+
+        assert_eq!(expected, format!("{:?}", plan));
+        assert_eq!(&DataType::Int64, plan.schema().field(0).data_type());
+
+        Ok(())
+    }
+
+    #[test]
+    fn union_different_number_of_columns() -> Result<()> {
+        let plan = LogicalPlanBuilder::scan(
+            "default",
+            "employee.csv",
+            &employee_schema(),
+            Some(vec![0, 3]),
+        )?
+        .build()?;
+        let other = LogicalPlanBuilder::scan(
+            "default",
+            "employee.csv",
+            &employee_schema(),
+            Some(vec![0]),
+        )?
+        .build()?;
+
+        match LogicalPlanBuilder::from(&plan).union(&other) {
+            Err(DataFusionError::Plan(e)) => {
+                assert_eq!(
+                    e,
+                    "Set operations require inputs with the same number of columns, \
+                     but the inputs have 2 and 1 columns"
+                );
+                Ok(())
+            }
+            _ => Err(DataFusionError::Plan(
+                "Plan should have returned an DataFusionError::Plan".to_string(),
+            )),
+        }
+    }
+
     #[test]
     fn projection_non_unique_names() -> Result<()> {
         let plan = LogicalPlanBuilder::scan(
@@ -31,10 +31,14 @@ use arrow::{
 use crate::error::{DataFusionError, Result};
 use crate::physical_plan::{
     aggregates, expressions::binary_operator_data_type, functions, udf::ScalarUDF,
+    window_functions,
 };
 use crate::{physical_plan::udaf::AggregateUDF, scalar::ScalarValue};
 use functions::{ReturnTypeFunction, ScalarFunctionImplementation, Signature};
 use std::collections::HashSet;
+use window_functions::{BuiltInWindowFunction, WindowFunction};
+
+use super::WindowFrame;
 
 /// `Expr` is a logical expression. A logical expression is something like `1 + 1`, or `CAST(c1 AS int)`.
 /// Logical expressions know how to compute its [arrow::datatypes::DataType] and nullability.
@@ -76,6 +80,28 @@ pub enum Expr {
     IsNotNull(Box<Expr>),
     /// Whether an expression is Null. This expression is never null.
     IsNull(Box<Expr>),
+    /// Whether an expression is equal to any of the expressions of a list, such as
+    /// "state IN ('CO', 'NY')"
+    InList {
+        /// The expression to look up in the list
+        expr: Box<Expr>,
+        /// The list of expressions
+        list: Vec<Expr>,
+        /// Whether the expression is NOT IN the list
+        negated: bool,
+    },
+    /// Whether an expression is between two other expressions, bounds included, such as
+    /// "age BETWEEN 18 AND 65"
+    Between {
+        /// The expression to compare with the bounds
+        expr: Box<Expr>,
+        /// Whether the expression is NOT BETWEEN the bounds
+        negated: bool,
+        /// The lower bound
+        low: Box<Expr>,
+        /// The upper bound
+        high: Box<Expr>,
+    },
     /// The CASE expression is similar to a series of nested if/else and there are two forms that
     /// can be used. The first form consists of a series of boolean "when" expressions with
     /// corresponding "then" expressions, and an optional "else" expression.
@@ -147,6 +173,20 @@ pub enum Expr {
         /// List of expressions to feed to the functions as arguments
         args: Vec<Expr>,
     },
+    /// Represents the call of a window function, which is evaluated for every row over the
+    /// rows of its partition, such as "SUM(amount) OVER (PARTITION BY region ORDER BY day)"
+    WindowFunction {
+        /// Name of the function
+        fun: WindowFunction,
+        /// List of expressions to feed to the functions as arguments
+        args: Vec<Expr>,
+        /// The expressions that partition the rows
+        partition_by: Vec<Expr>,
+        /// The sort expressions that order the rows of every partition
+        order_by: Vec<Expr>,
+        /// The frame of the function, when one was specified
+        window_frame: Option<WindowFrame>,
+    },
     /// Represents a reference to all fields in a schema.
     Wildcard,
 }
@@ -195,9 +235,18 @@ impl Expr {
                     .collect::<Result<Vec<_>>>()?;
                 Ok((fun.return_type)(&data_types)?.as_ref().clone())
             }
+            Expr::WindowFunction { fun, args, .. } => {
+                let data_types = args
+                    .iter()
+                    .map(|e| e.get_type(schema))
+                    .collect::<Result<Vec<_>>>()?;
+                window_functions::return_type(fun, &data_types)
+            }
             Expr::Not(_) => Ok(DataType::Boolean),
             Expr::IsNull(_) => Ok(DataType::Boolean),
             Expr::IsNotNull(_) => Ok(DataType::Boolean),
+            Expr::InList { .. } => Ok(DataType::Boolean),
+            Expr::Between { .. } => Ok(DataType::Boolean),
             Expr::BinaryExpr {
                 ref left,
                 ref right,
@@ -249,9 +298,33 @@ impl Expr {
             Expr::ScalarUDF { .. } => Ok(true),
             Expr::AggregateFunction { .. } => Ok(true),
             Expr::AggregateUDF { .. } => Ok(true),
+            Expr::WindowFunction { fun, .. } => match fun {
+                // every row has a number and a rank
+                WindowFunction::BuiltInWindowFunction(
+                    BuiltInWindowFunction::RowNumber,
+                )
+                | WindowFunction::BuiltInWindowFunction(BuiltInWindowFunction::Rank)
+                | WindowFunction::BuiltInWindowFunction(
+                    BuiltInWindowFunction::DenseRank,
+                ) => Ok(false),
+                _ => Ok(true),
+            },
             Expr::Not(expr) => expr.nullable(input_schema),
             Expr::IsNull(_) => Ok(false),
             Expr::IsNotNull(_) => Ok(false),
+            Expr::InList { expr, list, .. } => {
+                // the expression is null when it is not found and the list has nulls
+                let mut nullable = expr.nullable(input_schema)?;
+                for e in list {
+                    nullable = nullable || e.nullable(input_schema)?;
+                }
+                Ok(nullable)
+            }
+            Expr::Between {
+                expr, low, high, ..
+            } => Ok(expr.nullable(input_schema)?
+                || low.nullable(input_schema)?
+                || high.nullable(input_schema)?),
             Expr::BinaryExpr {
                 ref left,
                 ref right,
@@ -363,6 +436,26 @@ impl Expr {
         binary_expr(self.clone(), Operator::NotLike, other)
     }
 
+    /// Whether the expression is between `low` and `high`, bounds included
+    pub fn between(&self, low: Expr, high: Expr) -> Expr {
+        Expr::Between {
+            expr: Box::new(self.clone()),
+            negated: false,
+            low: Box::new(low),
+            high: Box::new(high),
+        }
+    }
+
+    /// Whether the expression is not between `low` and `high`
+    pub fn not_between(&self, low: Expr, high: Expr) -> Expr {
+        Expr::Between {
+            expr: Box::new(self.clone()),
+            negated: true,
+            low: Box::new(low),
+            high: Box::new(high),
+        }
+    }
+
     /// Alias
     pub fn alias(&self, name: &str) -> Expr {
         Expr::Alias(Box::new(self.clone()), name.to_owned())
@@ -499,6 +592,16 @@ pub fn or(left: Expr, right: Expr) -> Expr {
     }
 }
 
+/// return a new expression that is true when `expr` is equal to any of the expressions of
+/// `list`, or when it is not if `negated`
+pub fn in_list(expr: Expr, list: Vec<Expr>, negated: bool) -> Expr {
+    Expr::InList {
+        expr: Box::new(expr),
+        list,
+        negated,
+    }
+}
+
 /// Create a column expression based on a column name
 pub fn col(name: &str) -> Expr {
     Expr::Column(name.to_owned())
@@ -643,6 +746,123 @@ pub fn concat(args: Vec<Expr>) -> Expr {
     }
 }
 
+macro_rules! unary_string_expr {
+    ($ENUM:ident, $FUNC:ident, $DOC:expr) => {
+        #[doc = $DOC]
+        pub fn $FUNC(e: Expr) -> Expr {
+            Expr::ScalarFunction {
+                fun: functions::BuiltinScalarFunction::$ENUM,
+                args: vec![e],
+            }
+        }
+    };
+}
+
+macro_rules! string_expr {
+    ($ENUM:ident, $FUNC:ident, $DOC:expr) => {
+        #[doc = $DOC]
+        pub fn $FUNC(args: Vec<Expr>) -> Expr {
+            Expr::ScalarFunction {
+                fun: functions::BuiltinScalarFunction::$ENUM,
+                args,
+            }
+        }
+    };
+}
+
+// generate methods for creating the supported string expressions
+unary_string_expr!(Lower, lower, "converts a string to lower case");
+unary_string_expr!(Upper, upper, "converts a string to upper case");
+unary_string_expr!(Reverse, reverse, "reverses the characters of a string");
+unary_string_expr!(Initcap, initcap, "capitalizes each word of a string");
+unary_string_expr!(Chr, chr, "returns the character of a code point");
+unary_string_expr!(Ascii, ascii, "returns the code point of a character");
+unary_string_expr!(Md5, md5, "returns the hexadecimal MD5 hash of a string");
+unary_string_expr!(
+    Sha256,
+    sha256,
+    "returns the hexadecimal SHA-256 hash of a string"
+);
+string_expr!(Trim, trim, "removes characters from both ends of a string");
+string_expr!(
+    Ltrim,
+    ltrim,
+    "removes characters from the start of a string"
+);
+string_expr!(Rtrim, rtrim, "removes characters from the end of a string");
+string_expr!(Lpad, lpad, "pads a string on the left to a length");
+string_expr!(Rpad, rpad, "pads a string on the right to a length");
+string_expr!(
+    Substr,
+    substr,
+    "extracts the characters of a string from a position"
+);
+string_expr!(
+    Replace,
+    replace,
+    "replaces all the occurrences of a substring"
+);
+string_expr!(
+    SplitPart,
+    split_part,
+    "returns a field of a string split by a delimiter"
+);
+string_expr!(
+    StartsWith,
+    starts_with,
+    "returns whether a string starts with a prefix"
+);
+string_expr!(
+    Strpos,
+    strpos,
+    "returns the position of a substring in a string"
+);
+string_expr!(Left, left, "returns the first characters of a string");
+string_expr!(Right, right, "returns the last characters of a string");
+string_expr!(Repeat, repeat, "repeats a string a number of times");
+string_expr!(
+    RegexpMatch,
+    regexp_match,
+    "returns the captures of a regex in a string"
+);
+string_expr!(
+    RegexpReplace,
+    regexp_replace,
+    "replaces the matches of a regex"
+);
+
+/// truncates a timestamp to a granularity, such as 'hour' or 'month'
+pub fn date_trunc(granularity: Expr, timestamp: Expr) -> Expr {
+    Expr::ScalarFunction {
+        fun: functions::BuiltinScalarFunction::DateTrunc,
+        args: vec![granularity, timestamp],
+    }
+}
+
+/// returns a field of a timestamp or date, such as 'year' or 'dow'
+pub fn date_part(field: Expr, timestamp: Expr) -> Expr {
+    Expr::ScalarFunction {
+        fun: functions::BuiltinScalarFunction::DatePart,
+        args: vec![field, timestamp],
+    }
+}
+
+/// converts a string of the form 'YYYY-MM-DD' to a date
+pub fn to_date(e: Expr) -> Expr {
+    Expr::ScalarFunction {
+        fun: functions::BuiltinScalarFunction::ToDate,
+        args: vec![e],
+    }
+}
+
+/// returns the time that the query started, the same for all its rows
+pub fn now() -> Expr {
+    Expr::ScalarFunction {
+        fun: functions::BuiltinScalarFunction::Now,
+        args: vec![],
+    }
+}
+
 /// returns an array of fixed size with each argument on it.
 pub fn array(args: Vec<Expr>) -> Expr {
     Expr::ScalarFunction {
@@ -731,6 +951,24 @@ impl fmt::Debug for Expr {
             Expr::Not(expr) => write!(f, "NOT {:?}", expr),
             Expr::IsNull(expr) => write!(f, "{:?} IS NULL", expr),
             Expr::IsNotNull(expr) => write!(f, "{:?} IS NOT NULL", expr),
+            Expr::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                let list: Vec<String> = list.iter().map(|e| format!("{:?}", e)).collect();
+                let not = if *negated { "NOT " } else { "" };
+                write!(f, "{:?} {}IN ({})", expr, not, list.join(", "))
+            }
+            Expr::Between {
+                expr,
+                negated,
+                low,
+                high,
+            } => {
+                let not = if *negated { "NOT " } else { "" };
+                write!(f, "{:?} {}BETWEEN {:?} AND {:?}", expr, not, low, high)
+            }
             Expr::BinaryExpr { left, op, right } => {
                 write!(f, "{:?} {:?} {:?}", left, op, right)
             }
@@ -765,11 +1003,57 @@ impl fmt::Debug for Expr {
             Expr::AggregateUDF { fun, ref args, .. } => {
                 fmt_function(f, &fun.name, false, args)
             }
+            Expr::WindowFunction {
+                fun,
+                args,
+                partition_by,
+                order_by,
+                window_frame,
+            } => {
+                let args: Vec<String> = args.iter().map(|e| format!("{:?}", e)).collect();
+                let partition_by: Vec<String> =
+                    partition_by.iter().map(|e| format!("{:?}", e)).collect();
+                let order_by: Vec<String> =
+                    order_by.iter().map(|e| format!("{:?}", e)).collect();
+                write!(
+                    f,
+                    "{}",
+                    window_function_string(
+                        &fun.to_string(),
+                        &args,
+                        &partition_by,
+                        &order_by,
+                        window_frame
+                    )
+                )
+            }
             Expr::Wildcard => write!(f, "*"),
         }
     }
 }
 
+/// Returns the string of a call of a window function, given the strings of its expressions,
+/// e.g. `SUM(c2) OVER (PARTITION BY c1 ORDER BY c3 ASC NULLS FIRST)`
+fn window_function_string(
+    fun: &str,
+    args: &[String],
+    partition_by: &[String],
+    order_by: &[String],
+    window_frame: &Option<WindowFrame>,
+) -> String {
+    let mut over = vec![];
+    if !partition_by.is_empty() {
+        over.push(format!("PARTITION BY {}", partition_by.join(", ")));
+    }
+    if !order_by.is_empty() {
+        over.push(format!("ORDER BY {}", order_by.join(", ")));
+    }
+    if let Some(window_frame) = window_frame {
+        over.push(format!("{}", window_frame));
+    }
+    format!("{}({}) OVER ({})", fun, args.join(", "), over.join(" "))
+}
+
 fn create_function_name(
     fun: &String,
     distinct: bool,
@@ -834,6 +1118,31 @@ fn create_name(e: &Expr, input_schema: &Schema) -> Result<String> {
             let expr = create_name(expr, input_schema)?;
             Ok(format!("{} IS NOT NULL", expr))
         }
+        Expr::InList {
+            expr,
+            list,
+            negated,
+        } => {
+            let expr = create_name(expr, input_schema)?;
+            let list = list
+                .iter()
+                .map(|e| create_name(e, input_schema))
+                .collect::<Result<Vec<_>>>()?;
+            let not = if *negated { "NOT " } else { "" };
+            Ok(format!("{} {}IN ({})", expr, not, list.join(", ")))
+        }
+        Expr::Between {
+            expr,
+            negated,
+            low,
+            high,
+        } => {
+            let expr = create_name(expr, input_schema)?;
+            let low = create_name(low, input_schema)?;
+            let high = create_name(high, input_schema)?;
+            let not = if *negated { "NOT " } else { "" };
+            Ok(format!("{} {}BETWEEN {} AND {}", expr, not, low, high))
+        }
         Expr::ScalarFunction { fun, args, .. } => {
             create_function_name(&fun.to_string(), false, args, input_schema)
         }
@@ -853,6 +1162,49 @@ fn create_name(e: &Expr, input_schema: &Schema) -> Result<String> {
             }
             Ok(format!("{}({})", fun.name, names.join(",")))
         }
+        Expr::WindowFunction {
+            fun,
+            args,
+            partition_by,
+            order_by,
+            window_frame,
+        } => {
+            let args = args
+                .iter()
+                .map(|e| create_name(e, input_schema))
+                .collect::<Result<Vec<_>>>()?;
+            let partition_by = partition_by
+                .iter()
+                .map(|e| create_name(e, input_schema))
+                .collect::<Result<Vec<_>>>()?;
+            let order_by = order_by
+                .iter()
+                .map(|e| match e {
+                    Expr::Sort {
+                        expr,
+                        asc,
+                        nulls_first,
+                    } => Ok(format!(
+                        "{} {} {}",
+                        create_name(expr, input_schema)?,
+                        if *asc { "ASC" } else { "DESC" },
+                        if *nulls_first {
+                            "NULLS FIRST"
+                        } else {
+                            "NULLS LAST"
+                        }
+                    )),
+                    other => create_name(other, input_schema),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(window_function_string(
+                &fun.to_string(),
+                &args,
+                &partition_by,
+                &order_by,
+                window_frame,
+            ))
+        }
         other => Err(DataFusionError::NotImplemented(format!(
             "Physical plan does not support logical expression {:?}",
             other
@@ -28,13 +28,18 @@ mod extension;
 mod operators;
 mod plan;
 mod registry;
+mod window_frames;
 
+pub(crate) use builder::coerce_set_operation_inputs;
 pub use builder::LogicalPlanBuilder;
 pub use display::display_schema;
 pub use expr::{
-    abs, acos, and, array, asin, atan, avg, binary_expr, case, ceil, col, concat, cos,
-    count, create_udaf, create_udf, exp, exprlist_to_fields, floor, length, lit, ln,
-    log10, log2, max, min, or, round, signum, sin, sqrt, sum, tan, trunc, when, Expr,
+    abs, acos, and, array, ascii, asin, atan, avg, binary_expr, case, ceil, chr, col,
+    concat, cos, count, create_udaf, create_udf, date_part, date_trunc, exp,
+    exprlist_to_fields, floor, in_list, initcap, left, length, lit, ln, log10, log2,
+    lower, lpad, ltrim, max, md5, min, now, or, regexp_match, regexp_replace, repeat,
+    replace, reverse, right, round, rpad, rtrim, sha256, signum, sin, split_part, sqrt,
+    starts_with, strpos, substr, sum, tan, to_date, trim, trunc, upper, when, Expr,
     Literal,
 };
 pub use extension::UserDefinedLogicalNode;
@@ -43,3 +48,4 @@ pub use plan::{
     JoinType, LogicalPlan, PlanType, PlanVisitor, StringifiedPlan, TableSource,
 };
 pub use registry::FunctionRegistry;
+pub use window_frames::{WindowFrame, WindowFrameBound, WindowFrameUnits};
@@ -50,6 +50,12 @@ pub enum JoinType {
     Left,
     /// Right join
     Right,
+    /// Full outer join
+    Full,
+    /// Left semi join: the rows of the left input that have a match on the right input
+    Semi,
+    /// Left anti join: the rows of the left input that have no match on the right input
+    Anti,
 }
 
 /// A LogicalPlan represents the different types of relational
@@ -98,6 +104,17 @@ pub enum LogicalPlan {
         /// The schema description of the aggregate output
         schema: SchemaRef,
     },
+    /// Evaluates window functions over its input (e.g. `ROW_NUMBER() OVER
+    /// (PARTITION BY c1 ORDER BY c2)`), appending one column per window
+    /// expression to the columns of its input.
+    Window {
+        /// The incoming logical plan
+        input: Arc<LogicalPlan>,
+        /// The window function expressions
+        window_expr: Vec<Expr>,
+        /// The schema description of the window output
+        schema: SchemaRef,
+    },
     /// Sorts its input according to a list of sort expressions.
     Sort {
         /// The sort expressions
@@ -115,9 +132,29 @@ pub enum LogicalPlan {
         on: Vec<(String, String)>,
         /// Join type
         join_type: JoinType,
+        /// Optional predicate over the columns of both inputs that the pairs of rows with
+        /// equal join columns must satisfy
+        filter: Option<Expr>,
+        /// The output schema, containing fields from the left and right inputs
+        schema: SchemaRef,
+    },
+    /// Combines every row of the left input with every row of the right input
+    CrossJoin {
+        /// Left input
+        left: Arc<LogicalPlan>,
+        /// Right input
+        right: Arc<LogicalPlan>,
         /// The output schema, containing fields from the left and right inputs
         schema: SchemaRef,
     },
+    /// Concatenates the rows of several logical plans that share the same
+    /// schema (essentially a UNION ALL).
+    Union {
+        /// Inputs to merge
+        inputs: Vec<LogicalPlan>,
+        /// The output schema, which is the schema of the first input
+        schema: SchemaRef,
+    },
     /// Produces rows from a table provider by reference or from the context
     TableScan {
         /// The name of the schema
@@ -200,6 +237,8 @@ pub enum LogicalPlan {
     Explain {
         /// Should extra (detailed, intermediate plans) be included?
         verbose: bool,
+        /// Should the plan be executed, to include the metrics of its execution?
+        analyze: bool,
         /// The logical plan that is being EXPLAIN'd
         plan: Arc<LogicalPlan>,
         /// Represent the various stages plans have gone through
@@ -234,8 +273,11 @@ impl LogicalPlan {
             LogicalPlan::Projection { schema, .. } => &schema,
             LogicalPlan::Filter { input, .. } => input.schema(),
             LogicalPlan::Aggregate { schema, .. } => &schema,
+            LogicalPlan::Window { schema, .. } => &schema,
             LogicalPlan::Sort { input, .. } => input.schema(),
             LogicalPlan::Join { schema, .. } => &schema,
+            LogicalPlan::CrossJoin { schema, .. } => &schema,
+            LogicalPlan::Union { schema, .. } => &schema,
             LogicalPlan::Limit { input, .. } => input.schema(),
             LogicalPlan::CreateExternalTable { schema, .. } => &schema,
             LogicalPlan::Explain { schema, .. } => &schema,
@@ -316,10 +358,20 @@ impl LogicalPlan {
             LogicalPlan::Projection { input, .. } => input.accept(visitor)?,
             LogicalPlan::Filter { input, .. } => input.accept(visitor)?,
             LogicalPlan::Aggregate { input, .. } => input.accept(visitor)?,
+            LogicalPlan::Window { input, .. } => input.accept(visitor)?,
             LogicalPlan::Sort { input, .. } => input.accept(visitor)?,
-            LogicalPlan::Join { left, right, .. } => {
+            LogicalPlan::Join { left, right, .. }
+            | LogicalPlan::CrossJoin { left, right, .. } => {
                 left.accept(visitor)? && right.accept(visitor)?
             }
+            LogicalPlan::Union { inputs, .. } => {
+                for input in inputs {
+                    if !input.accept(visitor)? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
             LogicalPlan::Limit { input, .. } => input.accept(visitor)?,
             LogicalPlan::Extension { node } => {
                 for input in node.inputs() {
@@ -573,6 +625,9 @@ impl LogicalPlan {
                         "Aggregate: groupBy=[{:?}], aggr=[{:?}]",
                         group_expr, aggr_expr
                     ),
+                    LogicalPlan::Window {
+                        ref window_expr, ..
+                    } => write!(f, "Window: windowExpr=[{:?}]", window_expr),
                     LogicalPlan::Sort { ref expr, .. } => {
                         write!(f, "Sort: ")?;
                         for i in 0..expr.len() {
@@ -583,11 +638,21 @@ impl LogicalPlan {
                         }
                         Ok(())
                     }
-                    LogicalPlan::Join { on: ref keys, .. } => {
+                    LogicalPlan::Join {
+                        on: ref keys,
+                        ref filter,
+                        ..
+                    } => {
                         let join_expr: Vec<String> =
                             keys.iter().map(|(l, r)| format!("{} = {}", l, r)).collect();
-                        write!(f, "Join: {}", join_expr.join(", "))
+                        write!(f, "Join: {}", join_expr.join(", "))?;
+                        if let Some(filter) = filter {
+                            write!(f, ", filter={:?}", filter)?;
+                        }
+                        Ok(())
                     }
+                    LogicalPlan::CrossJoin { .. } => write!(f, "CrossJoin"),
+                    LogicalPlan::Union { .. } => write!(f, "Union"),
                     LogicalPlan::Limit { ref n, .. } => write!(f, "Limit: {}", n),
                     LogicalPlan::CreateExternalTable { ref name, .. } => {
                         write!(f, "CreateExternalTable: {:?}", name)
@@ -619,6 +684,8 @@ pub enum PlanType {
     },
     /// The physical plan, prepared for execution
     PhysicalPlan,
+    /// The physical plan, annotated with the metrics of its execution
+    PhysicalPlanWithMetrics,
 }
 
 impl From<&PlanType> for String {
@@ -629,6 +696,7 @@ impl From<&PlanType> for String {
                 format!("logical_plan after {}", optimizer_name)
             }
             PlanType::PhysicalPlan => "physical_plan".into(),
+            PlanType::PhysicalPlanWithMetrics => "physical_plan_with_metrics".into(),
         }
     }
 }
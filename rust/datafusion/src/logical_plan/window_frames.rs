@@ -0,0 +1,246 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Window frames, which describe the rows of a partition over which a window function is
+//! evaluated for every row, such as `ROWS BETWEEN 1 PRECEDING AND 1 FOLLOWING`.
+
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+
+use sqlparser::ast;
+
+use crate::error::{DataFusionError, Result};
+
+/// The frame of a window function.
+///
+/// The frame is relative to the current row: it starts at `start_bound` and ends at
+/// `end_bound`, both included, and never extends beyond the partition of the current row.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowFrame {
+    /// Whether the bounds are measured in rows or in values of the ORDER BY expression
+    pub units: WindowFrameUnits,
+    /// The start of the frame
+    pub start_bound: WindowFrameBound,
+    /// The end of the frame
+    pub end_bound: WindowFrameBound,
+}
+
+impl Default for WindowFrame {
+    /// The frame of a window without an explicit frame clause,
+    /// `RANGE BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW`: the rows from the start of the
+    /// partition to the last peer of the current row. Without an ORDER BY, all the rows of a
+    /// partition are peers, so this frame is the whole partition.
+    fn default() -> Self {
+        WindowFrame {
+            units: WindowFrameUnits::Range,
+            start_bound: WindowFrameBound::Preceding(None),
+            end_bound: WindowFrameBound::CurrentRow,
+        }
+    }
+}
+
+impl fmt::Display for WindowFrame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} BETWEEN {} AND {}",
+            self.units, self.start_bound, self.end_bound
+        )
+    }
+}
+
+impl TryFrom<&ast::WindowFrame> for WindowFrame {
+    type Error = DataFusionError;
+
+    fn try_from(frame: &ast::WindowFrame) -> Result<Self> {
+        let units = match frame.units {
+            ast::WindowFrameUnits::Rows => WindowFrameUnits::Rows,
+            ast::WindowFrameUnits::Range => WindowFrameUnits::Range,
+            ast::WindowFrameUnits::Groups => {
+                return Err(DataFusionError::NotImplemented(
+                    "GROUPS window frames are not supported".to_owned(),
+                ))
+            }
+        };
+        let start_bound = WindowFrameBound::from(&frame.start_bound);
+        // `ROWS 1 PRECEDING` is a shorthand for `ROWS BETWEEN 1 PRECEDING AND CURRENT ROW`
+        let end_bound = match &frame.end_bound {
+            Some(bound) => WindowFrameBound::from(bound),
+            None => WindowFrameBound::CurrentRow,
+        };
+
+        if let WindowFrameBound::Following(None) = start_bound {
+            return Err(DataFusionError::Plan(
+                "A window frame cannot start at UNBOUNDED FOLLOWING".to_owned(),
+            ));
+        }
+        if let WindowFrameBound::Preceding(None) = end_bound {
+            return Err(DataFusionError::Plan(
+                "A window frame cannot end at UNBOUNDED PRECEDING".to_owned(),
+            ));
+        }
+        if start_bound.cmp(&end_bound) == Ordering::Greater {
+            return Err(DataFusionError::Plan(format!(
+                "A window frame cannot start at {} and end at {}",
+                start_bound, end_bound
+            )));
+        }
+
+        Ok(WindowFrame {
+            units,
+            start_bound,
+            end_bound,
+        })
+    }
+}
+
+/// The units of the bounds of a window frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowFrameUnits {
+    /// The bounds are offsets in rows from the current row
+    Rows,
+    /// The bounds are offsets from the value of the ORDER BY expression of the current row.
+    /// The rows with the same value are peers, which are all either in or out of the frame.
+    Range,
+}
+
+impl fmt::Display for WindowFrameUnits {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WindowFrameUnits::Rows => write!(f, "ROWS"),
+            WindowFrameUnits::Range => write!(f, "RANGE"),
+        }
+    }
+}
+
+/// A bound of a window frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowFrameBound {
+    /// `<N> PRECEDING`, or `UNBOUNDED PRECEDING` when `None`
+    Preceding(Option<u64>),
+    /// `CURRENT ROW`
+    CurrentRow,
+    /// `<N> FOLLOWING`, or `UNBOUNDED FOLLOWING` when `None`
+    Following(Option<u64>),
+}
+
+impl WindowFrameBound {
+    /// The position of the bound relative to the current row, used to order bounds
+    fn position(&self) -> (u8, i128) {
+        match self {
+            WindowFrameBound::Preceding(None) => (0, 0),
+            WindowFrameBound::Preceding(Some(n)) => (1, -(*n as i128)),
+            WindowFrameBound::CurrentRow => (1, 0),
+            WindowFrameBound::Following(Some(n)) => (1, *n as i128),
+            WindowFrameBound::Following(None) => (2, 0),
+        }
+    }
+}
+
+impl Ord for WindowFrameBound {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.position().cmp(&other.position())
+    }
+}
+
+impl PartialOrd for WindowFrameBound {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<&ast::WindowFrameBound> for WindowFrameBound {
+    fn from(bound: &ast::WindowFrameBound) -> Self {
+        match bound {
+            ast::WindowFrameBound::Preceding(n) => WindowFrameBound::Preceding(*n),
+            ast::WindowFrameBound::CurrentRow => WindowFrameBound::CurrentRow,
+            ast::WindowFrameBound::Following(n) => WindowFrameBound::Following(*n),
+        }
+    }
+}
+
+impl fmt::Display for WindowFrameBound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WindowFrameBound::Preceding(None) => write!(f, "UNBOUNDED PRECEDING"),
+            WindowFrameBound::Preceding(Some(n)) => write!(f, "{} PRECEDING", n),
+            WindowFrameBound::CurrentRow => write!(f, "CURRENT ROW"),
+            WindowFrameBound::Following(Some(n)) => write!(f, "{} FOLLOWING", n),
+            WindowFrameBound::Following(None) => write!(f, "UNBOUNDED FOLLOWING"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_frame_shorthand() -> Result<()> {
+        let frame = WindowFrame::try_from(&ast::WindowFrame {
+            units: ast::WindowFrameUnits::Rows,
+            start_bound: ast::WindowFrameBound::Preceding(Some(2)),
+            end_bound: None,
+        })?;
+        assert_eq!(
+            format!("{}", frame),
+            "ROWS BETWEEN 2 PRECEDING AND CURRENT ROW"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn window_frame_invalid_bounds() {
+        let invalid = vec![
+            (
+                ast::WindowFrameBound::Following(None),
+                ast::WindowFrameBound::Following(None),
+            ),
+            (
+                ast::WindowFrameBound::Preceding(None),
+                ast::WindowFrameBound::Preceding(None),
+            ),
+            (
+                ast::WindowFrameBound::CurrentRow,
+                ast::WindowFrameBound::Preceding(Some(1)),
+            ),
+            (
+                ast::WindowFrameBound::Following(Some(2)),
+                ast::WindowFrameBound::Following(Some(1)),
+            ),
+        ];
+        for (start_bound, end_bound) in invalid {
+            let frame = WindowFrame::try_from(&ast::WindowFrame {
+                units: ast::WindowFrameUnits::Rows,
+                start_bound,
+                end_bound: Some(end_bound),
+            });
+            assert!(frame.is_err());
+        }
+    }
+
+    #[test]
+    fn window_frame_groups() {
+        let frame = WindowFrame::try_from(&ast::WindowFrame {
+            units: ast::WindowFrameUnits::Groups,
+            start_bound: ast::WindowFrameBound::Preceding(None),
+            end_bound: None,
+        });
+        assert!(frame.is_err());
+    }
+}
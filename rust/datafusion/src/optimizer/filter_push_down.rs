@@ -18,7 +18,7 @@ use arrow::datatypes::Schema;
 
 use crate::error::Result;
 use crate::logical_plan::Expr;
-use crate::logical_plan::{and, LogicalPlan};
+use crate::logical_plan::{and, JoinType, LogicalPlan};
 use crate::optimizer::optimizer::OptimizerRule;
 use crate::optimizer::utils;
 use std::{
@@ -85,25 +85,33 @@ fn get_predicates<'a>(
 // * pushable to right: its columns is all on the right
 // * keep: the set of columns is not in only either left or right
 // Note that a predicate can be both pushed to the left and to the right.
+// Predicates are never pushed to the side of an outer join that may be padded with nulls.
 fn get_join_predicates<'a>(
     state: &'a State,
     left: &Schema,
     right: &Schema,
+    join_type: &JoinType,
 ) -> (
     Vec<&'a HashSet<String>>,
     Vec<&'a HashSet<String>>,
     Predicates<'a>,
 ) {
-    let left_columns = &left
-        .fields()
-        .iter()
-        .map(|f| f.name().clone())
-        .collect::<HashSet<_>>();
-    let right_columns = &right
-        .fields()
-        .iter()
-        .map(|f| f.name().clone())
-        .collect::<HashSet<_>>();
+    let columns = |schema: &Schema| {
+        schema
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect::<HashSet<_>>()
+    };
+    let (left_columns, right_columns) = match join_type {
+        JoinType::Inner => (columns(left), columns(right)),
+        JoinType::Left => (columns(left), HashSet::new()),
+        JoinType::Right => (HashSet::new(), columns(right)),
+        JoinType::Full => (HashSet::new(), HashSet::new()),
+        // semi and anti joins only return rows of the left
+        JoinType::Semi | JoinType::Anti => (columns(left), HashSet::new()),
+    };
+    let (left_columns, right_columns) = (&left_columns, &right_columns);
 
     let filters = state
         .filters
@@ -217,6 +225,39 @@ fn issue_filters(
     push_down(&state, &plan)
 }
 
+fn optimize_join(
+    mut state: State,
+    plan: &LogicalPlan,
+    left: &LogicalPlan,
+    right: &LogicalPlan,
+    join_type: &JoinType,
+) -> Result<LogicalPlan> {
+    let (pushable_to_left, pushable_to_right, keep) =
+        get_join_predicates(&state, &left.schema(), &right.schema(), join_type);
+
+    let mut left_state = state.clone();
+    left_state.filters = keep_filters(&left_state.filters, &pushable_to_left);
+    let left = optimize(left, left_state)?;
+
+    let mut right_state = state.clone();
+    right_state.filters = keep_filters(&right_state.filters, &pushable_to_right);
+    let right = optimize(right, right_state)?;
+
+    // create a new Join with the new `left` and `right`
+    let expr = utils::expressions(&plan);
+    let plan = utils::from_plan(&plan, &expr, &vec![left, right])?;
+
+    if keep.0.is_empty() {
+        Ok(plan)
+    } else {
+        // wrap the join on the filter whose predicates must be kept
+        let plan = add_filter(plan, &keep.0);
+        state.filters = remove_filters(&state.filters, &keep.1);
+
+        Ok(plan)
+    }
+}
+
 fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
     match plan {
         LogicalPlan::Filter { input, predicate } => {
@@ -281,6 +322,10 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
             // sort is filter-commutable
             push_down(&state, plan)
         }
+        LogicalPlan::Union { .. } => {
+            // union is filter-commutable, as all its inputs share the union's column names
+            push_down(&state, plan)
+        }
         LogicalPlan::Limit { input, .. } => {
             // limit is _not_ filter-commutable => collect all columns from its input
             let used_columns = input
@@ -291,31 +336,15 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
                 .collect::<HashSet<_>>();
             issue_filters(state, used_columns, plan)
         }
-        LogicalPlan::Join { left, right, .. } => {
-            let (pushable_to_left, pushable_to_right, keep) =
-                get_join_predicates(&state, &left.schema(), &right.schema());
-
-            let mut left_state = state.clone();
-            left_state.filters = keep_filters(&left_state.filters, &pushable_to_left);
-            let left = optimize(left, left_state)?;
-
-            let mut right_state = state.clone();
-            right_state.filters = keep_filters(&right_state.filters, &pushable_to_right);
-            let right = optimize(right, right_state)?;
-
-            // create a new Join with the new `left` and `right`
-            let expr = utils::expressions(&plan);
-            let plan = utils::from_plan(&plan, &expr, &vec![left, right])?;
-
-            if keep.0.is_empty() {
-                Ok(plan)
-            } else {
-                // wrap the join on the filter whose predicates must be kept
-                let plan = add_filter(plan, &keep.0);
-                state.filters = remove_filters(&state.filters, &keep.1);
-
-                Ok(plan)
-            }
+        LogicalPlan::Join {
+            left,
+            right,
+            join_type,
+            ..
+        } => optimize_join(state, plan, left, right, join_type),
+        LogicalPlan::CrossJoin { left, right, .. } => {
+            // a cross join behaves like an inner join regarding its predicates
+            optimize_join(state, plan, left, right, &JoinType::Inner)
         }
         _ => {
             // all other plans are _not_ filter-commutable
@@ -371,7 +400,7 @@ fn rewrite(expr: &Expr, projection: &HashMap<String, Expr>) -> Result<Expr> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::logical_plan::{lit, sum, Expr, LogicalPlanBuilder, Operator};
+    use crate::logical_plan::{in_list, lit, sum, Expr, LogicalPlanBuilder, Operator};
     use crate::test::*;
     use crate::{logical_plan::col, prelude::JoinType};
 
@@ -483,6 +512,23 @@ mod tests {
         Ok(())
     }
 
+    /// verifies that IN lists and BETWEEN are pushed down and re-written like other filters
+    #[test]
+    fn in_list_and_between() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = LogicalPlanBuilder::from(&table_scan)
+            .project(vec![col("a").alias("b"), col("c")])?
+            .filter(in_list(col("b"), vec![lit(1i64), lit(2i64)], false))?
+            .filter(col("c").not_between(lit(1i64), lit(5i64)))?
+            .build()?;
+        let expected = "\
+            Projection: #a AS b, #c\
+            \n  Filter: #c NOT BETWEEN Int64(1) AND Int64(5) And #a IN (Int64(1), Int64(2))\
+            \n    TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
     fn add(left: Expr, right: Expr) -> Expr {
         Expr::BinaryExpr {
             left: Box::new(left),
@@ -820,4 +866,81 @@ mod tests {
         assert_optimized_plan_eq(&plan, expected);
         Ok(())
     }
+
+    /// post-join predicates on the null-supplying side of an outer join are not pushed down
+    #[test]
+    fn filter_left_join_on_right_side() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let left = LogicalPlanBuilder::from(&table_scan)
+            .project(vec![col("a"), col("b")])?
+            .build()?;
+        let right = LogicalPlanBuilder::from(&table_scan)
+            .project(vec![col("a"), col("c")])?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(&left)
+            .join(&right, JoinType::Left, &["a"], &["a"])?
+            .filter(col("b").lt_eq(lit(1i64)))?
+            .filter(Expr::IsNull(Box::new(col("c"))))?
+            .build()?;
+
+        let expected = "\
+        Filter: #c IS NULL\
+        \n  Join: a = a\
+        \n    Projection: #a, #b\
+        \n      Filter: #b LtEq Int64(1)\
+        \n        TableScan: test projection=None\
+        \n    Projection: #a, #c\
+        \n      TableScan: test projection=None";
+
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn filter_union() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = LogicalPlanBuilder::from(&table_scan)
+            .union(&table_scan)?
+            .filter(col("a").eq(lit(1i64)))?
+            .build()?;
+
+        let expected = "\
+        Union\
+        \n  Filter: #a Eq Int64(1)\
+        \n    TableScan: test projection=None\
+        \n  Filter: #a Eq Int64(1)\
+        \n    TableScan: test projection=None";
+
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    /// predicates on either side of a cross join are pushed to that side
+    #[test]
+    fn filter_cross_join() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let left = LogicalPlanBuilder::from(&table_scan)
+            .project(vec![col("a"), col("b")])?
+            .build()?;
+        let right = LogicalPlanBuilder::from(&table_scan)
+            .project(vec![col("c")])?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(&left)
+            .cross_join(&right)?
+            .filter(col("b").lt_eq(lit(1i64)))?
+            .filter(col("c").eq(lit(2i64)))?
+            .build()?;
+
+        let expected = "\
+        CrossJoin\
+        \n  Projection: #a, #b\
+        \n    Filter: #b LtEq Int64(1)\
+        \n      TableScan: test projection=None\
+        \n  Projection: #c\
+        \n    Filter: #c Eq Int64(2)\
+        \n      TableScan: test projection=None";
+
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
 }
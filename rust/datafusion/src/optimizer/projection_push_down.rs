@@ -163,12 +163,16 @@ fn optimize_plan(
             right,
             on,
             join_type,
+            filter,
             schema,
         } => {
             for (l, r) in on {
                 new_required_columns.insert(l.to_owned());
                 new_required_columns.insert(r.to_owned());
             }
+            if let Some(filter) = filter {
+                utils::expr_to_column_names(filter, &mut new_required_columns)?;
+            }
             Ok(LogicalPlan::Join {
                 left: Arc::new(optimize_plan(
                     optimizer,
@@ -185,9 +189,29 @@ fn optimize_plan(
 
                 join_type: join_type.clone(),
                 on: on.clone(),
+                filter: filter.clone(),
                 schema: schema.clone(),
             })
         }
+        LogicalPlan::CrossJoin {
+            left,
+            right,
+            schema,
+        } => Ok(LogicalPlan::CrossJoin {
+            left: Arc::new(optimize_plan(
+                optimizer,
+                &left,
+                &new_required_columns,
+                true,
+            )?),
+            right: Arc::new(optimize_plan(
+                optimizer,
+                &right,
+                &new_required_columns,
+                true,
+            )?),
+            schema: schema.clone(),
+        }),
         LogicalPlan::Aggregate {
             schema,
             input,
@@ -241,6 +265,71 @@ fn optimize_plan(
                 schema: SchemaRef::new(new_schema),
             })
         }
+        LogicalPlan::Window {
+            schema,
+            input,
+            window_expr,
+        } => {
+            // window:
+            // * remove any window expression that is not required
+            // * construct the new set of required columns
+
+            // the window expressions are the last fields of the schema
+            let window_fields =
+                &schema.fields()[schema.fields().len() - window_expr.len()..];
+            let mut new_window_expr = Vec::new();
+            let mut new_window_fields = Vec::new();
+            window_expr
+                .iter()
+                .zip(window_fields)
+                .map(|(expr, field)| {
+                    if required_columns.contains(field.name()) {
+                        new_window_expr.push(expr.clone());
+                        new_window_fields.push(field.clone());
+
+                        // add to the new set of required columns
+                        utils::expr_to_column_names(expr, &mut new_required_columns)
+                    } else {
+                        Ok(())
+                    }
+                })
+                .collect::<Result<()>>()?;
+
+            let new_input =
+                optimize_plan(optimizer, &input, &new_required_columns, has_projection)?;
+
+            // no window expression is required: the window is not needed at all
+            if new_window_expr.is_empty() {
+                return Ok(new_input);
+            }
+
+            let mut new_fields = new_input.schema().fields().clone();
+            new_fields.extend(new_window_fields);
+
+            Ok(LogicalPlan::Window {
+                window_expr: new_window_expr,
+                input: Arc::new(new_input),
+                schema: SchemaRef::new(Schema::new(new_fields)),
+            })
+        }
+        LogicalPlan::Union { inputs, schema } => {
+            // all inputs of a union must keep the union's schema, so every column is
+            // required from every input
+            let new_required_columns = schema
+                .fields()
+                .iter()
+                .map(|f| f.name().clone())
+                .collect::<HashSet<String>>();
+            Ok(LogicalPlan::Union {
+                inputs: inputs
+                    .iter()
+                    .map(|input| {
+                        optimize_plan(optimizer, input, &new_required_columns, true)
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+                schema: schema.clone(),
+            })
+        }
         // scans:
         // * remove un-used columns from the scan projection
         LogicalPlan::TableScan {
@@ -331,10 +420,18 @@ fn optimize_plan(
         }
         LogicalPlan::Explain {
             verbose,
+            analyze,
             plan,
             stringified_plans,
             schema,
-        } => optimize_explain(optimizer, *verbose, &*plan, stringified_plans, &*schema),
+        } => optimize_explain(
+            optimizer,
+            *verbose,
+            *analyze,
+            &*plan,
+            stringified_plans,
+            &*schema,
+        ),
         // all other nodes: Add any additional columns used by
         // expressions in this node to the list of required columns
         LogicalPlan::Limit { .. }
@@ -367,6 +464,8 @@ mod tests {
     use super::*;
     use crate::logical_plan::{col, lit};
     use crate::logical_plan::{max, min, Expr, LogicalPlanBuilder};
+    use crate::physical_plan::aggregates::AggregateFunction;
+    use crate::physical_plan::window_functions::{BuiltInWindowFunction, WindowFunction};
     use crate::test::*;
     use arrow::datatypes::DataType;
 
@@ -581,6 +680,41 @@ mod tests {
         Ok(())
     }
 
+    /// tests that it removes a window expression that is never used downstream
+    #[test]
+    fn table_unused_window() -> Result<()> {
+        let table_scan = test_table_scan()?;
+
+        let row_number = Expr::WindowFunction {
+            fun: WindowFunction::BuiltInWindowFunction(BuiltInWindowFunction::RowNumber),
+            args: vec![],
+            partition_by: vec![col("c")],
+            order_by: vec![col("a").sort(true, true)],
+            window_frame: None,
+        };
+        // we never use "MAX(b)" => remove it
+        let max_b = Expr::WindowFunction {
+            fun: WindowFunction::AggregateFunction(AggregateFunction::Max),
+            args: vec![col("b")],
+            partition_by: vec![],
+            order_by: vec![],
+            window_frame: None,
+        };
+        let plan = LogicalPlanBuilder::from(&table_scan)
+            .window(vec![row_number.alias("rn"), max_b.alias("max_b")])?
+            .project(vec![col("a"), col("rn")])?
+            .build()?;
+
+        let expected = "\
+        Projection: #a, #rn\
+        \n  Window: windowExpr=[[ROW_NUMBER() OVER (PARTITION BY #c ORDER BY #a ASC NULLS FIRST) AS rn]]\
+        \n    TableScan: test projection=Some([0, 2])";
+
+        assert_optimized_plan_eq(&plan, expected);
+
+        Ok(())
+    }
+
     fn assert_optimized_plan_eq(plan: &LogicalPlan, expected: &str) {
         let optimized_plan = optimize(plan).expect("failed to optimize plan");
         let formatted_plan = format!("{:?}", optimized_plan);
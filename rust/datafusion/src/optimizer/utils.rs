@@ -62,6 +62,18 @@ pub fn expr_to_column_names(expr: &Expr, accum: &mut HashSet<String>) -> Result<
         Expr::Not(e) => expr_to_column_names(e, accum),
         Expr::IsNull(e) => expr_to_column_names(e, accum),
         Expr::IsNotNull(e) => expr_to_column_names(e, accum),
+        Expr::InList { expr, list, .. } => {
+            expr_to_column_names(expr, accum)?;
+            exprlist_to_column_names(list, accum)
+        }
+        Expr::Between {
+            expr, low, high, ..
+        } => {
+            expr_to_column_names(expr, accum)?;
+            expr_to_column_names(low, accum)?;
+            expr_to_column_names(high, accum)?;
+            Ok(())
+        }
         Expr::BinaryExpr { left, right, .. } => {
             expr_to_column_names(left, accum)?;
             expr_to_column_names(right, accum)?;
@@ -91,6 +103,16 @@ pub fn expr_to_column_names(expr: &Expr, accum: &mut HashSet<String>) -> Result<
         Expr::AggregateUDF { args, .. } => exprlist_to_column_names(args, accum),
         Expr::ScalarFunction { args, .. } => exprlist_to_column_names(args, accum),
         Expr::ScalarUDF { args, .. } => exprlist_to_column_names(args, accum),
+        Expr::WindowFunction {
+            args,
+            partition_by,
+            order_by,
+            ..
+        } => {
+            exprlist_to_column_names(args, accum)?;
+            exprlist_to_column_names(partition_by, accum)?;
+            exprlist_to_column_names(order_by, accum)
+        }
         Expr::Wildcard => Err(DataFusionError::Internal(
             "Wildcard expressions are not valid in a logical query plan".to_owned(),
         )),
@@ -102,6 +124,7 @@ pub fn expr_to_column_names(expr: &Expr, accum: &mut HashSet<String>) -> Result<
 pub fn optimize_explain(
     optimizer: &mut impl OptimizerRule,
     verbose: bool,
+    analyze: bool,
     plan: &LogicalPlan,
     stringified_plans: &Vec<StringifiedPlan>,
     schema: &Schema,
@@ -120,6 +143,7 @@ pub fn optimize_explain(
 
     Ok(LogicalPlan::Explain {
         verbose,
+        analyze,
         plan,
         stringified_plans,
         schema,
@@ -140,9 +164,15 @@ pub fn expressions(plan: &LogicalPlan) -> Vec<Expr> {
             result.extend(aggr_expr.clone());
             result
         }
-        LogicalPlan::Join { on, .. } => {
-            on.iter().flat_map(|(l, r)| vec![col(l), col(r)]).collect()
+        LogicalPlan::Join { on, filter, .. } => {
+            let mut result: Vec<Expr> =
+                on.iter().flat_map(|(l, r)| vec![col(l), col(r)]).collect();
+            if let Some(filter) = filter {
+                result.push(filter.clone());
+            }
+            result
         }
+        LogicalPlan::Window { window_expr, .. } => window_expr.clone(),
         LogicalPlan::Sort { expr, .. } => expr.clone(),
         LogicalPlan::Extension { node } => node.expressions(),
         // plans without expressions
@@ -152,6 +182,8 @@ pub fn expressions(plan: &LogicalPlan) -> Vec<Expr> {
         | LogicalPlan::CsvScan { .. }
         | LogicalPlan::EmptyRelation { .. }
         | LogicalPlan::Limit { .. }
+        | LogicalPlan::CrossJoin { .. }
+        | LogicalPlan::Union { .. }
         | LogicalPlan::CreateExternalTable { .. }
         | LogicalPlan::Explain { .. } => vec![],
     }
@@ -163,8 +195,11 @@ pub fn inputs(plan: &LogicalPlan) -> Vec<&LogicalPlan> {
         LogicalPlan::Projection { input, .. } => vec![input],
         LogicalPlan::Filter { input, .. } => vec![input],
         LogicalPlan::Aggregate { input, .. } => vec![input],
+        LogicalPlan::Window { input, .. } => vec![input],
         LogicalPlan::Sort { input, .. } => vec![input],
         LogicalPlan::Join { left, right, .. } => vec![left, right],
+        LogicalPlan::CrossJoin { left, right, .. } => vec![left, right],
+        LogicalPlan::Union { inputs, .. } => inputs.iter().collect(),
         LogicalPlan::Limit { input, .. } => vec![input],
         LogicalPlan::Extension { node } => node.inputs(),
         // plans without inputs
@@ -202,6 +237,11 @@ pub fn from_plan(
             input: Arc::new(inputs[0].clone()),
             schema: schema.clone(),
         }),
+        LogicalPlan::Window { schema, .. } => Ok(LogicalPlan::Window {
+            window_expr: expr.clone(),
+            input: Arc::new(inputs[0].clone()),
+            schema: schema.clone(),
+        }),
         LogicalPlan::Sort { .. } => Ok(LogicalPlan::Sort {
             expr: expr.clone(),
             input: Arc::new(inputs[0].clone()),
@@ -209,6 +249,7 @@ pub fn from_plan(
         LogicalPlan::Join {
             join_type,
             on,
+            filter,
             schema,
             ..
         } => Ok(LogicalPlan::Join {
@@ -216,6 +257,17 @@ pub fn from_plan(
             right: Arc::new(inputs[1].clone()),
             join_type: join_type.clone(),
             on: on.clone(),
+            // the filter follows the join columns in the expressions of the join
+            filter: filter.as_ref().map(|_| expr[on.len() * 2].clone()),
+            schema: schema.clone(),
+        }),
+        LogicalPlan::CrossJoin { schema, .. } => Ok(LogicalPlan::CrossJoin {
+            left: Arc::new(inputs[0].clone()),
+            right: Arc::new(inputs[1].clone()),
+            schema: schema.clone(),
+        }),
+        LogicalPlan::Union { schema, .. } => Ok(LogicalPlan::Union {
+            inputs: inputs.clone(),
             schema: schema.clone(),
         }),
         LogicalPlan::Limit { n, .. } => Ok(LogicalPlan::Limit {
@@ -244,12 +296,35 @@ pub fn expr_sub_expressions(expr: &Expr) -> Result<Vec<Expr>> {
         }
         Expr::IsNull(e) => Ok(vec![e.as_ref().to_owned()]),
         Expr::IsNotNull(e) => Ok(vec![e.as_ref().to_owned()]),
+        Expr::InList { expr, list, .. } => {
+            let mut expr_list = vec![expr.as_ref().to_owned()];
+            expr_list.extend(list.iter().cloned());
+            Ok(expr_list)
+        }
+        Expr::Between {
+            expr, low, high, ..
+        } => Ok(vec![
+            expr.as_ref().to_owned(),
+            low.as_ref().to_owned(),
+            high.as_ref().to_owned(),
+        ]),
         Expr::ScalarFunction { args, .. } => Ok(args.iter().map(|e| e.clone()).collect()),
         Expr::ScalarUDF { args, .. } => Ok(args.iter().map(|e| e.clone()).collect()),
         Expr::AggregateFunction { args, .. } => {
             Ok(args.iter().map(|e| e.clone()).collect())
         }
         Expr::AggregateUDF { args, .. } => Ok(args.iter().map(|e| e.clone()).collect()),
+        Expr::WindowFunction {
+            args,
+            partition_by,
+            order_by,
+            ..
+        } => {
+            let mut expr_list = args.clone();
+            expr_list.extend(partition_by.iter().cloned());
+            expr_list.extend(order_by.iter().cloned());
+            Ok(expr_list)
+        }
         Expr::Case {
             expr,
             when_then_expr,
@@ -295,6 +370,17 @@ pub fn rewrite_expression(expr: &Expr, expressions: &Vec<Expr>) -> Result<Expr>
         }),
         Expr::IsNull(_) => Ok(Expr::IsNull(Box::new(expressions[0].clone()))),
         Expr::IsNotNull(_) => Ok(Expr::IsNotNull(Box::new(expressions[0].clone()))),
+        Expr::InList { negated, .. } => Ok(Expr::InList {
+            expr: Box::new(expressions[0].clone()),
+            list: expressions[1..].to_vec(),
+            negated: *negated,
+        }),
+        Expr::Between { negated, .. } => Ok(Expr::Between {
+            expr: Box::new(expressions[0].clone()),
+            negated: *negated,
+            low: Box::new(expressions[1].clone()),
+            high: Box::new(expressions[2].clone()),
+        }),
         Expr::ScalarFunction { fun, .. } => Ok(Expr::ScalarFunction {
             fun: fun.clone(),
             args: expressions.clone(),
@@ -312,6 +398,24 @@ pub fn rewrite_expression(expr: &Expr, expressions: &Vec<Expr>) -> Result<Expr>
             fun: fun.clone(),
             args: expressions.clone(),
         }),
+        Expr::WindowFunction {
+            fun,
+            args,
+            partition_by,
+            window_frame,
+            ..
+        } => {
+            // the expressions are the arguments, followed by the partition and sort expressions
+            let partition_start = args.len();
+            let order_start = partition_start + partition_by.len();
+            Ok(Expr::WindowFunction {
+                fun: fun.clone(),
+                args: expressions[..partition_start].to_vec(),
+                partition_by: expressions[partition_start..order_start].to_vec(),
+                order_by: expressions[order_start..].to_vec(),
+                window_frame: *window_frame,
+            })
+        }
         Expr::Case { .. } => {
             let mut base_expr: Option<Box<Expr>> = None;
             let mut when_then: Vec<(Box<Expr>, Box<Expr>)> = vec![];
@@ -423,6 +527,7 @@ mod tests {
         let optimized_explain = optimize_explain(
             &mut optimizer,
             true,
+            false,
             &empty_plan,
             &vec![StringifiedPlan::new(PlanType::LogicalPlan, "...")],
             &*schema,
@@ -431,10 +536,12 @@ mod tests {
         match &optimized_explain {
             LogicalPlan::Explain {
                 verbose,
+                analyze,
                 stringified_plans,
                 ..
             } => {
                 assert_eq!(*verbose, true);
+                assert_eq!(*analyze, false);
 
                 let expected_stringified_plans = vec![
                     StringifiedPlan::new(PlanType::LogicalPlan, "..."),
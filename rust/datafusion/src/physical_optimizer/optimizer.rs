@@ -0,0 +1,54 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Physical query optimizer traits
+
+use std::sync::Arc;
+
+use crate::error::Result;
+use crate::physical_plan::ExecutionPlan;
+
+/// A physical optimizer rule transforms an execution plan into another execution plan that
+/// returns the same results, but executes more efficiently.
+pub trait PhysicalOptimizerRule {
+    /// Perform optimizations on the plan
+    fn optimize(
+        &mut self,
+        plan: Arc<dyn ExecutionPlan>,
+    ) -> Result<Arc<dyn ExecutionPlan>>;
+    /// Produce a human readable name for this optimizer rule
+    fn name(&self) -> &str;
+
+    /// Convenience rule for writing optimizers: recursively invoke optimize on the
+    /// children of the plan and then return a plan of the same type with the optimized
+    /// children.
+    fn optimize_children(
+        &mut self,
+        plan: Arc<dyn ExecutionPlan>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let children = plan.children();
+        if children.is_empty() {
+            // leaf node, children cannot be replaced
+            return Ok(plan);
+        }
+        let children = children
+            .into_iter()
+            .map(|child| self.optimize(child))
+            .collect::<Result<Vec<_>>>()?;
+        plan.with_new_children(children)
+    }
+}
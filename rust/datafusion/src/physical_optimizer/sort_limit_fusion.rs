@@ -0,0 +1,155 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Sort Limit Fusion optimizer rule replaces a sort followed by a limit with a top-k, which
+//! only keeps the rows within the limit in memory
+
+use std::sync::Arc;
+
+use crate::error::Result;
+use crate::physical_optimizer::optimizer::PhysicalOptimizerRule;
+use crate::physical_plan::limit::GlobalLimitExec;
+use crate::physical_plan::merge::MergeExec;
+use crate::physical_plan::sort::SortExec;
+use crate::physical_plan::sort_preserving_merge::SortPreservingMergeExec;
+use crate::physical_plan::top_k::TopKExec;
+use crate::physical_plan::ExecutionPlan;
+
+/// Optimizer that replaces a [GlobalLimitExec] whose input is a [SortExec], or a
+/// [SortPreservingMergeExec] of a [SortExec], with a [TopKExec], such as for
+/// `ORDER BY x LIMIT k`
+pub struct SortLimitFusion {}
+
+impl PhysicalOptimizerRule for SortLimitFusion {
+    fn optimize(
+        &mut self,
+        plan: Arc<dyn ExecutionPlan>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let plan = self.optimize_children(plan)?;
+
+        let limit = match plan.as_any().downcast_ref::<GlobalLimitExec>() {
+            Some(limit) => limit,
+            None => return Ok(plan),
+        };
+        // the partitions that a sort preserves are merged after it
+        let sorted = match limit
+            .input()
+            .as_any()
+            .downcast_ref::<SortPreservingMergeExec>()
+        {
+            Some(merge) => merge.input(),
+            None => limit.input(),
+        };
+        let sort = match sorted.as_any().downcast_ref::<SortExec>() {
+            Some(sort) => sort,
+            None => return Ok(plan),
+        };
+
+        // the top-k reads the partitions that the sort merges on its own
+        let input = match sort.input().as_any().downcast_ref::<MergeExec>() {
+            Some(merge) => merge.input().clone(),
+            None => sort.input().clone(),
+        };
+        Ok(Arc::new(TopKExec::new(
+            sort.expr().to_vec(),
+            input,
+            limit.limit(),
+        )))
+    }
+
+    fn name(&self) -> &str {
+        "sort_limit_fusion"
+    }
+}
+
+impl SortLimitFusion {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::{col, PhysicalSortExpr};
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::sort::SortOptions;
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    fn sort(input: Arc<dyn ExecutionPlan>) -> Result<Arc<dyn ExecutionPlan>> {
+        let expr = vec![PhysicalSortExpr {
+            expr: col("a"),
+            options: SortOptions::default(),
+        }];
+        Ok(Arc::new(SortExec::try_new(expr, input, 1)?))
+    }
+
+    #[test]
+    fn fuse_sort_limit() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let input = Arc::new(MemoryExec::try_new(&vec![vec![], vec![]], schema, None)?);
+
+        // the sort merges the partitions of its input
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(GlobalLimitExec::new(
+            sort(Arc::new(MergeExec::new(input.clone())))?,
+            10,
+            1,
+        ));
+        let optimized = SortLimitFusion::new().optimize(plan)?;
+        let top_k = optimized
+            .as_any()
+            .downcast_ref::<TopKExec>()
+            .expect("a top-k");
+        assert_eq!(top_k.k(), 10);
+        assert_eq!(top_k.expr().len(), 1);
+        assert_eq!(
+            top_k.children()[0].output_partitioning().partition_count(),
+            2
+        );
+
+        // the partitions are sorted in parallel and then merged
+        let expr = vec![PhysicalSortExpr {
+            expr: col("a"),
+            options: SortOptions::default(),
+        }];
+        let sorted = SortExec::try_new(expr.clone(), input.clone(), 1)?
+            .with_preserve_partitioning(true);
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(GlobalLimitExec::new(
+            Arc::new(SortPreservingMergeExec::new(expr, Arc::new(sorted), 1024)),
+            5,
+            1,
+        ));
+        let optimized = SortLimitFusion::new().optimize(plan)?;
+        let top_k = optimized
+            .as_any()
+            .downcast_ref::<TopKExec>()
+            .expect("a top-k");
+        assert_eq!(top_k.k(), 5);
+        assert_eq!(
+            top_k.children()[0].output_partitioning().partition_count(),
+            2
+        );
+
+        // a sort without a limit is kept
+        let plan = sort(input)?;
+        let optimized = SortLimitFusion::new().optimize(plan)?;
+        assert!(optimized.as_any().downcast_ref::<SortExec>().is_some());
+
+        Ok(())
+    }
+}
@@ -32,9 +32,14 @@ use super::{
     Accumulator, AggregateExpr, PhysicalExpr,
 };
 use crate::error::{DataFusionError, Result};
+use crate::physical_plan::approx_expressions;
 use crate::physical_plan::distinct_expressions;
 use crate::physical_plan::expressions;
-use arrow::datatypes::{DataType, Schema};
+use crate::physical_plan::statistical_expressions::{
+    self, Covariance, StatsType, Variance,
+};
+use crate::scalar::ScalarValue;
+use arrow::datatypes::{DataType, Field, Schema};
 use expressions::{avg_return_type, sum_return_type};
 use std::{fmt, str::FromStr, sync::Arc};
 
@@ -60,12 +65,57 @@ pub enum AggregateFunction {
     Max,
     /// avg
     Avg,
+    /// variance of a sample, also VAR_SAMP
+    Variance,
+    /// variance of a population
+    VariancePop,
+    /// standard deviation of a sample, also STDDEV_SAMP
+    Stddev,
+    /// standard deviation of a population
+    StddevPop,
+    /// covariance of a sample, also COVAR_SAMP
+    Covariance,
+    /// covariance of a population
+    CovariancePop,
+    /// Pearson correlation coefficient
+    Correlation,
+    /// median
+    Median,
+    /// approximate percentile, with a t-digest
+    ApproxPercentile,
+    /// approximate number of distinct values, with a HyperLogLog sketch
+    ApproxDistinct,
+    /// list of all the values
+    ArrayAgg,
+    /// whether all the values are true
+    BoolAnd,
+    /// whether any of the values is true
+    BoolOr,
+    /// concatenation of the values, with a delimiter
+    StringAgg,
+    /// the value of the only row, which fails on more rows. It is only planned for
+    /// scalar subqueries.
+    SingleValue,
 }
 
 impl fmt::Display for AggregateFunction {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // uppercase of the debug.
-        write!(f, "{}", format!("{:?}", self).to_uppercase())
+        match self {
+            AggregateFunction::VariancePop => write!(f, "VAR_POP"),
+            AggregateFunction::StddevPop => write!(f, "STDDEV_POP"),
+            AggregateFunction::Covariance => write!(f, "COVAR"),
+            AggregateFunction::CovariancePop => write!(f, "COVAR_POP"),
+            AggregateFunction::Correlation => write!(f, "CORR"),
+            AggregateFunction::ApproxPercentile => write!(f, "APPROX_PERCENTILE"),
+            AggregateFunction::ApproxDistinct => write!(f, "APPROX_DISTINCT"),
+            AggregateFunction::ArrayAgg => write!(f, "ARRAY_AGG"),
+            AggregateFunction::BoolAnd => write!(f, "BOOL_AND"),
+            AggregateFunction::BoolOr => write!(f, "BOOL_OR"),
+            AggregateFunction::StringAgg => write!(f, "STRING_AGG"),
+            AggregateFunction::SingleValue => write!(f, "SINGLE_VALUE"),
+            // uppercase of the debug.
+            _ => write!(f, "{}", format!("{:?}", self).to_uppercase()),
+        }
     }
 }
 
@@ -78,6 +128,20 @@ impl FromStr for AggregateFunction {
             "COUNT" => AggregateFunction::Count,
             "AVG" => AggregateFunction::Avg,
             "SUM" => AggregateFunction::Sum,
+            "VARIANCE" | "VAR_SAMP" => AggregateFunction::Variance,
+            "VAR_POP" => AggregateFunction::VariancePop,
+            "STDDEV" | "STDDEV_SAMP" => AggregateFunction::Stddev,
+            "STDDEV_POP" => AggregateFunction::StddevPop,
+            "COVAR" | "COVAR_SAMP" => AggregateFunction::Covariance,
+            "COVAR_POP" => AggregateFunction::CovariancePop,
+            "CORR" => AggregateFunction::Correlation,
+            "MEDIAN" => AggregateFunction::Median,
+            "APPROX_PERCENTILE" => AggregateFunction::ApproxPercentile,
+            "APPROX_DISTINCT" => AggregateFunction::ApproxDistinct,
+            "ARRAY_AGG" => AggregateFunction::ArrayAgg,
+            "BOOL_AND" => AggregateFunction::BoolAnd,
+            "BOOL_OR" => AggregateFunction::BoolOr,
+            "STRING_AGG" => AggregateFunction::StringAgg,
             _ => {
                 return Err(DataFusionError::Plan(format!(
                     "There is no built-in function named {}",
@@ -97,13 +161,32 @@ pub fn return_type(
     // or the execution panics.
 
     // verify that this is a valid set of data types for this function
-    data_types(arg_types, &signature(fun))?;
+    data_types(arg_types, &signature(fun, arg_types))?;
 
     match fun {
         AggregateFunction::Count => Ok(DataType::UInt64),
-        AggregateFunction::Max | AggregateFunction::Min => Ok(arg_types[0].clone()),
+        AggregateFunction::Max
+        | AggregateFunction::Min
+        | AggregateFunction::SingleValue => Ok(arg_types[0].clone()),
         AggregateFunction::Sum => sum_return_type(&arg_types[0]),
         AggregateFunction::Avg => avg_return_type(&arg_types[0]),
+        AggregateFunction::Variance
+        | AggregateFunction::VariancePop
+        | AggregateFunction::Stddev
+        | AggregateFunction::StddevPop
+        | AggregateFunction::Covariance
+        | AggregateFunction::CovariancePop
+        | AggregateFunction::Correlation
+        | AggregateFunction::Median
+        | AggregateFunction::ApproxPercentile => Ok(DataType::Float64),
+        AggregateFunction::ApproxDistinct => Ok(DataType::UInt64),
+        AggregateFunction::ArrayAgg => Ok(DataType::List(Box::new(Field::new(
+            "item",
+            arg_types[0].clone(),
+            true,
+        )))),
+        AggregateFunction::BoolAnd | AggregateFunction::BoolOr => Ok(DataType::Boolean),
+        AggregateFunction::StringAgg => Ok(DataType::Utf8),
     }
 }
 
@@ -116,14 +199,15 @@ pub fn create_aggregate_expr(
     input_schema: &Schema,
     name: String,
 ) -> Result<Arc<dyn AggregateExpr>> {
-    // coerce
-    let arg = coerce(args, input_schema, &signature(fun))?[0].clone();
-
     let arg_types = args
         .iter()
         .map(|e| e.data_type(input_schema))
         .collect::<Result<Vec<_>>>()?;
 
+    // coerce
+    let coerced_args = coerce(args, input_schema, &signature(fun, &arg_types))?;
+    let arg = coerced_args[0].clone();
+
     let return_type = return_type(&fun, &arg_types)?;
 
     Ok(match (fun, distinct) {
@@ -160,9 +244,100 @@ pub fn create_aggregate_expr(
                 "AVG(DISTINCT) aggregations are not available".to_string(),
             ));
         }
+        (AggregateFunction::BoolAnd, _) => Arc::new(expressions::BoolAnd::new(arg, name)),
+        (AggregateFunction::BoolOr, _) => Arc::new(expressions::BoolOr::new(arg, name)),
+        (AggregateFunction::ApproxDistinct, _) => {
+            // the result is the same with or without DISTINCT
+            Arc::new(approx_expressions::ApproxDistinct::new(arg, name))
+        }
+        (_, true) => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "{}(DISTINCT) aggregations are not available",
+                fun
+            )));
+        }
+        (AggregateFunction::Variance, false) => {
+            Arc::new(Variance::new(arg, name, StatsType::Sample))
+        }
+        (AggregateFunction::VariancePop, false) => {
+            Arc::new(Variance::new(arg, name, StatsType::Population))
+        }
+        (AggregateFunction::Stddev, false) => {
+            Arc::new(Variance::new_stddev(arg, name, StatsType::Sample))
+        }
+        (AggregateFunction::StddevPop, false) => {
+            Arc::new(Variance::new_stddev(arg, name, StatsType::Population))
+        }
+        (AggregateFunction::Covariance, false) => Arc::new(Covariance::new(
+            arg,
+            coerced_args[1].clone(),
+            name,
+            StatsType::Sample,
+        )),
+        (AggregateFunction::CovariancePop, false) => Arc::new(Covariance::new(
+            arg,
+            coerced_args[1].clone(),
+            name,
+            StatsType::Population,
+        )),
+        (AggregateFunction::Correlation, false) => Arc::new(
+            statistical_expressions::Correlation::new(arg, coerced_args[1].clone(), name),
+        ),
+        (AggregateFunction::Median, false) => {
+            Arc::new(statistical_expressions::Median::new(arg, name))
+        }
+        (AggregateFunction::ApproxPercentile, false) => {
+            // the literal before its coercion to a float
+            let percentile = match literal_arg(fun, &args[1])? {
+                ScalarValue::Float64(Some(percentile)) => percentile,
+                ScalarValue::Int64(Some(percentile)) => percentile as f64,
+                other => {
+                    return Err(DataFusionError::Plan(format!(
+                        "The percentile of {} must be a number, not {:?}",
+                        fun, other
+                    )))
+                }
+            };
+            Arc::new(approx_expressions::ApproxPercentile::try_new(
+                arg, percentile, name,
+            )?)
+        }
+        (AggregateFunction::ArrayAgg, false) => {
+            Arc::new(expressions::ArrayAgg::new(arg, name, arg_types[0].clone()))
+        }
+        (AggregateFunction::StringAgg, false) => {
+            let delimiter = match literal_arg(fun, &args[1])? {
+                ScalarValue::Utf8(Some(delimiter)) => delimiter,
+                other => {
+                    return Err(DataFusionError::Plan(format!(
+                        "The delimiter of {} must be a string, not {:?}",
+                        fun, other
+                    )))
+                }
+            };
+            Arc::new(expressions::StringAgg::new(arg, delimiter, name))
+        }
+        (AggregateFunction::SingleValue, false) => Arc::new(
+            expressions::SingleValue::new(arg, name, arg_types[0].clone()),
+        ),
     })
 }
 
+/// Returns the value of an argument of `fun` that must be the same for all the rows, such
+/// as the percentile of APPROX_PERCENTILE
+fn literal_arg(
+    fun: &AggregateFunction,
+    arg: &Arc<dyn PhysicalExpr>,
+) -> Result<ScalarValue> {
+    match arg.as_any().downcast_ref::<expressions::Literal>() {
+        Some(literal) => Ok(literal.value().clone()),
+        None => Err(DataFusionError::Plan(format!(
+            "The second argument of {} must be a constant",
+            fun
+        ))),
+    }
+}
+
 static NUMERICS: &'static [DataType] = &[
     DataType::Int8,
     DataType::Int16,
@@ -176,19 +351,70 @@ static NUMERICS: &'static [DataType] = &[
     DataType::Float64,
 ];
 
-/// the signatures supported by the function `fun`.
-fn signature(fun: &AggregateFunction) -> Signature {
+/// The numeric types, and the decimal types of `arg_types`, since decimal types are
+/// parameterized by their precision and scale
+fn numerics_and_decimals(arg_types: &[DataType]) -> Vec<DataType> {
+    let mut valid = NUMERICS.to_vec();
+    for arg_type in arg_types {
+        if let DataType::Decimal(_, _) = arg_type {
+            valid.push(arg_type.clone());
+        }
+    }
+    valid
+}
+
+/// the signatures supported by the function `fun`, for arguments of `arg_types`.
+fn signature(fun: &AggregateFunction, arg_types: &[DataType]) -> Signature {
     // note: the physical expression must accept the type returned by this function or the execution panics.
     match fun {
-        AggregateFunction::Count => Signature::Any(1),
+        AggregateFunction::Count | AggregateFunction::SingleValue => Signature::Any(1),
         AggregateFunction::Min | AggregateFunction::Max => {
             let mut valid = vec![DataType::Utf8, DataType::LargeUtf8];
-            valid.extend_from_slice(NUMERICS);
+            valid.extend(numerics_and_decimals(arg_types));
             Signature::Uniform(1, valid)
         }
         AggregateFunction::Avg | AggregateFunction::Sum => {
-            Signature::Uniform(1, NUMERICS.to_vec())
+            Signature::Uniform(1, numerics_and_decimals(arg_types))
+        }
+        // the statistics are computed from floats
+        AggregateFunction::Variance
+        | AggregateFunction::VariancePop
+        | AggregateFunction::Stddev
+        | AggregateFunction::StddevPop
+        | AggregateFunction::Median => Signature::Uniform(1, vec![DataType::Float64]),
+        AggregateFunction::Covariance
+        | AggregateFunction::CovariancePop
+        | AggregateFunction::Correlation
+        | AggregateFunction::ApproxPercentile => {
+            Signature::Uniform(2, vec![DataType::Float64])
+        }
+        AggregateFunction::ApproxDistinct => {
+            // the types whose values can be hashed
+            Signature::Uniform(
+                1,
+                vec![
+                    DataType::Boolean,
+                    DataType::Utf8,
+                    DataType::Int8,
+                    DataType::Int16,
+                    DataType::Int32,
+                    DataType::Int64,
+                    DataType::UInt8,
+                    DataType::UInt16,
+                    DataType::UInt32,
+                    DataType::UInt64,
+                ],
+            )
         }
+        AggregateFunction::ArrayAgg => {
+            let mut valid = vec![DataType::Boolean, DataType::Utf8];
+            valid.extend_from_slice(NUMERICS);
+            Signature::Uniform(1, valid)
+        }
+        AggregateFunction::BoolAnd | AggregateFunction::BoolOr => {
+            Signature::Uniform(1, vec![DataType::Boolean])
+        }
+        AggregateFunction::StringAgg => Signature::Uniform(2, vec![DataType::Utf8]),
     }
 }
 
@@ -247,4 +473,72 @@ mod tests {
         assert!(observed.is_err());
         Ok(())
     }
+
+    #[test]
+    fn test_decimal_return_types() -> Result<()> {
+        let decimal = vec![DataType::Decimal(10, 2)];
+        let observed = return_type(&AggregateFunction::Sum, &decimal)?;
+        assert_eq!(DataType::Decimal(20, 2), observed);
+
+        let observed = return_type(&AggregateFunction::Avg, &decimal)?;
+        assert_eq!(DataType::Decimal(14, 6), observed);
+
+        let observed = return_type(&AggregateFunction::Max, &decimal)?;
+        assert_eq!(DataType::Decimal(10, 2), observed);
+
+        let observed =
+            return_type(&AggregateFunction::Sum, &vec![DataType::Decimal(38, 4)])?;
+        assert_eq!(DataType::Decimal(38, 4), observed);
+        Ok(())
+    }
+
+    #[test]
+    fn test_statistical_return_types() -> Result<()> {
+        let observed = return_type(&AggregateFunction::Variance, &vec![DataType::Int32])?;
+        assert_eq!(DataType::Float64, observed);
+
+        let observed = return_type(
+            &AggregateFunction::Correlation,
+            &vec![DataType::Float32, DataType::UInt8],
+        )?;
+        assert_eq!(DataType::Float64, observed);
+
+        let observed =
+            return_type(&AggregateFunction::ApproxDistinct, &vec![DataType::Utf8])?;
+        assert_eq!(DataType::UInt64, observed);
+
+        let observed = return_type(&AggregateFunction::ArrayAgg, &vec![DataType::Int8])?;
+        assert_eq!(
+            DataType::List(Box::new(Field::new("item", DataType::Int8, true))),
+            observed
+        );
+
+        assert!(return_type(&AggregateFunction::Stddev, &vec![DataType::Utf8]).is_err());
+        assert!(
+            return_type(&AggregateFunction::Covariance, &vec![DataType::Float64])
+                .is_err()
+        );
+        assert!(
+            return_type(&AggregateFunction::BoolAnd, &vec![DataType::Int32]).is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_aggregate_names() -> Result<()> {
+        for name in &[
+            "VAR_POP",
+            "STDDEV",
+            "COVAR",
+            "APPROX_PERCENTILE",
+            "STRING_AGG",
+        ] {
+            assert_eq!(AggregateFunction::from_str(name)?.to_string(), *name);
+        }
+        assert_eq!(
+            AggregateFunction::from_str("var_samp")?,
+            AggregateFunction::Variance
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,256 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the EXPLAIN ANALYZE operator
+
+use std::any::Any;
+use std::fmt::Write;
+use std::sync::Arc;
+
+use crate::error::{DataFusionError, Result};
+use crate::{
+    logical_plan::{PlanType, StringifiedPlan},
+    physical_plan::{common::SizedRecordBatchStream, ExecutionPlan},
+};
+use arrow::{array::StringBuilder, datatypes::SchemaRef, record_batch::RecordBatch};
+use futures::StreamExt;
+
+use super::common::spawn_abortable;
+use super::metrics::{with_metrics, MetricsExec};
+use super::{Partitioning, SendableRecordBatchStream};
+use async_trait::async_trait;
+
+/// Explain analyze execution plan operator. This operator executes its input, discarding
+/// its output, and passes the strings of the plans it has when it is created to its
+/// output, followed by the physical plan annotated with the metrics of its execution.
+#[derive(Debug, Clone)]
+pub struct AnalyzeExec {
+    /// The plan to execute and analyze
+    input: Arc<dyn ExecutionPlan>,
+
+    /// The schema that this exec plan node outputs
+    schema: SchemaRef,
+
+    /// The strings to be printed before the analyzed plan
+    stringified_plans: Vec<StringifiedPlan>,
+}
+
+impl AnalyzeExec {
+    /// Create a new AnalyzeExec
+    pub fn new(
+        input: Arc<dyn ExecutionPlan>,
+        schema: SchemaRef,
+        stringified_plans: Vec<StringifiedPlan>,
+    ) -> Self {
+        AnalyzeExec {
+            input,
+            schema,
+            stringified_plans,
+        }
+    }
+
+    /// Input execution plan
+    pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.input
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for AnalyzeExec {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    /// Get the output partitioning of this plan
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(AnalyzeExec::new(
+                children[0].clone(),
+                self.schema.clone(),
+                self.stringified_plans.clone(),
+            ))),
+            _ => Err(DataFusionError::Internal(
+                "AnalyzeExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        if 0 != partition {
+            return Err(DataFusionError::Internal(format!(
+                "AnalyzeExec invalid partition {}",
+                partition
+            )));
+        }
+
+        // execute all the partitions of the input, recording their metrics
+        let plan = with_metrics(self.input.clone())?;
+        let (tasks, _guards): (Vec<_>, Vec<_>) =
+            (0..plan.output_partitioning().partition_count())
+                .map(|partition| {
+                    let plan = plan.clone();
+                    spawn_abortable(async move {
+                        let mut stream = plan.execute(partition).await?;
+                        while let Some(batch) = stream.next().await {
+                            batch?;
+                        }
+                        Ok(())
+                    })
+                })
+                .unzip();
+        for task in tasks {
+            let result: Result<()> = task
+                .await
+                .map_err(|e| DataFusionError::Execution(e.to_string()))?
+                .unwrap_or_else(|| {
+                    Err(DataFusionError::Execution(
+                        "the execution of the plan was aborted".to_string(),
+                    ))
+                });
+            result?;
+        }
+
+        let mut analyzed = String::new();
+        write_metrics(&plan, 0, &mut analyzed)?;
+        let mut stringified_plans = self.stringified_plans.clone();
+        stringified_plans.push(StringifiedPlan::new(
+            PlanType::PhysicalPlanWithMetrics,
+            analyzed,
+        ));
+
+        let mut type_builder = StringBuilder::new(stringified_plans.len());
+        let mut plan_builder = StringBuilder::new(stringified_plans.len());
+
+        for p in &stringified_plans {
+            type_builder.append_value(&String::from(&p.plan_type))?;
+            plan_builder.append_value(&p.plan)?;
+        }
+
+        let record_batch = RecordBatch::try_new(
+            self.schema.clone(),
+            vec![
+                Arc::new(type_builder.finish()),
+                Arc::new(plan_builder.finish()),
+            ],
+        )?;
+
+        Ok(Box::pin(SizedRecordBatchStream::new(
+            self.schema.clone(),
+            vec![Arc::new(record_batch)],
+        )))
+    }
+}
+
+/// Writes the name of each plan below `plan`, indented by its depth, with its metrics
+fn write_metrics(
+    plan: &Arc<dyn ExecutionPlan>,
+    depth: usize,
+    output: &mut String,
+) -> Result<()> {
+    let plan = plan.as_any().downcast_ref::<MetricsExec>().ok_or_else(|| {
+        DataFusionError::Internal(
+            "AnalyzeExec expects metrics for every plan".to_string(),
+        )
+    })?;
+    let description = format!("{:?}", plan.input());
+    let name = description
+        .split(|c: char| c == ' ' || c == '{' || c == '(')
+        .next()
+        .unwrap_or_default();
+    writeln!(
+        output,
+        "{:indent$}{}: {}",
+        "",
+        name,
+        plan.metrics(),
+        indent = depth * 2
+    )
+    .map_err(|e| DataFusionError::Internal(e.to_string()))?;
+    for child in plan.input().children() {
+        write_metrics(&child, depth + 1, output)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::LogicalPlan;
+    use crate::physical_plan::common;
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::merge::MergeExec;
+    use arrow::array::{Int32Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    #[tokio::test]
+    async fn analyze() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(vec![1, 2, 3]))],
+        )?;
+        let input = Arc::new(MemoryExec::try_new(
+            &vec![vec![batch.clone()], vec![batch]],
+            schema,
+            None,
+        )?);
+        let analyze = AnalyzeExec::new(
+            Arc::new(MergeExec::new(input)),
+            LogicalPlan::explain_schema(),
+            vec![StringifiedPlan::new(PlanType::LogicalPlan, "...")],
+        );
+
+        let batches = common::collect(analyze.execute(0).await?).await?;
+        assert_eq!(batches.len(), 1);
+        let plan_types = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        let plans = batches[0]
+            .column(1)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(plan_types.len(), 2);
+        assert_eq!(plan_types.value(0), "logical_plan");
+        assert_eq!(plan_types.value(1), "physical_plan_with_metrics");
+
+        let lines = plans.value(1).lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("MergeExec: output_rows=6, output_batches=2"));
+        assert!(lines[1].starts_with("  MemoryExec: output_rows=6, output_batches=2"));
+
+        Ok(())
+    }
+}
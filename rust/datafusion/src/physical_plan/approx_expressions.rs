@@ -0,0 +1,562 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the approximate aggregate functions, whose state has a bounded size however
+//! many values they aggregate:
+//!
+//! * APPROX_DISTINCT estimates the number of distinct values with a HyperLogLog sketch.
+//! * APPROX_PERCENTILE estimates a percentile of the values with a t-digest.
+
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use arrow::array::{Array, ArrayRef, Float64Array};
+use arrow::datatypes::{DataType, Field};
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::expressions::format_state_name;
+use crate::physical_plan::hash_utils::create_hashes;
+use crate::physical_plan::{Accumulator, AggregateExpr, PhysicalExpr};
+use crate::scalar::ScalarValue;
+
+/// The number of bits of a hash that select the register of a HyperLogLog sketch, for a
+/// standard error of about 1.6%
+const HLL_PRECISION: u32 = 12;
+const HLL_REGISTERS: usize = 1 << HLL_PRECISION;
+
+/// A HyperLogLog sketch of the hashes of a set of values, from which the number of distinct
+/// values is estimated. Sketches of different sets are merged into the sketch of their
+/// union, as long as the values are hashed in the same way.
+#[derive(Debug, Clone)]
+struct HyperLogLog {
+    /// the largest number of leading zeros plus one of the hashes of each register
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    fn new() -> Self {
+        Self {
+            registers: vec![0; HLL_REGISTERS],
+        }
+    }
+
+    fn add(&mut self, hash: u64) {
+        let index = (hash >> (64 - HLL_PRECISION)) as usize;
+        // the bit after the remaining bits bounds the number of leading zeros
+        let remaining = (hash << HLL_PRECISION) | (1 << (HLL_PRECISION - 1));
+        let rank = remaining.leading_zeros() as u8 + 1;
+        self.registers[index] = self.registers[index].max(rank);
+    }
+
+    fn merge(&mut self, registers: &[u8]) {
+        self.registers
+            .iter_mut()
+            .zip(registers)
+            .for_each(|(register, other)| *register = (*register).max(*other));
+    }
+
+    fn count(&self) -> u64 {
+        let m = HLL_REGISTERS as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum = self
+            .registers
+            .iter()
+            .map(|register| 2f64.powi(-(*register as i32)))
+            .sum::<f64>();
+        let estimate = alpha * m * m / sum;
+        let zeros = self.registers.iter().filter(|r| **r == 0).count();
+        // small cardinalities are estimated more accurately by linear counting
+        let estimate = if estimate <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            estimate
+        };
+        estimate.round() as u64
+    }
+}
+
+/// APPROX_DISTINCT aggregate expression
+#[derive(Debug)]
+pub struct ApproxDistinct {
+    name: String,
+    expr: Arc<dyn PhysicalExpr>,
+}
+
+impl ApproxDistinct {
+    /// Create a new APPROX_DISTINCT aggregate function
+    pub fn new(expr: Arc<dyn PhysicalExpr>, name: String) -> Self {
+        Self { name, expr }
+    }
+}
+
+impl AggregateExpr for ApproxDistinct {
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, DataType::UInt64, false))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(vec![Field::new(
+            &format_state_name(&self.name, "hll_registers"),
+            DataType::List(Box::new(Field::new("item", DataType::UInt8, true))),
+            true,
+        )])
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(ApproxDistinctAccumulator {
+            hll: HyperLogLog::new(),
+        }))
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+}
+
+#[derive(Debug)]
+struct ApproxDistinctAccumulator {
+    hll: HyperLogLog,
+}
+
+impl Accumulator for ApproxDistinctAccumulator {
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        let registers = self
+            .hll
+            .registers
+            .iter()
+            .map(|register| ScalarValue::UInt8(Some(*register)))
+            .collect();
+        Ok(vec![ScalarValue::List(Some(registers), DataType::UInt8)])
+    }
+
+    fn update(&mut self, values: &Vec<ScalarValue>) -> Result<()> {
+        self.update_batch(&vec![values[0].to_array()])
+    }
+
+    fn update_batch(&mut self, values: &Vec<ArrayRef>) -> Result<()> {
+        // the hashes do not depend on the process, so that sketches of different
+        // partitions can be merged
+        let hashes = create_hashes(&values[..1])?;
+        for (i, hash) in hashes.into_iter().enumerate() {
+            if values[0].is_valid(i) {
+                self.hll.add(hash);
+            }
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &Vec<ScalarValue>) -> Result<()> {
+        match &states[0] {
+            ScalarValue::List(Some(registers), _) => {
+                let registers = registers
+                    .iter()
+                    .map(|register| match register {
+                        ScalarValue::UInt8(register) => Ok(register.unwrap_or_default()),
+                        other => Err(DataFusionError::Internal(format!(
+                            "Unexpected register {:?} of APPROX_DISTINCT",
+                            other
+                        ))),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                self.hll.merge(&registers);
+                Ok(())
+            }
+            ScalarValue::List(None, _) => Ok(()),
+            other => Err(DataFusionError::Internal(format!(
+                "Unexpected state {:?} of APPROX_DISTINCT",
+                other
+            ))),
+        }
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        Ok(ScalarValue::UInt64(Some(self.hll.count())))
+    }
+}
+
+/// The compression of a t-digest, which bounds the number of its centroids
+const TDIGEST_COMPRESSION: f64 = 100.0;
+
+/// The number of values that are buffered before they are merged into the centroids
+const TDIGEST_BUFFER_SIZE: usize = 500;
+
+/// A centroid of a t-digest: the mean of a number of adjacent values
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+/// A merging t-digest of a set of values, from which their quantiles are estimated. The
+/// values are summarized by centroids, which are smaller close to the extreme quantiles so
+/// that those are estimated more accurately.
+#[derive(Debug, Clone)]
+struct TDigest {
+    /// the centroids, ordered by mean
+    centroids: Vec<Centroid>,
+    /// the values that are not merged into the centroids yet
+    buffer: Vec<f64>,
+    min: f64,
+    max: f64,
+}
+
+impl TDigest {
+    fn new() -> Self {
+        Self {
+            centroids: vec![],
+            buffer: vec![],
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.buffer.push(value);
+        if self.buffer.len() >= TDIGEST_BUFFER_SIZE {
+            self.compress(vec![]);
+        }
+    }
+
+    /// Merges the buffered values and `centroids` into the centroids of this digest
+    fn compress(&mut self, centroids: Vec<Centroid>) {
+        let mut all = std::mem::take(&mut self.centroids);
+        all.extend(centroids);
+        all.extend(
+            self.buffer
+                .drain(..)
+                .map(|mean| Centroid { mean, weight: 1.0 }),
+        );
+        if all.is_empty() {
+            return;
+        }
+        all.sort_by(|a, b| a.mean.partial_cmp(&b.mean).unwrap_or(Ordering::Equal));
+
+        let total = all.iter().map(|c| c.weight).sum::<f64>();
+        let mut merged = Vec::with_capacity(all.len());
+        let mut current = all[0];
+        // the weight of the centroids before the current one
+        let mut weight_before = 0.0;
+        for next in all.into_iter().skip(1) {
+            let weight = current.weight + next.weight;
+            // a centroid may hold at most the weight that the scale function allows at
+            // its quantile, which is smallest at the extremes
+            let q = (weight_before + weight / 2.0) / total;
+            if weight <= 4.0 * total * q * (1.0 - q) / TDIGEST_COMPRESSION {
+                current.mean += (next.mean - current.mean) * next.weight / weight;
+                current.weight = weight;
+            } else {
+                weight_before += current.weight;
+                merged.push(current);
+                current = next;
+            }
+        }
+        merged.push(current);
+        self.centroids = merged;
+    }
+
+    /// Merges the centroids of another digest, and its extremes, into this digest
+    fn merge(&mut self, centroids: Vec<Centroid>, min: f64, max: f64) {
+        self.min = self.min.min(min);
+        self.max = self.max.max(max);
+        self.compress(centroids);
+    }
+
+    /// Estimates the quantile `q` of the values, between 0 and 1, interpolating between
+    /// the means of the centroids around it
+    fn quantile(&mut self, q: f64) -> Option<f64> {
+        self.compress(vec![]);
+        if self.centroids.is_empty() {
+            return None;
+        }
+        let total = self.centroids.iter().map(|c| c.weight).sum::<f64>();
+        let rank = q * total;
+
+        // the center of each centroid is at the middle of its weight
+        let mut previous = (0.0, self.min);
+        let mut weight_before = 0.0;
+        for centroid in &self.centroids {
+            let center = weight_before + centroid.weight / 2.0;
+            if rank < center {
+                let (previous_rank, previous_mean) = previous;
+                let fraction = if center > previous_rank {
+                    (rank - previous_rank) / (center - previous_rank)
+                } else {
+                    0.0
+                };
+                return Some(previous_mean + fraction * (centroid.mean - previous_mean));
+            }
+            previous = (center, centroid.mean);
+            weight_before += centroid.weight;
+        }
+        let (previous_rank, previous_mean) = previous;
+        let fraction = if total > previous_rank {
+            (rank - previous_rank) / (total - previous_rank)
+        } else {
+            0.0
+        };
+        Some(previous_mean + fraction * (self.max - previous_mean))
+    }
+}
+
+/// APPROX_PERCENTILE aggregate expression
+#[derive(Debug)]
+pub struct ApproxPercentile {
+    name: String,
+    expr: Arc<dyn PhysicalExpr>,
+    percentile: f64,
+}
+
+impl ApproxPercentile {
+    /// Create a new APPROX_PERCENTILE aggregate function of the `percentile` of the
+    /// values, which must be between 0 and 1
+    pub fn try_new(
+        expr: Arc<dyn PhysicalExpr>,
+        percentile: f64,
+        name: String,
+    ) -> Result<Self> {
+        if !(0.0..=1.0).contains(&percentile) {
+            return Err(DataFusionError::Plan(format!(
+                "The percentile of APPROX_PERCENTILE must be between 0 and 1, not {}",
+                percentile
+            )));
+        }
+        Ok(Self {
+            name,
+            expr,
+            percentile,
+        })
+    }
+}
+
+impl AggregateExpr for ApproxPercentile {
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, DataType::Float64, true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        let list = DataType::List(Box::new(Field::new("item", DataType::Float64, true)));
+        Ok(vec![
+            Field::new(&format_state_name(&self.name, "means"), list.clone(), true),
+            Field::new(&format_state_name(&self.name, "weights"), list, true),
+            Field::new(
+                &format_state_name(&self.name, "min"),
+                DataType::Float64,
+                true,
+            ),
+            Field::new(
+                &format_state_name(&self.name, "max"),
+                DataType::Float64,
+                true,
+            ),
+        ])
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(ApproxPercentileAccumulator {
+            digest: TDigest::new(),
+            percentile: self.percentile,
+        }))
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+}
+
+#[derive(Debug)]
+struct ApproxPercentileAccumulator {
+    digest: TDigest,
+    percentile: f64,
+}
+
+/// The values of a list of `Float64` scalars
+fn list_to_f64s(list: &ScalarValue) -> Result<Vec<f64>> {
+    match list {
+        ScalarValue::List(Some(values), _) => values
+            .iter()
+            .map(|value| match value {
+                ScalarValue::Float64(Some(value)) => Ok(*value),
+                other => Err(DataFusionError::Internal(format!(
+                    "Unexpected centroid {:?} of APPROX_PERCENTILE",
+                    other
+                ))),
+            })
+            .collect(),
+        ScalarValue::List(None, _) => Ok(vec![]),
+        other => Err(DataFusionError::Internal(format!(
+            "Unexpected state {:?} of APPROX_PERCENTILE",
+            other
+        ))),
+    }
+}
+
+impl Accumulator for ApproxPercentileAccumulator {
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        let mut digest = self.digest.clone();
+        digest.compress(vec![]);
+        let (means, weights) = digest
+            .centroids
+            .iter()
+            .map(|c| (ScalarValue::from(c.mean), ScalarValue::from(c.weight)))
+            .unzip();
+        let extreme =
+            |value: f64| ScalarValue::Float64(Some(value).filter(|v| v.is_finite()));
+        Ok(vec![
+            ScalarValue::List(Some(means), DataType::Float64),
+            ScalarValue::List(Some(weights), DataType::Float64),
+            extreme(digest.min),
+            extreme(digest.max),
+        ])
+    }
+
+    fn update(&mut self, values: &Vec<ScalarValue>) -> Result<()> {
+        match &values[0] {
+            ScalarValue::Float64(Some(value)) => self.digest.add(*value),
+            ScalarValue::Float64(None) => {}
+            other => {
+                return Err(DataFusionError::Internal(format!(
+                    "Unexpected value {:?} of APPROX_PERCENTILE",
+                    other
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    fn update_batch(&mut self, values: &Vec<ArrayRef>) -> Result<()> {
+        let values = values[0]
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .ok_or_else(|| {
+                DataFusionError::Internal(
+                    "could not cast values to Float64Array".to_string(),
+                )
+            })?;
+        for i in 0..values.len() {
+            if values.is_valid(i) {
+                self.digest.add(values.value(i));
+            }
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &Vec<ScalarValue>) -> Result<()> {
+        let means = list_to_f64s(&states[0])?;
+        let weights = list_to_f64s(&states[1])?;
+        let centroids = means
+            .into_iter()
+            .zip(weights)
+            .map(|(mean, weight)| Centroid { mean, weight })
+            .collect::<Vec<_>>();
+        let extreme = |state: &ScalarValue, default: f64| match state {
+            ScalarValue::Float64(value) => value.unwrap_or(default),
+            _ => default,
+        };
+        self.digest.merge(
+            centroids,
+            extreme(&states[2], f64::INFINITY),
+            extreme(&states[3], f64::NEG_INFINITY),
+        );
+        Ok(())
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        let mut digest = self.digest.clone();
+        Ok(ScalarValue::Float64(digest.quantile(self.percentile)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::col;
+    use arrow::array::Int64Array;
+
+    /// Accumulates `values` in partitions of `partition_size` values, and merges the states
+    /// of their accumulators
+    fn aggregate(
+        agg: &dyn AggregateExpr,
+        values: ArrayRef,
+        partition_size: usize,
+    ) -> Result<ScalarValue> {
+        let mut merged = agg.create_accumulator()?;
+        let mut offset = 0;
+        while offset < values.len() {
+            let length = partition_size.min(values.len() - offset);
+            let mut accumulator = agg.create_accumulator()?;
+            accumulator.update_batch(&vec![values.slice(offset, length)])?;
+            let states = accumulator
+                .state()?
+                .iter()
+                .map(|s| s.to_array())
+                .collect::<Vec<_>>();
+            merged.merge_batch(&states)?;
+            offset += length;
+        }
+        merged.evaluate()
+    }
+
+    #[test]
+    fn approx_distinct() -> Result<()> {
+        // 10000 distinct values, each repeated twice across the partitions
+        let values: ArrayRef = Arc::new(Int64Array::from(
+            (0..20000).map(|i| Some(i % 10000)).collect::<Vec<_>>(),
+        ));
+        let agg = ApproxDistinct::new(col("a"), "d".to_string());
+        let count = match aggregate(&agg, values, 3000)? {
+            ScalarValue::UInt64(Some(count)) => count,
+            other => panic!("unexpected count {:?}", other),
+        };
+        assert!((9500..10500).contains(&count), "{}", count);
+
+        let values: ArrayRef =
+            Arc::new(Int64Array::from(vec![Some(1), None, Some(2), Some(1)]));
+        assert_eq!(aggregate(&agg, values, 2)?, ScalarValue::UInt64(Some(2)));
+        Ok(())
+    }
+
+    #[test]
+    fn approx_percentile() -> Result<()> {
+        let values: ArrayRef = Arc::new(Float64Array::from(
+            (0..=10000)
+                .rev()
+                .map(|i| Some(i as f64))
+                .collect::<Vec<_>>(),
+        ));
+        for (percentile, expected) in &[(0.0, 0.0), (0.5, 5000.0), (0.99, 9900.0)] {
+            let agg = ApproxPercentile::try_new(col("a"), *percentile, "p".to_string())?;
+            match aggregate(&agg, values.clone(), 1500)? {
+                ScalarValue::Float64(Some(actual)) => assert!(
+                    (actual - expected).abs() <= 50.0,
+                    "{} is not about {}",
+                    actual,
+                    expected
+                ),
+                other => panic!("unexpected percentile {:?}", other),
+            }
+        }
+
+        let values: ArrayRef = Arc::new(Float64Array::from(vec![None, None]));
+        let agg = ApproxPercentile::try_new(col("a"), 0.5, "p".to_string())?;
+        assert_eq!(aggregate(&agg, values, 1)?, ScalarValue::Float64(None));
+
+        assert!(ApproxPercentile::try_new(col("a"), 1.5, "p".to_string()).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,247 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the cancellation of the execution of a query, either on demand through a
+//! [CancellationToken] or once a timeout elapses.
+//!
+//! The tasks that operators spawn to execute their input are aborted once the stream of
+//! their output is dropped. A cancelled query stops by dropping the stream of its output,
+//! which drops the streams of all its operators in turn.
+
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use arrow::datatypes::SchemaRef;
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+
+use futures::{Future, Stream};
+use tokio::time::Delay;
+
+use super::{RecordBatchStream, SendableRecordBatchStream};
+use crate::error::{DataFusionError, Result};
+
+/// A token that cancels the execution of the queries that it is passed to, such as to
+/// [crate::execution::context::ExecutionContext::collect_with_cancellation]. Clones of a
+/// token share its state, so that a query can be cancelled from another task.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    state: Arc<CancellationState>,
+}
+
+#[derive(Debug, Default)]
+struct CancellationState {
+    cancelled: AtomicBool,
+    /// the tasks to wake once the token is cancelled
+    wakers: Mutex<Vec<Waker>>,
+}
+
+impl CancellationToken {
+    /// Create a new token that is not cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the queries of this token
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::SeqCst);
+        if let Ok(mut wakers) = self.state.wakers.lock() {
+            wakers.drain(..).for_each(Waker::wake);
+        }
+    }
+
+    /// Whether this token was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Wakes the task of `cx` once this token is cancelled
+    fn register(&self, cx: &Context<'_>) {
+        if let Ok(mut wakers) = self.state.wakers.lock() {
+            if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                wakers.push(cx.waker().clone());
+            }
+        }
+    }
+}
+
+/// The cancellation of a query, by its token or once its timeout elapses
+#[derive(Debug)]
+pub(crate) struct Cancellation {
+    token: CancellationToken,
+    /// the timeout of the query, with the delay that elapses with it
+    timeout: Option<(Duration, Pin<Box<Delay>>)>,
+}
+
+impl Cancellation {
+    /// Create the cancellation of a query that starts now
+    pub fn new(token: CancellationToken, timeout: Option<Duration>) -> Self {
+        Self {
+            token,
+            timeout: timeout
+                .map(|timeout| (timeout, Box::pin(tokio::time::delay_for(timeout)))),
+        }
+    }
+
+    /// Returns the error of the query once it is cancelled, or registers the task of `cx` to
+    /// be woken once it is
+    fn poll_cancelled(&mut self, cx: &mut Context<'_>) -> Option<DataFusionError> {
+        if let Some((timeout, delay)) = &mut self.timeout {
+            if delay.as_mut().poll(cx).is_ready() {
+                // the timeout only cancels this query, not the other queries of the token
+                return Some(DataFusionError::Cancelled(format!(
+                    "the query timed out after {:?}",
+                    timeout
+                )));
+            }
+        }
+        if self.token.is_cancelled() {
+            return Some(DataFusionError::Cancelled(
+                "the query was cancelled".to_owned(),
+            ));
+        }
+        self.token.register(cx);
+        None
+    }
+
+    /// Runs `future` until it completes or the query is cancelled
+    pub async fn run<T>(&mut self, future: impl Future<Output = Result<T>>) -> Result<T> {
+        futures::pin_mut!(future);
+        futures::future::poll_fn(|cx| match self.poll_cancelled(cx) {
+            Some(e) => Poll::Ready(Err(e)),
+            None => future.as_mut().poll(cx),
+        })
+        .await
+    }
+}
+
+/// A stream that stops with an error once its query is cancelled, dropping its input
+pub(crate) struct CancellableStream {
+    schema: SchemaRef,
+    /// the input, until it is exhausted or the query is cancelled
+    input: Option<SendableRecordBatchStream>,
+    cancellation: Cancellation,
+}
+
+impl CancellableStream {
+    /// Create a new stream of `input` that stops once `cancellation` does
+    pub fn new(input: SendableRecordBatchStream, cancellation: Cancellation) -> Self {
+        Self {
+            schema: input.schema(),
+            input: Some(input),
+            cancellation,
+        }
+    }
+}
+
+impl Stream for CancellableStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let input = match this.input.as_mut() {
+            Some(input) => input,
+            None => return Poll::Ready(None),
+        };
+        if let Some(e) = this.cancellation.poll_cancelled(cx) {
+            // dropping the input aborts the tasks that execute it
+            this.input = None;
+            return Poll::Ready(Some(Err(e.into_arrow_external_error())));
+        }
+        let poll = input.as_mut().poll_next(cx);
+        if let Poll::Ready(None) = poll {
+            this.input = None;
+        }
+        poll
+    }
+}
+
+impl RecordBatchStream for CancellableStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::common;
+    use arrow::datatypes::Schema;
+
+    /// A stream that never returns a batch
+    struct PendingStream {
+        schema: SchemaRef,
+    }
+
+    impl Stream for PendingStream {
+        type Item = ArrowResult<RecordBatch>;
+
+        fn poll_next(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+        ) -> Poll<Option<Self::Item>> {
+            Poll::Pending
+        }
+    }
+
+    impl RecordBatchStream for PendingStream {
+        fn schema(&self) -> SchemaRef {
+            self.schema.clone()
+        }
+    }
+
+    fn pending() -> SendableRecordBatchStream {
+        Box::pin(PendingStream {
+            schema: Arc::new(Schema::empty()),
+        })
+    }
+
+    #[tokio::test]
+    async fn cancel() -> Result<()> {
+        let token = CancellationToken::new();
+        let stream =
+            CancellableStream::new(pending(), Cancellation::new(token.clone(), None));
+
+        let cancel = token.clone();
+        tokio::spawn(async move { cancel.cancel() });
+        let e = common::collect(Box::pin(stream)).await.unwrap_err();
+        assert!(e.to_string().contains("the query was cancelled"));
+        assert!(token.is_cancelled());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn timeout() -> Result<()> {
+        let token = CancellationToken::new();
+        let timeout = Some(Duration::from_millis(10));
+        let mut cancellation = Cancellation::new(token.clone(), timeout);
+
+        let e = cancellation
+            .run(futures::future::pending::<Result<()>>())
+            .await
+            .unwrap_err();
+        assert!(e.to_string().contains("the query timed out after 10ms"));
+        // the token is only cancelled on demand
+        assert!(!token.is_cancelled());
+
+        Ok(())
+    }
+}
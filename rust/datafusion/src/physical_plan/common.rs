@@ -17,11 +17,13 @@
 
 //! Defines common code used in execution plans
 
+use std::cmp::Ordering;
 use std::fs;
 use std::fs::metadata;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
+use super::metrics::ComputeFuture;
 use super::{RecordBatchStream, SendableRecordBatchStream};
 use crate::error::{DataFusionError, Result};
 
@@ -30,6 +32,8 @@ use array::{
     Int8Array, LargeStringArray, StringArray, UInt16Array, UInt32Array, UInt64Array,
     UInt8Array,
 };
+use arrow::array::{build_compare, DynComparator};
+use arrow::compute::{concat, SortOptions};
 use arrow::datatypes::{DataType, SchemaRef};
 use arrow::error::Result as ArrowResult;
 use arrow::record_batch::RecordBatch;
@@ -37,7 +41,9 @@ use arrow::{
     array::{self, ArrayRef},
     datatypes::Schema,
 };
-use futures::{Stream, TryStreamExt};
+use futures::future::{abortable, AbortHandle};
+use futures::{Future, Stream, TryStreamExt};
+use tokio::task::JoinHandle;
 
 /// Stream of record batches
 pub struct SizedRecordBatchStream {
@@ -114,6 +120,184 @@ pub fn build_file_list(dir: &str, filenames: &mut Vec<String>, ext: &str) -> Res
     Ok(())
 }
 
+/// Returns the total size in bytes of the files, or `None` when the size of a file is unknown
+pub fn files_size(filenames: &[String]) -> Option<usize> {
+    filenames
+        .iter()
+        .map(|filename| metadata(filename).ok().map(|m| m.len() as usize))
+        .sum()
+}
+
+/// Combines record batches with schema `schema` into one
+pub fn combine_batches(
+    batches: &[RecordBatch],
+    schema: &SchemaRef,
+) -> ArrowResult<RecordBatch> {
+    RecordBatch::try_new(
+        schema.clone(),
+        schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(i, _)| {
+                concat(
+                    &batches
+                        .iter()
+                        .map(|batch| batch.columns()[i].clone())
+                        .collect::<Vec<ArrayRef>>(),
+                )
+            })
+            .collect::<ArrowResult<Vec<ArrayRef>>>()?,
+    )
+}
+
+/// Compares the row `left_row` of the columns `left` with the row `right_row` of the columns
+/// `right`, which are sorted by `sort_options`. `comparators` compare the non-null values of
+/// every pair of columns, see [build_comparators].
+pub fn compare_rows(
+    left: &[ArrayRef],
+    left_row: usize,
+    right: &[ArrayRef],
+    right_row: usize,
+    comparators: &[DynComparator],
+    sort_options: &[SortOptions],
+) -> Ordering {
+    for (i, options) in sort_options.iter().enumerate() {
+        let ordering = match (left[i].is_valid(left_row), right[i].is_valid(right_row)) {
+            (true, true) => {
+                let ordering = comparators[i](left_row, right_row);
+                if options.descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            }
+            (false, false) => Ordering::Equal,
+            (false, true) if options.nulls_first => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            (true, false) if options.nulls_first => Ordering::Greater,
+            (true, false) => Ordering::Less,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Returns the comparators of the values of every pair of columns of `left` and `right`
+pub fn build_comparators<'a>(
+    left: &'a [ArrayRef],
+    right: &'a [ArrayRef],
+) -> Result<Vec<DynComparator<'a>>> {
+    Ok(left
+        .iter()
+        .zip(right.iter())
+        .map(|(l, r)| build_compare(l.as_ref(), r.as_ref()))
+        .collect::<ArrowResult<Vec<_>>>()?)
+}
+
+/// Returns the comparators of the sort keys of every pair of runs, whose sort keys are
+/// `keys`, indexed by the left run and then by the right run
+pub(crate) fn build_run_comparators<'a>(
+    keys: &'a [Vec<ArrayRef>],
+) -> Result<Vec<Vec<Vec<DynComparator<'a>>>>> {
+    keys.iter()
+        .map(|left| {
+            keys.iter()
+                .map(|right| build_comparators(left, right))
+                .collect::<Result<Vec<_>>>()
+        })
+        .collect()
+}
+
+/// The next row of a sorted run that is being merged with other runs. Cursors are ordered
+/// so that a [std::collections::BinaryHeap] of them pops the cursor of the first row.
+pub(crate) struct RunCursor<'a> {
+    /// the index of the run
+    pub run: usize,
+    /// the next row of the run
+    pub row: usize,
+    /// the values of the sort expressions of every run
+    pub keys: &'a [Vec<ArrayRef>],
+    /// the comparators of the values of the sort expressions of every pair of runs, see
+    /// [build_run_comparators]
+    pub comparators: &'a [Vec<Vec<DynComparator<'a>>>],
+    /// the options of the sort expressions
+    pub sort_options: &'a [SortOptions],
+}
+
+impl<'a> RunCursor<'a> {
+    /// Compares the row of this cursor with the row of `other`
+    fn compare(&self, other: &Self) -> Ordering {
+        compare_rows(
+            &self.keys[self.run],
+            self.row,
+            &self.keys[other.run],
+            other.row,
+            &self.comparators[self.run][other.run],
+            self.sort_options,
+        )
+    }
+}
+
+impl<'a> Ord for RunCursor<'a> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // the heap pops its greatest cursor, which must be the cursor of the first row.
+        // Equal rows are taken from the runs in their order.
+        other.compare(self).then_with(|| other.run.cmp(&self.run))
+    }
+}
+
+impl<'a> PartialOrd for RunCursor<'a> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a> PartialEq for RunCursor<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<'a> Eq for RunCursor<'a> {}
+
+/// Returns the number of bytes that the batch occupies in memory
+pub(crate) fn batch_memory_size(batch: &RecordBatch) -> usize {
+    batch
+        .columns()
+        .iter()
+        .map(|column| column.get_array_memory_size())
+        .sum()
+}
+
+/// Aborts a task that was spawned with [spawn_abortable] when it is dropped
+#[derive(Debug)]
+pub(crate) struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Spawns `task` and returns its handle, whose output is `None` when the task is aborted,
+/// with a guard that aborts the task when dropped. The streams that receive the output of
+/// a task keep its guard, so that the task stops once its output is no longer consumed,
+/// such as when the query is cancelled. The task records its metrics for the plan that
+/// spawns it.
+pub(crate) fn spawn_abortable<T>(
+    task: impl Future<Output = T> + Send + 'static,
+) -> (JoinHandle<Option<T>>, AbortOnDrop)
+where
+    T: Send + 'static,
+{
+    let (task, handle) = abortable(ComputeFuture::current(task));
+    let task = tokio::spawn(async move { task.await.ok() });
+    (task, AbortOnDrop(handle))
+}
+
 /// creates an empty record batch.
 pub fn create_batch_empty(schema: &Schema) -> ArrowResult<RecordBatch> {
     let columns = schema
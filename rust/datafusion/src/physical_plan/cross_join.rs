@@ -0,0 +1,420 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the cross join plan, which combines every row of the left with every row of the
+//! right.
+
+use std::any::Any;
+use std::sync::Arc;
+use std::task::Poll;
+
+use async_trait::async_trait;
+use futures::future::{BoxFuture, FutureExt};
+use futures::lock::Mutex;
+use futures::{Stream, StreamExt};
+
+use arrow::array::{make_array, ArrayRef, MutableArrayData};
+use arrow::datatypes::{Schema, SchemaRef};
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+
+use super::{
+    common, hash_utils::build_cross_join_schema, merge::MergeExec, metrics,
+    ExecutionPlan, Partitioning, RecordBatchStream, SendableRecordBatchStream,
+};
+use crate::error::{DataFusionError, Result};
+use crate::execution::memory_manager::{MemoryManager, MemoryReservation};
+
+// The batches of the left, and the reservation of their memory
+type CrossJoinLeftData = (Vec<RecordBatch>, MemoryReservation);
+
+/// Cross join execution plan: the left side is collected into memory and every batch of the
+/// right is combined with all of its rows.
+#[derive(Debug)]
+pub struct CrossJoinExec {
+    /// left side, which is collected into memory
+    left: Arc<dyn ExecutionPlan>,
+    /// right side, which is streamed
+    right: Arc<dyn ExecutionPlan>,
+    /// The schema once the join is applied
+    schema: SchemaRef,
+    /// The batches of the left, collected by the first partition whose stream is polled
+    /// and shared by all the partitions
+    left_data: Arc<Mutex<Option<Arc<CrossJoinLeftData>>>>,
+    /// Manager that the memory of the batches of the left is reserved from
+    memory_manager: Arc<MemoryManager>,
+}
+
+impl CrossJoinExec {
+    /// Tries to create a new [CrossJoinExec].
+    /// # Error
+    /// This function errors when the left and right sides have columns with the same name.
+    pub fn try_new(
+        left: Arc<dyn ExecutionPlan>,
+        right: Arc<dyn ExecutionPlan>,
+    ) -> Result<Self> {
+        let schema = Arc::new(build_cross_join_schema(&left.schema(), &right.schema())?);
+
+        Ok(CrossJoinExec {
+            left,
+            right,
+            schema,
+            left_data: Arc::new(Mutex::new(None)),
+            memory_manager: Arc::new(MemoryManager::new(None)),
+        })
+    }
+
+    /// Reserve the memory of the batches of the left from `memory_manager`. The join
+    /// fails once more memory cannot be reserved.
+    pub fn with_memory_manager(mut self, memory_manager: Arc<MemoryManager>) -> Self {
+        self.memory_manager = memory_manager;
+        self
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for CrossJoinExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.left.clone(), self.right.clone()]
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            2 => Ok(Arc::new(
+                CrossJoinExec::try_new(children[0].clone(), children[1].clone())?
+                    .with_memory_manager(self.memory_manager.clone()),
+            )),
+            _ => Err(DataFusionError::Internal(
+                "CrossJoinExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        self.right.output_partitioning()
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        // as in the hash join, the left is collected once, on the first poll of a stream
+        let left_fut = collect_left_data(
+            self.left.clone(),
+            self.memory_manager.new_reservation(),
+            self.left_data.clone(),
+        )
+        .boxed();
+
+        let right = self.right.execute(partition).await?;
+        Ok(Box::pin(CrossJoinStream {
+            schema: self.schema.clone(),
+            left_fut: Some(left_fut),
+            left: None,
+            right,
+        }))
+    }
+}
+
+/// Returns the batches of all the partitions of `left`, which the first partition of the
+/// join to be polled collects and stores in `left_data`, while the other partitions wait
+/// for it
+async fn collect_left_data(
+    left: Arc<dyn ExecutionPlan>,
+    mut reservation: MemoryReservation,
+    left_data: Arc<Mutex<Option<Arc<CrossJoinLeftData>>>>,
+) -> Result<Arc<CrossJoinLeftData>> {
+    let mut left_data = left_data.lock().await;
+    match left_data.as_ref() {
+        Some(data) => Ok(data.clone()),
+        None => {
+            // merge all parts into a single stream
+            let mut stream = MergeExec::new(left).execute(0).await?;
+            let mut batches = vec![];
+            while let Some(batch) = stream.next().await {
+                let batch = batch?;
+                reservation.try_grow(common::batch_memory_size(&batch))?;
+                batches.push(batch);
+            }
+            metrics::record_memory(reservation.size());
+            let data = Arc::new((batches, reservation));
+            *left_data = Some(data.clone());
+            Ok(data)
+        }
+    }
+}
+
+/// A stream that issues [RecordBatch]es as they arrive from the right of the join.
+struct CrossJoinStream {
+    /// Output schema
+    schema: SchemaRef,
+    /// collects the batches of the left, until it is polled to completion
+    left_fut: Option<BoxFuture<'static, Result<Arc<CrossJoinLeftData>>>>,
+    /// all batches of the left, once they are collected
+    left: Option<Arc<CrossJoinLeftData>>,
+    /// right
+    right: SendableRecordBatchStream,
+}
+
+impl RecordBatchStream for CrossJoinStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+/// Combines every row of `left` with every row of `right`. The rows are ordered by the rows
+/// of the left and then by the rows of the right.
+/// `left` must contain at least one batch.
+fn build_batch(
+    left: &[RecordBatch],
+    right: &RecordBatch,
+    schema: &Schema,
+) -> ArrowResult<RecordBatch> {
+    // (batch, row) of every row of the left
+    let left_rows = left
+        .iter()
+        .enumerate()
+        .flat_map(|(index, batch)| (0..batch.num_rows()).map(move |row| (index, row)))
+        .collect::<Vec<_>>();
+    let num_rows = left_rows.len() * right.num_rows();
+
+    let mut columns: Vec<ArrayRef> = Vec::with_capacity(schema.fields().len());
+    for column in 0..left[0].num_columns() {
+        let arrays = left
+            .iter()
+            .map(|batch| batch.column(column).data())
+            .collect::<Vec<_>>();
+        let arrays = arrays
+            .iter()
+            .map(|array| array.as_ref())
+            .collect::<Vec<_>>();
+        let mut mutable = MutableArrayData::new(arrays, false, num_rows);
+        // every row of the left is repeated once per row of the right
+        for (batch, row) in &left_rows {
+            for _ in 0..right.num_rows() {
+                mutable.extend(*batch, *row, *row + 1);
+            }
+        }
+        columns.push(make_array(Arc::new(mutable.freeze())));
+    }
+    for column in right.columns() {
+        let array = column.data();
+        let mut mutable = MutableArrayData::new(vec![array.as_ref()], false, num_rows);
+        // the whole right is repeated once per row of the left
+        for _ in 0..left_rows.len() {
+            mutable.extend(0, 0, right.num_rows());
+        }
+        columns.push(make_array(Arc::new(mutable.freeze())));
+    }
+    RecordBatch::try_new(Arc::new(schema.clone()), columns)
+}
+
+impl Stream for CrossJoinStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let stream = self.get_mut();
+        let left = match &stream.left {
+            Some(left) => left.clone(),
+            None => {
+                let left_fut = match stream.left_fut.as_mut() {
+                    Some(left_fut) => left_fut,
+                    // the left failed to be collected
+                    None => return Poll::Ready(None),
+                };
+                let result = match left_fut.poll_unpin(cx) {
+                    Poll::Ready(result) => result,
+                    Poll::Pending => return Poll::Pending,
+                };
+                stream.left_fut = None;
+                match result {
+                    Ok(left) => {
+                        stream.left = Some(left.clone());
+                        left
+                    }
+                    Err(e) => {
+                        return Poll::Ready(Some(Err(
+                            DataFusionError::into_arrow_external_error(e),
+                        )))
+                    }
+                }
+            }
+        };
+        if left.0.is_empty() {
+            // there is nothing to combine the right with
+            return Poll::Ready(None);
+        }
+        stream
+            .right
+            .poll_next_unpin(cx)
+            .map(|maybe_batch| match maybe_batch {
+                Some(Ok(batch)) => Some(build_batch(&left.0, &batch, &stream.schema)),
+                other => other,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{
+        physical_plan::memory::MemoryExec,
+        test::{build_table_i32, columns, format_batch},
+    };
+
+    fn build_table(
+        a: (&str, &Vec<i32>),
+        b: (&str, &Vec<i32>),
+        c: (&str, &Vec<i32>),
+    ) -> Arc<dyn ExecutionPlan> {
+        let batch = build_table_i32(a, b, c);
+        let schema = batch.schema();
+        Arc::new(MemoryExec::try_new(&vec![vec![batch]], schema, None).unwrap())
+    }
+
+    #[tokio::test]
+    async fn cross_join() -> Result<()> {
+        let left = build_table(
+            ("a1", &vec![1, 2]),
+            ("b1", &vec![3, 4]),
+            ("c1", &vec![5, 6]),
+        );
+        let right = build_table(
+            ("a2", &vec![10, 20, 30]),
+            ("b2", &vec![40, 50, 60]),
+            ("c2", &vec![70, 80, 90]),
+        );
+
+        let join = CrossJoinExec::try_new(left, right)?;
+
+        let columns = columns(&join.schema());
+        assert_eq!(columns, vec!["a1", "b1", "c1", "a2", "b2", "c2"]);
+
+        let stream = join.execute(0).await?;
+        let batches = common::collect(stream).await?;
+        assert_eq!(batches.len(), 1);
+
+        let result = format_batch(&batches[0]);
+        let expected = vec![
+            "1,3,5,10,40,70",
+            "1,3,5,20,50,80",
+            "1,3,5,30,60,90",
+            "2,4,6,10,40,70",
+            "2,4,6,20,50,80",
+            "2,4,6,30,60,90",
+        ];
+        assert_eq!(result, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn cross_join_same_column_names() {
+        let left = build_table(
+            ("a1", &vec![1, 2]),
+            ("b1", &vec![3, 4]),
+            ("c1", &vec![5, 6]),
+        );
+        let right = build_table(
+            ("a1", &vec![10, 20, 30]),
+            ("b2", &vec![40, 50, 60]),
+            ("c2", &vec![70, 80, 90]),
+        );
+
+        assert!(CrossJoinExec::try_new(left, right).is_err());
+    }
+
+    #[tokio::test]
+    async fn cross_join_partitions_share_left() -> Result<()> {
+        let left = build_table(
+            ("a1", &vec![1, 2]),
+            ("b1", &vec![3, 4]),
+            ("c1", &vec![5, 6]),
+        );
+        let batch = build_table_i32(
+            ("a2", &vec![10, 20]),
+            ("b2", &vec![40, 50]),
+            ("c2", &vec![70, 80]),
+        );
+        let schema = batch.schema();
+        let right = Arc::new(MemoryExec::try_new(
+            &vec![vec![batch.clone()], vec![batch]],
+            schema,
+            None,
+        )?);
+
+        let memory_manager = Arc::new(MemoryManager::new(None));
+        let join = CrossJoinExec::try_new(left.clone(), right)?
+            .with_memory_manager(memory_manager.clone());
+
+        let mut num_rows = 0;
+        for partition in 0..2 {
+            let batches = common::collect(join.execute(partition).await?).await?;
+            num_rows += batches.iter().map(|batch| batch.num_rows()).sum::<usize>();
+        }
+        assert_eq!(num_rows, 8);
+
+        // the left is collected once, and its memory is reserved while the join is kept
+        let left_size = common::collect(left.execute(0).await?)
+            .await?
+            .iter()
+            .map(common::batch_memory_size)
+            .sum::<usize>();
+        assert_eq!(memory_manager.reserved(), left_size);
+        drop(join);
+        assert_eq!(memory_manager.reserved(), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cross_join_memory_limit() -> Result<()> {
+        let left = build_table(
+            ("a1", &vec![1, 2]),
+            ("b1", &vec![3, 4]),
+            ("c1", &vec![5, 6]),
+        );
+        let right = build_table(
+            ("a2", &vec![10, 20, 30]),
+            ("b2", &vec![40, 50, 60]),
+            ("c2", &vec![70, 80, 90]),
+        );
+
+        // the batches of the left do not fit in the memory limit
+        let memory_manager = Arc::new(MemoryManager::new(Some(1)));
+        let join = CrossJoinExec::try_new(left, right)?
+            .with_memory_manager(memory_manager.clone());
+
+        let e = common::collect(join.execute(0).await?).await.unwrap_err();
+        assert!(e.to_string().contains("Resources exhausted"), "{}", e);
+        assert_eq!(memory_manager.reserved(), 0);
+
+        Ok(())
+    }
+}
@@ -202,6 +202,10 @@ impl ExecutionPlan for CsvExec {
         Partitioning::UnknownPartitioning(self.filenames.len())
     }
 
+    fn output_size_estimate(&self) -> Option<usize> {
+        common::files_size(&self.filenames)
+    }
+
     fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
         // this is a leaf node and has no children
         vec![]
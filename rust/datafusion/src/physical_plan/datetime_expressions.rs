@@ -17,15 +17,26 @@
 
 //! DateTime expressions
 
+use std::convert::TryFrom;
 use std::sync::Arc;
 
 use crate::error::{DataFusionError, Result};
 use arrow::{
-    array::{Array, ArrayData, ArrayRef, StringArray, TimestampNanosecondArray},
+    array::{
+        Array, ArrayData, ArrayRef, Date32Array, Date64Array, Int32Array,
+        IntervalDayTimeArray, IntervalYearMonthArray, PrimitiveArray, StringArray,
+        TimestampMicrosecondArray, TimestampMillisecondArray, TimestampNanosecondArray,
+        TimestampSecondArray,
+    },
     buffer::Buffer,
-    datatypes::{DataType, TimeUnit, ToByteSlice},
+    compute::kernels::temporal,
+    datatypes::{ArrowPrimitiveType, DataType, IntervalUnit, TimeUnit, ToByteSlice},
 };
-use chrono::{prelude::*, LocalResult};
+use chrono::{prelude::*, Duration, LocalResult};
+
+const NANOSECONDS_IN_SECOND: i64 = 1_000_000_000;
+const NANOSECONDS_IN_MILLISECOND: i64 = 1_000_000;
+const MILLISECONDS_IN_DAY: i64 = 86_400_000;
 
 #[inline]
 /// Accepts a string in RFC3339 / ISO8601 standard format and some
@@ -205,6 +216,311 @@ pub fn to_timestamp(args: &[ArrayRef]) -> Result<TimestampNanosecondArray> {
     Ok(TimestampNanosecondArray::from(Arc::new(data)))
 }
 
+/// Returns the value of the string argument `name`, which must be the same for every row,
+/// such as a literal, or `None` when it is null or there are no rows
+fn constant_string_arg<'a>(array: &'a ArrayRef, name: &str) -> Result<Option<&'a str>> {
+    let array = array
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .ok_or_else(|| {
+            DataFusionError::Internal(format!("could not cast {} to StringArray", name))
+        })?;
+    let value = if array.len() == 0 || array.is_null(0) {
+        None
+    } else {
+        Some(array.value(0))
+    };
+    let is_constant = (1..array.len()).all(|i| match value {
+        Some(value) => array.is_valid(i) && array.value(i) == value,
+        None => array.is_null(i),
+    });
+    if !is_constant {
+        return Err(DataFusionError::NotImplemented(format!(
+            "the {} must be the same for every row",
+            name
+        )));
+    }
+    Ok(value)
+}
+
+/// Converts nanoseconds since the UNIX epoch to a datetime
+fn nanos_to_datetime(nanos: i64) -> NaiveDateTime {
+    NaiveDateTime::from_timestamp(
+        nanos.div_euclid(NANOSECONDS_IN_SECOND),
+        nanos.rem_euclid(NANOSECONDS_IN_SECOND) as u32,
+    )
+}
+
+/// Converts a datetime to nanoseconds since the UNIX epoch, unless they are out of range
+fn datetime_to_nanos(datetime: NaiveDateTime) -> Option<i64> {
+    datetime
+        .timestamp()
+        .checked_mul(NANOSECONDS_IN_SECOND)?
+        .checked_add(datetime.timestamp_subsec_nanos() as i64)
+}
+
+/// Truncates `datetime` to the start of the `granularity` that it is in
+fn truncate(granularity: &str, datetime: NaiveDateTime) -> Result<NaiveDateTime> {
+    let date = datetime.date();
+    let time = datetime.time();
+    let first_day_of_month = |month| NaiveDate::from_ymd(date.year(), month, 1);
+    Ok(match granularity {
+        "second" => date.and_hms(time.hour(), time.minute(), time.second()),
+        "minute" => date.and_hms(time.hour(), time.minute(), 0),
+        "hour" => date.and_hms(time.hour(), 0, 0),
+        "day" => date.and_hms(0, 0, 0),
+        // weeks start on Mondays
+        "week" => {
+            let days_since_monday = date.weekday().num_days_from_monday() as i64;
+            (date - Duration::days(days_since_monday)).and_hms(0, 0, 0)
+        }
+        "month" => first_day_of_month(date.month()).and_hms(0, 0, 0),
+        "quarter" => first_day_of_month(date.month0() / 3 * 3 + 1).and_hms(0, 0, 0),
+        "year" => first_day_of_month(1).and_hms(0, 0, 0),
+        other => {
+            return Err(DataFusionError::Execution(format!(
+                "Unsupported date_trunc granularity: {}",
+                other
+            )))
+        }
+    })
+}
+
+/// Truncates the `Timestamp(Nanosecond, None)` timestamps of the second argument to the
+/// start of the granularity of the first, such as `'hour'` or `'month'`, which must be
+/// the same for every row
+pub fn date_trunc(args: &[ArrayRef]) -> Result<TimestampNanosecondArray> {
+    let granularity = constant_string_arg(&args[0], "granularity")?
+        .map(|granularity| granularity.to_lowercase());
+    let timestamps = args[1]
+        .as_any()
+        .downcast_ref::<TimestampNanosecondArray>()
+        .ok_or_else(|| {
+            DataFusionError::Internal(
+                "could not cast date_trunc input to TimestampNanosecondArray".to_string(),
+            )
+        })?;
+
+    let result = (0..timestamps.len())
+        .map(|i| match &granularity {
+            Some(granularity) if timestamps.is_valid(i) => {
+                let datetime = nanos_to_datetime(timestamps.value(i));
+                Ok(datetime_to_nanos(truncate(granularity, datetime)?))
+            }
+            _ => Ok(None),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(TimestampNanosecondArray::from_opt_vec(result, None))
+}
+
+/// Downcasts `$ARRAY` to `$ARRAY_TYPE`
+macro_rules! downcast_temporal {
+    ($ARRAY:expr, $ARRAY_TYPE:ident) => {{
+        $ARRAY
+            .as_any()
+            .downcast_ref::<$ARRAY_TYPE>()
+            .ok_or_else(|| {
+                DataFusionError::Internal(format!(
+                    "could not cast the input to {}",
+                    stringify!($ARRAY_TYPE)
+                ))
+            })?
+    }};
+}
+
+/// Invokes the temporal kernel `$FUNC` on `$ARRAY`, of a date or timestamp type
+macro_rules! extract_date_part {
+    ($ARRAY:expr, $FUNC:ident) => {{
+        let array = $ARRAY;
+        match array.data_type() {
+            DataType::Date32(_) => {
+                temporal::$FUNC(downcast_temporal!(array, Date32Array))
+            }
+            DataType::Date64(_) => {
+                temporal::$FUNC(downcast_temporal!(array, Date64Array))
+            }
+            DataType::Timestamp(TimeUnit::Second, _) => {
+                temporal::$FUNC(downcast_temporal!(array, TimestampSecondArray))
+            }
+            DataType::Timestamp(TimeUnit::Millisecond, _) => {
+                temporal::$FUNC(downcast_temporal!(array, TimestampMillisecondArray))
+            }
+            DataType::Timestamp(TimeUnit::Microsecond, _) => {
+                temporal::$FUNC(downcast_temporal!(array, TimestampMicrosecondArray))
+            }
+            DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+                temporal::$FUNC(downcast_temporal!(array, TimestampNanosecondArray))
+            }
+            other => {
+                return Err(DataFusionError::Internal(format!(
+                    "Unsupported data type {:?} for function date_part",
+                    other
+                )))
+            }
+        }
+    }};
+}
+
+/// Extracts the field of the first argument, such as `'year'`, `'dow'` (the day of the
+/// week, from 0 for Sunday) or `'hour'`, which must be the same for every row, from the
+/// dates or timestamps of the second argument, as an `Int32`
+pub fn date_part(args: &[ArrayRef]) -> Result<ArrayRef> {
+    let field = match constant_string_arg(&args[0], "field")? {
+        Some(field) => field.to_lowercase(),
+        // a null field extracts nulls
+        None => return Ok(Arc::new(Int32Array::from(vec![None; args[1].len()]))),
+    };
+    let array = &args[1];
+    let result = match field.as_str() {
+        "year" => extract_date_part!(array, year),
+        "quarter" => extract_date_part!(array, quarter),
+        "month" => extract_date_part!(array, month),
+        "week" => extract_date_part!(array, week),
+        "day" => extract_date_part!(array, day),
+        "dow" => extract_date_part!(array, day_of_week),
+        "doy" => extract_date_part!(array, day_of_year),
+        "hour" => extract_date_part!(array, hour),
+        "minute" => extract_date_part!(array, minute),
+        "second" => extract_date_part!(array, second),
+        other => {
+            return Err(DataFusionError::Execution(format!(
+                "Unsupported date_part field: {}",
+                other
+            )))
+        }
+    };
+    Ok(Arc::new(result?))
+}
+
+/// Converts an array of strings such as `2020-09-08` into `Date32(Day)`
+pub fn to_date(args: &[ArrayRef]) -> Result<Date32Array> {
+    let string_args =
+        args[0]
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .ok_or_else(|| {
+                DataFusionError::Internal(
+                    "could not cast to_date input to StringArray".to_string(),
+                )
+            })?;
+    let epoch = NaiveDate::from_ymd(1970, 1, 1);
+
+    let result = string_args
+        .iter()
+        .map(|string| {
+            string
+                .map(|string| {
+                    NaiveDate::parse_from_str(string, "%Y-%m-%d")
+                        .map(|date| date.signed_duration_since(epoch).num_days() as i32)
+                        .map_err(|_| {
+                            DataFusionError::Execution(format!(
+                                "Error parsing '{}' as date",
+                                string
+                            ))
+                        })
+                })
+                .transpose()
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Date32Array::from(result))
+}
+
+/// Returns the number of days of `month` of `year`
+fn days_in_month(year: i32, month: u32) -> Option<u32> {
+    let (next_year, next_month) = if month == 12 {
+        (year.checked_add(1)?, 1)
+    } else {
+        (year, month + 1)
+    };
+    NaiveDate::from_ymd_opt(next_year, next_month, 1).map(|date| date.pred().day())
+}
+
+/// Adds `months` to `datetime`, keeping its day of the month unless the resulting month
+/// is shorter, in which case its last day is used
+fn add_months(datetime: NaiveDateTime, months: i64) -> Option<NaiveDateTime> {
+    let months =
+        (datetime.year() as i64 * 12 + datetime.month0() as i64).checked_add(months)?;
+    let year = i32::try_from(months.div_euclid(12)).ok()?;
+    let month = months.rem_euclid(12) as u32 + 1;
+    let day = datetime.day().min(days_in_month(year, month)?);
+    NaiveDate::from_ymd_opt(year, month, day).map(|date| date.and_time(datetime.time()))
+}
+
+/// Adds `interval`, a number of days in its high 32 bits and of milliseconds in its low 32
+/// bits, multiplied by `sign`, to `nanos`
+fn add_day_time(nanos: i64, interval: i64, sign: i64) -> Option<i64> {
+    let days = interval >> 32;
+    let milliseconds = interval as i32 as i64;
+    let milliseconds = sign * (days * MILLISECONDS_IN_DAY + milliseconds);
+    nanos.checked_add(milliseconds.checked_mul(NANOSECONDS_IN_MILLISECOND)?)
+}
+
+/// Applies `op` to each timestamp and the interval of the same row, of which a result that
+/// is out of range is an error
+fn add_each<T, F>(
+    timestamps: &TimestampNanosecondArray,
+    intervals: &PrimitiveArray<T>,
+    op: F,
+) -> Result<TimestampNanosecondArray>
+where
+    T: ArrowPrimitiveType,
+    F: Fn(i64, T::Native) -> Option<i64>,
+{
+    let result = (0..timestamps.len())
+        .map(|i| {
+            if timestamps.is_null(i) || intervals.is_null(i) {
+                return Ok(None);
+            }
+            op(timestamps.value(i), intervals.value(i))
+                .map(Some)
+                .ok_or_else(|| {
+                    DataFusionError::Execution(
+                        "The result of adding an interval to a timestamp is out of range"
+                            .to_string(),
+                    )
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(TimestampNanosecondArray::from_opt_vec(result, None))
+}
+
+/// Adds the intervals of `intervals`, or subtracts them when `negated`, to the
+/// `Timestamp(Nanosecond, None)` timestamps of `timestamps`
+pub fn add_intervals(
+    timestamps: &ArrayRef,
+    intervals: &ArrayRef,
+    negated: bool,
+) -> Result<TimestampNanosecondArray> {
+    let timestamps = timestamps
+        .as_any()
+        .downcast_ref::<TimestampNanosecondArray>()
+        .ok_or_else(|| {
+            DataFusionError::Internal(
+                "could not cast timestamps to TimestampNanosecondArray".to_string(),
+            )
+        })?;
+    let sign = if negated { -1 } else { 1 };
+    match intervals.data_type() {
+        DataType::Interval(IntervalUnit::YearMonth) => add_each(
+            timestamps,
+            downcast_temporal!(intervals, IntervalYearMonthArray),
+            |nanos, months| {
+                add_months(nanos_to_datetime(nanos), sign * months as i64)
+                    .and_then(datetime_to_nanos)
+            },
+        ),
+        DataType::Interval(IntervalUnit::DayTime) => add_each(
+            timestamps,
+            downcast_temporal!(intervals, IntervalDayTimeArray),
+            |nanos, interval| add_day_time(nanos, interval, sign),
+        ),
+        other => Err(DataFusionError::Internal(format!(
+            "Cannot add {:?} to timestamps",
+            other
+        ))),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -379,6 +695,136 @@ mod tests {
         Ok(())
     }
 
+    fn timestamps(values: Vec<&str>) -> Result<ArrayRef> {
+        let values = values
+            .into_iter()
+            .map(|value| string_to_timestamp_nanos(value).map(Some))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Arc::new(TimestampNanosecondArray::from_opt_vec(
+            values, None,
+        )))
+    }
+
+    fn strings(value: &str, len: usize) -> ArrayRef {
+        Arc::new(StringArray::from(vec![value; len]))
+    }
+
+    #[test]
+    fn date_trunc_granularities() -> Result<()> {
+        let input = timestamps(vec!["2020-09-08T13:42:29.190855Z"])?;
+        let cases = vec![
+            ("second", "2020-09-08T13:42:29Z"),
+            ("minute", "2020-09-08T13:42:00Z"),
+            ("hour", "2020-09-08T13:00:00Z"),
+            ("day", "2020-09-08T00:00:00Z"),
+            ("week", "2020-09-07T00:00:00Z"),
+            ("month", "2020-09-01T00:00:00Z"),
+            ("QUARTER", "2020-07-01T00:00:00Z"),
+            ("year", "2020-01-01T00:00:00Z"),
+        ];
+        for (granularity, expected) in cases {
+            let result = date_trunc(&[strings(granularity, 1), input.clone()])?;
+            assert_eq!(
+                result.value(0),
+                string_to_timestamp_nanos(expected)?,
+                "{}",
+                granularity
+            );
+        }
+
+        let e = date_trunc(&[strings("century", 1), input]).unwrap_err();
+        assert!(e.to_string().contains("Unsupported date_trunc granularity"));
+        Ok(())
+    }
+
+    #[test]
+    fn date_part_fields() -> Result<()> {
+        let input = timestamps(vec!["2020-09-08T13:42:29Z", "2021-01-03T00:00:00Z"])?;
+        let cases = vec![
+            ("year", [2020, 2021]),
+            ("quarter", [3, 1]),
+            ("month", [9, 1]),
+            ("week", [37, 53]),
+            ("day", [8, 3]),
+            ("dow", [2, 0]),
+            ("doy", [252, 3]),
+            ("hour", [13, 0]),
+            ("minute", [42, 0]),
+            ("second", [29, 0]),
+        ];
+        for (field, expected) in cases {
+            let result = date_part(&[strings(field, 2), input.clone()])?;
+            let result = result.as_any().downcast_ref::<Int32Array>().unwrap();
+            assert_eq!([result.value(0), result.value(1)], expected, "{}", field);
+        }
+
+        // dates
+        let dates: ArrayRef = Arc::new(to_date(&[strings("2020-02-29", 1)])?);
+        let result = date_part(&[strings("day", 1), dates])?;
+        let result = result.as_any().downcast_ref::<Int32Array>().unwrap();
+        assert_eq!(result.value(0), 29);
+
+        // the field of every row must be the same
+        let fields: ArrayRef = Arc::new(StringArray::from(vec!["year", "day"]));
+        assert!(date_part(&[fields, input]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn to_date_strings() -> Result<()> {
+        let input: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("1970-01-02"),
+            None,
+            Some("1969-12-31"),
+        ]));
+        let result = to_date(&[input])?;
+        assert_eq!(result.value(0), 1);
+        assert!(result.is_null(1));
+        assert_eq!(result.value(2), -1);
+
+        assert!(to_date(&[strings("2020-13-01", 1)]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn add_intervals_to_timestamps() -> Result<()> {
+        let input = timestamps(vec!["2020-01-31T10:00:00Z", "2020-03-01T00:00:00Z"])?;
+
+        // 1 month
+        let months: ArrayRef = Arc::new(IntervalYearMonthArray::from(vec![1, 1]));
+        let result = add_intervals(&input, &months, false)?;
+        assert_eq!(
+            result.value(0),
+            string_to_timestamp_nanos("2020-02-29T10:00:00Z")?
+        );
+        assert_eq!(
+            result.value(1),
+            string_to_timestamp_nanos("2020-04-01T00:00:00Z")?
+        );
+        let result = add_intervals(&input, &months, true)?;
+        assert_eq!(
+            result.value(0),
+            string_to_timestamp_nanos("2019-12-31T10:00:00Z")?
+        );
+
+        // 1 day and 1 hour
+        let day_time = 1_i64 << 32 | 3_600_000;
+        let days: ArrayRef =
+            Arc::new(IntervalDayTimeArray::from(vec![Some(day_time), None]));
+        let result = add_intervals(&input, &days, false)?;
+        assert_eq!(
+            result.value(0),
+            string_to_timestamp_nanos("2020-02-01T11:00:00Z")?
+        );
+        assert!(result.is_null(1));
+        let result = add_intervals(&input, &days, true)?;
+        assert_eq!(
+            result.value(0),
+            string_to_timestamp_nanos("2020-01-30T09:00:00Z")?
+        );
+        Ok(())
+    }
+
     #[test]
     fn to_timestamp_invalid_input_type() -> Result<()> {
         // pass the wrong type of input array to to_timestamp and test
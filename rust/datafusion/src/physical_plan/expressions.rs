@@ -17,21 +17,33 @@
 
 //! Defines physical expressions that can evaluated at runtime during query execution
 
+use std::any::Any;
+use std::collections::HashSet;
 use std::convert::TryFrom;
 use std::fmt;
 use std::sync::Arc;
 
+use super::datetime_expressions;
+use super::grouped_accumulators;
 use super::ColumnarValue;
 use crate::error::{DataFusionError, Result};
 use crate::logical_plan::Operator;
-use crate::physical_plan::{Accumulator, AggregateExpr, PhysicalExpr};
+use crate::physical_plan::{
+    Accumulator, AggregateExpr, GroupedAccumulator, PhysicalExpr,
+};
 use crate::scalar::ScalarValue;
 use arrow::array::{self, Array, BooleanBuilder, LargeStringArray};
 use arrow::compute;
 use arrow::compute::kernels;
-use arrow::compute::kernels::arithmetic::{add, divide, multiply, subtract};
+use arrow::compute::kernels::arithmetic::{
+    add, add_decimal, add_decimal_type, divide, divide_decimal, divide_decimal_type,
+    multiply, multiply_decimal, multiply_decimal_type, subtract, subtract_decimal,
+};
 use arrow::compute::kernels::boolean::{and, nullif, or};
 use arrow::compute::kernels::comparison::{eq, gt, gt_eq, lt, lt_eq, neq};
+use arrow::compute::kernels::comparison::{
+    eq_decimal, gt_decimal, gt_eq_decimal, lt_decimal, lt_eq_decimal, neq_decimal,
+};
 use arrow::compute::kernels::comparison::{
     eq_scalar, gt_eq_scalar, gt_scalar, lt_eq_scalar, lt_scalar, neq_scalar,
 };
@@ -48,11 +60,12 @@ use arrow::datatypes::{DataType, DateUnit, Schema, TimeUnit};
 use arrow::record_batch::RecordBatch;
 use arrow::{
     array::{
-        ArrayRef, BooleanArray, Date32Array, Float32Array, Float64Array, Int16Array,
-        Int32Array, Int64Array, Int8Array, StringArray, TimestampNanosecondArray,
-        UInt16Array, UInt32Array, UInt64Array, UInt8Array,
+        ArrayRef, BooleanArray, Date32Array, DecimalArray, Float32Array, Float64Array,
+        Int16Array, Int32Array, Int64Array, Int8Array, StringArray,
+        TimestampNanosecondArray, UInt16Array, UInt32Array, UInt64Array, UInt8Array,
     },
     datatypes::Field,
+    util::decimal::{self, MAX_DECIMAL_PRECISION},
 };
 use compute::can_cast_types;
 
@@ -74,6 +87,11 @@ impl Column {
             name: name.to_owned(),
         }
     }
+
+    /// The name of the column
+    pub fn name(&self) -> &str {
+        &self.name
+    }
 }
 
 impl fmt::Display for Column {
@@ -83,6 +101,11 @@ impl fmt::Display for Column {
 }
 
 impl PhysicalExpr for Column {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     /// Get the data type of this expression, given the schema of the input
     fn data_type(&self, input_schema: &Schema) -> Result<DataType> {
         Ok(input_schema
@@ -129,6 +152,11 @@ pub fn sum_return_type(arg_type: &DataType) -> Result<DataType> {
         }
         DataType::Float32 => Ok(DataType::Float32),
         DataType::Float64 => Ok(DataType::Float64),
+        // like Spark, the sum has ten more integer digits
+        DataType::Decimal(precision, scale) => Ok(DataType::Decimal(
+            MAX_DECIMAL_PRECISION.min(precision + 10),
+            *scale,
+        )),
         other => Err(DataFusionError::Plan(format!(
             "SUM does not support type \"{:?}\"",
             other
@@ -172,11 +200,19 @@ impl AggregateExpr for Sum {
     fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
         Ok(Box::new(SumAccumulator::try_new(&self.data_type)?))
     }
+
+    fn create_grouped_accumulator(&self) -> Result<Option<Box<dyn GroupedAccumulator>>> {
+        Ok(grouped_accumulators::sum_grouped_accumulator(
+            &self.data_type,
+        ))
+    }
 }
 
 #[derive(Debug)]
 struct SumAccumulator {
     sum: ScalarValue,
+    // the number of non-null values, so that retracting all of them makes the sum null
+    count: u64,
 }
 
 impl SumAccumulator {
@@ -184,6 +220,7 @@ impl SumAccumulator {
     pub fn try_new(data_type: &DataType) -> Result<Self> {
         Ok(Self {
             sum: ScalarValue::try_from(data_type)?,
+            count: 0,
         })
     }
 }
@@ -210,6 +247,11 @@ fn sum_batch(values: &ArrayRef) -> Result<ScalarValue> {
         DataType::UInt32 => typed_sum_delta_batch!(values, UInt32Array, UInt32),
         DataType::UInt16 => typed_sum_delta_batch!(values, UInt16Array, UInt16),
         DataType::UInt8 => typed_sum_delta_batch!(values, UInt8Array, UInt8),
+        DataType::Decimal(_, scale) => {
+            let array = values.as_any().downcast_ref::<DecimalArray>().unwrap();
+            let delta = compute::sum_decimal(array)?;
+            ScalarValue::Decimal128(delta, MAX_DECIMAL_PRECISION, *scale)
+        }
         e => {
             return Err(DataFusionError::Internal(format!(
                 "Sum is not expected to receive the type {:?}",
@@ -219,6 +261,36 @@ fn sum_batch(values: &ArrayRef) -> Result<ScalarValue> {
     })
 }
 
+// returns `lhs - rhs`, where `rhs` is a sum of values that were added to the sum `lhs`
+// and has its type.
+fn sum_difference(lhs: &ScalarValue, rhs: &ScalarValue) -> Result<ScalarValue> {
+    Ok(match (lhs, rhs) {
+        (_, rhs) if rhs.is_null() => lhs.clone(),
+        (ScalarValue::Float64(Some(lhs)), ScalarValue::Float64(Some(rhs))) => {
+            ScalarValue::Float64(Some(lhs - rhs))
+        }
+        (ScalarValue::Float32(Some(lhs)), ScalarValue::Float32(Some(rhs))) => {
+            ScalarValue::Float32(Some(lhs - rhs))
+        }
+        (ScalarValue::UInt64(Some(lhs)), ScalarValue::UInt64(Some(rhs))) => {
+            ScalarValue::UInt64(Some(lhs - rhs))
+        }
+        (ScalarValue::Int64(Some(lhs)), ScalarValue::Int64(Some(rhs))) => {
+            ScalarValue::Int64(Some(lhs - rhs))
+        }
+        (
+            ScalarValue::Decimal128(Some(lhs), precision, scale),
+            ScalarValue::Decimal128(Some(rhs), _, _),
+        ) => ScalarValue::Decimal128(Some(lhs - rhs), *precision, *scale),
+        e => {
+            return Err(DataFusionError::Internal(format!(
+                "Sum is not expected to retract a scalar {:?}",
+                e
+            )))
+        }
+    })
+}
+
 // returns the sum of two scalar values, including coercion into $TYPE.
 macro_rules! typed_sum {
     ($OLD_VALUE:expr, $DELTA:expr, $SCALAR:ident, $TYPE:ident) => {{
@@ -294,6 +366,29 @@ fn sum(lhs: &ScalarValue, rhs: &ScalarValue) -> Result<ScalarValue> {
         (ScalarValue::Int64(lhs), ScalarValue::Int8(rhs)) => {
             typed_sum!(lhs, rhs, Int64, i64)
         }
+        // decimals are summed in the precision and scale of the sum
+        (
+            ScalarValue::Decimal128(lhs, precision, scale),
+            ScalarValue::Decimal128(rhs, _, rhs_scale),
+        ) => {
+            let rhs = match rhs {
+                Some(rhs) => Some(decimal::rescale(*rhs, *rhs_scale, *scale)),
+                None => None,
+            };
+            let sum = match (lhs, rhs) {
+                (_, None) => *lhs,
+                (None, Some(rhs)) => rhs,
+                (Some(lhs), Some(rhs)) => rhs.and_then(|rhs| lhs.checked_add(rhs)),
+            }
+            .filter(|sum| decimal::fits_precision(*sum, *precision));
+            if sum.is_none() && (lhs.is_some() || rhs.is_some()) {
+                return Err(DataFusionError::Execution(format!(
+                    "The sum overflows the type {:?}",
+                    DataType::Decimal(*precision, *scale)
+                )));
+            }
+            ScalarValue::Decimal128(sum, *precision, *scale)
+        }
         e => {
             return Err(DataFusionError::Internal(format!(
                 "Sum is not expected to receive a scalar {:?}",
@@ -306,16 +401,35 @@ fn sum(lhs: &ScalarValue, rhs: &ScalarValue) -> Result<ScalarValue> {
 impl Accumulator for SumAccumulator {
     fn update_batch(&mut self, values: &Vec<ArrayRef>) -> Result<()> {
         let values = &values[0];
+        self.count += (values.len() - values.data().null_count()) as u64;
         self.sum = sum(&self.sum, &sum_batch(values)?)?;
         Ok(())
     }
 
     fn update(&mut self, values: &Vec<ScalarValue>) -> Result<()> {
         // sum(v1, v2, v3) = v1 + v2 + v3
+        self.count += (!values[0].is_null()) as u64;
         self.sum = sum(&self.sum, &values[0])?;
         Ok(())
     }
 
+    fn supports_retract(&self) -> bool {
+        true
+    }
+
+    fn retract_batch(&mut self, values: &Vec<ArrayRef>) -> Result<()> {
+        let values = &values[0];
+        self.count -= (values.len() - values.data().null_count()) as u64;
+        let null = ScalarValue::try_from(&self.sum.get_datatype())?;
+        self.sum = if self.count == 0 {
+            null
+        } else {
+            // the sum of the values is coerced into the type of the sum
+            sum_difference(&self.sum, &sum(&null, &sum_batch(values)?)?)?
+        };
+        Ok(())
+    }
+
     fn merge(&mut self, states: &Vec<ScalarValue>) -> Result<()> {
         // sum(sum1, sum2) = sum1 + sum2
         self.update(states)
@@ -357,6 +471,11 @@ pub fn avg_return_type(arg_type: &DataType) -> Result<DataType> {
         | DataType::UInt64
         | DataType::Float32
         | DataType::Float64 => Ok(DataType::Float64),
+        // like Spark, the average has four more fractional digits
+        DataType::Decimal(precision, scale) => Ok(DataType::Decimal(
+            MAX_DECIMAL_PRECISION.min(precision + 4),
+            MAX_DECIMAL_PRECISION.min(scale + 4),
+        )),
         other => Err(DataFusionError::Plan(format!(
             "AVG does not support {:?}",
             other
@@ -364,6 +483,15 @@ pub fn avg_return_type(arg_type: &DataType) -> Result<DataType> {
     }
 }
 
+/// The type of the sum of an average of type `data_type`, which for decimals has the
+/// scale of the average
+fn avg_sum_type(data_type: &DataType) -> DataType {
+    match data_type {
+        DataType::Decimal(_, scale) => DataType::Decimal(MAX_DECIMAL_PRECISION, *scale),
+        other => other.clone(),
+    }
+}
+
 impl Avg {
     /// Create a new AVG aggregate function
     pub fn new(expr: Arc<dyn PhysicalExpr>, name: String, data_type: DataType) -> Self {
@@ -378,7 +506,7 @@ impl Avg {
 
 impl AggregateExpr for Avg {
     fn field(&self) -> Result<Field> {
-        Ok(Field::new(&self.name, DataType::Float64, true))
+        Ok(Field::new(&self.name, self.data_type.clone(), true))
     }
 
     fn state_fields(&self) -> Result<Vec<Field>> {
@@ -390,17 +518,22 @@ impl AggregateExpr for Avg {
             ),
             Field::new(
                 &format_state_name(&self.name, "sum"),
-                DataType::Float64,
+                avg_sum_type(&self.data_type),
                 true,
             ),
         ])
     }
 
     fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
-        Ok(Box::new(AvgAccumulator::try_new(
-            // avg is f64
-            &DataType::Float64,
-        )?))
+        Ok(Box::new(AvgAccumulator::try_new(&self.data_type)?))
+    }
+
+    fn create_grouped_accumulator(&self) -> Result<Option<Box<dyn GroupedAccumulator>>> {
+        // the native grouped accumulator computes averages of f64
+        Ok(match self.data_type {
+            DataType::Float64 => Some(grouped_accumulators::avg_grouped_accumulator()),
+            _ => None,
+        })
     }
 
     fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
@@ -414,13 +547,17 @@ pub(crate) struct AvgAccumulator {
     // sum is used for null
     sum: ScalarValue,
     count: u64,
+    // the type of the average
+    data_type: DataType,
 }
 
 impl AvgAccumulator {
+    /// Creates an accumulator of an average of type `datatype`
     pub fn try_new(datatype: &DataType) -> Result<Self> {
         Ok(Self {
-            sum: ScalarValue::try_from(datatype)?,
+            sum: ScalarValue::try_from(&avg_sum_type(datatype))?,
             count: 0,
+            data_type: datatype.clone(),
         })
     }
 }
@@ -447,6 +584,23 @@ impl Accumulator for AvgAccumulator {
         Ok(())
     }
 
+    fn supports_retract(&self) -> bool {
+        true
+    }
+
+    fn retract_batch(&mut self, values: &Vec<ArrayRef>) -> Result<()> {
+        let values = &values[0];
+        self.count -= (values.len() - values.data().null_count()) as u64;
+        let null = ScalarValue::try_from(&self.sum.get_datatype())?;
+        self.sum = if self.count == 0 {
+            null
+        } else {
+            // the sum of the values is coerced into the type of the sum
+            sum_difference(&self.sum, &sum(&null, &sum_batch(values)?)?)?
+        };
+        Ok(())
+    }
+
     fn merge(&mut self, states: &Vec<ScalarValue>) -> Result<()> {
         let count = &states[0];
         // counts are summed
@@ -472,13 +626,21 @@ impl Accumulator for AvgAccumulator {
     }
 
     fn evaluate(&self) -> Result<ScalarValue> {
-        match self.sum {
-            ScalarValue::Float64(e) => Ok(ScalarValue::Float64(match e {
+        match (&self.sum, &self.data_type) {
+            (ScalarValue::Float64(e), _) => Ok(ScalarValue::Float64(match e {
                 Some(f) => Some(f / self.count as f64),
                 None => None,
             })),
+            // the sum has the scale of the average
+            (ScalarValue::Decimal128(e, _, _), DataType::Decimal(precision, scale)) => {
+                Ok(ScalarValue::Decimal128(
+                    e.map(|sum| decimal::div_round(sum, self.count as i128)),
+                    *precision,
+                    *scale,
+                ))
+            }
             _ => Err(DataFusionError::Internal(
-                "Sum should be f64 on average".to_string(),
+                "Sum should be f64 or decimal on average".to_string(),
             )),
         }
     }
@@ -529,6 +691,12 @@ impl AggregateExpr for Max {
     fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
         Ok(Box::new(MaxAccumulator::try_new(&self.data_type)?))
     }
+
+    fn create_grouped_accumulator(&self) -> Result<Option<Box<dyn GroupedAccumulator>>> {
+        Ok(grouped_accumulators::max_grouped_accumulator(
+            &self.data_type,
+        ))
+    }
 }
 
 // Statically-typed version of min/max(array) -> ScalarValue for string types.
@@ -541,6 +709,15 @@ macro_rules! typed_min_max_batch_string {
     }};
 }
 
+// Statically-typed version of min/max(array) -> ScalarValue for decimals.
+macro_rules! typed_min_max_batch_decimal {
+    ($VALUES:expr, $PRECISION:expr, $SCALE:expr, $OP:ident) => {{
+        let array = $VALUES.as_any().downcast_ref::<DecimalArray>().unwrap();
+        let value = compute::$OP(array);
+        ScalarValue::Decimal128(value, *$PRECISION, *$SCALE)
+    }};
+}
+
 // Statically-typed version of min/max(array) -> ScalarValue for non-string types.
 macro_rules! typed_min_max_batch {
     ($VALUES:expr, $ARRAYTYPE:ident, $SCALAR:ident, $OP:ident) => {{
@@ -590,6 +767,9 @@ fn min_batch(values: &ArrayRef) -> Result<ScalarValue> {
         DataType::LargeUtf8 => {
             typed_min_max_batch_string!(values, LargeStringArray, LargeUtf8, min_string)
         }
+        DataType::Decimal(precision, scale) => {
+            typed_min_max_batch_decimal!(values, precision, scale, min_decimal)
+        }
         _ => min_max_batch!(values, min),
     })
 }
@@ -603,6 +783,9 @@ fn max_batch(values: &ArrayRef) -> Result<ScalarValue> {
         DataType::LargeUtf8 => {
             typed_min_max_batch_string!(values, LargeStringArray, LargeUtf8, max_string)
         }
+        DataType::Decimal(precision, scale) => {
+            typed_min_max_batch_decimal!(values, precision, scale, max_decimal)
+        }
         _ => min_max_batch!(values, max),
     })
 }
@@ -671,6 +854,18 @@ macro_rules! min_max {
             (ScalarValue::LargeUtf8(lhs), ScalarValue::LargeUtf8(rhs)) => {
                 typed_min_max_string!(lhs, rhs, LargeUtf8, $OP)
             }
+            (
+                ScalarValue::Decimal128(lhs, precision, scale),
+                ScalarValue::Decimal128(rhs, _, rhs_scale),
+            ) if scale == rhs_scale => {
+                let value = match (lhs, rhs) {
+                    (None, None) => None,
+                    (Some(a), None) => Some(*a),
+                    (None, Some(b)) => Some(*b),
+                    (Some(a), Some(b)) => Some((*a).$OP(*b)),
+                };
+                ScalarValue::Decimal128(value, *precision, *scale)
+            }
             e => {
                 return Err(DataFusionError::Internal(format!(
                     "MIN/MAX is not expected to receive a scalar {:?}",
@@ -781,6 +976,12 @@ impl AggregateExpr for Min {
     fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
         Ok(Box::new(MinAccumulator::try_new(&self.data_type)?))
     }
+
+    fn create_grouped_accumulator(&self) -> Result<Option<Box<dyn GroupedAccumulator>>> {
+        Ok(grouped_accumulators::min_grouped_accumulator(
+            &self.data_type,
+        ))
+    }
 }
 
 #[derive(Debug)]
@@ -874,6 +1075,10 @@ impl AggregateExpr for Count {
     fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
         Ok(Box::new(CountAccumulator::new()))
     }
+
+    fn create_grouped_accumulator(&self) -> Result<Option<Box<dyn GroupedAccumulator>>> {
+        Ok(Some(grouped_accumulators::count_grouped_accumulator()))
+    }
 }
 
 #[derive(Debug)]
@@ -916,6 +1121,26 @@ impl Accumulator for CountAccumulator {
         self.update_from_option(&delta)
     }
 
+    fn supports_retract(&self) -> bool {
+        true
+    }
+
+    fn retract_batch(&mut self, values: &Vec<ArrayRef>) -> Result<()> {
+        let array = &values[0];
+        let delta = (array.len() - array.data().null_count()) as u64;
+        self.count = match &self.count {
+            ScalarValue::UInt64(count) => {
+                ScalarValue::UInt64(count.map(|count| count - delta))
+            }
+            _ => {
+                return Err(DataFusionError::Internal(
+                    "Count is always of type u64".to_string(),
+                ))
+            }
+        };
+        Ok(())
+    }
+
     fn update(&mut self, values: &Vec<ScalarValue>) -> Result<()> {
         let value = &values[0];
         self.count = match (&self.count, value.is_null()) {
@@ -956,6 +1181,462 @@ impl Accumulator for CountAccumulator {
     }
 }
 
+/// BOOL_AND aggregate expression
+/// Returns whether all the non-null values of the given expression are true.
+#[derive(Debug)]
+pub struct BoolAnd {
+    name: String,
+    expr: Arc<dyn PhysicalExpr>,
+}
+
+impl BoolAnd {
+    /// Create a new BOOL_AND aggregate function
+    pub fn new(expr: Arc<dyn PhysicalExpr>, name: String) -> Self {
+        Self { name, expr }
+    }
+}
+
+impl AggregateExpr for BoolAnd {
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, DataType::Boolean, true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(vec![Field::new(
+            &format_state_name(&self.name, "bool_and"),
+            DataType::Boolean,
+            true,
+        )])
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(BoolAccumulator::new(true)))
+    }
+}
+
+/// BOOL_OR aggregate expression
+/// Returns whether any of the non-null values of the given expression is true.
+#[derive(Debug)]
+pub struct BoolOr {
+    name: String,
+    expr: Arc<dyn PhysicalExpr>,
+}
+
+impl BoolOr {
+    /// Create a new BOOL_OR aggregate function
+    pub fn new(expr: Arc<dyn PhysicalExpr>, name: String) -> Self {
+        Self { name, expr }
+    }
+}
+
+impl AggregateExpr for BoolOr {
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, DataType::Boolean, true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(vec![Field::new(
+            &format_state_name(&self.name, "bool_or"),
+            DataType::Boolean,
+            true,
+        )])
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(BoolAccumulator::new(false)))
+    }
+}
+
+/// An accumulator of the conjunction or the disjunction of booleans, which is null until
+/// a non-null value is accumulated
+#[derive(Debug)]
+struct BoolAccumulator {
+    value: Option<bool>,
+    /// whether the values are combined with AND rather than OR
+    conjunction: bool,
+}
+
+impl BoolAccumulator {
+    fn new(conjunction: bool) -> Self {
+        Self {
+            value: None,
+            conjunction,
+        }
+    }
+
+    fn update_from_option(&mut self, value: Option<bool>) {
+        self.value = match (self.value, value) {
+            (Some(lhs), Some(rhs)) if self.conjunction => Some(lhs && rhs),
+            (Some(lhs), Some(rhs)) => Some(lhs || rhs),
+            (lhs, None) => lhs,
+            (None, rhs) => rhs,
+        }
+    }
+}
+
+impl Accumulator for BoolAccumulator {
+    fn update_batch(&mut self, values: &Vec<ArrayRef>) -> Result<()> {
+        let array = values[0]
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .ok_or_else(|| {
+                DataFusionError::Internal(
+                    "could not cast values to BooleanArray".to_string(),
+                )
+            })?;
+        for i in 0..array.len() {
+            if array.is_valid(i) {
+                self.update_from_option(Some(array.value(i)));
+            }
+        }
+        Ok(())
+    }
+
+    fn update(&mut self, values: &Vec<ScalarValue>) -> Result<()> {
+        match &values[0] {
+            ScalarValue::Boolean(value) => {
+                self.update_from_option(*value);
+                Ok(())
+            }
+            other => Err(DataFusionError::Internal(format!(
+                "Unexpected value {:?} of a boolean aggregate",
+                other
+            ))),
+        }
+    }
+
+    fn merge(&mut self, states: &Vec<ScalarValue>) -> Result<()> {
+        self.update(states)
+    }
+
+    fn merge_batch(&mut self, states: &Vec<ArrayRef>) -> Result<()> {
+        self.update_batch(states)
+    }
+
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        Ok(vec![ScalarValue::Boolean(self.value)])
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        Ok(ScalarValue::Boolean(self.value))
+    }
+}
+
+/// ARRAY_AGG aggregate expression
+/// Returns a list with all the values of the given expression, including its nulls.
+#[derive(Debug)]
+pub struct ArrayAgg {
+    name: String,
+    data_type: DataType,
+    expr: Arc<dyn PhysicalExpr>,
+}
+
+impl ArrayAgg {
+    /// Create a new ARRAY_AGG aggregate function of values of `data_type`
+    pub fn new(expr: Arc<dyn PhysicalExpr>, name: String, data_type: DataType) -> Self {
+        Self {
+            name,
+            data_type,
+            expr,
+        }
+    }
+}
+
+impl AggregateExpr for ArrayAgg {
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(
+            &self.name,
+            DataType::List(Box::new(Field::new("item", self.data_type.clone(), true))),
+            true,
+        ))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(vec![Field::new(
+            &format_state_name(&self.name, "array_agg"),
+            DataType::List(Box::new(Field::new("item", self.data_type.clone(), true))),
+            true,
+        )])
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(ArrayAggAccumulator {
+            values: vec![],
+            data_type: self.data_type.clone(),
+        }))
+    }
+}
+
+#[derive(Debug)]
+struct ArrayAggAccumulator {
+    values: Vec<ScalarValue>,
+    data_type: DataType,
+}
+
+impl Accumulator for ArrayAggAccumulator {
+    fn update(&mut self, values: &Vec<ScalarValue>) -> Result<()> {
+        self.values.push(values[0].clone());
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &Vec<ScalarValue>) -> Result<()> {
+        match &states[0] {
+            ScalarValue::List(Some(values), _) => {
+                self.values.extend(values.iter().cloned());
+                Ok(())
+            }
+            ScalarValue::List(None, _) => Ok(()),
+            other => Err(DataFusionError::Internal(format!(
+                "Unexpected state {:?} of ARRAY_AGG",
+                other
+            ))),
+        }
+    }
+
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        Ok(vec![self.evaluate()?])
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        // the aggregate of no rows is null rather than an empty list
+        let values = if self.values.is_empty() {
+            None
+        } else {
+            Some(self.values.clone())
+        };
+        Ok(ScalarValue::List(values, self.data_type.clone()))
+    }
+}
+
+/// STRING_AGG aggregate expression
+/// Returns the concatenation of the non-null values of the given expression, separated
+/// by a delimiter.
+#[derive(Debug)]
+pub struct StringAgg {
+    name: String,
+    delimiter: String,
+    expr: Arc<dyn PhysicalExpr>,
+}
+
+impl StringAgg {
+    /// Create a new STRING_AGG aggregate function, separating the values by `delimiter`
+    pub fn new(expr: Arc<dyn PhysicalExpr>, delimiter: String, name: String) -> Self {
+        Self {
+            name,
+            delimiter,
+            expr,
+        }
+    }
+}
+
+impl AggregateExpr for StringAgg {
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, DataType::Utf8, true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(vec![Field::new(
+            &format_state_name(&self.name, "string_agg"),
+            DataType::Utf8,
+            true,
+        )])
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(StringAggAccumulator {
+            value: None,
+            delimiter: self.delimiter.clone(),
+        }))
+    }
+}
+
+#[derive(Debug)]
+struct StringAggAccumulator {
+    value: Option<String>,
+    delimiter: String,
+}
+
+impl StringAggAccumulator {
+    fn append(&mut self, value: &str) {
+        match &mut self.value {
+            Some(current) => {
+                current.push_str(&self.delimiter);
+                current.push_str(value);
+            }
+            None => self.value = Some(value.to_string()),
+        }
+    }
+}
+
+impl Accumulator for StringAggAccumulator {
+    fn update_batch(&mut self, values: &Vec<ArrayRef>) -> Result<()> {
+        let array = values[0]
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .ok_or_else(|| {
+                DataFusionError::Internal(
+                    "could not cast values to StringArray".to_string(),
+                )
+            })?;
+        for i in 0..array.len() {
+            if array.is_valid(i) {
+                self.append(array.value(i));
+            }
+        }
+        Ok(())
+    }
+
+    fn update(&mut self, values: &Vec<ScalarValue>) -> Result<()> {
+        match &values[0] {
+            ScalarValue::Utf8(Some(value)) => self.append(value),
+            ScalarValue::Utf8(None) => {}
+            other => {
+                return Err(DataFusionError::Internal(format!(
+                    "Unexpected value {:?} of STRING_AGG",
+                    other
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &Vec<ScalarValue>) -> Result<()> {
+        // the state is the concatenation of the values of a partition
+        self.update(states)
+    }
+
+    fn merge_batch(&mut self, states: &Vec<ArrayRef>) -> Result<()> {
+        self.update_batch(states)
+    }
+
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        Ok(vec![ScalarValue::Utf8(self.value.clone())])
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        Ok(ScalarValue::Utf8(self.value.clone()))
+    }
+}
+
+/// SINGLE_VALUE aggregate expression
+/// Returns the value of the given expression for the only row, and fails when there is
+/// more than one row.
+#[derive(Debug)]
+pub struct SingleValue {
+    name: String,
+    data_type: DataType,
+    expr: Arc<dyn PhysicalExpr>,
+}
+
+impl SingleValue {
+    /// Create a new SINGLE_VALUE aggregate function of values of `data_type`
+    pub fn new(expr: Arc<dyn PhysicalExpr>, name: String, data_type: DataType) -> Self {
+        Self {
+            name,
+            data_type,
+            expr,
+        }
+    }
+}
+
+impl AggregateExpr for SingleValue {
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, self.data_type.clone(), true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(vec![
+            Field::new(
+                &format_state_name(&self.name, "value"),
+                self.data_type.clone(),
+                true,
+            ),
+            Field::new(
+                &format_state_name(&self.name, "count"),
+                DataType::UInt64,
+                true,
+            ),
+        ])
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(SingleValueAccumulator {
+            value: ScalarValue::try_from(&self.data_type)?,
+            count: 0,
+        }))
+    }
+}
+
+#[derive(Debug)]
+struct SingleValueAccumulator {
+    value: ScalarValue,
+    count: u64,
+}
+
+impl SingleValueAccumulator {
+    fn add(&mut self, value: &ScalarValue, count: u64) -> Result<()> {
+        if count == 0 {
+            return Ok(());
+        }
+        self.count += count;
+        if self.count > 1 {
+            return Err(DataFusionError::Execution(
+                "More than one row returned by a subquery used as an expression"
+                    .to_owned(),
+            ));
+        }
+        self.value = value.clone();
+        Ok(())
+    }
+}
+
+impl Accumulator for SingleValueAccumulator {
+    fn update(&mut self, values: &Vec<ScalarValue>) -> Result<()> {
+        self.add(&values[0], 1)
+    }
+
+    fn merge(&mut self, states: &Vec<ScalarValue>) -> Result<()> {
+        match &states[1] {
+            ScalarValue::UInt64(Some(count)) => self.add(&states[0], *count),
+            other => Err(DataFusionError::Internal(format!(
+                "Unexpected count {:?} of SINGLE_VALUE",
+                other
+            ))),
+        }
+    }
+
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        Ok(vec![
+            self.value.clone(),
+            ScalarValue::UInt64(Some(self.count)),
+        ])
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        Ok(self.value.clone())
+    }
+}
+
 /// Invoke a compute kernel on a pair of binary data arrays
 macro_rules! compute_utf8_op {
     ($LEFT:expr, $RIGHT:expr, $OP:ident, $DT:ident) => {{
@@ -1224,6 +1905,10 @@ fn string_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType>
 pub fn numerical_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType> {
     use arrow::datatypes::DataType::*;
 
+    if is_decimal(lhs_type) || is_decimal(rhs_type) {
+        return decimal_coercion(lhs_type, rhs_type);
+    }
+
     // error on any non-numeric type
     if !is_numeric(lhs_type) || !is_numeric(rhs_type) {
         return None;
@@ -1264,13 +1949,128 @@ pub fn numerical_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<Da
         (UInt16, _) => Some(UInt16),
         (_, UInt16) => Some(UInt16),
 
-        (UInt8, _) => Some(UInt8),
-        (_, UInt8) => Some(UInt8),
+        (UInt8, _) => Some(UInt8),
+        (_, UInt8) => Some(UInt8),
+
+        _ => None,
+    }
+}
+
+fn is_decimal(data_type: &DataType) -> bool {
+    match data_type {
+        DataType::Decimal(_, _) => true,
+        _ => false,
+    }
+}
+
+/// The decimal type that holds all the values of an integer type, if `data_type` is one
+fn integer_decimal_type(data_type: &DataType) -> Option<DataType> {
+    use arrow::datatypes::DataType::*;
+    let precision = match data_type {
+        Int8 | UInt8 => 3,
+        Int16 | UInt16 => 5,
+        Int32 | UInt32 => 10,
+        Int64 => 19,
+        UInt64 => 20,
+        _ => return None,
+    };
+    Some(Decimal(precision, 0))
+}
+
+/// The types that the arguments of a decimal computation are cast to, if one of them is
+/// a decimal and the other one a decimal or an integer: decimals keep their precision and
+/// scale, since the decimal kernels accept different ones, and integers become decimals
+fn decimal_operand_types(
+    lhs_type: &DataType,
+    rhs_type: &DataType,
+) -> Option<(DataType, DataType)> {
+    match (lhs_type, rhs_type) {
+        (DataType::Decimal(_, _), DataType::Decimal(_, _)) => {
+            Some((lhs_type.clone(), rhs_type.clone()))
+        }
+        (DataType::Decimal(_, _), _) => {
+            integer_decimal_type(rhs_type).map(|rhs| (lhs_type.clone(), rhs))
+        }
+        (_, DataType::Decimal(_, _)) => {
+            integer_decimal_type(lhs_type).map(|lhs| (lhs, rhs_type.clone()))
+        }
+        _ => None,
+    }
+}
+
+/// Coercion rule for decimals: a decimal and a float are coerced to a float, and a
+/// decimal and a decimal or an integer to a decimal with the integer digits and the
+/// fractional digits of both, within the maximum precision
+fn decimal_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType> {
+    use arrow::datatypes::DataType::*;
+    match (lhs_type, rhs_type) {
+        (Decimal(_, _), Float32) | (Decimal(_, _), Float64) => return Some(Float64),
+        (Float32, Decimal(_, _)) | (Float64, Decimal(_, _)) => return Some(Float64),
+        _ => {}
+    }
+    match decimal_operand_types(lhs_type, rhs_type)? {
+        (Decimal(p1, s1), Decimal(p2, s2)) => {
+            let scale = s1.max(s2);
+            let integer_digits = (p1 - s1).max(p2 - s2);
+            Some(Decimal(
+                MAX_DECIMAL_PRECISION.min(integer_digits + scale),
+                scale,
+            ))
+        }
+        _ => None,
+    }
+}
 
+/// The types that the arguments of `op` are cast to when it is computed by a decimal
+/// kernel, which are those of [decimal_operand_types] for arithmetic and comparisons
+fn decimal_operands(
+    lhs_type: &DataType,
+    op: &Operator,
+    rhs_type: &DataType,
+) -> Option<(DataType, DataType)> {
+    match op {
+        Operator::Plus
+        | Operator::Minus
+        | Operator::Multiply
+        | Operator::Divide
+        | Operator::Eq
+        | Operator::NotEq
+        | Operator::Lt
+        | Operator::LtEq
+        | Operator::Gt
+        | Operator::GtEq => decimal_operand_types(lhs_type, rhs_type),
         _ => None,
     }
 }
 
+/// Evaluates `op` on two decimal arrays, which may have different precisions and scales
+fn decimal_binary_op(
+    left: &ArrayRef,
+    op: &Operator,
+    right: &ArrayRef,
+) -> Result<ArrayRef> {
+    let left = left.as_any().downcast_ref::<DecimalArray>().unwrap();
+    let right = right.as_any().downcast_ref::<DecimalArray>().unwrap();
+    Ok(match op {
+        Operator::Plus => Arc::new(add_decimal(left, right)?),
+        Operator::Minus => Arc::new(subtract_decimal(left, right)?),
+        Operator::Multiply => Arc::new(multiply_decimal(left, right)?),
+        Operator::Divide => Arc::new(divide_decimal(left, right)?),
+        Operator::Eq => Arc::new(eq_decimal(left, right)?),
+        Operator::NotEq => Arc::new(neq_decimal(left, right)?),
+        Operator::Lt => Arc::new(lt_decimal(left, right)?),
+        Operator::LtEq => Arc::new(lt_eq_decimal(left, right)?),
+        Operator::Gt => Arc::new(gt_decimal(left, right)?),
+        Operator::GtEq => Arc::new(gt_eq_decimal(left, right)?),
+        other => {
+            return Err(DataFusionError::Internal(format!(
+                "Cannot evaluate binary expression {:?} on decimals",
+                other
+            )))
+        }
+    })
+}
+
 // coercion rules for equality operations. This is a superset of all numerical coercion rules.
 fn eq_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType> {
     if lhs_type == rhs_type {
@@ -1349,6 +2149,20 @@ pub fn binary_operator_data_type(
     op: &Operator,
     rhs_type: &DataType,
 ) -> Result<DataType> {
+    if is_interval_arithmetic(lhs_type, op, rhs_type) {
+        return Ok(DataType::Timestamp(TimeUnit::Nanosecond, None));
+    }
+
+    if let Some((lhs_type, rhs_type)) = decimal_operands(lhs_type, op, rhs_type) {
+        // the precision and scale of decimal arithmetic depend on those of both arguments
+        return Ok(match op {
+            Operator::Plus | Operator::Minus => add_decimal_type(&lhs_type, &rhs_type)?,
+            Operator::Multiply => multiply_decimal_type(&lhs_type, &rhs_type)?,
+            Operator::Divide => divide_decimal_type(&lhs_type, &rhs_type)?,
+            _ => DataType::Boolean,
+        });
+    }
+
     // validate that it is possible to perform the operation on incoming types.
     // (or the return datatype cannot be infered)
     let common_type = common_binary_type(lhs_type, op, rhs_type)?;
@@ -1375,6 +2189,21 @@ pub fn binary_operator_data_type(
     }
 }
 
+/// Whether the binary operator adds an interval to, or subtracts it from, a timestamp,
+/// which is computed without coercing both arguments to a common type
+fn is_interval_arithmetic(
+    lhs_type: &DataType,
+    op: &Operator,
+    rhs_type: &DataType,
+) -> bool {
+    match (lhs_type, rhs_type) {
+        (DataType::Timestamp(_, None), DataType::Interval(_)) => {
+            *op == Operator::Plus || *op == Operator::Minus
+        }
+        _ => false,
+    }
+}
+
 /// return two physical expressions that are optionally coerced to a
 /// common type that the binary operator supports.
 fn binary_cast(
@@ -1386,6 +2215,19 @@ fn binary_cast(
     let lhs_type = &lhs.data_type(input_schema)?;
     let rhs_type = &rhs.data_type(input_schema)?;
 
+    if is_interval_arithmetic(lhs_type, op, rhs_type) {
+        // intervals are added to timestamps of nanoseconds
+        let timestamp_type = DataType::Timestamp(TimeUnit::Nanosecond, None);
+        return Ok((cast(lhs, input_schema, timestamp_type)?, rhs));
+    }
+
+    if let Some((lhs_type, rhs_type)) = decimal_operands(lhs_type, op, rhs_type) {
+        return Ok((
+            cast(lhs, input_schema, lhs_type)?,
+            cast(rhs, input_schema, rhs_type)?,
+        ));
+    }
+
     let cast_type = common_binary_type(lhs_type, op, rhs_type)?;
 
     Ok((
@@ -1395,6 +2237,11 @@ fn binary_cast(
 }
 
 impl PhysicalExpr for BinaryExpr {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn data_type(&self, input_schema: &Schema) -> Result<DataType> {
         binary_operator_data_type(
             &self.left.data_type(input_schema)?,
@@ -1413,6 +2260,23 @@ impl PhysicalExpr for BinaryExpr {
         let left_data_type = left_value.data_type();
         let right_data_type = right_value.data_type();
 
+        if is_interval_arithmetic(&left_data_type, &self.op, &right_data_type) {
+            let num_rows = batch.num_rows();
+            let timestamps = left_value.into_array(num_rows);
+            let intervals = right_value.into_array(num_rows);
+            let negated = self.op == Operator::Minus;
+            return Ok(ColumnarValue::Array(Arc::new(
+                datetime_expressions::add_intervals(&timestamps, &intervals, negated)?,
+            )));
+        }
+
+        if is_decimal(&left_data_type) && is_decimal(&right_data_type) {
+            let num_rows = batch.num_rows();
+            let left = left_value.into_array(num_rows);
+            let right = right_value.into_array(num_rows);
+            return decimal_binary_op(&left, &self.op, &right).map(ColumnarValue::Array);
+        }
+
         if left_data_type != right_data_type {
             return Err(DataFusionError::Internal(format!(
                 "Cannot evaluate binary expression {:?} with types {:?} and {:?}",
@@ -1628,6 +2492,11 @@ impl fmt::Display for NotExpr {
 }
 
 impl PhysicalExpr for NotExpr {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
         return Ok(DataType::Boolean);
     }
@@ -1701,6 +2570,11 @@ impl fmt::Display for IsNullExpr {
     }
 }
 impl PhysicalExpr for IsNullExpr {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
         return Ok(DataType::Boolean);
     }
@@ -1746,6 +2620,11 @@ impl fmt::Display for IsNotNullExpr {
     }
 }
 impl PhysicalExpr for IsNotNullExpr {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
         return Ok(DataType::Boolean);
     }
@@ -1772,6 +2651,268 @@ pub fn is_not_null(arg: Arc<dyn PhysicalExpr>) -> Result<Arc<dyn PhysicalExpr>>
     Ok(Arc::new(IsNotNullExpr::new(arg)))
 }
 
+/// The minimum number of literals of an IN list for which the expression is evaluated by
+/// looking up the values in a hash set instead of comparing them with every literal.
+pub const IN_LIST_HASH_THRESHOLD: usize = 8;
+
+/// IN list expression, such as "state IN ('CO', 'NY')"
+#[derive(Debug)]
+pub struct InListExpr {
+    expr: Arc<dyn PhysicalExpr>,
+    list: Vec<Arc<dyn PhysicalExpr>>,
+    negated: bool,
+}
+
+impl InListExpr {
+    /// Create a new IN list expression. `expr` and `list` are expected to share a type.
+    pub fn new(
+        expr: Arc<dyn PhysicalExpr>,
+        list: Vec<Arc<dyn PhysicalExpr>>,
+        negated: bool,
+    ) -> Self {
+        Self {
+            expr,
+            list,
+            negated,
+        }
+    }
+}
+
+impl fmt::Display for InListExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list: Vec<String> = self.list.iter().map(|e| format!("{}", e)).collect();
+        let not = if self.negated { "NOT " } else { "" };
+        write!(f, "{} {}IN ({})", self.expr, not, list.join(", "))
+    }
+}
+
+/// Looks up the values of a primitive array in the set of the non-null literals of a list
+macro_rules! in_list_primitive_set {
+    ($VALUE:expr, $LIST:expr, $ARRAY_TYPE:ident, $SCALAR:ident) => {{
+        let array = $VALUE.as_any().downcast_ref::<$ARRAY_TYPE>().unwrap();
+        let mut set = HashSet::with_capacity($LIST.len());
+        let mut has_null = false;
+        for value in $LIST {
+            match value {
+                ScalarValue::$SCALAR(Some(v)) => {
+                    set.insert(*v);
+                }
+                ScalarValue::$SCALAR(None) => has_null = true,
+                other => {
+                    return Err(DataFusionError::Internal(format!(
+                        "The IN list contains {:?}, which is not of type {:?}",
+                        other,
+                        array.data_type()
+                    )))
+                }
+            }
+        }
+        (0..array.len())
+            .map(|i| {
+                in_list_result(
+                    array.is_null(i),
+                    || set.contains(&array.value(i)),
+                    has_null,
+                )
+            })
+            .collect::<Vec<_>>()
+    }};
+}
+
+/// The result of a single row of an IN list given whether the value is null, whether it is
+/// found in the list and whether the list contains nulls, following SQL semantics.
+fn in_list_result<F: FnOnce() -> bool>(
+    is_null: bool,
+    found: F,
+    has_null: bool,
+) -> Option<bool> {
+    if is_null {
+        None
+    } else if found() {
+        Some(true)
+    } else if has_null {
+        None
+    } else {
+        Some(false)
+    }
+}
+
+/// Evaluates an IN list of literals by looking up every value in a hash set.
+/// Returns `None` when the type of the values is not supported.
+fn in_list_set(
+    value: &ArrayRef,
+    list: &[ScalarValue],
+) -> Result<Option<Vec<Option<bool>>>> {
+    let result = match value.data_type() {
+        DataType::Int8 => in_list_primitive_set!(value, list, Int8Array, Int8),
+        DataType::Int16 => in_list_primitive_set!(value, list, Int16Array, Int16),
+        DataType::Int32 => in_list_primitive_set!(value, list, Int32Array, Int32),
+        DataType::Int64 => in_list_primitive_set!(value, list, Int64Array, Int64),
+        DataType::UInt8 => in_list_primitive_set!(value, list, UInt8Array, UInt8),
+        DataType::UInt16 => in_list_primitive_set!(value, list, UInt16Array, UInt16),
+        DataType::UInt32 => in_list_primitive_set!(value, list, UInt32Array, UInt32),
+        DataType::UInt64 => in_list_primitive_set!(value, list, UInt64Array, UInt64),
+        DataType::Date32(DateUnit::Day) => {
+            in_list_primitive_set!(value, list, Date32Array, Date32)
+        }
+        DataType::Utf8 => {
+            let array = value.as_any().downcast_ref::<StringArray>().unwrap();
+            let mut set = HashSet::with_capacity(list.len());
+            let mut has_null = false;
+            for value in list {
+                match value {
+                    ScalarValue::Utf8(Some(v)) => {
+                        set.insert(v.as_str());
+                    }
+                    ScalarValue::Utf8(None) => has_null = true,
+                    other => {
+                        return Err(DataFusionError::Internal(format!(
+                            "The IN list contains {:?}, which is not of type Utf8",
+                            other
+                        )))
+                    }
+                }
+            }
+            (0..array.len())
+                .map(|i| {
+                    in_list_result(
+                        array.is_null(i),
+                        || set.contains(array.value(i)),
+                        has_null,
+                    )
+                })
+                .collect::<Vec<_>>()
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(result))
+}
+
+impl PhysicalExpr for InListExpr {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(DataType::Boolean)
+    }
+
+    fn nullable(&self, input_schema: &Schema) -> Result<bool> {
+        let mut nullable = self.expr.nullable(input_schema)?;
+        for e in &self.list {
+            nullable = nullable || e.nullable(input_schema)?;
+        }
+        Ok(nullable)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let num_rows = batch.num_rows();
+        let value = self.expr.evaluate(batch)?.into_array(num_rows);
+        let list = self
+            .list
+            .iter()
+            .map(|e| e.evaluate(batch))
+            .collect::<Result<Vec<_>>>()?;
+
+        let literals = list
+            .iter()
+            .map(|e| match e {
+                ColumnarValue::Scalar(scalar) => Some(scalar.clone()),
+                ColumnarValue::Array(_) => None,
+            })
+            .collect::<Option<Vec<_>>>();
+        let from_set = match literals {
+            Some(literals) if literals.len() >= IN_LIST_HASH_THRESHOLD => {
+                in_list_set(&value, &literals)?
+            }
+            _ => None,
+        };
+
+        let found = match from_set {
+            Some(found) => found,
+            None => {
+                // compare the values with every item of the list, combining the results
+                // with a three-valued OR
+                let mut found = vec![Some(false); num_rows];
+                for item in list {
+                    let item = item.into_array(num_rows);
+                    let equal: Result<ArrayRef> = binary_array_op!(value, item, eq);
+                    let equal = equal?;
+                    let equal = equal.as_any().downcast_ref::<BooleanArray>().unwrap();
+                    for (i, row) in found.iter_mut().enumerate() {
+                        *row = match (*row, equal.is_null(i)) {
+                            (Some(true), _) => Some(true),
+                            (_, false) if equal.value(i) => Some(true),
+                            (_, true) => None,
+                            (row, false) => row,
+                        };
+                    }
+                }
+                found
+            }
+        };
+
+        let result = if self.negated {
+            found.into_iter().map(|row| row.map(|b| !b)).collect()
+        } else {
+            found
+        };
+        Ok(ColumnarValue::Array(Arc::new(BooleanArray::from(result))))
+    }
+}
+
+/// Create an IN list expression whose expression and list items are coerced to a common type.
+/// This function errors if there is no such type.
+pub fn in_list(
+    expr: Arc<dyn PhysicalExpr>,
+    list: Vec<Arc<dyn PhysicalExpr>>,
+    negated: bool,
+    input_schema: &Schema,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    let expr_type = expr.data_type(input_schema)?;
+    let mut cast_type = expr_type.clone();
+    for e in &list {
+        let item_type = e.data_type(input_schema)?;
+        cast_type = eq_coercion(&cast_type, &item_type).ok_or_else(|| {
+            DataFusionError::Plan(format!(
+                "'{:?} IN (...)' can't be evaluated because the list contains a {:?}, \
+                 and there isn't a common type to coerce the types to",
+                expr_type, item_type
+            ))
+        })?;
+    }
+
+    let list = list
+        .into_iter()
+        .map(|e| cast(e, input_schema, cast_type.clone()))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Arc::new(InListExpr::new(
+        cast(expr, input_schema, cast_type)?,
+        list,
+        negated,
+    )))
+}
+
+/// Create a BETWEEN expression, which is evaluated as `expr >= low AND expr <= high`, or
+/// `expr < low OR expr > high` when `negated`.
+pub fn between(
+    expr: Arc<dyn PhysicalExpr>,
+    low: Arc<dyn PhysicalExpr>,
+    high: Arc<dyn PhysicalExpr>,
+    negated: bool,
+    input_schema: &Schema,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    let (low_op, high_op, op) = if negated {
+        (Operator::Lt, Operator::Gt, Operator::Or)
+    } else {
+        (Operator::GtEq, Operator::LtEq, Operator::And)
+    };
+    let low = binary(expr.clone(), low_op, low, input_schema)?;
+    let high = binary(expr, high_op, high, input_schema)?;
+    binary(low, op, high, input_schema)
+}
+
 /// The CASE expression is similar to a series of nested if/else and there are two forms that
 /// can be used. The first form consists of a series of boolean "when" expressions with
 /// corresponding "then" expressions, and an optional "else" expression.
@@ -2147,6 +3288,11 @@ impl CaseExpr {
 }
 
 impl PhysicalExpr for CaseExpr {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn data_type(&self, input_schema: &Schema) -> Result<DataType> {
         self.when_then_expr[0].1.data_type(input_schema)
     }
@@ -2206,6 +3352,11 @@ impl fmt::Display for CastExpr {
 }
 
 impl PhysicalExpr for CastExpr {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
         Ok(self.cast_type.clone())
     }
@@ -2264,6 +3415,11 @@ impl Literal {
     pub fn new(value: ScalarValue) -> Self {
         Self { value }
     }
+
+    /// The value of this literal
+    pub fn value(&self) -> &ScalarValue {
+        &self.value
+    }
 }
 
 impl fmt::Display for Literal {
@@ -2273,6 +3429,11 @@ impl fmt::Display for Literal {
 }
 
 impl PhysicalExpr for Literal {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
         Ok(self.value.get_datatype())
     }
@@ -2816,6 +3977,70 @@ mod tests {
         )
     }
 
+    #[test]
+    fn sum_decimal() -> Result<()> {
+        let a: ArrayRef = Arc::new(DecimalArray::from_opt_vec(
+            vec![Some(125), None, Some(250), Some(-75)],
+            10,
+            2,
+        ));
+        generic_test_op!(
+            a,
+            DataType::Decimal(10, 2),
+            Sum,
+            ScalarValue::Decimal128(Some(300), 20, 2),
+            DataType::Decimal(20, 2)
+        )
+    }
+
+    #[test]
+    fn avg_decimal() -> Result<()> {
+        let a: ArrayRef = Arc::new(DecimalArray::from_opt_vec(
+            vec![Some(125), Some(250), Some(-75)],
+            10,
+            2,
+        ));
+        generic_test_op!(
+            a,
+            DataType::Decimal(10, 2),
+            Avg,
+            ScalarValue::Decimal128(Some(1_000_000), 14, 6),
+            DataType::Decimal(14, 6)
+        )
+    }
+
+    #[test]
+    fn max_decimal() -> Result<()> {
+        let a: ArrayRef = Arc::new(DecimalArray::from_opt_vec(
+            vec![Some(125), None, Some(250), Some(-75)],
+            10,
+            2,
+        ));
+        generic_test_op!(
+            a,
+            DataType::Decimal(10, 2),
+            Max,
+            ScalarValue::Decimal128(Some(250), 10, 2),
+            DataType::Decimal(10, 2)
+        )
+    }
+
+    #[test]
+    fn min_decimal() -> Result<()> {
+        let a: ArrayRef = Arc::new(DecimalArray::from_opt_vec(
+            vec![Some(125), None, Some(250), Some(-75)],
+            10,
+            2,
+        ));
+        generic_test_op!(
+            a,
+            DataType::Decimal(10, 2),
+            Min,
+            ScalarValue::Decimal128(Some(-75), 10, 2),
+            DataType::Decimal(10, 2)
+        )
+    }
+
     #[test]
     fn max_utf8() -> Result<()> {
         let a: ArrayRef = Arc::new(StringArray::from(vec!["d", "a", "c", "b"]));
@@ -3322,6 +4547,63 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn decimal_op() -> Result<()> {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Decimal(10, 2), false),
+            Field::new("b", DataType::Int32, false),
+        ]);
+        let a = DecimalArray::from_opt_vec(vec![Some(125), Some(250), Some(-75)], 10, 2);
+        let b = Int32Array::from(vec![2, 0, 1]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema.clone()),
+            vec![Arc::new(a), Arc::new(b)],
+        )?;
+
+        // the integer is computed as a decimal of scale 0
+        let expression = binary(col("a"), Operator::Plus, col("b"), &schema)?;
+        assert_eq!(expression.data_type(&schema)?, DataType::Decimal(13, 2));
+        let result = expression.evaluate(&batch)?.into_array(batch.num_rows());
+        let result = result.as_any().downcast_ref::<DecimalArray>().unwrap();
+        assert_eq!(*result.data_type(), DataType::Decimal(13, 2));
+        assert_eq!(
+            (0..3).map(|i| result.value(i)).collect::<Vec<_>>(),
+            vec![325, 250, 25]
+        );
+
+        let expression = binary(col("a"), Operator::Multiply, col("b"), &schema)?;
+        assert_eq!(expression.data_type(&schema)?, DataType::Decimal(21, 2));
+        let result = expression.evaluate(&batch)?.into_array(batch.num_rows());
+        let result = result.as_any().downcast_ref::<DecimalArray>().unwrap();
+        assert_eq!(
+            (0..3).map(|i| result.value(i)).collect::<Vec<_>>(),
+            vec![250, 0, -75]
+        );
+
+        let expression = binary(col("a"), Operator::Gt, col("b"), &schema)?;
+        assert_eq!(expression.data_type(&schema)?, DataType::Boolean);
+        let result = expression.evaluate(&batch)?.into_array(batch.num_rows());
+        let result = result.as_any().downcast_ref::<BooleanArray>().unwrap();
+        assert_eq!(
+            (0..3).map(|i| result.value(i)).collect::<Vec<_>>(),
+            vec![false, true, false]
+        );
+
+        let expression = binary(col("a"), Operator::Divide, col("b"), &schema)?;
+        assert!(expression.evaluate(&batch).is_err());
+
+        // a decimal and a float are computed as floats
+        assert_eq!(
+            numerical_coercion(&DataType::Decimal(10, 2), &DataType::Float32),
+            Some(DataType::Float64)
+        );
+        assert_eq!(
+            numerical_coercion(&DataType::Decimal(10, 2), &DataType::Decimal(5, 4)),
+            Some(DataType::Decimal(12, 4))
+        );
+        Ok(())
+    }
+
     #[test]
     fn minus_op() -> Result<()> {
         let schema = Arc::new(Schema::new(vec![
@@ -3497,6 +4779,145 @@ mod tests {
         Ok(())
     }
 
+    fn evaluate_boolean(
+        expr: Arc<dyn PhysicalExpr>,
+        batch: &RecordBatch,
+    ) -> Result<Vec<Option<bool>>> {
+        let result = expr.evaluate(batch)?.into_array(batch.num_rows());
+        let result = result
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .expect("failed to downcast to BooleanArray");
+        Ok((0..result.len())
+            .map(|i| {
+                if result.is_null(i) {
+                    None
+                } else {
+                    Some(result.value(i))
+                }
+            })
+            .collect())
+    }
+
+    #[test]
+    fn in_list_op() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Utf8, true)]);
+        let a = StringArray::from(vec![Some("a"), Some("d"), None]);
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![Arc::new(a)])?;
+        let list = || {
+            vec![
+                lit(ScalarValue::Utf8(Some("a".to_string()))),
+                lit(ScalarValue::Utf8(Some("b".to_string()))),
+            ]
+        };
+
+        // expression: "a IN ('a', 'b')"
+        let expr = in_list(col("a"), list(), false, &schema)?;
+        assert_eq!(format!("{}", expr), "a IN (a, b)");
+        assert_eq!(expr.data_type(&schema)?, DataType::Boolean);
+        let result = evaluate_boolean(expr, &batch)?;
+        assert_eq!(result, vec![Some(true), Some(false), None]);
+
+        // expression: "a NOT IN ('a', 'b')"
+        let expr = in_list(col("a"), list(), true, &schema)?;
+        let result = evaluate_boolean(expr, &batch)?;
+        assert_eq!(result, vec![Some(false), Some(true), None]);
+
+        // expression: "a IN ('a', NULL)"
+        let expr = in_list(
+            col("a"),
+            vec![
+                lit(ScalarValue::Utf8(Some("a".to_string()))),
+                lit(ScalarValue::Utf8(None)),
+            ],
+            false,
+            &schema,
+        )?;
+        let result = evaluate_boolean(expr, &batch)?;
+        assert_eq!(result, vec![Some(true), None, None]);
+
+        Ok(())
+    }
+
+    /// verify that long lists, which are looked up in a hash set, return the same values
+    /// as short ones.
+    #[test]
+    fn in_list_hash_set() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int32, true)]);
+        let a = Int32Array::from(vec![Some(1), Some(2), Some(100), None]);
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![Arc::new(a)])?;
+
+        // the literals are Int64, which the column is coerced to
+        let mut list = (1..=IN_LIST_HASH_THRESHOLD as i64)
+            .map(|i| lit(ScalarValue::Int64(Some(i * 2))))
+            .collect::<Vec<_>>();
+        let expr = in_list(col("a"), list, false, &schema)?;
+        let result = evaluate_boolean(expr, &batch)?;
+        assert_eq!(result, vec![Some(false), Some(true), Some(false), None]);
+
+        list = (1..=IN_LIST_HASH_THRESHOLD as i64)
+            .map(|i| lit(ScalarValue::Int64(Some(i * 2))))
+            .collect::<Vec<_>>();
+        list.push(lit(ScalarValue::Int64(None)));
+        let expr = in_list(col("a"), list, true, &schema)?;
+        let result = evaluate_boolean(expr, &batch)?;
+        assert_eq!(result, vec![None, Some(false), None, None]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn in_list_no_common_type() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int32, true)]);
+
+        let expr = in_list(
+            col("a"),
+            vec![lit(ScalarValue::Utf8(Some("a".to_string())))],
+            false,
+            &schema,
+        );
+        assert!(expr.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn between_op() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int32, true)]);
+        let a = Int32Array::from(vec![Some(1), Some(2), Some(3), Some(4), None]);
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![Arc::new(a)])?;
+
+        // expression: "a BETWEEN 2 AND 3"
+        let expr = between(
+            col("a"),
+            lit(ScalarValue::Int32(Some(2))),
+            lit(ScalarValue::Int32(Some(3))),
+            false,
+            &schema,
+        )?;
+        let result = evaluate_boolean(expr, &batch)?;
+        assert_eq!(
+            result,
+            vec![Some(false), Some(true), Some(true), Some(false), None]
+        );
+
+        // expression: "a NOT BETWEEN 2 AND 3"
+        let expr = between(
+            col("a"),
+            lit(ScalarValue::Int32(Some(2))),
+            lit(ScalarValue::Int32(Some(3))),
+            true,
+            &schema,
+        )?;
+        let result = evaluate_boolean(expr, &batch)?;
+        assert_eq!(
+            result,
+            vec![Some(true), Some(false), Some(false), Some(true), None]
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn case_with_expr() -> Result<()> {
         let batch = case_test_batch()?;
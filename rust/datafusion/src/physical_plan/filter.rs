@@ -25,6 +25,7 @@ use std::task::{Context, Poll};
 
 use super::{RecordBatchStream, SendableRecordBatchStream};
 use crate::error::{DataFusionError, Result};
+use crate::physical_plan::expressions::PhysicalSortExpr;
 use crate::physical_plan::{ExecutionPlan, Partitioning, PhysicalExpr};
 use arrow::array::BooleanArray;
 use arrow::compute::filter_record_batch;
@@ -87,6 +88,15 @@ impl ExecutionPlan for FilterExec {
         self.input.output_partitioning()
     }
 
+    fn output_size_estimate(&self) -> Option<usize> {
+        // the filter keeps at most all the rows of its input
+        self.input.output_size_estimate()
+    }
+
+    fn output_ordering(&self) -> Option<Vec<PhysicalSortExpr>> {
+        self.input.output_ordering()
+    }
+
     fn with_new_children(
         &self,
         children: Vec<Arc<dyn ExecutionPlan>>,
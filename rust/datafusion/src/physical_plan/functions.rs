@@ -42,12 +42,11 @@ use crate::physical_plan::string_expressions;
 use arrow::{
     array::ArrayRef,
     compute::kernels::length::length,
-    datatypes::TimeUnit,
-    datatypes::{DataType, Field, Schema},
+    datatypes::{DataType, DateUnit, Field, Schema, TimeUnit},
     record_batch::RecordBatch,
 };
 use fmt::{Debug, Formatter};
-use std::{fmt, str::FromStr, sync::Arc};
+use std::{any::Any, fmt, str::FromStr, sync::Arc};
 
 /// A function's signature, which defines the function's supported argument types.
 #[derive(Debug, Clone)]
@@ -67,6 +66,9 @@ pub enum Signature {
     Exact(Vec<DataType>),
     /// fixed number of arguments of arbitrary types
     Any(usize),
+    /// one of a list of signatures, such as for a function with optional arguments
+    // The first signature that the arguments can be coerced to is used
+    OneOf(Vec<Signature>),
 }
 
 /// Scalar function
@@ -118,8 +120,62 @@ pub enum BuiltinScalarFunction {
     Length,
     /// concat
     Concat,
+    /// lower
+    Lower,
+    /// upper
+    Upper,
+    /// trim, also known as btrim
+    Trim,
+    /// ltrim
+    Ltrim,
+    /// rtrim
+    Rtrim,
+    /// lpad
+    Lpad,
+    /// rpad
+    Rpad,
+    /// substr
+    Substr,
+    /// replace
+    Replace,
+    /// split_part
+    SplitPart,
+    /// starts_with
+    StartsWith,
+    /// strpos
+    Strpos,
+    /// reverse
+    Reverse,
+    /// left
+    Left,
+    /// right
+    Right,
+    /// initcap
+    Initcap,
+    /// repeat
+    Repeat,
+    /// chr
+    Chr,
+    /// ascii
+    Ascii,
+    /// md5
+    Md5,
+    /// sha256
+    Sha256,
+    /// regexp_match
+    RegexpMatch,
+    /// regexp_replace
+    RegexpReplace,
     /// to_timestamp
     ToTimestamp,
+    /// to_date
+    ToDate,
+    /// date_trunc
+    DateTrunc,
+    /// date_part, also the function of EXTRACT
+    DatePart,
+    /// now, the time that the query started
+    Now,
     /// construct an array from columns
     Array,
     /// SQL NULLIF()
@@ -128,8 +184,17 @@ pub enum BuiltinScalarFunction {
 
 impl fmt::Display for BuiltinScalarFunction {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // lowercase of the debug.
-        write!(f, "{}", format!("{:?}", self).to_lowercase())
+        match self {
+            BuiltinScalarFunction::ToDate => write!(f, "to_date"),
+            BuiltinScalarFunction::DateTrunc => write!(f, "date_trunc"),
+            BuiltinScalarFunction::DatePart => write!(f, "date_part"),
+            BuiltinScalarFunction::SplitPart => write!(f, "split_part"),
+            BuiltinScalarFunction::StartsWith => write!(f, "starts_with"),
+            BuiltinScalarFunction::RegexpMatch => write!(f, "regexp_match"),
+            BuiltinScalarFunction::RegexpReplace => write!(f, "regexp_replace"),
+            // lowercase of the debug.
+            _ => write!(f, "{}", format!("{:?}", self).to_lowercase()),
+        }
     }
 }
 
@@ -156,7 +221,34 @@ impl FromStr for BuiltinScalarFunction {
             "signum" => BuiltinScalarFunction::Signum,
             "length" => BuiltinScalarFunction::Length,
             "concat" => BuiltinScalarFunction::Concat,
+            "lower" => BuiltinScalarFunction::Lower,
+            "upper" => BuiltinScalarFunction::Upper,
+            "trim" | "btrim" => BuiltinScalarFunction::Trim,
+            "ltrim" => BuiltinScalarFunction::Ltrim,
+            "rtrim" => BuiltinScalarFunction::Rtrim,
+            "lpad" => BuiltinScalarFunction::Lpad,
+            "rpad" => BuiltinScalarFunction::Rpad,
+            "substr" => BuiltinScalarFunction::Substr,
+            "replace" => BuiltinScalarFunction::Replace,
+            "split_part" => BuiltinScalarFunction::SplitPart,
+            "starts_with" => BuiltinScalarFunction::StartsWith,
+            "strpos" => BuiltinScalarFunction::Strpos,
+            "reverse" => BuiltinScalarFunction::Reverse,
+            "left" => BuiltinScalarFunction::Left,
+            "right" => BuiltinScalarFunction::Right,
+            "initcap" => BuiltinScalarFunction::Initcap,
+            "repeat" => BuiltinScalarFunction::Repeat,
+            "chr" => BuiltinScalarFunction::Chr,
+            "ascii" => BuiltinScalarFunction::Ascii,
+            "md5" => BuiltinScalarFunction::Md5,
+            "sha256" => BuiltinScalarFunction::Sha256,
+            "regexp_match" => BuiltinScalarFunction::RegexpMatch,
+            "regexp_replace" => BuiltinScalarFunction::RegexpReplace,
             "to_timestamp" => BuiltinScalarFunction::ToTimestamp,
+            "to_date" => BuiltinScalarFunction::ToDate,
+            "date_trunc" => BuiltinScalarFunction::DateTrunc,
+            "date_part" => BuiltinScalarFunction::DatePart,
+            "now" => BuiltinScalarFunction::Now,
             "array" => BuiltinScalarFunction::Array,
             "nullif" => BuiltinScalarFunction::NullIf,
             _ => {
@@ -180,9 +272,9 @@ pub fn return_type(
     // verify that this is a valid set of data types for this function
     data_types(&arg_types, &signature(fun))?;
 
-    if arg_types.len() == 0 {
+    if arg_types.len() == 0 && fun != &BuiltinScalarFunction::Now {
         // functions currently cannot be evaluated without arguments, as they can't
-        // know the number of rows to return.
+        // know the number of rows to return. `now` is planned as a literal instead.
         return Err(DataFusionError::Plan(format!(
             "Function '{}' requires at least one argument",
             fun
@@ -203,9 +295,43 @@ pub fn return_type(
             }
         }),
         BuiltinScalarFunction::Concat => Ok(DataType::Utf8),
-        BuiltinScalarFunction::ToTimestamp => {
+        BuiltinScalarFunction::Lower
+        | BuiltinScalarFunction::Upper
+        | BuiltinScalarFunction::Trim
+        | BuiltinScalarFunction::Ltrim
+        | BuiltinScalarFunction::Rtrim
+        | BuiltinScalarFunction::Lpad
+        | BuiltinScalarFunction::Rpad
+        | BuiltinScalarFunction::Substr
+        | BuiltinScalarFunction::Replace
+        | BuiltinScalarFunction::SplitPart
+        | BuiltinScalarFunction::Reverse
+        | BuiltinScalarFunction::Left
+        | BuiltinScalarFunction::Right
+        | BuiltinScalarFunction::Initcap
+        | BuiltinScalarFunction::Repeat
+        | BuiltinScalarFunction::Md5
+        | BuiltinScalarFunction::Sha256
+        | BuiltinScalarFunction::RegexpReplace => Ok(arg_types[0].clone()),
+        BuiltinScalarFunction::StartsWith => Ok(DataType::Boolean),
+        BuiltinScalarFunction::Strpos => Ok(match arg_types[0] {
+            DataType::LargeUtf8 => DataType::Int64,
+            _ => DataType::Int32,
+        }),
+        BuiltinScalarFunction::Chr => Ok(DataType::Utf8),
+        BuiltinScalarFunction::Ascii => Ok(DataType::Int32),
+        BuiltinScalarFunction::RegexpMatch => Ok(DataType::List(Box::new(Field::new(
+            "item",
+            DataType::Utf8,
+            true,
+        )))),
+        BuiltinScalarFunction::ToTimestamp
+        | BuiltinScalarFunction::DateTrunc
+        | BuiltinScalarFunction::Now => {
             Ok(DataType::Timestamp(TimeUnit::Nanosecond, None))
         }
+        BuiltinScalarFunction::ToDate => Ok(DataType::Date32(DateUnit::Day)),
+        BuiltinScalarFunction::DatePart => Ok(DataType::Int32),
         BuiltinScalarFunction::Array => Ok(DataType::FixedSizeList(
             Box::new(Field::new("item", arg_types[0].clone(), true)),
             arg_types.len() as i32,
@@ -219,6 +345,22 @@ pub fn return_type(
     }
 }
 
+/// Invokes the string function `$FUNC` of [string_expressions] for the type of the strings
+/// of the first of `$ARGS`
+macro_rules! invoke_string_function {
+    ($ARGS:expr, $FUNC:ident) => {
+        match $ARGS[0].data_type() {
+            DataType::Utf8 => string_expressions::$FUNC::<i32>($ARGS),
+            DataType::LargeUtf8 => string_expressions::$FUNC::<i64>($ARGS),
+            other => Err(DataFusionError::Internal(format!(
+                "Unsupported data type {:?} for function {}",
+                other,
+                stringify!($FUNC)
+            ))),
+        }
+    };
+}
+
 /// Create a physical (function) expression.
 /// This function errors when `args`' can't be coerced to a valid argument type of the function.
 pub fn create_physical_expr(
@@ -252,7 +394,51 @@ pub fn create_physical_expr(
         BuiltinScalarFunction::ToTimestamp => {
             |args| Ok(Arc::new(datetime_expressions::to_timestamp(args)?))
         }
+        BuiltinScalarFunction::ToDate => {
+            |args| Ok(Arc::new(datetime_expressions::to_date(args)?))
+        }
+        BuiltinScalarFunction::DateTrunc => {
+            |args| Ok(Arc::new(datetime_expressions::date_trunc(args)?))
+        }
+        BuiltinScalarFunction::DatePart => datetime_expressions::date_part,
+        BuiltinScalarFunction::Now => |_| {
+            Err(DataFusionError::Internal(
+                "now() is planned as a literal of the time that its query started"
+                    .to_string(),
+            ))
+        },
         BuiltinScalarFunction::Array => |args| Ok(array_expressions::array(args)?),
+        BuiltinScalarFunction::Lower => |args| invoke_string_function!(args, lower),
+        BuiltinScalarFunction::Upper => |args| invoke_string_function!(args, upper),
+        BuiltinScalarFunction::Trim => |args| invoke_string_function!(args, trim),
+        BuiltinScalarFunction::Ltrim => |args| invoke_string_function!(args, ltrim),
+        BuiltinScalarFunction::Rtrim => |args| invoke_string_function!(args, rtrim),
+        BuiltinScalarFunction::Lpad => |args| invoke_string_function!(args, lpad),
+        BuiltinScalarFunction::Rpad => |args| invoke_string_function!(args, rpad),
+        BuiltinScalarFunction::Substr => |args| invoke_string_function!(args, substr),
+        BuiltinScalarFunction::Replace => |args| invoke_string_function!(args, replace),
+        BuiltinScalarFunction::SplitPart => {
+            |args| invoke_string_function!(args, split_part)
+        }
+        BuiltinScalarFunction::StartsWith => {
+            |args| invoke_string_function!(args, starts_with)
+        }
+        BuiltinScalarFunction::Strpos => |args| invoke_string_function!(args, strpos),
+        BuiltinScalarFunction::Reverse => |args| invoke_string_function!(args, reverse),
+        BuiltinScalarFunction::Left => |args| invoke_string_function!(args, left),
+        BuiltinScalarFunction::Right => |args| invoke_string_function!(args, right),
+        BuiltinScalarFunction::Initcap => |args| invoke_string_function!(args, initcap),
+        BuiltinScalarFunction::Repeat => |args| invoke_string_function!(args, repeat),
+        BuiltinScalarFunction::Chr => string_expressions::chr,
+        BuiltinScalarFunction::Ascii => |args| invoke_string_function!(args, ascii),
+        BuiltinScalarFunction::Md5 => |args| invoke_string_function!(args, md5),
+        BuiltinScalarFunction::Sha256 => |args| invoke_string_function!(args, sha256),
+        BuiltinScalarFunction::RegexpMatch => {
+            |args| invoke_string_function!(args, regexp_match)
+        }
+        BuiltinScalarFunction::RegexpReplace => {
+            |args| invoke_string_function!(args, regexp_replace)
+        }
     });
     // coerce
     let args = coerce(args, input_schema, &signature(fun))?;
@@ -280,13 +466,76 @@ fn signature(fun: &BuiltinScalarFunction) -> Signature {
             Signature::Uniform(1, vec![DataType::Utf8, DataType::LargeUtf8])
         }
         BuiltinScalarFunction::Concat => Signature::Variadic(vec![DataType::Utf8]),
-        BuiltinScalarFunction::ToTimestamp => Signature::Uniform(1, vec![DataType::Utf8]),
+        BuiltinScalarFunction::ToTimestamp | BuiltinScalarFunction::ToDate => {
+            Signature::Uniform(1, vec![DataType::Utf8])
+        }
+        BuiltinScalarFunction::DateTrunc => Signature::Exact(vec![
+            DataType::Utf8,
+            DataType::Timestamp(TimeUnit::Nanosecond, None),
+        ]),
+        BuiltinScalarFunction::DatePart => Signature::OneOf(
+            vec![
+                DataType::Timestamp(TimeUnit::Nanosecond, None),
+                DataType::Timestamp(TimeUnit::Microsecond, None),
+                DataType::Timestamp(TimeUnit::Millisecond, None),
+                DataType::Timestamp(TimeUnit::Second, None),
+                DataType::Date32(DateUnit::Day),
+                DataType::Date64(DateUnit::Millisecond),
+            ]
+            .into_iter()
+            .map(|data_type| Signature::Exact(vec![DataType::Utf8, data_type]))
+            .collect(),
+        ),
+        BuiltinScalarFunction::Now => Signature::Exact(vec![]),
         BuiltinScalarFunction::Array => {
             Signature::Variadic(array_expressions::SUPPORTED_ARRAY_TYPES.to_vec())
         }
         BuiltinScalarFunction::NullIf => {
             Signature::Uniform(2, SUPPORTED_NULLIF_TYPES.to_vec())
         }
+        BuiltinScalarFunction::Lower
+        | BuiltinScalarFunction::Upper
+        | BuiltinScalarFunction::Reverse
+        | BuiltinScalarFunction::Initcap
+        | BuiltinScalarFunction::Ascii
+        | BuiltinScalarFunction::Md5
+        | BuiltinScalarFunction::Sha256 => string_signature(vec![vec![]]),
+        BuiltinScalarFunction::Trim
+        | BuiltinScalarFunction::Ltrim
+        | BuiltinScalarFunction::Rtrim => {
+            string_signature(vec![vec![], vec![DataType::Utf8]])
+        }
+        BuiltinScalarFunction::Lpad | BuiltinScalarFunction::Rpad => {
+            string_signature(vec![
+                vec![DataType::Int64],
+                vec![DataType::Int64, DataType::Utf8],
+            ])
+        }
+        BuiltinScalarFunction::Substr => string_signature(vec![
+            vec![DataType::Int64],
+            vec![DataType::Int64, DataType::Int64],
+        ]),
+        BuiltinScalarFunction::Replace => {
+            string_signature(vec![vec![DataType::Utf8, DataType::Utf8]])
+        }
+        BuiltinScalarFunction::SplitPart => {
+            string_signature(vec![vec![DataType::Utf8, DataType::Int64]])
+        }
+        BuiltinScalarFunction::StartsWith | BuiltinScalarFunction::Strpos => {
+            string_signature(vec![vec![DataType::Utf8]])
+        }
+        BuiltinScalarFunction::Left
+        | BuiltinScalarFunction::Right
+        | BuiltinScalarFunction::Repeat => string_signature(vec![vec![DataType::Int64]]),
+        BuiltinScalarFunction::Chr => Signature::Uniform(1, vec![DataType::Int64]),
+        BuiltinScalarFunction::RegexpMatch => string_signature(vec![
+            vec![DataType::Utf8],
+            vec![DataType::Utf8, DataType::Utf8],
+        ]),
+        BuiltinScalarFunction::RegexpReplace => string_signature(vec![
+            vec![DataType::Utf8, DataType::Utf8],
+            vec![DataType::Utf8, DataType::Utf8, DataType::Utf8],
+        ]),
         // math expressions expect 1 argument of type f64 or f32
         // priority is given to f64 because e.g. `sqrt(1i32)` is in IR (real numbers) and thus we
         // return the best approximation for it (in f64).
@@ -296,6 +545,23 @@ fn signature(fun: &BuiltinScalarFunction) -> Signature {
     }
 }
 
+/// The signature of a string function, whose first argument is a `Utf8` or `LargeUtf8`
+/// string, followed by the arguments of one of `other_args`
+fn string_signature(other_args: Vec<Vec<DataType>>) -> Signature {
+    // `LargeUtf8` comes first, as any type can be coerced to `Utf8`
+    let signatures = [DataType::LargeUtf8, DataType::Utf8]
+        .iter()
+        .flat_map(|string_type| {
+            other_args.iter().map(move |other_args| {
+                let mut args = vec![string_type.clone()];
+                args.extend(other_args.iter().cloned());
+                Signature::Exact(args)
+            })
+        })
+        .collect();
+    Signature::OneOf(signatures)
+}
+
 /// Physical expression of a scalar function
 pub struct ScalarFunctionExpr {
     fun: ScalarFunctionImplementation,
@@ -348,6 +614,11 @@ impl fmt::Display for ScalarFunctionExpr {
 }
 
 impl PhysicalExpr for ScalarFunctionExpr {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
         Ok(self.return_type.clone())
     }
@@ -375,11 +646,47 @@ mod tests {
     use super::*;
     use crate::{error::Result, physical_plan::expressions::lit, scalar::ScalarValue};
     use arrow::{
-        array::{ArrayRef, FixedSizeListArray, Float64Array, Int32Array, StringArray},
+        array::{
+            ArrayRef, BooleanArray, FixedSizeListArray, Float64Array, Int32Array,
+            Int64Array, LargeStringArray, ListArray, StringArray,
+        },
         datatypes::Field,
         record_batch::RecordBatch,
     };
 
+    /// Evaluates the function `$FUNC` of the literals `$ARGS` and checks that it returns
+    /// `$EXPECTED`, an `Option` of the value of an array `$ARRAY_TYPE` of `$DATA_TYPE`
+    macro_rules! test_function {
+        ($FUNC:ident, $ARGS:expr, $EXPECTED:expr, $DATA_TYPE:expr, $ARRAY_TYPE:ident) => {
+            // any type works here: we evaluate against literals
+            let schema = Schema::new(vec![Field::new("a", DataType::Int32, false)]);
+            let columns: Vec<ArrayRef> = vec![Arc::new(Int32Array::from(vec![1]))];
+
+            let expr =
+                create_physical_expr(&BuiltinScalarFunction::$FUNC, &$ARGS, &schema)?;
+            assert_eq!(expr.data_type(&schema)?, $DATA_TYPE);
+
+            let batch = RecordBatch::try_new(Arc::new(schema.clone()), columns)?;
+            let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
+            let result = result.as_any().downcast_ref::<$ARRAY_TYPE>().unwrap();
+            match $EXPECTED {
+                Some(expected) => {
+                    assert!(result.is_valid(0));
+                    assert_eq!(result.value(0), expected);
+                }
+                None => assert!(result.is_null(0)),
+            }
+        };
+    }
+
+    fn utf8(value: &str) -> Arc<dyn PhysicalExpr> {
+        lit(ScalarValue::Utf8(Some(value.to_string())))
+    }
+
+    fn int64(value: i64) -> Arc<dyn PhysicalExpr> {
+        lit(ScalarValue::Int64(Some(value)))
+    }
+
     fn generic_test_math(value: ScalarValue, expected: &str) -> Result<()> {
         // any type works here: we evaluate against a literal of `value`
         let schema = Schema::new(vec![Field::new("a", DataType::Int32, false)]);
@@ -464,6 +771,287 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_string_functions() -> Result<()> {
+        use DataType::{Boolean, Utf8};
+
+        test_function!(Lower, vec![utf8("ÀbC")], Some("àbc"), Utf8, StringArray);
+        test_function!(Upper, vec![utf8("aBc")], Some("ABC"), Utf8, StringArray);
+        test_function!(Trim, vec![utf8("  a b ")], Some("a b"), Utf8, StringArray);
+        test_function!(
+            Trim,
+            vec![utf8("xyaxy"), utf8("xy")],
+            Some("a"),
+            Utf8,
+            StringArray
+        );
+        test_function!(Ltrim, vec![utf8("  a ")], Some("a "), Utf8, StringArray);
+        test_function!(Rtrim, vec![utf8("  a ")], Some("  a"), Utf8, StringArray);
+        test_function!(
+            Lpad,
+            vec![utf8("hi"), int64(5)],
+            Some("   hi"),
+            Utf8,
+            StringArray
+        );
+        test_function!(
+            Lpad,
+            vec![utf8("hi"), int64(5), utf8("xy")],
+            Some("xyxhi"),
+            Utf8,
+            StringArray
+        );
+        test_function!(
+            Lpad,
+            vec![utf8("hello"), int64(2)],
+            Some("he"),
+            Utf8,
+            StringArray
+        );
+        test_function!(
+            Rpad,
+            vec![utf8("hi"), int64(5), utf8("xy")],
+            Some("hixyx"),
+            Utf8,
+            StringArray
+        );
+        test_function!(
+            Substr,
+            vec![utf8("alphabet"), int64(3)],
+            Some("phabet"),
+            Utf8,
+            StringArray
+        );
+        test_function!(
+            Substr,
+            vec![utf8("alphabet"), int64(0), int64(3)],
+            Some("al"),
+            Utf8,
+            StringArray
+        );
+        test_function!(
+            Replace,
+            vec![utf8("abcabc"), utf8("b"), utf8("XX")],
+            Some("aXXcaXXc"),
+            Utf8,
+            StringArray
+        );
+        test_function!(
+            SplitPart,
+            vec![utf8("a~@~b~@~c"), utf8("~@~"), int64(2)],
+            Some("b"),
+            Utf8,
+            StringArray
+        );
+        test_function!(
+            SplitPart,
+            vec![utf8("a,b"), utf8(","), int64(3)],
+            Some(""),
+            Utf8,
+            StringArray
+        );
+        test_function!(
+            StartsWith,
+            vec![utf8("alphabet"), utf8("alph")],
+            Some(true),
+            Boolean,
+            BooleanArray
+        );
+        test_function!(
+            Strpos,
+            vec![utf8("ésoj"), utf8("oj")],
+            Some(3),
+            DataType::Int32,
+            Int32Array
+        );
+        test_function!(Reverse, vec![utf8("abé")], Some("éba"), Utf8, StringArray);
+        test_function!(
+            Left,
+            vec![utf8("abcde"), int64(2)],
+            Some("ab"),
+            Utf8,
+            StringArray
+        );
+        test_function!(
+            Left,
+            vec![utf8("abcde"), int64(-2)],
+            Some("abc"),
+            Utf8,
+            StringArray
+        );
+        test_function!(
+            Right,
+            vec![utf8("abcde"), int64(2)],
+            Some("de"),
+            Utf8,
+            StringArray
+        );
+        test_function!(
+            Right,
+            vec![utf8("abcde"), int64(-2)],
+            Some("cde"),
+            Utf8,
+            StringArray
+        );
+        test_function!(
+            Initcap,
+            vec![utf8("hi THOMAS-o'neil")],
+            Some("Hi Thomas-O'Neil"),
+            Utf8,
+            StringArray
+        );
+        test_function!(
+            Repeat,
+            vec![utf8("Pg"), int64(3)],
+            Some("PgPgPg"),
+            Utf8,
+            StringArray
+        );
+        test_function!(Chr, vec![int64(65)], Some("A"), Utf8, StringArray);
+        test_function!(
+            Ascii,
+            vec![utf8("x")],
+            Some(120),
+            DataType::Int32,
+            Int32Array
+        );
+        test_function!(
+            Md5,
+            vec![utf8("abc")],
+            Some("900150983cd24fb0d6963f7d28e17f72"),
+            Utf8,
+            StringArray
+        );
+        test_function!(
+            Sha256,
+            vec![utf8("abc")],
+            Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+            Utf8,
+            StringArray
+        );
+        test_function!(
+            RegexpReplace,
+            vec![utf8("foobarbaz"), utf8("b(..)"), utf8("X\\1Y")],
+            Some("fooXarYbaz"),
+            Utf8,
+            StringArray
+        );
+        test_function!(
+            RegexpReplace,
+            vec![utf8("foobarbaz"), utf8("B(..)"), utf8("X\\1Y"), utf8("gi")],
+            Some("fooXarYXazY"),
+            Utf8,
+            StringArray
+        );
+
+        // nulls
+        test_function!(
+            Lower,
+            vec![lit(ScalarValue::Utf8(None))],
+            None::<&str>,
+            Utf8,
+            StringArray
+        );
+        test_function!(
+            Repeat,
+            vec![utf8("a"), lit(ScalarValue::Int64(None))],
+            None::<&str>,
+            Utf8,
+            StringArray
+        );
+
+        // large strings, and integers of other types
+        test_function!(
+            Left,
+            vec![
+                lit(ScalarValue::LargeUtf8(Some("abc".to_string()))),
+                lit(ScalarValue::Int32(Some(2))),
+            ],
+            Some("ab"),
+            DataType::LargeUtf8,
+            LargeStringArray
+        );
+        test_function!(
+            Strpos,
+            vec![
+                lit(ScalarValue::LargeUtf8(Some("abc".to_string()))),
+                utf8("c")
+            ],
+            Some(3),
+            DataType::Int64,
+            Int64Array
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_regexp_match() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int32, false)]);
+        let columns: Vec<ArrayRef> = vec![Arc::new(Int32Array::from(vec![1]))];
+        let expr = create_physical_expr(
+            &BuiltinScalarFunction::RegexpMatch,
+            &vec![utf8("foobarbequebaz"), utf8("(BAR)(beque)"), utf8("i")],
+            &schema,
+        )?;
+
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), columns)?;
+        let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
+        let result = result.as_any().downcast_ref::<ListArray>().unwrap();
+        let captures = result.value(0);
+        let captures = captures.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(captures.len(), 2);
+        assert_eq!(captures.value(0), "bar");
+        assert_eq!(captures.value(1), "beque");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_function_errors() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int32, false)]);
+        let columns: Vec<ArrayRef> = vec![Arc::new(Int32Array::from(vec![1]))];
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), columns)?;
+
+        let cases = vec![
+            (
+                BuiltinScalarFunction::Substr,
+                vec![utf8("a"), int64(1), int64(-1)],
+                "negative substring length not allowed",
+            ),
+            (
+                BuiltinScalarFunction::SplitPart,
+                vec![utf8("a"), utf8(","), int64(0)],
+                "field position must be greater than zero",
+            ),
+            (
+                BuiltinScalarFunction::Chr,
+                vec![int64(0)],
+                "null character not permitted",
+            ),
+            (
+                BuiltinScalarFunction::RegexpReplace,
+                vec![utf8("a"), utf8("("), utf8("b")],
+                "invalid regular expression",
+            ),
+        ];
+        for (fun, args, message) in cases {
+            let expr = create_physical_expr(&fun, &args, &schema)?;
+            let e = expr.evaluate(&batch).unwrap_err();
+            assert!(e.to_string().contains(message), "{}", e);
+        }
+
+        // the number of arguments of no signature
+        assert!(create_physical_expr(
+            &BuiltinScalarFunction::Lpad,
+            &vec![utf8("a")],
+            &schema
+        )
+        .is_err());
+
+        Ok(())
+    }
+
     fn generic_test_array(
         value1: ScalarValue,
         value2: ScalarValue,
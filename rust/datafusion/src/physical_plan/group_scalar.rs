@@ -35,6 +35,8 @@ pub(crate) enum GroupByScalar {
     Int32(i32),
     Int64(i64),
     Utf8(String),
+    /// A decimal value with its precision and scale
+    Decimal128(i128, usize, usize),
 }
 
 impl TryFrom<&ScalarValue> for GroupByScalar {
@@ -51,6 +53,9 @@ impl TryFrom<&ScalarValue> for GroupByScalar {
             ScalarValue::UInt32(Some(v)) => GroupByScalar::UInt32(*v),
             ScalarValue::UInt64(Some(v)) => GroupByScalar::UInt64(*v),
             ScalarValue::Utf8(Some(v)) => GroupByScalar::Utf8(v.clone()),
+            ScalarValue::Decimal128(Some(v), precision, scale) => {
+                GroupByScalar::Decimal128(*v, *precision, *scale)
+            }
             ScalarValue::Int8(None)
             | ScalarValue::Int16(None)
             | ScalarValue::Int32(None)
@@ -59,7 +64,8 @@ impl TryFrom<&ScalarValue> for GroupByScalar {
             | ScalarValue::UInt16(None)
             | ScalarValue::UInt32(None)
             | ScalarValue::UInt64(None)
-            | ScalarValue::Utf8(None) => {
+            | ScalarValue::Utf8(None)
+            | ScalarValue::Decimal128(None, _, _) => {
                 return Err(DataFusionError::Internal(format!(
                     "Cannot convert a ScalarValue holding NULL ({:?})",
                     scalar_value
@@ -87,6 +93,9 @@ impl From<&GroupByScalar> for ScalarValue {
             GroupByScalar::UInt32(v) => ScalarValue::UInt32(Some(*v)),
             GroupByScalar::UInt64(v) => ScalarValue::UInt64(Some(*v)),
             GroupByScalar::Utf8(v) => ScalarValue::Utf8(Some(v.clone())),
+            GroupByScalar::Decimal128(v, precision, scale) => {
+                ScalarValue::Decimal128(Some(*v), *precision, *scale)
+            }
         }
     }
 }
@@ -0,0 +1,595 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the grouped accumulators of the aggregate functions, which accumulate the
+//! values of all the groups of a grouped aggregation at once.
+//!
+//! The aggregate functions `SUM`, `COUNT`, `MIN`, `MAX` and `AVG` of primitive types have
+//! native grouped accumulators, that keep the state of the groups in vectors indexed by
+//! group. The groups of any other aggregate function are accumulated by
+//! [GroupedAccumulatorAdapter], with an [Accumulator] per group.
+
+use std::ops::Add;
+use std::sync::Arc;
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::{Accumulator, AggregateExpr, GroupedAccumulator};
+use crate::scalar::ScalarValue;
+use arrow::array::{
+    Array, ArrayRef, Float64Array, PrimitiveArray, UInt32Array, UInt64Array,
+};
+use arrow::compute;
+use arrow::datatypes::{
+    ArrowPrimitiveType, DataType, Float32Type, Float64Type, Int16Type, Int32Type,
+    Int64Type, Int8Type, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+};
+
+/// Creates the grouped accumulator of an aggregate expression: its native grouped
+/// accumulator if it has one, or else a [GroupedAccumulatorAdapter] of its accumulator.
+pub fn create_grouped_accumulator(
+    expr: &Arc<dyn AggregateExpr>,
+) -> Result<Box<dyn GroupedAccumulator>> {
+    Ok(match expr.create_grouped_accumulator()? {
+        Some(accumulator) => accumulator,
+        None => Box::new(GroupedAccumulatorAdapter::new(expr.clone())),
+    })
+}
+
+/// Casts `array` to the primitive type `T`, as the input of a grouped accumulator.
+fn cast_primitive<T: ArrowPrimitiveType>(array: &ArrayRef) -> Result<ArrayRef> {
+    Ok(compute::cast(array, &T::DATA_TYPE)?)
+}
+
+/// Downcasts an array that was cast to the primitive type `T`.
+fn as_primitive<T: ArrowPrimitiveType>(array: &ArrayRef) -> Result<&PrimitiveArray<T>> {
+    array
+        .as_any()
+        .downcast_ref::<PrimitiveArray<T>>()
+        .ok_or_else(|| {
+            DataFusionError::Internal(format!(
+                "Grouped accumulator expected an array of type {:?}, got {:?}",
+                T::DATA_TYPE,
+                array.data_type()
+            ))
+        })
+}
+
+/// Calls `$FN::<T>($ARGS)` with the primitive type `T` of `$DATA_TYPE`, or returns `None`
+/// when the type is not numeric.
+macro_rules! numeric_grouped_accumulator {
+    ($DATA_TYPE:expr, $FN:ident $(, $ARGS:expr)*) => {{
+        match $DATA_TYPE {
+            DataType::Int8 => Some($FN::<Int8Type>($($ARGS),*)),
+            DataType::Int16 => Some($FN::<Int16Type>($($ARGS),*)),
+            DataType::Int32 => Some($FN::<Int32Type>($($ARGS),*)),
+            DataType::Int64 => Some($FN::<Int64Type>($($ARGS),*)),
+            DataType::UInt8 => Some($FN::<UInt8Type>($($ARGS),*)),
+            DataType::UInt16 => Some($FN::<UInt16Type>($($ARGS),*)),
+            DataType::UInt32 => Some($FN::<UInt32Type>($($ARGS),*)),
+            DataType::UInt64 => Some($FN::<UInt64Type>($($ARGS),*)),
+            DataType::Float32 => Some($FN::<Float32Type>($($ARGS),*)),
+            DataType::Float64 => Some($FN::<Float64Type>($($ARGS),*)),
+            _ => None,
+        }
+    }};
+}
+
+/// The grouped accumulator of `SUM` of type `data_type`, if it is numeric.
+pub(crate) fn sum_grouped_accumulator(
+    data_type: &DataType,
+) -> Option<Box<dyn GroupedAccumulator>> {
+    fn new<T: ArrowPrimitiveType>() -> Box<dyn GroupedAccumulator>
+    where
+        T::Native: Add<Output = T::Native>,
+    {
+        Box::new(SumGroupedAccumulator::<T> { sums: vec![] })
+    }
+    numeric_grouped_accumulator!(data_type, new)
+}
+
+/// The grouped accumulator of `MIN` of type `data_type`, if it is numeric.
+pub(crate) fn min_grouped_accumulator(
+    data_type: &DataType,
+) -> Option<Box<dyn GroupedAccumulator>> {
+    numeric_grouped_accumulator!(data_type, min_max, true)
+}
+
+/// The grouped accumulator of `MAX` of type `data_type`, if it is numeric.
+pub(crate) fn max_grouped_accumulator(
+    data_type: &DataType,
+) -> Option<Box<dyn GroupedAccumulator>> {
+    numeric_grouped_accumulator!(data_type, min_max, false)
+}
+
+fn min_max<T: ArrowPrimitiveType>(is_min: bool) -> Box<dyn GroupedAccumulator> {
+    Box::new(MinMaxGroupedAccumulator::<T> {
+        values: vec![],
+        is_min,
+    })
+}
+
+/// The grouped accumulator of `COUNT`.
+pub(crate) fn count_grouped_accumulator() -> Box<dyn GroupedAccumulator> {
+    Box::new(CountGroupedAccumulator { counts: vec![] })
+}
+
+/// The grouped accumulator of `AVG`.
+pub(crate) fn avg_grouped_accumulator() -> Box<dyn GroupedAccumulator> {
+    Box::new(AvgGroupedAccumulator {
+        counts: vec![],
+        sums: vec![],
+    })
+}
+
+/// The sum of every group, of the primitive type `T` to which the values are cast
+#[derive(Debug)]
+struct SumGroupedAccumulator<T: ArrowPrimitiveType> {
+    // null until a non-null value is added to the group
+    sums: Vec<Option<T::Native>>,
+}
+
+impl<T: ArrowPrimitiveType> GroupedAccumulator for SumGroupedAccumulator<T>
+where
+    T::Native: Add<Output = T::Native>,
+{
+    fn update_batch(
+        &mut self,
+        values: &[ArrayRef],
+        group_indices: &[usize],
+        num_groups: usize,
+    ) -> Result<()> {
+        self.sums.resize(num_groups, None);
+        let values = cast_primitive::<T>(&values[0])?;
+        let values = as_primitive::<T>(&values)?;
+        for (row, group) in group_indices.iter().enumerate() {
+            if values.is_valid(row) {
+                let value = values.value(row);
+                let sum = &mut self.sums[*group];
+                *sum = Some(match sum {
+                    Some(sum) => *sum + value,
+                    None => value,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn merge_batch(
+        &mut self,
+        states: &[ArrayRef],
+        group_indices: &[usize],
+        num_groups: usize,
+    ) -> Result<()> {
+        // sum(sum1, sum2, sum3, ...) = sum1 + sum2 + sum3 + ...
+        self.update_batch(states, group_indices, num_groups)
+    }
+
+    fn state(&self) -> Result<Vec<ArrayRef>> {
+        Ok(vec![self.evaluate()?])
+    }
+
+    fn evaluate(&self) -> Result<ArrayRef> {
+        Ok(Arc::new(self.sums.iter().collect::<PrimitiveArray<T>>()))
+    }
+
+    fn size(&self) -> usize {
+        self.sums.capacity() * std::mem::size_of::<Option<T::Native>>()
+    }
+}
+
+/// The minimum or maximum of every group, of the primitive type `T` of the values
+#[derive(Debug)]
+struct MinMaxGroupedAccumulator<T: ArrowPrimitiveType> {
+    // null until a non-null value is added to the group
+    values: Vec<Option<T::Native>>,
+    // whether this is the minimum, or the maximum
+    is_min: bool,
+}
+
+impl<T: ArrowPrimitiveType> GroupedAccumulator for MinMaxGroupedAccumulator<T> {
+    fn update_batch(
+        &mut self,
+        values: &[ArrayRef],
+        group_indices: &[usize],
+        num_groups: usize,
+    ) -> Result<()> {
+        self.values.resize(num_groups, None);
+        let values = as_primitive::<T>(&values[0])?;
+        for (row, group) in group_indices.iter().enumerate() {
+            if values.is_valid(row) {
+                let value = values.value(row);
+                let current = &mut self.values[*group];
+                let replace = match current {
+                    Some(current) if self.is_min => value < *current,
+                    Some(current) => value > *current,
+                    None => true,
+                };
+                if replace {
+                    *current = Some(value);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn merge_batch(
+        &mut self,
+        states: &[ArrayRef],
+        group_indices: &[usize],
+        num_groups: usize,
+    ) -> Result<()> {
+        self.update_batch(states, group_indices, num_groups)
+    }
+
+    fn state(&self) -> Result<Vec<ArrayRef>> {
+        Ok(vec![self.evaluate()?])
+    }
+
+    fn evaluate(&self) -> Result<ArrayRef> {
+        Ok(Arc::new(self.values.iter().collect::<PrimitiveArray<T>>()))
+    }
+
+    fn size(&self) -> usize {
+        self.values.capacity() * std::mem::size_of::<Option<T::Native>>()
+    }
+}
+
+/// The number of non-null values of every group
+#[derive(Debug)]
+struct CountGroupedAccumulator {
+    counts: Vec<u64>,
+}
+
+impl GroupedAccumulator for CountGroupedAccumulator {
+    fn update_batch(
+        &mut self,
+        values: &[ArrayRef],
+        group_indices: &[usize],
+        num_groups: usize,
+    ) -> Result<()> {
+        self.counts.resize(num_groups, 0);
+        let values = &values[0];
+        for (row, group) in group_indices.iter().enumerate() {
+            self.counts[*group] += values.is_valid(row) as u64;
+        }
+        Ok(())
+    }
+
+    fn merge_batch(
+        &mut self,
+        states: &[ArrayRef],
+        group_indices: &[usize],
+        num_groups: usize,
+    ) -> Result<()> {
+        self.counts.resize(num_groups, 0);
+        let counts = as_primitive::<UInt64Type>(&states[0])?;
+        for (row, group) in group_indices.iter().enumerate() {
+            if counts.is_valid(row) {
+                self.counts[*group] += counts.value(row);
+            }
+        }
+        Ok(())
+    }
+
+    fn state(&self) -> Result<Vec<ArrayRef>> {
+        Ok(vec![self.evaluate()?])
+    }
+
+    fn evaluate(&self) -> Result<ArrayRef> {
+        Ok(Arc::new(UInt64Array::from(self.counts.clone())))
+    }
+
+    fn size(&self) -> usize {
+        self.counts.capacity() * std::mem::size_of::<u64>()
+    }
+}
+
+/// The average of every group, from the number and the sum of its non-null values
+#[derive(Debug)]
+struct AvgGroupedAccumulator {
+    counts: Vec<u64>,
+    // null until a non-null value is added to the group
+    sums: Vec<Option<f64>>,
+}
+
+impl AvgGroupedAccumulator {
+    fn add(&mut self, group: usize, count: u64, sum: f64) {
+        self.counts[group] += count;
+        let current = &mut self.sums[group];
+        *current = Some(current.unwrap_or(0.0) + sum);
+    }
+}
+
+impl GroupedAccumulator for AvgGroupedAccumulator {
+    fn update_batch(
+        &mut self,
+        values: &[ArrayRef],
+        group_indices: &[usize],
+        num_groups: usize,
+    ) -> Result<()> {
+        self.counts.resize(num_groups, 0);
+        self.sums.resize(num_groups, None);
+        let values = cast_primitive::<Float64Type>(&values[0])?;
+        let values = as_primitive::<Float64Type>(&values)?;
+        for (row, group) in group_indices.iter().enumerate() {
+            if values.is_valid(row) {
+                self.add(*group, 1, values.value(row));
+            }
+        }
+        Ok(())
+    }
+
+    fn merge_batch(
+        &mut self,
+        states: &[ArrayRef],
+        group_indices: &[usize],
+        num_groups: usize,
+    ) -> Result<()> {
+        self.counts.resize(num_groups, 0);
+        self.sums.resize(num_groups, None);
+        let counts = as_primitive::<UInt64Type>(&states[0])?;
+        let sums = as_primitive::<Float64Type>(&states[1])?;
+        for (row, group) in group_indices.iter().enumerate() {
+            // the sum of a state is null when its count is zero
+            if sums.is_valid(row) {
+                self.add(*group, counts.value(row), sums.value(row));
+            }
+        }
+        Ok(())
+    }
+
+    fn state(&self) -> Result<Vec<ArrayRef>> {
+        Ok(vec![
+            Arc::new(UInt64Array::from(self.counts.clone())),
+            Arc::new(Float64Array::from(self.sums.clone())),
+        ])
+    }
+
+    fn evaluate(&self) -> Result<ArrayRef> {
+        Ok(Arc::new(
+            self.sums
+                .iter()
+                .zip(&self.counts)
+                .map(|(sum, count)| sum.map(|sum| sum / *count as f64))
+                .collect::<Float64Array>(),
+        ))
+    }
+
+    fn size(&self) -> usize {
+        self.counts.capacity() * std::mem::size_of::<u64>()
+            + self.sums.capacity() * std::mem::size_of::<Option<f64>>()
+    }
+}
+
+/// A grouped accumulator of any aggregate expression, that keeps the [Accumulator] of the
+/// expression of every group. The rows of a batch are sorted by group, so that every
+/// group is updated from a slice of its rows in a single call.
+#[derive(Debug)]
+pub struct GroupedAccumulatorAdapter {
+    expr: Arc<dyn AggregateExpr>,
+    accumulators: Vec<Box<dyn Accumulator>>,
+}
+
+impl GroupedAccumulatorAdapter {
+    /// Create a new adapter of the accumulators of `expr`
+    pub fn new(expr: Arc<dyn AggregateExpr>) -> Self {
+        Self {
+            expr,
+            accumulators: vec![],
+        }
+    }
+
+    fn update_groups(
+        &mut self,
+        values: &[ArrayRef],
+        group_indices: &[usize],
+        num_groups: usize,
+        merge: bool,
+    ) -> Result<()> {
+        while self.accumulators.len() < num_groups {
+            self.accumulators.push(self.expr.create_accumulator()?);
+        }
+
+        // the sort is stable, so that the rows of every group keep their order
+        let mut rows = (0..group_indices.len() as u32).collect::<Vec<_>>();
+        rows.sort_by_key(|row| group_indices[*row as usize]);
+        let groups = rows
+            .iter()
+            .map(|row| group_indices[*row as usize])
+            .collect::<Vec<_>>();
+        let indices = UInt32Array::from(rows);
+        let values = values
+            .iter()
+            .map(|array| compute::take(array, &indices, None))
+            .collect::<arrow::error::Result<Vec<_>>>()?;
+
+        let mut start = 0;
+        while start < groups.len() {
+            let group = groups[start];
+            let end = start + groups[start..].iter().take_while(|g| **g == group).count();
+            let values = values
+                .iter()
+                .map(|array| array.slice(start, end - start))
+                .collect::<Vec<_>>();
+            let accumulator = &mut self.accumulators[group];
+            if merge {
+                accumulator.merge_batch(&values)?;
+            } else {
+                accumulator.update_batch(&values)?;
+            }
+            start = end;
+        }
+        Ok(())
+    }
+}
+
+/// Concatenates scalar values, of any type, into an array.
+fn scalars_to_array(scalars: &[ScalarValue]) -> Result<ArrayRef> {
+    let arrays = scalars.iter().map(|v| v.to_array()).collect::<Vec<_>>();
+    Ok(compute::concat(&arrays)?)
+}
+
+impl GroupedAccumulator for GroupedAccumulatorAdapter {
+    fn update_batch(
+        &mut self,
+        values: &[ArrayRef],
+        group_indices: &[usize],
+        num_groups: usize,
+    ) -> Result<()> {
+        self.update_groups(values, group_indices, num_groups, false)
+    }
+
+    fn merge_batch(
+        &mut self,
+        states: &[ArrayRef],
+        group_indices: &[usize],
+        num_groups: usize,
+    ) -> Result<()> {
+        self.update_groups(states, group_indices, num_groups, true)
+    }
+
+    fn state(&self) -> Result<Vec<ArrayRef>> {
+        let states = self
+            .accumulators
+            .iter()
+            .map(|accumulator| accumulator.state())
+            .collect::<Result<Vec<_>>>()?;
+        (0..self.expr.state_fields()?.len())
+            .map(|i| {
+                let scalars = states.iter().map(|s| s[i].clone()).collect::<Vec<_>>();
+                scalars_to_array(&scalars)
+            })
+            .collect()
+    }
+
+    fn evaluate(&self) -> Result<ArrayRef> {
+        let values = self
+            .accumulators
+            .iter()
+            .map(|accumulator| accumulator.evaluate())
+            .collect::<Result<Vec<_>>>()?;
+        scalars_to_array(&values)
+    }
+
+    fn size(&self) -> usize {
+        // the memory that the accumulators allocate for their state is not accounted
+        self.accumulators
+            .iter()
+            .map(|accumulator| std::mem::size_of_val(&**accumulator))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::{col, Avg, Count, Max, Min, StringAgg, Sum};
+    use arrow::array::{Int32Array, StringArray};
+
+    fn format_array(array: &ArrayRef) -> Vec<String> {
+        (0..array.len())
+            .map(|i| match ScalarValue::try_from_array(array, i) {
+                Ok(v) if !v.is_null() => v.to_string(),
+                _ => "NULL".to_string(),
+            })
+            .collect()
+    }
+
+    /// Accumulates two batches into two partial accumulators, merges them into a final
+    /// accumulator, and returns its values
+    fn aggregate(
+        mut partial1: Box<dyn GroupedAccumulator>,
+        mut partial2: Box<dyn GroupedAccumulator>,
+        mut last: Box<dyn GroupedAccumulator>,
+    ) -> Result<Vec<String>> {
+        let values: ArrayRef = Arc::new(Int32Array::from(vec![
+            Some(1),
+            Some(2),
+            None,
+            Some(4),
+            Some(5),
+            None,
+        ]));
+        // the groups 0, 1 and 2, of which the group 2 only has nulls
+        partial1.update_batch(&[values.slice(0, 3)], &[0, 1, 2], 3)?;
+        partial1.update_batch(&[values.slice(3, 3)], &[1, 0, 2], 3)?;
+        partial2.update_batch(&[values.slice(0, 2)], &[0, 0], 1)?;
+
+        last.merge_batch(&partial1.state()?, &[1, 0, 2], 3)?;
+        last.merge_batch(&partial2.state()?, &[1], 3)?;
+        // a group that only exists in the final accumulator
+        last.merge_batch(&partial2.state()?, &[3], 4)?;
+        Ok(format_array(&last.evaluate()?))
+    }
+
+    /// The native grouped accumulator of `expr`, and the adapter of its accumulator, must
+    /// aggregate to `expected`
+    fn check(expr: Arc<dyn AggregateExpr>, expected: Vec<&str>) -> Result<()> {
+        let native = || expr.create_grouped_accumulator().map(Option::unwrap);
+        let adapter = || -> Result<Box<dyn GroupedAccumulator>> {
+            Ok(Box::new(GroupedAccumulatorAdapter::new(expr.clone())))
+        };
+        assert_eq!(aggregate(native()?, native()?, native()?)?, expected);
+        assert_eq!(aggregate(adapter()?, adapter()?, adapter()?)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn native_grouped_accumulators() -> Result<()> {
+        check(
+            Arc::new(Sum::new(col("a"), "SUM(a)".to_string(), DataType::Int64)),
+            vec!["6", "9", "NULL", "3"],
+        )?;
+        check(
+            Arc::new(Count::new(
+                col("a"),
+                "COUNT(a)".to_string(),
+                DataType::UInt64,
+            )),
+            vec!["2", "4", "0", "2"],
+        )?;
+        check(
+            Arc::new(Min::new(col("a"), "MIN(a)".to_string(), DataType::Int32)),
+            vec!["2", "1", "NULL", "1"],
+        )?;
+        check(
+            Arc::new(Max::new(col("a"), "MAX(a)".to_string(), DataType::Int32)),
+            vec!["4", "5", "NULL", "2"],
+        )?;
+        check(
+            Arc::new(Avg::new(col("a"), "AVG(a)".to_string(), DataType::Float64)),
+            vec!["3", "2.25", "NULL", "1.5"],
+        )
+    }
+
+    #[test]
+    fn adapter_keeps_order_of_rows() -> Result<()> {
+        let expr: Arc<dyn AggregateExpr> =
+            Arc::new(StringAgg::new(col("a"), ",".to_string(), "agg".to_string()));
+        assert!(expr.create_grouped_accumulator()?.is_none());
+
+        let mut accumulator = create_grouped_accumulator(&expr)?;
+        let values: ArrayRef = Arc::new(StringArray::from(vec!["a", "b", "c", "d", "e"]));
+        accumulator.update_batch(&[values.clone()], &[1, 0, 1, 1, 0], 2)?;
+        accumulator.update_batch(&[values.slice(0, 1)], &[2], 3)?;
+        assert_eq!(
+            format_array(&accumulator.evaluate()?),
+            vec!["b,e", "a,c,d", "a"]
+        );
+        Ok(())
+    }
+}
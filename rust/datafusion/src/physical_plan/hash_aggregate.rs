@@ -18,32 +18,42 @@
 //! Defines the execution plan for the hash aggregate operation
 
 use std::any::Any;
+use std::fs::File;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::io::{BufReader, BufWriter, Seek, SeekFrom};
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use futures::{
+    channel::mpsc,
+    sink::SinkExt,
     stream::{Stream, StreamExt},
     Future,
 };
 
 use crate::error::{DataFusionError, Result};
-use crate::physical_plan::{Accumulator, AggregateExpr};
+use crate::execution::memory_manager::{MemoryManager, MemoryReservation};
+use crate::physical_plan::{Accumulator, AggregateExpr, GroupedAccumulator};
 use crate::physical_plan::{Distribution, ExecutionPlan, Partitioning, PhysicalExpr};
+use crate::scalar::ScalarValue;
 
 use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
 use arrow::error::{ArrowError, Result as ArrowResult};
+use arrow::ipc::reader::FileReader;
+use arrow::ipc::writer::FileWriter;
 use arrow::record_batch::RecordBatch;
 use arrow::{
     array::{
-        ArrayRef, Int16Array, Int32Array, Int64Array, Int8Array, StringArray,
-        UInt16Array, UInt32Array, UInt64Array, UInt8Array,
+        ArrayRef, DecimalArray, Int16Array, Int32Array, Int64Array, Int8Array,
+        StringArray, UInt16Array, UInt32Array, UInt64Array, UInt8Array,
     },
     compute,
 };
 use pin_project_lite::pin_project;
 
 use super::{
-    common, expressions::Column, group_scalar::GroupByScalar, RecordBatchStream,
+    common, expressions::Column, group_scalar::GroupByScalar,
+    grouped_accumulators::create_grouped_accumulator, metrics, RecordBatchStream,
     SendableRecordBatchStream,
 };
 use ahash::RandomState;
@@ -58,6 +68,9 @@ pub enum AggregateMode {
     Partial,
     /// Final aggregate that produces a single partition of output
     Final,
+    /// Final aggregate that works on partitions of its input that are partitioned by the
+    /// group keys, and produces a partition of output per input partition
+    FinalPartitioned,
 }
 
 /// Hash aggregate execution plan
@@ -68,6 +81,8 @@ pub struct HashAggregateExec {
     aggr_expr: Vec<Arc<dyn AggregateExpr>>,
     input: Arc<dyn ExecutionPlan>,
     schema: SchemaRef,
+    memory_budget: usize,
+    memory_manager: Arc<MemoryManager>,
 }
 
 fn create_schema(
@@ -92,7 +107,7 @@ fn create_schema(
                 fields.extend(expr.state_fields()?.iter().cloned())
             }
         }
-        AggregateMode::Final => {
+        AggregateMode::Final | AggregateMode::FinalPartitioned => {
             // in final mode, the field with the final result of the accumulator
             for expr in aggr_expr {
                 fields.push(expr.field()?)
@@ -121,8 +136,25 @@ impl HashAggregateExec {
             aggr_expr,
             input,
             schema,
+            memory_budget: usize::MAX,
+            memory_manager: Arc::new(MemoryManager::new(None)),
         })
     }
+
+    /// Spill the partial state of the groups to disk whenever they exceed `memory_budget`
+    /// bytes. This only applies to aggregations with a GROUP BY.
+    pub fn with_memory_budget(mut self, memory_budget: usize) -> Self {
+        self.memory_budget = memory_budget;
+        self
+    }
+
+    /// Reserve the memory of the groups from `memory_manager`. Their partial state is also
+    /// spilled to disk whenever more memory cannot be reserved. This only applies to
+    /// aggregations with a GROUP BY.
+    pub fn with_memory_manager(mut self, memory_manager: Arc<MemoryManager>) -> Self {
+        self.memory_manager = memory_manager;
+        self
+    }
 }
 
 #[async_trait]
@@ -144,6 +176,9 @@ impl ExecutionPlan for HashAggregateExec {
         match &self.mode {
             AggregateMode::Partial => Distribution::UnspecifiedDistribution,
             AggregateMode::Final => Distribution::SinglePartition,
+            AggregateMode::FinalPartitioned => Distribution::HashPartitioned(
+                self.group_expr.iter().map(|x| x.0.clone()).collect(),
+            ),
         }
     }
 
@@ -170,6 +205,10 @@ impl ExecutionPlan for HashAggregateExec {
                 group_expr,
                 self.aggr_expr.clone(),
                 input,
+                GroupsMemory {
+                    budget: self.memory_budget,
+                    reservation: self.memory_manager.new_reservation(),
+                },
             )))
         }
     }
@@ -179,12 +218,16 @@ impl ExecutionPlan for HashAggregateExec {
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         match children.len() {
-            1 => Ok(Arc::new(HashAggregateExec::try_new(
-                self.mode,
-                self.group_expr.clone(),
-                self.aggr_expr.clone(),
-                children[0].clone(),
-            )?)),
+            1 => Ok(Arc::new(
+                HashAggregateExec::try_new(
+                    self.mode,
+                    self.group_expr.clone(),
+                    self.aggr_expr.clone(),
+                    children[0].clone(),
+                )?
+                .with_memory_budget(self.memory_budget)
+                .with_memory_manager(self.memory_manager.clone()),
+            )),
             _ => Err(DataFusionError::Internal(
                 "HashAggregateExec wrong number of children".to_string(),
             )),
@@ -216,30 +259,46 @@ Example: average
 * The RecordBatch is (sent back / transmitted over network)
 * Once all N record batches arrive, `merge` is performed, which builds a RecordBatch with N rows and 2 columns.
 * Finally, `get_value` returns an array with one entry computed from the state
+
+Grouped aggregations have a grouped accumulator per aggregation instead, with the state of
+all the groups. The rows of every batch are mapped to the index of their group, and the
+grouped accumulator updates all the groups from the arrays of the batch in a single call,
+which `SUM`, `COUNT`, `MIN`, `MAX` and `AVG` do without building a `ScalarValue` per row.
+
+Grouped aggregations keep the state of every group in memory. When the groups exceed
+the memory budget of the aggregation, their state is spilled:
+
+1. The groups are partitioned by hash of their key, and the RecordBatch of the state of every
+   partition is appended to a temporary Arrow IPC file of the partition.
+2. The groups are dropped from memory, and the aggregation continues with new groups.
+3. At the end of the aggregation, the remaining groups are spilled too, and the state of the
+   groups of every partition is `merge`d, one partition at a time. As the groups with the same
+   key are in the same partition, every partition produces the final groups of its keys.
+4. When the groups of a partition exceed the memory budget while they are merged, they are
+   spilled again into new partitions, by another hash of their keys, which are merged in
+   turn.
 */
 pin_project! {
     struct GroupedHashAggregateStream {
         schema: SchemaRef,
         #[pin]
-        output: futures::channel::oneshot::Receiver<ArrowResult<RecordBatch>>,
-        finished: bool,
+        output: mpsc::Receiver<ArrowResult<RecordBatch>>,
+        // aborts the aggregation once the stream is dropped
+        _task: common::AbortOnDrop,
     }
 }
 
 fn group_aggregate_batch(
     mode: &AggregateMode,
     group_expr: &Vec<Arc<dyn PhysicalExpr>>,
-    aggr_expr: &Vec<Arc<dyn AggregateExpr>>,
     batch: RecordBatch,
-    mut accumulators: Accumulators,
+    accumulators: &mut Accumulators,
     aggregate_expressions: &Vec<Vec<Arc<dyn PhysicalExpr>>>,
-) -> Result<Accumulators> {
+) -> Result<()> {
     // evaluate the grouping expressions
     let group_values = evaluate(group_expr, &batch)?;
 
     // evaluate the aggregation expressions.
-    // We could evaluate them after the `take`, but since we need to evaluate all
-    // of them anyways, it is more performant to do it while they are together.
     let aggr_input_values = evaluate_many(aggregate_expressions, &batch)?;
 
     // create vector large enough to hold the grouping key
@@ -251,71 +310,56 @@ fn group_aggregate_batch(
     }
 
     // 1.1 construct the key from the group values
-    // 1.2 construct the mapping key if it does not exist
-    // 1.3 add the row' index to `indices`
+    // 1.2 construct the mapping key -> group index if it does not exist
+    // 1.3 add the group index of the row to `group_indices`
+    let mut group_indices = Vec::with_capacity(batch.num_rows());
     for row in 0..batch.num_rows() {
         // 1.1
-        create_key(&group_values, row, &mut key)
-            .map_err(DataFusionError::into_arrow_external_error)?;
+        create_key(&group_values, row, &mut key)?;
 
-        match accumulators.get_mut(&key) {
+        let group = match accumulators.groups.get(&key) {
             // 1.2
             None => {
-                let accumulator_set = create_accumulators(aggr_expr)
-                    .map_err(DataFusionError::into_arrow_external_error)?;
-
-                accumulators
-                    .insert(key.clone(), (accumulator_set, Box::new(vec![row as u32])));
+                let group = accumulators.groups.len();
+                accumulators.keys_size += key_memory_size(&key);
+                accumulators.groups.insert(key.clone(), group);
+                group
             }
-            // 1.3
-            Some((_, v)) => v.push(row as u32),
-        }
+            Some(group) => *group,
+        };
+        // 1.3
+        group_indices.push(group);
     }
 
-    // 2.1 for each key
-    // 2.2 for each aggregation
-    // 2.3 `take` from each of its arrays the keys' values
-    // 2.4 update / merge the accumulator with the values
-    // 2.5 clear indices
+    // 2. update / merge every accumulator with the values of all the groups at once
+    let num_groups = accumulators.groups.len();
     accumulators
+        .accumulators
         .iter_mut()
-        // 2.1
-        .map(|(_, (accumulator_set, indices))| {
-            // 2.2
-            accumulator_set
-                .into_iter()
-                .zip(&aggr_input_values)
-                .map(|(accumulator, aggr_array)| {
-                    (
-                        accumulator,
-                        aggr_array
-                            .iter()
-                            .map(|array| {
-                                // 2.3
-                                compute::take(
-                                    array,
-                                    &UInt32Array::from(*indices.clone()),
-                                    None, // None: no index check
-                                )
-                                .unwrap()
-                            })
-                            .collect::<Vec<ArrayRef>>(),
-                    )
-                })
-                // 2.4
-                .map(|(accumulator, values)| match mode {
-                    AggregateMode::Partial => accumulator.update_batch(&values),
-                    AggregateMode::Final => {
-                        // note: the aggregation here is over states, not values, thus the merge
-                        accumulator.merge_batch(&values)
-                    }
-                })
-                .collect::<Result<()>>()
-                // 2.5
-                .and(Ok(indices.clear()))
+        .zip(&aggr_input_values)
+        .map(|(accumulator, values)| match mode {
+            AggregateMode::Partial => {
+                accumulator.update_batch(values, &group_indices, num_groups)
+            }
+            AggregateMode::Final | AggregateMode::FinalPartitioned => {
+                // note: the aggregation here is over states, not values, thus the merge
+                accumulator.merge_batch(values, &group_indices, num_groups)
+            }
         })
-        .collect::<Result<()>>()?;
-    Ok(accumulators)
+        .collect::<Result<()>>()
+}
+
+/// The number of partitions that the groups spilled by a grouped aggregation are
+/// partitioned into
+const SPILL_PARTITIONS: usize = 16;
+
+/// The memory that the groups of a grouped aggregation may use before their partial state
+/// is spilled to disk
+struct GroupsMemory {
+    /// Size in bytes of the groups above which they are spilled
+    budget: usize,
+    /// The memory reserved for the groups, which are spilled once it cannot grow
+    reservation: MemoryReservation,
 }
 
 async fn compute_grouped_hash_aggregate(
@@ -324,33 +368,224 @@ async fn compute_grouped_hash_aggregate(
     group_expr: Vec<Arc<dyn PhysicalExpr>>,
     aggr_expr: Vec<Arc<dyn AggregateExpr>>,
     mut input: SendableRecordBatchStream,
-) -> ArrowResult<RecordBatch> {
+    mut memory: GroupsMemory,
+    sender: &mut mpsc::Sender<ArrowResult<RecordBatch>>,
+) -> Result<()> {
     // the expressions to evaluate the batch, one vec of expressions per aggregation
-    let aggregate_expressions = aggregate_expressions(&aggr_expr, &mode)
-        .map_err(DataFusionError::into_arrow_external_error)?;
+    let aggregate_expressions = aggregate_expressions(&aggr_expr, &mode)?;
 
-    // mapping key -> (set of accumulators, indices of the key in the batch)
-    // * the indexes are updated at each row
+    // mapping key -> group index, and the grouped accumulators of the groups
+    // * the group indexes are updated at each row
     // * the accumulators are updated at the end of each batch
-    // * the indexes are `clear`ed at the end of each batch
-    //let mut accumulators: Accumulators = FnvHashMap::default();
 
     // iterate over all input batches and update the accumulators
-    let mut accumulators = Accumulators::default();
+    let mut accumulators = Accumulators::try_new(&aggr_expr)?;
+    let mut spill: Option<AggregateSpill> = None;
     while let Some(batch) = input.next().await {
         let batch = batch?;
-        accumulators = group_aggregate_batch(
+        group_aggregate_batch(
             &mode,
             &group_expr,
-            &aggr_expr,
             batch,
-            accumulators,
+            &mut accumulators,
             &aggregate_expressions,
-        )
-        .map_err(DataFusionError::into_arrow_external_error)?;
+        )?;
+        // the estimated size in bytes of the groups in `accumulators`
+        let memory_size = accumulators.memory_size();
+        metrics::record_memory(memory_size);
+
+        if memory_size > memory.budget
+            || memory.reservation.try_resize(memory_size).is_err()
+        {
+            if spill.is_none() {
+                let state_schema =
+                    create_state_schema(&schema, group_expr.len(), &aggr_expr)?;
+                spill = Some(AggregateSpill::try_new(Arc::new(state_schema))?);
+            }
+            if let Some(spill) = &mut spill {
+                spill.write(&accumulators, group_expr.len())?;
+            }
+            accumulators = Accumulators::try_new(&aggr_expr)?;
+            memory.reservation.free();
+        }
     }
 
-    create_batch_from_map(&mode, &accumulators, group_expr.len(), &schema)
+    let mut spill = match spill {
+        Some(spill) => spill,
+        None => {
+            // all the groups fit in memory
+            let batch =
+                create_batch_from_map(&mode, &accumulators, group_expr.len(), &schema);
+            // If send fails, plan being torn down, there is no place to send the batch
+            sender.send(batch).await.ok();
+            return Ok(());
+        }
+    };
+    spill.write(&accumulators, group_expr.len())?;
+    drop(accumulators);
+    let state_schema = spill.schema.clone();
+
+    // the groups of every partition are merged from their state, in final mode
+    let merge_group_expr = state_schema.fields()[..group_expr.len()]
+        .iter()
+        .map(|f| Arc::new(Column::new(f.name())) as Arc<dyn PhysicalExpr>)
+        .collect::<Vec<_>>();
+    let merge_expressions = aggregate_expressions(&aggr_expr, &AggregateMode::Final)?;
+    let mut files = spill.finish()?;
+    while let Some(file) = files.pop() {
+        let mut accumulators = Accumulators::try_new(&aggr_expr)?;
+        // the groups of the partition that do not fit in memory are partitioned again,
+        // by another hash of their keys
+        let mut respill: Option<AggregateSpill> = None;
+        for batch in FileReader::try_new(BufReader::new(file))? {
+            group_aggregate_batch(
+                &AggregateMode::Final,
+                &merge_group_expr,
+                batch?,
+                &mut accumulators,
+                &merge_expressions,
+            )?;
+            let memory_size = accumulators.memory_size();
+            metrics::record_memory(memory_size);
+            // a single group cannot be partitioned any further
+            if accumulators.groups.len() > 1
+                && (memory_size > memory.budget
+                    || memory.reservation.try_resize(memory_size).is_err())
+            {
+                if respill.is_none() {
+                    respill = Some(AggregateSpill::try_new(state_schema.clone())?);
+                }
+                if let Some(respill) = &mut respill {
+                    respill.write(&accumulators, group_expr.len())?;
+                }
+                accumulators = Accumulators::try_new(&aggr_expr)?;
+                memory.reservation.free();
+            }
+        }
+        if let Some(mut respill) = respill {
+            respill.write(&accumulators, group_expr.len())?;
+            files.extend(respill.finish()?);
+            continue;
+        }
+        if accumulators.is_empty() {
+            continue;
+        }
+
+        let batch =
+            create_batch_from_map(&mode, &accumulators, group_expr.len(), &schema);
+        if sender.send(batch).await.is_err() {
+            // the plan is being torn down, there is no place to send the batch
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+/// The state of the groups spilled by a grouped aggregation, in one temporary Arrow IPC file
+/// per partition of the group keys
+struct AggregateSpill {
+    /// The schema of the state of the groups
+    schema: SchemaRef,
+    /// Hashes the group keys to their partition
+    random_state: RandomState,
+    /// The file of every partition, with its writer
+    files: Vec<(File, FileWriter<BufWriter<File>>)>,
+}
+
+impl AggregateSpill {
+    fn try_new(schema: SchemaRef) -> Result<Self> {
+        let files = (0..SPILL_PARTITIONS)
+            .map(|_| {
+                let file = tempfile::tempfile()?;
+                let writer =
+                    FileWriter::try_new(BufWriter::new(file.try_clone()?), &schema)?;
+                Ok((file, writer))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            schema,
+            random_state: RandomState::new(),
+            files,
+        })
+    }
+
+    /// Writes the state of the groups to the files of their partitions
+    fn write(
+        &mut self,
+        accumulators: &Accumulators,
+        num_group_expr: usize,
+    ) -> Result<()> {
+        if accumulators.is_empty() {
+            return Ok(());
+        }
+        let batch = create_batch_from_map(
+            &AggregateMode::Partial,
+            accumulators,
+            num_group_expr,
+            &self.schema,
+        )?;
+
+        // the rows of the groups of every partition
+        let mut partitions = vec![vec![]; SPILL_PARTITIONS];
+        for (group, key) in accumulators.keys().iter().enumerate() {
+            let mut hasher = self.random_state.build_hasher();
+            key.hash(&mut hasher);
+            let partition = (hasher.finish() % SPILL_PARTITIONS as u64) as usize;
+            partitions[partition].push(group as u32);
+        }
+
+        for (rows, (_, writer)) in partitions.into_iter().zip(self.files.iter_mut()) {
+            if !rows.is_empty() {
+                let indices = UInt32Array::from(rows);
+                let columns = batch
+                    .columns()
+                    .iter()
+                    .map(|column| compute::take(column, &indices, None))
+                    .collect::<ArrowResult<Vec<_>>>()?;
+                writer.write(&RecordBatch::try_new(self.schema.clone(), columns)?)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Finishes the files of the partitions, and returns them positioned at their start.
+    /// The files are removed once they are closed.
+    fn finish(self) -> Result<Vec<File>> {
+        self.files
+            .into_iter()
+            .map(|(mut file, mut writer)| {
+                writer.finish()?;
+                file.seek(SeekFrom::Start(0))?;
+                Ok(file)
+            })
+            .collect()
+    }
+}
+
+/// Creates the schema of the state of the groups of an aggregation: the group keys,
+/// followed by the state of the accumulators.
+fn create_state_schema(
+    schema: &Schema,
+    num_group_expr: usize,
+    aggr_expr: &[Arc<dyn AggregateExpr>],
+) -> Result<Schema> {
+    let mut fields = schema.fields()[..num_group_expr].to_vec();
+    for expr in aggr_expr {
+        fields.extend(expr.state_fields()?.iter().cloned())
+    }
+    Ok(Schema::new(fields))
+}
+
+/// Estimates the number of bytes that the key of a group, and its index, occupy in the map
+/// of the groups.
+fn key_memory_size(key: &[GroupByScalar]) -> usize {
+    key.iter()
+        .map(|scalar| match scalar {
+            GroupByScalar::Utf8(s) => std::mem::size_of_val(scalar) + s.len(),
+            _ => std::mem::size_of_val(scalar),
+        })
+        .sum::<usize>()
+        + std::mem::size_of::<usize>()
 }
 
 impl GroupedHashAggregateStream {
@@ -361,33 +596,87 @@ impl GroupedHashAggregateStream {
         group_expr: Vec<Arc<dyn PhysicalExpr>>,
         aggr_expr: Vec<Arc<dyn AggregateExpr>>,
         input: SendableRecordBatchStream,
+        memory: GroupsMemory,
     ) -> Self {
-        let (tx, rx) = futures::channel::oneshot::channel();
+        let (mut sender, receiver) = mpsc::channel(1);
 
         let schema_clone = schema.clone();
-        tokio::spawn(async move {
+        let (_, task) = common::spawn_abortable(async move {
             let result = compute_grouped_hash_aggregate(
                 mode,
                 schema_clone,
                 group_expr,
                 aggr_expr,
                 input,
+                memory,
+                &mut sender,
             )
             .await;
-            tx.send(result)
+            if let Err(e) = result {
+                // If send fails, plan being torn down, there is no place to send the error
+                sender
+                    .send(Err(DataFusionError::into_arrow_external_error(e)))
+                    .await
+                    .ok();
+            }
         });
 
         GroupedHashAggregateStream {
             schema,
-            output: rx,
-            finished: false,
+            output: receiver,
+            _task: task,
         }
     }
 }
 
 type AccumulatorSet = Vec<Box<dyn Accumulator>>;
-type Accumulators =
-    HashMap<Vec<GroupByScalar>, (AccumulatorSet, Box<Vec<u32>>), RandomState>;
+
+/// The groups of a grouped aggregation, and the grouped accumulators of their aggregates
+struct Accumulators {
+    /// maps the key of every group to its index in the grouped accumulators
+    groups: HashMap<Vec<GroupByScalar>, usize, RandomState>,
+    /// one grouped accumulator per aggregate expression, with the state of all the groups
+    accumulators: Vec<Box<dyn GroupedAccumulator>>,
+    /// the estimated size in bytes of the keys of the groups
+    keys_size: usize,
+}
+
+impl Accumulators {
+    fn try_new(aggr_expr: &[Arc<dyn AggregateExpr>]) -> Result<Self> {
+        Ok(Self {
+            groups: HashMap::default(),
+            accumulators: aggr_expr
+                .iter()
+                .map(create_grouped_accumulator)
+                .collect::<Result<Vec<_>>>()?,
+            keys_size: 0,
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// The keys of the groups, ordered by their index
+    fn keys(&self) -> Vec<&[GroupByScalar]> {
+        let mut keys = vec![&[] as &[GroupByScalar]; self.groups.len()];
+        for (key, group) in &self.groups {
+            keys[*group] = key.as_slice();
+        }
+        keys
+    }
+
+    /// Estimates the number of bytes that the groups occupy in memory: their keys and the
+    /// state of their accumulators.
+    fn memory_size(&self) -> usize {
+        self.keys_size
+            + self
+                .accumulators
+                .iter()
+                .map(|accumulator| accumulator.size())
+                .sum::<usize>()
+    }
+}
 
 impl Stream for GroupedHashAggregateStream {
     type Item = ArrowResult<RecordBatch>;
@@ -396,27 +685,7 @@ impl Stream for GroupedHashAggregateStream {
         self: std::pin::Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Self::Item>> {
-        if self.finished {
-            return Poll::Ready(None);
-        }
-
-        // is the output ready?
-        let this = self.project();
-        let output_poll = this.output.poll(cx);
-
-        match output_poll {
-            Poll::Ready(result) => {
-                *this.finished = true;
-
-                // check for error in receiving channel and unwrap actual result
-                let result = match result {
-                    Err(e) => Err(ArrowError::ExternalError(Box::new(e))), // error receiving
-                    Ok(result) => result,
-                };
-                Poll::Ready(Some(result))
-            }
-            Poll::Pending => Poll::Pending,
-        }
+        self.project().output.poll_next(cx)
     }
 }
 
@@ -474,7 +743,7 @@ fn aggregate_expressions(
             Ok(aggr_expr.iter().map(|agg| agg.expressions()).collect())
         }
         // in this mode, we build the merge expressions of the aggregation
-        AggregateMode::Final => Ok(aggr_expr
+        AggregateMode::Final | AggregateMode::FinalPartitioned => Ok(aggr_expr
             .iter()
             .map(|agg| merge_expressions(agg))
             .collect::<Result<Vec<_>>>()?),
@@ -487,6 +756,8 @@ pin_project! {
         #[pin]
         output: futures::channel::oneshot::Receiver<ArrowResult<RecordBatch>>,
         finished: bool,
+        // aborts the aggregation once the stream is dropped
+        _task: common::AbortOnDrop,
     }
 }
 
@@ -529,16 +800,18 @@ impl HashAggregateStream {
         let (tx, rx) = futures::channel::oneshot::channel();
 
         let schema_clone = schema.clone();
-        tokio::spawn(async move {
+        let (_, task) = common::spawn_abortable(async move {
             let result =
                 compute_hash_aggregate(mode, schema_clone, aggr_expr, input).await;
-            tx.send(result)
+            // If send fails, plan being torn down, there is no place to send the result
+            tx.send(result).ok();
         });
 
         HashAggregateStream {
             schema,
             output: rx,
             finished: false,
+            _task: task,
         }
     }
 }
@@ -570,7 +843,7 @@ fn aggregate_batch(
                 AggregateMode::Partial => {
                     accum.update_batch(values)?;
                 }
-                AggregateMode::Final => {
+                AggregateMode::Final | AggregateMode::FinalPartitioned => {
                     accum.merge_batch(values)?;
                 }
             }
@@ -617,17 +890,6 @@ impl RecordBatchStream for HashAggregateStream {
     }
 }
 
-/// Given Vec<Vec<ArrayRef>>, concatenates the inners `Vec<ArrayRef>` into `ArrayRef`, returning `Vec<ArrayRef>`
-/// This assumes that `arrays` is not empty.
-fn concatenate(arrays: Vec<Vec<ArrayRef>>) -> ArrowResult<Vec<ArrayRef>> {
-    (0..arrays[0].len())
-        .map(|column| {
-            let array_list = arrays.iter().map(|a| a[column].clone()).collect::<Vec<_>>();
-            compute::concat(&array_list)
-        })
-        .collect::<ArrowResult<Vec<_>>>()
-}
-
 /// Create a RecordBatch with all group keys and accumulator' states or values.
 fn create_batch_from_map(
     mode: &AggregateMode,
@@ -635,50 +897,29 @@ fn create_batch_from_map(
     num_group_expr: usize,
     output_schema: &Schema,
 ) -> ArrowResult<RecordBatch> {
-    // 1. for each key
-    // 2. create single-row ArrayRef with all group expressions
-    // 3. create single-row ArrayRef with all aggregate states or values
-    // 4. collect all in a vector per key of vec<ArrayRef>, vec[i][j]
-    // 5. concatenate the arrays over the second index [j] into a single vec<ArrayRef>.
-    let arrays = accumulators
-        .iter()
-        .map(|(k, (accumulator_set, _))| {
-            // 2.
-            let mut groups = (0..num_group_expr)
-                .map(|i| match &k[i] {
-                    GroupByScalar::Int8(n) => {
-                        Arc::new(Int8Array::from(vec![*n])) as ArrayRef
-                    }
-                    GroupByScalar::Int16(n) => Arc::new(Int16Array::from(vec![*n])),
-                    GroupByScalar::Int32(n) => Arc::new(Int32Array::from(vec![*n])),
-                    GroupByScalar::Int64(n) => Arc::new(Int64Array::from(vec![*n])),
-                    GroupByScalar::UInt8(n) => Arc::new(UInt8Array::from(vec![*n])),
-                    GroupByScalar::UInt16(n) => Arc::new(UInt16Array::from(vec![*n])),
-                    GroupByScalar::UInt32(n) => Arc::new(UInt32Array::from(vec![*n])),
-                    GroupByScalar::UInt64(n) => Arc::new(UInt64Array::from(vec![*n])),
-                    GroupByScalar::Utf8(str) => Arc::new(StringArray::from(vec![&**str])),
-                })
-                .collect::<Vec<ArrayRef>>();
-
-            // 3.
-            groups.extend(
-                finalize_aggregation(accumulator_set, mode)
-                    .map_err(DataFusionError::into_arrow_external_error)?,
-            );
+    if accumulators.is_empty() {
+        return common::create_batch_empty(output_schema);
+    }
 
-            Ok(groups)
+    // 1. create an ArrayRef with the keys of all the groups for every group expression
+    // 2. create ArrayRefs with the states or values of all the groups for every aggregate
+    let keys = accumulators.keys();
+    let mut columns = (0..num_group_expr)
+        .map(|i| {
+            let scalars = keys
+                .iter()
+                .map(|key| ScalarValue::from(&key[i]))
+                .collect::<Vec<_>>();
+            ScalarValue::iter_to_array(output_schema.field(i).data_type(), &scalars)
         })
-        // 4.
-        .collect::<ArrowResult<Vec<Vec<ArrayRef>>>>()?;
-
-    let batch = if arrays.len() != 0 {
-        // 5.
-        let columns = concatenate(arrays)?;
-        RecordBatch::try_new(Arc::new(output_schema.to_owned()), columns)?
-    } else {
-        common::create_batch_empty(output_schema)?
-    };
-    Ok(batch)
+        .collect::<Result<Vec<_>>>()
+        .map_err(DataFusionError::into_arrow_external_error)?;
+
+    columns.extend(
+        finalize_grouped_aggregation(&accumulators.accumulators, mode)
+            .map_err(DataFusionError::into_arrow_external_error)?,
+    );
+    RecordBatch::try_new(Arc::new(output_schema.to_owned()), columns)
 }
 
 fn create_accumulators(
@@ -710,7 +951,7 @@ fn finalize_aggregation(
                 .collect::<Result<Vec<_>>>()?;
             Ok(a.iter().flatten().cloned().collect::<Vec<_>>())
         }
-        AggregateMode::Final => {
+        AggregateMode::Final | AggregateMode::FinalPartitioned => {
             // merge the state to the final value
             accumulators
                 .iter()
@@ -720,6 +961,31 @@ fn finalize_aggregation(
     }
 }
 
+/// returns a vector of ArrayRefs with a row per group, where each entry corresponds to
+/// either the final values (mode = Final) or states (mode = Partial) of the groups
+fn finalize_grouped_aggregation(
+    accumulators: &[Box<dyn GroupedAccumulator>],
+    mode: &AggregateMode,
+) -> Result<Vec<ArrayRef>> {
+    match mode {
+        AggregateMode::Partial => {
+            // build the vector of states
+            let states = accumulators
+                .iter()
+                .map(|accumulator| accumulator.state())
+                .collect::<Result<Vec<_>>>()?;
+            Ok(states.into_iter().flatten().collect())
+        }
+        AggregateMode::Final | AggregateMode::FinalPartitioned => {
+            // merge the state to the final value
+            accumulators
+                .iter()
+                .map(|accumulator| accumulator.evaluate())
+                .collect()
+        }
+    }
+}
+
 /// Create a Vec<GroupByScalar> that can be used as a map key
 pub(crate) fn create_key(
     group_by_keys: &[ArrayRef],
@@ -765,6 +1031,10 @@ pub(crate) fn create_key(
                 let array = col.as_any().downcast_ref::<StringArray>().unwrap();
                 vec[i] = GroupByScalar::Utf8(String::from(array.value(row)))
             }
+            DataType::Decimal(precision, scale) => {
+                let array = col.as_any().downcast_ref::<DecimalArray>().unwrap();
+                vec[i] = GroupByScalar::Decimal128(array.value(row), *precision, *scale)
+            }
             _ => {
                 // This is internal because we should have caught this before.
                 return Err(DataFusionError::Internal(
@@ -983,4 +1253,98 @@ mod tests {
 
         check_aggregates(input).await
     }
+
+    #[tokio::test]
+    async fn aggregate_with_spill() -> Result<()> {
+        let input: Arc<dyn ExecutionPlan> =
+            Arc::new(TestYieldingExec { yield_first: false });
+
+        let groups: Vec<(Arc<dyn PhysicalExpr>, String)> =
+            vec![(col("a"), "a".to_string())];
+
+        let aggregates: Vec<Arc<dyn AggregateExpr>> = vec![Arc::new(Avg::new(
+            col("b"),
+            "AVG(b)".to_string(),
+            DataType::Float64,
+        ))];
+
+        // every group exceeds the memory budget, so that the groups of every batch are spilled
+        let partial_aggregate = Arc::new(
+            HashAggregateExec::try_new(
+                AggregateMode::Partial,
+                groups.clone(),
+                aggregates.clone(),
+                input,
+            )?
+            .with_memory_budget(1),
+        );
+
+        let result = common::collect(partial_aggregate.execute(0).await?).await?;
+
+        let mut rows = result
+            .iter()
+            .flat_map(crate::test::format_batch)
+            .collect::<Vec<_>>();
+        rows.sort();
+        assert_eq!(rows, vec!["2,2,2.0", "3,3,7.0", "4,3,11.0"]);
+
+        let merged_aggregate = Arc::new(
+            HashAggregateExec::try_new(
+                AggregateMode::Final,
+                vec![(col("a"), "a".to_string())],
+                aggregates,
+                Arc::new(MergeExec::new(partial_aggregate)),
+            )?
+            .with_memory_budget(1),
+        );
+
+        let result = common::collect(merged_aggregate.execute(0).await?).await?;
+
+        let mut rows = result
+            .iter()
+            .flat_map(crate::test::format_batch)
+            .collect::<Vec<_>>();
+        rows.sort();
+        assert_eq!(
+            rows,
+            vec!["2,1.0", "3,2.3333333333333335", "4,3.6666666666666665"]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn aggregate_memory_limit() -> Result<()> {
+        let input: Arc<dyn ExecutionPlan> =
+            Arc::new(TestYieldingExec { yield_first: false });
+
+        let aggregates: Vec<Arc<dyn AggregateExpr>> = vec![Arc::new(Avg::new(
+            col("b"),
+            "AVG(b)".to_string(),
+            DataType::Float64,
+        ))];
+
+        // the groups are spilled once their memory cannot be reserved, and the groups of
+        // a partition of the keys are spilled again until each group is merged on its own
+        let memory_manager = Arc::new(MemoryManager::new(Some(1)));
+        let partial_aggregate = Arc::new(
+            HashAggregateExec::try_new(
+                AggregateMode::Partial,
+                vec![(col("a"), "a".to_string())],
+                aggregates,
+                input,
+            )?
+            .with_memory_manager(memory_manager.clone()),
+        );
+
+        let result = common::collect(partial_aggregate.execute(0).await?).await?;
+
+        let mut rows = result
+            .iter()
+            .flat_map(crate::test::format_batch)
+            .collect::<Vec<_>>();
+        rows.sort();
+        assert_eq!(rows, vec!["2,2,2.0", "3,3,7.0", "4,3,11.0"]);
+        assert_eq!(memory_manager.reserved(), 0);
+        Ok(())
+    }
 }
@@ -18,30 +18,39 @@
 //! Defines the join plan for executing partitions in parallel and then joining the results
 //! into a set of partitions.
 
+use std::convert::TryFrom;
 use std::sync::Arc;
-use std::{
-    any::Any,
-    collections::{HashMap, HashSet},
-};
+use std::task::Poll;
+use std::{any::Any, collections::HashMap};
 
 use async_trait::async_trait;
+use futures::future::{BoxFuture, FutureExt};
+use futures::lock::Mutex;
 use futures::{Stream, StreamExt, TryStreamExt};
 
-use arrow::array::{make_array, Array, MutableArrayData};
-use arrow::datatypes::{Schema, SchemaRef};
+use arrow::array::{
+    make_array, Array, ArrayDataRef, ArrayRef, BooleanArray, MutableArrayData,
+};
+use arrow::datatypes::{Field, Schema, SchemaRef};
 use arrow::error::Result as ArrowResult;
 use arrow::record_batch::RecordBatch;
 
-use super::{expressions::col, hash_aggregate::create_key};
 use super::{
-    hash_utils::{build_join_schema, check_join_is_valid, JoinOn, JoinType},
+    common,
+    hash_utils::{
+        build_join_schema, check_join_is_valid, normalize_decimal, JoinOn, JoinType,
+    },
     merge::MergeExec,
+    metrics,
 };
+use super::{expressions::col, hash_aggregate::create_key};
 use crate::error::{DataFusionError, Result};
+use crate::execution::memory_manager::{MemoryManager, MemoryReservation};
+use crate::scalar::ScalarValue;
 
 use super::{
-    group_scalar::GroupByScalar, ExecutionPlan, Partitioning, RecordBatchStream,
-    SendableRecordBatchStream,
+    group_scalar::GroupByScalar, ExecutionPlan, Partitioning, PhysicalExpr,
+    RecordBatchStream, SendableRecordBatchStream,
 };
 use ahash::RandomState;
 
@@ -50,12 +59,25 @@ type Index = (usize, usize);
 // A pair (left index, right index)
 // Note that while this is currently equal to `Index`, the `JoinIndex` is semantically different
 // as a left join may issue None indices, in which case
-type JoinIndex = Option<(usize, usize)>;
+pub(crate) type JoinIndex = Option<(usize, usize)>;
 // Maps ["on" value] -> [list of indices with this key's value]
 // E.g. [1, 2] -> [(0, 3), (1, 6), (0, 8)] indicates that (column1, column2) = [1, 2] is true
 // for rows 3 and 8 from batch 0 and row 6 from batch 1.
 type JoinHashMap = HashMap<Vec<GroupByScalar>, Vec<Index>, RandomState>;
-type JoinLeftData = (JoinHashMap, Vec<RecordBatch>);
+// The hash map of the left with its batches, and the reservation of the memory of the batches
+type JoinLeftData = (JoinHashMap, Vec<RecordBatch>, MemoryReservation);
+
+/// How a hash join partitions its inputs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PartitionMode {
+    /// Both sides are partitioned by the hash of their join keys into the same number of
+    /// partitions, and every partition of the left is joined with the same partition of the
+    /// right
+    Partitioned,
+    /// The left is collected into a single hash table, which is built once and shared by
+    /// all the partitions of the right
+    CollectLeft,
+}
 
 /// join execution plan executes partitions in parallel and combines them into a set of
 /// partitions.
@@ -69,30 +91,63 @@ pub struct HashJoinExec {
     on: Vec<(String, String)>,
     /// How the join is performed
     join_type: JoinType,
+    /// Optional predicate that the pairs of rows with equal keys must satisfy
+    filter: Option<Arc<dyn PhysicalExpr>>,
+    /// The schema of the pairs of rows on which `filter` is evaluated
+    filter_schema: SchemaRef,
     /// The schema once the join is applied
     schema: SchemaRef,
+    /// How the inputs are partitioned
+    mode: PartitionMode,
+    /// The hash table of the left in [PartitionMode::CollectLeft], built by the first
+    /// partition whose stream is polled
+    build_side: Arc<Mutex<Option<Arc<JoinLeftData>>>>,
+    /// Manager that the memory of the batches of the left is reserved from
+    memory_manager: Arc<MemoryManager>,
 }
 
 impl HashJoinExec {
     /// Tries to create a new [HashJoinExec].
     /// # Error
     /// This function errors when it is not possible to join the left and right sides on keys `on`.
+    /// `filter` is evaluated on the pairs of rows with equal keys, which have the fields of
+    /// an inner join of both sides.
+    /// In [PartitionMode::Partitioned], both sides must have the same number of partitions.
     pub fn try_new(
         left: Arc<dyn ExecutionPlan>,
         right: Arc<dyn ExecutionPlan>,
         on: &JoinOn,
         join_type: &JoinType,
+        filter: Option<Arc<dyn PhysicalExpr>>,
+        mode: PartitionMode,
     ) -> Result<Self> {
         let left_schema = left.schema();
         let right_schema = right.schema();
         check_join_is_valid(&left_schema, &right_schema, &on)?;
 
+        if mode == PartitionMode::Partitioned
+            && left.output_partitioning().partition_count()
+                != right.output_partitioning().partition_count()
+        {
+            return Err(DataFusionError::Plan(format!(
+                "The sides of a partitioned hash join must have the same number of partitions, but the left has {} and the right has {}",
+                left.output_partitioning().partition_count(),
+                right.output_partitioning().partition_count()
+            )));
+        }
+
         let schema = Arc::new(build_join_schema(
             &left_schema,
             &right_schema,
             on,
             &join_type,
         ));
+        let filter_schema = Arc::new(build_join_schema(
+            &left_schema,
+            &right_schema,
+            on,
+            &JoinType::Inner,
+        ));
 
         let on = on
             .iter()
@@ -104,9 +159,26 @@ impl HashJoinExec {
             right,
             on,
             join_type: join_type.clone(),
+            filter,
+            filter_schema,
             schema,
+            mode,
+            build_side: Arc::new(Mutex::new(None)),
+            memory_manager: Arc::new(MemoryManager::new(None)),
         })
     }
+
+    /// Reserve the memory of the batches of the left from `memory_manager`. The join fails
+    /// once more memory cannot be reserved.
+    pub fn with_memory_manager(mut self, memory_manager: Arc<MemoryManager>) -> Self {
+        self.memory_manager = memory_manager;
+        self
+    }
+
+    /// How the inputs of the join are partitioned
+    pub fn partition_mode(&self) -> PartitionMode {
+        self.mode
+    }
 }
 
 #[async_trait]
@@ -128,12 +200,17 @@ impl ExecutionPlan for HashJoinExec {
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         match children.len() {
-            2 => Ok(Arc::new(HashJoinExec::try_new(
-                children[0].clone(),
-                children[1].clone(),
-                &self.on,
-                &self.join_type,
-            )?)),
+            2 => Ok(Arc::new(
+                HashJoinExec::try_new(
+                    children[0].clone(),
+                    children[1].clone(),
+                    &self.on,
+                    &self.join_type,
+                    self.filter.clone(),
+                    self.mode,
+                )?
+                .with_memory_manager(self.memory_manager.clone()),
+            )),
             _ => Err(DataFusionError::Internal(
                 "HashJoinExec wrong number of children".to_string(),
             )),
@@ -141,61 +218,135 @@ impl ExecutionPlan for HashJoinExec {
     }
 
     fn output_partitioning(&self) -> Partitioning {
-        self.right.output_partitioning()
+        if self.mode == PartitionMode::CollectLeft
+            && needs_visited_left_side(&self.join_type)
+        {
+            // the right is merged into a single partition, see `execute`
+            Partitioning::UnknownPartitioning(1)
+        } else {
+            self.right.output_partitioning()
+        }
     }
 
     async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
-        // merge all parts into a single stream
-        // this is currently expensive as we re-compute this for every part from the right
-        // TODO: Fix this issue: we can't share this state across parts on the right.
-        // We need to change this `execute` to allow sharing state across parts...
-        let merge = MergeExec::new(self.left.clone());
-        let stream = merge.execute(0).await?;
-
-        let on_left = self
-            .on
-            .iter()
-            .map(|on| on.0.clone())
-            .collect::<HashSet<_>>();
-        let on_right = self
-            .on
-            .iter()
-            .map(|on| on.1.clone())
-            .collect::<HashSet<_>>();
-
-        // This operation performs 2 steps at once:
-        // 1. creates a [JoinHashMap] of all batches from the stream
-        // 2. stores the batches in a vector.
-        let initial = (JoinHashMap::default(), Vec::new(), 0);
-        let left_data = stream
-            .try_fold(initial, |mut acc, batch| async {
-                let hash = &mut acc.0;
-                let values = &mut acc.1;
-                let index = acc.2;
-                update_hash(&on_left, &batch, hash, index).unwrap();
-                values.push(batch);
-                acc.2 += 1;
-                Ok(acc)
-            })
-            .await?;
-        // we have the batches and the hash map with their keys. We can how create a stream
-        // over the right that uses this information to issue new batches.
+        // the key columns must be kept in the order of `on` so that the left and right keys
+        // of each row are comparable
+        let on_left = self.on.iter().map(|on| on.0.clone()).collect::<Vec<_>>();
+        let on_right = self.on.iter().map(|on| on.1.clone()).collect::<Vec<_>>();
+
+        // the hash table of the left is built on the first poll of the stream, so that
+        // executing the join neither reads the left nor waits for it
+        let left = self.left.clone();
+        let reservation = self.memory_manager.new_reservation();
+        let left_fut = match self.mode {
+            PartitionMode::CollectLeft => {
+                collect_left_data(left, on_left, reservation, self.build_side.clone())
+                    .boxed()
+            }
+            PartitionMode::Partitioned => {
+                partition_left_data(left, partition, on_left, reservation).boxed()
+            }
+        };
 
-        let stream = self.right.execute(partition).await?;
+        // Whether a row of the left has a match is only known once all the rows of the
+        // right that it may match were seen, which requires a single stream over the right
+        // unless both sides are partitioned by their keys.
+        let stream = if self.mode == PartitionMode::CollectLeft
+            && needs_visited_left_side(&self.join_type)
+        {
+            MergeExec::new(self.right.clone())
+                .execute(partition)
+                .await?
+        } else {
+            self.right.execute(partition).await?
+        };
         Ok(Box::pin(HashJoinStream {
             schema: self.schema.clone(),
             on_right,
             join_type: self.join_type.clone(),
-            left_data: (left_data.0, left_data.1),
+            filter: self.filter.clone(),
+            filter_schema: self.filter_schema.clone(),
+            left_schema: self.left.schema(),
+            right_schema: self.right.schema(),
+            left_fut: Some(left_fut),
+            left_data: None,
+            visited_left_side: vec![],
             right: stream,
+            is_exhausted: false,
         }))
     }
 }
 
+/// Returns the hash table of all the partitions of `left`, in [PartitionMode::CollectLeft]
+/// mode. The first partition of the join to be polled builds it and stores it in
+/// `build_side`, while the other partitions wait for it.
+async fn collect_left_data(
+    left: Arc<dyn ExecutionPlan>,
+    on: Vec<String>,
+    reservation: MemoryReservation,
+    build_side: Arc<Mutex<Option<Arc<JoinLeftData>>>>,
+) -> Result<Arc<JoinLeftData>> {
+    let mut build_side = build_side.lock().await;
+    match build_side.as_ref() {
+        Some(left_data) => Ok(left_data.clone()),
+        None => {
+            // merge all parts into a single stream
+            let stream = MergeExec::new(left).execute(0).await?;
+            let left_data = Arc::new(build_left_data(stream, &on, reservation).await?);
+            *build_side = Some(left_data.clone());
+            Ok(left_data)
+        }
+    }
+}
+
+/// Returns the hash table of the partition `partition` of `left` in
+/// [PartitionMode::Partitioned], as its rows only match the rows of the same partition of
+/// the right
+async fn partition_left_data(
+    left: Arc<dyn ExecutionPlan>,
+    partition: usize,
+    on: Vec<String>,
+    reservation: MemoryReservation,
+) -> Result<Arc<JoinLeftData>> {
+    let stream = left.execute(partition).await?;
+    Ok(Arc::new(build_left_data(stream, &on, reservation).await?))
+}
+
+/// Collects the batches of `stream` and builds the [JoinHashMap] of their keys `on`,
+/// reserving the memory of the batches with `reservation`
+async fn build_left_data(
+    stream: SendableRecordBatchStream,
+    on: &[String],
+    reservation: MemoryReservation,
+) -> Result<JoinLeftData> {
+    // This operation performs 2 steps at once:
+    // 1. creates a [JoinHashMap] of all batches from the stream
+    // 2. stores the batches in a vector.
+    let initial = (JoinHashMap::default(), Vec::new(), 0, reservation);
+    let (hash, batches, _, reservation) = stream
+        .try_fold(initial, |mut acc, batch| async {
+            acc.3
+                .try_grow(common::batch_memory_size(&batch))
+                .map_err(DataFusionError::into_arrow_external_error)?;
+            let hash = &mut acc.0;
+            let values = &mut acc.1;
+            let index = acc.2;
+            update_hash(on, &batch, hash, index)
+                .map_err(DataFusionError::into_arrow_external_error)?;
+            values.push(batch);
+            acc.2 += 1;
+            Ok(acc)
+        })
+        .await?;
+    metrics::record_memory(reservation.size());
+    Ok((hash, batches, reservation))
+}
+
 /// Updates `hash` with new entries from [RecordBatch] evaluated against the expressions `on`,
-/// assuming that the [RecordBatch] corresponds to the `index`th
+/// assuming that the [RecordBatch] corresponds to the `index`th. The rows with a null key
+/// are left out, as they match no row.
 fn update_hash(
-    on: &HashSet<String>,
+    on: &[String],
     batch: &RecordBatch,
     hash: &mut JoinHashMap,
     index: usize,
@@ -213,7 +364,11 @@ fn update_hash(
 
     // update the hash map
     for row in 0..batch.num_rows() {
+        if keys_values.iter().any(|values| values.is_null(row)) {
+            continue;
+        }
         create_key(&keys_values, row, &mut key)?;
+        normalize_key(&mut key);
         match hash.get_mut(&key) {
             Some(v) => v.push((index, row)),
             None => {
@@ -224,18 +379,53 @@ fn update_hash(
     Ok(())
 }
 
+/// Replaces the decimals of `key` by their value without trailing zeros and without
+/// precision, so that the keys of both sides match when their decimal types differ
+fn normalize_key(key: &mut [GroupByScalar]) {
+    for value in key.iter_mut() {
+        if let GroupByScalar::Decimal128(unscaled, _, scale) = value {
+            let (unscaled, scale) = normalize_decimal(*unscaled, *scale);
+            *value = GroupByScalar::Decimal128(unscaled, 0, scale);
+        }
+    }
+}
+
+/// Whether the join must track which rows of the left have a match on the right, as it emits
+/// rows of the left once the right is exhausted.
+fn needs_visited_left_side(join_type: &JoinType) -> bool {
+    match join_type {
+        JoinType::Left | JoinType::Full | JoinType::Semi | JoinType::Anti => true,
+        JoinType::Inner | JoinType::Right => false,
+    }
+}
+
 /// A stream that issues [RecordBatch]es as they arrive from the right  of the join.
 struct HashJoinStream {
     /// Input schema
     schema: Arc<Schema>,
     /// columns from the right used to compute the hash
-    on_right: HashSet<String>,
+    on_right: Vec<String>,
     /// type of the join
     join_type: JoinType,
-    /// information from the left
-    left_data: JoinLeftData,
+    /// predicate that the pairs of rows with equal keys must satisfy
+    filter: Option<Arc<dyn PhysicalExpr>>,
+    /// schema of the pairs of rows on which `filter` is evaluated
+    filter_schema: SchemaRef,
+    /// schema of the left
+    left_schema: SchemaRef,
+    /// schema of the right
+    right_schema: SchemaRef,
+    /// builds the information from the left, until it is polled to completion
+    left_fut: Option<BoxFuture<'static, Result<Arc<JoinLeftData>>>>,
+    /// information from the left, once it is built
+    left_data: Option<Arc<JoinLeftData>>,
+    /// for every batch of the left, whether each of its rows has a match on the right.
+    /// Empty when the join type does not need it.
+    visited_left_side: Vec<Vec<bool>>,
     /// right
     right: SendableRecordBatchStream,
+    /// whether the rows of the left that are only emitted at the end were emitted
+    is_exhausted: bool,
 }
 
 impl RecordBatchStream for HashJoinStream {
@@ -247,25 +437,18 @@ impl RecordBatchStream for HashJoinStream {
 /// Returns a new [RecordBatch] by combining the `left` and `right` according to `indices`.
 /// The resulting batch has [Schema] `schema`.
 /// # Error
-/// This function errors when:
-/// *
-fn build_batch_from_indices(
+/// This function errors when a field of `schema` is on neither side of the join.
+pub(crate) fn build_batch_from_indices(
     schema: &Schema,
-    left: &Vec<RecordBatch>,
-    right: &RecordBatch,
+    left_schema: &Schema,
+    left: &[RecordBatch],
+    right_schema: &Schema,
+    right: &[RecordBatch],
     join_type: &JoinType,
     indices: &[(JoinIndex, JoinIndex)],
-) -> ArrowResult<RecordBatch> {
-    if left.is_empty() {
-        todo!("Create empty record batch");
-    }
-    // this is just for symmetry of the code below.
-    let right = vec![right.clone()];
-
-    let (primary_is_left, primary, secondary) = match join_type {
-        JoinType::Inner | JoinType::Left => (true, left, &right),
-        JoinType::Right => (false, &right, left),
-    };
+) -> Result<RecordBatch> {
+    let left_indices = indices.iter().map(|(left, _)| *left);
+    let right_indices = indices.iter().map(|(_, right)| *right);
 
     // build the columns of the new [RecordBatch]:
     // 1. pick whether the column is from the left or right
@@ -273,70 +456,139 @@ fn build_batch_from_indices(
     let mut columns: Vec<Arc<dyn Array>> = Vec::with_capacity(schema.fields().len());
     for field in schema.fields() {
         // pick the column (left or right) based on the field name.
-        // Note that we take `.data()` to gather the [ArrayData] of each array.
-        let (is_primary, arrays) = match primary[0].schema().index_of(field.name()) {
-            Ok(i) => Ok((true, primary.iter().map(|batch| batch.column(i).data()).collect::<Vec<_>>())),
-            Err(_) => {
-                match secondary[0].schema().index_of(field.name()) {
-                    Ok(i) => Ok((false, secondary.iter().map(|batch| batch.column(i).data()).collect::<Vec<_>>())),
-                    _ => Err(DataFusionError::Internal(
-                        format!("During execution, the column {} was not found in neither the left or right side of the join", field.name()).to_string()
-                    ))
-                }
-            }
-        }.map_err(DataFusionError::into_arrow_external_error)?;
+        let left_column = left_schema.index_of(field.name()).ok();
+        let right_column = right_schema.index_of(field.name()).ok();
 
-        // create a vector of references to be passed to [MutableArrayData]
-        let arrays = arrays
-            .iter()
-            .map(|array| array.as_ref())
-            .collect::<Vec<_>>();
-        let capacity = arrays.iter().map(|array| array.len()).sum();
-        let mut mutable = MutableArrayData::new(arrays, true, capacity);
-
-        let is_left =
-            (is_primary && primary_is_left) || (!is_primary && !primary_is_left);
-        if is_left {
-            // use the left indices
-            for (join_index, _) in indices {
-                match join_index {
-                    Some((batch, row)) => mutable.extend(*batch, *row, *row + 1),
-                    None => mutable.extend_nulls(1),
-                }
+        let array = match (join_type, left_column, right_column) {
+            // on a right join, the right side takes precedence
+            (JoinType::Right, _, Some(i)) => {
+                take_from_batches(field, right, i, right_indices.clone())?
             }
-        } else {
-            // use the right indices
-            for (_, join_index) in indices {
-                match join_index {
-                    Some((batch, row)) => mutable.extend(*batch, *row, *row + 1),
-                    None => mutable.extend_nulls(1),
-                }
+            // on a full join, a key that both sides share is taken from the right for the
+            // rows without a match on the left
+            (JoinType::Full, Some(l), Some(r)) => {
+                let offset = left.len();
+                let mut batches = left.to_vec();
+                batches.extend_from_slice(right);
+                let arrays = batches
+                    .iter()
+                    .enumerate()
+                    .map(|(index, batch)| {
+                        let column = if index < offset { l } else { r };
+                        batch.column(column).data()
+                    })
+                    .collect::<Vec<_>>();
+                let full_indices = indices.iter().map(|(left, right)| match (left, right) {
+                    (Some(index), _) => Some(*index),
+                    (None, Some((batch, row))) => Some((offset + batch, *row)),
+                    (None, None) => None,
+                });
+                take_from_arrays(field, &arrays, full_indices, indices.len())?
+            }
+            (_, Some(i), _) => take_from_batches(field, left, i, left_indices.clone())?,
+            (_, None, Some(i)) => {
+                take_from_batches(field, right, i, right_indices.clone())?
+            }
+            (_, None, None) => {
+                return Err(DataFusionError::Internal(format!(
+                    "During execution, the column {} was not found in neither the left or right side of the join",
+                    field.name()
+                )))
             }
         };
-        let array = make_array(Arc::new(mutable.freeze()));
         columns.push(array);
     }
     Ok(RecordBatch::try_new(Arc::new(schema.clone()), columns)?)
 }
 
-fn build_batch(
-    batch: &RecordBatch,
-    left_data: &JoinLeftData,
-    on_right: &HashSet<String>,
-    join_type: &JoinType,
-    schema: &Schema,
-) -> ArrowResult<RecordBatch> {
-    let mut right_hash =
-        JoinHashMap::with_capacity_and_hasher(batch.num_rows(), RandomState::new());
-    update_hash(on_right, batch, &mut right_hash, 0).unwrap();
+/// Returns an array with the values of column `column` of `batches` at `indices`, where
+/// each index is a (batch, row) pair and `None` stands for a null value.
+fn take_from_batches(
+    field: &Field,
+    batches: &[RecordBatch],
+    column: usize,
+    indices: impl ExactSizeIterator<Item = JoinIndex>,
+) -> Result<ArrayRef> {
+    let arrays = batches
+        .iter()
+        .map(|batch| batch.column(column).data())
+        .collect::<Vec<_>>();
+    let len = indices.len();
+    take_from_arrays(field, &arrays, indices, len)
+}
 
-    let indices = build_join_indexes(&left_data.0, &right_hash, join_type).unwrap();
+/// Returns an array with the values of `arrays` at `indices`, where each index is an
+/// (array, row) pair and `None` stands for a null value.
+fn take_from_arrays(
+    field: &Field,
+    arrays: &[ArrayDataRef],
+    indices: impl Iterator<Item = JoinIndex>,
+    len: usize,
+) -> Result<ArrayRef> {
+    if arrays.is_empty() {
+        // there is nothing to take from (e.g. the left is empty), so all values are null
+        return Ok(ScalarValue::try_from(field.data_type())?.to_array_of_size(len));
+    }
 
-    build_batch_from_indices(schema, &left_data.1, &batch, join_type, &indices)
+    // create a vector of references to be passed to [MutableArrayData]
+    let arrays = arrays
+        .iter()
+        .map(|array| array.as_ref())
+        .collect::<Vec<_>>();
+    let mut mutable = MutableArrayData::new(arrays, true, len);
+    for index in indices {
+        match index {
+            Some((array, row)) => mutable.extend(array, row, row + 1),
+            None => mutable.extend_nulls(1),
+        }
+    }
+    Ok(make_array(Arc::new(mutable.freeze())))
+}
+
+impl HashJoinStream {
+    /// Joins a batch of the right with the left
+    fn build_batch(
+        &mut self,
+        left_data: &JoinLeftData,
+        batch: &RecordBatch,
+    ) -> Result<RecordBatch> {
+        let mut right_hash =
+            JoinHashMap::with_capacity_and_hasher(batch.num_rows(), RandomState::new());
+        update_hash(&self.on_right, batch, &mut right_hash, 0)?;
+
+        let matches = build_join_matches(&left_data.0, &right_hash);
+        let matches = match &self.filter {
+            Some(filter) => apply_join_filter(
+                filter,
+                &self.filter_schema,
+                &self.left_schema,
+                &left_data.1,
+                batch,
+                matches,
+            )?,
+            None => matches,
+        };
+
+        let indices = build_join_indexes(
+            matches,
+            batch.num_rows(),
+            &self.join_type,
+            &mut self.visited_left_side,
+        );
+
+        build_batch_from_indices(
+            &self.schema,
+            &self.left_schema,
+            &left_data.1,
+            &batch.schema(),
+            &[batch.clone()],
+            &self.join_type,
+            &indices,
+        )
+    }
 }
 
-/// returns a vector with (index from left, index from right).
-/// The size of this vector corresponds to the total size of a joined batch
+/// returns the pairs of (index from left, index from right) of the rows with equal keys.
 // For a join on column A:
 // left       right
 //     batch 1
@@ -362,99 +614,213 @@ fn build_batch(
 // (0, 0)     (1, 2)
 // (1, 1)     (1, 1)
 // (1, 0)     (1, 2)
+fn build_join_matches(left: &JoinHashMap, right: &JoinHashMap) -> Vec<(Index, Index)> {
+    let mut matches = Vec::new(); // unknown a prior size
+    for (key, right_indexes) in right {
+        if let Some(left_indexes) = left.get(key) {
+            // for every item on the left and right with this key, add the respective pair
+            left_indexes.iter().for_each(|x| {
+                right_indexes.iter().for_each(|y| {
+                    matches.push((*x, *y));
+                })
+            })
+        }
+    }
+    matches
+}
+
+/// returns the pairs of `matches` that satisfy `filter`. `filter` is evaluated on a batch
+/// with the columns of both sides of each pair.
+fn apply_join_filter(
+    filter: &Arc<dyn PhysicalExpr>,
+    filter_schema: &Schema,
+    left_schema: &Schema,
+    left: &[RecordBatch],
+    right: &RecordBatch,
+    matches: Vec<(Index, Index)>,
+) -> Result<Vec<(Index, Index)>> {
+    if matches.is_empty() {
+        return Ok(matches);
+    }
+
+    let indices = matches
+        .iter()
+        .map(|(l, r)| (Some(*l), Some(*r)))
+        .collect::<Vec<_>>();
+    let pairs = build_batch_from_indices(
+        filter_schema,
+        left_schema,
+        left,
+        &right.schema(),
+        &[right.clone()],
+        &JoinType::Inner,
+        &indices,
+    )?;
+
+    let mask = filter.evaluate(&pairs)?.into_array(pairs.num_rows());
+    let mask = mask
+        .as_any()
+        .downcast_ref::<BooleanArray>()
+        .ok_or_else(|| {
+            DataFusionError::Internal(
+                "The filter of a join must evaluate to a boolean array".to_string(),
+            )
+        })?;
+
+    // a null result of the filter does not satisfy it
+    Ok(matches
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| mask.is_valid(*i) && mask.value(*i))
+        .map(|(_, pair)| pair)
+        .collect())
+}
+
+/// returns a vector with (index from left, index from right) of the rows that the join emits
+/// for a batch of the right with `right_rows` rows, given the pairs of rows that match.
+/// The size of this vector corresponds to the total size of a joined batch.
+// The rows of the left with a match are marked in `visited_left_side` (when it is tracked),
+// as the rows of the left without a match are only known once the right is exhausted.
 fn build_join_indexes(
-    left: &JoinHashMap,
-    right: &JoinHashMap,
+    matches: Vec<(Index, Index)>,
+    right_rows: usize,
     join_type: &JoinType,
-) -> Result<Vec<(JoinIndex, JoinIndex)>> {
+    visited_left_side: &mut Vec<Vec<bool>>,
+) -> Vec<(JoinIndex, JoinIndex)> {
+    if needs_visited_left_side(join_type) {
+        for ((batch, row), _) in &matches {
+            visited_left_side[*batch][*row] = true;
+        }
+    }
+
     match join_type {
-        JoinType::Inner => {
-            // inner => key intersection
-            // unfortunately rust does not support intersection of map keys :(
-            let left_set: HashSet<Vec<GroupByScalar>> = left.keys().cloned().collect();
-            let left_right: HashSet<Vec<GroupByScalar>> = right.keys().cloned().collect();
-            let inner = left_set.intersection(&left_right);
-
-            let mut indexes = Vec::new(); // unknown a prior size
-            for key in inner {
-                // the unwrap never happens by construction of the key
-                let left_indexes = left.get(key).unwrap();
-                let right_indexes = right.get(key).unwrap();
-
-                // for every item on the left and right with this key, add the respective pair
-                left_indexes.iter().for_each(|x| {
-                    right_indexes.iter().for_each(|y| {
-                        // on an inner join, left and right indices are present
-                        indexes.push((Some(*x), Some(*y)));
-                    })
-                })
+        JoinType::Inner | JoinType::Left => matches
+            .into_iter()
+            .map(|(l, r)| (Some(l), Some(r)))
+            .collect(),
+        JoinType::Right | JoinType::Full => {
+            // the rows of the right without a match are padded with nulls
+            let mut visited_right_side = vec![false; right_rows];
+            for (_, (_, row)) in &matches {
+                visited_right_side[*row] = true;
             }
-            Ok(indexes)
-        }
-        JoinType::Left => {
-            // left => left keys
-            let mut indexes = Vec::new(); // unknown a prior size
-            for (key, left_indexes) in left {
-                // for every item on the left and right with this key, add the respective pair
-                if let Some(right_indexes) = right.get(key) {
-                    left_indexes.iter().for_each(|x| {
-                        right_indexes.iter().for_each(|y| {
-                            // on an inner join, left and right indices are present
-                            indexes.push((Some(*x), Some(*y)));
-                        })
-                    })
-                } else {
-                    // key not on the right => push Nones
-                    left_indexes.iter().for_each(|x| {
-                        indexes.push((Some(*x), None));
-                    })
+            let mut indexes = matches
+                .into_iter()
+                .map(|(l, r)| (Some(l), Some(r)))
+                .collect::<Vec<_>>();
+            for (row, visited) in visited_right_side.iter().enumerate() {
+                if !visited {
+                    indexes.push((None, Some((0, row))));
                 }
             }
-            Ok(indexes)
+            indexes
         }
-        JoinType::Right => {
-            // right => right keys
-            let mut indexes = Vec::new(); // unknown a prior size
-            for (key, right_indexes) in right {
-                // for every item on the left and right with this key, add the respective pair
-                if let Some(left_indexes) = left.get(key) {
-                    left_indexes.iter().for_each(|x| {
-                        right_indexes.iter().for_each(|y| {
-                            // on an inner join, left and right indices are present
-                            indexes.push((Some(*x), Some(*y)));
-                        })
-                    })
-                } else {
-                    // key not on the left => push Nones
-                    right_indexes.iter().for_each(|x| {
-                        indexes.push((None, Some(*x)));
-                    })
-                }
+        // semi and anti joins only emit rows of the left once the right is exhausted
+        JoinType::Semi | JoinType::Anti => vec![],
+    }
+}
+
+/// returns the indices of the rows of the left that are emitted once the right is exhausted:
+/// the rows with a match on semi joins, and the rows without a match on left, full and anti joins.
+fn build_final_indexes(
+    visited_left_side: &[Vec<bool>],
+    join_type: &JoinType,
+) -> Vec<(JoinIndex, JoinIndex)> {
+    let emit_visited = match join_type {
+        JoinType::Semi => true,
+        _ => false,
+    };
+    let mut indexes = Vec::new();
+    for (batch, visited) in visited_left_side.iter().enumerate() {
+        for (row, is_visited) in visited.iter().enumerate() {
+            if *is_visited == emit_visited {
+                indexes.push((Some((batch, row)), None));
             }
-            Ok(indexes)
         }
     }
+    indexes
 }
 
 impl Stream for HashJoinStream {
     type Item = ArrowResult<RecordBatch>;
 
     fn poll_next(
-        mut self: std::pin::Pin<&mut Self>,
+        self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Option<Self::Item>> {
-        self.right
-            .poll_next_unpin(cx)
-            .map(|maybe_batch| match maybe_batch {
-                Some(Ok(batch)) => Some(build_batch(
-                    &batch,
-                    &self.left_data,
-                    &self.on_right,
-                    &self.join_type,
-                    &self.schema,
-                )),
-                other => other,
-            })
+        let stream = self.get_mut();
+        let left_data = match &stream.left_data {
+            Some(left_data) => left_data.clone(),
+            None => {
+                let left_fut = match stream.left_fut.as_mut() {
+                    Some(left_fut) => left_fut,
+                    // the left failed to be built
+                    None => return Poll::Ready(None),
+                };
+                let result = match left_fut.poll_unpin(cx) {
+                    Poll::Ready(result) => result,
+                    Poll::Pending => return Poll::Pending,
+                };
+                stream.left_fut = None;
+                let left_data = match result {
+                    Ok(left_data) => left_data,
+                    Err(e) => {
+                        return Poll::Ready(Some(Err(
+                            DataFusionError::into_arrow_external_error(e),
+                        )))
+                    }
+                };
+                if needs_visited_left_side(&stream.join_type) {
+                    stream.visited_left_side = left_data
+                        .1
+                        .iter()
+                        .map(|batch| vec![false; batch.num_rows()])
+                        .collect();
+                }
+                stream.left_data = Some(left_data.clone());
+                left_data
+            }
+        };
+        loop {
+            match stream.right.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(batch))) => {
+                    let result = stream.build_batch(&left_data, &batch);
+                    match (&stream.join_type, result) {
+                        // semi and anti joins only emit rows once the right is exhausted
+                        (JoinType::Semi, Ok(_)) | (JoinType::Anti, Ok(_)) => continue,
+                        (_, result) => {
+                            return Poll::Ready(Some(
+                                result
+                                    .map_err(DataFusionError::into_arrow_external_error),
+                            ))
+                        }
+                    }
+                }
+                Poll::Ready(None) => {
+                    if stream.is_exhausted || !needs_visited_left_side(&stream.join_type)
+                    {
+                        return Poll::Ready(None);
+                    }
+                    stream.is_exhausted = true;
+
+                    let indices =
+                        build_final_indexes(&stream.visited_left_side, &stream.join_type);
+                    let result = build_batch_from_indices(
+                        &stream.schema,
+                        &stream.left_schema,
+                        &left_data.1,
+                        &stream.right_schema,
+                        &[],
+                        &stream.join_type,
+                        &indices,
+                    );
+                    return Poll::Ready(Some(
+                        result.map_err(DataFusionError::into_arrow_external_error),
+                    ));
+                }
+                other => return other,
+            }
+        }
     }
 }
 
@@ -462,11 +828,16 @@ impl Stream for HashJoinStream {
 mod tests {
 
     use crate::{
-        physical_plan::{common, memory::MemoryExec},
+        logical_plan::Operator,
+        physical_plan::{
+            common, expressions::binary, memory::MemoryExec, repartition::RepartitionExec,
+        },
         test::{build_table_i32, columns, format_batch},
     };
 
     use super::*;
+    use arrow::array::Int32Array;
+    use arrow::datatypes::DataType;
     use std::collections::HashSet;
     use std::sync::Arc;
 
@@ -490,7 +861,46 @@ mod tests {
             .iter()
             .map(|(l, r)| (l.to_string(), r.to_string()))
             .collect();
-        HashJoinExec::try_new(left, right, &on, join_type)
+        HashJoinExec::try_new(
+            left,
+            right,
+            &on,
+            join_type,
+            None,
+            PartitionMode::CollectLeft,
+        )
+    }
+
+    /// Joins `left` and `right` with the filter `c1 > a2`
+    fn join_with_filter(
+        left: Arc<dyn ExecutionPlan>,
+        right: Arc<dyn ExecutionPlan>,
+        on: &[(&str, &str)],
+        join_type: &JoinType,
+    ) -> Result<HashJoinExec> {
+        let on: Vec<_> = on
+            .iter()
+            .map(|(l, r)| (l.to_string(), r.to_string()))
+            .collect();
+        let filter_schema =
+            build_join_schema(&left.schema(), &right.schema(), &on, &JoinType::Inner);
+        let filter = binary(col("c1"), Operator::Gt, col("a2"), &filter_schema)?;
+        HashJoinExec::try_new(
+            left,
+            right,
+            &on,
+            join_type,
+            Some(filter),
+            PartitionMode::CollectLeft,
+        )
+    }
+
+    /// Returns the rows of all `batches`
+    fn format_batches(batches: &[RecordBatch]) -> Vec<String> {
+        batches
+            .iter()
+            .flat_map(|batch| format_batch(batch))
+            .collect()
     }
 
     /// Asserts that the rows are the same, taking into account that their order
@@ -536,6 +946,34 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn join_memory_limit() -> Result<()> {
+        let left = build_table(
+            ("a1", &vec![1, 2, 3]),
+            ("b1", &vec![4, 5, 5]),
+            ("c1", &vec![7, 8, 9]),
+        );
+        let right = build_table(
+            ("a2", &vec![10, 20, 30]),
+            ("b1", &vec![4, 5, 6]),
+            ("c2", &vec![70, 80, 90]),
+        );
+        let on = &[("b1", "b1")];
+
+        // the batches of the left do not fit in the memory limit
+        let memory_manager = Arc::new(MemoryManager::new(Some(1)));
+        let join = join(left, right, on, &JoinType::Inner)?
+            .with_memory_manager(memory_manager.clone());
+
+        // the left is only built once the stream of the join is polled
+        let stream = join.execute(0).await?;
+        let e = common::collect(stream).await.unwrap_err();
+        assert!(e.to_string().contains("Resources exhausted"), "{}", e);
+        assert_eq!(memory_manager.reserved(), 0);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn join_inner_one_no_shared_column_names() -> Result<()> {
         let left = build_table(
@@ -707,7 +1145,8 @@ mod tests {
         let stream = join.execute(0).await?;
         let batches = common::collect(stream).await?;
 
-        let result = format_batch(&batches[0]);
+        // the rows of the left without a match are emitted once the right is exhausted
+        let result = format_batches(&batches);
         let expected = vec!["1,4,7,10,70", "2,5,8,20,80", "3,7,9,NULL,NULL"];
 
         assert_same_rows(&result, &expected);
@@ -744,4 +1183,421 @@ mod tests {
 
         Ok(())
     }
+
+    /// Test where the right has 2 parts: the rows of the left without a match must only be
+    /// emitted once, after all parts of the right were seen
+    #[tokio::test]
+    async fn join_left_two_parts_right() -> Result<()> {
+        let left = build_table(
+            ("a1", &vec![1, 2, 3]),
+            ("b1", &vec![4, 5, 7]), // 7 does not exist on the right
+            ("c1", &vec![7, 8, 9]),
+        );
+
+        let batch1 = build_table_i32(
+            ("a2", &vec![10, 20]),
+            ("b1", &vec![4, 6]),
+            ("c2", &vec![70, 80]),
+        );
+        let batch2 =
+            build_table_i32(("a2", &vec![30]), ("b1", &vec![5]), ("c2", &vec![90]));
+        let schema = batch1.schema();
+        let right = Arc::new(
+            MemoryExec::try_new(&vec![vec![batch1], vec![batch2]], schema, None).unwrap(),
+        );
+        let on = &[("b1", "b1")];
+
+        let join = join(left, right, on, &JoinType::Left)?;
+        assert_eq!(join.output_partitioning().partition_count(), 1);
+
+        let stream = join.execute(0).await?;
+        let batches = common::collect(stream).await?;
+
+        let result = format_batches(&batches);
+        let expected = vec!["1,4,7,10,70", "2,5,8,30,90", "3,7,9,NULL,NULL"];
+        assert_eq!(result.len(), expected.len());
+        assert_same_rows(&result, &expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_full_one() -> Result<()> {
+        let left = build_table(
+            ("a1", &vec![1, 2, 3]),
+            ("b1", &vec![4, 5, 7]), // 7 does not exist on the right
+            ("c1", &vec![7, 8, 9]),
+        );
+        let right = build_table(
+            ("a2", &vec![10, 20, 30]),
+            ("b1", &vec![4, 5, 6]), // 6 does not exist on the left
+            ("c2", &vec![70, 80, 90]),
+        );
+        let on = &[("b1", "b1")];
+
+        let join = join(left, right, on, &JoinType::Full)?;
+
+        let columns = columns(&join.schema());
+        assert_eq!(columns, vec!["a1", "b1", "c1", "a2", "c2"]);
+
+        let stream = join.execute(0).await?;
+        let batches = common::collect(stream).await?;
+
+        // the key of the row without a match on the left is taken from the right
+        let result = format_batches(&batches);
+        let expected = vec![
+            "1,4,7,10,70",
+            "2,5,8,20,80",
+            "NULL,6,NULL,30,90",
+            "3,7,9,NULL,NULL",
+        ];
+        assert_eq!(result.len(), expected.len());
+        assert_same_rows(&result, &expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_semi() -> Result<()> {
+        let left = build_table(
+            ("a1", &vec![1, 2, 3, 4]),
+            ("b1", &vec![4, 5, 5, 7]), // 7 does not exist on the right
+            ("c1", &vec![7, 8, 9, 10]),
+        );
+        let right = build_table(
+            ("a2", &vec![10, 20, 30]),
+            ("b1", &vec![4, 5, 5]), // 5 exists twice on the right
+            ("c2", &vec![70, 80, 90]),
+        );
+        let on = &[("b1", "b1")];
+
+        let join = join(left, right, on, &JoinType::Semi)?;
+
+        let columns = columns(&join.schema());
+        assert_eq!(columns, vec!["a1", "b1", "c1"]);
+
+        let stream = join.execute(0).await?;
+        let batches = common::collect(stream).await?;
+
+        // every row of the left is emitted at most once
+        let result = format_batches(&batches);
+        let expected = vec!["1,4,7", "2,5,8", "3,5,9"];
+        assert_eq!(result.len(), expected.len());
+        assert_same_rows(&result, &expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_anti() -> Result<()> {
+        let left = build_table(
+            ("a1", &vec![1, 2, 3, 4]),
+            ("b1", &vec![4, 5, 5, 7]), // 7 does not exist on the right
+            ("c1", &vec![7, 8, 9, 10]),
+        );
+        let right = build_table(
+            ("a2", &vec![10, 20, 30]),
+            ("b1", &vec![4, 5, 6]),
+            ("c2", &vec![70, 80, 90]),
+        );
+        let on = &[("b1", "b1")];
+
+        let join = join(left, right, on, &JoinType::Anti)?;
+
+        let columns = columns(&join.schema());
+        assert_eq!(columns, vec!["a1", "b1", "c1"]);
+
+        let stream = join.execute(0).await?;
+        let batches = common::collect(stream).await?;
+
+        let result = format_batches(&batches);
+        assert_eq!(result, vec!["4,7,10"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_null_keys() -> Result<()> {
+        let left_schema = Arc::new(Schema::new(vec![
+            Field::new("a1", DataType::Int32, false),
+            Field::new("b1", DataType::Int32, true),
+        ]));
+        let left_batch = RecordBatch::try_new(
+            left_schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3])),
+                Arc::new(Int32Array::from(vec![None, Some(1), Some(2)])),
+            ],
+        )?;
+        let right_schema = Arc::new(Schema::new(vec![
+            Field::new("b2", DataType::Int32, true),
+            Field::new("c2", DataType::Int32, false),
+        ]));
+        let right_batch = RecordBatch::try_new(
+            right_schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![None, Some(0), Some(2)])),
+                Arc::new(Int32Array::from(vec![10, 20, 30])),
+            ],
+        )?;
+
+        // a null key matches neither a null key nor the default value of its type
+        let cases = vec![
+            (JoinType::Inner, vec!["3,2,2,30"]),
+            (
+                JoinType::Left,
+                vec!["3,2,2,30", "1,NULL,NULL,NULL", "2,1,NULL,NULL"],
+            ),
+            (
+                JoinType::Right,
+                vec!["3,2,2,30", "NULL,NULL,NULL,10", "NULL,NULL,0,20"],
+            ),
+            (
+                JoinType::Full,
+                vec![
+                    "3,2,2,30",
+                    "1,NULL,NULL,NULL",
+                    "2,1,NULL,NULL",
+                    "NULL,NULL,NULL,10",
+                    "NULL,NULL,0,20",
+                ],
+            ),
+            (JoinType::Semi, vec!["3,2"]),
+            (JoinType::Anti, vec!["1,NULL", "2,1"]),
+        ];
+
+        for (join_type, expected) in cases {
+            let left = Arc::new(MemoryExec::try_new(
+                &vec![vec![left_batch.clone()]],
+                left_schema.clone(),
+                None,
+            )?);
+            let right = Arc::new(MemoryExec::try_new(
+                &vec![vec![right_batch.clone()]],
+                right_schema.clone(),
+                None,
+            )?);
+            let join = join(left, right, &[("b1", "b2")], &join_type)?;
+
+            let stream = join.execute(0).await?;
+            let batches = common::collect(stream).await?;
+
+            let result = format_batches(&batches);
+            assert_eq!(result.len(), expected.len(), "{:?}", join_type);
+            assert_same_rows(&result, &expected);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_right_empty_left() -> Result<()> {
+        let batch = build_table_i32(("a1", &vec![1]), ("b1", &vec![4]), ("c1", &vec![7]));
+        let schema = batch.schema();
+        // the left has no batches at all
+        let left = Arc::new(MemoryExec::try_new(&vec![vec![]], schema, None).unwrap());
+        let right = build_table(
+            ("a2", &vec![10, 20]),
+            ("b1", &vec![4, 5]),
+            ("c2", &vec![70, 80]),
+        );
+        let on = &[("b1", "b1")];
+
+        let join = join(left, right, on, &JoinType::Right)?;
+
+        let stream = join.execute(0).await?;
+        let batches = common::collect(stream).await?;
+
+        let result = format_batches(&batches);
+        let expected = vec!["NULL,NULL,10,4,70", "NULL,NULL,20,5,80"];
+        assert_same_rows(&result, &expected);
+
+        Ok(())
+    }
+
+    fn filter_tables() -> (Arc<dyn ExecutionPlan>, Arc<dyn ExecutionPlan>) {
+        let left = build_table(
+            ("a1", &vec![1, 2, 3]),
+            ("b1", &vec![4, 5, 5]),
+            ("c1", &vec![7, 8, 9]),
+        );
+        let right = build_table(
+            ("a2", &vec![10, 8, 30]),
+            ("b1", &vec![4, 5, 6]),
+            ("c2", &vec![70, 80, 90]),
+        );
+        (left, right)
+    }
+
+    #[tokio::test]
+    async fn join_inner_with_filter() -> Result<()> {
+        let (left, right) = filter_tables();
+        let join = join_with_filter(left, right, &[("b1", "b1")], &JoinType::Inner)?;
+
+        let stream = join.execute(0).await?;
+        let batches = common::collect(stream).await?;
+
+        // only the pair (9, 8) satisfies `c1 > a2`
+        let result = format_batches(&batches);
+        assert_eq!(result, vec!["3,5,9,8,80"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_left_with_filter() -> Result<()> {
+        let (left, right) = filter_tables();
+        let join = join_with_filter(left, right, &[("b1", "b1")], &JoinType::Left)?;
+
+        let stream = join.execute(0).await?;
+        let batches = common::collect(stream).await?;
+
+        // the rows of the left without a pair that satisfies the filter are padded with nulls
+        let result = format_batches(&batches);
+        let expected = vec!["1,4,7,NULL,NULL", "2,5,8,NULL,NULL", "3,5,9,8,80"];
+        assert_eq!(result.len(), expected.len());
+        assert_same_rows(&result, &expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_right_with_filter() -> Result<()> {
+        let (left, right) = filter_tables();
+        let join = join_with_filter(left, right, &[("b1", "b1")], &JoinType::Right)?;
+
+        let stream = join.execute(0).await?;
+        let batches = common::collect(stream).await?;
+
+        let result = format_batches(&batches);
+        let expected = vec!["NULL,NULL,10,4,70", "3,9,8,5,80", "NULL,NULL,30,6,90"];
+        assert_eq!(result.len(), expected.len());
+        assert_same_rows(&result, &expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_full_with_filter() -> Result<()> {
+        let (left, right) = filter_tables();
+        let join = join_with_filter(left, right, &[("b1", "b1")], &JoinType::Full)?;
+
+        let stream = join.execute(0).await?;
+        let batches = common::collect(stream).await?;
+
+        let result = format_batches(&batches);
+        let expected = vec![
+            "3,5,9,8,80",
+            "NULL,4,NULL,10,70",
+            "NULL,6,NULL,30,90",
+            "1,4,7,NULL,NULL",
+            "2,5,8,NULL,NULL",
+        ];
+        assert_eq!(result.len(), expected.len());
+        assert_same_rows(&result, &expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_anti_with_filter() -> Result<()> {
+        let (left, right) = filter_tables();
+        let join = join_with_filter(left, right, &[("b1", "b1")], &JoinType::Anti)?;
+
+        let stream = join.execute(0).await?;
+        let batches = common::collect(stream).await?;
+
+        let result = format_batches(&batches);
+        let expected = vec!["1,4,7", "2,5,8"];
+        assert_same_rows(&result, &expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_full_partitioned() -> Result<()> {
+        let left = build_table(
+            ("a1", &vec![1, 2, 3, 4]),
+            ("b1", &vec![4, 5, 7, 5]), // 7 does not exist on the right
+            ("c1", &vec![7, 8, 9, 10]),
+        );
+        let right = build_table(
+            ("a2", &vec![10, 20, 30]),
+            ("b1", &vec![4, 5, 6]), // 6 does not exist on the left
+            ("c2", &vec![70, 80, 90]),
+        );
+        // both sides are partitioned by their keys
+        let left = Arc::new(RepartitionExec::try_new(
+            left,
+            Partitioning::Hash(vec![col("b1")], 3),
+        )?);
+        let right = Arc::new(RepartitionExec::try_new(
+            right,
+            Partitioning::Hash(vec![col("b1")], 3),
+        )?);
+        let on = vec![("b1".to_string(), "b1".to_string())];
+
+        let join = HashJoinExec::try_new(
+            left,
+            right,
+            &on,
+            &JoinType::Full,
+            None,
+            PartitionMode::Partitioned,
+        )?;
+        assert_eq!(join.output_partitioning().partition_count(), 3);
+
+        // the partitions are all executed before any of them is consumed, as executing a
+        // partition does not build its hash table
+        let mut streams = vec![];
+        for partition in 0..3 {
+            streams.push(join.execute(partition).await?);
+        }
+        let mut batches = vec![];
+        for stream in streams.into_iter().rev() {
+            batches.extend(common::collect(stream).await?);
+        }
+
+        let result = format_batches(&batches);
+        let expected = vec![
+            "1,4,7,10,70",
+            "2,5,8,20,80",
+            "4,5,10,20,80",
+            "3,7,9,NULL,NULL",
+            "NULL,6,NULL,30,90",
+        ];
+
+        assert_same_rows(&result, &expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn join_partitioned_requires_same_partitions() {
+        let left = build_table(
+            ("a1", &vec![1, 2, 3]),
+            ("b1", &vec![4, 5, 7]),
+            ("c1", &vec![7, 8, 9]),
+        );
+        let right = build_table(
+            ("a2", &vec![10, 20, 30]),
+            ("b1", &vec![4, 5, 6]),
+            ("c2", &vec![70, 80, 90]),
+        );
+        let right = Arc::new(
+            RepartitionExec::try_new(right, Partitioning::Hash(vec![col("b1")], 2))
+                .unwrap(),
+        );
+        let on = vec![("b1".to_string(), "b1".to_string())];
+
+        let join = HashJoinExec::try_new(
+            left,
+            right,
+            &on,
+            &JoinType::Inner,
+            None,
+            PartitionMode::Partitioned,
+        );
+        assert!(join.is_err());
+    }
 }
@@ -18,8 +18,14 @@
 //! Functionality used both on logical and physical plans
 
 use crate::error::{DataFusionError, Result};
-use arrow::datatypes::{Field, Schema};
+use arrow::array::{
+    Array, ArrayRef, BooleanArray, DecimalArray, Int16Array, Int32Array, Int64Array,
+    Int8Array, StringArray, UInt16Array, UInt32Array, UInt64Array, UInt8Array,
+};
+use arrow::datatypes::{DataType, Field, Schema};
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
 
 /// All valid types of joins.
 #[derive(Clone, Debug)]
@@ -30,6 +36,12 @@ pub enum JoinType {
     Left,
     /// Right
     Right,
+    /// Full
+    Full,
+    /// Semi: rows of the left that have a match on the right
+    Semi,
+    /// Anti: rows of the left that do not have a match on the right
+    Anti,
 }
 
 /// The on clause of the join, as vector of (left, right) columns.
@@ -89,7 +101,8 @@ fn check_join_set_is_valid(
 }
 
 /// Creates a schema for a join operation.
-/// The fields from the left side are first
+/// The fields from the left side are first. Semi and anti joins only return the fields
+/// of the left side.
 pub fn build_join_schema(
     left: &Schema,
     right: &Schema,
@@ -97,8 +110,10 @@ pub fn build_join_schema(
     join_type: &JoinType,
 ) -> Schema {
     let fields: Vec<Field> = match join_type {
-        JoinType::Inner | JoinType::Left => {
-            // remove right-side join keys if they have the same names as the left-side
+        JoinType::Inner | JoinType::Left | JoinType::Full => {
+            // remove right-side join keys if they have the same names as the left-side.
+            // On a full join, the values of these keys are taken from the right side
+            // for the rows that have no match on the left side.
             let duplicate_keys = &on
                 .iter()
                 .filter(|(l, r)| l == r)
@@ -133,14 +148,116 @@ pub fn build_join_schema(
             // left then right
             left_fields.chain(right_fields).cloned().collect()
         }
+        JoinType::Semi | JoinType::Anti => left.fields().clone(),
     };
     Schema::new(fields)
 }
 
+/// Creates a schema for a cross join operation: the fields of the left side followed by the
+/// fields of the right side.
+/// # Error
+/// This function errors when both sides have columns with the same name.
+pub fn build_cross_join_schema(left: &Schema, right: &Schema) -> Result<Schema> {
+    let left_names: HashSet<&String> = left.fields().iter().map(|f| f.name()).collect();
+    let collisions = right
+        .fields()
+        .iter()
+        .map(|f| f.name())
+        .filter(|name| left_names.contains(name))
+        .collect::<HashSet<_>>();
+
+    if collisions.len() > 0 {
+        return Err(DataFusionError::Plan(format!(
+            "The left schema and the right schema of a cross join have the following columns with the same name: {:?}. Consider aliasing them.",
+            collisions,
+        )));
+    };
+
+    Ok(Schema::new(
+        left.fields()
+            .iter()
+            .chain(right.fields().iter())
+            .cloned()
+            .collect(),
+    ))
+}
+
+/// Combines the hash of a value with the hash of the previous values of a row
+fn combine_hashes(l: u64, r: u64) -> u64 {
+    let hash = (17 * 37u64).wrapping_add(l);
+    hash.wrapping_mul(37).wrapping_add(r)
+}
+
+macro_rules! hash_array {
+    ($ARRAY_TYPE:ident, $COLUMN:expr, $HASHES:ident) => {{
+        let array = $COLUMN.as_any().downcast_ref::<$ARRAY_TYPE>().unwrap();
+        for (row, hash) in $HASHES.iter_mut().enumerate() {
+            // null values leave the hash of the row unchanged
+            if array.is_valid(row) {
+                let mut hasher = DefaultHasher::new();
+                array.value(row).hash(&mut hasher);
+                *hash = combine_hashes(*hash, hasher.finish());
+            }
+        }
+    }};
+}
+
+/// Returns the unscaled value and the scale of the decimal `value` with scale `scale`
+/// without its trailing zeros, which equal decimals of any scale have in common
+pub fn normalize_decimal(value: i128, scale: usize) -> (i128, usize) {
+    let (mut value, mut scale) = (value, scale);
+    while scale > 0 && value % 10 == 0 {
+        value /= 10;
+        scale -= 1;
+    }
+    (value, scale)
+}
+
+/// Creates the hash of every row of `arrays`, which all have the same length.
+/// The hashes do not depend on the process nor on the operator that computes them, so that
+/// rows with the same values are assigned to the same partition by every operator that
+/// partitions them by hash. Decimals are hashed by their value, whatever their scale.
+pub fn create_hashes(arrays: &[ArrayRef]) -> Result<Vec<u64>> {
+    let num_rows = arrays.first().map(|array| array.len()).unwrap_or(0);
+    let mut hashes = vec![0; num_rows];
+    for column in arrays {
+        match column.data_type() {
+            DataType::Boolean => hash_array!(BooleanArray, column, hashes),
+            DataType::UInt8 => hash_array!(UInt8Array, column, hashes),
+            DataType::UInt16 => hash_array!(UInt16Array, column, hashes),
+            DataType::UInt32 => hash_array!(UInt32Array, column, hashes),
+            DataType::UInt64 => hash_array!(UInt64Array, column, hashes),
+            DataType::Int8 => hash_array!(Int8Array, column, hashes),
+            DataType::Int16 => hash_array!(Int16Array, column, hashes),
+            DataType::Int32 => hash_array!(Int32Array, column, hashes),
+            DataType::Int64 => hash_array!(Int64Array, column, hashes),
+            DataType::Utf8 => hash_array!(StringArray, column, hashes),
+            DataType::Decimal(_, scale) => {
+                let array = column.as_any().downcast_ref::<DecimalArray>().unwrap();
+                for (row, hash) in hashes.iter_mut().enumerate() {
+                    if array.is_valid(row) {
+                        let mut hasher = DefaultHasher::new();
+                        normalize_decimal(array.value(row), *scale).hash(&mut hasher);
+                        *hash = combine_hashes(*hash, hasher.finish());
+                    }
+                }
+            }
+            other => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "Hashing values of type {:?} is not supported",
+                    other
+                )))
+            }
+        }
+    }
+    Ok(hashes)
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use std::sync::Arc;
 
     fn check(left: &[&str], right: &[&str], on: &[(&str, &str)]) -> Result<()> {
         let left = left.iter().map(|x| x.to_string()).collect::<HashSet<_>>();
@@ -198,4 +315,60 @@ mod tests {
 
         assert!(check(&left, &right, on).is_ok());
     }
+
+    #[test]
+    fn cross_join_schema() -> Result<()> {
+        let left = Schema::new(vec![Field::new("a", DataType::Int32, false)]);
+        let right = Schema::new(vec![Field::new("b", DataType::Utf8, true)]);
+
+        let schema = build_cross_join_schema(&left, &right)?;
+        let names = schema
+            .fields()
+            .iter()
+            .map(|f| f.name().as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["a", "b"]);
+
+        // both sides have a column "a"
+        assert!(build_cross_join_schema(&left, &left).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn create_hashes_of_rows() -> Result<()> {
+        let a: ArrayRef =
+            Arc::new(Int32Array::from(vec![Some(1), Some(2), Some(1), None]));
+        let b: ArrayRef = Arc::new(StringArray::from(vec!["x", "y", "x", "x"]));
+
+        let hashes = create_hashes(&[a.clone(), b.clone()])?;
+        assert_eq!(hashes.len(), 4);
+        // rows with the same values have the same hash, whichever arrays they are in
+        assert_eq!(hashes[0], hashes[2]);
+        assert_ne!(hashes[0], hashes[1]);
+        assert_eq!(hashes, create_hashes(&[a, b])?);
+
+        let d: ArrayRef = Arc::new(DecimalArray::from_opt_vec(
+            vec![Some(125), Some(250), Some(125), None],
+            10,
+            2,
+        ));
+        let hashes = create_hashes(&[d])?;
+        assert_eq!(hashes[0], hashes[2]);
+        assert_ne!(hashes[0], hashes[1]);
+
+        // 1.25 has the same hash whatever its scale
+        let e: ArrayRef = Arc::new(DecimalArray::from_opt_vec(
+            vec![Some(1250), Some(2500), Some(1251)],
+            12,
+            3,
+        ));
+        let scaled_hashes = create_hashes(&[e])?;
+        assert_eq!(hashes[0], scaled_hashes[0]);
+        assert_eq!(hashes[1], scaled_hashes[1]);
+        assert_ne!(hashes[0], scaled_hashes[2]);
+
+        let c: ArrayRef = Arc::new(arrow::array::Float64Array::from(vec![1.0]));
+        assert!(create_hashes(&[c]).is_err());
+        Ok(())
+    }
 }
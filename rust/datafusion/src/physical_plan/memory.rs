@@ -63,6 +63,26 @@ impl ExecutionPlan for MemoryExec {
         Partitioning::UnknownPartitioning(self.partitions.len())
     }
 
+    fn output_size_estimate(&self) -> Option<usize> {
+        let size = self
+            .partitions
+            .iter()
+            .flatten()
+            .map(|batch| {
+                // only the projected columns are read
+                let columns = match &self.projection {
+                    Some(projection) => projection.clone(),
+                    None => (0..batch.num_columns()).collect(),
+                };
+                columns
+                    .iter()
+                    .map(|i| batch.column(*i).get_array_memory_size())
+                    .sum::<usize>()
+            })
+            .sum();
+        Some(size)
+    }
+
     fn with_new_children(
         &self,
         _: Vec<Arc<dyn ExecutionPlan>>,
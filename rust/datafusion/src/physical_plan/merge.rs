@@ -34,8 +34,10 @@ use arrow::{
     error::{ArrowError, Result as ArrowResult},
 };
 
+use super::common::{spawn_abortable, AbortOnDrop};
 use super::RecordBatchStream;
 use crate::error::{DataFusionError, Result};
+use crate::physical_plan::expressions::PhysicalSortExpr;
 use crate::physical_plan::ExecutionPlan;
 use crate::physical_plan::Partitioning;
 
@@ -55,6 +57,11 @@ impl MergeExec {
     pub fn new(input: Arc<dyn ExecutionPlan>) -> Self {
         MergeExec { input }
     }
+
+    /// Input execution plan
+    pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.input
+    }
 }
 
 #[async_trait]
@@ -77,6 +84,15 @@ impl ExecutionPlan for MergeExec {
         Partitioning::UnknownPartitioning(1)
     }
 
+    fn output_ordering(&self) -> Option<Vec<PhysicalSortExpr>> {
+        // a single input partition is passed through
+        if self.input.output_partitioning().partition_count() == 1 {
+            self.input.output_ordering()
+        } else {
+            None
+        }
+    }
+
     fn with_new_children(
         &self,
         children: Vec<Arc<dyn ExecutionPlan>>,
@@ -116,10 +132,11 @@ impl ExecutionPlan for MergeExec {
 
                 // spawn independent tasks whose resulting streams (of batches)
                 // are sent to the channel for consumption.
+                let mut tasks = Vec::with_capacity(input_partitions);
                 for part_i in (0..input_partitions) {
                     let input = self.input.clone();
                     let mut sender = sender.clone();
-                    tokio::spawn(async move {
+                    let (_, task) = spawn_abortable(async move {
                         let mut stream = match input.execute(part_i).await {
                             Err(e) => {
                                 // If send fails, plan being torn
@@ -137,11 +154,13 @@ impl ExecutionPlan for MergeExec {
                             sender.send(item).await.ok();
                         }
                     });
+                    tasks.push(task);
                 }
 
                 Ok(Box::pin(MergeStream {
                     input: receiver,
                     schema: self.schema(),
+                    _tasks: tasks,
                 }))
             }
         }
@@ -153,6 +172,8 @@ pin_project! {
         schema: SchemaRef,
         #[pin]
         input: mpsc::Receiver<ArrowResult<RecordBatch>>,
+        // aborts the tasks that execute the input partitions once the stream is dropped
+        _tasks: Vec<AbortOnDrop>,
     }
 }
 
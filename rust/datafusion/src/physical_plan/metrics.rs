@@ -0,0 +1,372 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the metrics that plans record while they execute, such as for
+//! `EXPLAIN ANALYZE`.
+//!
+//! A plan records metrics once it is wrapped in a [MetricsExec]. While the plan computes,
+//! be it when its output stream is polled, when it is executed or in the tasks that it
+//! spawns, its metrics are the current metrics of the thread: the compute time is then
+//! recorded into them, excluding the time that the inputs of the plan spend computing,
+//! and plans can record the memory that they use with [record_memory].
+
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::fmt::{self, Display, Formatter};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use arrow::datatypes::SchemaRef;
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+
+use async_trait::async_trait;
+use futures::{Future, Stream};
+
+use super::expressions::PhysicalSortExpr;
+use super::{
+    Distribution, ExecutionPlan, Partitioning, RecordBatchStream,
+    SendableRecordBatchStream,
+};
+use crate::error::{DataFusionError, Result};
+
+/// The metrics of the execution of a plan, over all its partitions
+#[derive(Debug, Default)]
+pub struct ExecutionMetrics {
+    output_rows: AtomicUsize,
+    output_batches: AtomicUsize,
+    /// in nanoseconds
+    elapsed_compute: AtomicU64,
+    peak_memory: AtomicUsize,
+}
+
+impl ExecutionMetrics {
+    /// Create new metrics, with nothing recorded
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of rows that the plan returned
+    pub fn output_rows(&self) -> usize {
+        self.output_rows.load(Ordering::Relaxed)
+    }
+
+    /// Number of batches that the plan returned
+    pub fn output_batches(&self) -> usize {
+        self.output_batches.load(Ordering::Relaxed)
+    }
+
+    /// Time that the plan spent computing, excluding the time of its inputs
+    pub fn elapsed_compute(&self) -> Duration {
+        Duration::from_nanos(self.elapsed_compute.load(Ordering::Relaxed))
+    }
+
+    /// Largest size in bytes of the memory that a partition of the plan used at once
+    pub fn peak_memory(&self) -> usize {
+        self.peak_memory.load(Ordering::Relaxed)
+    }
+
+    fn record_output(&self, batch: &RecordBatch) {
+        self.output_rows
+            .fetch_add(batch.num_rows(), Ordering::Relaxed);
+        self.output_batches.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_compute(&self, elapsed: Duration) {
+        self.elapsed_compute
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+impl Display for ExecutionMetrics {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "output_rows={}, output_batches={}, elapsed_compute={:?}, peak_memory={}",
+            self.output_rows(),
+            self.output_batches(),
+            self.elapsed_compute(),
+            self.peak_memory()
+        )
+    }
+}
+
+thread_local! {
+    /// The metrics of the plan that is computing on this thread, if any
+    static CURRENT_METRICS: RefCell<Option<Arc<ExecutionMetrics>>> = RefCell::new(None);
+    /// The time that the inputs of the plan that is computing on this thread spent
+    /// computing so far
+    static INPUTS_COMPUTE: Cell<Duration> = Cell::new(Duration::from_secs(0));
+}
+
+/// Returns the metrics of the plan that is computing on this thread, if any
+pub(crate) fn current_metrics() -> Option<Arc<ExecutionMetrics>> {
+    CURRENT_METRICS.with(|current| current.borrow().clone())
+}
+
+/// Records that the plan that is computing on this thread uses `bytes` of memory in the
+/// partition that it is computing, if the plan records metrics
+pub fn record_memory(bytes: usize) {
+    CURRENT_METRICS.with(|current| {
+        if let Some(metrics) = current.borrow().as_ref() {
+            metrics.peak_memory.fetch_max(bytes, Ordering::Relaxed);
+        }
+    })
+}
+
+/// Runs `f` as a computation of the plan of `metrics`, if any, and records the time that
+/// it takes, excluding the computations of other plans that it runs
+fn compute<T>(metrics: &Option<Arc<ExecutionMetrics>>, f: impl FnOnce() -> T) -> T {
+    let metrics = match metrics {
+        Some(metrics) => metrics,
+        None => return f(),
+    };
+
+    let outer_metrics =
+        CURRENT_METRICS.with(|current| current.replace(Some(metrics.clone())));
+    let outer_inputs =
+        INPUTS_COMPUTE.with(|inputs| inputs.replace(Duration::from_secs(0)));
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+    let inputs = INPUTS_COMPUTE.with(|inputs| inputs.replace(outer_inputs + elapsed));
+    CURRENT_METRICS.with(|current| current.replace(outer_metrics));
+
+    metrics.record_compute(elapsed.checked_sub(inputs).unwrap_or_default());
+    result
+}
+
+pin_project_lite::pin_project! {
+    /// A future that computes for the plan of `metrics`, such as a task that the plan
+    /// spawns
+    pub(crate) struct ComputeFuture<F> {
+        #[pin]
+        future: F,
+        metrics: Option<Arc<ExecutionMetrics>>,
+    }
+}
+
+impl<F: Future> ComputeFuture<F> {
+    /// Create a future that computes `future` for the plan that is computing on this thread
+    pub fn current(future: F) -> Self {
+        Self {
+            future,
+            metrics: current_metrics(),
+        }
+    }
+}
+
+impl<F: Future> Future for ComputeFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let future = this.future;
+        compute(this.metrics, || future.poll(cx))
+    }
+}
+
+/// Execution plan that records the metrics of its input, which it executes unchanged
+#[derive(Debug)]
+pub struct MetricsExec {
+    input: Arc<dyn ExecutionPlan>,
+    metrics: Arc<ExecutionMetrics>,
+}
+
+impl MetricsExec {
+    /// Create a new MetricsExec that records the metrics of `input`
+    pub fn new(input: Arc<dyn ExecutionPlan>) -> Self {
+        Self {
+            input,
+            metrics: Arc::new(ExecutionMetrics::new()),
+        }
+    }
+
+    /// Input execution plan
+    pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.input
+    }
+
+    /// The metrics of the input, recorded so far
+    pub fn metrics(&self) -> &Arc<ExecutionMetrics> {
+        &self.metrics
+    }
+}
+
+/// Wraps `plan` and all the plans below it in a [MetricsExec]
+pub fn with_metrics(plan: Arc<dyn ExecutionPlan>) -> Result<Arc<dyn ExecutionPlan>> {
+    let children = plan
+        .children()
+        .into_iter()
+        .map(with_metrics)
+        .collect::<Result<Vec<_>>>()?;
+    let plan = if children.is_empty() {
+        plan
+    } else {
+        plan.with_new_children(children)?
+    };
+    Ok(Arc::new(MetricsExec::new(plan)))
+}
+
+#[async_trait]
+impl ExecutionPlan for MetricsExec {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        self.input.output_partitioning()
+    }
+
+    fn required_child_distribution(&self) -> Distribution {
+        self.input.required_child_distribution()
+    }
+
+    fn output_size_estimate(&self) -> Option<usize> {
+        self.input.output_size_estimate()
+    }
+
+    fn output_ordering(&self) -> Option<Vec<PhysicalSortExpr>> {
+        self.input.output_ordering()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(MetricsExec::new(children[0].clone()))),
+            _ => Err(DataFusionError::Internal(
+                "MetricsExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        let metrics = Some(self.metrics.clone());
+        let input = ComputeFuture {
+            future: self.input.execute(partition),
+            metrics: metrics.clone(),
+        }
+        .await?;
+        Ok(Box::pin(MetricsStream { input, metrics }))
+    }
+}
+
+/// A stream that records the output and the compute time of the plan of `metrics`
+struct MetricsStream {
+    input: SendableRecordBatchStream,
+    metrics: Option<Arc<ExecutionMetrics>>,
+}
+
+impl Stream for MetricsStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let input = &mut this.input;
+        let poll = compute(&this.metrics, || input.as_mut().poll_next(cx));
+        if let (Poll::Ready(Some(Ok(batch))), Some(metrics)) = (&poll, &this.metrics) {
+            metrics.record_output(batch);
+        }
+        poll
+    }
+}
+
+impl RecordBatchStream for MetricsStream {
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::common;
+    use crate::physical_plan::expressions::{col, lit};
+    use crate::physical_plan::filter::FilterExec;
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::sort::{SortExec, SortOptions};
+    use crate::scalar::ScalarValue;
+    use arrow::array::Int32Array;
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    #[tokio::test]
+    async fn record_metrics() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batches = (0..3)
+            .map(|i| {
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![Arc::new(Int32Array::from(vec![i, i + 10]))],
+                )
+            })
+            .collect::<ArrowResult<Vec<_>>>()?;
+        let input = Arc::new(MemoryExec::try_new(&[batches], schema, None)?);
+        let filter = Arc::new(FilterExec::try_new(
+            crate::physical_plan::expressions::binary(
+                col("a"),
+                crate::logical_plan::Operator::Lt,
+                lit(ScalarValue::Int32(Some(10))),
+                &input.schema(),
+            )?,
+            input,
+        )?);
+        let sort = Arc::new(SortExec::try_new(
+            vec![PhysicalSortExpr {
+                expr: col("a"),
+                options: SortOptions::default(),
+            }],
+            filter,
+            1,
+        )?);
+
+        let plan = with_metrics(sort)?;
+        let batches = common::collect(plan.execute(0).await?).await?;
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 3);
+
+        let sort = plan.as_any().downcast_ref::<MetricsExec>().unwrap();
+        assert_eq!(sort.metrics().output_rows(), 3);
+        assert_eq!(sort.metrics().output_batches(), 1);
+        // the sort buffers the rows of the filter
+        assert!(sort.metrics().peak_memory() > 0);
+
+        let filter = sort.input().children()[0].clone();
+        let filter = filter.as_any().downcast_ref::<MetricsExec>().unwrap();
+        assert_eq!(filter.metrics().output_rows(), 3);
+        assert_eq!(filter.metrics().output_batches(), 3);
+
+        let scan = filter.input().children()[0].clone();
+        let scan = scan.as_any().downcast_ref::<MetricsExec>().unwrap();
+        assert_eq!(scan.metrics().output_rows(), 6);
+        assert_eq!(scan.metrics().peak_memory(), 0);
+
+        Ok(())
+    }
+}
@@ -21,15 +21,17 @@ use std::fmt::{Debug, Display};
 use std::sync::Arc;
 use std::{any::Any, pin::Pin};
 
+use crate::error::{DataFusionError, Result};
 use crate::execution::context::ExecutionContextState;
 use crate::logical_plan::LogicalPlan;
-use crate::{error::Result, scalar::ScalarValue};
+use crate::scalar::ScalarValue;
 use arrow::datatypes::{DataType, Schema, SchemaRef};
 use arrow::error::Result as ArrowResult;
 use arrow::record_batch::RecordBatch;
 use arrow::{array::ArrayRef, datatypes::Field};
 
 use async_trait::async_trait;
+use expressions::PhysicalSortExpr;
 use futures::stream::Stream;
 
 /// Trait for types that stream [arrow::record_batch::RecordBatch]
@@ -69,6 +71,16 @@ pub trait ExecutionPlan: Debug + Send + Sync {
     fn required_child_distribution(&self) -> Distribution {
         Distribution::UnspecifiedDistribution
     }
+    /// Returns an estimate of the size in bytes of the output of this plan, or `None` when
+    /// it is unknown. The planner uses it to choose how to execute operators such as joins.
+    fn output_size_estimate(&self) -> Option<usize> {
+        None
+    }
+    /// Returns the expressions by which the rows of every output partition of this plan
+    /// are sorted, or `None` when their order is unknown
+    fn output_ordering(&self) -> Option<Vec<PhysicalSortExpr>> {
+        None
+    }
     /// Get a list of child execution plans that provide the input for this plan. The returned list
     /// will be empty for leaf nodes, will contain a single value for unary nodes, or two
     /// values for binary nodes (such as joins).
@@ -87,6 +99,11 @@ pub trait ExecutionPlan: Debug + Send + Sync {
 /// Partitioning schemes supported by operators.
 #[derive(Debug, Clone)]
 pub enum Partitioning {
+    /// Allocate batches to the partitions in turn, with the given number of partitions
+    RoundRobinBatch(usize),
+    /// Allocate rows by the hash of the values of the expressions, with the given number of
+    /// partitions
+    Hash(Vec<Arc<dyn PhysicalExpr>>, usize),
     /// Unknown partitioning scheme
     UnknownPartitioning(usize),
 }
@@ -96,6 +113,8 @@ impl Partitioning {
     pub fn partition_count(&self) -> usize {
         use Partitioning::*;
         match self {
+            RoundRobinBatch(n) => *n,
+            Hash(_, n) => *n,
             UnknownPartitioning(n) => *n,
         }
     }
@@ -108,6 +127,9 @@ pub enum Distribution {
     UnspecifiedDistribution,
     /// A single partition is required
     SinglePartition,
+    /// The rows with the same values of the expressions are required to be in the same
+    /// partition
+    HashPartitioned(Vec<Arc<dyn PhysicalExpr>>),
 }
 
 /// Represents the result from an expression
@@ -137,6 +159,9 @@ impl ColumnarValue {
 /// Expression that can be evaluated against a RecordBatch
 /// A Physical expression knows its type, nullability and how to evaluate itself.
 pub trait PhysicalExpr: Send + Sync + Display + Debug {
+    /// Returns the physical expression as [`Any`](std::any::Any) so that it can be
+    /// downcast to a specific implementation.
+    fn as_any(&self) -> &dyn Any;
     /// Get the data type of this expression, given the schema of the input
     fn data_type(&self, input_schema: &Schema) -> Result<DataType>;
     /// Determine whether this expression is nullable, given the schema of the input
@@ -166,6 +191,30 @@ pub trait AggregateExpr: Send + Sync + Debug {
     /// expressions that are passed to the Accumulator.
     /// Single-column aggregations such as `sum` return a single value, others (e.g. `cov`) return many.
     fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>>;
+
+    /// the accumulator used to accumulate the values of all the groups of a grouped
+    /// aggregation at once, if this aggregation has one.
+    /// it receives the same arguments and returns the same states as the accumulator of
+    /// `create_accumulator`, which accumulates the groups without one.
+    fn create_grouped_accumulator(&self) -> Result<Option<Box<dyn GroupedAccumulator>>> {
+        Ok(None)
+    }
+}
+
+/// A window expression that:
+/// * knows its resulting field
+/// * knows how to evaluate itself over all the rows of its input, which are given in a
+///   single batch as a window function needs the whole partition of every row
+pub trait WindowExpr: Send + Sync + Debug {
+    /// the name of the window expression, i.e. the name of its resulting field
+    fn name(&self) -> &str;
+
+    /// the field of the final result of this window function.
+    fn field(&self) -> Result<Field>;
+
+    /// evaluates the window function for every row of the batch, returning an array with
+    /// the same number of rows as the batch, in the order of the batch.
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ArrayRef>;
 }
 
 /// An accumulator represents a stateful object that lives throughout the evaluation of multiple rows and
@@ -218,13 +267,72 @@ pub trait Accumulator: Send + Sync + Debug {
             .collect::<Result<_>>()
     }
 
+    /// whether the accumulator can remove values from its state via `retract_batch`,
+    /// which lets a window function slide it over the frames of consecutive rows.
+    fn supports_retract(&self) -> bool {
+        false
+    }
+
+    /// removes from the accumulator's state the values of a vector of arrays, which were
+    /// previously added via `update_batch`.
+    fn retract_batch(&mut self, _values: &Vec<ArrayRef>) -> Result<()> {
+        Err(DataFusionError::Internal(format!(
+            "The accumulator {:?} cannot retract values",
+            self
+        )))
+    }
+
     /// returns its value based on its current state.
     fn evaluate(&self) -> Result<ScalarValue>;
 }
 
+/// A grouped accumulator accumulates the values of every group of a grouped aggregation,
+/// whose groups are identified by their index, from `0` to the number of groups.
+/// It knows how to:
+/// * update the state of many groups from arrays of inputs via `update_batch`
+/// * update the state of many groups from arrays of states via `merge_batch`
+/// * convert the state of all the groups to arrays via `state`
+/// * compute the final value of all the groups via `evaluate`
+///
+/// Unlike [Accumulator], it does not convert the values to [ScalarValue]s, nor does it
+/// need the rows of every group to be taken from the arrays of inputs.
+pub trait GroupedAccumulator: Send + Sync + Debug {
+    /// updates the state of the groups from a vector of arrays, where the group of the
+    /// row `i` of the arrays is `group_indices[i]`. `num_groups` is the number of groups
+    /// after this update, which includes the groups that are new in `group_indices`.
+    fn update_batch(
+        &mut self,
+        values: &[ArrayRef],
+        group_indices: &[usize],
+        num_groups: usize,
+    ) -> Result<()>;
+
+    /// updates the state of the groups from a vector of states, as in `update_batch`.
+    fn merge_batch(
+        &mut self,
+        states: &[ArrayRef],
+        group_indices: &[usize],
+        num_groups: usize,
+    ) -> Result<()>;
+
+    /// Returns the state of all the groups, with the state of the group `i` in the row
+    /// `i` of the arrays.
+    fn state(&self) -> Result<Vec<ArrayRef>>;
+
+    /// returns the values of all the groups, with the value of the group `i` in row `i`.
+    fn evaluate(&self) -> Result<ArrayRef>;
+
+    /// the estimated number of bytes that the state of the groups occupies in memory.
+    fn size(&self) -> usize;
+}
+
 pub mod aggregates;
+pub mod analyze;
+pub mod approx_expressions;
 pub mod array_expressions;
+pub mod cancellation;
 pub mod common;
+pub mod cross_join;
 pub mod csv;
 pub mod datetime_expressions;
 pub mod distinct_expressions;
@@ -234,6 +342,7 @@ pub mod expressions;
 pub mod filter;
 pub mod functions;
 pub mod group_scalar;
+pub mod grouped_accumulators;
 pub mod hash_aggregate;
 pub mod hash_join;
 pub mod hash_utils;
@@ -241,11 +350,20 @@ pub mod limit;
 pub mod math_expressions;
 pub mod memory;
 pub mod merge;
+pub mod metrics;
 pub mod parquet;
 pub mod planner;
 pub mod projection;
+pub mod repartition;
 pub mod sort;
+pub mod sort_merge_join;
+pub mod sort_preserving_merge;
+pub mod statistical_expressions;
 pub mod string_expressions;
+pub mod top_k;
 pub mod type_coercion;
 pub mod udaf;
 pub mod udf;
+pub mod union;
+pub mod window_functions;
+pub mod windows;
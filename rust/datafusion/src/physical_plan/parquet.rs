@@ -25,6 +25,7 @@ use std::{fmt, thread};
 
 use super::{RecordBatchStream, SendableRecordBatchStream};
 use crate::error::{DataFusionError, Result};
+use crate::physical_plan::expressions::{Column, PhysicalSortExpr};
 use crate::physical_plan::ExecutionPlan;
 use crate::physical_plan::{common, Partitioning};
 use arrow::datatypes::{Schema, SchemaRef};
@@ -50,6 +51,8 @@ pub struct ParquetExec {
     projection: Vec<usize>,
     /// Batch size
     batch_size: usize,
+    /// Expressions by which the rows of every file are sorted
+    output_ordering: Vec<PhysicalSortExpr>,
 }
 
 impl ParquetExec {
@@ -97,8 +100,19 @@ impl ParquetExec {
             schema: Arc::new(projected_schema),
             projection,
             batch_size,
+            output_ordering: vec![],
         }
     }
+
+    /// Declare that the rows of every file are sorted by `output_ordering`, whose
+    /// expressions are columns of the files
+    pub fn with_output_ordering(
+        mut self,
+        output_ordering: Vec<PhysicalSortExpr>,
+    ) -> Self {
+        self.output_ordering = output_ordering;
+        self
+    }
 }
 
 #[async_trait]
@@ -122,6 +136,31 @@ impl ExecutionPlan for ParquetExec {
         Partitioning::UnknownPartitioning(self.filenames.len())
     }
 
+    fn output_size_estimate(&self) -> Option<usize> {
+        common::files_size(&self.filenames)
+    }
+
+    fn output_ordering(&self) -> Option<Vec<PhysicalSortExpr>> {
+        // the rows are sorted by the longest prefix of the ordering of the files whose
+        // columns are projected
+        let ordering = self
+            .output_ordering
+            .iter()
+            .take_while(|sort_expr| {
+                match sort_expr.expr.as_any().downcast_ref::<Column>() {
+                    Some(column) => self.schema.index_of(column.name()).is_ok(),
+                    None => false,
+                }
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        if ordering.is_empty() {
+            None
+        } else {
+            Some(ordering)
+        }
+    }
+
     fn with_new_children(
         &self,
         children: Vec<Arc<dyn ExecutionPlan>>,
@@ -25,23 +25,33 @@ use crate::execution::context::ExecutionContextState;
 use crate::logical_plan::{
     Expr, LogicalPlan, PlanType, StringifiedPlan, TableSource, UserDefinedLogicalNode,
 };
+use crate::physical_plan::analyze::AnalyzeExec;
+use crate::physical_plan::cross_join::CrossJoinExec;
 use crate::physical_plan::csv::{CsvExec, CsvReadOptions};
 use crate::physical_plan::explain::ExplainExec;
 use crate::physical_plan::expressions::{CaseExpr, Column, Literal, PhysicalSortExpr};
 use crate::physical_plan::filter::FilterExec;
 use crate::physical_plan::hash_aggregate::{AggregateMode, HashAggregateExec};
-use crate::physical_plan::hash_join::HashJoinExec;
-use crate::physical_plan::hash_utils;
+use crate::physical_plan::hash_join::{HashJoinExec, PartitionMode};
+use crate::physical_plan::hash_utils::{self, JoinOn};
 use crate::physical_plan::limit::{GlobalLimitExec, LocalLimitExec};
 use crate::physical_plan::memory::MemoryExec;
 use crate::physical_plan::merge::MergeExec;
 use crate::physical_plan::parquet::ParquetExec;
 use crate::physical_plan::projection::ProjectionExec;
+use crate::physical_plan::repartition::RepartitionExec;
 use crate::physical_plan::sort::SortExec;
+use crate::physical_plan::sort_merge_join::SortMergeJoinExec;
+use crate::physical_plan::sort_preserving_merge::SortPreservingMergeExec;
 use crate::physical_plan::udf;
+use crate::physical_plan::union::UnionExec;
+use crate::physical_plan::windows::{self, WindowAggExec};
 use crate::physical_plan::{expressions, Distribution};
-use crate::physical_plan::{AggregateExpr, ExecutionPlan, PhysicalExpr, PhysicalPlanner};
+use crate::physical_plan::{
+    AggregateExpr, ExecutionPlan, Partitioning, PhysicalExpr, PhysicalPlanner, WindowExpr,
+};
 use crate::prelude::JoinType;
+use crate::scalar::ScalarValue;
 use crate::variable::VarType;
 use arrow::compute::SortOptions;
 use arrow::datatypes::Schema;
@@ -125,6 +135,30 @@ impl DefaultPhysicalPlanner {
                         })
                         .collect(),
                 ),
+                Distribution::HashPartitioned(exprs) => plan.with_new_children(
+                    children
+                        .iter()
+                        .map(|child| {
+                            if is_hash_partitioned(child.as_ref(), &exprs) {
+                                Ok(child.clone())
+                            } else {
+                                Ok(Arc::new(
+                                    RepartitionExec::try_new(
+                                        child.clone(),
+                                        Partitioning::Hash(
+                                            exprs.clone(),
+                                            ctx_state.config.concurrency,
+                                        ),
+                                    )?
+                                    .with_memory_manager(
+                                        ctx_state.memory_manager.clone(),
+                                    ),
+                                )
+                                    as Arc<dyn ExecutionPlan>)
+                            }
+                        })
+                        .collect::<Result<Vec<_>>>()?,
+                ),
             }
         }
     }
@@ -226,28 +260,58 @@ impl DefaultPhysicalPlanner {
                     .map(|e| self.create_aggregate_expr(e, &input_schema, ctx_state))
                     .collect::<Result<Vec<_>>>()?;
 
-                let initial_aggr = Arc::new(HashAggregateExec::try_new(
-                    AggregateMode::Partial,
-                    groups.clone(),
-                    aggregates.clone(),
-                    input,
-                )?);
+                let memory_budget = ctx_state.config.aggregate_memory_budget;
+                // with a GROUP BY, the final aggregation runs in parallel on the partitions
+                // of the groups, which are partitioned by their keys
+                let final_mode = if !groups.is_empty() && ctx_state.config.concurrency > 1
+                {
+                    AggregateMode::FinalPartitioned
+                } else {
+                    AggregateMode::Final
+                };
+                let initial_aggr = Arc::new(
+                    HashAggregateExec::try_new(
+                        AggregateMode::Partial,
+                        groups.clone(),
+                        aggregates.clone(),
+                        input,
+                    )?
+                    .with_memory_budget(memory_budget)
+                    .with_memory_manager(ctx_state.memory_manager.clone()),
+                );
 
                 let final_group: Vec<Arc<dyn PhysicalExpr>> =
                     (0..groups.len()).map(|i| col(&groups[i].1)).collect();
 
                 // construct a second aggregation, keeping the final column name equal to the first aggregation
                 // and the expressions corresponding to the respective aggregate
-                Ok(Arc::new(HashAggregateExec::try_new(
-                    AggregateMode::Final,
-                    final_group
-                        .iter()
-                        .enumerate()
-                        .map(|(i, expr)| (expr.clone(), groups[i].1.clone()))
-                        .collect(),
-                    aggregates,
-                    initial_aggr,
-                )?))
+                Ok(Arc::new(
+                    HashAggregateExec::try_new(
+                        final_mode,
+                        final_group
+                            .iter()
+                            .enumerate()
+                            .map(|(i, expr)| (expr.clone(), groups[i].1.clone()))
+                            .collect(),
+                        aggregates,
+                        initial_aggr,
+                    )?
+                    .with_memory_budget(memory_budget)
+                    .with_memory_manager(ctx_state.memory_manager.clone()),
+                ))
+            }
+            LogicalPlan::Window {
+                input, window_expr, ..
+            } => {
+                let input = self.create_physical_plan(input, ctx_state)?;
+                let input_schema = input.as_ref().schema();
+
+                let window_expr = window_expr
+                    .iter()
+                    .map(|e| self.create_window_expr(e, &input_schema, ctx_state))
+                    .collect::<Result<Vec<_>>>()?;
+
+                Ok(Arc::new(WindowAggExec::try_new(window_expr, input)?))
             }
             LogicalPlan::Filter {
                 input, predicate, ..
@@ -284,17 +348,33 @@ impl DefaultPhysicalPlanner {
                     })
                     .collect::<Result<Vec<_>>>()?;
 
-                Ok(Arc::new(SortExec::try_new(
-                    sort_expr,
+                // the partitions of the input are sorted in parallel and then merged
+                let preserve_partitioning =
+                    input.output_partitioning().partition_count() > 1;
+                let sort = SortExec::try_new(
+                    sort_expr.clone(),
                     input,
                     ctx_state.config.concurrency,
-                )?))
+                )?
+                .with_spill_threshold(ctx_state.config.sort_spill_threshold)
+                .with_preserve_partitioning(preserve_partitioning)
+                .with_memory_manager(ctx_state.memory_manager.clone());
+                if preserve_partitioning {
+                    Ok(Arc::new(SortPreservingMergeExec::new(
+                        sort_expr,
+                        Arc::new(sort),
+                        ctx_state.config.batch_size,
+                    )))
+                } else {
+                    Ok(Arc::new(sort))
+                }
             }
             LogicalPlan::Join {
                 left,
                 right,
                 on: keys,
                 join_type,
+                filter,
                 ..
             } => {
                 let left = self.create_physical_plan(left, ctx_state)?;
@@ -303,13 +383,110 @@ impl DefaultPhysicalPlanner {
                     JoinType::Inner => hash_utils::JoinType::Inner,
                     JoinType::Left => hash_utils::JoinType::Left,
                     JoinType::Right => hash_utils::JoinType::Right,
+                    JoinType::Full => hash_utils::JoinType::Full,
+                    JoinType::Semi => hash_utils::JoinType::Semi,
+                    JoinType::Anti => hash_utils::JoinType::Anti,
                 };
-                Ok(Arc::new(HashJoinExec::try_new(
-                    left,
-                    right,
-                    &keys,
-                    &physical_join_type,
-                )?))
+                let filter = match filter {
+                    Some(filter) => {
+                        // the filter is evaluated on the columns of both sides
+                        let filter_schema = hash_utils::build_join_schema(
+                            &left.schema(),
+                            &right.schema(),
+                            &keys,
+                            &hash_utils::JoinType::Inner,
+                        );
+                        Some(self.create_physical_expr(
+                            filter,
+                            &filter_schema,
+                            ctx_state,
+                        )?)
+                    }
+                    None => None,
+                };
+
+                // inputs that are both sorted by the keys are merged without collecting
+                // either side, unless the pairs of rows must also satisfy a filter
+                let sort_options = match filter {
+                    None => sort_merge_options(left.as_ref(), right.as_ref(), &keys),
+                    Some(_) => None,
+                };
+
+                // a small left is collected into a single hash table that all the partitions
+                // of the right share, as is a left whose size is unknown. Otherwise, both
+                // sides are partitioned by their keys and every partition is joined on
+                // its own.
+                let concurrency = ctx_state.config.concurrency;
+                let collect_left = concurrency == 1
+                    || match left.output_size_estimate() {
+                        Some(size) => {
+                            size <= ctx_state.config.hash_join_collect_left_threshold
+                        }
+                        None => true,
+                    };
+                if let Some(sort_options) = sort_options {
+                    Ok(Arc::new(SortMergeJoinExec::try_new(
+                        left,
+                        right,
+                        &keys,
+                        &physical_join_type,
+                        sort_options,
+                        ctx_state.config.batch_size,
+                    )?))
+                } else if collect_left {
+                    Ok(Arc::new(
+                        HashJoinExec::try_new(
+                            left,
+                            right,
+                            &keys,
+                            &physical_join_type,
+                            filter,
+                            PartitionMode::CollectLeft,
+                        )?
+                        .with_memory_manager(ctx_state.memory_manager.clone()),
+                    ))
+                } else {
+                    let left_keys = keys.iter().map(|(l, _)| col(l)).collect();
+                    let right_keys = keys.iter().map(|(_, r)| col(r)).collect();
+                    Ok(Arc::new(
+                        HashJoinExec::try_new(
+                            Arc::new(
+                                RepartitionExec::try_new(
+                                    left,
+                                    Partitioning::Hash(left_keys, concurrency),
+                                )?
+                                .with_memory_manager(ctx_state.memory_manager.clone()),
+                            ),
+                            Arc::new(
+                                RepartitionExec::try_new(
+                                    right,
+                                    Partitioning::Hash(right_keys, concurrency),
+                                )?
+                                .with_memory_manager(ctx_state.memory_manager.clone()),
+                            ),
+                            &keys,
+                            &physical_join_type,
+                            filter,
+                            PartitionMode::Partitioned,
+                        )?
+                        .with_memory_manager(ctx_state.memory_manager.clone()),
+                    ))
+                }
+            }
+            LogicalPlan::CrossJoin { left, right, .. } => {
+                let left = self.create_physical_plan(left, ctx_state)?;
+                let right = self.create_physical_plan(right, ctx_state)?;
+                Ok(Arc::new(
+                    CrossJoinExec::try_new(left, right)?
+                        .with_memory_manager(ctx_state.memory_manager.clone()),
+                ))
+            }
+            LogicalPlan::Union { inputs, .. } => {
+                let inputs = inputs
+                    .iter()
+                    .map(|input| self.create_physical_plan(input, ctx_state))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(Arc::new(UnionExec::new(inputs)))
             }
             LogicalPlan::EmptyRelation {
                 produce_one_row,
@@ -348,6 +525,7 @@ impl DefaultPhysicalPlanner {
             }
             LogicalPlan::Explain {
                 verbose,
+                analyze,
                 plan,
                 stringified_plans,
                 schema,
@@ -368,7 +546,15 @@ impl DefaultPhysicalPlanner {
                     ));
                 }
                 let schema_ref = Arc::new(schema.as_ref().clone());
-                Ok(Arc::new(ExplainExec::new(schema_ref, stringified_plans)))
+                if *analyze {
+                    Ok(Arc::new(AnalyzeExec::new(
+                        input,
+                        schema_ref,
+                        stringified_plans,
+                    )))
+                } else {
+                    Ok(Arc::new(ExplainExec::new(schema_ref, stringified_plans)))
+                }
             }
             LogicalPlan::Extension { node } => {
                 let inputs = node
@@ -512,6 +698,44 @@ impl DefaultPhysicalPlanner {
             Expr::IsNotNull(expr) => expressions::is_not_null(
                 self.create_physical_expr(expr, input_schema, ctx_state)?,
             ),
+            Expr::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                let list = list
+                    .iter()
+                    .map(|e| self.create_physical_expr(e, input_schema, ctx_state))
+                    .collect::<Result<Vec<_>>>()?;
+                expressions::in_list(
+                    self.create_physical_expr(expr, input_schema, ctx_state)?,
+                    list,
+                    *negated,
+                    input_schema,
+                )
+            }
+            Expr::Between {
+                expr,
+                negated,
+                low,
+                high,
+            } => expressions::between(
+                self.create_physical_expr(expr, input_schema, ctx_state)?,
+                self.create_physical_expr(low, input_schema, ctx_state)?,
+                self.create_physical_expr(high, input_schema, ctx_state)?,
+                *negated,
+                input_schema,
+            ),
+            Expr::ScalarFunction {
+                fun: functions::BuiltinScalarFunction::Now,
+                ..
+            } => {
+                // now() is the same for every row of a query: the time that it started
+                let now = ctx_state.query_execution_start_time.timestamp_nanos();
+                Ok(Arc::new(Literal::new(ScalarValue::TimestampNanosecond(
+                    Some(now),
+                ))))
+            }
             Expr::ScalarFunction { fun, args } => {
                 let physical_args = args
                     .iter()
@@ -589,6 +813,73 @@ impl DefaultPhysicalPlanner {
         }
     }
 
+    /// Create a window expression from a logical expression
+    pub fn create_window_expr(
+        &self,
+        e: &Expr,
+        input_schema: &Schema,
+        ctx_state: &ExecutionContextState,
+    ) -> Result<Arc<dyn WindowExpr>> {
+        // unpack aliased logical expressions, e.g. "rank() over (order by c1) as r"
+        let (name, e) = match e {
+            Expr::Alias(sub_expr, alias) => (alias.clone(), sub_expr.as_ref()),
+            _ => (e.name(input_schema)?, e),
+        };
+
+        match e {
+            Expr::WindowFunction {
+                fun,
+                args,
+                partition_by,
+                order_by,
+                window_frame,
+            } => {
+                let args = args
+                    .iter()
+                    .map(|e| self.create_physical_expr(e, input_schema, ctx_state))
+                    .collect::<Result<Vec<_>>>()?;
+                let partition_by = partition_by
+                    .iter()
+                    .map(|e| self.create_physical_expr(e, input_schema, ctx_state))
+                    .collect::<Result<Vec<_>>>()?;
+                let order_by = order_by
+                    .iter()
+                    .map(|e| match e {
+                        Expr::Sort {
+                            expr,
+                            asc,
+                            nulls_first,
+                        } => self.create_physical_sort_expr(
+                            expr,
+                            input_schema,
+                            SortOptions {
+                                descending: !*asc,
+                                nulls_first: *nulls_first,
+                            },
+                            ctx_state,
+                        ),
+                        _ => Err(DataFusionError::Plan(
+                            "Window ORDER BY only accepts sort expressions".to_string(),
+                        )),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                windows::create_window_expr(
+                    fun,
+                    &args,
+                    &partition_by,
+                    &order_by,
+                    *window_frame,
+                    input_schema,
+                    name,
+                )
+            }
+            other => Err(DataFusionError::Internal(format!(
+                "Invalid window expression '{:?}'",
+                other
+            ))),
+        }
+    }
+
     /// Create an aggregate expression from a logical expression
     pub fn create_physical_sort_expr(
         &self,
@@ -604,6 +895,68 @@ impl DefaultPhysicalPlanner {
     }
 }
 
+/// Whether the rows of `plan` with the same values of `exprs` are in the same partition
+fn is_hash_partitioned(
+    plan: &dyn ExecutionPlan,
+    exprs: &[Arc<dyn PhysicalExpr>],
+) -> bool {
+    match plan.output_partitioning() {
+        Partitioning::Hash(partition_exprs, _) => {
+            // the expressions are compared by their display, e.g. the names of columns
+            partition_exprs.len() == exprs.len()
+                && partition_exprs
+                    .iter()
+                    .zip(exprs)
+                    .all(|(l, r)| format!("{}", l) == format!("{}", r))
+        }
+        partitioning => partitioning.partition_count() == 1,
+    }
+}
+
+/// Returns the sort options of every pair of keys of `on` when `left` and `right` both have
+/// a single partition whose rows are sorted by their keys, in the order of `on` and with the
+/// same options
+fn sort_merge_options(
+    left: &dyn ExecutionPlan,
+    right: &dyn ExecutionPlan,
+    on: &JoinOn,
+) -> Option<Vec<SortOptions>> {
+    if left.output_partitioning().partition_count() != 1
+        || right.output_partitioning().partition_count() != 1
+    {
+        return None;
+    }
+    let left_ordering = left.output_ordering()?;
+    let right_ordering = right.output_ordering()?;
+    if left_ordering.len() < on.len() || right_ordering.len() < on.len() {
+        return None;
+    }
+
+    let is_column = |expr: &Arc<dyn PhysicalExpr>, name: &str| match expr
+        .as_any()
+        .downcast_ref::<Column>()
+    {
+        Some(column) => column.name() == name,
+        None => false,
+    };
+    on.iter()
+        .zip(left_ordering.iter().zip(right_ordering.iter()))
+        .map(|((l, r), (left_sort, right_sort))| {
+            let left_options = left_sort.options;
+            let right_options = right_sort.options;
+            if is_column(&left_sort.expr, l)
+                && is_column(&right_sort.expr, r)
+                && left_options.descending == right_options.descending
+                && left_options.nulls_first == right_options.nulls_first
+            {
+                Some(left_options)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
 fn tuple_err<T, R>(value: (Result<T>, Result<R>)) -> Result<(T, R)> {
     match value {
         (Ok(e), Ok(e1)) => Ok((e, e1)),
@@ -633,6 +986,7 @@ impl ExtensionPlanner for DefaultExtensionPlanner {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::execution::memory_manager::MemoryManager;
     use crate::physical_plan::{csv::CsvReadOptions, expressions, Partitioning};
     use crate::{
         logical_plan::{col, lit, sum, LogicalPlanBuilder},
@@ -651,6 +1005,8 @@ mod tests {
             var_provider: HashMap::new(),
             aggregate_functions: HashMap::new(),
             config: ExecutionConfig::new(),
+            memory_manager: Arc::new(MemoryManager::new(None)),
+            query_execution_start_time: chrono::Utc::now(),
         }
     }
 
@@ -684,6 +1040,135 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_partitioned_aggregate() -> Result<()> {
+        let testdata = arrow_testdata_path();
+        let path = format!("{}/csv/aggregate_test_100.csv", testdata);
+
+        let options = CsvReadOptions::new().schema_infer_max_records(100);
+        let logical_plan = LogicalPlanBuilder::scan_csv(&path, options, None)?
+            .aggregate(vec![col("c1")], vec![sum(col("c2"))])?
+            .build()?;
+
+        let mut ctx_state = make_ctx_state();
+        ctx_state.config = ExecutionConfig::new().with_concurrency(4);
+        let planner = DefaultPhysicalPlanner::default();
+        let plan = planner.create_physical_plan(&logical_plan, &ctx_state)?;
+
+        // the partial aggregates are repartitioned by the group keys
+        let plan_str = format!("{:?}", plan);
+        assert!(plan_str.contains("mode: FinalPartitioned"));
+        assert!(plan_str.contains("RepartitionExec"));
+        assert_eq!(plan.output_partitioning().partition_count(), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_join_partition_mode() -> Result<()> {
+        let testdata = arrow_testdata_path();
+        let path = format!("{}/csv/aggregate_test_100.csv", testdata);
+
+        let options = CsvReadOptions::new().schema_infer_max_records(100);
+        // the right only has the key, so that the columns of both sides do not collide
+        let right =
+            LogicalPlanBuilder::scan_csv(&path, options, Some(vec![0]))?.build()?;
+        let logical_plan = LogicalPlanBuilder::scan_csv(&path, options, None)?
+            .join(&right, JoinType::Semi, &["c1"], &["c1"])?
+            .build()?;
+        let planner = DefaultPhysicalPlanner::default();
+
+        // the left input is below the threshold, so it is collected
+        let mut ctx_state = make_ctx_state();
+        ctx_state.config = ExecutionConfig::new().with_concurrency(4);
+        let plan = planner.create_physical_plan(&logical_plan, &ctx_state)?;
+        let plan_str = format!("{:?}", plan);
+        assert!(plan_str.contains("mode: CollectLeft"));
+        assert!(!plan_str.contains("RepartitionExec"));
+
+        // the left input is above the threshold, so both inputs are partitioned
+        ctx_state.config = ExecutionConfig::new()
+            .with_concurrency(4)
+            .with_hash_join_collect_left_threshold(0);
+        let plan = planner.create_physical_plan(&logical_plan, &ctx_state)?;
+        let plan_str = format!("{:?}", plan);
+        assert!(plan_str.contains("mode: Partitioned"));
+        assert!(plan_str.contains("RepartitionExec"));
+        assert_eq!(plan.output_partitioning().partition_count(), 4);
+
+        // without concurrency, the left input is collected whatever its size
+        ctx_state.config = ExecutionConfig::new()
+            .with_concurrency(1)
+            .with_hash_join_collect_left_threshold(0);
+        let plan = planner.create_physical_plan(&logical_plan, &ctx_state)?;
+        let plan_str = format!("{:?}", plan);
+        assert!(plan_str.contains("mode: CollectLeft"));
+        assert!(!plan_str.contains("RepartitionExec"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_join_unknown_left_size() -> Result<()> {
+        let testdata = arrow_testdata_path();
+        let path = format!("{}/csv/aggregate_test_100.csv", testdata);
+
+        let options = CsvReadOptions::new().schema_infer_max_records(100);
+        let right =
+            LogicalPlanBuilder::scan_csv(&path, options, Some(vec![0]))?.build()?;
+        // the size of the output of an aggregate is not estimated
+        let logical_plan = LogicalPlanBuilder::scan_csv(&path, options, None)?
+            .aggregate(vec![col("c1")], vec![sum(col("c2"))])?
+            .join(&right, JoinType::Inner, &["c1"], &["c1"])?
+            .build()?;
+
+        let mut ctx_state = make_ctx_state();
+        ctx_state.config = ExecutionConfig::new()
+            .with_concurrency(4)
+            .with_hash_join_collect_left_threshold(0);
+        let planner = DefaultPhysicalPlanner::default();
+        let plan = planner.create_physical_plan(&logical_plan, &ctx_state)?;
+
+        // the left input is collected, as its size is unknown
+        let plan_str = format!("{:?}", plan);
+        assert!(plan_str.contains("mode: CollectLeft"));
+        assert!(!plan_str.contains("mode: Partitioned"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_merge_join() -> Result<()> {
+        let testdata = arrow_testdata_path();
+        let path = format!("{}/csv/aggregate_test_100.csv", testdata);
+
+        let options = CsvReadOptions::new().schema_infer_max_records(100);
+        let left = LogicalPlanBuilder::scan_csv(&path, options, None)?
+            .sort(vec![col("c1").sort(true, false)])?;
+
+        // both inputs are sorted by the keys, so they are merged. The right only has the
+        // key, so that the columns of both sides do not collide.
+        let right = LogicalPlanBuilder::scan_csv(&path, options, Some(vec![0]))?
+            .sort(vec![col("c1").sort(true, false)])?
+            .build()?;
+        let logical_plan = left
+            .join(&right, JoinType::Semi, &["c1"], &["c1"])?
+            .build()?;
+        let plan_str = format!("{:?}", plan(&logical_plan)?);
+        assert!(plan_str.contains("SortMergeJoinExec"));
+
+        // the inputs are not sorted by the keys
+        let right =
+            LogicalPlanBuilder::scan_csv(&path, options, Some(vec![1]))?.build()?;
+        let logical_plan = left
+            .join(&right, JoinType::Semi, &["c2"], &["c2"])?
+            .build()?;
+        let plan_str = format!("{:?}", plan(&logical_plan)?);
+        assert!(plan_str.contains("HashJoinExec"));
+
+        Ok(())
+    }
+
     #[test]
     fn test_create_not() -> Result<()> {
         let schema = Schema::new(vec![Field::new("a", DataType::Boolean, true)]);
@@ -26,6 +26,7 @@ use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use crate::error::{DataFusionError, Result};
+use crate::physical_plan::expressions::{col, Column, PhysicalSortExpr};
 use crate::physical_plan::{ExecutionPlan, Partitioning, PhysicalExpr};
 use arrow::datatypes::{Field, Schema, SchemaRef};
 use arrow::error::Result as ArrowResult;
@@ -98,6 +99,40 @@ impl ExecutionPlan for ProjectionExec {
         self.input.output_partitioning()
     }
 
+    fn output_size_estimate(&self) -> Option<usize> {
+        self.input.output_size_estimate()
+    }
+
+    fn output_ordering(&self) -> Option<Vec<PhysicalSortExpr>> {
+        // the rows are sorted by the longest prefix of the sort expressions of the input
+        // whose columns are projected
+        let mut ordering = vec![];
+        for sort_expr in self.input.output_ordering()? {
+            let name = match sort_expr.expr.as_any().downcast_ref::<Column>() {
+                Some(column) => column.name(),
+                None => break,
+            };
+            let projected = self.expr.iter().find(|(expr, _)| {
+                match expr.as_any().downcast_ref::<Column>() {
+                    Some(column) => column.name() == name,
+                    None => false,
+                }
+            });
+            match projected {
+                Some((_, alias)) => ordering.push(PhysicalSortExpr {
+                    expr: col(alias),
+                    options: sort_expr.options,
+                }),
+                None => break,
+            }
+        }
+        if ordering.is_empty() {
+            None
+        } else {
+            Some(ordering)
+        }
+    }
+
     fn with_new_children(
         &self,
         children: Vec<Arc<dyn ExecutionPlan>>,
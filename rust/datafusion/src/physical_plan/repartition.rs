@@ -0,0 +1,591 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the repartition plan, which redistributes the rows of its input partitions to a
+//! new set of partitions
+
+use std::any::Any;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Seek, SeekFrom};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use futures::channel::mpsc;
+use futures::stream::{Stream, StreamExt};
+
+use async_trait::async_trait;
+
+use arrow::array::{ArrayRef, UInt32Array};
+use arrow::compute::take;
+use arrow::datatypes::SchemaRef;
+use arrow::error::{ArrowError, Result as ArrowResult};
+use arrow::ipc::reader::FileReader;
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::RecordBatch;
+
+use super::common::{batch_memory_size, spawn_abortable, AbortOnDrop};
+use super::{hash_utils::create_hashes, RecordBatchStream, SendableRecordBatchStream};
+use crate::error::{DataFusionError, Result};
+use crate::execution::memory_manager::{MemoryManager, MemoryReservation};
+use crate::physical_plan::{ExecutionPlan, Partitioning};
+
+use pin_project_lite::pin_project;
+
+/// A batch buffered by an output partition, in memory, or in a temporary Arrow IPC file
+/// when its memory cannot be reserved
+#[derive(Debug)]
+enum OutputBatch {
+    Memory(RecordBatch),
+    Spilled(File),
+}
+
+type OutputSender = mpsc::UnboundedSender<ArrowResult<OutputBatch>>;
+type OutputReceiver = mpsc::UnboundedReceiver<ArrowResult<OutputBatch>>;
+/// The memory of the batches that the output partitions of an execution buffer
+type OutputReservation = Arc<Mutex<MemoryReservation>>;
+/// The receiver of an output partition, with the memory of the buffered batches and the
+/// tasks that send to the output partitions
+type OutputChannel = (OutputReceiver, OutputReservation, Arc<Vec<AbortOnDrop>>);
+
+/// Repartition execution plan executes its input partitions in parallel and redistributes
+/// their rows to its output partitions, either by batch in turn or by hash of the values of
+/// expressions. No guarantees are made about the order of the rows of a partition.
+///
+/// The output partitions buffer the batches that are not consumed yet, so that they can
+/// be consumed in any order. The memory of the buffered batches is reserved from a
+/// [MemoryManager], and the batches whose memory cannot be reserved are spilled to disk.
+#[derive(Debug)]
+pub struct RepartitionExec {
+    /// Input execution plan
+    input: Arc<dyn ExecutionPlan>,
+    /// Partitioning scheme of the output
+    partitioning: Partitioning,
+    /// The receiver of every output partition of the current execution, with the tasks
+    /// that send to the receivers, which are aborted once the receivers are all dropped.
+    /// The receivers are created when an output partition is executed for the first time
+    /// in the execution, and taken by the execution of their partition. A new execution
+    /// starts once every output partition of the current execution was executed.
+    channels: Mutex<Vec<Option<OutputChannel>>>,
+    /// Manager that the memory of the buffered batches is reserved from
+    memory_manager: Arc<MemoryManager>,
+}
+
+impl RepartitionExec {
+    /// Create a new RepartitionExec
+    pub fn try_new(
+        input: Arc<dyn ExecutionPlan>,
+        partitioning: Partitioning,
+    ) -> Result<Self> {
+        match &partitioning {
+            Partitioning::RoundRobinBatch(_) => {}
+            Partitioning::Hash(exprs, _) if !exprs.is_empty() => {}
+            Partitioning::Hash(_, _) => {
+                return Err(DataFusionError::Plan(
+                    "Hash repartitioning requires at least one expression".to_owned(),
+                ))
+            }
+            Partitioning::UnknownPartitioning(_) => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "Repartitioning to {:?} is not supported",
+                    partitioning
+                )))
+            }
+        }
+        if partitioning.partition_count() == 0 {
+            return Err(DataFusionError::Plan(
+                "Repartitioning requires at least one output partition".to_owned(),
+            ));
+        }
+
+        Ok(Self {
+            input,
+            partitioning,
+            channels: Mutex::new(vec![]),
+            memory_manager: Arc::new(MemoryManager::new(None)),
+        })
+    }
+
+    /// Reserve the memory of the buffered batches from `memory_manager`. The batches are
+    /// spilled to disk whenever their memory cannot be reserved.
+    pub fn with_memory_manager(mut self, memory_manager: Arc<MemoryManager>) -> Self {
+        self.memory_manager = memory_manager;
+        self
+    }
+
+    /// Spawns a task per input partition, which sends the rows of the partition to the
+    /// output partitions, and returns the receivers of the output partitions
+    fn start(&self) -> Vec<Option<OutputChannel>> {
+        let (senders, receivers): (Vec<_>, Vec<_>) =
+            (0..self.partitioning.partition_count())
+                .map(|_| mpsc::unbounded())
+                .unzip();
+        let reservation = Arc::new(Mutex::new(self.memory_manager.new_reservation()));
+
+        let mut tasks = vec![];
+        for input_partition in 0..self.input.output_partitioning().partition_count() {
+            let input = self.input.clone();
+            let partitioning = self.partitioning.clone();
+            let senders = senders.clone();
+            let reservation = reservation.clone();
+            let (_, task) = spawn_abortable(async move {
+                if let Err(e) = repartition(
+                    input,
+                    input_partition,
+                    &partitioning,
+                    &senders,
+                    &reservation,
+                )
+                .await
+                {
+                    // every output partition misses the rows of the input partition
+                    let message = e.to_string();
+                    for sender in &senders {
+                        let e = DataFusionError::Execution(message.clone());
+                        // If send fails, plan being torn down, there is no place to send
+                        // the error
+                        sender
+                            .unbounded_send(Err(ArrowError::ExternalError(Box::new(e))))
+                            .ok();
+                    }
+                }
+            });
+            tasks.push(task);
+        }
+
+        let tasks = Arc::new(tasks);
+        receivers
+            .into_iter()
+            .map(|receiver| Some((receiver, reservation.clone(), tasks.clone())))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for RepartitionExec {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    /// Get the output partitioning of this plan
+    fn output_partitioning(&self) -> Partitioning {
+        self.partitioning.clone()
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(
+                RepartitionExec::try_new(children[0].clone(), self.partitioning.clone())?
+                    .with_memory_manager(self.memory_manager.clone()),
+            )),
+            _ => Err(DataFusionError::Internal(
+                "RepartitionExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        if partition >= self.partitioning.partition_count() {
+            return Err(DataFusionError::Internal(format!(
+                "RepartitionExec invalid partition {}",
+                partition
+            )));
+        }
+
+        let receiver = {
+            let mut channels = self.channels.lock().map_err(|e| {
+                DataFusionError::Internal(format!(
+                    "RepartitionExec channels are poisoned: {}",
+                    e
+                ))
+            })?;
+            // once every output partition of the current execution was executed,
+            // executing a partition starts a new execution, which repartitions the input
+            // again
+            if channels.iter().all(Option::is_none) {
+                *channels = self.start();
+            }
+            channels[partition].take()
+        };
+
+        match receiver {
+            Some((receiver, reservation, tasks)) => Ok(Box::pin(RepartitionStream {
+                input: receiver,
+                schema: self.schema(),
+                reservation,
+                _tasks: tasks,
+            })),
+            None => Err(DataFusionError::Internal(format!(
+                "RepartitionExec partition {} was already executed, while other \
+                 partitions of its execution were not",
+                partition
+            ))),
+        }
+    }
+}
+
+/// Executes the partition `partition` of `input` and sends its rows to the output
+/// partitions of `partitioning`, whose senders are `senders`
+async fn repartition(
+    input: Arc<dyn ExecutionPlan>,
+    partition: usize,
+    partitioning: &Partitioning,
+    senders: &[OutputSender],
+    reservation: &OutputReservation,
+) -> Result<()> {
+    let mut stream = input.execute(partition).await?;
+
+    // the output partition of the next batch. The input partitions start at different
+    // output partitions, so that their first batches are spread.
+    let mut next_partition = partition % senders.len();
+    while let Some(batch) = stream.next().await {
+        let batch = batch?;
+        match partitioning {
+            Partitioning::RoundRobinBatch(_) => {
+                send(&senders[next_partition], batch, reservation)?;
+                next_partition = (next_partition + 1) % senders.len();
+            }
+            Partitioning::Hash(exprs, _) => {
+                let arrays = exprs
+                    .iter()
+                    .map(|expr| Ok(expr.evaluate(&batch)?.into_array(batch.num_rows())))
+                    .collect::<Result<Vec<_>>>()?;
+                let hashes = create_hashes(&arrays)?;
+
+                // the rows of the batch that belong to every output partition
+                let mut indices = vec![vec![]; senders.len()];
+                for (row, hash) in hashes.iter().enumerate() {
+                    indices[(*hash % senders.len() as u64) as usize].push(row as u32);
+                }
+
+                for (sender, indices) in senders.iter().zip(indices) {
+                    if indices.is_empty() {
+                        continue;
+                    }
+                    let indices = UInt32Array::from(indices);
+                    let columns = batch
+                        .columns()
+                        .iter()
+                        .map(|column| take(column, &indices, None))
+                        .collect::<ArrowResult<Vec<ArrayRef>>>()?;
+                    let batch = RecordBatch::try_new(batch.schema(), columns)?;
+                    send(sender, batch, reservation)?;
+                }
+            }
+            Partitioning::UnknownPartitioning(_) => {
+                return Err(DataFusionError::Internal(
+                    "RepartitionExec cannot repartition to an unknown partitioning"
+                        .to_owned(),
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+fn lock_reservation(reservation: &OutputReservation) -> MutexGuard<MemoryReservation> {
+    // the reservation is always consistent, even if a thread panicked while holding it
+    reservation
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Sends `batch` to an output partition, in memory if its memory can be reserved with
+/// `reservation`, or spilled to a temporary file otherwise
+fn send(
+    sender: &OutputSender,
+    batch: RecordBatch,
+    reservation: &OutputReservation,
+) -> Result<()> {
+    let size = batch_memory_size(&batch);
+    let reserved = lock_reservation(reservation).try_grow(size).is_ok();
+    let output = if reserved {
+        OutputBatch::Memory(batch)
+    } else {
+        OutputBatch::Spilled(spill(&batch)?)
+    };
+    // If send fails, plan being torn down, there is no place to send the batch
+    if sender.unbounded_send(Ok(output)).is_err() && reserved {
+        lock_reservation(reservation).shrink(size);
+    }
+    Ok(())
+}
+
+/// Writes a batch to a temporary Arrow IPC file, and returns the file positioned at its
+/// start. The file is removed once it is closed.
+fn spill(batch: &RecordBatch) -> Result<File> {
+    let mut file = tempfile::tempfile()?;
+    {
+        let mut writer =
+            FileWriter::try_new(BufWriter::new(file.try_clone()?), &batch.schema())?;
+        writer.write(batch)?;
+        writer.finish()?;
+    }
+    file.seek(SeekFrom::Start(0))?;
+    Ok(file)
+}
+
+/// Reads the batch of a file written by [spill]
+fn read_spill(file: File) -> ArrowResult<RecordBatch> {
+    let mut reader = FileReader::try_new(BufReader::new(file))?;
+    reader.next().unwrap_or_else(|| {
+        Err(ArrowError::IoError(
+            "The spilled batch of RepartitionExec is missing".to_owned(),
+        ))
+    })
+}
+
+pin_project! {
+    struct RepartitionStream {
+        schema: SchemaRef,
+        #[pin]
+        input: OutputReceiver,
+        // the memory of the batches buffered by the output partitions, which the batches
+        // release once they are received
+        reservation: OutputReservation,
+        // aborts the tasks that repartition the input once the streams of all the output
+        // partitions are dropped
+        _tasks: Arc<Vec<AbortOnDrop>>,
+    }
+}
+
+impl Stream for RepartitionStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = self.project();
+        let reservation = this.reservation;
+        this.input.poll_next(cx).map(|output| {
+            output.map(|output| match output? {
+                OutputBatch::Memory(batch) => {
+                    lock_reservation(reservation).shrink(batch_memory_size(&batch));
+                    Ok(batch)
+                }
+                OutputBatch::Spilled(file) => read_spill(file),
+            })
+        })
+    }
+}
+
+impl RecordBatchStream for RepartitionStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::physical_plan::common;
+    use crate::physical_plan::expressions::col;
+    use crate::physical_plan::memory::MemoryExec;
+    use arrow::array::{Array, UInt32Array};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use std::collections::HashMap;
+
+    /// a partition of 6 batches of 4 rows each, with the values 0 to 5 in column `a`
+    fn partition() -> Result<(SchemaRef, Vec<RecordBatch>)> {
+        let schema =
+            Arc::new(Schema::new(vec![Field::new("a", DataType::UInt32, false)]));
+        let batches = (0..6)
+            .map(|i| {
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![Arc::new(UInt32Array::from(vec![i, i + 1, i + 2, i + 3]))],
+                )
+            })
+            .collect::<ArrowResult<Vec<_>>>()?;
+        Ok((schema, batches))
+    }
+
+    /// Executes the output partitions, and consumes them one at a time
+    async fn execute_all(exec: &RepartitionExec) -> Result<Vec<Vec<RecordBatch>>> {
+        let mut partitions = vec![];
+        for partition in 0..exec.output_partitioning().partition_count() {
+            partitions.push(common::collect(exec.execute(partition).await?).await?);
+        }
+        Ok(partitions)
+    }
+
+    fn num_rows(batches: &[RecordBatch]) -> usize {
+        batches.iter().map(|batch| batch.num_rows()).sum()
+    }
+
+    /// the values of column `a` of the batches
+    fn values(batches: &[RecordBatch]) -> Vec<u32> {
+        batches
+            .iter()
+            .flat_map(|batch| {
+                let a = batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<UInt32Array>()
+                    .unwrap();
+                (0..a.len()).map(|row| a.value(row)).collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn round_robin_batch() -> Result<()> {
+        let (schema, batches) = partition()?;
+        let input = Arc::new(MemoryExec::try_new(&vec![batches], schema, None)?);
+        let exec = RepartitionExec::try_new(input, Partitioning::RoundRobinBatch(3))?;
+
+        let partitions = execute_all(&exec).await?;
+        assert_eq!(partitions.len(), 3);
+        for partition in &partitions {
+            assert_eq!(partition.len(), 2);
+        }
+
+        // executing the partitions again repartitions the input again
+        let partitions = execute_all(&exec).await?;
+        assert_eq!(partitions.len(), 3);
+        for partition in &partitions {
+            assert_eq!(partition.len(), 2);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn interleaved_executions() -> Result<()> {
+        let (schema, batches) = partition()?;
+        let input = Arc::new(MemoryExec::try_new(&vec![batches], schema, None)?);
+        let exec = RepartitionExec::try_new(input, Partitioning::RoundRobinBatch(3))?;
+
+        let stream2 = exec.execute(2).await?;
+        let stream0 = exec.execute(0).await?;
+        // the execution still has a partition that was not executed
+        assert!(exec.execute(0).await.is_err());
+        let stream1 = exec.execute(1).await?;
+
+        // a new execution starts once every partition was executed, which the partitions
+        // of the first execution do not share
+        let stream1_again = exec.execute(1).await?;
+        let stream0_again = exec.execute(0).await?;
+        let stream2_again = exec.execute(2).await?;
+
+        for stream in vec![stream1, stream1_again, stream0, stream2_again, stream2] {
+            assert_eq!(num_rows(&common::collect(stream).await?), 8);
+        }
+        assert_eq!(num_rows(&common::collect(stream0_again).await?), 8);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn many_batches() -> Result<()> {
+        // the output partitions buffer many batches, as they are consumed one at a time
+        let (schema, batches) = partition()?;
+        let batches = (0..20).flat_map(|_| batches.clone()).collect::<Vec<_>>();
+        let input = Arc::new(MemoryExec::try_new(
+            &vec![batches.clone(), batches],
+            schema,
+            None,
+        )?);
+        let exec =
+            RepartitionExec::try_new(input, Partitioning::Hash(vec![col("a")], 3))?;
+
+        let partitions = execute_all(&exec).await?;
+        let num_rows = partitions.iter().map(|p| num_rows(p)).sum::<usize>();
+        assert_eq!(num_rows, 2 * 20 * 6 * 4);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn spill_buffered_batches() -> Result<()> {
+        let (schema, batches) = partition()?;
+        let input = Arc::new(MemoryExec::try_new(
+            &vec![batches.clone(), batches.clone()],
+            schema,
+            None,
+        )?);
+        // the memory of no batch can be reserved, so that every batch is spilled
+        let memory_manager = Arc::new(MemoryManager::new(Some(1)));
+        let exec = RepartitionExec::try_new(input, Partitioning::RoundRobinBatch(2))?
+            .with_memory_manager(memory_manager.clone());
+
+        let partitions = execute_all(&exec).await?;
+        let mut expected = values(&batches);
+        expected.extend(values(&batches));
+        expected.sort_unstable();
+        let mut actual = values(&partitions.concat());
+        actual.sort_unstable();
+        assert_eq!(actual, expected);
+        assert_eq!(memory_manager.reserved(), 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn hash() -> Result<()> {
+        let (schema, batches) = partition()?;
+        let input = Arc::new(MemoryExec::try_new(
+            &vec![batches.clone(), batches],
+            schema,
+            None,
+        )?);
+        let exec =
+            RepartitionExec::try_new(input, Partitioning::Hash(vec![col("a")], 4))?;
+
+        // the partition of every value, with its number of rows
+        let mut values: HashMap<u32, (usize, usize)> = HashMap::new();
+        for (index, partition) in execute_all(&exec).await?.iter().enumerate() {
+            for batch in partition {
+                let a = batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<UInt32Array>()
+                    .unwrap();
+                for row in 0..a.len() {
+                    let entry = values.entry(a.value(row)).or_insert((index, 0));
+                    assert_eq!(entry.0, index);
+                    entry.1 += 1;
+                }
+            }
+        }
+
+        assert_eq!(values.len(), 9);
+        assert_eq!(values.values().map(|(_, rows)| rows).sum::<usize>(), 48);
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_partitioning() -> Result<()> {
+        let (schema, batches) = partition()?;
+        let input = Arc::new(MemoryExec::try_new(&vec![batches], schema, None)?);
+        assert!(RepartitionExec::try_new(
+            input.clone(),
+            Partitioning::UnknownPartitioning(2)
+        )
+        .is_err());
+        assert!(RepartitionExec::try_new(input, Partitioning::Hash(vec![], 2)).is_err());
+        Ok(())
+    }
+}
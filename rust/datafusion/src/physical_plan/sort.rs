@@ -18,30 +18,47 @@
 //! Defines the SORT plan
 
 use std::any::Any;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Seek, SeekFrom};
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
-use futures::stream::Stream;
-use futures::Future;
+use futures::channel::mpsc;
+use futures::sink::SinkExt;
+use futures::stream::{Stream, StreamExt};
 
 use pin_project_lite::pin_project;
 
+use arrow::array::{ArrayRef, UInt32Array};
 pub use arrow::compute::SortOptions;
-use arrow::compute::{concat, lexsort_to_indices, take, SortColumn, TakeOptions};
+use arrow::compute::{lexsort_to_indices, take, SortColumn, TakeOptions};
 use arrow::datatypes::SchemaRef;
 use arrow::error::Result as ArrowResult;
+use arrow::ipc::reader::FileReader;
+use arrow::ipc::writer::FileWriter;
 use arrow::record_batch::RecordBatch;
-use arrow::{array::ArrayRef, error::ArrowError};
 
+use super::common::{batch_memory_size, combine_batches, spawn_abortable, AbortOnDrop};
+use super::metrics;
+use super::sort_preserving_merge::merge_streams;
 use super::{RecordBatchStream, SendableRecordBatchStream};
 use crate::error::{DataFusionError, Result};
+use crate::execution::memory_manager::{MemoryManager, MemoryReservation};
 use crate::physical_plan::expressions::PhysicalSortExpr;
-use crate::physical_plan::{common, Distribution, ExecutionPlan, Partitioning};
+use crate::physical_plan::{Distribution, ExecutionPlan, Partitioning};
 
 use async_trait::async_trait;
 
-/// Sort execution plan
+/// Sort execution plan.
+///
+/// The input is sorted in memory, unless the batches that the sort buffers exceed its
+/// spill threshold: the buffered batches are then sorted into a run that is written to a
+/// temporary file, and the runs are merged once the whole input is sorted into runs.
+///
+/// Unless it preserves the partitioning of its input, the sort requires a single input
+/// partition. Otherwise, every input partition is sorted on its own, such as to be merged
+/// by a [super::sort_preserving_merge::SortPreservingMergeExec].
 #[derive(Debug)]
 pub struct SortExec {
     /// Input schema
@@ -50,10 +67,16 @@ pub struct SortExec {
     expr: Vec<PhysicalSortExpr>,
     /// Number of threads to execute input partitions on before combining into a single partition
     concurrency: usize,
+    /// Size in bytes of the buffered input batches above which they are spilled to disk
+    spill_threshold: usize,
+    /// Whether every input partition is sorted into its own output partition
+    preserve_partitioning: bool,
+    /// Manager that the memory of the buffered input batches is reserved from
+    memory_manager: Arc<MemoryManager>,
 }
 
 impl SortExec {
-    /// Create a new sort execution plan
+    /// Create a new sort execution plan, which sorts its whole input in memory
     pub fn try_new(
         expr: Vec<PhysicalSortExpr>,
         input: Arc<dyn ExecutionPlan>,
@@ -63,8 +86,42 @@ impl SortExec {
             expr,
             input,
             concurrency,
+            spill_threshold: usize::MAX,
+            preserve_partitioning: false,
+            memory_manager: Arc::new(MemoryManager::new(None)),
         })
     }
+
+    /// Spill sorted runs of the input to disk whenever the buffered input batches exceed
+    /// `spill_threshold` bytes
+    pub fn with_spill_threshold(mut self, spill_threshold: usize) -> Self {
+        self.spill_threshold = spill_threshold;
+        self
+    }
+
+    /// Sort every input partition into its own output partition, instead of sorting the
+    /// whole input into a single partition
+    pub fn with_preserve_partitioning(mut self, preserve_partitioning: bool) -> Self {
+        self.preserve_partitioning = preserve_partitioning;
+        self
+    }
+
+    /// Reserve the memory of the buffered input batches from `memory_manager`. Sorted runs
+    /// are also spilled to disk whenever more memory cannot be reserved.
+    pub fn with_memory_manager(mut self, memory_manager: Arc<MemoryManager>) -> Self {
+        self.memory_manager = memory_manager;
+        self
+    }
+
+    /// Input execution plan
+    pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.input
+    }
+
+    /// Sort expressions
+    pub fn expr(&self) -> &[PhysicalSortExpr] {
+        &self.expr
+    }
 }
 
 #[async_trait]
@@ -84,11 +141,23 @@ impl ExecutionPlan for SortExec {
 
     /// Get the output partitioning of this plan
     fn output_partitioning(&self) -> Partitioning {
-        Partitioning::UnknownPartitioning(1)
+        if self.preserve_partitioning {
+            self.input.output_partitioning()
+        } else {
+            Partitioning::UnknownPartitioning(1)
+        }
     }
 
     fn required_child_distribution(&self) -> Distribution {
-        Distribution::SinglePartition
+        if self.preserve_partitioning {
+            Distribution::UnspecifiedDistribution
+        } else {
+            Distribution::SinglePartition
+        }
+    }
+
+    fn output_ordering(&self) -> Option<Vec<PhysicalSortExpr>> {
+        Some(self.expr.clone())
     }
 
     fn with_new_children(
@@ -96,11 +165,16 @@ impl ExecutionPlan for SortExec {
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         match children.len() {
-            1 => Ok(Arc::new(SortExec::try_new(
-                self.expr.clone(),
-                children[0].clone(),
-                self.concurrency,
-            )?)),
+            1 => Ok(Arc::new(
+                SortExec::try_new(
+                    self.expr.clone(),
+                    children[0].clone(),
+                    self.concurrency,
+                )?
+                .with_spill_threshold(self.spill_threshold)
+                .with_preserve_partitioning(self.preserve_partitioning)
+                .with_memory_manager(self.memory_manager.clone()),
+            )),
             _ => Err(DataFusionError::Internal(
                 "SortExec wrong number of children".to_string(),
             )),
@@ -108,67 +182,61 @@ impl ExecutionPlan for SortExec {
     }
 
     async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
-        if 0 != partition {
+        if partition >= self.output_partitioning().partition_count() {
             return Err(DataFusionError::Internal(format!(
                 "SortExec invalid partition {}",
                 partition
             )));
         }
 
-        // sort needs to operate on a single partition currently
-        if 1 != self.input.output_partitioning().partition_count() {
+        // unless the partitions are sorted on their own, the sort needs to operate on a
+        // single partition
+        if !self.preserve_partitioning
+            && 1 != self.input.output_partitioning().partition_count()
+        {
             return Err(DataFusionError::Internal(
                 "SortExec requires a single input partition".to_owned(),
             ));
         }
-        let input = self.input.execute(0).await?;
+        let input = self.input.execute(partition).await?;
 
-        Ok(Box::pin(SortStream::new(input, self.expr.clone())))
+        Ok(Box::pin(SortStream::new(
+            input,
+            self.expr.clone(),
+            self.spill_threshold,
+            self.memory_manager.new_reservation(),
+        )))
     }
 }
 
-fn sort_batches(
-    batches: &Vec<RecordBatch>,
-    schema: &SchemaRef,
+/// Returns the indices of the rows of the batch in sorted order
+pub(crate) fn sort_indices(
+    batch: &RecordBatch,
     expr: &[PhysicalSortExpr],
-) -> ArrowResult<RecordBatch> {
-    // combine all record batches into one for each column
-    let combined_batch = RecordBatch::try_new(
-        schema.clone(),
-        schema
-            .fields()
-            .iter()
-            .enumerate()
-            .map(|(i, _)| {
-                concat(
-                    &batches
-                        .iter()
-                        .map(|batch| batch.columns()[i].clone())
-                        .collect::<Vec<ArrayRef>>(),
-                )
-            })
-            .collect::<ArrowResult<Vec<ArrayRef>>>()?,
-    )?;
-
-    // sort combined record batch
-    let indices = lexsort_to_indices(
+) -> ArrowResult<UInt32Array> {
+    lexsort_to_indices(
         &expr
             .iter()
-            .map(|e| e.evaluate_to_sort_column(&combined_batch))
+            .map(|e| e.evaluate_to_sort_column(batch))
             .collect::<Result<Vec<SortColumn>>>()
             .map_err(DataFusionError::into_arrow_external_error)?,
-    )?;
+    )
+}
 
-    // reorder all rows based on sorted indices
+/// Returns the rows of the batch at the given indices
+pub(crate) fn take_rows(
+    batch: &RecordBatch,
+    indices: &UInt32Array,
+) -> ArrowResult<RecordBatch> {
     RecordBatch::try_new(
-        schema.clone(),
-        combined_batch
+        batch.schema(),
+        batch
             .columns()
             .iter()
             .map(|column| {
                 take(
                     column,
-                    &indices,
+                    indices,
                     // disable bound check overhead since indices are already generated from
                     // the same record batch
                     Some(TakeOptions {
@@ -180,34 +248,179 @@ fn sort_batches(
     )
 }
 
+fn sort_batches(
+    batches: &[RecordBatch],
+    schema: &SchemaRef,
+    expr: &[PhysicalSortExpr],
+) -> ArrowResult<RecordBatch> {
+    // combine all record batches into one for each column
+    let combined_batch = combine_batches(batches, schema)?;
+
+    // sort combined record batch
+    let indices = sort_indices(&combined_batch, expr)?;
+
+    // reorder all rows based on sorted indices
+    take_rows(&combined_batch, &indices)
+}
+
+/// Sorts the input and sends the sorted batches to `sender`.
+///
+/// The input batches are buffered until they exceed `spill_threshold` bytes, or until the
+/// memory for them cannot be reserved with `reservation`, when they are sorted into a run
+/// that is spilled to a temporary file. Unless the whole input fits in memory, the runs are
+/// then merged.
+async fn external_sort(
+    mut input: SendableRecordBatchStream,
+    expr: &[PhysicalSortExpr],
+    spill_threshold: usize,
+    mut reservation: MemoryReservation,
+    sender: &mut mpsc::Sender<ArrowResult<RecordBatch>>,
+) -> Result<()> {
+    let schema = input.schema();
+    let mut buffered = vec![];
+    let mut buffered_size = 0;
+    let mut runs = vec![];
+    // the runs are spilled in batches of the size of the largest input batch
+    let mut batch_size = 0;
+    while let Some(batch) = input.next().await {
+        let batch = batch?;
+        batch_size = batch_size.max(batch.num_rows());
+        let size = batch_memory_size(&batch);
+        buffered_size += size;
+        buffered.push(batch);
+        metrics::record_memory(buffered_size);
+        if buffered_size > spill_threshold || reservation.try_grow(size).is_err() {
+            let run = sort_batches(&buffered, &schema, expr)?;
+            runs.push(spill(&run, batch_size)?);
+            buffered.clear();
+            buffered_size = 0;
+            reservation.free();
+        }
+    }
+
+    if runs.is_empty() {
+        // the whole input fits in memory
+        if !buffered.is_empty() {
+            // If send fails, plan being torn down, there is no place to send the batch
+            sender
+                .send(sort_batches(&buffered, &schema, expr))
+                .await
+                .ok();
+        }
+        return Ok(());
+    }
+    if !buffered.is_empty() {
+        let run = sort_batches(&buffered, &schema, expr)?;
+        runs.push(spill(&run, batch_size)?);
+    }
+    merge_runs(runs, &schema, expr, batch_size, sender).await
+}
+
+/// Writes a sorted run to a temporary Arrow IPC file, in batches of `batch_size` rows, and
+/// returns the file positioned at its start. The file is removed once it is closed.
+fn spill(run: &RecordBatch, batch_size: usize) -> Result<File> {
+    let mut file = tempfile::tempfile()?;
+    {
+        let mut writer =
+            FileWriter::try_new(BufWriter::new(file.try_clone()?), &run.schema())?;
+        let mut offset = 0;
+        while offset < run.num_rows() {
+            let end = run.num_rows().min(offset + batch_size);
+            let indices =
+                UInt32Array::from((offset as u32..end as u32).collect::<Vec<_>>());
+            writer.write(&take_rows(run, &indices)?)?;
+            offset = end;
+        }
+        writer.finish()?;
+    }
+    file.seek(SeekFrom::Start(0))?;
+    Ok(file)
+}
+
+/// Merges sorted runs into batches of `batch_size` rows, and sends them to `sender`.
+///
+/// The runs are read one batch at a time and merged as streams by a heap of the next row
+/// of every run.
+async fn merge_runs(
+    runs: Vec<File>,
+    schema: &SchemaRef,
+    expr: &[PhysicalSortExpr],
+    batch_size: usize,
+    sender: &mut mpsc::Sender<ArrowResult<RecordBatch>>,
+) -> Result<()> {
+    let streams = runs
+        .into_iter()
+        .map(|file| {
+            let stream = RunStream {
+                reader: FileReader::try_new(BufReader::new(file))?,
+                schema: schema.clone(),
+            };
+            Ok(Box::pin(stream) as SendableRecordBatchStream)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    merge_streams(streams, schema, expr, batch_size, sender).await
+}
+
+/// The batches of a sorted run spilled to a file
+struct RunStream {
+    reader: FileReader<BufReader<File>>,
+    schema: SchemaRef,
+}
+
+impl Stream for RunStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.reader.next())
+    }
+}
+
+impl RecordBatchStream for RunStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
 pin_project! {
     struct SortStream {
         #[pin]
-        output: futures::channel::oneshot::Receiver<ArrowResult<RecordBatch>>,
-        finished: bool,
+        output: mpsc::Receiver<ArrowResult<RecordBatch>>,
         schema: SchemaRef,
+        // aborts the sort once the stream is dropped
+        _task: AbortOnDrop,
     }
 }
 
 impl SortStream {
-    fn new(input: SendableRecordBatchStream, expr: Vec<PhysicalSortExpr>) -> Self {
-        let (tx, rx) = futures::channel::oneshot::channel();
+    fn new(
+        input: SendableRecordBatchStream,
+        expr: Vec<PhysicalSortExpr>,
+        spill_threshold: usize,
+        reservation: MemoryReservation,
+    ) -> Self {
+        let (mut sender, receiver) = mpsc::channel(1);
 
         let schema = input.schema();
-        tokio::spawn(async move {
-            let schema = input.schema();
-            let sorted_batch = common::collect(input)
-                .await
-                .map_err(DataFusionError::into_arrow_external_error)
-                .and_then(move |batches| sort_batches(&batches, &schema, &expr));
-
-            tx.send(sorted_batch)
+        let (_, task) = spawn_abortable(async move {
+            if let Err(e) =
+                external_sort(input, &expr, spill_threshold, reservation, &mut sender)
+                    .await
+            {
+                // If send fails, plan being torn down, there is no place to send the error
+                sender
+                    .send(Err(DataFusionError::into_arrow_external_error(e)))
+                    .await
+                    .ok();
+            }
         });
 
         Self {
-            output: rx,
-            finished: false,
+            output: receiver,
             schema,
+            _task: task,
         }
     }
 }
@@ -216,27 +429,7 @@ impl Stream for SortStream {
     type Item = ArrowResult<RecordBatch>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        if self.finished {
-            return Poll::Ready(None);
-        }
-
-        // is the output ready?
-        let this = self.project();
-        let output_poll = this.output.poll(cx);
-
-        match output_poll {
-            Poll::Ready(result) => {
-                *this.finished = true;
-
-                // check for error in receiving channel and unwrap actual result
-                let result = match result {
-                    Err(e) => Err(ArrowError::ExternalError(Box::new(e))), // error receiving
-                    Ok(result) => result,
-                };
-                Poll::Ready(Some(result))
-            }
-            Poll::Pending => Poll::Pending,
-        }
+        self.project().output.poll_next(cx)
     }
 }
 
@@ -408,4 +601,134 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_sort_spill() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, false),
+        ]));
+
+        // every batch exceeds the spill threshold, so that every batch is spilled as a run
+        let batches = (0..4)
+            .map(|i| {
+                let a = (0..10)
+                    .map(|j| match (i * 10 + j) % 7 {
+                        0 => None,
+                        _ => Some((i * 10 + j) * 13 % 17),
+                    })
+                    .collect::<Vec<_>>();
+                let b = (0..10)
+                    .map(|j| format!("{}", i * 10 + j))
+                    .collect::<Vec<_>>();
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![
+                        Arc::new(Int32Array::from(a)),
+                        Arc::new(StringArray::from(
+                            b.iter().map(|s| s.as_str()).collect::<Vec<_>>(),
+                        )),
+                    ],
+                )
+            })
+            .collect::<ArrowResult<Vec<_>>>()?;
+
+        let sort_exec = Arc::new(
+            SortExec::try_new(
+                vec![
+                    PhysicalSortExpr {
+                        expr: col("a"),
+                        options: SortOptions {
+                            descending: true,
+                            nulls_first: true,
+                        },
+                    },
+                    PhysicalSortExpr {
+                        expr: col("b"),
+                        options: SortOptions::default(),
+                    },
+                ],
+                Arc::new(MemoryExec::try_new(&vec![batches], schema, None)?),
+                2,
+            )?
+            .with_spill_threshold(1),
+        );
+
+        let result: Vec<RecordBatch> = test::execute(sort_exec).await?;
+        assert!(result.len() > 1);
+
+        let mut actual = vec![];
+        for batch in &result {
+            let a = as_primitive_array::<Int32Type>(batch.column(0));
+            let b = as_string_array(batch.column(1));
+            for i in 0..batch.num_rows() {
+                let a = if a.is_valid(i) {
+                    Some(a.value(i))
+                } else {
+                    None
+                };
+                actual.push((a, b.value(i).to_owned()));
+            }
+        }
+
+        let mut expected = (0..40)
+            .map(|i| match i % 7 {
+                0 => (None, format!("{}", i)),
+                _ => (Some(i * 13 % 17), format!("{}", i)),
+            })
+            .collect::<Vec<_>>();
+        // nulls first, then descending values of `a`, then ascending values of `b`
+        expected.sort_by(|(a1, b1), (a2, b2)| match (a1, a2) {
+            (None, None) => b1.cmp(b2),
+            (None, Some(_)) => std::cmp::Ordering::Less,
+            (Some(_), None) => std::cmp::Ordering::Greater,
+            (Some(a1), Some(a2)) => a2.cmp(a1).then_with(|| b1.cmp(b2)),
+        });
+
+        assert_eq!(expected, actual);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sort_memory_limit() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batches = (0..4)
+            .map(|i| {
+                let a = (0..10).map(|j| (i * 10 + j) * 13 % 40).collect::<Vec<_>>();
+                RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(a))])
+            })
+            .collect::<ArrowResult<Vec<_>>>()?;
+
+        // no batch fits in the memory limit, so that every batch is spilled as a run
+        // instead of failing the sort
+        let memory_manager = Arc::new(MemoryManager::new(Some(1)));
+        let sort_exec = Arc::new(
+            SortExec::try_new(
+                vec![PhysicalSortExpr {
+                    expr: col("a"),
+                    options: SortOptions::default(),
+                }],
+                Arc::new(MemoryExec::try_new(&vec![batches], schema, None)?),
+                1,
+            )?
+            .with_memory_manager(memory_manager.clone()),
+        );
+
+        let result: Vec<RecordBatch> = test::execute(sort_exec).await?;
+        assert!(result.len() > 1);
+        let actual = result
+            .iter()
+            .flat_map(|batch| {
+                let a = as_primitive_array::<Int32Type>(batch.column(0));
+                (0..batch.num_rows())
+                    .map(|i| a.value(i))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(actual, (0..40).collect::<Vec<_>>());
+        assert_eq!(memory_manager.reserved(), 0);
+
+        Ok(())
+    }
 }
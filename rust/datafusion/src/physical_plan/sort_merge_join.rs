@@ -0,0 +1,796 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the sort-merge join plan, which joins two inputs that are sorted by their join
+//! keys by merging them one key at a time.
+
+use std::any::Any;
+use std::cmp::Ordering;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::channel::mpsc;
+use futures::sink::SinkExt;
+use futures::stream::{Stream, StreamExt};
+
+use pin_project_lite::pin_project;
+
+use arrow::array::ArrayRef;
+use arrow::compute::SortOptions;
+use arrow::datatypes::SchemaRef;
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+
+use async_trait::async_trait;
+
+use super::common::{
+    build_comparators, combine_batches, compare_rows, spawn_abortable, AbortOnDrop,
+};
+use super::expressions::{col, PhysicalSortExpr};
+use super::hash_join::{build_batch_from_indices, JoinIndex};
+use super::hash_utils::{build_join_schema, check_join_is_valid, JoinOn, JoinType};
+use super::{
+    Distribution, ExecutionPlan, Partitioning, RecordBatchStream,
+    SendableRecordBatchStream,
+};
+use crate::error::{DataFusionError, Result};
+
+/// Sort-merge join execution plan.
+///
+/// Both inputs must have a single partition whose rows are sorted by the join keys, in the
+/// order of `on` and with `sort_options`. The inputs are merged one key at a time, so that
+/// only the rows of the current key of each input are kept in memory.
+#[derive(Debug)]
+pub struct SortMergeJoinExec {
+    /// left side of the join
+    left: Arc<dyn ExecutionPlan>,
+    /// right side of the join
+    right: Arc<dyn ExecutionPlan>,
+    /// Set of common columns used to join on
+    on: Vec<(String, String)>,
+    /// How the join is performed
+    join_type: JoinType,
+    /// How the rows of both sides are sorted by every key of `on`
+    sort_options: Vec<SortOptions>,
+    /// The schema once the join is applied
+    schema: SchemaRef,
+    /// Number of rows above which the joined rows are emitted as a batch
+    batch_size: usize,
+}
+
+impl SortMergeJoinExec {
+    /// Tries to create a new [SortMergeJoinExec].
+    /// # Error
+    /// This function errors when it is not possible to join the left and right sides on keys
+    /// `on`, or when there is not one sort option per key.
+    pub fn try_new(
+        left: Arc<dyn ExecutionPlan>,
+        right: Arc<dyn ExecutionPlan>,
+        on: &JoinOn,
+        join_type: &JoinType,
+        sort_options: Vec<SortOptions>,
+        batch_size: usize,
+    ) -> Result<Self> {
+        let left_schema = left.schema();
+        let right_schema = right.schema();
+        check_join_is_valid(&left_schema, &right_schema, &on)?;
+
+        if sort_options.len() != on.len() {
+            return Err(DataFusionError::Plan(format!(
+                "A sort-merge join requires one sort option per join key, but has {} keys and {} sort options",
+                on.len(),
+                sort_options.len()
+            )));
+        }
+
+        let schema = Arc::new(build_join_schema(
+            &left_schema,
+            &right_schema,
+            on,
+            &join_type,
+        ));
+
+        let on = on
+            .iter()
+            .map(|(l, r)| (l.to_string(), r.to_string()))
+            .collect();
+
+        Ok(Self {
+            left,
+            right,
+            on,
+            join_type: join_type.clone(),
+            sort_options,
+            schema,
+            batch_size,
+        })
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for SortMergeJoinExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.left.clone(), self.right.clone()]
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn required_child_distribution(&self) -> Distribution {
+        Distribution::SinglePartition
+    }
+
+    fn output_ordering(&self) -> Option<Vec<PhysicalSortExpr>> {
+        // the rows are emitted in the order of the keys of the side whose rows are all
+        // emitted with their keys. On a full join, the keys of the rows of either side
+        // without a match are null on the other side.
+        let keys = match self.join_type {
+            JoinType::Inner | JoinType::Left | JoinType::Semi | JoinType::Anti => {
+                self.on.iter().map(|(l, _)| l).collect::<Vec<_>>()
+            }
+            JoinType::Right => self.on.iter().map(|(_, r)| r).collect::<Vec<_>>(),
+            JoinType::Full => return None,
+        };
+        Some(
+            keys.iter()
+                .zip(self.sort_options.iter())
+                .map(|(name, options)| PhysicalSortExpr {
+                    expr: col(name),
+                    options: *options,
+                })
+                .collect(),
+        )
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            2 => Ok(Arc::new(SortMergeJoinExec::try_new(
+                children[0].clone(),
+                children[1].clone(),
+                &self.on,
+                &self.join_type,
+                self.sort_options.clone(),
+                self.batch_size,
+            )?)),
+            _ => Err(DataFusionError::Internal(
+                "SortMergeJoinExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        if 0 != partition {
+            return Err(DataFusionError::Internal(format!(
+                "SortMergeJoinExec invalid partition {}",
+                partition
+            )));
+        }
+
+        // the rows of both sides are only sorted within a partition
+        if 1 != self.left.output_partitioning().partition_count()
+            || 1 != self.right.output_partitioning().partition_count()
+        {
+            return Err(DataFusionError::Internal(
+                "SortMergeJoinExec requires a single input partition on each side"
+                    .to_owned(),
+            ));
+        }
+
+        let on_left = self.on.iter().map(|on| on.0.clone()).collect::<Vec<_>>();
+        let on_right = self.on.iter().map(|on| on.1.clone()).collect::<Vec<_>>();
+        let left = SortedInput::new(
+            self.left.execute(0).await?,
+            on_left,
+            self.sort_options.clone(),
+        );
+        let right = SortedInput::new(
+            self.right.execute(0).await?,
+            on_right,
+            self.sort_options.clone(),
+        );
+
+        let (sender, receiver) = mpsc::channel(1);
+        let mut joiner = MergeJoiner {
+            schema: self.schema.clone(),
+            left_schema: self.left.schema(),
+            right_schema: self.right.schema(),
+            join_type: self.join_type.clone(),
+            sort_options: self.sort_options.clone(),
+            batch_size: self.batch_size,
+            output: vec![],
+            output_rows: 0,
+            sender,
+        };
+        let (_, task) = spawn_abortable(async move {
+            if let Err(e) = joiner.join(left, right).await {
+                // If send fails, plan being torn down, there is no place to send the error
+                joiner
+                    .sender
+                    .send(Err(DataFusionError::into_arrow_external_error(e)))
+                    .await
+                    .ok();
+            }
+        });
+
+        Ok(Box::pin(SortMergeJoinStream {
+            output: receiver,
+            schema: self.schema.clone(),
+            _task: task,
+        }))
+    }
+}
+
+/// Returns the rows `offset..offset + length` of `batch`
+fn slice_batch(batch: &RecordBatch, offset: usize, length: usize) -> Result<RecordBatch> {
+    Ok(RecordBatch::try_new(
+        batch.schema(),
+        batch
+            .columns()
+            .iter()
+            .map(|column| column.slice(offset, length))
+            .collect(),
+    )?)
+}
+
+/// Evaluates the keys `on` of `batch`
+fn evaluate_keys(batch: &RecordBatch, on: &[String]) -> Result<Vec<ArrayRef>> {
+    on.iter()
+        .map(|name| Ok(col(name).evaluate(batch)?.into_array(batch.num_rows())))
+        .collect()
+}
+
+/// The consecutive rows of an input with equal keys
+struct KeyGroup {
+    /// the rows of the group
+    batch: RecordBatch,
+    /// the keys of the rows of the group
+    keys: Vec<ArrayRef>,
+}
+
+impl KeyGroup {
+    /// Whether a key of the group is null, in which case its rows match no row
+    fn has_null_key(&self) -> bool {
+        self.keys.iter().any(|key| key.is_null(0))
+    }
+}
+
+/// An input of the join, which is read one [KeyGroup] at a time
+struct SortedInput {
+    stream: SendableRecordBatchStream,
+    /// the keys by which the input is sorted
+    on: Vec<String>,
+    sort_options: Vec<SortOptions>,
+    /// the batch being read, with its keys
+    batch: Option<(RecordBatch, Vec<ArrayRef>)>,
+    /// the next row of `batch` to read
+    row: usize,
+    /// the keys of the last group that was read, used to check that the input is sorted
+    last_keys: Option<Vec<ArrayRef>>,
+}
+
+impl SortedInput {
+    fn new(
+        stream: SendableRecordBatchStream,
+        on: Vec<String>,
+        sort_options: Vec<SortOptions>,
+    ) -> Self {
+        Self {
+            stream,
+            on,
+            sort_options,
+            batch: None,
+            row: 0,
+            last_keys: None,
+        }
+    }
+
+    /// Reads batches until there is a row to read, returning `false` once the input is
+    /// exhausted
+    async fn fill(&mut self) -> Result<bool> {
+        loop {
+            if let Some((batch, _)) = &self.batch {
+                if self.row < batch.num_rows() {
+                    return Ok(true);
+                }
+            }
+            match self.stream.next().await {
+                Some(batch) => {
+                    let batch = batch?;
+                    let keys = evaluate_keys(&batch, &self.on)?;
+                    self.batch = Some((batch, keys));
+                    self.row = 0;
+                }
+                None => {
+                    self.batch = None;
+                    return Ok(false);
+                }
+            }
+        }
+    }
+
+    /// Reads the next group of rows with equal keys, which may span several batches
+    async fn next_group(&mut self) -> Result<Option<KeyGroup>> {
+        if !self.fill().await? {
+            return Ok(None);
+        }
+        // the keys of the first row of the group
+        let first_keys = match &self.batch {
+            Some((_, keys)) => keys
+                .iter()
+                .map(|key| key.slice(self.row, 1))
+                .collect::<Vec<_>>(),
+            None => return Ok(None),
+        };
+
+        let mut slices = vec![];
+        loop {
+            let (end, num_rows) = match &self.batch {
+                Some((batch, keys)) => {
+                    let comparators = build_comparators(keys, &first_keys)?;
+                    let mut end = self.row;
+                    while end < batch.num_rows()
+                        && compare_rows(
+                            keys,
+                            end,
+                            &first_keys,
+                            0,
+                            &comparators,
+                            &self.sort_options,
+                        ) == Ordering::Equal
+                    {
+                        end += 1;
+                    }
+                    if end > self.row {
+                        slices.push(slice_batch(batch, self.row, end - self.row)?);
+                    }
+                    (end, batch.num_rows())
+                }
+                None => break,
+            };
+            self.row = end;
+            // the group continues in the next batch when it reaches the end of this one
+            if end < num_rows || !self.fill().await? {
+                break;
+            }
+        }
+
+        let batch = combine_batches(&slices, &slices[0].schema())?;
+        let keys = evaluate_keys(&batch, &self.on)?;
+
+        if let Some(last_keys) = &self.last_keys {
+            let comparators = build_comparators(last_keys, &keys)?;
+            if compare_rows(last_keys, 0, &keys, 0, &comparators, &self.sort_options)
+                != Ordering::Less
+            {
+                return Err(DataFusionError::Execution(
+                    "The input of a sort-merge join is not sorted by the join keys"
+                        .to_owned(),
+                ));
+            }
+        }
+        self.last_keys = Some(first_keys);
+
+        Ok(Some(KeyGroup { batch, keys }))
+    }
+}
+
+/// Merges the groups of both inputs and sends the joined rows in batches
+struct MergeJoiner {
+    schema: SchemaRef,
+    left_schema: SchemaRef,
+    right_schema: SchemaRef,
+    join_type: JoinType,
+    sort_options: Vec<SortOptions>,
+    batch_size: usize,
+    /// the joined rows that were not sent yet
+    output: Vec<RecordBatch>,
+    /// the number of rows of `output`
+    output_rows: usize,
+    sender: mpsc::Sender<ArrowResult<RecordBatch>>,
+}
+
+impl MergeJoiner {
+    /// Joins `left` and `right`
+    async fn join(
+        &mut self,
+        mut left: SortedInput,
+        mut right: SortedInput,
+    ) -> Result<()> {
+        let mut left_group = left.next_group().await?;
+        let mut right_group = right.next_group().await?;
+        loop {
+            // the side whose group has the smallest key is advanced. A side that is
+            // exhausted is greater than any key of the other side.
+            let ordering = match (&left_group, &right_group) {
+                (Some(l), Some(r)) => {
+                    let comparators = build_comparators(&l.keys, &r.keys)?;
+                    compare_rows(&l.keys, 0, &r.keys, 0, &comparators, &self.sort_options)
+                }
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => break,
+            };
+            match ordering {
+                Ordering::Less => {
+                    if let Some(l) = &left_group {
+                        self.emit_left_unmatched(l).await?;
+                    }
+                    left_group = left.next_group().await?;
+                }
+                Ordering::Greater => {
+                    if let Some(r) = &right_group {
+                        self.emit_right_unmatched(r).await?;
+                    }
+                    right_group = right.next_group().await?;
+                }
+                Ordering::Equal => {
+                    if let (Some(l), Some(r)) = (&left_group, &right_group) {
+                        if l.has_null_key() {
+                            // null keys are equal in the order, but do not match
+                            self.emit_left_unmatched(l).await?;
+                            self.emit_right_unmatched(r).await?;
+                        } else {
+                            self.emit_matched(l, r).await?;
+                        }
+                    }
+                    left_group = left.next_group().await?;
+                    right_group = right.next_group().await?;
+                }
+            }
+        }
+        self.flush().await
+    }
+
+    /// Emits the rows of a group of the left without a match on the right
+    async fn emit_left_unmatched(&mut self, left: &KeyGroup) -> Result<()> {
+        match self.join_type {
+            JoinType::Left | JoinType::Full | JoinType::Anti => {
+                let indices = (0..left.batch.num_rows())
+                    .map(|row| (Some((0, row)), None))
+                    .collect::<Vec<_>>();
+                self.emit(&[left.batch.clone()], &[], &indices).await
+            }
+            JoinType::Inner | JoinType::Right | JoinType::Semi => Ok(()),
+        }
+    }
+
+    /// Emits the rows of a group of the right without a match on the left
+    async fn emit_right_unmatched(&mut self, right: &KeyGroup) -> Result<()> {
+        match self.join_type {
+            JoinType::Right | JoinType::Full => {
+                let indices = (0..right.batch.num_rows())
+                    .map(|row| (None, Some((0, row))))
+                    .collect::<Vec<_>>();
+                self.emit(&[], &[right.batch.clone()], &indices).await
+            }
+            JoinType::Inner | JoinType::Left | JoinType::Semi | JoinType::Anti => Ok(()),
+        }
+    }
+
+    /// Emits the rows of two groups with equal keys
+    async fn emit_matched(&mut self, left: &KeyGroup, right: &KeyGroup) -> Result<()> {
+        let indices = match self.join_type {
+            JoinType::Inner | JoinType::Left | JoinType::Right | JoinType::Full => {
+                // every row of the left is paired with every row of the right
+                let mut indices =
+                    Vec::with_capacity(left.batch.num_rows() * right.batch.num_rows());
+                for l in 0..left.batch.num_rows() {
+                    for r in 0..right.batch.num_rows() {
+                        indices.push((Some((0, l)), Some((0, r))));
+                    }
+                }
+                indices
+            }
+            JoinType::Semi => (0..left.batch.num_rows())
+                .map(|row| (Some((0, row)), None))
+                .collect(),
+            JoinType::Anti => return Ok(()),
+        };
+        self.emit(&[left.batch.clone()], &[right.batch.clone()], &indices)
+            .await
+    }
+
+    /// Buffers the rows `indices` of `left` and `right`, and sends the buffered rows once
+    /// they reach the batch size
+    async fn emit(
+        &mut self,
+        left: &[RecordBatch],
+        right: &[RecordBatch],
+        indices: &[(JoinIndex, JoinIndex)],
+    ) -> Result<()> {
+        let batch = build_batch_from_indices(
+            &self.schema,
+            &self.left_schema,
+            left,
+            &self.right_schema,
+            right,
+            &self.join_type,
+            indices,
+        )?;
+        self.output_rows += batch.num_rows();
+        self.output.push(batch);
+        if self.output_rows >= self.batch_size {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Sends the buffered rows as a single batch
+    async fn flush(&mut self) -> Result<()> {
+        if self.output.is_empty() {
+            return Ok(());
+        }
+        let batch = combine_batches(&self.output, &self.schema)?;
+        self.output.clear();
+        self.output_rows = 0;
+        // If send fails, plan being torn down, there is no place to send the batch
+        self.sender.send(Ok(batch)).await.ok();
+        Ok(())
+    }
+}
+
+pin_project! {
+    struct SortMergeJoinStream {
+        #[pin]
+        output: mpsc::Receiver<ArrowResult<RecordBatch>>,
+        schema: SchemaRef,
+        // aborts the join once the stream is dropped
+        _task: AbortOnDrop,
+    }
+}
+
+impl Stream for SortMergeJoinStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.project().output.poll_next(cx)
+    }
+}
+
+impl RecordBatchStream for SortMergeJoinStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::{common, memory::MemoryExec};
+    use crate::test::{build_table_i32, columns, format_batch};
+    use arrow::array::Int32Array;
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    /// Returns a plan with a single partition of `batches`
+    fn build_partition(batches: Vec<RecordBatch>) -> Arc<dyn ExecutionPlan> {
+        let schema = batches[0].schema();
+        Arc::new(MemoryExec::try_new(&vec![batches], schema, None).unwrap())
+    }
+
+    fn build_table(
+        a: (&str, &Vec<i32>),
+        b: (&str, &Vec<i32>),
+        c: (&str, &Vec<i32>),
+    ) -> Arc<dyn ExecutionPlan> {
+        build_partition(vec![build_table_i32(a, b, c)])
+    }
+
+    fn join(
+        left: Arc<dyn ExecutionPlan>,
+        right: Arc<dyn ExecutionPlan>,
+        on: &[(&str, &str)],
+        join_type: &JoinType,
+    ) -> Result<SortMergeJoinExec> {
+        let on: Vec<_> = on
+            .iter()
+            .map(|(l, r)| (l.to_string(), r.to_string()))
+            .collect();
+        // a small batch size, so that the joined rows are sent in several batches
+        SortMergeJoinExec::try_new(
+            left,
+            right,
+            &on,
+            join_type,
+            vec![SortOptions::default(); on.len()],
+            2,
+        )
+    }
+
+    /// Executes `join` and returns its rows in order
+    async fn execute(join: &SortMergeJoinExec) -> Result<Vec<String>> {
+        let batches = common::collect(join.execute(0).await?).await?;
+        Ok(batches.iter().flat_map(format_batch).collect())
+    }
+
+    #[tokio::test]
+    async fn join_inner_one() -> Result<()> {
+        let left = build_table(
+            ("a1", &vec![1, 2, 3]),
+            ("b1", &vec![4, 5, 5]), // this has a repetition
+            ("c1", &vec![7, 8, 9]),
+        );
+        let right = build_table(
+            ("a2", &vec![10, 20, 30]),
+            ("b1", &vec![4, 5, 6]),
+            ("c2", &vec![70, 80, 90]),
+        );
+
+        let join = join(left, right, &[("b1", "b1")], &JoinType::Inner)?;
+
+        let columns = columns(&join.schema());
+        assert_eq!(columns, vec!["a1", "b1", "c1", "a2", "c2"]);
+
+        let ordering = join.output_ordering().unwrap();
+        assert_eq!(ordering.len(), 1);
+        assert_eq!(ordering[0].expr.to_string(), "b1");
+
+        let expected = vec!["1,4,7,10,70", "2,5,8,20,80", "3,5,9,20,80"];
+        assert_eq!(execute(&join).await?, expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_inner_keys_across_batches() -> Result<()> {
+        // the rows with the key 5 span two batches on both sides
+        let left = build_partition(vec![
+            build_table_i32(
+                ("a1", &vec![1, 2]),
+                ("b1", &vec![4, 5]),
+                ("c1", &vec![7, 8]),
+            ),
+            build_table_i32(("a1", &vec![3]), ("b1", &vec![5]), ("c1", &vec![9])),
+        ]);
+        let right = build_partition(vec![
+            build_table_i32(("a2", &vec![10]), ("b1", &vec![4]), ("c2", &vec![70])),
+            build_table_i32(
+                ("a2", &vec![20, 30]),
+                ("b1", &vec![5, 5]),
+                ("c2", &vec![80, 90]),
+            ),
+        ]);
+
+        let join = join(left, right, &[("b1", "b1")], &JoinType::Inner)?;
+
+        let expected = vec![
+            "1,4,7,10,70",
+            "2,5,8,20,80",
+            "2,5,8,30,90",
+            "3,5,9,20,80",
+            "3,5,9,30,90",
+        ];
+        assert_eq!(execute(&join).await?, expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_types() -> Result<()> {
+        let cases = vec![
+            (JoinType::Inner, vec!["1,4,7,10,4,70", "2,5,8,20,5,80"]),
+            (
+                JoinType::Left,
+                vec!["1,4,7,10,4,70", "2,5,8,20,5,80", "3,7,9,NULL,NULL,NULL"],
+            ),
+            (
+                JoinType::Right,
+                vec!["1,4,7,10,4,70", "2,5,8,20,5,80", "NULL,NULL,NULL,30,6,90"],
+            ),
+            (
+                JoinType::Full,
+                vec![
+                    "1,4,7,10,4,70",
+                    "2,5,8,20,5,80",
+                    "NULL,NULL,NULL,30,6,90",
+                    "3,7,9,NULL,NULL,NULL",
+                ],
+            ),
+            (JoinType::Semi, vec!["1,4,7", "2,5,8"]),
+            (JoinType::Anti, vec!["3,7,9"]),
+        ];
+
+        for (join_type, expected) in cases {
+            let left = build_table(
+                ("a1", &vec![1, 2, 3]),
+                ("b1", &vec![4, 5, 7]),
+                ("c1", &vec![7, 8, 9]),
+            );
+            let right = build_table(
+                ("a2", &vec![10, 20, 30]),
+                ("b2", &vec![4, 5, 6]),
+                ("c2", &vec![70, 80, 90]),
+            );
+            let join = join(left, right, &[("b1", "b2")], &join_type)?;
+            assert_eq!(execute(&join).await?, expected, "{:?}", join_type);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_null_keys() -> Result<()> {
+        let left_schema = Arc::new(Schema::new(vec![
+            Field::new("a1", DataType::Int32, false),
+            Field::new("b1", DataType::Int32, true),
+        ]));
+        let left = build_partition(vec![RecordBatch::try_new(
+            left_schema,
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3])),
+                Arc::new(Int32Array::from(vec![None, Some(1), Some(2)])),
+            ],
+        )?]);
+        let right_schema = Arc::new(Schema::new(vec![
+            Field::new("b1", DataType::Int32, true),
+            Field::new("c2", DataType::Int32, false),
+        ]));
+        let right = build_partition(vec![RecordBatch::try_new(
+            right_schema,
+            vec![
+                Arc::new(Int32Array::from(vec![None, Some(2)])),
+                Arc::new(Int32Array::from(vec![10, 20])),
+            ],
+        )?]);
+
+        // null keys do not match
+        let inner = join(
+            left.clone(),
+            right.clone(),
+            &[("b1", "b1")],
+            &JoinType::Inner,
+        )?;
+        assert_eq!(execute(&inner).await?, vec!["3,2,20"]);
+
+        let full = join(left, right, &[("b1", "b1")], &JoinType::Full)?;
+        let expected = vec!["1,NULL,NULL", "NULL,NULL,10", "2,1,NULL", "3,2,20"];
+        assert_eq!(execute(&full).await?, expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_unsorted_input() -> Result<()> {
+        let left = build_table(
+            ("a1", &vec![1, 2, 3]),
+            ("b1", &vec![5, 4, 6]),
+            ("c1", &vec![7, 8, 9]),
+        );
+        let right = build_table(
+            ("a2", &vec![10, 20, 30]),
+            ("b1", &vec![4, 5, 6]),
+            ("c2", &vec![70, 80, 90]),
+        );
+
+        let join = join(left, right, &[("b1", "b1")], &JoinType::Inner)?;
+        assert!(execute(&join).await.is_err());
+
+        Ok(())
+    }
+}
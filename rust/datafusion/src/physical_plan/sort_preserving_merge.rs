@@ -0,0 +1,693 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the sort preserving merge plan, which merges the sorted partitions of its input
+//! into a single sorted partition
+
+use std::any::Any;
+use std::collections::BinaryHeap;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::channel::mpsc;
+use futures::sink::SinkExt;
+use futures::stream::{Stream, StreamExt};
+
+use pin_project_lite::pin_project;
+
+use arrow::array::{make_array, ArrayRef, MutableArrayData};
+use arrow::compute::SortOptions;
+use arrow::datatypes::SchemaRef;
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+
+use async_trait::async_trait;
+
+use super::common::{build_run_comparators, spawn_abortable, AbortOnDrop, RunCursor};
+use super::expressions::PhysicalSortExpr;
+use super::{ExecutionPlan, Partitioning, RecordBatchStream, SendableRecordBatchStream};
+use crate::error::{DataFusionError, Result};
+
+/// Sort preserving merge execution plan.
+///
+/// Every partition of the input must be sorted by the sort expressions, such as by a
+/// [super::sort::SortExec] that preserves the partitioning of its input. The partitions are
+/// read concurrently and merged with a heap of the next row of every partition into a
+/// single sorted partition.
+#[derive(Debug)]
+pub struct SortPreservingMergeExec {
+    /// Input execution plan
+    input: Arc<dyn ExecutionPlan>,
+    /// Sort expressions
+    expr: Vec<PhysicalSortExpr>,
+    /// Maximum number of rows of the output batches
+    batch_size: usize,
+}
+
+impl SortPreservingMergeExec {
+    /// Create a new sort preserving merge execution plan, which merges the partitions of
+    /// `input`, sorted by `expr`, into batches of `batch_size` rows
+    pub fn new(
+        expr: Vec<PhysicalSortExpr>,
+        input: Arc<dyn ExecutionPlan>,
+        batch_size: usize,
+    ) -> Self {
+        Self {
+            input,
+            expr,
+            batch_size,
+        }
+    }
+
+    /// Input execution plan
+    pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.input
+    }
+
+    /// Sort expressions
+    pub fn expr(&self) -> &[PhysicalSortExpr] {
+        &self.expr
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for SortPreservingMergeExec {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    /// Get the output partitioning of this plan
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn output_ordering(&self) -> Option<Vec<PhysicalSortExpr>> {
+        Some(self.expr.clone())
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(SortPreservingMergeExec::new(
+                self.expr.clone(),
+                children[0].clone(),
+                self.batch_size,
+            ))),
+            _ => Err(DataFusionError::Internal(
+                "SortPreservingMergeExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        if 0 != partition {
+            return Err(DataFusionError::Internal(format!(
+                "SortPreservingMergeExec invalid partition {}",
+                partition
+            )));
+        }
+
+        let (mut sender, receiver) = mpsc::channel(1);
+        let input = self.input.clone();
+        let expr = self.expr.clone();
+        let batch_size = self.batch_size;
+        let (_, task) = spawn_abortable(async move {
+            if let Err(e) =
+                sort_preserving_merge(input, &expr, batch_size, &mut sender).await
+            {
+                // If send fails, plan being torn down, there is no place to send the error
+                sender
+                    .send(Err(DataFusionError::into_arrow_external_error(e)))
+                    .await
+                    .ok();
+            }
+        });
+
+        Ok(Box::pin(SortPreservingMergeStream {
+            output: receiver,
+            schema: self.schema(),
+            _task: task,
+        }))
+    }
+}
+
+/// An input partition that is being merged
+struct MergeInput {
+    stream: SendableRecordBatchStream,
+    /// the current batch of the partition, or `None` once the partition is exhausted
+    batch: Option<RecordBatch>,
+    /// the values of the sort expressions of the current batch
+    keys: Vec<ArrayRef>,
+    /// the next row of the current batch to merge
+    row: usize,
+    /// the index of the current batch among the batches of the rows to output
+    slot: usize,
+}
+
+impl MergeInput {
+    /// Reads the next batch of the partition with any rows, if any
+    async fn next_batch(&mut self, expr: &[PhysicalSortExpr]) -> Result<()> {
+        self.batch = None;
+        self.keys = vec![];
+        self.row = 0;
+        while let Some(batch) = self.stream.next().await {
+            let batch = batch?;
+            if batch.num_rows() > 0 {
+                self.keys = expr
+                    .iter()
+                    .map(|e| Ok(e.evaluate_to_sort_column(&batch)?.values))
+                    .collect::<Result<Vec<_>>>()?;
+                self.batch = Some(batch);
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// The number of rows of the current batch
+    fn num_rows(&self) -> usize {
+        self.batch.as_ref().map_or(0, |batch| batch.num_rows())
+    }
+}
+
+/// Merges the partitions of `input`, which are sorted by `expr`, and sends the merged
+/// batches of `batch_size` rows to `sender`
+async fn sort_preserving_merge(
+    input: Arc<dyn ExecutionPlan>,
+    expr: &[PhysicalSortExpr],
+    batch_size: usize,
+    sender: &mut mpsc::Sender<ArrowResult<RecordBatch>>,
+) -> Result<()> {
+    let schema = input.schema();
+
+    // every partition is executed and read by a task of its own, so that the partitions
+    // progress while the merge waits for the next batch of another partition
+    let mut streams = vec![];
+    let mut tasks = vec![];
+    for partition in 0..input.output_partitioning().partition_count() {
+        let (mut sender, receiver) = mpsc::channel(1);
+        let input = input.clone();
+        let (_, task) = spawn_abortable(async move {
+            let mut stream = match input.execute(partition).await {
+                Err(e) => {
+                    // If send fails, plan being torn down, there is no place to send the
+                    // error
+                    sender
+                        .send(Err(DataFusionError::into_arrow_external_error(e)))
+                        .await
+                        .ok();
+                    return;
+                }
+                Ok(stream) => stream,
+            };
+            while let Some(item) = stream.next().await {
+                if sender.send(item).await.is_err() {
+                    // the merge is being torn down, there is no place to send the batch
+                    return;
+                }
+            }
+        });
+        tasks.push(task);
+        streams.push(Box::pin(InputStream {
+            input: receiver,
+            schema: schema.clone(),
+        }) as SendableRecordBatchStream);
+    }
+
+    // the tasks are aborted once the merge is done
+    let result = merge_streams(streams, &schema, expr, batch_size, sender).await;
+    drop(tasks);
+    result
+}
+
+/// Merges `streams` of batches of `schema`, which are sorted by `expr`, and sends the
+/// merged batches of `batch_size` rows to `sender`. The next row of every stream is kept
+/// in a heap, so that only the current batch of every stream is held in memory.
+pub(crate) async fn merge_streams(
+    streams: Vec<SendableRecordBatchStream>,
+    schema: &SchemaRef,
+    expr: &[PhysicalSortExpr],
+    batch_size: usize,
+    sender: &mut mpsc::Sender<ArrowResult<RecordBatch>>,
+) -> Result<()> {
+    // the output batches have at least one row, so that the merge progresses
+    let batch_size = batch_size.max(1);
+    let sort_options = expr.iter().map(|e| e.options).collect::<Vec<_>>();
+
+    // the batches of the rows to output
+    let mut batches = vec![];
+    // the rows to output, as the index of their batch and their row within the batch
+    let mut rows = Vec::with_capacity(batch_size);
+
+    let mut inputs = vec![];
+    for stream in streams {
+        let mut input = MergeInput {
+            stream,
+            batch: None,
+            keys: vec![],
+            row: 0,
+            slot: 0,
+        };
+        input.next_batch(expr).await?;
+        if let Some(batch) = &input.batch {
+            input.slot = batches.len();
+            batches.push(batch.clone());
+        }
+        inputs.push(input);
+    }
+
+    loop {
+        merge_batches(&mut inputs, &sort_options, batch_size, &mut rows)?;
+
+        if rows.len() == batch_size {
+            let output = build_batch(schema, &batches, &rows)?;
+            if sender.send(Ok(output)).await.is_err() {
+                // the plan is being torn down, there is no place to send the batch
+                return Ok(());
+            }
+            rows.clear();
+            // only the current batches of the inputs have rows left to output
+            batches.clear();
+            for input in inputs.iter_mut() {
+                if let Some(batch) = &input.batch {
+                    input.slot = batches.len();
+                    batches.push(batch.clone());
+                }
+            }
+        }
+
+        // the rows of an input can only be merged again once its next batch is read
+        let mut exhausted = true;
+        for input in inputs.iter_mut() {
+            if input.batch.is_some() && input.row == input.num_rows() {
+                input.next_batch(expr).await?;
+                if let Some(batch) = &input.batch {
+                    input.slot = batches.len();
+                    batches.push(batch.clone());
+                }
+            }
+            exhausted &= input.batch.is_none();
+        }
+
+        if exhausted {
+            if !rows.is_empty() {
+                // If send fails, plan being torn down, there is no place to send the batch
+                sender.send(build_batch(schema, &batches, &rows)).await.ok();
+            }
+            return Ok(());
+        }
+    }
+}
+
+/// Merges the rows of the current batches of `inputs` into `rows`, until `rows` holds
+/// `batch_size` rows or the current batch of an input is exhausted
+fn merge_batches(
+    inputs: &mut [MergeInput],
+    sort_options: &[SortOptions],
+    batch_size: usize,
+    rows: &mut Vec<(usize, usize)>,
+) -> Result<()> {
+    let keys = inputs
+        .iter()
+        .map(|input| input.keys.clone())
+        .collect::<Vec<_>>();
+    let comparators = build_run_comparators(&keys)?;
+
+    let mut heap = inputs
+        .iter()
+        .enumerate()
+        .filter(|(_, input)| input.batch.is_some())
+        .map(|(run, input)| RunCursor {
+            run,
+            row: input.row,
+            keys: &keys,
+            comparators: &comparators,
+            sort_options,
+        })
+        .collect::<BinaryHeap<_>>();
+
+    while rows.len() < batch_size {
+        let mut cursor = match heap.pop() {
+            Some(cursor) => cursor,
+            None => break,
+        };
+        let input = &mut inputs[cursor.run];
+        rows.push((input.slot, cursor.row));
+        cursor.row += 1;
+        input.row = cursor.row;
+        if cursor.row == input.num_rows() {
+            break;
+        }
+        heap.push(cursor);
+    }
+    Ok(())
+}
+
+/// Builds a batch from `rows` of `batches`, given as the index of their batch and their row
+/// within the batch
+fn build_batch(
+    schema: &SchemaRef,
+    batches: &[RecordBatch],
+    rows: &[(usize, usize)],
+) -> ArrowResult<RecordBatch> {
+    let columns = (0..schema.fields().len())
+        .map(|column| {
+            let arrays = batches
+                .iter()
+                .map(|batch| batch.column(column).data())
+                .collect::<Vec<_>>();
+            let arrays = arrays
+                .iter()
+                .map(|array| array.as_ref())
+                .collect::<Vec<_>>();
+            let mut mutable = MutableArrayData::new(arrays, false, rows.len());
+            // consecutive rows of a batch are copied at once
+            let mut start = 0;
+            while start < rows.len() {
+                let (batch, row) = rows[start];
+                let mut end = start + 1;
+                while end < rows.len() && rows[end] == (batch, row + end - start) {
+                    end += 1;
+                }
+                mutable.extend(batch, row, row + end - start);
+                start = end;
+            }
+            make_array(Arc::new(mutable.freeze()))
+        })
+        .collect::<Vec<_>>();
+    RecordBatch::try_new(schema.clone(), columns)
+}
+
+pin_project! {
+    /// The batches of an input partition, which a task reads
+    struct InputStream {
+        #[pin]
+        input: mpsc::Receiver<ArrowResult<RecordBatch>>,
+        schema: SchemaRef,
+    }
+}
+
+impl Stream for InputStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.project().input.poll_next(cx)
+    }
+}
+
+impl RecordBatchStream for InputStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+pin_project! {
+    struct SortPreservingMergeStream {
+        #[pin]
+        output: mpsc::Receiver<ArrowResult<RecordBatch>>,
+        schema: SchemaRef,
+        // aborts the merge once the stream is dropped
+        _task: AbortOnDrop,
+    }
+}
+
+impl Stream for SortPreservingMergeStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.project().output.poll_next(cx)
+    }
+}
+
+impl RecordBatchStream for SortPreservingMergeStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::common;
+    use crate::physical_plan::expressions::col;
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::sort::SortExec;
+    use crate::test::format_batch;
+    use arrow::array::{Int32Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use futures::channel::oneshot;
+    use futures::future::{BoxFuture, FutureExt};
+    use std::sync::Mutex;
+
+    /// Returns a plan with a partition per vector of batches of `partitions`, with the
+    /// values of every batch in column `a` and their partition in column `b`
+    fn build_partitions(
+        partitions: Vec<Vec<Vec<Option<i32>>>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, false),
+        ]));
+        let partitions = partitions
+            .into_iter()
+            .enumerate()
+            .map(|(partition, batches)| {
+                batches
+                    .into_iter()
+                    .map(|values| {
+                        let names = vec![format!("p{}", partition); values.len()];
+                        let names = names.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+                        Ok(RecordBatch::try_new(
+                            schema.clone(),
+                            vec![
+                                Arc::new(Int32Array::from(values)),
+                                Arc::new(StringArray::from(names)),
+                            ],
+                        )?)
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Arc::new(MemoryExec::try_new(&partitions, schema, None)?))
+    }
+
+    async fn execute(exec: SortPreservingMergeExec) -> Result<Vec<RecordBatch>> {
+        common::collect(exec.execute(0).await?).await
+    }
+
+    #[tokio::test]
+    async fn merge_sorted_partitions() -> Result<()> {
+        let input = build_partitions(vec![
+            vec![vec![None, Some(1), Some(4)], vec![], vec![Some(6), Some(9)]],
+            vec![vec![Some(2), Some(4)], vec![Some(5)]],
+            vec![],
+            vec![vec![None, Some(3), Some(7), Some(8)]],
+        ])?;
+        let expr = vec![PhysicalSortExpr {
+            expr: col("a"),
+            options: SortOptions::default(),
+        }];
+
+        let merge = SortPreservingMergeExec::new(expr, input, 4);
+        let batches = execute(merge).await?;
+        assert_eq!(
+            batches
+                .iter()
+                .map(|batch| batch.num_rows())
+                .collect::<Vec<_>>(),
+            vec![4, 4, 4]
+        );
+        // equal rows are taken from the partitions in their order
+        let rows = batches.iter().flat_map(format_batch).collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            vec![
+                "NULL,p0", "NULL,p3", "1,p0", "2,p1", "3,p3", "4,p0", "4,p1", "5,p1",
+                "6,p0", "7,p3", "8,p3", "9,p0",
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn merge_partitions_sorted_in_parallel() -> Result<()> {
+        let input = build_partitions(vec![
+            vec![vec![Some(5), None, Some(1)], vec![Some(8)]],
+            vec![vec![Some(7), Some(2)], vec![Some(3), Some(6)]],
+        ])?;
+        let expr = vec![PhysicalSortExpr {
+            expr: col("a"),
+            options: SortOptions {
+                descending: true,
+                nulls_first: false,
+            },
+        }];
+
+        let sort =
+            SortExec::try_new(expr.clone(), input, 2)?.with_preserve_partitioning(true);
+        assert_eq!(sort.output_partitioning().partition_count(), 2);
+
+        let merge = SortPreservingMergeExec::new(expr, Arc::new(sort), 1024);
+        let rows = execute(merge)
+            .await?
+            .iter()
+            .flat_map(format_batch)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            vec!["8,p0", "7,p1", "6,p1", "5,p0", "3,p1", "2,p1", "1,p0", "NULL,p0"]
+        );
+
+        Ok(())
+    }
+
+    /// A plan of two partitions of a batch each, whose first partition only produces its
+    /// batch once the batch of the second partition is read
+    #[derive(Debug)]
+    struct DependentPartitionsExec {
+        batches: Vec<RecordBatch>,
+        sender: Mutex<Option<oneshot::Sender<()>>>,
+        receiver: Mutex<Option<oneshot::Receiver<()>>>,
+    }
+
+    impl DependentPartitionsExec {
+        fn new(batches: Vec<RecordBatch>) -> Self {
+            let (sender, receiver) = oneshot::channel();
+            Self {
+                batches,
+                sender: Mutex::new(Some(sender)),
+                receiver: Mutex::new(Some(receiver)),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl ExecutionPlan for DependentPartitionsExec {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn schema(&self) -> SchemaRef {
+            self.batches[0].schema()
+        }
+
+        fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+            vec![]
+        }
+
+        fn output_partitioning(&self) -> Partitioning {
+            Partitioning::UnknownPartitioning(2)
+        }
+
+        fn with_new_children(
+            &self,
+            _: Vec<Arc<dyn ExecutionPlan>>,
+        ) -> Result<Arc<dyn ExecutionPlan>> {
+            Err(DataFusionError::Internal(
+                "DependentPartitionsExec has no children".to_string(),
+            ))
+        }
+
+        async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+            let batch = self.batches[partition].clone();
+            let stream: BoxFuture<'static, ArrowResult<RecordBatch>> = if partition == 0 {
+                let receiver = self.receiver.lock().unwrap().take().unwrap();
+                async move {
+                    receiver.await.ok();
+                    Ok(batch)
+                }
+                .boxed()
+            } else {
+                let sender = self.sender.lock().unwrap().take().unwrap();
+                async move {
+                    sender.send(()).ok();
+                    Ok(batch)
+                }
+                .boxed()
+            };
+            Ok(Box::pin(OnceStream {
+                schema: self.schema(),
+                stream: futures::stream::once(stream).boxed(),
+            }))
+        }
+    }
+
+    struct OnceStream {
+        schema: SchemaRef,
+        stream: futures::stream::BoxStream<'static, ArrowResult<RecordBatch>>,
+    }
+
+    impl Stream for OnceStream {
+        type Item = ArrowResult<RecordBatch>;
+
+        fn poll_next(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<Option<Self::Item>> {
+            self.stream.poll_next_unpin(cx)
+        }
+    }
+
+    impl RecordBatchStream for OnceStream {
+        fn schema(&self) -> SchemaRef {
+            self.schema.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn merge_partitions_read_concurrently() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batches = vec![vec![1, 3], vec![2, 4]]
+            .into_iter()
+            .map(|values| {
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![Arc::new(Int32Array::from(values))],
+                )
+            })
+            .collect::<ArrowResult<Vec<_>>>()?;
+        let expr = vec![PhysicalSortExpr {
+            expr: col("a"),
+            options: SortOptions::default(),
+        }];
+
+        // the first partition only progresses once the second partition is read
+        let input = Arc::new(DependentPartitionsExec::new(batches));
+        let merge = SortPreservingMergeExec::new(expr, input, 1024);
+        let rows = execute(merge)
+            .await?
+            .iter()
+            .flat_map(format_batch)
+            .collect::<Vec<_>>();
+        assert_eq!(rows, vec!["1", "2", "3", "4"]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,786 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the statistical aggregate functions: the variance, standard deviation,
+//! covariance and correlation, which are computed from moments that are merged in a
+//! numerically stable way, and the median.
+
+use std::sync::Arc;
+
+use arrow::array::{Array, ArrayRef, Float64Array};
+use arrow::datatypes::{DataType, Field};
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::expressions::format_state_name;
+use crate::physical_plan::{Accumulator, AggregateExpr, PhysicalExpr};
+use crate::scalar::ScalarValue;
+
+/// Whether a statistic is computed for a sample of a population or for the whole
+/// population, i.e. whether its sum of squares is divided by `n - 1` or by `n`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsType {
+    /// the values are a sample of the population
+    Sample,
+    /// the values are the whole population
+    Population,
+}
+
+impl StatsType {
+    /// The divisor of a sum of squares of `count` values, if the statistic is defined
+    fn divisor(&self, count: u64) -> Option<f64> {
+        match self {
+            StatsType::Sample if count > 1 => Some((count - 1) as f64),
+            StatsType::Population if count > 0 => Some(count as f64),
+            _ => None,
+        }
+    }
+}
+
+/// The count, the means and the co-moment of pairs of values, i.e. the sum of the
+/// products of their deviations from their means. The co-moment of a value with itself is
+/// its sum of squares.
+#[derive(Debug, Default, Clone, Copy)]
+struct CoMoments {
+    count: u64,
+    mean1: f64,
+    mean2: f64,
+    co_moment: f64,
+}
+
+impl CoMoments {
+    /// Adds a pair of values, with Welford's algorithm
+    fn update(&mut self, value1: f64, value2: f64) {
+        self.count += 1;
+        let delta1 = value1 - self.mean1;
+        self.mean1 += delta1 / self.count as f64;
+        self.mean2 += (value2 - self.mean2) / self.count as f64;
+        self.co_moment += delta1 * (value2 - self.mean2);
+    }
+
+    /// Adds the pairs of values of `other`
+    fn merge(&mut self, other: &CoMoments) {
+        if other.count == 0 {
+            return;
+        }
+        let count = self.count + other.count;
+        let delta1 = other.mean1 - self.mean1;
+        let delta2 = other.mean2 - self.mean2;
+        let weight = other.count as f64 / count as f64;
+        self.co_moment += other.co_moment + delta1 * delta2 * self.count as f64 * weight;
+        self.mean1 += delta1 * weight;
+        self.mean2 += delta2 * weight;
+        self.count = count;
+    }
+
+    /// Adds each pair of non-null values of `values1` and `values2`
+    fn update_batch(&mut self, values1: &ArrayRef, values2: &ArrayRef) -> Result<()> {
+        let values1 = downcast_float64(values1)?;
+        let values2 = downcast_float64(values2)?;
+        for i in 0..values1.len() {
+            if values1.is_valid(i) && values2.is_valid(i) {
+                self.update(values1.value(i), values2.value(i));
+            }
+        }
+        Ok(())
+    }
+
+    /// The covariance of the pairs of values
+    fn covariance(&self, stats_type: StatsType) -> Option<f64> {
+        stats_type
+            .divisor(self.count)
+            .map(|divisor| self.co_moment / divisor)
+    }
+}
+
+fn downcast_float64(array: &ArrayRef) -> Result<&Float64Array> {
+    array
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .ok_or_else(|| {
+            DataFusionError::Internal("could not cast values to Float64Array".to_string())
+        })
+}
+
+fn scalar_to_u64(value: &ScalarValue) -> Result<u64> {
+    match value {
+        ScalarValue::UInt64(value) => Ok(value.unwrap_or_default()),
+        other => Err(DataFusionError::Internal(format!(
+            "Unexpected count {:?} of a statistical aggregate",
+            other
+        ))),
+    }
+}
+
+fn scalar_to_f64(value: &ScalarValue) -> Result<f64> {
+    match value {
+        ScalarValue::Float64(value) => Ok(value.unwrap_or_default()),
+        other => Err(DataFusionError::Internal(format!(
+            "Unexpected moment {:?} of a statistical aggregate",
+            other
+        ))),
+    }
+}
+
+/// The value of a `Float64` scalar, if it is not null
+fn scalar_to_option_f64(value: &ScalarValue) -> Result<Option<f64>> {
+    match value {
+        ScalarValue::Float64(value) => Ok(*value),
+        other => Err(DataFusionError::Internal(format!(
+            "Unexpected value {:?} of a statistical aggregate",
+            other
+        ))),
+    }
+}
+
+/// VARIANCE and STDDEV aggregate expressions, of a sample or of a population
+#[derive(Debug)]
+pub struct Variance {
+    name: String,
+    expr: Arc<dyn PhysicalExpr>,
+    stats_type: StatsType,
+    /// whether the standard deviation is returned rather than the variance
+    stddev: bool,
+}
+
+impl Variance {
+    /// Create a new VARIANCE aggregate function
+    pub fn new(expr: Arc<dyn PhysicalExpr>, name: String, stats_type: StatsType) -> Self {
+        Self {
+            name,
+            expr,
+            stats_type,
+            stddev: false,
+        }
+    }
+
+    /// Create a new STDDEV aggregate function
+    pub fn new_stddev(
+        expr: Arc<dyn PhysicalExpr>,
+        name: String,
+        stats_type: StatsType,
+    ) -> Self {
+        Self {
+            stddev: true,
+            ..Self::new(expr, name, stats_type)
+        }
+    }
+}
+
+impl AggregateExpr for Variance {
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, DataType::Float64, true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(vec![
+            Field::new(
+                &format_state_name(&self.name, "count"),
+                DataType::UInt64,
+                true,
+            ),
+            Field::new(
+                &format_state_name(&self.name, "mean"),
+                DataType::Float64,
+                true,
+            ),
+            Field::new(
+                &format_state_name(&self.name, "m2"),
+                DataType::Float64,
+                true,
+            ),
+        ])
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(VarianceAccumulator {
+            moments: CoMoments::default(),
+            stats_type: self.stats_type,
+            stddev: self.stddev,
+        }))
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+}
+
+/// An accumulator of the variance, from the co-moments of each value with itself
+#[derive(Debug)]
+struct VarianceAccumulator {
+    moments: CoMoments,
+    stats_type: StatsType,
+    stddev: bool,
+}
+
+impl Accumulator for VarianceAccumulator {
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        Ok(vec![
+            ScalarValue::from(self.moments.count),
+            ScalarValue::from(self.moments.mean1),
+            ScalarValue::from(self.moments.co_moment),
+        ])
+    }
+
+    fn update(&mut self, values: &Vec<ScalarValue>) -> Result<()> {
+        if let Some(value) = scalar_to_option_f64(&values[0])? {
+            self.moments.update(value, value);
+        }
+        Ok(())
+    }
+
+    fn update_batch(&mut self, values: &Vec<ArrayRef>) -> Result<()> {
+        self.moments.update_batch(&values[0], &values[0])
+    }
+
+    fn merge(&mut self, states: &Vec<ScalarValue>) -> Result<()> {
+        let mean = scalar_to_f64(&states[1])?;
+        self.moments.merge(&CoMoments {
+            count: scalar_to_u64(&states[0])?,
+            mean1: mean,
+            mean2: mean,
+            co_moment: scalar_to_f64(&states[2])?,
+        });
+        Ok(())
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        let variance = self.moments.covariance(self.stats_type);
+        Ok(ScalarValue::Float64(if self.stddev {
+            variance.map(f64::sqrt)
+        } else {
+            variance
+        }))
+    }
+}
+
+/// COVAR aggregate expression, of a sample or of a population
+#[derive(Debug)]
+pub struct Covariance {
+    name: String,
+    expr1: Arc<dyn PhysicalExpr>,
+    expr2: Arc<dyn PhysicalExpr>,
+    stats_type: StatsType,
+}
+
+impl Covariance {
+    /// Create a new COVAR aggregate function
+    pub fn new(
+        expr1: Arc<dyn PhysicalExpr>,
+        expr2: Arc<dyn PhysicalExpr>,
+        name: String,
+        stats_type: StatsType,
+    ) -> Self {
+        Self {
+            name,
+            expr1,
+            expr2,
+            stats_type,
+        }
+    }
+}
+
+impl AggregateExpr for Covariance {
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, DataType::Float64, true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(vec![
+            Field::new(
+                &format_state_name(&self.name, "count"),
+                DataType::UInt64,
+                true,
+            ),
+            Field::new(
+                &format_state_name(&self.name, "mean1"),
+                DataType::Float64,
+                true,
+            ),
+            Field::new(
+                &format_state_name(&self.name, "mean2"),
+                DataType::Float64,
+                true,
+            ),
+            Field::new(
+                &format_state_name(&self.name, "co_moment"),
+                DataType::Float64,
+                true,
+            ),
+        ])
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(CovarianceAccumulator {
+            moments: CoMoments::default(),
+            stats_type: self.stats_type,
+        }))
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr1.clone(), self.expr2.clone()]
+    }
+}
+
+#[derive(Debug)]
+struct CovarianceAccumulator {
+    moments: CoMoments,
+    stats_type: StatsType,
+}
+
+impl Accumulator for CovarianceAccumulator {
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        Ok(vec![
+            ScalarValue::from(self.moments.count),
+            ScalarValue::from(self.moments.mean1),
+            ScalarValue::from(self.moments.mean2),
+            ScalarValue::from(self.moments.co_moment),
+        ])
+    }
+
+    fn update(&mut self, values: &Vec<ScalarValue>) -> Result<()> {
+        let value1 = scalar_to_option_f64(&values[0])?;
+        let value2 = scalar_to_option_f64(&values[1])?;
+        if let (Some(value1), Some(value2)) = (value1, value2) {
+            self.moments.update(value1, value2);
+        }
+        Ok(())
+    }
+
+    fn update_batch(&mut self, values: &Vec<ArrayRef>) -> Result<()> {
+        self.moments.update_batch(&values[0], &values[1])
+    }
+
+    fn merge(&mut self, states: &Vec<ScalarValue>) -> Result<()> {
+        self.moments.merge(&CoMoments {
+            count: scalar_to_u64(&states[0])?,
+            mean1: scalar_to_f64(&states[1])?,
+            mean2: scalar_to_f64(&states[2])?,
+            co_moment: scalar_to_f64(&states[3])?,
+        });
+        Ok(())
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        Ok(ScalarValue::Float64(
+            self.moments.covariance(self.stats_type),
+        ))
+    }
+}
+
+/// CORR aggregate expression, the Pearson correlation coefficient
+#[derive(Debug)]
+pub struct Correlation {
+    name: String,
+    expr1: Arc<dyn PhysicalExpr>,
+    expr2: Arc<dyn PhysicalExpr>,
+}
+
+impl Correlation {
+    /// Create a new CORR aggregate function
+    pub fn new(
+        expr1: Arc<dyn PhysicalExpr>,
+        expr2: Arc<dyn PhysicalExpr>,
+        name: String,
+    ) -> Self {
+        Self { name, expr1, expr2 }
+    }
+}
+
+impl AggregateExpr for Correlation {
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, DataType::Float64, true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(vec![
+            Field::new(
+                &format_state_name(&self.name, "count"),
+                DataType::UInt64,
+                true,
+            ),
+            Field::new(
+                &format_state_name(&self.name, "mean1"),
+                DataType::Float64,
+                true,
+            ),
+            Field::new(
+                &format_state_name(&self.name, "mean2"),
+                DataType::Float64,
+                true,
+            ),
+            Field::new(
+                &format_state_name(&self.name, "co_moment"),
+                DataType::Float64,
+                true,
+            ),
+            Field::new(
+                &format_state_name(&self.name, "m2_1"),
+                DataType::Float64,
+                true,
+            ),
+            Field::new(
+                &format_state_name(&self.name, "m2_2"),
+                DataType::Float64,
+                true,
+            ),
+        ])
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(CorrelationAccumulator::default()))
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr1.clone(), self.expr2.clone()]
+    }
+}
+
+/// An accumulator of the correlation, from the co-moments of the pairs of values and of
+/// each of their values with itself
+#[derive(Debug, Default)]
+struct CorrelationAccumulator {
+    moments: CoMoments,
+    moments1: CoMoments,
+    moments2: CoMoments,
+}
+
+impl CorrelationAccumulator {
+    fn update_pair(&mut self, value1: f64, value2: f64) {
+        self.moments.update(value1, value2);
+        self.moments1.update(value1, value1);
+        self.moments2.update(value2, value2);
+    }
+}
+
+impl Accumulator for CorrelationAccumulator {
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        Ok(vec![
+            ScalarValue::from(self.moments.count),
+            ScalarValue::from(self.moments.mean1),
+            ScalarValue::from(self.moments.mean2),
+            ScalarValue::from(self.moments.co_moment),
+            ScalarValue::from(self.moments1.co_moment),
+            ScalarValue::from(self.moments2.co_moment),
+        ])
+    }
+
+    fn update(&mut self, values: &Vec<ScalarValue>) -> Result<()> {
+        let value1 = scalar_to_option_f64(&values[0])?;
+        let value2 = scalar_to_option_f64(&values[1])?;
+        if let (Some(value1), Some(value2)) = (value1, value2) {
+            self.update_pair(value1, value2);
+        }
+        Ok(())
+    }
+
+    fn update_batch(&mut self, values: &Vec<ArrayRef>) -> Result<()> {
+        let values1 = downcast_float64(&values[0])?;
+        let values2 = downcast_float64(&values[1])?;
+        for i in 0..values1.len() {
+            if values1.is_valid(i) && values2.is_valid(i) {
+                self.update_pair(values1.value(i), values2.value(i));
+            }
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &Vec<ScalarValue>) -> Result<()> {
+        let count = scalar_to_u64(&states[0])?;
+        let mean1 = scalar_to_f64(&states[1])?;
+        let mean2 = scalar_to_f64(&states[2])?;
+        self.moments.merge(&CoMoments {
+            count,
+            mean1,
+            mean2,
+            co_moment: scalar_to_f64(&states[3])?,
+        });
+        self.moments1.merge(&CoMoments {
+            count,
+            mean1,
+            mean2: mean1,
+            co_moment: scalar_to_f64(&states[4])?,
+        });
+        self.moments2.merge(&CoMoments {
+            count,
+            mean1: mean2,
+            mean2,
+            co_moment: scalar_to_f64(&states[5])?,
+        });
+        Ok(())
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        let denominator = (self.moments1.co_moment * self.moments2.co_moment).sqrt();
+        // the correlation of constant values is undefined
+        Ok(ScalarValue::Float64(
+            if self.moments.count == 0 || denominator == 0.0 {
+                None
+            } else {
+                Some(self.moments.co_moment / denominator)
+            },
+        ))
+    }
+}
+
+/// MEDIAN aggregate expression, which buffers all the non-null values of each group
+#[derive(Debug)]
+pub struct Median {
+    name: String,
+    expr: Arc<dyn PhysicalExpr>,
+}
+
+impl Median {
+    /// Create a new MEDIAN aggregate function
+    pub fn new(expr: Arc<dyn PhysicalExpr>, name: String) -> Self {
+        Self { name, expr }
+    }
+}
+
+impl AggregateExpr for Median {
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, DataType::Float64, true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(vec![Field::new(
+            &format_state_name(&self.name, "values"),
+            DataType::List(Box::new(Field::new("item", DataType::Float64, true))),
+            true,
+        )])
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(MedianAccumulator { values: vec![] }))
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+}
+
+#[derive(Debug)]
+struct MedianAccumulator {
+    values: Vec<f64>,
+}
+
+impl Accumulator for MedianAccumulator {
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        let values = self.values.iter().map(|v| ScalarValue::from(*v)).collect();
+        Ok(vec![ScalarValue::List(Some(values), DataType::Float64)])
+    }
+
+    fn update(&mut self, values: &Vec<ScalarValue>) -> Result<()> {
+        if let Some(value) = scalar_to_option_f64(&values[0])? {
+            self.values.push(value);
+        }
+        Ok(())
+    }
+
+    fn update_batch(&mut self, values: &Vec<ArrayRef>) -> Result<()> {
+        let values = downcast_float64(&values[0])?;
+        self.values.extend(
+            (0..values.len())
+                .filter(|i| values.is_valid(*i))
+                .map(|i| values.value(i)),
+        );
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &Vec<ScalarValue>) -> Result<()> {
+        match &states[0] {
+            ScalarValue::List(Some(values), _) => {
+                for value in values {
+                    if let Some(value) = scalar_to_option_f64(value)? {
+                        self.values.push(value);
+                    }
+                }
+                Ok(())
+            }
+            ScalarValue::List(None, _) => Ok(()),
+            other => Err(DataFusionError::Internal(format!(
+                "Unexpected state {:?} of MEDIAN",
+                other
+            ))),
+        }
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        let mut values = self.values.clone();
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let len = values.len();
+        // the median of an even number of values is the mean of the two middle values
+        Ok(ScalarValue::Float64(match len {
+            0 => None,
+            _ if len % 2 == 0 => Some((values[len / 2 - 1] + values[len / 2]) / 2.0),
+            _ => Some(values[len / 2]),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::col;
+    use arrow::datatypes::Schema;
+    use arrow::record_batch::RecordBatch;
+
+    /// Aggregates `batch` in two partitions, merging the states of their accumulators
+    fn aggregate(
+        batch: &RecordBatch,
+        agg: Arc<dyn AggregateExpr>,
+    ) -> Result<ScalarValue> {
+        let mut accumulators = vec![agg.create_accumulator()?, agg.create_accumulator()?];
+        let half = batch.num_rows() / 2;
+        for (i, accumulator) in accumulators.iter_mut().enumerate() {
+            let values = agg
+                .expressions()
+                .iter()
+                .map(|e| e.evaluate(batch).map(|v| v.into_array(batch.num_rows())))
+                .map(|array| {
+                    array.map(|a| {
+                        if i == 0 {
+                            a.slice(0, half)
+                        } else {
+                            a.slice(half, a.len() - half)
+                        }
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            accumulator.update_batch(&values)?;
+        }
+
+        let mut merged = agg.create_accumulator()?;
+        for accumulator in &accumulators {
+            let states = accumulator
+                .state()?
+                .iter()
+                .map(|s| s.to_array())
+                .collect::<Vec<_>>();
+            merged.merge_batch(&states)?;
+        }
+        merged.evaluate()
+    }
+
+    fn batch(
+        values1: Vec<Option<f64>>,
+        values2: Vec<Option<f64>>,
+    ) -> Result<RecordBatch> {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Float64, true),
+            Field::new("b", DataType::Float64, true),
+        ]);
+        Ok(RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Float64Array::from(values1)),
+                Arc::new(Float64Array::from(values2)),
+            ],
+        )?)
+    }
+
+    fn assert_approx_eq(actual: ScalarValue, expected: f64) {
+        match actual {
+            ScalarValue::Float64(Some(actual)) => {
+                assert!(
+                    (actual - expected).abs() < 1e-9,
+                    "{} != {}",
+                    actual,
+                    expected
+                )
+            }
+            other => panic!("{:?} is not {}", other, expected),
+        }
+    }
+
+    #[test]
+    fn variance_and_stddev() -> Result<()> {
+        let batch = batch(
+            vec![
+                Some(2.0),
+                Some(4.0),
+                None,
+                Some(4.0),
+                Some(4.0),
+                Some(5.0),
+                Some(5.0),
+                Some(7.0),
+                Some(9.0),
+            ],
+            vec![None; 9],
+        )?;
+        let name = "v".to_string();
+
+        let agg = Arc::new(Variance::new(col("a"), name.clone(), StatsType::Population));
+        assert_approx_eq(aggregate(&batch, agg)?, 4.0);
+        let agg = Arc::new(Variance::new(col("a"), name.clone(), StatsType::Sample));
+        assert_approx_eq(aggregate(&batch, agg)?, 32.0 / 7.0);
+        let agg = Arc::new(Variance::new_stddev(
+            col("a"),
+            name.clone(),
+            StatsType::Population,
+        ));
+        assert_approx_eq(aggregate(&batch, agg)?, 2.0);
+
+        // the variance of a sample of a single value is undefined
+        let batch = self::batch(vec![Some(1.0), None], vec![None, None])?;
+        let agg = Arc::new(Variance::new(col("a"), name, StatsType::Sample));
+        assert_eq!(aggregate(&batch, agg)?, ScalarValue::Float64(None));
+        Ok(())
+    }
+
+    #[test]
+    fn covariance_and_correlation() -> Result<()> {
+        let batch = batch(
+            vec![Some(1.0), Some(2.0), Some(3.0), None, Some(4.0)],
+            vec![Some(2.0), Some(4.0), Some(6.0), Some(1.0), Some(8.0)],
+        )?;
+        let name = "c".to_string();
+
+        let agg = Arc::new(Covariance::new(
+            col("a"),
+            col("b"),
+            name.clone(),
+            StatsType::Sample,
+        ));
+        assert_approx_eq(aggregate(&batch, agg)?, 10.0 / 3.0);
+        let agg = Arc::new(Covariance::new(
+            col("a"),
+            col("b"),
+            name.clone(),
+            StatsType::Population,
+        ));
+        assert_approx_eq(aggregate(&batch, agg)?, 2.5);
+        let agg = Arc::new(Correlation::new(col("a"), col("b"), name));
+        assert_approx_eq(aggregate(&batch, agg)?, 1.0);
+        Ok(())
+    }
+
+    #[test]
+    fn median() -> Result<()> {
+        let batch = batch(
+            vec![Some(3.0), Some(1.0), None, Some(4.0), Some(2.0)],
+            vec![None; 5],
+        )?;
+        let agg = Arc::new(Median::new(col("a"), "m".to_string()));
+        assert_approx_eq(aggregate(&batch, agg)?, 2.5);
+
+        let batch = self::batch(vec![Some(3.0), Some(1.0), Some(2.0)], vec![None; 3])?;
+        let agg = Arc::new(Median::new(col("a"), "m".to_string()));
+        assert_approx_eq(aggregate(&batch, agg)?, 2.0);
+        Ok(())
+    }
+}
@@ -16,9 +16,24 @@
 // under the License.
 
 //! String expressions
+//!
+//! Unless noted otherwise, the functions of this module take a `Utf8` or `LargeUtf8` string
+//! as their first argument and return the same type, and follow the semantics of the
+//! PostgreSQL functions of the same name. A row whose arguments contain a null is null.
+
+use std::any::type_name;
+use std::collections::HashMap;
+use std::sync::Arc;
 
 use crate::error::{DataFusionError, Result};
-use arrow::array::{Array, ArrayRef, StringArray, StringBuilder};
+use arrow::array::{
+    Array, ArrayRef, BooleanArray, GenericStringArray, Int32Array, Int64Array,
+    ListBuilder, StringArray, StringBuilder, StringOffsetSizeTrait,
+};
+use arrow::datatypes::DataType;
+use md5::Md5;
+use regex::Regex;
+use sha2::{Digest, Sha256};
 
 macro_rules! downcast_vec {
     ($ARGS:expr, $ARRAY_TYPE:ident) => {{
@@ -66,3 +81,602 @@ pub fn concatenate(args: &[ArrayRef]) -> Result<StringArray> {
     }
     Ok(builder.finish())
 }
+
+/// Downcasts the argument `$ARG` named `$NAME` to `$ARRAY_TYPE`
+macro_rules! downcast_arg {
+    ($ARG:expr, $NAME:expr, $ARRAY_TYPE:ty) => {{
+        $ARG.as_any().downcast_ref::<$ARRAY_TYPE>().ok_or_else(|| {
+            DataFusionError::Internal(format!(
+                "could not cast {} to {}",
+                $NAME,
+                type_name::<$ARRAY_TYPE>()
+            ))
+        })?
+    }};
+}
+
+/// Applies `op` to each string of the first argument
+fn unary_string_function<T, R, F>(args: &[ArrayRef], op: F) -> Result<ArrayRef>
+where
+    T: StringOffsetSizeTrait,
+    R: AsRef<str>,
+    F: Fn(&str) -> R,
+{
+    let string_array = downcast_arg!(args[0], "string", GenericStringArray<T>);
+    let result = string_array
+        .iter()
+        .map(|string| string.map(|string| op(string)))
+        .collect::<GenericStringArray<T>>();
+    Ok(Arc::new(result))
+}
+
+/// Applies `op` to each string of the first argument and the string of the same row of
+/// the second argument
+fn binary_string_function<T, R, F>(
+    args: &[ArrayRef],
+    name: &str,
+    op: F,
+) -> Result<ArrayRef>
+where
+    T: StringOffsetSizeTrait,
+    R: AsRef<str>,
+    F: Fn(&str, &str) -> R,
+{
+    let string_array = downcast_arg!(args[0], "string", GenericStringArray<T>);
+    let other_array = downcast_arg!(args[1], name, StringArray);
+    let result = string_array
+        .iter()
+        .zip(other_array.iter())
+        .map(|(string, other)| match (string, other) {
+            (Some(string), Some(other)) => Some(op(string, other)),
+            _ => None,
+        })
+        .collect::<GenericStringArray<T>>();
+    Ok(Arc::new(result))
+}
+
+/// Applies `op` to each string of the first argument and the integer of the same row of
+/// the second argument
+fn string_integer_function<T, R, F>(
+    args: &[ArrayRef],
+    name: &str,
+    op: F,
+) -> Result<ArrayRef>
+where
+    T: StringOffsetSizeTrait,
+    R: AsRef<str>,
+    F: Fn(&str, i64) -> Result<R>,
+{
+    let string_array = downcast_arg!(args[0], "string", GenericStringArray<T>);
+    let integer_array = downcast_arg!(args[1], name, Int64Array);
+    let result = string_array
+        .iter()
+        .zip(integer_array.iter())
+        .map(|(string, integer)| match (string, integer) {
+            (Some(string), Some(integer)) => op(string, integer).map(Some),
+            _ => Ok(None),
+        })
+        .collect::<Result<GenericStringArray<T>>>()?;
+    Ok(Arc::new(result))
+}
+
+/// Converts the lengths or positions of strings to an array of the integer type of the
+/// length of `T`, `Int32` for `Utf8` and `Int64` for `LargeUtf8`
+fn position_array<T: StringOffsetSizeTrait>(values: Vec<Option<usize>>) -> ArrayRef {
+    if T::DATA_TYPE == DataType::LargeUtf8 {
+        Arc::new(
+            values
+                .into_iter()
+                .map(|value| value.map(|value| value as i64))
+                .collect::<Int64Array>(),
+        )
+    } else {
+        Arc::new(
+            values
+                .into_iter()
+                .map(|value| value.map(|value| value as i32))
+                .collect::<Int32Array>(),
+        )
+    }
+}
+
+/// Converts each string to lower case
+pub fn lower<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    unary_string_function::<T, _, _>(args, str::to_lowercase)
+}
+
+/// Converts each string to upper case
+pub fn upper<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    unary_string_function::<T, _, _>(args, str::to_uppercase)
+}
+
+/// Removes the characters of the optional second argument, spaces by default, from the
+/// start of each string when `left` and from its end when `right`
+fn trim_function<T: StringOffsetSizeTrait>(
+    args: &[ArrayRef],
+    left: bool,
+    right: bool,
+) -> Result<ArrayRef> {
+    let trim = |string: &'_ str, characters: &str| -> String {
+        let is_trimmed = |c: char| characters.contains(c);
+        let string = if left {
+            string.trim_start_matches(is_trimmed)
+        } else {
+            string
+        };
+        let string = if right {
+            string.trim_end_matches(is_trimmed)
+        } else {
+            string
+        };
+        string.to_owned()
+    };
+    match args.len() {
+        1 => unary_string_function::<T, _, _>(args, |string| trim(string, " ")),
+        2 => binary_string_function::<T, _, _>(args, "characters", trim),
+        other => Err(DataFusionError::Internal(format!(
+            "trim was called with {} arguments. It requires 1 or 2.",
+            other
+        ))),
+    }
+}
+
+/// Removes the longest string of spaces, or of the characters of the optional second
+/// argument, from the start and end of each string
+pub fn trim<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    trim_function::<T>(args, true, true)
+}
+
+/// Removes the longest string of spaces, or of the characters of the optional second
+/// argument, from the start of each string
+pub fn ltrim<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    trim_function::<T>(args, true, false)
+}
+
+/// Removes the longest string of spaces, or of the characters of the optional second
+/// argument, from the end of each string
+pub fn rtrim<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    trim_function::<T>(args, false, true)
+}
+
+/// Extends each string to the length of the second argument, in characters, by filling
+/// it with the characters of the optional third argument, spaces by default, or
+/// truncates it when it is longer
+fn pad_function<T: StringOffsetSizeTrait>(
+    args: &[ArrayRef],
+    left: bool,
+) -> Result<ArrayRef> {
+    let pad = |string: &str, length: i64, fill: &str| -> String {
+        let length = length.max(0) as usize;
+        let string_length = string.chars().count();
+        if length <= string_length {
+            return string.chars().take(length).collect();
+        }
+        if fill.is_empty() {
+            return string.to_owned();
+        }
+        let padding = fill
+            .chars()
+            .cycle()
+            .take(length - string_length)
+            .collect::<String>();
+        if left {
+            padding + string
+        } else {
+            string.to_owned() + &padding
+        }
+    };
+    match args.len() {
+        2 => string_integer_function::<T, _, _>(args, "length", |string, length| {
+            Ok(pad(string, length, " "))
+        }),
+        3 => {
+            let string_array = downcast_arg!(args[0], "string", GenericStringArray<T>);
+            let length_array = downcast_arg!(args[1], "length", Int64Array);
+            let fill_array = downcast_arg!(args[2], "fill", StringArray);
+            let result = string_array
+                .iter()
+                .zip(length_array.iter())
+                .zip(fill_array.iter())
+                .map(|((string, length), fill)| match (string, length, fill) {
+                    (Some(string), Some(length), Some(fill)) => {
+                        Some(pad(string, length, fill))
+                    }
+                    _ => None,
+                })
+                .collect::<GenericStringArray<T>>();
+            Ok(Arc::new(result))
+        }
+        other => Err(DataFusionError::Internal(format!(
+            "pad was called with {} arguments. It requires 2 or 3.",
+            other
+        ))),
+    }
+}
+
+/// Extends each string on the left to a length, see [pad_function]
+pub fn lpad<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    pad_function::<T>(args, true)
+}
+
+/// Extends each string on the right to a length, see [pad_function]
+pub fn rpad<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    pad_function::<T>(args, false)
+}
+
+/// Extracts the characters of each string from the position of the second argument,
+/// counted from 1, up to the optional number of characters of the third argument
+pub fn substr<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    let substr = |string: &str, start: i64, count: Option<i64>| -> Result<String> {
+        let end = match count {
+            Some(count) if count < 0 => {
+                return Err(DataFusionError::Execution(
+                    "negative substring length not allowed".to_string(),
+                ))
+            }
+            Some(count) => start.saturating_add(count),
+            None => i64::MAX,
+        };
+        // the positions before the first character are counted, but not extracted
+        let skip = (start.max(1) - 1) as usize;
+        let take = end.saturating_sub(start.max(1)).max(0) as usize;
+        Ok(string.chars().skip(skip).take(take).collect())
+    };
+    match args.len() {
+        2 => string_integer_function::<T, _, _>(args, "start", |string, start| {
+            substr(string, start, None)
+        }),
+        3 => {
+            let string_array = downcast_arg!(args[0], "string", GenericStringArray<T>);
+            let start_array = downcast_arg!(args[1], "start", Int64Array);
+            let count_array = downcast_arg!(args[2], "count", Int64Array);
+            let result = string_array
+                .iter()
+                .zip(start_array.iter())
+                .zip(count_array.iter())
+                .map(|((string, start), count)| match (string, start, count) {
+                    (Some(string), Some(start), Some(count)) => {
+                        substr(string, start, Some(count)).map(Some)
+                    }
+                    _ => Ok(None),
+                })
+                .collect::<Result<GenericStringArray<T>>>()?;
+            Ok(Arc::new(result))
+        }
+        other => Err(DataFusionError::Internal(format!(
+            "substr was called with {} arguments. It requires 2 or 3.",
+            other
+        ))),
+    }
+}
+
+/// Replaces all the occurrences of the second argument in each string with the third
+pub fn replace<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    let string_array = downcast_arg!(args[0], "string", GenericStringArray<T>);
+    let from_array = downcast_arg!(args[1], "from", StringArray);
+    let to_array = downcast_arg!(args[2], "to", StringArray);
+    let result = string_array
+        .iter()
+        .zip(from_array.iter())
+        .zip(to_array.iter())
+        .map(|((string, from), to)| match (string, from, to) {
+            // an empty string occurs nowhere
+            (Some(string), Some(""), Some(_)) => Some(string.to_owned()),
+            (Some(string), Some(from), Some(to)) => Some(string.replace(from, to)),
+            _ => None,
+        })
+        .collect::<GenericStringArray<T>>();
+    Ok(Arc::new(result))
+}
+
+/// Splits each string at the occurrences of the second argument, and returns the field at
+/// the position of the third argument, counted from 1, or an empty string when there are
+/// fewer fields
+pub fn split_part<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    let string_array = downcast_arg!(args[0], "string", GenericStringArray<T>);
+    let delimiter_array = downcast_arg!(args[1], "delimiter", StringArray);
+    let n_array = downcast_arg!(args[2], "n", Int64Array);
+    let result = string_array
+        .iter()
+        .zip(delimiter_array.iter())
+        .zip(n_array.iter())
+        .map(|((string, delimiter), n)| match (string, delimiter, n) {
+            (Some(_), Some(_), Some(n)) if n <= 0 => Err(DataFusionError::Execution(
+                "field position must be greater than zero".to_string(),
+            )),
+            // a string without a delimiter has a single field
+            (Some(string), Some(""), Some(1)) => Ok(Some(string)),
+            (Some(_), Some(""), Some(_)) => Ok(Some("")),
+            (Some(string), Some(delimiter), Some(n)) => Ok(Some(
+                string.split(delimiter).nth(n as usize - 1).unwrap_or(""),
+            )),
+            _ => Ok(None),
+        })
+        .collect::<Result<GenericStringArray<T>>>()?;
+    Ok(Arc::new(result))
+}
+
+/// Returns whether each string starts with the second argument, as a `Boolean`
+pub fn starts_with<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    let string_array = downcast_arg!(args[0], "string", GenericStringArray<T>);
+    let prefix_array = downcast_arg!(args[1], "prefix", StringArray);
+    let result = string_array
+        .iter()
+        .zip(prefix_array.iter())
+        .map(|(string, prefix)| match (string, prefix) {
+            (Some(string), Some(prefix)) => Some(string.starts_with(prefix)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    Ok(Arc::new(BooleanArray::from(result)))
+}
+
+/// Returns the position of the first occurrence of the second argument in each string,
+/// counted in characters from 1, or 0 when it does not occur. The position is an `Int32`
+/// for `Utf8` strings and an `Int64` for `LargeUtf8` strings.
+pub fn strpos<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    let string_array = downcast_arg!(args[0], "string", GenericStringArray<T>);
+    let substring_array = downcast_arg!(args[1], "substring", StringArray);
+    let result = string_array
+        .iter()
+        .zip(substring_array.iter())
+        .map(|(string, substring)| match (string, substring) {
+            (Some(string), Some(substring)) => Some(
+                string
+                    .find(substring)
+                    .map(|index| string[..index].chars().count() + 1)
+                    .unwrap_or(0),
+            ),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    Ok(position_array::<T>(result))
+}
+
+/// Reverses the order of the characters of each string
+pub fn reverse<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    unary_string_function::<T, _, _>(args, |string| {
+        string.chars().rev().collect::<String>()
+    })
+}
+
+/// Returns the first characters of each string, as many as the second argument, or all
+/// but as many of its last characters when the second argument is negative
+pub fn left<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    string_integer_function::<T, _, _>(args, "n", |string, n| {
+        let take = if n >= 0 {
+            n as usize
+        } else {
+            let length = string.chars().count();
+            length.saturating_sub(n.saturating_neg() as usize)
+        };
+        Ok(string.chars().take(take).collect::<String>())
+    })
+}
+
+/// Returns the last characters of each string, as many as the second argument, or all
+/// but as many of its first characters when the second argument is negative
+pub fn right<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    string_integer_function::<T, _, _>(args, "n", |string, n| {
+        let skip = if n >= 0 {
+            let length = string.chars().count();
+            length.saturating_sub(n as usize)
+        } else {
+            n.saturating_neg() as usize
+        };
+        Ok(string.chars().skip(skip).collect::<String>())
+    })
+}
+
+/// Converts the first letter of each word of each string to upper case and its other
+/// letters to lower case, where words are sequences of alphanumeric characters
+pub fn initcap<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    unary_string_function::<T, _, _>(args, |string| {
+        let mut result = String::with_capacity(string.len());
+        let mut in_word = false;
+        for c in string.chars() {
+            if in_word {
+                result.extend(c.to_lowercase());
+            } else {
+                result.extend(c.to_uppercase());
+            }
+            in_word = c.is_alphanumeric();
+        }
+        result
+    })
+}
+
+/// Repeats each string as many times as the second argument
+pub fn repeat<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    string_integer_function::<T, _, _>(args, "n", |string, n| {
+        Ok(string.repeat(n.max(0) as usize))
+    })
+}
+
+/// Returns the character of each `Int64` code point, as a `Utf8` string
+pub fn chr(args: &[ArrayRef]) -> Result<ArrayRef> {
+    let integer_array = downcast_arg!(args[0], "code", Int64Array);
+    let result = integer_array
+        .iter()
+        .map(|code| {
+            code.map(|code| match code {
+                0 => Err(DataFusionError::Execution(
+                    "null character not permitted".to_string(),
+                )),
+                code => std::char::from_u32(code as u32)
+                    .filter(|_| code > 0 && code <= u32::MAX as i64)
+                    .map(|c| c.to_string())
+                    .ok_or_else(|| {
+                        DataFusionError::Execution(format!(
+                            "requested character {} is not a valid code point",
+                            code
+                        ))
+                    }),
+            })
+            .transpose()
+        })
+        .collect::<Result<StringArray>>()?;
+    Ok(Arc::new(result))
+}
+
+/// Returns the code point of the first character of each string, or 0 for an empty
+/// string, as an `Int32`
+pub fn ascii<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    let string_array = downcast_arg!(args[0], "string", GenericStringArray<T>);
+    let result = string_array
+        .iter()
+        .map(|string| string.map(|string| string.chars().next().map_or(0, |c| c as i32)))
+        .collect::<Int32Array>();
+    Ok(Arc::new(result))
+}
+
+/// Computes the MD5 hash of each string, as a hexadecimal string
+pub fn md5<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    unary_string_function::<T, _, _>(args, |string| {
+        format!("{:x}", Md5::digest(string.as_bytes()))
+    })
+}
+
+/// Computes the SHA-256 hash of each string, as a hexadecimal string
+pub fn sha256<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    unary_string_function::<T, _, _>(args, |string| {
+        format!("{:x}", Sha256::digest(string.as_bytes()))
+    })
+}
+
+/// The regular expressions of a batch, compiled once per pattern and flags
+#[derive(Default)]
+struct Regexes {
+    regexes: HashMap<(String, String), Regex>,
+}
+
+impl Regexes {
+    /// Returns the regular expression of `pattern` with `flags`, and whether it replaces
+    /// all its matches, as with the flag `g`
+    fn get(&mut self, pattern: &str, flags: &str) -> Result<(&Regex, bool)> {
+        let global = flags.contains('g');
+        let key = (pattern.to_owned(), flags.to_owned());
+        if !self.regexes.contains_key(&key) {
+            let mut inline_flags = String::new();
+            for flag in flags.chars() {
+                match flag {
+                    'i' | 'm' | 's' | 'x' => inline_flags.push(flag),
+                    'g' => {}
+                    other => {
+                        return Err(DataFusionError::Execution(format!(
+                            "invalid regular expression option: {}",
+                            other
+                        )))
+                    }
+                }
+            }
+            let full_pattern = if inline_flags.is_empty() {
+                pattern.to_owned()
+            } else {
+                format!("(?{}){}", inline_flags, pattern)
+            };
+            let regex = Regex::new(&full_pattern).map_err(|e| {
+                DataFusionError::Execution(format!("invalid regular expression: {}", e))
+            })?;
+            self.regexes.insert(key.clone(), regex);
+        }
+        Ok((&self.regexes[&key], global))
+    }
+}
+
+/// Returns the flags of the optional argument at `index` of each row
+fn regex_flags(args: &[ArrayRef], index: usize) -> Result<Option<&StringArray>> {
+    Ok(match args.get(index) {
+        Some(flags) => Some(downcast_arg!(flags, "flags", StringArray)),
+        None => None,
+    })
+}
+
+/// Returns the substrings that the capture groups of the regular expression of the second
+/// argument match in the first match of each string, or the whole match when it has no
+/// capture groups, as a `List` of `Utf8`, or null when it does not match. The optional
+/// third argument has flags of the regular expression, such as `i`.
+pub fn regexp_match<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    let string_array = downcast_arg!(args[0], "string", GenericStringArray<T>);
+    let pattern_array = downcast_arg!(args[1], "pattern", StringArray);
+    let flags_array = regex_flags(args, 2)?;
+    if flags_array.map_or(false, |flags| {
+        flags.iter().flatten().any(|f| f.contains('g'))
+    }) {
+        return Err(DataFusionError::NotImplemented(
+            "regexp_match does not support the global flag g".to_string(),
+        ));
+    }
+
+    let mut regexes = Regexes::default();
+    let mut builder = ListBuilder::new(StringBuilder::new(string_array.len()));
+    for i in 0..string_array.len() {
+        let flags = match flags_array {
+            Some(flags) if flags.is_null(i) => None,
+            Some(flags) => Some(flags.value(i)),
+            None => Some(""),
+        };
+        if string_array.is_null(i) || pattern_array.is_null(i) || flags.is_none() {
+            builder.append(false)?;
+            continue;
+        }
+        let (regex, _) = regexes.get(pattern_array.value(i), flags.unwrap())?;
+        match regex.captures(string_array.value(i)) {
+            Some(captures) if captures.len() == 1 => {
+                builder.values().append_value(&captures[0])?;
+                builder.append(true)?;
+            }
+            Some(captures) => {
+                for group in captures.iter().skip(1) {
+                    match group {
+                        Some(group) => builder.values().append_value(group.as_str())?,
+                        None => builder.values().append_null()?,
+                    }
+                }
+                builder.append(true)?;
+            }
+            None => builder.append(false)?,
+        }
+    }
+    Ok(Arc::new(builder.finish()))
+}
+
+/// Replaces the first match of the regular expression of the second argument in each
+/// string, or all its matches with the flag `g`, with the third argument, in which `\n`
+/// refers to the `n`th capture group. The optional fourth argument has the flags of the
+/// regular expression, such as `g` and `i`.
+pub fn regexp_replace<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    let string_array = downcast_arg!(args[0], "string", GenericStringArray<T>);
+    let pattern_array = downcast_arg!(args[1], "pattern", StringArray);
+    let replacement_array = downcast_arg!(args[2], "replacement", StringArray);
+    let flags_array = regex_flags(args, 3)?;
+
+    // the capture groups of replacements are written as \1 rather than ${1}
+    let group_reference = Regex::new(r"\\(\d)").unwrap();
+    let mut regexes = Regexes::default();
+    let result = (0..string_array.len())
+        .map(|i| {
+            let flags = match flags_array {
+                Some(flags) if flags.is_null(i) => return Ok(None),
+                Some(flags) => flags.value(i),
+                None => "",
+            };
+            if string_array.is_null(i)
+                || pattern_array.is_null(i)
+                || replacement_array.is_null(i)
+            {
+                return Ok(None);
+            }
+            let (regex, global) = regexes.get(pattern_array.value(i), flags)?;
+            let replacement = group_reference
+                .replace_all(&replacement_array.value(i).replace('$', "$$"), "$${$1}");
+            let string = string_array.value(i);
+            Ok(Some(if global {
+                regex.replace_all(string, replacement.as_ref())
+            } else {
+                regex.replace(string, replacement.as_ref())
+            }))
+        })
+        .collect::<Result<GenericStringArray<T>>>()?;
+    Ok(Arc::new(result))
+}
@@ -0,0 +1,404 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the TOP-K plan, which returns the first k rows of its input in the order of
+//! sort expressions, such as for `ORDER BY x LIMIT k`
+
+use std::any::Any;
+use std::collections::BinaryHeap;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::channel::mpsc;
+use futures::sink::SinkExt;
+use futures::stream::{Stream, StreamExt};
+
+use pin_project_lite::pin_project;
+
+use arrow::array::UInt32Array;
+use arrow::datatypes::SchemaRef;
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+
+use async_trait::async_trait;
+
+use super::common::{
+    build_run_comparators, combine_batches, spawn_abortable, AbortOnDrop, RunCursor,
+};
+use super::expressions::PhysicalSortExpr;
+use super::sort::{sort_indices, take_rows};
+use super::{ExecutionPlan, Partitioning, RecordBatchStream, SendableRecordBatchStream};
+use crate::error::{DataFusionError, Result};
+
+/// Top-K execution plan.
+///
+/// Every input partition is read concurrently, keeping only the first k rows of the
+/// partition seen so far, sorted. Once the partitions are exhausted, their first k rows are
+/// merged with a heap of the next row of every partition. The output is a single sorted
+/// partition with at most k rows.
+#[derive(Debug)]
+pub struct TopKExec {
+    /// Input execution plan
+    input: Arc<dyn ExecutionPlan>,
+    /// Sort expressions
+    expr: Vec<PhysicalSortExpr>,
+    /// Maximum number of rows to return
+    k: usize,
+}
+
+impl TopKExec {
+    /// Create a new Top-K execution plan, which returns the first `k` rows of `input` in
+    /// the order of `expr`
+    pub fn new(
+        expr: Vec<PhysicalSortExpr>,
+        input: Arc<dyn ExecutionPlan>,
+        k: usize,
+    ) -> Self {
+        Self { input, expr, k }
+    }
+
+    /// Sort expressions
+    pub fn expr(&self) -> &[PhysicalSortExpr] {
+        &self.expr
+    }
+
+    /// Maximum number of rows to return
+    pub fn k(&self) -> usize {
+        self.k
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for TopKExec {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    /// Get the output partitioning of this plan
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn output_ordering(&self) -> Option<Vec<PhysicalSortExpr>> {
+        Some(self.expr.clone())
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(TopKExec::new(
+                self.expr.clone(),
+                children[0].clone(),
+                self.k,
+            ))),
+            _ => Err(DataFusionError::Internal(
+                "TopKExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        if 0 != partition {
+            return Err(DataFusionError::Internal(format!(
+                "TopKExec invalid partition {}",
+                partition
+            )));
+        }
+
+        let (mut sender, receiver) = mpsc::channel(1);
+        let input = self.input.clone();
+        let expr = self.expr.clone();
+        let k = self.k;
+        let (_, task) = spawn_abortable(async move {
+            let result = match top_k(input, expr, k).await {
+                Ok(Some(batch)) => Ok(batch),
+                Ok(None) => return,
+                Err(e) => Err(DataFusionError::into_arrow_external_error(e)),
+            };
+            // If send fails, plan being torn down, there is no place to send the result
+            sender.send(result).await.ok();
+        });
+
+        Ok(Box::pin(TopKStream {
+            output: receiver,
+            schema: self.schema(),
+            _task: task,
+        }))
+    }
+}
+
+/// Returns the first `k` rows of all the partitions of `input` in the order of `expr`, or
+/// `None` when there are none
+async fn top_k(
+    input: Arc<dyn ExecutionPlan>,
+    expr: Vec<PhysicalSortExpr>,
+    k: usize,
+) -> Result<Option<RecordBatch>> {
+    // the tasks of the partitions are aborted when this future is dropped
+    let partitions = input.output_partitioning().partition_count();
+    let (handles, _tasks): (Vec<_>, Vec<_>) = (0..partitions)
+        .map(|partition| {
+            let input = input.clone();
+            let expr = expr.clone();
+            spawn_abortable(async move {
+                partition_top_k(input.execute(partition).await?, &expr, k).await
+            })
+        })
+        .unzip();
+
+    let mut runs = vec![];
+    for handle in handles {
+        let run = handle
+            .await
+            .map_err(|e| DataFusionError::Execution(e.to_string()))?
+            .ok_or_else(|| {
+                DataFusionError::Execution("TopKExec partition was aborted".to_owned())
+            })??;
+        if let Some(run) = run {
+            runs.push(run);
+        }
+    }
+    merge_runs(&runs, &expr, k)
+}
+
+/// Returns the first `k` rows of `input` in the order of `expr`, or `None` when there are
+/// none. Every batch of the input is sorted and merged with the first `k` rows of the
+/// previous batches, so that at most `k` rows are kept besides the batch.
+async fn partition_top_k(
+    mut input: SendableRecordBatchStream,
+    expr: &[PhysicalSortExpr],
+    k: usize,
+) -> Result<Option<RecordBatch>> {
+    let mut first_rows: Option<RecordBatch> = None;
+    while let Some(batch) = input.next().await {
+        let batch = batch?;
+        if batch.num_rows() == 0 {
+            continue;
+        }
+        let sorted = take_rows(&batch, &sort_indices(&batch, expr)?)?;
+        let runs = match first_rows.take() {
+            Some(first_rows) => vec![first_rows, sorted],
+            None => vec![sorted],
+        };
+        first_rows = merge_runs(&runs, expr, k)?;
+    }
+    Ok(first_rows)
+}
+
+/// Merges `runs`, which are sorted by `expr`, and returns their first `k` rows, or `None`
+/// when there are none
+fn merge_runs(
+    runs: &[RecordBatch],
+    expr: &[PhysicalSortExpr],
+    k: usize,
+) -> Result<Option<RecordBatch>> {
+    if runs.is_empty() || k == 0 {
+        return Ok(None);
+    }
+
+    let keys = runs
+        .iter()
+        .map(|run| {
+            expr.iter()
+                .map(|e| Ok(e.evaluate_to_sort_column(run)?.values))
+                .collect::<Result<Vec<_>>>()
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let comparators = build_run_comparators(&keys)?;
+    let sort_options = expr.iter().map(|e| e.options).collect::<Vec<_>>();
+
+    let mut heap = BinaryHeap::new();
+    for (run, batch) in runs.iter().enumerate() {
+        if batch.num_rows() > 0 {
+            heap.push(RunCursor {
+                run,
+                row: 0,
+                keys: &keys,
+                comparators: &comparators,
+                sort_options: &sort_options,
+            });
+        }
+    }
+
+    // the index of the first row of every run in the combined runs
+    let mut offsets = Vec::with_capacity(runs.len());
+    let mut offset = 0;
+    for run in runs {
+        offsets.push(offset);
+        offset += run.num_rows();
+    }
+
+    let mut indices = vec![];
+    while indices.len() < k {
+        match heap.pop() {
+            Some(mut cursor) => {
+                indices.push((offsets[cursor.run] + cursor.row) as u32);
+                cursor.row += 1;
+                if cursor.row < runs[cursor.run].num_rows() {
+                    heap.push(cursor);
+                }
+            }
+            None => break,
+        }
+    }
+    if indices.is_empty() {
+        return Ok(None);
+    }
+
+    let combined = combine_batches(runs, &runs[0].schema())?;
+    Ok(Some(take_rows(&combined, &UInt32Array::from(indices))?))
+}
+
+pin_project! {
+    struct TopKStream {
+        #[pin]
+        output: mpsc::Receiver<ArrowResult<RecordBatch>>,
+        schema: SchemaRef,
+        // aborts the top-k once the stream is dropped
+        _task: AbortOnDrop,
+    }
+}
+
+impl Stream for TopKStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.project().output.poll_next(cx)
+    }
+}
+
+impl RecordBatchStream for TopKStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::common;
+    use crate::physical_plan::expressions::col;
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::test::format_batch;
+    use arrow::array::Int32Array;
+    use arrow::compute::SortOptions;
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    /// Returns a plan with a partition of a batch per vector of `partitions`, with the
+    /// values of the vector in column `a` and their position in column `b`
+    fn build_partitions(
+        partitions: Vec<Vec<Option<i32>>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Int32, false),
+        ]));
+        let partitions = partitions
+            .into_iter()
+            .map(|values| {
+                let positions = (0..values.len() as i32).collect::<Vec<_>>();
+                Ok(vec![RecordBatch::try_new(
+                    schema.clone(),
+                    vec![
+                        Arc::new(Int32Array::from(values)),
+                        Arc::new(Int32Array::from(positions)),
+                    ],
+                )?])
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Arc::new(MemoryExec::try_new(&partitions, schema, None)?))
+    }
+
+    async fn execute(exec: TopKExec) -> Result<Vec<String>> {
+        let batches = common::collect(exec.execute(0).await?).await?;
+        Ok(batches.iter().flat_map(format_batch).collect())
+    }
+
+    #[tokio::test]
+    async fn top_k() -> Result<()> {
+        let input = build_partitions(vec![
+            vec![Some(5), Some(1), Some(9)],
+            vec![Some(3), None, Some(7), Some(2)],
+        ])?;
+        let expr = vec![PhysicalSortExpr {
+            expr: col("a"),
+            options: SortOptions::default(),
+        }];
+
+        // nulls come first
+        let top_k = TopKExec::new(expr, input, 3);
+        assert_eq!(execute(top_k).await?, vec!["NULL,1", "1,1", "2,3"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn top_k_descending() -> Result<()> {
+        let input = build_partitions(vec![
+            vec![Some(5), Some(1), Some(9)],
+            vec![Some(3), None, Some(7), Some(9)],
+        ])?;
+        let expr = vec![
+            PhysicalSortExpr {
+                expr: col("a"),
+                options: SortOptions {
+                    descending: true,
+                    nulls_first: false,
+                },
+            },
+            PhysicalSortExpr {
+                expr: col("b"),
+                options: SortOptions::default(),
+            },
+        ];
+
+        let top_k = TopKExec::new(expr, input, 4);
+        assert_eq!(execute(top_k).await?, vec!["9,2", "9,3", "7,2", "5,0"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn top_k_more_than_input() -> Result<()> {
+        let input = build_partitions(vec![vec![Some(2), Some(1)], vec![]])?;
+        let expr = vec![PhysicalSortExpr {
+            expr: col("a"),
+            options: SortOptions::default(),
+        }];
+
+        let top_k = TopKExec::new(expr.clone(), input.clone(), 10);
+        assert_eq!(execute(top_k).await?, vec!["1,1", "2,0"]);
+
+        // no rows are returned for k = 0
+        let top_k = TopKExec::new(expr, input, 0);
+        assert!(execute(top_k).await?.is_empty());
+
+        Ok(())
+    }
+}
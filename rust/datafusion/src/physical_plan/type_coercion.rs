@@ -68,6 +68,31 @@ pub fn data_types(
     current_types: &Vec<DataType>,
     signature: &Signature,
 ) -> Result<Vec<DataType>> {
+    let valid_types = get_valid_types(signature, current_types)?;
+
+    if valid_types.contains(current_types) {
+        return Ok(current_types.clone());
+    }
+
+    for valid_types in valid_types {
+        if let Some(types) = maybe_data_types(&valid_types, &current_types) {
+            return Ok(types);
+        }
+    }
+
+    // none possible -> Error
+    Err(DataFusionError::Plan(format!(
+        "Coercion from {:?} to the signature {:?} failed.",
+        current_types, signature
+    )))
+}
+
+/// Returns the lists of types of the arguments that `signature` accepts, for arguments
+/// of `current_types`
+fn get_valid_types(
+    signature: &Signature,
+    current_types: &Vec<DataType>,
+) -> Result<Vec<Vec<DataType>>> {
     let valid_types = match signature {
         Signature::Variadic(valid_types) => valid_types
             .iter()
@@ -95,23 +120,15 @@ pub fn data_types(
             }
             vec![(0..*number).map(|i| current_types[i].clone()).collect()]
         }
+        Signature::OneOf(signatures) => signatures
+            .iter()
+            // a signature that cannot accept the arguments, such as one of another number
+            // of arguments, does not contribute valid types
+            .filter_map(|signature| get_valid_types(signature, current_types).ok())
+            .flatten()
+            .collect(),
     };
-
-    if valid_types.contains(current_types) {
-        return Ok(current_types.clone());
-    }
-
-    for valid_types in valid_types {
-        if let Some(types) = maybe_data_types(&valid_types, &current_types) {
-            return Ok(types);
-        }
-    }
-
-    // none possible -> Error
-    Err(DataFusionError::Plan(format!(
-        "Coercion from {:?} to the signature {:?} failed.",
-        current_types, signature
-    )))
+    Ok(valid_types)
 }
 
 /// Try to coerce current_types into valid_types.
@@ -198,6 +215,27 @@ pub fn can_coerce_from(type_into: &DataType, type_from: &DataType) -> bool {
     }
 }
 
+/// Returns the type that values of both `lhs_type` and `rhs_type` can be coerced to, if any.
+/// This is used to find the type of a column shared by several relations, such as the
+/// inputs of a UNION.
+pub fn common_type(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType> {
+    if lhs_type == rhs_type || can_coerce_from(lhs_type, rhs_type) {
+        return Some(lhs_type.clone());
+    }
+    if can_coerce_from(rhs_type, lhs_type) {
+        return Some(rhs_type.clone());
+    }
+    // e.g. a signed and an unsigned integer can both be widened to a larger signed integer
+    vec![
+        DataType::Int16,
+        DataType::Int32,
+        DataType::Int64,
+        DataType::Float64,
+    ]
+    .into_iter()
+    .find(|t| can_coerce_from(t, lhs_type) && can_coerce_from(t, rhs_type))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -242,6 +280,29 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_common_type() {
+        // this vec contains: lhs, rhs, expected result
+        let cases = vec![
+            (
+                DataType::Boolean,
+                DataType::Boolean,
+                Some(DataType::Boolean),
+            ),
+            (DataType::Int64, DataType::Int32, Some(DataType::Int64)),
+            (DataType::UInt8, DataType::Float64, Some(DataType::Float64)),
+            (DataType::Int8, DataType::UInt8, Some(DataType::Int16)),
+            (DataType::UInt32, DataType::Int32, Some(DataType::Int64)),
+            (DataType::UInt64, DataType::Int8, Some(DataType::Float64)),
+            (DataType::Utf8, DataType::Int32, Some(DataType::Utf8)),
+            (DataType::Boolean, DataType::Int32, None),
+        ];
+
+        for case in cases {
+            assert_eq!(common_type(&case.0, &case.1), case.2)
+        }
+    }
+
     #[test]
     fn test_coerce() -> Result<()> {
         // create a schema
@@ -313,6 +374,15 @@ mod tests {
                 Signature::Any(1),
                 vec![DataType::Float32],
             )?,
+            // the signature of 2 arguments is chosen, with u16 -> i64
+            case(
+                vec![DataType::Utf8, DataType::UInt16],
+                Signature::OneOf(vec![
+                    Signature::Any(1),
+                    Signature::Exact(vec![DataType::Utf8, DataType::Int64]),
+                ]),
+                vec![DataType::Utf8, DataType::Int64],
+            )?,
         ];
 
         for case in cases {
@@ -0,0 +1,143 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the union plan for combining the partitions of several inputs with the same
+//! schema into a single set of partitions
+
+use std::any::Any;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use arrow::datatypes::SchemaRef;
+
+use super::SendableRecordBatchStream;
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::{ExecutionPlan, Partitioning};
+
+/// Union execution plan concatenates the partitions of its inputs: its output partitions
+/// are the partitions of the first input, followed by the partitions of the second input,
+/// and so on. No data is moved between partitions.
+#[derive(Debug)]
+pub struct UnionExec {
+    /// Input execution plans
+    inputs: Vec<Arc<dyn ExecutionPlan>>,
+}
+
+impl UnionExec {
+    /// Create a new UnionExec
+    pub fn new(inputs: Vec<Arc<dyn ExecutionPlan>>) -> Self {
+        UnionExec { inputs }
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for UnionExec {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.inputs[0].schema()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        self.inputs.clone()
+    }
+
+    /// Get the output partitioning of this plan
+    fn output_partitioning(&self) -> Partitioning {
+        let partition_count = self
+            .inputs
+            .iter()
+            .map(|input| input.output_partitioning().partition_count())
+            .sum();
+        Partitioning::UnknownPartitioning(partition_count)
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            0 => Err(DataFusionError::Internal(
+                "UnionExec requires at least one child".to_string(),
+            )),
+            _ => Ok(Arc::new(UnionExec::new(children))),
+        }
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        // find the input that owns this partition
+        let mut input_partition = partition;
+        for input in self.inputs.iter() {
+            let partition_count = input.output_partitioning().partition_count();
+            if input_partition < partition_count {
+                return input.execute(input_partition).await;
+            }
+            input_partition -= partition_count;
+        }
+
+        Err(DataFusionError::Internal(format!(
+            "UnionExec invalid partition {}",
+            partition
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::physical_plan::common;
+    use crate::physical_plan::csv::{CsvExec, CsvReadOptions};
+    use crate::test;
+
+    #[tokio::test]
+    async fn union() -> Result<()> {
+        let schema = test::aggr_test_schema();
+
+        let path = test::create_partitioned_csv("aggregate_test_100.csv", 4)?;
+        let csv =
+            CsvExec::try_new(&path, CsvReadOptions::new().schema(&schema), None, 1024)?;
+
+        let path = test::create_partitioned_csv("aggregate_test_100.csv", 5)?;
+        let csv2 =
+            CsvExec::try_new(&path, CsvReadOptions::new().schema(&schema), None, 1024)?;
+
+        let union = UnionExec::new(vec![Arc::new(csv), Arc::new(csv2)]);
+
+        // output of UnionExec should have the partitions of both inputs
+        assert_eq!(union.output_partitioning().partition_count(), 9);
+
+        let mut row_count = 0;
+        for partition in 0..9 {
+            let stream = union.execute(partition).await?;
+            let batches = common::collect(stream).await?;
+            row_count += batches.iter().map(|batch| batch.num_rows()).sum::<usize>();
+        }
+
+        // there should be a total of 200 rows
+        assert_eq!(row_count, 200);
+
+        // partitions beyond the last input partition are invalid
+        assert!(union.execute(9).await.is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Declaration of window functions.
+//! A window function is either a built-in window function, such as `ROW_NUMBER`, or an
+//! aggregate function evaluated over the frame of every row, such as `SUM`.
+
+use super::aggregates::{self, AggregateFunction};
+use crate::error::{DataFusionError, Result};
+use arrow::datatypes::DataType;
+use std::{fmt, str::FromStr};
+
+/// Enum of all window functions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WindowFunction {
+    /// an aggregate function, evaluated over the frame of every row
+    AggregateFunction(AggregateFunction),
+    /// a built-in window function
+    BuiltInWindowFunction(BuiltInWindowFunction),
+}
+
+impl fmt::Display for WindowFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WindowFunction::AggregateFunction(fun) => write!(f, "{}", fun),
+            WindowFunction::BuiltInWindowFunction(fun) => write!(f, "{}", fun),
+        }
+    }
+}
+
+impl FromStr for WindowFunction {
+    type Err = DataFusionError;
+    fn from_str(name: &str) -> Result<WindowFunction> {
+        if let Ok(fun) = BuiltInWindowFunction::from_str(name) {
+            Ok(WindowFunction::BuiltInWindowFunction(fun))
+        } else if let Ok(fun) = AggregateFunction::from_str(name) {
+            Ok(WindowFunction::AggregateFunction(fun))
+        } else {
+            Err(DataFusionError::Plan(format!(
+                "There is no window function named {}",
+                name
+            )))
+        }
+    }
+}
+
+/// Enum of all built-in window functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltInWindowFunction {
+    /// the number of the row in its partition, starting at 1
+    RowNumber,
+    /// the rank of the row in its partition, with gaps after peers
+    Rank,
+    /// the rank of the row in its partition, without gaps
+    DenseRank,
+    /// the value of the row that precedes the current row by an offset
+    Lag,
+    /// the value of the row that follows the current row by an offset
+    Lead,
+    /// the value of the first row of the frame
+    FirstValue,
+    /// the value of the last row of the frame
+    LastValue,
+}
+
+impl fmt::Display for BuiltInWindowFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            BuiltInWindowFunction::RowNumber => "ROW_NUMBER",
+            BuiltInWindowFunction::Rank => "RANK",
+            BuiltInWindowFunction::DenseRank => "DENSE_RANK",
+            BuiltInWindowFunction::Lag => "LAG",
+            BuiltInWindowFunction::Lead => "LEAD",
+            BuiltInWindowFunction::FirstValue => "FIRST_VALUE",
+            BuiltInWindowFunction::LastValue => "LAST_VALUE",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for BuiltInWindowFunction {
+    type Err = DataFusionError;
+    fn from_str(name: &str) -> Result<BuiltInWindowFunction> {
+        Ok(match &*name.to_uppercase() {
+            "ROW_NUMBER" => BuiltInWindowFunction::RowNumber,
+            "RANK" => BuiltInWindowFunction::Rank,
+            "DENSE_RANK" => BuiltInWindowFunction::DenseRank,
+            "LAG" => BuiltInWindowFunction::Lag,
+            "LEAD" => BuiltInWindowFunction::Lead,
+            "FIRST_VALUE" => BuiltInWindowFunction::FirstValue,
+            "LAST_VALUE" => BuiltInWindowFunction::LastValue,
+            _ => {
+                return Err(DataFusionError::Plan(format!(
+                    "There is no built-in window function named {}",
+                    name
+                )))
+            }
+        })
+    }
+}
+
+impl BuiltInWindowFunction {
+    /// Whether the function is evaluated over the frame of every row. The other functions
+    /// are evaluated over the whole partition of the row.
+    pub fn uses_window_frame(&self) -> bool {
+        match self {
+            BuiltInWindowFunction::FirstValue | BuiltInWindowFunction::LastValue => true,
+            _ => false,
+        }
+    }
+}
+
+/// Returns the datatype of the window function
+pub fn return_type(fun: &WindowFunction, arg_types: &[DataType]) -> Result<DataType> {
+    match fun {
+        WindowFunction::AggregateFunction(fun) => {
+            aggregates::return_type(fun, &arg_types.to_vec())
+        }
+        WindowFunction::BuiltInWindowFunction(fun) => {
+            // the number of arguments that the function accepts
+            let (min, max) = match fun {
+                BuiltInWindowFunction::RowNumber
+                | BuiltInWindowFunction::Rank
+                | BuiltInWindowFunction::DenseRank => (0, 0),
+                // LAG(value [, offset [, default]])
+                BuiltInWindowFunction::Lag | BuiltInWindowFunction::Lead => (1, 3),
+                BuiltInWindowFunction::FirstValue | BuiltInWindowFunction::LastValue => {
+                    (1, 1)
+                }
+            };
+            if arg_types.len() < min || arg_types.len() > max {
+                let expected = if min == max {
+                    format!("{}", min)
+                } else {
+                    format!("between {} and {}", min, max)
+                };
+                return Err(DataFusionError::Plan(format!(
+                    "The function {} expects {} arguments, but received {}",
+                    fun,
+                    expected,
+                    arg_types.len()
+                )));
+            }
+
+            match fun {
+                BuiltInWindowFunction::RowNumber
+                | BuiltInWindowFunction::Rank
+                | BuiltInWindowFunction::DenseRank => Ok(DataType::UInt64),
+                BuiltInWindowFunction::Lag
+                | BuiltInWindowFunction::Lead
+                | BuiltInWindowFunction::FirstValue
+                | BuiltInWindowFunction::LastValue => Ok(arg_types[0].clone()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_function_from_str() -> Result<()> {
+        assert_eq!(
+            WindowFunction::from_str("row_number")?,
+            WindowFunction::BuiltInWindowFunction(BuiltInWindowFunction::RowNumber)
+        );
+        assert_eq!(
+            WindowFunction::from_str("sum")?,
+            WindowFunction::AggregateFunction(AggregateFunction::Sum)
+        );
+        assert!(WindowFunction::from_str("abs").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn window_function_return_type() -> Result<()> {
+        let fun = WindowFunction::from_str("rank")?;
+        assert_eq!(return_type(&fun, &[])?, DataType::UInt64);
+        assert!(return_type(&fun, &[DataType::Int32]).is_err());
+
+        let fun = WindowFunction::from_str("lag")?;
+        assert_eq!(
+            return_type(&fun, &[DataType::Utf8, DataType::Int64])?,
+            DataType::Utf8
+        );
+        assert!(return_type(&fun, &[]).is_err());
+
+        let fun = WindowFunction::from_str("count")?;
+        assert_eq!(return_type(&fun, &[DataType::Utf8])?, DataType::UInt64);
+        Ok(())
+    }
+}
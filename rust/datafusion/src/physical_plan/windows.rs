@@ -0,0 +1,1065 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the window aggregation plan, which evaluates window functions such as
+//! `ROW_NUMBER() OVER (PARTITION BY c1 ORDER BY c2)` and appends their results to the
+//! rows of its input.
+
+use std::any::Any;
+use std::cmp::Ordering;
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::stream::Stream;
+use futures::Future;
+
+use pin_project_lite::pin_project;
+
+use arrow::array::{build_compare, Array, ArrayRef, DynComparator};
+use arrow::array::{Float64Array, UInt32Array, UInt64Array};
+use arrow::compute::{cast, concat, lexsort_to_indices, take, SortColumn};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::error::{ArrowError, Result as ArrowResult};
+use arrow::record_batch::RecordBatch;
+
+use super::{RecordBatchStream, SendableRecordBatchStream};
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::{WindowFrame, WindowFrameBound, WindowFrameUnits};
+use crate::physical_plan::expressions::{is_numeric, PhysicalSortExpr};
+use crate::physical_plan::window_functions::{
+    self, BuiltInWindowFunction, WindowFunction,
+};
+use crate::physical_plan::{
+    aggregates, common, AggregateExpr, ColumnarValue, Distribution, ExecutionPlan,
+    Partitioning, PhysicalExpr, WindowExpr,
+};
+use crate::scalar::ScalarValue;
+
+use async_trait::async_trait;
+
+/// Window aggregation execution plan
+#[derive(Debug)]
+pub struct WindowAggExec {
+    /// Input plan
+    input: Arc<dyn ExecutionPlan>,
+    /// Window expressions
+    window_expr: Vec<Arc<dyn WindowExpr>>,
+    /// Schema after the window functions are applied
+    schema: SchemaRef,
+}
+
+impl WindowAggExec {
+    /// Create a new window aggregation execution plan
+    pub fn try_new(
+        window_expr: Vec<Arc<dyn WindowExpr>>,
+        input: Arc<dyn ExecutionPlan>,
+    ) -> Result<Self> {
+        let mut fields = input.schema().fields().clone();
+        for expr in &window_expr {
+            fields.push(expr.field()?);
+        }
+
+        Ok(Self {
+            input,
+            window_expr,
+            schema: Arc::new(Schema::new(fields)),
+        })
+    }
+
+    /// Window expressions
+    pub fn window_expr(&self) -> &[Arc<dyn WindowExpr>] {
+        &self.window_expr
+    }
+
+    /// Input plan
+    pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.input
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for WindowAggExec {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    /// Get the output partitioning of this plan
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn required_child_distribution(&self) -> Distribution {
+        Distribution::SinglePartition
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(WindowAggExec::try_new(
+                self.window_expr.clone(),
+                children[0].clone(),
+            )?)),
+            _ => Err(DataFusionError::Internal(
+                "WindowAggExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        if 0 != partition {
+            return Err(DataFusionError::Internal(format!(
+                "WindowAggExec invalid partition {}",
+                partition
+            )));
+        }
+
+        // window functions need every row of a partition, so they operate on a single
+        // input partition
+        if 1 != self.input.output_partitioning().partition_count() {
+            return Err(DataFusionError::Internal(
+                "WindowAggExec requires a single input partition".to_owned(),
+            ));
+        }
+        let input = self.input.execute(0).await?;
+
+        Ok(Box::pin(WindowAggStream::new(
+            input,
+            self.window_expr.clone(),
+            self.schema.clone(),
+        )))
+    }
+}
+
+/// Creates a physical window expression of the window function `fun` with the arguments
+/// `args`, evaluated over the partitions of `partition_by` sorted by `order_by`.
+pub fn create_window_expr(
+    fun: &WindowFunction,
+    args: &[Arc<dyn PhysicalExpr>],
+    partition_by: &[Arc<dyn PhysicalExpr>],
+    order_by: &[PhysicalSortExpr],
+    window_frame: Option<WindowFrame>,
+    input_schema: &Schema,
+    name: String,
+) -> Result<Arc<dyn WindowExpr>> {
+    let window = WindowSpec::try_new(
+        partition_by.to_vec(),
+        order_by.to_vec(),
+        window_frame,
+        input_schema,
+    )?;
+    Ok(match fun {
+        WindowFunction::AggregateFunction(fun) => Arc::new(AggregateWindowExpr {
+            aggregate: aggregates::create_aggregate_expr(
+                fun,
+                false,
+                &args.to_vec(),
+                input_schema,
+                name.clone(),
+            )?,
+            window,
+            name,
+        }),
+        WindowFunction::BuiltInWindowFunction(fun) => {
+            let arg_types = args
+                .iter()
+                .map(|e| e.data_type(input_schema))
+                .collect::<Result<Vec<_>>>()?;
+            let data_type = window_functions::return_type(
+                &WindowFunction::BuiltInWindowFunction(*fun),
+                &arg_types,
+            )?;
+            Arc::new(BuiltInWindowExpr {
+                fun: *fun,
+                args: args.to_vec(),
+                data_type,
+                window,
+                name,
+            })
+        }
+    })
+}
+
+/// A window expression that evaluates an aggregate function over the frame of every row,
+/// such as `SUM(c1) OVER (ORDER BY c2)`.
+#[derive(Debug)]
+pub struct AggregateWindowExpr {
+    aggregate: Arc<dyn AggregateExpr>,
+    window: WindowSpec,
+    name: String,
+}
+
+impl WindowExpr for AggregateWindowExpr {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn field(&self) -> Result<Field> {
+        self.aggregate.field()
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ArrayRef> {
+        let sorted = self.window.sort(batch)?;
+        let args = self
+            .aggregate
+            .expressions()
+            .iter()
+            .map(|e| sorted.sort_argument(e, batch))
+            .collect::<Result<Vec<_>>>()?;
+
+        let window_frame = &self.window.window_frame;
+        let mut values = Vec::with_capacity(batch.num_rows());
+        for partition in &sorted.partitions {
+            // the frames of consecutive rows overlap, and neither their start nor their
+            // end moves back, so a single accumulator is updated with the rows that enter
+            // the frame and retracts the rows that leave it. The accumulators that cannot
+            // retract rows are only reused while the start of the frame does not move.
+            let mut accumulator = self.aggregate.create_accumulator()?;
+            let mut accumulated = partition.start..partition.start;
+            for row in partition.clone() {
+                let frame = sorted.frame(window_frame, partition, row);
+                let slides = frame.start >= accumulated.start
+                    && frame.end >= accumulated.end
+                    && (frame.start == accumulated.start
+                        || accumulator.supports_retract());
+                if !slides {
+                    accumulator = self.aggregate.create_accumulator()?;
+                    accumulated = frame.start..frame.start;
+                }
+                if frame.end > accumulated.end {
+                    accumulator
+                        .update_batch(&slice_arrays(&args, accumulated.end..frame.end))?;
+                }
+                if frame.start > accumulated.start {
+                    accumulator.retract_batch(&slice_arrays(
+                        &args,
+                        accumulated.start..frame.start,
+                    ))?;
+                }
+                accumulated = frame;
+                values.push(accumulator.evaluate()?);
+            }
+        }
+
+        let values = ScalarValue::iter_to_array(self.field()?.data_type(), &values)?;
+        sorted.unsort(&values)
+    }
+}
+
+/// A window expression that evaluates a built-in window function, such as `ROW_NUMBER()`.
+#[derive(Debug)]
+pub struct BuiltInWindowExpr {
+    fun: BuiltInWindowFunction,
+    args: Vec<Arc<dyn PhysicalExpr>>,
+    data_type: DataType,
+    window: WindowSpec,
+    name: String,
+}
+
+impl WindowExpr for BuiltInWindowExpr {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn field(&self) -> Result<Field> {
+        let nullable = match self.fun {
+            BuiltInWindowFunction::RowNumber
+            | BuiltInWindowFunction::Rank
+            | BuiltInWindowFunction::DenseRank => false,
+            _ => true,
+        };
+        Ok(Field::new(&self.name, self.data_type.clone(), nullable))
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ArrayRef> {
+        let sorted = self.window.sort(batch)?;
+        let values = match self.fun {
+            BuiltInWindowFunction::RowNumber
+            | BuiltInWindowFunction::Rank
+            | BuiltInWindowFunction::DenseRank => {
+                Arc::new(UInt64Array::from(self.rank(&sorted))) as ArrayRef
+            }
+            BuiltInWindowFunction::Lag | BuiltInWindowFunction::Lead => {
+                self.shift(&sorted, batch)?
+            }
+            BuiltInWindowFunction::FirstValue | BuiltInWindowFunction::LastValue => {
+                let value = sorted.sort_argument(&self.args[0], batch)?;
+                let window_frame = &self.window.window_frame;
+                let mut indices = Vec::with_capacity(batch.num_rows());
+                for partition in &sorted.partitions {
+                    for row in partition.clone() {
+                        let frame = sorted.frame(window_frame, partition, row);
+                        indices.push(match (frame.is_empty(), self.fun) {
+                            (true, _) => None,
+                            (false, BuiltInWindowFunction::FirstValue) => {
+                                Some(frame.start as u32)
+                            }
+                            (false, _) => Some(frame.end as u32 - 1),
+                        });
+                    }
+                }
+                take(&value, &UInt32Array::from(indices), None)?
+            }
+        };
+        sorted.unsort(&values)
+    }
+}
+
+impl BuiltInWindowExpr {
+    /// The row numbers or ranks of the sorted rows
+    fn rank(&self, sorted: &SortedWindow) -> Vec<u64> {
+        let mut ranks = Vec::with_capacity(sorted.peers.len());
+        for partition in &sorted.partitions {
+            let mut dense_rank = 0;
+            for row in partition.clone() {
+                let peers = &sorted.peers[row];
+                if peers.start == row {
+                    dense_rank += 1;
+                }
+                ranks.push(match self.fun {
+                    BuiltInWindowFunction::RowNumber => {
+                        (row - partition.start + 1) as u64
+                    }
+                    BuiltInWindowFunction::Rank => {
+                        (peers.start - partition.start + 1) as u64
+                    }
+                    _ => dense_rank,
+                });
+            }
+        }
+        ranks
+    }
+
+    /// The values of the rows that precede (`LAG`) or follow (`LEAD`) the sorted rows by
+    /// an offset within their partition, or the default value when there is no such row
+    fn shift(&self, sorted: &SortedWindow, batch: &RecordBatch) -> Result<ArrayRef> {
+        let num_rows = batch.num_rows();
+        let offset = match self.args.get(1) {
+            Some(offset) => shift_offset(offset, batch)?,
+            None => 1,
+        };
+        let offset = match self.fun {
+            BuiltInWindowFunction::Lag => -offset,
+            _ => offset,
+        };
+
+        // the default values follow the values, so that a row without a shifted row
+        // takes its own default value
+        let value = sorted.sort_argument(&self.args[0], batch)?;
+        let (values, has_default) = match self.args.get(2) {
+            Some(default) => {
+                let default =
+                    cast(&sorted.sort_argument(default, batch)?, &self.data_type)?;
+                (concat(&[value, default])?, true)
+            }
+            None => (value, false),
+        };
+
+        let mut indices = Vec::with_capacity(num_rows);
+        for partition in &sorted.partitions {
+            for row in partition.clone() {
+                let shifted = row as i64 + offset;
+                indices.push(
+                    if shifted >= partition.start as i64 && shifted < partition.end as i64
+                    {
+                        Some(shifted as u32)
+                    } else if has_default {
+                        Some((num_rows + row) as u32)
+                    } else {
+                        None
+                    },
+                );
+            }
+        }
+        Ok(take(&values, &UInt32Array::from(indices), None)?)
+    }
+}
+
+/// The offset of `LAG` and `LEAD`, which must be a constant integer
+fn shift_offset(expr: &Arc<dyn PhysicalExpr>, batch: &RecordBatch) -> Result<i64> {
+    match expr.evaluate(batch)? {
+        ColumnarValue::Scalar(ScalarValue::Int8(Some(v))) => Ok(v as i64),
+        ColumnarValue::Scalar(ScalarValue::Int16(Some(v))) => Ok(v as i64),
+        ColumnarValue::Scalar(ScalarValue::Int32(Some(v))) => Ok(v as i64),
+        ColumnarValue::Scalar(ScalarValue::Int64(Some(v))) => Ok(v),
+        ColumnarValue::Scalar(ScalarValue::UInt8(Some(v))) => Ok(v as i64),
+        ColumnarValue::Scalar(ScalarValue::UInt16(Some(v))) => Ok(v as i64),
+        ColumnarValue::Scalar(ScalarValue::UInt32(Some(v))) => Ok(v as i64),
+        ColumnarValue::Scalar(ScalarValue::UInt64(Some(v))) => Ok(v as i64),
+        _ => Err(DataFusionError::Plan(format!(
+            "The offset of LAG and LEAD must be a constant integer, but received {}",
+            expr
+        ))),
+    }
+}
+
+/// The partitioning, ordering and frame over which a window function is evaluated
+#[derive(Debug)]
+struct WindowSpec {
+    partition_by: Vec<Arc<dyn PhysicalExpr>>,
+    order_by: Vec<PhysicalSortExpr>,
+    window_frame: WindowFrame,
+}
+
+impl WindowSpec {
+    fn try_new(
+        partition_by: Vec<Arc<dyn PhysicalExpr>>,
+        order_by: Vec<PhysicalSortExpr>,
+        window_frame: Option<WindowFrame>,
+        input_schema: &Schema,
+    ) -> Result<Self> {
+        let window_frame = window_frame.unwrap_or_default();
+        if window_frame.units == WindowFrameUnits::Range
+            && (has_offset(&window_frame.start_bound)
+                || has_offset(&window_frame.end_bound))
+        {
+            if order_by.len() != 1 {
+                return Err(DataFusionError::Plan(
+                    "A RANGE window frame with an offset requires exactly one ORDER BY \
+                     expression"
+                        .to_owned(),
+                ));
+            }
+            let data_type = order_by[0].expr.data_type(input_schema)?;
+            if !is_numeric(&data_type) {
+                return Err(DataFusionError::Plan(format!(
+                    "A RANGE window frame with an offset requires a numeric ORDER BY \
+                     expression, but received {:?}",
+                    data_type
+                )));
+            }
+        }
+
+        Ok(Self {
+            partition_by,
+            order_by,
+            window_frame,
+        })
+    }
+
+    /// Sorts the rows of the batch by partition and order, and finds the partitions and
+    /// the peers of the sorted rows
+    fn sort(&self, batch: &RecordBatch) -> Result<SortedWindow> {
+        let num_rows = batch.num_rows();
+        let mut sort_columns = self
+            .partition_by
+            .iter()
+            .map(|e| {
+                Ok(SortColumn {
+                    values: e.evaluate(batch)?.into_array(num_rows),
+                    options: None,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        for e in &self.order_by {
+            sort_columns.push(e.evaluate_to_sort_column(batch)?);
+        }
+
+        let indices = if sort_columns.is_empty() {
+            UInt32Array::from((0..num_rows as u32).collect::<Vec<_>>())
+        } else {
+            lexsort_to_indices(&sort_columns)?
+        };
+        let sorted_columns = sort_columns
+            .iter()
+            .map(|c| take(&c.values, &indices, None))
+            .collect::<ArrowResult<Vec<_>>>()?;
+        let (partition_columns, order_columns) =
+            sorted_columns.split_at(self.partition_by.len());
+
+        let partitions = find_ranges(partition_columns, 0..num_rows)?;
+        let mut peers = Vec::with_capacity(num_rows);
+        for partition in &partitions {
+            for range in find_ranges(order_columns, partition.clone())? {
+                for _ in range.clone() {
+                    peers.push(range.clone());
+                }
+            }
+        }
+
+        let range_keys = if self.window_frame.units == WindowFrameUnits::Range
+            && !order_columns.is_empty()
+        {
+            Some(range_keys(&order_columns[0], &self.order_by[0])?)
+        } else {
+            None
+        };
+
+        Ok(SortedWindow {
+            indices,
+            partitions,
+            peers,
+            range_keys,
+        })
+    }
+}
+
+/// Whether the bound is at an offset from the current row
+fn has_offset(bound: &WindowFrameBound) -> bool {
+    match bound {
+        WindowFrameBound::Preceding(Some(_)) | WindowFrameBound::Following(Some(_)) => {
+            true
+        }
+        _ => false,
+    }
+}
+
+/// The keys of the sorted values of the ORDER BY expression used to find the bounds of
+/// RANGE frames with an offset: the values are converted to `f64` and negated when sorted
+/// in descending order, so that the keys are in ascending order, with the nulls at
+/// either infinity.
+fn range_keys(values: &ArrayRef, sort_expr: &PhysicalSortExpr) -> Result<Vec<f64>> {
+    let values = cast(values, &DataType::Float64)?;
+    let values = values.as_any().downcast_ref::<Float64Array>().unwrap();
+    let options = sort_expr.options;
+    Ok((0..values.len())
+        .map(|i| match (values.is_null(i), options.descending) {
+            (true, _) if options.nulls_first => f64::NEG_INFINITY,
+            (true, _) => f64::INFINITY,
+            (false, true) => -values.value(i),
+            (false, false) => values.value(i),
+        })
+        .collect())
+}
+
+/// Splits `range` into the runs of consecutive rows with equal values in all the columns
+fn find_ranges(columns: &[ArrayRef], range: Range<usize>) -> Result<Vec<Range<usize>>> {
+    let comparators = columns
+        .iter()
+        .map(|c| build_compare(c.as_ref(), c.as_ref()))
+        .collect::<ArrowResult<Vec<DynComparator>>>()?;
+    let equal = |left: usize, right: usize| {
+        columns
+            .iter()
+            .zip(comparators.iter())
+            .all(|(column, compare)| {
+                match (column.is_null(left), column.is_null(right)) {
+                    (true, true) => true,
+                    (false, false) => compare(left, right) == Ordering::Equal,
+                    _ => false,
+                }
+            })
+    };
+
+    let mut ranges = vec![];
+    let mut start = range.start;
+    for row in range.start + 1..range.end {
+        if !equal(row - 1, row) {
+            ranges.push(start..row);
+            start = row;
+        }
+    }
+    if start < range.end {
+        ranges.push(start..range.end);
+    }
+    Ok(ranges)
+}
+
+fn slice_arrays(arrays: &[ArrayRef], range: Range<usize>) -> Vec<ArrayRef> {
+    arrays
+        .iter()
+        .map(|array| array.slice(range.start, range.len()))
+        .collect()
+}
+
+/// The rows of a batch sorted by the partition and order of a window
+struct SortedWindow {
+    /// the indices in the batch of the sorted rows
+    indices: UInt32Array,
+    /// the ranges of the sorted rows of every partition
+    partitions: Vec<Range<usize>>,
+    /// for every sorted row, the range of its peers: the rows of its partition with
+    /// the same values of the ORDER BY expressions
+    peers: Vec<Range<usize>>,
+    /// for RANGE frames, the keys of the ORDER BY expression, see `range_keys`
+    range_keys: Option<Vec<f64>>,
+}
+
+impl SortedWindow {
+    /// Evaluates an argument of a window function and sorts its values
+    fn sort_argument(
+        &self,
+        expr: &Arc<dyn PhysicalExpr>,
+        batch: &RecordBatch,
+    ) -> Result<ArrayRef> {
+        let values = expr.evaluate(batch)?.into_array(batch.num_rows());
+        Ok(take(&values, &self.indices, None)?)
+    }
+
+    /// Reorders the values of the sorted rows into the order of the rows of the batch
+    fn unsort(&self, values: &ArrayRef) -> Result<ArrayRef> {
+        let mut positions = vec![0_u32; self.indices.len()];
+        for position in 0..self.indices.len() {
+            positions[self.indices.value(position) as usize] = position as u32;
+        }
+        Ok(take(values, &UInt32Array::from(positions), None)?)
+    }
+
+    /// The range of sorted rows in the frame of the sorted row `row` of `partition`
+    fn frame(
+        &self,
+        window_frame: &WindowFrame,
+        partition: &Range<usize>,
+        row: usize,
+    ) -> Range<usize> {
+        let start = self.frame_position(
+            window_frame,
+            &window_frame.start_bound,
+            partition,
+            row,
+            0,
+        );
+        let end =
+            self.frame_position(window_frame, &window_frame.end_bound, partition, row, 1);
+        start..end.max(start)
+    }
+
+    /// The position of a bound of the frame of the sorted row `row`: the first row of the
+    /// frame for the start bound (`end` is 0), and the first row after the frame for
+    /// the end bound (`end` is 1)
+    fn frame_position(
+        &self,
+        window_frame: &WindowFrame,
+        bound: &WindowFrameBound,
+        partition: &Range<usize>,
+        row: usize,
+        end: usize,
+    ) -> usize {
+        let offset = match bound {
+            WindowFrameBound::Preceding(None) => return partition.start,
+            WindowFrameBound::Following(None) => return partition.end,
+            WindowFrameBound::CurrentRow => 0,
+            WindowFrameBound::Preceding(Some(n)) => -(*n as i64),
+            WindowFrameBound::Following(Some(n)) => *n as i64,
+        };
+        match (window_frame.units, &self.range_keys) {
+            (WindowFrameUnits::Rows, _) => {
+                let position = row as i64 + offset + end as i64;
+                position
+                    .max(partition.start as i64)
+                    .min(partition.end as i64) as usize
+            }
+            (WindowFrameUnits::Range, Some(keys)) if offset != 0 => {
+                let key = keys[row] + offset as f64;
+                if end == 0 {
+                    first_position(keys, partition, |k| k >= key)
+                } else {
+                    first_position(keys, partition, |k| k > key)
+                }
+            }
+            (WindowFrameUnits::Range, _) => {
+                let peers = &self.peers[row];
+                if end == 0 {
+                    peers.start
+                } else {
+                    peers.end
+                }
+            }
+        }
+    }
+}
+
+/// The first position of `range` whose key satisfies `predicate`, or the end of the range.
+/// The predicate must be false and then true over the keys of the range.
+fn first_position(
+    keys: &[f64],
+    range: &Range<usize>,
+    predicate: impl Fn(f64) -> bool,
+) -> usize {
+    let (mut low, mut high) = (range.start, range.end);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if predicate(keys[middle]) {
+            high = middle;
+        } else {
+            low = middle + 1;
+        }
+    }
+    low
+}
+
+fn compute_window_aggregates(
+    batches: Vec<RecordBatch>,
+    window_expr: &[Arc<dyn WindowExpr>],
+    input_schema: &SchemaRef,
+    schema: &SchemaRef,
+) -> Result<RecordBatch> {
+    // every row of a partition is needed to evaluate a window function, so the whole
+    // input is combined into a single batch
+    let batch = if batches.is_empty() {
+        common::create_batch_empty(input_schema)?
+    } else {
+        RecordBatch::try_new(
+            input_schema.clone(),
+            (0..input_schema.fields().len())
+                .map(|i| {
+                    concat(
+                        &batches
+                            .iter()
+                            .map(|batch| batch.column(i).clone())
+                            .collect::<Vec<ArrayRef>>(),
+                    )
+                })
+                .collect::<ArrowResult<Vec<ArrayRef>>>()?,
+        )?
+    };
+
+    let mut columns = batch.columns().to_vec();
+    for expr in window_expr {
+        columns.push(expr.evaluate(&batch)?);
+    }
+    Ok(RecordBatch::try_new(schema.clone(), columns)?)
+}
+
+pin_project! {
+    struct WindowAggStream {
+        #[pin]
+        output: futures::channel::oneshot::Receiver<ArrowResult<RecordBatch>>,
+        finished: bool,
+        schema: SchemaRef,
+        // aborts the computation of the window functions once the stream is dropped
+        _task: common::AbortOnDrop,
+    }
+}
+
+impl WindowAggStream {
+    fn new(
+        input: SendableRecordBatchStream,
+        window_expr: Vec<Arc<dyn WindowExpr>>,
+        schema: SchemaRef,
+    ) -> Self {
+        let (tx, rx) = futures::channel::oneshot::channel();
+
+        let output_schema = schema.clone();
+        let (_, task) = common::spawn_abortable(async move {
+            let input_schema = input.schema();
+            let batch = common::collect(input)
+                .await
+                .and_then(move |batches| {
+                    compute_window_aggregates(
+                        batches,
+                        &window_expr,
+                        &input_schema,
+                        &output_schema,
+                    )
+                })
+                .map_err(DataFusionError::into_arrow_external_error);
+
+            // If send fails, plan being torn down, there is no place to send the batch
+            tx.send(batch).ok();
+        });
+
+        Self {
+            output: rx,
+            finished: false,
+            schema,
+            _task: task,
+        }
+    }
+}
+
+impl Stream for WindowAggStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.finished {
+            return Poll::Ready(None);
+        }
+
+        // is the output ready?
+        let this = self.project();
+        let output_poll = this.output.poll(cx);
+
+        match output_poll {
+            Poll::Ready(result) => {
+                *this.finished = true;
+
+                // check for error in receiving channel and unwrap actual result
+                let result = match result {
+                    Err(e) => Err(ArrowError::ExternalError(Box::new(e))), // error receiving
+                    Ok(result) => result,
+                };
+                Poll::Ready(Some(result))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl RecordBatchStream for WindowAggStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::{col, lit};
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::test;
+    use arrow::array::{Int32Array, Int64Array, StringArray};
+    use arrow::compute::SortOptions;
+    use std::str::FromStr;
+
+    fn test_batch() -> Result<RecordBatch> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Utf8, false),
+            Field::new("b", DataType::Int32, false),
+            Field::new("c", DataType::Int32, false),
+        ]));
+        Ok(RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(StringArray::from(vec!["x", "y", "x", "x", "y", "x"])),
+                Arc::new(Int32Array::from(vec![1, 1, 2, 2, 3, 4])),
+                Arc::new(Int32Array::from(vec![10, 20, 30, 40, 50, 60])),
+            ],
+        )?)
+    }
+
+    /// a window expression partitioned by `a`
+    fn window_expr(
+        fun: &str,
+        args: Vec<Arc<dyn PhysicalExpr>>,
+        order_by: &str,
+        window_frame: Option<WindowFrame>,
+        name: &str,
+    ) -> Result<Arc<dyn WindowExpr>> {
+        create_window_expr(
+            &WindowFunction::from_str(fun)?,
+            &args,
+            &[col("a")],
+            &[PhysicalSortExpr {
+                expr: col(order_by),
+                options: SortOptions::default(),
+            }],
+            window_frame,
+            &test_batch()?.schema(),
+            name.to_owned(),
+        )
+    }
+
+    async fn execute_window(
+        window_expr: Vec<Arc<dyn WindowExpr>>,
+    ) -> Result<Vec<String>> {
+        let batch = test_batch()?;
+        let input =
+            MemoryExec::try_new(&vec![vec![batch.clone()]], batch.schema(), None)?;
+        let window = WindowAggExec::try_new(window_expr, Arc::new(input))?;
+        let result = test::execute(Arc::new(window)).await?;
+        assert_eq!(result.len(), 1);
+        Ok(test::format_batch(&result[0]))
+    }
+
+    #[tokio::test]
+    async fn window_ranks() -> Result<()> {
+        let result = execute_window(vec![
+            window_expr("row_number", vec![], "c", None, "row_number")?,
+            window_expr("rank", vec![], "b", None, "rank")?,
+            window_expr("dense_rank", vec![], "b", None, "dense_rank")?,
+        ])
+        .await?;
+
+        let expected = vec![
+            "x,1,10,1,1,1",
+            "y,1,20,1,1,1",
+            "x,2,30,2,2,2",
+            "x,2,40,3,2,2",
+            "y,3,50,2,2,2",
+            "x,4,60,4,4,3",
+        ];
+        assert_eq!(result, expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn window_aggregate_frames() -> Result<()> {
+        let rows = WindowFrame {
+            units: WindowFrameUnits::Rows,
+            start_bound: WindowFrameBound::Preceding(Some(1)),
+            end_bound: WindowFrameBound::Following(Some(1)),
+        };
+        let range = WindowFrame {
+            units: WindowFrameUnits::Range,
+            ..rows
+        };
+        let result = execute_window(vec![
+            // the default frame includes the peers of the current row
+            window_expr("sum", vec![col("c")], "b", None, "running")?,
+            window_expr("sum", vec![col("c")], "c", Some(rows), "rows")?,
+            window_expr("sum", vec![col("c")], "b", Some(range), "range")?,
+        ])
+        .await?;
+
+        let expected = vec![
+            "x,1,10,10,40,80",
+            "y,1,20,20,70,20",
+            "x,2,30,80,80,80",
+            "x,2,40,80,130,80",
+            "y,3,50,70,70,50",
+            "x,4,60,140,100,60",
+        ];
+        assert_eq!(result, expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn window_aggregate_large_frames() -> Result<()> {
+        // frames of 1000 rows slide over 10000 rows, of which every 7th is null
+        let num_rows = 10_000;
+        let values: Vec<Option<i32>> = (0..num_rows as i32)
+            .map(|i| if i % 7 == 0 { None } else { Some(i) })
+            .collect();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Utf8, false),
+            Field::new("b", DataType::Int32, false),
+            Field::new("c", DataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec!["x"; num_rows])),
+                Arc::new(Int32Array::from((0..num_rows as i32).collect::<Vec<_>>())),
+                Arc::new(Int32Array::from(values.clone())),
+            ],
+        )?;
+        let window_frame = WindowFrame {
+            units: WindowFrameUnits::Rows,
+            start_bound: WindowFrameBound::Preceding(Some(500)),
+            end_bound: WindowFrameBound::Following(Some(499)),
+        };
+        let window_expr = ["sum", "count", "avg", "max"]
+            .iter()
+            .map(|fun| {
+                create_window_expr(
+                    &WindowFunction::from_str(fun)?,
+                    &[col("c")],
+                    &[col("a")],
+                    &[PhysicalSortExpr {
+                        expr: col("b"),
+                        options: SortOptions::default(),
+                    }],
+                    Some(window_frame),
+                    &schema,
+                    fun.to_string(),
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let input = MemoryExec::try_new(&vec![vec![batch]], schema, None)?;
+        let window = WindowAggExec::try_new(window_expr, Arc::new(input))?;
+        let result = test::execute(Arc::new(window)).await?;
+        assert_eq!(result.len(), 1);
+
+        let column = |i: usize| result[0].column(i).clone();
+        let sums = column(3);
+        let sums = sums.as_any().downcast_ref::<Int64Array>().unwrap();
+        let counts = column(4);
+        let counts = counts.as_any().downcast_ref::<UInt64Array>().unwrap();
+        let avgs = column(5);
+        let avgs = avgs.as_any().downcast_ref::<Float64Array>().unwrap();
+        let maxs = column(6);
+        let maxs = maxs.as_any().downcast_ref::<Int32Array>().unwrap();
+        for row in 0..num_rows {
+            let frame: Vec<i64> = values
+                [row.saturating_sub(500)..(row + 500).min(num_rows)]
+                .iter()
+                .flatten()
+                .map(|value| *value as i64)
+                .collect();
+            let sum: i64 = frame.iter().sum();
+            assert_eq!(sums.value(row), sum);
+            assert_eq!(counts.value(row), frame.len() as u64);
+            assert!((avgs.value(row) - sum as f64 / frame.len() as f64).abs() < 1e-6);
+            assert_eq!(maxs.value(row) as i64, *frame.iter().max().unwrap());
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn window_shift_and_values() -> Result<()> {
+        let following = WindowFrame {
+            units: WindowFrameUnits::Rows,
+            start_bound: WindowFrameBound::CurrentRow,
+            end_bound: WindowFrameBound::Following(None),
+        };
+        let result = execute_window(vec![
+            window_expr("lag", vec![col("c")], "c", None, "lag")?,
+            window_expr(
+                "lead",
+                vec![
+                    col("c"),
+                    lit(ScalarValue::Int64(Some(1))),
+                    lit(ScalarValue::Int64(Some(0))),
+                ],
+                "c",
+                None,
+                "lead",
+            )?,
+            window_expr("first_value", vec![col("c")], "c", None, "first")?,
+            window_expr("last_value", vec![col("c")], "c", Some(following), "last")?,
+        ])
+        .await?;
+
+        let expected = vec![
+            "x,1,10,NULL,30,10,60",
+            "y,1,20,NULL,50,20,50",
+            "x,2,30,10,40,10,60",
+            "x,2,40,30,60,10,60",
+            "y,3,50,20,0,20,50",
+            "x,4,60,40,0,10,60",
+        ];
+        assert_eq!(result, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn range_offset_requires_single_order_by() -> Result<()> {
+        let window_frame = WindowFrame {
+            units: WindowFrameUnits::Range,
+            start_bound: WindowFrameBound::Preceding(Some(1)),
+            end_bound: WindowFrameBound::CurrentRow,
+        };
+        let result = create_window_expr(
+            &WindowFunction::from_str("sum")?,
+            &[col("c")],
+            &[],
+            &[],
+            Some(window_frame),
+            &test_batch()?.schema(),
+            "sum".to_owned(),
+        );
+        assert!(result.is_err());
+
+        // the ORDER BY expression must be numeric
+        let result = window_expr("sum", vec![col("c")], "a", Some(window_frame), "sum");
+        assert!(result.is_err());
+        Ok(())
+    }
+}
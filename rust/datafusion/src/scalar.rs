@@ -17,11 +17,12 @@
 
 //! This module provides ScalarValue, an enum that can be used for storage of single elements
 
-use std::{convert::TryFrom, fmt, sync::Arc};
+use std::{convert::TryFrom, fmt, iter::FromIterator, sync::Arc};
 
 use arrow::array::{
-    Int16Builder, Int32Builder, Int64Builder, Int8Builder, ListBuilder, UInt16Builder,
-    UInt32Builder, UInt64Builder, UInt8Builder,
+    BooleanBuilder, Float32Builder, Float64Builder, Int16Builder, Int32Builder,
+    Int64Builder, Int8Builder, ListBuilder, StringBuilder, UInt16Builder, UInt32Builder,
+    UInt64Builder, UInt8Builder,
 };
 use arrow::{
     array::ArrayRef,
@@ -29,11 +30,13 @@ use arrow::{
 };
 use arrow::{
     array::{
-        Array, BooleanArray, Date32Array, Float32Array, Float64Array, Int16Array,
-        Int32Array, Int64Array, Int8Array, LargeStringArray, ListArray, StringArray,
-        UInt16Array, UInt32Array, UInt64Array, UInt8Array,
+        Array, BooleanArray, Date32Array, DecimalArray, Float32Array, Float64Array,
+        Int16Array, Int32Array, Int64Array, Int8Array, IntervalDayTimeArray,
+        IntervalYearMonthArray, LargeStringArray, ListArray, StringArray,
+        TimestampNanosecondArray, UInt16Array, UInt32Array, UInt64Array, UInt8Array,
     },
-    datatypes::DateUnit,
+    datatypes::{DateUnit, IntervalUnit, TimeUnit},
+    util::decimal,
 };
 
 use crate::error::{DataFusionError, Result};
@@ -64,6 +67,9 @@ pub enum ScalarValue {
     UInt32(Option<u32>),
     /// unsigned 64bit int
     UInt64(Option<u64>),
+    /// 128bit decimal, of the given precision and scale, stored as an integer of its
+    /// value times `10^scale`
+    Decimal128(Option<i128>, usize, usize),
     /// utf-8 encoded string.
     Utf8(Option<String>),
     /// utf-8 encoded string representing a LargeString's arrow type.
@@ -72,6 +78,13 @@ pub enum ScalarValue {
     List(Option<Vec<ScalarValue>>, DataType),
     /// Date stored as a signed 32bit int
     Date32(Option<i32>),
+    /// Timestamp in nanoseconds since the UNIX epoch
+    TimestampNanosecond(Option<i64>),
+    /// Interval of a number of months
+    IntervalYearMonth(Option<i32>),
+    /// Interval of a number of days, in the high 32 bits, and of milliseconds, in the low
+    /// 32 bits
+    IntervalDayTime(Option<i64>),
 }
 
 macro_rules! typed_cast {
@@ -118,6 +131,57 @@ macro_rules! build_list {
     }};
 }
 
+macro_rules! build_string_list {
+    ($VALUES:expr, $SIZE:expr) => {{
+        match $VALUES {
+            None => {
+                let mut builder = ListBuilder::new(StringBuilder::new(0));
+                for _ in 0..$SIZE {
+                    builder.append(false).unwrap();
+                }
+                builder.finish()
+            }
+            Some(values) => {
+                let mut builder = ListBuilder::new(StringBuilder::new(values.len()));
+
+                for _ in 0..$SIZE {
+                    for scalar_value in values {
+                        match scalar_value {
+                            ScalarValue::Utf8(Some(v)) => {
+                                builder.values().append_value(v).unwrap()
+                            }
+                            ScalarValue::Utf8(None) => {
+                                builder.values().append_null().unwrap();
+                            }
+                            _ => panic!("Incompatible ScalarValue for list"),
+                        };
+                    }
+                    builder.append(true).unwrap();
+                }
+
+                builder.finish()
+            }
+        }
+    }};
+}
+
+macro_rules! scalars_to_values {
+    ($DATA_TYPE:expr, $SCALARS:expr, $SCALAR:ident) => {{
+        $SCALARS
+            .iter()
+            .cloned()
+            .map(|scalar| match scalar {
+                ScalarValue::$SCALAR(v) => Ok(v),
+                scalar if scalar.is_null() => Ok(None),
+                scalar => Err(DataFusionError::Internal(format!(
+                    "Cannot build an array of type {:?} from the scalar {:?}",
+                    $DATA_TYPE, scalar
+                ))),
+            })
+            .collect::<Result<Vec<_>>>()?
+    }};
+}
+
 impl ScalarValue {
     /// Getter for the `DataType` of the value
     pub fn get_datatype(&self) -> DataType {
@@ -133,12 +197,22 @@ impl ScalarValue {
             ScalarValue::Int64(_) => DataType::Int64,
             ScalarValue::Float32(_) => DataType::Float32,
             ScalarValue::Float64(_) => DataType::Float64,
+            ScalarValue::Decimal128(_, precision, scale) => {
+                DataType::Decimal(*precision, *scale)
+            }
             ScalarValue::Utf8(_) => DataType::Utf8,
             ScalarValue::LargeUtf8(_) => DataType::LargeUtf8,
             ScalarValue::List(_, data_type) => {
                 DataType::List(Box::new(Field::new("item", data_type.clone(), true)))
             }
             ScalarValue::Date32(_) => DataType::Date32(DateUnit::Day),
+            ScalarValue::TimestampNanosecond(_) => {
+                DataType::Timestamp(TimeUnit::Nanosecond, None)
+            }
+            ScalarValue::IntervalYearMonth(_) => {
+                DataType::Interval(IntervalUnit::YearMonth)
+            }
+            ScalarValue::IntervalDayTime(_) => DataType::Interval(IntervalUnit::DayTime),
         }
     }
 
@@ -156,9 +230,14 @@ impl ScalarValue {
             | ScalarValue::Int64(None)
             | ScalarValue::Float32(None)
             | ScalarValue::Float64(None)
+            | ScalarValue::Decimal128(None, _, _)
             | ScalarValue::Utf8(None)
             | ScalarValue::LargeUtf8(None)
-            | ScalarValue::List(None, _) => true,
+            | ScalarValue::List(None, _)
+            | ScalarValue::Date32(None)
+            | ScalarValue::TimestampNanosecond(None)
+            | ScalarValue::IntervalYearMonth(None)
+            | ScalarValue::IntervalDayTime(None) => true,
             _ => false,
         }
     }
@@ -186,6 +265,9 @@ impl ScalarValue {
             ScalarValue::UInt16(e) => Arc::new(UInt16Array::from(vec![*e; size])),
             ScalarValue::UInt32(e) => Arc::new(UInt32Array::from(vec![*e; size])),
             ScalarValue::UInt64(e) => Arc::new(UInt64Array::from(vec![*e; size])),
+            ScalarValue::Decimal128(e, precision, scale) => Arc::new(
+                DecimalArray::from_opt_vec(vec![*e; size], *precision, *scale),
+            ),
             ScalarValue::Utf8(e) => Arc::new(StringArray::from(vec![e.as_deref(); size])),
             ScalarValue::LargeUtf8(e) => {
                 Arc::new(LargeStringArray::from(vec![e.as_deref(); size]))
@@ -199,12 +281,103 @@ impl ScalarValue {
                 DataType::UInt16 => build_list!(UInt16Builder, UInt16, values, size),
                 DataType::UInt32 => build_list!(UInt32Builder, UInt32, values, size),
                 DataType::UInt64 => build_list!(UInt64Builder, UInt64, values, size),
+                DataType::Float32 => build_list!(Float32Builder, Float32, values, size),
+                DataType::Float64 => build_list!(Float64Builder, Float64, values, size),
+                DataType::Boolean => build_list!(BooleanBuilder, Boolean, values, size),
+                DataType::Utf8 => build_string_list!(values, size),
                 _ => panic!("Unexpected DataType for list"),
             }),
             ScalarValue::Date32(e) => Arc::new(Date32Array::from(vec![*e; size])),
+            ScalarValue::TimestampNanosecond(e) => {
+                Arc::new(TimestampNanosecondArray::from_opt_vec(vec![*e; size], None))
+            }
+            ScalarValue::IntervalYearMonth(e) => {
+                Arc::new(IntervalYearMonthArray::from(vec![*e; size]))
+            }
+            ScalarValue::IntervalDayTime(e) => {
+                Arc::new(IntervalDayTimeArray::from(vec![*e; size]))
+            }
         }
     }
 
+    /// Converts a sequence of scalars of type `data_type` into an array, with one element
+    /// per scalar. Null scalars of any type are accepted as nulls of `data_type`.
+    pub fn iter_to_array(
+        data_type: &DataType,
+        scalars: &[ScalarValue],
+    ) -> Result<ArrayRef> {
+        Ok(match data_type {
+            DataType::Boolean => Arc::new(BooleanArray::from(scalars_to_values!(
+                data_type, scalars, Boolean
+            ))),
+            DataType::Float64 => Arc::new(Float64Array::from(scalars_to_values!(
+                data_type, scalars, Float64
+            ))),
+            DataType::Float32 => Arc::new(Float32Array::from(scalars_to_values!(
+                data_type, scalars, Float32
+            ))),
+            DataType::Int8 => Arc::new(Int8Array::from(scalars_to_values!(
+                data_type, scalars, Int8
+            ))),
+            DataType::Int16 => Arc::new(Int16Array::from(scalars_to_values!(
+                data_type, scalars, Int16
+            ))),
+            DataType::Int32 => Arc::new(Int32Array::from(scalars_to_values!(
+                data_type, scalars, Int32
+            ))),
+            DataType::Int64 => Arc::new(Int64Array::from(scalars_to_values!(
+                data_type, scalars, Int64
+            ))),
+            DataType::UInt8 => Arc::new(UInt8Array::from(scalars_to_values!(
+                data_type, scalars, UInt8
+            ))),
+            DataType::UInt16 => Arc::new(UInt16Array::from(scalars_to_values!(
+                data_type, scalars, UInt16
+            ))),
+            DataType::UInt32 => Arc::new(UInt32Array::from(scalars_to_values!(
+                data_type, scalars, UInt32
+            ))),
+            DataType::UInt64 => Arc::new(UInt64Array::from(scalars_to_values!(
+                data_type, scalars, UInt64
+            ))),
+            DataType::Decimal(precision, scale) => {
+                let values = scalars
+                    .iter()
+                    .map(|scalar| match scalar {
+                        ScalarValue::Decimal128(v, _, s) if s == scale => Ok(*v),
+                        scalar if scalar.is_null() => Ok(None),
+                        scalar => Err(DataFusionError::Internal(format!(
+                            "Cannot build an array of type {:?} from the scalar {:?}",
+                            data_type, scalar
+                        ))),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Arc::new(DecimalArray::from_opt_vec(values, *precision, *scale))
+            }
+            DataType::Utf8 => Arc::new(StringArray::from_iter(scalars_to_values!(
+                data_type, scalars, Utf8
+            ))),
+            DataType::LargeUtf8 => Arc::new(LargeStringArray::from_iter(
+                scalars_to_values!(data_type, scalars, LargeUtf8),
+            )),
+            DataType::Date32(DateUnit::Day) => Arc::new(Date32Array::from(
+                scalars_to_values!(data_type, scalars, Date32),
+            )),
+            DataType::Timestamp(TimeUnit::Nanosecond, None) => {
+                Arc::new(TimestampNanosecondArray::from_opt_vec(
+                    scalars_to_values!(data_type, scalars, TimestampNanosecond),
+                    None,
+                ))
+            }
+            other => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "Building an array of type {:?} from scalars is not supported",
+                    other
+                )))
+            }
+        })
+    }
+
     /// Converts a value in `array` at `index` into a ScalarValue
     pub fn try_from_array(array: &ArrayRef, index: usize) -> Result<Self> {
         Ok(match array.data_type() {
@@ -219,6 +392,14 @@ impl ScalarValue {
             DataType::Int32 => typed_cast!(array, index, Int32Array, Int32),
             DataType::Int16 => typed_cast!(array, index, Int16Array, Int16),
             DataType::Int8 => typed_cast!(array, index, Int8Array, Int8),
+            DataType::Decimal(precision, scale) => {
+                let array = array.as_any().downcast_ref::<DecimalArray>().unwrap();
+                let value = match array.is_null(index) {
+                    true => None,
+                    false => Some(array.value(index)),
+                };
+                ScalarValue::Decimal128(value, *precision, *scale)
+            }
             DataType::Utf8 => typed_cast!(array, index, StringArray, Utf8),
             DataType::LargeUtf8 => typed_cast!(array, index, LargeStringArray, LargeUtf8),
             DataType::List(nested_type) => {
@@ -240,6 +421,15 @@ impl ScalarValue {
             DataType::Date32(DateUnit::Day) => {
                 typed_cast!(array, index, Date32Array, Date32)
             }
+            DataType::Timestamp(TimeUnit::Nanosecond, None) => {
+                typed_cast!(array, index, TimestampNanosecondArray, TimestampNanosecond)
+            }
+            DataType::Interval(IntervalUnit::YearMonth) => {
+                typed_cast!(array, index, IntervalYearMonthArray, IntervalYearMonth)
+            }
+            DataType::Interval(IntervalUnit::DayTime) => {
+                typed_cast!(array, index, IntervalDayTimeArray, IntervalDayTime)
+            }
             other => {
                 return Err(DataFusionError::NotImplemented(format!(
                     "Can't create a scalar of array of type \"{:?}\"",
@@ -355,7 +545,23 @@ impl TryFrom<ScalarValue> for i32 {
     }
 }
 
-impl_try_from!(Int64, i64);
+// special implementation for i64 because of TimestampNanosecond
+impl TryFrom<ScalarValue> for i64 {
+    type Error = DataFusionError;
+
+    fn try_from(value: ScalarValue) -> Result<Self> {
+        match value {
+            ScalarValue::Int64(Some(inner_value))
+            | ScalarValue::TimestampNanosecond(Some(inner_value)) => Ok(inner_value),
+            _ => Err(DataFusionError::Internal(format!(
+                "Cannot convert {:?} to {}",
+                value,
+                std::any::type_name::<Self>()
+            ))),
+        }
+    }
+}
+
 impl_try_from!(UInt8, u8);
 impl_try_from!(UInt16, u16);
 impl_try_from!(UInt32, u32);
@@ -380,11 +586,24 @@ impl TryFrom<&DataType> for ScalarValue {
             &DataType::UInt16 => ScalarValue::UInt16(None),
             &DataType::UInt32 => ScalarValue::UInt32(None),
             &DataType::UInt64 => ScalarValue::UInt64(None),
+            &DataType::Decimal(precision, scale) => {
+                ScalarValue::Decimal128(None, precision, scale)
+            }
             &DataType::Utf8 => ScalarValue::Utf8(None),
             &DataType::LargeUtf8 => ScalarValue::LargeUtf8(None),
             &DataType::List(ref nested_type) => {
                 ScalarValue::List(None, nested_type.data_type().clone())
             }
+            &DataType::Date32(DateUnit::Day) => ScalarValue::Date32(None),
+            &DataType::Timestamp(TimeUnit::Nanosecond, None) => {
+                ScalarValue::TimestampNanosecond(None)
+            }
+            &DataType::Interval(IntervalUnit::YearMonth) => {
+                ScalarValue::IntervalYearMonth(None)
+            }
+            &DataType::Interval(IntervalUnit::DayTime) => {
+                ScalarValue::IntervalDayTime(None)
+            }
             _ => {
                 return Err(DataFusionError::NotImplemented(format!(
                     "Can't create a scalar of type \"{:?}\"",
@@ -418,6 +637,9 @@ impl fmt::Display for ScalarValue {
             ScalarValue::UInt16(e) => format_option!(f, e)?,
             ScalarValue::UInt32(e) => format_option!(f, e)?,
             ScalarValue::UInt64(e) => format_option!(f, e)?,
+            ScalarValue::Decimal128(e, _, scale) => {
+                format_option!(f, e.map(|v| decimal::format_decimal(v, *scale)))?
+            }
             ScalarValue::Utf8(e) => format_option!(f, e)?,
             ScalarValue::LargeUtf8(e) => format_option!(f, e)?,
             ScalarValue::List(e, _) => match e {
@@ -432,6 +654,9 @@ impl fmt::Display for ScalarValue {
                 None => write!(f, "NULL")?,
             },
             ScalarValue::Date32(e) => format_option!(f, e)?,
+            ScalarValue::TimestampNanosecond(e) => format_option!(f, e)?,
+            ScalarValue::IntervalYearMonth(e) => format_option!(f, e)?,
+            ScalarValue::IntervalDayTime(e) => format_option!(f, e)?,
         };
         Ok(())
     }
@@ -451,10 +676,18 @@ impl fmt::Debug for ScalarValue {
             ScalarValue::UInt16(_) => write!(f, "UInt16({})", self),
             ScalarValue::UInt32(_) => write!(f, "UInt32({})", self),
             ScalarValue::UInt64(_) => write!(f, "UInt64({})", self),
+            ScalarValue::Decimal128(_, precision, scale) => {
+                write!(f, "Decimal128({}, {}, {})", self, precision, scale)
+            }
             ScalarValue::Utf8(_) => write!(f, "Utf8(\"{}\")", self),
             ScalarValue::LargeUtf8(_) => write!(f, "LargeUtf8(\"{}\")", self),
             ScalarValue::List(_, _) => write!(f, "List([{}])", self),
             ScalarValue::Date32(_) => write!(f, "Date32(\"{}\")", self),
+            ScalarValue::TimestampNanosecond(_) => {
+                write!(f, "TimestampNanosecond({})", self)
+            }
+            ScalarValue::IntervalYearMonth(_) => write!(f, "IntervalYearMonth({})", self),
+            ScalarValue::IntervalDayTime(_) => write!(f, "IntervalDayTime({})", self),
         }
     }
 }
@@ -463,6 +696,48 @@ impl fmt::Debug for ScalarValue {
 mod tests {
     use super::*;
 
+    #[test]
+    fn scalar_iter_to_array() -> Result<()> {
+        let scalars = vec![
+            ScalarValue::Int64(Some(1)),
+            ScalarValue::Int64(None),
+            ScalarValue::UInt8(None),
+            ScalarValue::Int64(Some(3)),
+        ];
+        let array = ScalarValue::iter_to_array(&DataType::Int64, &scalars)?;
+        let array = array.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(array.len(), 4);
+        assert_eq!(array.value(0), 1);
+        assert!(array.is_null(1));
+        assert!(array.is_null(2));
+        assert_eq!(array.value(3), 3);
+
+        let scalars = vec![
+            ScalarValue::Utf8(Some("a".to_owned())),
+            ScalarValue::Int64(Some(1)),
+        ];
+        assert!(ScalarValue::iter_to_array(&DataType::Utf8, &scalars).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn scalar_decimal() -> Result<()> {
+        let scalar = ScalarValue::Decimal128(Some(-12345), 7, 2);
+        assert_eq!(DataType::Decimal(7, 2), scalar.get_datatype());
+        assert_eq!("-123.45", scalar.to_string());
+        assert_eq!("Decimal128(-123.45, 7, 2)", format!("{:?}", scalar));
+
+        let scalars = vec![scalar.clone(), ScalarValue::Decimal128(None, 7, 2)];
+        let array = ScalarValue::iter_to_array(&DataType::Decimal(7, 2), &scalars)?;
+        assert_eq!(&DataType::Decimal(7, 2), array.data_type());
+        assert_eq!(scalar, ScalarValue::try_from_array(&array, 0)?);
+        assert!(ScalarValue::try_from_array(&array, 1)?.is_null());
+
+        let scalars = vec![ScalarValue::Decimal128(Some(1), 7, 3)];
+        assert!(ScalarValue::iter_to_array(&DataType::Decimal(7, 2), &scalars).is_err());
+        Ok(())
+    }
+
     #[test]
     fn scalar_list_null_to_array() -> Result<()> {
         let list_array_ref = ScalarValue::List(None, DataType::UInt64).to_array();
@@ -503,4 +778,30 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn scalar_string_list_to_array() -> Result<()> {
+        let list_array_ref = ScalarValue::List(
+            Some(vec![
+                ScalarValue::Utf8(Some("a".to_owned())),
+                ScalarValue::Utf8(None),
+            ]),
+            DataType::Utf8,
+        )
+        .to_array_of_size(2);
+
+        let list_array = list_array_ref.as_any().downcast_ref::<ListArray>().unwrap();
+        assert_eq!(list_array.len(), 2);
+        assert_eq!(list_array.values().len(), 4);
+
+        let string_array_ref = list_array.value(1);
+        let string_array = string_array_ref
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(string_array.value(0), "a");
+        assert!(string_array.is_null(1));
+
+        Ok(())
+    }
 }
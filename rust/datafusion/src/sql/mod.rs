@@ -20,3 +20,4 @@
 
 pub mod parser;
 pub mod planner;
+mod utils;
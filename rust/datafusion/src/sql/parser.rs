@@ -59,11 +59,13 @@ pub struct CreateExternalTable {
     pub location: String,
 }
 
-/// DataFusion extension DDL for `EXPLAIN` and `EXPLAIN VERBOSE`
+/// DataFusion extension DDL for `EXPLAIN`, `EXPLAIN ANALYZE` and `EXPLAIN VERBOSE`
 #[derive(Debug, Clone, PartialEq)]
 pub struct ExplainPlan {
     /// If true, dumps more intermediate plans and results of optimizaton passes
     pub verbose: bool,
+    /// If true, executes the statement and dumps its plan with the metrics of its execution
+    pub analyze: bool,
     /// The statement for which to generate an planning explanation
     pub statement: Box<Statement>,
 }
@@ -184,6 +186,16 @@ impl DFParser {
     /// Parse an SQL EXPLAIN statement.
     pub fn parse_explain(&mut self) -> Result<Statement, ParserError> {
         // Parser is at the token immediately after EXPLAIN
+        // Check for EXPLAIN ANALYZE
+        let analyze = match self.parser.peek_token() {
+            // ANALYZE is a keyword in some versions of the SQL parser
+            Token::Word(w) if w.value.to_uppercase() == "ANALYZE" => {
+                self.parser.next_token();
+                true
+            }
+            _ => false,
+        };
+
         // Check for EXPLAIN VERBOSE
         let verbose = match self.parser.peek_token() {
             Token::Word(w) => match w.keyword {
@@ -197,7 +209,11 @@ impl DFParser {
         };
 
         let statement = Box::new(self.parse_statement()?);
-        let explain_plan = ExplainPlan { statement, verbose };
+        let explain_plan = ExplainPlan {
+            statement,
+            verbose,
+            analyze,
+        };
         Ok(Statement::Explain(explain_plan))
     }
 
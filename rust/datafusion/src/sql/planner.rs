@@ -17,12 +17,14 @@
 
 //! SQL Query Planner (produces logical plan from SQL AST)
 
+use std::convert::TryFrom;
 use std::str::FromStr;
 use std::sync::Arc;
 
 use crate::logical_plan::Expr::Alias;
 use crate::logical_plan::{
-    and, lit, Expr, LogicalPlan, LogicalPlanBuilder, Operator, PlanType, StringifiedPlan,
+    and, binary_expr, coerce_set_operation_inputs, col, count, lit, or, when, Expr,
+    LogicalPlan, LogicalPlanBuilder, Operator, PlanType, StringifiedPlan, WindowFrame,
 };
 use crate::scalar::ScalarValue;
 use crate::{
@@ -31,23 +33,29 @@ use crate::{
 };
 use crate::{
     physical_plan::udf::ScalarUDF,
-    physical_plan::{aggregates, functions},
+    physical_plan::{aggregates, functions, window_functions},
     sql::parser::{CreateExternalTable, FileType, Statement as DFStatement},
 };
 
 use arrow::datatypes::*;
+use arrow::util::decimal::MAX_DECIMAL_PRECISION;
 
 use super::parser::ExplainPlan;
+use super::utils::{
+    find_aggregate_exprs, find_window_exprs, qualified_field_name, rebase_expr,
+    unaliased_names, ColumnName, QualifiedSchema,
+};
+use crate::optimizer::utils::expr_to_column_names;
 use crate::prelude::JoinType;
 use sqlparser::ast::{
-    BinaryOperator, DataType as SQLDataType, Expr as SQLExpr, Join, JoinConstraint,
-    JoinOperator, Query, Select, SelectItem, SetExpr, TableFactor, TableWithJoins,
-    UnaryOperator, Value,
+    BinaryOperator, DataType as SQLDataType, DateTimeField, Expr as SQLExpr, Function,
+    Ident, Join, JoinConstraint, JoinOperator, Query, Select, SelectItem, SetExpr,
+    SetOperator, TableAlias, TableFactor, TableWithJoins, UnaryOperator, Value,
+    WindowSpec,
 };
 use sqlparser::ast::{ColumnDef as SQLColumnDef, ColumnOption};
 use sqlparser::ast::{OrderByExpr, Statement};
-use sqlparser::parser::ParserError::ParserError;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 /// The SchemaProvider trait allows the query planner to obtain meta-data about tables and
 /// functions referenced in SQL statements
@@ -65,6 +73,28 @@ pub struct SqlToRel<'a, S: SchemaProvider> {
     schema_provider: &'a S,
 }
 
+/// The plan of a relation of a SQL query along with the names of its columns in the query
+#[derive(Clone)]
+struct Relation {
+    plan: LogicalPlan,
+    schema: QualifiedSchema,
+}
+
+impl Relation {
+    /// A relation whose columns do not belong to any relation, e.g. a set operation
+    fn unqualified(plan: LogicalPlan) -> Self {
+        let schema = QualifiedSchema::unqualified(plan.schema());
+        Self { plan, schema }
+    }
+
+    /// The relation of `plan`, which is derived from the relations of `scope`, e.g. their
+    /// join or a filter of them
+    fn derived(plan: LogicalPlan, scope: &QualifiedSchema) -> Self {
+        let schema = scope.qualify(plan.schema());
+        Self { plan, schema }
+    }
+}
+
 impl<'a, S: SchemaProvider> SqlToRel<'a, S> {
     /// Create a new query planner
     pub fn new(schema_provider: &'a S) -> Self {
@@ -92,17 +122,179 @@ impl<'a, S: SchemaProvider> SqlToRel<'a, S> {
 
     /// Generate a logic plan from an SQL query
     pub fn query_to_plan(&self, query: &Query) -> Result<LogicalPlan> {
-        let plan = match &query.body {
-            SetExpr::Select(s) => self.select_to_plan(s.as_ref()),
+        Ok(self.query_to_plan_with_ctes(query, &HashMap::new())?.plan)
+    }
+
+    /// Generate a logic plan from an SQL query, where `ctes` are the common table
+    /// expressions (WITH clauses) of the enclosing queries that are in scope
+    fn query_to_plan_with_ctes(
+        &self,
+        query: &Query,
+        ctes: &HashMap<String, Relation>,
+    ) -> Result<Relation> {
+        let query_ctes;
+        let ctes = if query.ctes.is_empty() {
+            ctes
+        } else {
+            query_ctes = self.ctes_to_plans(query, ctes)?;
+            &query_ctes
+        };
+
+        let relation = self.set_expr_to_plan(&query.body, ctes)?;
+
+        let plan = self.order_by(&relation, &query.order_by)?;
+
+        let plan = self.limit(&plan, &query.limit)?;
+        Ok(Relation::derived(plan, &relation.schema))
+    }
+
+    /// Plans each common table expression of `query` once, in order, so that a CTE can
+    /// reference the ones defined before it. Returns the CTEs in scope of the query's body.
+    fn ctes_to_plans(
+        &self,
+        query: &Query,
+        ctes: &HashMap<String, Relation>,
+    ) -> Result<HashMap<String, Relation>> {
+        let mut scope = ctes.clone();
+        let mut names = HashSet::new();
+        for cte in &query.ctes {
+            let name = cte.alias.name.value.clone();
+            if !names.insert(name.clone()) {
+                return Err(DataFusionError::Plan(format!(
+                    "WITH query name \"{}\" specified more than once",
+                    name
+                )));
+            }
+
+            let relation = self.query_to_plan_with_ctes(&cte.query, &scope)?;
+            let relation = if cte.alias.columns.is_empty() {
+                relation
+            } else {
+                self.rename_columns(&relation, &name, &cte.alias.columns, None)?
+            };
+            scope.insert(name, relation);
+        }
+        Ok(scope)
+    }
+
+    /// Renames the columns of `relation`, the relation `name`, to `columns` (or keeps
+    /// their names when `columns` is empty) and qualifies them with `qualifier`
+    fn rename_columns(
+        &self,
+        relation: &Relation,
+        name: &str,
+        columns: &[Ident],
+        qualifier: Option<&str>,
+    ) -> Result<Relation> {
+        let fields = relation.plan.schema().fields();
+        if !columns.is_empty() && fields.len() != columns.len() {
+            return Err(DataFusionError::Plan(format!(
+                "Relation \"{}\" has {} columns, but {} column names were specified",
+                name,
+                fields.len(),
+                columns.len()
+            )));
+        }
+
+        let column_names: Vec<ColumnName> = relation
+            .schema
+            .columns()
+            .iter()
+            .enumerate()
+            .map(|(i, column)| ColumnName {
+                relation: qualifier.map(|q| q.to_owned()),
+                name: match columns.get(i) {
+                    Some(column) => column.value.clone(),
+                    None => column.name.clone(),
+                },
+            })
+            .collect();
+        let expr = fields
+            .iter()
+            .zip(&column_names)
+            .map(|(field, column)| {
+                let new_name = match &column.relation {
+                    Some(qualifier) => qualified_field_name(qualifier, &column.name),
+                    None => column.name.clone(),
+                };
+                if &new_name == field.name() {
+                    col(field.name())
+                } else {
+                    col(field.name()).alias(&new_name)
+                }
+            })
+            .collect();
+        let plan = LogicalPlanBuilder::from(&relation.plan)
+            .project(expr)?
+            .build()?;
+        let schema = QualifiedSchema::new(plan.schema().as_ref().clone(), column_names);
+        Ok(Relation { plan, schema })
+    }
+
+    /// Qualifies the columns of `relation` with the name of `alias`, renaming them to the
+    /// column names of `alias` if any. Without an alias, the columns are qualified with
+    /// `default_qualifier`.
+    fn alias_relation(
+        &self,
+        relation: Relation,
+        alias: &Option<TableAlias>,
+        default_qualifier: Option<&str>,
+    ) -> Result<Relation> {
+        match alias {
+            Some(alias) => self.rename_columns(
+                &relation,
+                &alias.name.value,
+                &alias.columns,
+                Some(&alias.name.value),
+            ),
+            None => Ok(Relation {
+                schema: relation.schema.with_relation(default_qualifier),
+                plan: relation.plan,
+            }),
+        }
+    }
+
+    /// Generate a logical plan from the body of an SQL query
+    fn set_expr_to_plan(
+        &self,
+        set_expr: &SetExpr,
+        ctes: &HashMap<String, Relation>,
+    ) -> Result<Relation> {
+        match set_expr {
+            SetExpr::Select(s) => self.select_to_plan(s.as_ref(), ctes),
+            SetExpr::Query(q) => self.query_to_plan_with_ctes(q.as_ref(), ctes),
+            SetExpr::SetOperation {
+                op,
+                left,
+                right,
+                all,
+            } => {
+                let left = self.set_expr_to_plan(left.as_ref(), ctes)?.plan;
+                let right = self.set_expr_to_plan(right.as_ref(), ctes)?.plan;
+                let plan = match (op, all) {
+                    (SetOperator::Union, true) => {
+                        LogicalPlanBuilder::from(&left).union(&right)?.build()
+                    }
+                    (SetOperator::Union, false) => {
+                        let union =
+                            LogicalPlanBuilder::from(&left).union(&right)?.build()?;
+                        distinct(&union)
+                    }
+                    (SetOperator::Intersect, false) => intersect(&left, &right),
+                    (SetOperator::Except, false) => except(&left, &right),
+                    (_, true) => Err(DataFusionError::NotImplemented(format!(
+                        "{} ALL is not implemented yet",
+                        op
+                    ))),
+                }?;
+                // the columns of a set operation do not belong to any relation
+                Ok(Relation::unqualified(plan))
+            }
             _ => Err(DataFusionError::NotImplemented(format!(
                 "Query {} not implemented yet",
-                query.body
+                set_expr
             ))),
-        }?;
-
-        let plan = self.order_by(&plan, &query.order_by)?;
-
-        self.limit(&plan, &query.limit)
+        }
     }
 
     /// Generate a logical plan from a CREATE EXTERNAL TABLE statement
@@ -156,6 +348,7 @@ impl<'a, S: SchemaProvider> SqlToRel<'a, S> {
         explain_plan: &ExplainPlan,
     ) -> Result<LogicalPlan> {
         let verbose = explain_plan.verbose;
+        let analyze = explain_plan.analyze;
         let plan = self.statement_to_plan(&explain_plan.statement)?;
 
         let stringified_plans = vec![StringifiedPlan::new(
@@ -168,6 +361,7 @@ impl<'a, S: SchemaProvider> SqlToRel<'a, S> {
 
         Ok(LogicalPlan::Explain {
             verbose,
+            analyze,
             plan,
             stringified_plans,
             schema,
@@ -198,7 +392,7 @@ impl<'a, S: SchemaProvider> SqlToRel<'a, S> {
             SQLDataType::Char(_) | SQLDataType::Varchar(_) | SQLDataType::Text => {
                 Ok(DataType::Utf8)
             }
-            SQLDataType::Decimal(_, _) => Ok(DataType::Float64),
+            SQLDataType::Decimal(precision, scale) => make_decimal_type(precision, scale),
             SQLDataType::Float(_) => Ok(DataType::Float32),
             SQLDataType::Real | SQLDataType::Double => Ok(DataType::Float64),
             SQLDataType::Boolean => Ok(DataType::Boolean),
@@ -212,24 +406,34 @@ impl<'a, S: SchemaProvider> SqlToRel<'a, S> {
         }
     }
 
-    fn plan_from_tables(&self, from: &Vec<TableWithJoins>) -> Result<Vec<LogicalPlan>> {
+    fn plan_from_tables(
+        &self,
+        from: &Vec<TableWithJoins>,
+        ctes: &HashMap<String, Relation>,
+    ) -> Result<Vec<Relation>> {
         match from.len() {
-            0 => Ok(vec![LogicalPlanBuilder::empty(true).build()?]),
+            0 => Ok(vec![Relation::unqualified(
+                LogicalPlanBuilder::empty(true).build()?,
+            )]),
             _ => from
                 .iter()
-                .map(|t| self.plan_table_with_joins(t))
+                .map(|t| self.plan_table_with_joins(t, ctes))
                 .collect::<Result<Vec<_>>>(),
         }
     }
 
-    fn plan_table_with_joins(&self, t: &TableWithJoins) -> Result<LogicalPlan> {
-        let left = self.create_relation(&t.relation)?;
+    fn plan_table_with_joins(
+        &self,
+        t: &TableWithJoins,
+        ctes: &HashMap<String, Relation>,
+    ) -> Result<Relation> {
+        let left = self.create_relation(&t.relation, ctes)?;
         match t.joins.len() {
             0 => Ok(left),
             n => {
-                let mut left = self.parse_relation_join(&left, &t.joins[0])?;
+                let mut left = self.parse_relation_join(&left, &t.joins[0], ctes)?;
                 for i in 1..n {
-                    left = self.parse_relation_join(&left, &t.joins[i])?;
+                    left = self.parse_relation_join(&left, &t.joins[i], ctes)?;
                 }
                 Ok(left)
             }
@@ -238,11 +442,12 @@ impl<'a, S: SchemaProvider> SqlToRel<'a, S> {
 
     fn parse_relation_join(
         &self,
-        left: &LogicalPlan,
+        left: &Relation,
         join: &Join,
-    ) -> Result<LogicalPlan> {
-        let right = self.create_relation(&join.relation)?;
-        match &join.join_operator {
+        ctes: &HashMap<String, Relation>,
+    ) -> Result<Relation> {
+        let right = self.create_relation(&join.relation, ctes)?;
+        let plan = match &join.join_operator {
             JoinOperator::LeftOuter(constraint) => {
                 self.parse_join(left, &right, constraint, JoinType::Left)
             }
@@ -252,101 +457,227 @@ impl<'a, S: SchemaProvider> SqlToRel<'a, S> {
             JoinOperator::Inner(constraint) => {
                 self.parse_join(left, &right, constraint, JoinType::Inner)
             }
+            JoinOperator::FullOuter(constraint) => {
+                self.parse_join(left, &right, constraint, JoinType::Full)
+            }
+            JoinOperator::CrossJoin => LogicalPlanBuilder::from(&left.plan)
+                .cross_join(&right.plan)?
+                .build(),
             other => Err(DataFusionError::NotImplemented(format!(
                 "Unsupported JOIN operator {:?}",
                 other
             ))),
-        }
+        }?;
+        Ok(Relation::derived(plan, &left.schema.join(&right.schema)))
     }
 
     fn parse_join(
         &self,
-        left: &LogicalPlan,
-        right: &LogicalPlan,
+        left: &Relation,
+        right: &Relation,
         constraint: &JoinConstraint,
         join_type: JoinType,
     ) -> Result<LogicalPlan> {
         match constraint {
             JoinConstraint::On(sql_expr) => {
-                let mut keys: Vec<(String, String)> = vec![];
-                let join_schema = create_join_schema(left.schema(), &right.schema())?;
+                let join_schema = left.schema.join(&right.schema);
+                check_unique_columns(join_schema.schema().fields())?;
 
                 // parse ON expression
-                let expr = self.sql_to_rex(sql_expr, &join_schema)?;
+                let expr = self.sql_expr_to_rex(sql_expr, &join_schema)?;
+
+                // split the condition into join keys, as pairs of (left, right) columns,
+                // and the predicates that are evaluated on the pairs of rows with equal keys
+                let mut keys = vec![];
+                let mut filters = vec![];
+                split_join_condition(
+                    &expr,
+                    left.plan.schema(),
+                    right.plan.schema(),
+                    &mut keys,
+                    &mut filters,
+                );
+                let filter = filters.into_iter().fold(None, |acc, expr| match acc {
+                    Some(acc) => Some(and(acc, expr)),
+                    None => Some(expr),
+                });
+
+                let builder = LogicalPlanBuilder::from(&left.plan);
+                if keys.is_empty() {
+                    return match (join_type, filter) {
+                        (JoinType::Inner, Some(filter)) => builder
+                            .cross_join(&right.plan)?
+                            .filter(filter)?
+                            .build(),
+                        (JoinType::Inner, None) => {
+                            builder.cross_join(&right.plan)?.build()
+                        }
+                        (join_type, _) => Err(DataFusionError::NotImplemented(format!(
+                            "{:?} JOIN without an equality condition between columns of both sides is not supported",
+                            join_type
+                        ))),
+                    };
+                }
 
-                // extract join keys
-                extract_join_keys(&expr, &mut keys)?;
                 let left_keys: Vec<&str> =
                     keys.iter().map(|pair| pair.0.as_str()).collect();
                 let right_keys: Vec<&str> =
                     keys.iter().map(|pair| pair.1.as_str()).collect();
 
                 // return the logical plan representing the join
-                LogicalPlanBuilder::from(&left)
-                    .join(&right, join_type, &left_keys, &right_keys)?
+                builder
+                    .join_with_filter(
+                        &right.plan,
+                        join_type,
+                        &left_keys,
+                        &right_keys,
+                        filter,
+                    )?
                     .build()
             }
-            JoinConstraint::Using(_) => {
-                // https://issues.apache.org/jira/browse/ARROW-10728
-                Err(DataFusionError::NotImplemented(
-                    "JOIN with USING is not supported (https://issues.apache.org/jira/browse/ARROW-10728)".to_string(),
-                ))
+            JoinConstraint::Using(idents) => {
+                let names: Vec<String> =
+                    idents.iter().map(|ident| ident.value.clone()).collect();
+                self.plan_join_using(left, right, &names, join_type)
             }
             JoinConstraint::Natural => {
-                // https://issues.apache.org/jira/browse/ARROW-10727
-                Err(DataFusionError::NotImplemented(
-                    "NATURAL JOIN is not supported (https://issues.apache.org/jira/browse/ARROW-10727)".to_string(),
-                ))
+                // the columns with the same name on both sides, in the order of the left
+                let right_names = right
+                    .schema
+                    .columns()
+                    .iter()
+                    .map(|c| c.name.as_str())
+                    .collect::<HashSet<_>>();
+                let names: Vec<String> = left
+                    .schema
+                    .columns()
+                    .iter()
+                    .map(|c| c.name.as_str())
+                    .filter(|name| right_names.contains(name))
+                    .map(|name| name.to_string())
+                    .collect();
+                if names.is_empty() {
+                    // without common columns, a natural join is a cross join
+                    LogicalPlanBuilder::from(&left.plan)
+                        .cross_join(&right.plan)?
+                        .build()
+                } else {
+                    self.plan_join_using(left, right, &names, join_type)
+                }
+            }
+        }
+    }
+
+    /// Plans a join on the columns `names`, which both sides must have. As the join keeps a
+    /// single copy of each of these columns, the fields of the right are renamed to the
+    /// fields of the left when their names differ.
+    fn plan_join_using(
+        &self,
+        left: &Relation,
+        right: &Relation,
+        names: &[String],
+        join_type: JoinType,
+    ) -> Result<LogicalPlan> {
+        let mut keys = Vec::with_capacity(names.len());
+        let mut renames = HashMap::new();
+        for name in names {
+            let left_key = left.schema.resolve(None, name)?;
+            let right_key = right.schema.resolve(None, name)?;
+            if left_key != right_key {
+                renames.insert(right_key, left_key.clone());
             }
+            keys.push(left_key);
         }
+
+        let right = if renames.is_empty() {
+            right.plan.clone()
+        } else {
+            let expr = right
+                .plan
+                .schema()
+                .fields()
+                .iter()
+                .map(|f| match renames.get(f.name()) {
+                    Some(name) => col(f.name()).alias(name),
+                    None => col(f.name()),
+                })
+                .collect();
+            LogicalPlanBuilder::from(&right.plan)
+                .project(expr)?
+                .build()?
+        };
+
+        let keys: Vec<&str> = keys.iter().map(|key| key.as_str()).collect();
+        LogicalPlanBuilder::from(&left.plan)
+            .join_using(&right, join_type, &keys)?
+            .build()
     }
 
-    fn create_relation(&self, relation: &TableFactor) -> Result<LogicalPlan> {
+    fn create_relation(
+        &self,
+        relation: &TableFactor,
+        ctes: &HashMap<String, Relation>,
+    ) -> Result<Relation> {
         match relation {
-            TableFactor::Table { name, .. } => {
+            TableFactor::Table { name, alias, .. } => {
                 let table_name = name.to_string();
-                match self.schema_provider.get_table_meta(&table_name) {
-                    Some(schema) => LogicalPlanBuilder::scan(
-                        "default",
-                        &table_name,
-                        schema.as_ref(),
-                        None,
-                    )?
-                    .build(),
-                    None => Err(DataFusionError::Plan(format!(
-                        "no schema found for table {}",
-                        name
-                    ))),
-                }
+                // common table expressions take precedence over the tables of the catalog
+                let relation = match ctes.get(&table_name) {
+                    Some(cte) => cte.clone(),
+                    None => match self.schema_provider.get_table_meta(&table_name) {
+                        Some(schema) => Relation::unqualified(
+                            LogicalPlanBuilder::scan(
+                                "default",
+                                &table_name,
+                                schema.as_ref(),
+                                None,
+                            )?
+                            .build()?,
+                        ),
+                        None => {
+                            return Err(DataFusionError::Plan(format!(
+                                "no schema found for table {}",
+                                name
+                            )))
+                        }
+                    },
+                };
+                self.alias_relation(relation, alias, Some(&table_name))
+            }
+            TableFactor::Derived {
+                subquery, alias, ..
+            } => {
+                let relation = self.query_to_plan_with_ctes(subquery, ctes)?;
+                self.alias_relation(relation, alias, None)
             }
-            TableFactor::Derived { subquery, .. } => self.query_to_plan(subquery),
             TableFactor::NestedJoin(table_with_joins) => {
-                self.plan_table_with_joins(table_with_joins)
+                self.plan_table_with_joins(table_with_joins, ctes)
             }
         }
     }
 
     /// Generate a logic plan from an SQL select
-    fn select_to_plan(&self, select: &Select) -> Result<LogicalPlan> {
-        if select.having.is_some() {
-            return Err(DataFusionError::NotImplemented(
-                "HAVING is not implemented yet".to_string(),
-            ));
+    fn select_to_plan(
+        &self,
+        select: &Select,
+        ctes: &HashMap<String, Relation>,
+    ) -> Result<Relation> {
+        let relations = self.plan_from_tables(&select.from, ctes)?;
+        let mut scope = relations[0].schema.clone();
+        for relation in &relations[1..] {
+            scope = scope.join(&relation.schema);
         }
+        let plans: Vec<LogicalPlan> = relations.into_iter().map(|r| r.plan).collect();
 
-        let plans = self.plan_from_tables(&select.from)?;
+        // the conjuncts of the WHERE clause that contain subqueries are planned once the
+        // relations of the FROM clause are joined
+        let (selection, subquery_predicates) =
+            split_subquery_predicates(&select.selection);
 
-        let plan = match &select.selection {
+        let plan = match &selection {
             Some(predicate_expr) => {
-                // build join schema
-                let mut fields = vec![];
-                for plan in &plans {
-                    fields.extend_from_slice(&plan.schema().fields());
-                }
-                check_unique_columns(&fields)?;
-                let join_schema = Schema::new(fields);
-
-                let filter_expr = self.sql_to_rex(predicate_expr, &join_schema)?;
+                check_unique_columns(scope.schema().fields())?;
+                let filter_expr = self.sql_expr_to_rex(predicate_expr, &scope)?;
 
                 // look for expressions of the form `<column> = <column>`
                 let mut possible_join_keys = vec![];
@@ -370,16 +701,15 @@ impl<'a, S: SchemaProvider> SqlToRel<'a, S> {
                             join_keys.push((r.as_str(), l.as_str()));
                         }
                     }
+                    let builder = LogicalPlanBuilder::from(&left);
                     if join_keys.len() == 0 {
-                        return Err(DataFusionError::NotImplemented(
-                            "Cartesian joins are not supported".to_string(),
-                        ));
+                        // no predicate relates both relations
+                        left = builder.cross_join(right)?.build()?;
                     } else {
                         let left_keys: Vec<_> =
                             join_keys.iter().map(|(l, _)| *l).collect();
                         let right_keys: Vec<_> =
                             join_keys.iter().map(|(_, r)| *r).collect();
-                        let builder = LogicalPlanBuilder::from(&left);
                         left = builder
                             .join(right, JoinType::Inner, &left_keys, &right_keys)?
                             .build()?;
@@ -396,22 +726,32 @@ impl<'a, S: SchemaProvider> SqlToRel<'a, S> {
                 }
             }
             None => {
-                if plans.len() == 1 {
-                    Ok(plans[0].clone())
-                } else {
-                    Err(DataFusionError::NotImplemented(
-                        "Cartesian joins are not supported".to_string(),
-                    ))
+                let mut left = plans[0].clone();
+                for right in &plans[1..] {
+                    left = LogicalPlanBuilder::from(&left).cross_join(right)?.build()?;
                 }
+                Ok(left)
             }
         };
-        let plan = plan?;
+        let input = Relation {
+            plan: plan?,
+            schema: scope,
+        };
+        let input = if subquery_predicates.is_empty() {
+            input
+        } else {
+            self.plan_subquery_predicates(&input, &subquery_predicates, ctes)?
+        };
+        let plan = &input.plan;
 
         let projection_expr: Vec<Expr> = select
             .projection
             .iter()
-            .map(|e| self.sql_select_to_rex(&e, &plan.schema()))
-            .collect::<Result<Vec<Expr>>>()?;
+            .map(|e| self.sql_select_to_rex(&e, &input.schema))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .flatten()
+            .collect();
 
         let aggr_expr: Vec<Expr> = projection_expr
             .iter()
@@ -419,13 +759,477 @@ impl<'a, S: SchemaProvider> SqlToRel<'a, S> {
             .map(|e| e.clone())
             .collect();
 
-        // apply projection or aggregate
-        let plan = if (select.group_by.len() > 0) | (aggr_expr.len() > 0) {
-            self.aggregate(&plan, projection_expr, &select.group_by, aggr_expr)?
+        // optionally plan the HAVING predicate against the aggregate input
+        let having_expr = match &select.having {
+            Some(having) => Some(self.sql_expr_to_rex(having, &input.schema)?),
+            None => None,
+        };
+        let having_aggr_expr = match &having_expr {
+            Some(e) => find_aggregate_exprs(&[e.clone()], &plan.schema())?,
+            None => vec![],
+        };
+
+        let window_expr = find_window_exprs(&projection_expr, &plan.schema())?;
+
+        // apply projection, window or aggregate
+        let plan = if (select.group_by.len() > 0)
+            | (aggr_expr.len() > 0)
+            | (having_aggr_expr.len() > 0)
+        {
+            if window_expr.len() > 0 {
+                return Err(DataFusionError::NotImplemented(
+                    "Window functions are not supported in aggregate queries".to_owned(),
+                ));
+            }
+            self.aggregate(
+                &input,
+                projection_expr,
+                &select.group_by,
+                aggr_expr,
+                having_expr,
+                having_aggr_expr,
+            )?
+        } else if having_expr.is_some() {
+            return Err(DataFusionError::Plan(
+                "HAVING clause requires a GROUP BY clause or an aggregate expression"
+                    .to_owned(),
+            ));
+        } else if window_expr.len() > 0 {
+            self.window(&plan, projection_expr, window_expr)?
         } else {
             self.project(&plan, projection_expr)?
         };
-        Ok(plan)
+        name_output_columns(plan, &input.schema)
+    }
+
+    /// Plans the conjuncts of a WHERE clause that contain subqueries as joins of `input`,
+    /// the relations of the query, with the subqueries:
+    ///
+    /// * `[NOT] EXISTS (subquery)` is a semi (anti) join with the subquery on the predicates
+    ///   that correlate it with the query.
+    /// * `expr [NOT] IN (subquery)` is a semi (anti) join on `expr` and the column of
+    ///   the subquery, as well as on the correlated predicates. When `expr` or the column
+    ///   is nullable, the anti join of an uncorrelated `NOT IN` is filtered by the
+    ///   number of rows and of non-null values of the subquery, as `NOT IN` is null when
+    ///   the subquery returns a null, or when `expr` is null and the subquery has rows.
+    /// * the other conjuncts are filters where each scalar subquery is replaced by its
+    ///   value. An uncorrelated scalar subquery is aggregated to its single value, which
+    ///   fails on more than one row, and cross joined with the query. A correlated one is
+    ///   grouped by its correlated columns and left joined with the query.
+    fn plan_subquery_predicates(
+        &self,
+        input: &Relation,
+        predicates: &[&SQLExpr],
+        ctes: &HashMap<String, Relation>,
+    ) -> Result<Relation> {
+        let columns = column_names(&input.plan);
+        let mut relation = input.clone();
+        let mut filters = vec![];
+        // the columns of each subquery are named after its position in the WHERE clause
+        let mut num_subqueries = 0;
+        for predicate in predicates {
+            if let Some((subquery, negated)) = exists_subquery(predicate) {
+                let name = subquery_name(num_subqueries);
+                num_subqueries += 1;
+                let plan =
+                    self.exists_to_plan(&relation, subquery, negated, &name, ctes)?;
+                relation = Relation::derived(plan, &relation.schema);
+                continue;
+            }
+            if let SQLExpr::InSubquery {
+                expr,
+                subquery,
+                negated,
+            } = predicate
+            {
+                let name = subquery_name(num_subqueries);
+                num_subqueries += 1;
+                let plan = self.in_subquery_to_plan(
+                    &relation, expr, subquery, *negated, &name, ctes,
+                )?;
+                relation = Relation::derived(plan, &relation.schema);
+                continue;
+            }
+            let predicate =
+                transform_sql_expr(predicate, &mut |expr: &SQLExpr| match expr {
+                    SQLExpr::Subquery(subquery) => {
+                        let name = subquery_name(num_subqueries);
+                        num_subqueries += 1;
+                        let plan = self
+                            .scalar_subquery_to_plan(&relation, subquery, &name, ctes)?;
+                        // the column of the subquery is not qualified
+                        relation = Relation::derived(plan, &relation.schema);
+                        Ok(Some(SQLExpr::Identifier(Ident::new(subquery_column(
+                            &name, 0,
+                        )))))
+                    }
+                    _ => Ok(None),
+                })?;
+            filters.push(self.sql_expr_to_rex(&predicate, &relation.schema)?);
+        }
+
+        let plan = relation.plan;
+        let mut builder = LogicalPlanBuilder::from(&plan);
+        if let Some(filter) = filters.into_iter().fold(None, |acc, filter| match acc {
+            Some(acc) => Some(and(acc, filter)),
+            None => Some(filter),
+        }) {
+            builder = builder.filter(filter)?;
+        }
+        // remove the columns of scalar subqueries
+        if plan.schema().fields().len() != columns.len() {
+            builder = builder.project(columns.iter().map(|name| col(name)).collect())?;
+        }
+        Ok(Relation::derived(builder.build()?, &input.schema))
+    }
+
+    /// Plans `[NOT] EXISTS (subquery)` as a semi (anti) join of `input` with the subquery
+    fn exists_to_plan(
+        &self,
+        input: &Relation,
+        query: &Query,
+        negated: bool,
+        name: &str,
+        ctes: &HashMap<String, Relation>,
+    ) -> Result<LogicalPlan> {
+        let plan = &input.plan;
+        let subquery =
+            self.plan_subquery(query, &input.schema, name, SubqueryKind::Exists, ctes)?;
+        if !subquery.keys.is_empty() {
+            let join_type = if negated {
+                JoinType::Anti
+            } else {
+                JoinType::Semi
+            };
+            return join_subquery(plan, &subquery, join_type);
+        }
+        if subquery.filter.is_some() {
+            return Err(DataFusionError::NotImplemented(
+                "EXISTS subqueries without an equality predicate between a column of the \
+                 subquery and a column of the outer query are not supported"
+                    .to_owned(),
+            ));
+        }
+
+        // an uncorrelated subquery holds for all rows or for none: count whether it
+        // returns any row
+        let count_plan = LogicalPlanBuilder::from(&subquery.plan)
+            .limit(1)?
+            .aggregate(vec![], vec![count(lit(1_u8))])?
+            .build()?;
+        let count_plan = rename_subquery_columns(&count_plan, name)?;
+        let op = if negated { Operator::Eq } else { Operator::Gt };
+        let columns = column_names(plan);
+        LogicalPlanBuilder::from(plan)
+            .cross_join(&count_plan)?
+            .filter(binary_expr(col(&subquery_column(name, 0)), op, lit(0_u64)))?
+            .project(columns.iter().map(|name| col(name)).collect())?
+            .build()
+    }
+
+    /// Plans `expr [NOT] IN (subquery)` as a semi (anti) join of `input` with the
+    /// subquery
+    fn in_subquery_to_plan(
+        &self,
+        input: &Relation,
+        expr: &SQLExpr,
+        query: &Query,
+        negated: bool,
+        name: &str,
+        ctes: &HashMap<String, Relation>,
+    ) -> Result<LogicalPlan> {
+        let column = match self.sql_expr_to_rex(expr, &input.schema)? {
+            Expr::Column(column) => column,
+            _ => {
+                return Err(DataFusionError::NotImplemented(format!(
+                "Only columns are supported on the left side of IN (subquery), found {}",
+                expr
+            )))
+            }
+        };
+        let mut subquery =
+            self.plan_subquery(query, &input.schema, name, SubqueryKind::In, ctes)?;
+        if subquery.num_columns != 1 {
+            return Err(DataFusionError::Plan(format!(
+                "A subquery of IN must return a single column, but it returns {}",
+                subquery.num_columns
+            )));
+        }
+        let value = subquery_column(name, 0);
+        subquery.keys.insert(0, (column.clone(), value.clone()));
+        if !negated {
+            return join_subquery(&input.plan, &subquery, JoinType::Semi);
+        }
+        let nullable = input.plan.schema().field_with_name(&column)?.is_nullable()
+            || subquery.plan.schema().field(0).is_nullable();
+        if !nullable {
+            return join_subquery(&input.plan, &subquery, JoinType::Anti);
+        }
+        if subquery.keys.len() > 1 || subquery.filter.is_some() {
+            return Err(DataFusionError::Plan(format!(
+                "NOT IN with a correlated subquery requires that neither {} nor the \
+                 column of the subquery is nullable",
+                expr
+            )));
+        }
+
+        // the anti join keeps the rows whose `expr` is null, and ignores the nulls of the
+        // subquery: keep them only when the subquery returns no row, and keep no row when
+        // it returns a null
+        let rows = subquery_column(name, 1);
+        let values = subquery_column(name, 2);
+        let count_plan = LogicalPlanBuilder::from(&subquery.plan)
+            .aggregate(
+                vec![],
+                vec![
+                    count(lit(1_u8)).alias(&rows),
+                    count(col(&value)).alias(&values),
+                ],
+            )?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(&input.plan)
+            .cross_join(&count_plan)?
+            .build()?;
+        let filter = or(
+            binary_expr(col(&rows), Operator::Eq, lit(0_u64)),
+            and(
+                Expr::IsNotNull(Box::new(col(&column))),
+                binary_expr(col(&values), Operator::Eq, col(&rows)),
+            ),
+        );
+        let columns = column_names(&input.plan);
+        LogicalPlanBuilder::from(&join_subquery(&plan, &subquery, JoinType::Anti)?)
+            .filter(filter)?
+            .project(columns.iter().map(|name| col(name)).collect())?
+            .build()
+    }
+
+    /// Joins `input` with a scalar subquery, whose value is the first column of the
+    /// subquery
+    fn scalar_subquery_to_plan(
+        &self,
+        input: &Relation,
+        query: &Query,
+        name: &str,
+        ctes: &HashMap<String, Relation>,
+    ) -> Result<LogicalPlan> {
+        let plan = &input.plan;
+        let subquery =
+            self.plan_subquery(query, &input.schema, name, SubqueryKind::Scalar, ctes)?;
+        if subquery.num_columns != 1 {
+            return Err(DataFusionError::Plan(format!(
+                "A scalar subquery must return a single column, but it returns {}",
+                subquery.num_columns
+            )));
+        }
+        if subquery.filter.is_some() {
+            return Err(DataFusionError::NotImplemented(
+                "Scalar subqueries correlated by predicates other than equalities between \
+                 columns are not supported"
+                    .to_owned(),
+            ));
+        }
+
+        let value = subquery_column(name, 0);
+        if subquery.keys.is_empty() {
+            // the value of a subquery without rows is null, and a subquery with more rows
+            // fails, unless it is an aggregate without GROUP BY, which has a single row
+            let subquery_plan = if is_single_row(&subquery.plan) {
+                subquery.plan
+            } else {
+                let single_value = Expr::AggregateFunction {
+                    fun: aggregates::AggregateFunction::SingleValue,
+                    distinct: false,
+                    args: vec![col(&value)],
+                };
+                LogicalPlanBuilder::from(&subquery.plan)
+                    .aggregate(vec![], vec![single_value.alias(&value)])?
+                    .build()?
+            };
+            return LogicalPlanBuilder::from(plan)
+                .cross_join(&subquery_plan)?
+                .build();
+        }
+
+        if !is_count(&subquery.plan, &value) && depends_on_count(&subquery.plan, &value) {
+            // the left join has no row for the groups without rows, whose value would
+            // have to be computed from a COUNT of 0
+            return Err(DataFusionError::NotImplemented(
+                "Correlated scalar subqueries whose value is computed from a COUNT, \
+                 other than the COUNT itself, are not supported"
+                    .to_owned(),
+            ));
+        }
+        let plan = join_subquery(plan, &subquery, JoinType::Left)?;
+        if !is_count(&subquery.plan, &value) {
+            return Ok(plan);
+        }
+        // the left join has no value for the groups without rows, whose COUNT is 0
+        let expr = plan
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| {
+                if field.name() == &value {
+                    Ok(when(Expr::IsNull(Box::new(col(&value))), lit(0_u64))
+                        .otherwise(col(&value))?
+                        .alias(&value))
+                } else {
+                    Ok(col(field.name()))
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        LogicalPlanBuilder::from(&plan).project(expr)?.build()
+    }
+
+    /// Plans a subquery of the WHERE clause of a query whose relations have the scope
+    /// `outer`. The columns of the subquery are renamed after `name`.
+    ///
+    /// The predicates of the subquery's WHERE clause that reference columns of the outer query
+    /// are removed from the subquery and returned as the keys and the filter to join it with
+    /// the outer query. The columns of the subquery they reference are appended to its SELECT
+    /// list, and scalar subqueries are grouped by them.
+    fn plan_subquery(
+        &self,
+        query: &Query,
+        outer: &QualifiedSchema,
+        name: &str,
+        kind: SubqueryKind,
+        ctes: &HashMap<String, Relation>,
+    ) -> Result<Subquery> {
+        let query_ctes;
+        let ctes = if query.ctes.is_empty() {
+            ctes
+        } else {
+            query_ctes = self.ctes_to_plans(query, ctes)?;
+            &query_ctes
+        };
+
+        let select = match &query.body {
+            SetExpr::Select(select) => select.as_ref(),
+            _ => return self.plan_uncorrelated_subquery(query, name, ctes),
+        };
+        let selection = match &select.selection {
+            Some(selection) => selection,
+            None => return self.plan_uncorrelated_subquery(query, name, ctes),
+        };
+
+        // the predicates that cannot be planned against the relations of the subquery are
+        // expected to reference the outer query
+        let relations = self.plan_from_tables(&select.from, ctes)?;
+        let mut inner = relations[0].schema.clone();
+        for relation in &relations[1..] {
+            inner = inner.join(&relation.schema);
+        }
+        let mut conjuncts = vec![];
+        split_conjuncts(selection, &mut conjuncts);
+        let (inner_predicates, correlated_predicates): (Vec<&SQLExpr>, Vec<&SQLExpr>) =
+            conjuncts.into_iter().partition(|predicate| {
+                contains_subquery(predicate)
+                    || self.sql_expr_to_rex(predicate, &inner).is_ok()
+            });
+        if correlated_predicates.is_empty() {
+            return self.plan_uncorrelated_subquery(query, name, ctes);
+        }
+        if query.limit.is_some() {
+            return Err(DataFusionError::NotImplemented(
+                "LIMIT is not supported in correlated subqueries".to_owned(),
+            ));
+        }
+
+        // the columns of the subquery referenced by the correlated predicates
+        let mut correlated_columns: Vec<(String, SQLExpr)> = vec![];
+        for predicate in &correlated_predicates {
+            transform_sql_expr(predicate, &mut |expr: &SQLExpr| {
+                if let Some(column) = sql_column(expr, &inner) {
+                    if correlated_columns.iter().all(|(c, _)| c != &column) {
+                        correlated_columns.push((column, expr.clone()));
+                    }
+                }
+                Ok(None)
+            })?;
+        }
+
+        let mut projection = match kind {
+            SubqueryKind::Exists => vec![],
+            SubqueryKind::In | SubqueryKind::Scalar => select.projection.clone(),
+        };
+        let mut group_by = select.group_by.clone();
+        for (_, expr) in &correlated_columns {
+            projection.push(SelectItem::UnnamedExpr(expr.clone()));
+        }
+        if kind == SubqueryKind::Scalar {
+            if !group_by.is_empty() {
+                return Err(DataFusionError::NotImplemented(
+                    "GROUP BY is not supported in correlated scalar subqueries"
+                        .to_owned(),
+                ));
+            }
+            group_by = correlated_columns.iter().map(|(_, e)| e.clone()).collect();
+        }
+        let select = Select {
+            projection,
+            group_by,
+            selection: sql_conjunction(inner_predicates.into_iter().cloned()),
+            ..select.clone()
+        };
+        let plan = self.select_to_plan(&select, ctes)?.plan;
+        let num_columns = plan.schema().fields().len() - correlated_columns.len();
+        let plan = rename_subquery_columns(&plan, name)?;
+
+        // plan the correlated predicates against the columns of both queries
+        let join_schema = outer.join(&QualifiedSchema::unqualified(plan.schema()));
+        let mut keys = vec![];
+        let mut filters = vec![];
+        for predicate in correlated_predicates {
+            let predicate = transform_sql_expr(predicate, &mut |expr: &SQLExpr| {
+                Ok(sql_column(expr, &inner).map(|column| {
+                    let index = correlated_columns
+                        .iter()
+                        .position(|(c, _)| c == &column)
+                        .unwrap();
+                    SQLExpr::Identifier(Ident::new(subquery_column(
+                        name,
+                        num_columns + index,
+                    )))
+                }))
+            })?;
+            let predicate = self.sql_expr_to_rex(&predicate, &join_schema)?;
+            split_join_condition(
+                &predicate,
+                outer.schema(),
+                &plan.schema(),
+                &mut keys,
+                &mut filters,
+            );
+        }
+        let filter = filters.into_iter().fold(None, |acc, filter| match acc {
+            Some(acc) => Some(and(acc, filter)),
+            None => Some(filter),
+        });
+
+        Ok(Subquery {
+            plan,
+            num_columns,
+            keys,
+            filter,
+        })
+    }
+
+    /// Plans a subquery that does not reference columns of the outer query
+    fn plan_uncorrelated_subquery(
+        &self,
+        query: &Query,
+        name: &str,
+        ctes: &HashMap<String, Relation>,
+    ) -> Result<Subquery> {
+        let plan = self.query_to_plan_with_ctes(query, ctes)?.plan;
+        Ok(Subquery {
+            num_columns: plan.schema().fields().len(),
+            plan: rename_subquery_columns(&plan, name)?,
+            keys: vec![],
+            filter: None,
+        })
     }
 
     /// Wrap a plan in a projection
@@ -433,17 +1237,50 @@ impl<'a, S: SchemaProvider> SqlToRel<'a, S> {
         LogicalPlanBuilder::from(input).project(expr)?.build()
     }
 
-    /// Wrap a plan in an aggregate
-    fn aggregate(
+    /// Wrap a plan in a window that computes the window functions of the projection,
+    /// followed by the projection, which references the results of the window
+    /// functions as columns.
+    fn window(
         &self,
         input: &LogicalPlan,
         projection_expr: Vec<Expr>,
+        window_expr: Vec<Expr>,
+    ) -> Result<LogicalPlan> {
+        let plan = LogicalPlanBuilder::from(input)
+            .window(window_expr.clone())?
+            .build()?;
+
+        // the wildcard stands for the columns of the input, not for the window columns
+        let input_columns = column_names(input);
+        let projection_expr = projection_expr
+            .iter()
+            .flat_map(|e| match e {
+                Expr::Wildcard => input_columns.iter().map(|name| col(name)).collect(),
+                _ => vec![e.clone()],
+            })
+            .map(|e| rebase_expr(&e, &window_expr, &input.schema()))
+            .collect::<Result<Vec<Expr>>>()?;
+
+        self.project(&plan, projection_expr)
+    }
+
+    /// Wrap a plan in an aggregate, optionally filtered by a HAVING predicate.
+    ///
+    /// Aggregates that are only referenced by the HAVING predicate are
+    /// computed by the aggregate as well and projected away afterwards.
+    fn aggregate(
+        &self,
+        relation: &Relation,
+        projection_expr: Vec<Expr>,
         group_by: &Vec<SQLExpr>,
         aggr_expr: Vec<Expr>,
+        having_expr: Option<Expr>,
+        having_aggr_expr: Vec<Expr>,
     ) -> Result<LogicalPlan> {
+        let input = &relation.plan;
         let group_expr: Vec<Expr> = group_by
             .iter()
-            .map(|e| self.sql_to_rex(&e, &input.schema()))
+            .map(|e| self.sql_expr_to_rex(&e, &relation.schema))
             .collect::<Result<Vec<Expr>>>()?;
 
         let group_by_count = group_expr.len();
@@ -455,10 +1292,47 @@ impl<'a, S: SchemaProvider> SqlToRel<'a, S> {
             ));
         }
 
+        // add the aggregates of the HAVING clause that are not already computed
+        let mut aggr_expr = aggr_expr;
+        let mut aggr_names = unaliased_names(&aggr_expr, input.schema())?;
+        for e in having_aggr_expr {
+            let name = e.name(input.schema())?;
+            if !aggr_names.contains(&name) {
+                aggr_names.push(name);
+                aggr_expr.push(e);
+            }
+        }
+
+        let mut base_expr = group_expr.clone();
+        base_expr.extend_from_slice(&aggr_expr);
+
         let plan = LogicalPlanBuilder::from(&input)
             .aggregate(group_expr, aggr_expr)?
             .build()?;
 
+        let plan = match having_expr {
+            Some(having_expr) => {
+                let having_expr = rebase_expr(&having_expr, &base_expr, input.schema())?;
+
+                // the predicate may only reference the grouping and aggregate values
+                let mut columns = HashSet::new();
+                expr_to_column_names(&having_expr, &mut columns)?;
+                for name in &columns {
+                    if plan.schema().field_with_name(name).is_err() {
+                        return Err(DataFusionError::Plan(format!(
+                            "HAVING clause references non-aggregate value '{}'",
+                            name
+                        )));
+                    }
+                }
+
+                LogicalPlanBuilder::from(&plan)
+                    .filter(having_expr)?
+                    .build()?
+            }
+            None => plan,
+        };
+
         // optionally wrap in projection to preserve final order of fields
         let expected_columns: Vec<String> = projection_expr
             .iter()
@@ -503,19 +1377,19 @@ impl<'a, S: SchemaProvider> SqlToRel<'a, S> {
     /// Wrap the logical in a sort
     fn order_by(
         &self,
-        plan: &LogicalPlan,
+        relation: &Relation,
         order_by: &Vec<OrderByExpr>,
     ) -> Result<LogicalPlan> {
+        let plan = &relation.plan;
         if order_by.len() == 0 {
             return Ok(plan.clone());
         }
 
-        let input_schema = plan.schema();
         let order_by_rex: Result<Vec<Expr>> = order_by
             .iter()
             .map(|e| {
                 Ok(Expr::Sort {
-                    expr: Box::new(self.sql_to_rex(&e.expr, &input_schema).unwrap()),
+                    expr: Box::new(self.sql_expr_to_rex(&e.expr, &relation.schema)?),
                     // by default asc
                     asc: e.asc.unwrap_or(true),
                     // by default nulls first to be consistent with spark
@@ -527,43 +1401,138 @@ impl<'a, S: SchemaProvider> SqlToRel<'a, S> {
         LogicalPlanBuilder::from(&plan).sort(order_by_rex?)?.build()
     }
 
-    /// Generate a relational expression from a select SQL expression
-    fn sql_select_to_rex(&self, sql: &SelectItem, schema: &Schema) -> Result<Expr> {
-        match sql {
-            SelectItem::UnnamedExpr(expr) => self.sql_to_rex(expr, schema),
-            SelectItem::ExprWithAlias { expr, alias } => Ok(Alias(
-                Box::new(self.sql_to_rex(&expr, schema)?),
-                alias.value.clone(),
-            )),
-            SelectItem::Wildcard => Ok(Expr::Wildcard),
-            SelectItem::QualifiedWildcard(_) => Err(DataFusionError::NotImplemented(
-                "Qualified wildcards are not supported".to_string(),
-            )),
+    /// Generate a window function expression from a function call with an OVER clause
+    fn sql_window_function_to_rex(
+        &self,
+        function: &Function,
+        window: &WindowSpec,
+        schema: &QualifiedSchema,
+    ) -> Result<Expr> {
+        let fun = window_functions::WindowFunction::from_str(&function.name.to_string())?;
+        if function.distinct {
+            return Err(DataFusionError::NotImplemented(format!(
+                "DISTINCT is not supported in the window function {}",
+                fun
+            )));
         }
-    }
-
-    /// Generate a relational expression from a SQL expression
-    pub fn sql_to_rex(&self, sql: &SQLExpr, schema: &Schema) -> Result<Expr> {
-        match sql {
-            SQLExpr::Value(Value::Number(n)) => match n.parse::<i64>() {
-                Ok(n) => Ok(lit(n)),
-                Err(_) => Ok(lit(n.parse::<f64>().unwrap())),
-            },
-            SQLExpr::Value(Value::SingleQuotedString(ref s)) => Ok(lit(s.clone())),
 
-            SQLExpr::Identifier(ref id) => {
-                if &id.value[0..1] == "@" {
-                    let var_names = vec![id.value.clone()];
-                    Ok(Expr::ScalarVariable(var_names))
-                } else {
-                    match schema.field_with_name(&id.value) {
-                        Ok(field) => Ok(Expr::Column(field.name().clone())),
-                        Err(_) => Err(DataFusionError::Plan(format!(
-                            "Invalid identifier '{}' for schema {}",
-                            id,
-                            schema.to_string()
-                        ))),
-                    }
+        let is_count = fun
+            == window_functions::WindowFunction::AggregateFunction(
+                aggregates::AggregateFunction::Count,
+            );
+        let args = function
+            .args
+            .iter()
+            .map(|a| match a {
+                SQLExpr::Value(Value::Number(_)) | SQLExpr::Wildcard if is_count => {
+                    Ok(lit(1_u8))
+                }
+                _ => self.sql_expr_to_rex(a, schema),
+            })
+            .collect::<Result<Vec<Expr>>>()?;
+        let partition_by = window
+            .partition_by
+            .iter()
+            .map(|e| self.sql_expr_to_rex(e, schema))
+            .collect::<Result<Vec<Expr>>>()?;
+        let order_by = window
+            .order_by
+            .iter()
+            .map(|e| {
+                Ok(Expr::Sort {
+                    expr: Box::new(self.sql_expr_to_rex(&e.expr, schema)?),
+                    asc: e.asc.unwrap_or(true),
+                    nulls_first: e.nulls_first.unwrap_or(true),
+                })
+            })
+            .collect::<Result<Vec<Expr>>>()?;
+        let window_frame = match &window.window_frame {
+            Some(window_frame) => Some(WindowFrame::try_from(window_frame)?),
+            None => None,
+        };
+
+        Ok(Expr::WindowFunction {
+            fun,
+            args,
+            partition_by,
+            order_by,
+            window_frame,
+        })
+    }
+
+    /// Generate relational expressions from a select SQL expression
+    fn sql_select_to_rex(
+        &self,
+        sql: &SelectItem,
+        schema: &QualifiedSchema,
+    ) -> Result<Vec<Expr>> {
+        match sql {
+            SelectItem::UnnamedExpr(expr) => {
+                Ok(vec![self.sql_expr_to_rex(expr, schema)?])
+            }
+            SelectItem::ExprWithAlias { expr, alias } => Ok(vec![Alias(
+                Box::new(self.sql_expr_to_rex(&expr, schema)?),
+                alias.value.clone(),
+            )]),
+            SelectItem::Wildcard => Ok(vec![Expr::Wildcard]),
+            SelectItem::QualifiedWildcard(name) => {
+                let relation = name.to_string();
+                let expr = schema
+                    .relation_fields(&relation)
+                    .into_iter()
+                    .map(col)
+                    .collect::<Vec<_>>();
+                if expr.is_empty() {
+                    Err(DataFusionError::Plan(format!(
+                        "Invalid qualified wildcard '{}.*': there is no relation '{}' in scope",
+                        relation, relation
+                    )))
+                } else {
+                    Ok(expr)
+                }
+            }
+        }
+    }
+
+    /// Generate a relational expression from a SQL expression
+    pub fn sql_to_rex(&self, sql: &SQLExpr, schema: &Schema) -> Result<Expr> {
+        self.sql_expr_to_rex(sql, &QualifiedSchema::unqualified(schema))
+    }
+
+    /// Generate a relational expression from a SQL expression, whose columns are resolved
+    /// against the scope `schema`
+    fn sql_expr_to_rex(&self, sql: &SQLExpr, schema: &QualifiedSchema) -> Result<Expr> {
+        match sql {
+            SQLExpr::Value(Value::Number(n)) => match n.parse::<i64>() {
+                Ok(n) => Ok(lit(n)),
+                Err(_) => Ok(lit(n.parse::<f64>().unwrap())),
+            },
+            SQLExpr::Value(Value::SingleQuotedString(ref s)) => Ok(lit(s.clone())),
+
+            SQLExpr::Value(Value::Interval {
+                ref value,
+                ref leading_field,
+                ref last_field,
+                ..
+            }) => parse_interval(value, leading_field, last_field),
+
+            SQLExpr::Extract {
+                ref field,
+                ref expr,
+            } => Ok(Expr::ScalarFunction {
+                fun: functions::BuiltinScalarFunction::DatePart,
+                args: vec![
+                    lit(field.to_string().to_lowercase()),
+                    self.sql_expr_to_rex(expr, schema)?,
+                ],
+            }),
+
+            SQLExpr::Identifier(ref id) => {
+                if &id.value[0..1] == "@" {
+                    let var_names = vec![id.value.clone()];
+                    Ok(Expr::ScalarVariable(var_names))
+                } else {
+                    Ok(Expr::Column(schema.resolve(None, &id.value)?))
                 }
             }
 
@@ -575,11 +1544,16 @@ impl<'a, S: SchemaProvider> SqlToRel<'a, S> {
                 }
                 if &var_names[0][0..1] == "@" {
                     Ok(Expr::ScalarVariable(var_names))
+                } else if var_names.len() == 2 {
+                    // a column qualified by the name of a relation
+                    Ok(Expr::Column(
+                        schema.resolve(Some(&var_names[0]), &var_names[1])?,
+                    ))
                 } else {
                     Err(DataFusionError::Plan(format!(
                         "Invalid compound identifier '{:?}' for schema {}",
                         var_names,
-                        schema.to_string()
+                        schema.schema().to_string()
                     )))
                 }
             }
@@ -593,20 +1567,20 @@ impl<'a, S: SchemaProvider> SqlToRel<'a, S> {
                 else_result,
             } => {
                 let expr = if let Some(e) = operand {
-                    Some(Box::new(self.sql_to_rex(e, schema)?))
+                    Some(Box::new(self.sql_expr_to_rex(e, schema)?))
                 } else {
                     None
                 };
                 let when_expr = conditions
                     .iter()
-                    .map(|e| self.sql_to_rex(e, schema))
+                    .map(|e| self.sql_expr_to_rex(e, schema))
                     .collect::<Result<Vec<_>>>()?;
                 let then_expr = results
                     .iter()
-                    .map(|e| self.sql_to_rex(e, schema))
+                    .map(|e| self.sql_expr_to_rex(e, schema))
                     .collect::<Result<Vec<_>>>()?;
                 let else_expr = if let Some(e) = else_result {
-                    Some(Box::new(self.sql_to_rex(e, schema)?))
+                    Some(Box::new(self.sql_expr_to_rex(e, schema)?))
                 } else {
                     None
                 };
@@ -626,21 +1600,46 @@ impl<'a, S: SchemaProvider> SqlToRel<'a, S> {
                 ref expr,
                 ref data_type,
             } => Ok(Expr::Cast {
-                expr: Box::new(self.sql_to_rex(&expr, schema)?),
+                expr: Box::new(self.sql_expr_to_rex(&expr, schema)?),
                 data_type: convert_data_type(data_type)?,
             }),
 
             SQLExpr::IsNull(ref expr) => {
-                Ok(Expr::IsNull(Box::new(self.sql_to_rex(expr, schema)?)))
+                Ok(Expr::IsNull(Box::new(self.sql_expr_to_rex(expr, schema)?)))
             }
 
-            SQLExpr::IsNotNull(ref expr) => {
-                Ok(Expr::IsNotNull(Box::new(self.sql_to_rex(expr, schema)?)))
-            }
+            SQLExpr::IsNotNull(ref expr) => Ok(Expr::IsNotNull(Box::new(
+                self.sql_expr_to_rex(expr, schema)?,
+            ))),
+
+            SQLExpr::InList {
+                ref expr,
+                ref list,
+                negated,
+            } => Ok(Expr::InList {
+                expr: Box::new(self.sql_expr_to_rex(expr, schema)?),
+                list: list
+                    .iter()
+                    .map(|e| self.sql_expr_to_rex(e, schema))
+                    .collect::<Result<Vec<_>>>()?,
+                negated,
+            }),
+
+            SQLExpr::Between {
+                ref expr,
+                negated,
+                ref low,
+                ref high,
+            } => Ok(Expr::Between {
+                expr: Box::new(self.sql_expr_to_rex(expr, schema)?),
+                negated,
+                low: Box::new(self.sql_expr_to_rex(low, schema)?),
+                high: Box::new(self.sql_expr_to_rex(high, schema)?),
+            }),
 
             SQLExpr::UnaryOp { ref op, ref expr } => match *op {
                 UnaryOperator::Not => {
-                    Ok(Expr::Not(Box::new(self.sql_to_rex(expr, schema)?)))
+                    Ok(Expr::Not(Box::new(self.sql_expr_to_rex(expr, schema)?)))
                 }
                 _ => Err(DataFusionError::Internal(format!(
                     "SQL binary operator cannot be interpreted as a unary operator"
@@ -675,21 +1674,26 @@ impl<'a, S: SchemaProvider> SqlToRel<'a, S> {
                 }?;
 
                 Ok(Expr::BinaryExpr {
-                    left: Box::new(self.sql_to_rex(&left, &schema)?),
+                    left: Box::new(self.sql_expr_to_rex(&left, &schema)?),
                     op: operator,
-                    right: Box::new(self.sql_to_rex(&right, &schema)?),
+                    right: Box::new(self.sql_expr_to_rex(&right, &schema)?),
                 })
             }
 
             SQLExpr::Function(function) => {
                 let name: String = function.name.to_string();
 
+                // window functions are evaluated over the window of their OVER clause
+                if let Some(window) = &function.over {
+                    return self.sql_window_function_to_rex(function, window, schema);
+                }
+
                 // first, scalar built-in
                 if let Ok(fun) = functions::BuiltinScalarFunction::from_str(&name) {
                     let args = function
                         .args
                         .iter()
-                        .map(|a| self.sql_to_rex(a, schema))
+                        .map(|a| self.sql_expr_to_rex(a, schema))
                         .collect::<Result<Vec<Expr>>>()?;
 
                     return Ok(Expr::ScalarFunction { fun, args });
@@ -704,14 +1708,14 @@ impl<'a, S: SchemaProvider> SqlToRel<'a, S> {
                             .map(|a| match a {
                                 SQLExpr::Value(Value::Number(_)) => Ok(lit(1_u8)),
                                 SQLExpr::Wildcard => Ok(lit(1_u8)),
-                                _ => self.sql_to_rex(a, schema),
+                                _ => self.sql_expr_to_rex(a, schema),
                             })
                             .collect::<Result<Vec<Expr>>>()?
                     } else {
                         function
                             .args
                             .iter()
-                            .map(|a| self.sql_to_rex(a, schema))
+                            .map(|a| self.sql_expr_to_rex(a, schema))
                             .collect::<Result<Vec<Expr>>>()?
                     };
 
@@ -722,13 +1726,21 @@ impl<'a, S: SchemaProvider> SqlToRel<'a, S> {
                     });
                 };
 
+                if let Ok(fun) = window_functions::BuiltInWindowFunction::from_str(&name)
+                {
+                    return Err(DataFusionError::Plan(format!(
+                        "The window function {} requires an OVER clause",
+                        fun
+                    )));
+                }
+
                 // finally, user-defined functions (UDF) and UDAF
                 match self.schema_provider.get_function_meta(&name) {
                     Some(fm) => {
                         let args = function
                             .args
                             .iter()
-                            .map(|a| self.sql_to_rex(a, schema))
+                            .map(|a| self.sql_expr_to_rex(a, schema))
                             .collect::<Result<Vec<Expr>>>()?;
 
                         Ok(Expr::ScalarUDF { fun: fm, args })
@@ -738,7 +1750,7 @@ impl<'a, S: SchemaProvider> SqlToRel<'a, S> {
                             let args = function
                                 .args
                                 .iter()
-                                .map(|a| self.sql_to_rex(a, schema))
+                                .map(|a| self.sql_expr_to_rex(a, schema))
                                 .collect::<Result<Vec<Expr>>>()?;
 
                             Ok(Expr::AggregateUDF { fun: fm, args })
@@ -751,7 +1763,15 @@ impl<'a, S: SchemaProvider> SqlToRel<'a, S> {
                 }
             }
 
-            SQLExpr::Nested(e) => self.sql_to_rex(&e, &schema),
+            SQLExpr::Nested(e) => self.sql_expr_to_rex(&e, &schema),
+
+            SQLExpr::Subquery(_) | SQLExpr::InSubquery { .. } | SQLExpr::Exists(_) => {
+                Err(DataFusionError::NotImplemented(
+                    "Subqueries are only supported in WHERE clauses, and EXISTS and IN \
+                     subqueries only as conjuncts of them"
+                        .to_owned(),
+                ))
+            }
 
             _ => Err(DataFusionError::NotImplemented(format!(
                 "Unsupported ast node {:?} in sqltorel",
@@ -761,17 +1781,9 @@ impl<'a, S: SchemaProvider> SqlToRel<'a, S> {
     }
 }
 
-fn create_join_schema(left: &SchemaRef, right: &SchemaRef) -> Result<Schema> {
-    let mut fields = vec![];
-    fields.extend_from_slice(&left.fields());
-    fields.extend_from_slice(&right.fields());
-    check_unique_columns(&fields)?;
-    Ok(Schema::new(fields))
-}
-
 fn check_unique_columns(fields: &[Field]) -> Result<()> {
-    // Until https://issues.apache.org/jira/browse/ARROW-10732 is implemented, we need
-    // to ensure that schemas have unique field names
+    // Schemas must have unique field names. The fields of aliased relations are qualified
+    // by their alias, which allows joining a relation with itself.
     let unique_field_names = fields.iter().map(|f| f.name()).collect::<HashSet<_>>();
     if unique_field_names.len() == fields.len() {
         Ok(())
@@ -782,6 +1794,119 @@ fn check_unique_columns(fields: &[Field]) -> Result<()> {
     }
 }
 
+/// Returns the relation of `plan`, the SELECT list of a query on the relations of
+/// `scope`.
+///
+/// The columns of the relations that are selected as is keep their name and relation, so
+/// that e.g. `ORDER BY t.a` references the column `a` of the SELECT list. They are output
+/// under their own name instead of the name of their field (e.g. `a` rather than `t.a`
+/// for an aliased relation `t`), unless another output column has the same name.
+fn name_output_columns(plan: LogicalPlan, scope: &QualifiedSchema) -> Result<Relation> {
+    let columns: Vec<ColumnName> = match &plan {
+        LogicalPlan::Projection { expr, schema, .. } => expr
+            .iter()
+            .zip(schema.fields())
+            .map(|(e, field)| match e {
+                Expr::Column(name) => scope
+                    .column(name)
+                    .cloned()
+                    .unwrap_or_else(|| ColumnName::unqualified(field.name())),
+                _ => ColumnName::unqualified(field.name()),
+            })
+            .collect(),
+        _ => scope.qualify(plan.schema()).columns().to_vec(),
+    };
+
+    let mut counts = HashMap::new();
+    for column in &columns {
+        *counts.entry(column.name.as_str()).or_insert(0) += 1;
+    }
+    let fields = plan.schema().fields().clone();
+    let renames: Vec<Option<&str>> = fields
+        .iter()
+        .zip(&columns)
+        .map(|(field, column)| {
+            if &column.name != field.name() && counts[column.name.as_str()] == 1 {
+                Some(column.name.as_str())
+            } else {
+                None
+            }
+        })
+        .collect();
+    if renames.iter().all(|rename| rename.is_none()) {
+        let schema = QualifiedSchema::new(plan.schema().as_ref().clone(), columns);
+        return Ok(Relation { plan, schema });
+    }
+
+    let plan = match &plan {
+        LogicalPlan::Projection { expr, input, .. } => {
+            let expr = expr
+                .iter()
+                .zip(&renames)
+                .map(|(e, rename)| match rename {
+                    Some(name) => e.alias(name),
+                    None => e.clone(),
+                })
+                .collect();
+            LogicalPlanBuilder::from(input).project(expr)?.build()?
+        }
+        _ => {
+            let expr = fields
+                .iter()
+                .zip(&renames)
+                .map(|(field, rename)| match rename {
+                    Some(name) => col(field.name()).alias(name),
+                    None => col(field.name()),
+                })
+                .collect();
+            LogicalPlanBuilder::from(&plan).project(expr)?.build()?
+        }
+    };
+    let schema = QualifiedSchema::new(plan.schema().as_ref().clone(), columns);
+    Ok(Relation { plan, schema })
+}
+
+/// Removes duplicate rows by grouping on all the columns of `plan`
+fn distinct(plan: &LogicalPlan) -> Result<LogicalPlan> {
+    let group_expr = column_names(plan).iter().map(|name| col(name)).collect();
+    LogicalPlanBuilder::from(plan)
+        .aggregate(group_expr, vec![])?
+        .build()
+}
+
+/// Plans an INTERSECT as a semi join of the distinct rows of the left input with the right
+/// input on all columns
+fn intersect(left: &LogicalPlan, right: &LogicalPlan) -> Result<LogicalPlan> {
+    let (left, right, _) = coerce_set_operation_inputs(left, right)?;
+    let names = column_names(&left);
+    let keys = names.iter().map(|name| name.as_str()).collect::<Vec<_>>();
+
+    LogicalPlanBuilder::from(&distinct(&left)?)
+        .join(&right, JoinType::Semi, &keys, &keys)?
+        .build()
+}
+
+/// Plans an EXCEPT as an anti join of the distinct rows of the left input with the right
+/// input on all columns
+fn except(left: &LogicalPlan, right: &LogicalPlan) -> Result<LogicalPlan> {
+    let (left, right, _) = coerce_set_operation_inputs(left, right)?;
+    let names = column_names(&left);
+    let keys = names.iter().map(|name| name.as_str()).collect::<Vec<_>>();
+
+    LogicalPlanBuilder::from(&distinct(&left)?)
+        .join(&right, JoinType::Anti, &keys, &keys)?
+        .build()
+}
+
+/// Returns the names of the columns of `plan`
+fn column_names(plan: &LogicalPlan) -> Vec<String> {
+    plan.schema()
+        .fields()
+        .iter()
+        .map(|f| f.name().clone())
+        .collect()
+}
+
 /// Remove join expressions from a filter expression
 fn remove_join_expressions(
     expr: &Expr,
@@ -815,40 +1940,376 @@ fn remove_join_expressions(
     }
 }
 
-/// Parse equijoin ON condition which could be a single Eq or multiple conjunctive Eqs
+/// Splits a JOIN ON condition into the pairs of (left, right) columns that must be equal and
+/// the other predicates of the condition.
 ///
 /// Examples
 ///
-/// foo = bar
-/// foo = bar AND bar = baz AND ...
+/// foo = bar => keys: (foo, bar)
+/// foo = bar AND baz > qux => keys: (foo, bar), filters: baz > qux
 ///
-fn extract_join_keys(expr: &Expr, accum: &mut Vec<(String, String)>) -> Result<()> {
+/// A `column = column` predicate is only a key when it compares a column of each side and
+/// is part of the top-level conjunction of the condition.
+fn split_join_condition(
+    expr: &Expr,
+    left: &Schema,
+    right: &Schema,
+    keys: &mut Vec<(String, String)>,
+    filters: &mut Vec<Expr>,
+) {
     match expr {
-        Expr::BinaryExpr { left, op, right } => match op {
-            Operator::Eq => match (left.as_ref(), right.as_ref()) {
-                (Expr::Column(l), Expr::Column(r)) => {
-                    accum.push((l.to_owned(), r.to_owned()));
-                    Ok(())
+        Expr::BinaryExpr {
+            left: l,
+            op: Operator::And,
+            right: r,
+        } => {
+            split_join_condition(l, left, right, keys, filters);
+            split_join_condition(r, left, right, keys, filters);
+        }
+        Expr::BinaryExpr {
+            left: l,
+            op: Operator::Eq,
+            right: r,
+        } => match (l.as_ref(), r.as_ref()) {
+            (Expr::Column(a), Expr::Column(b))
+                if left.field_with_name(a).is_ok()
+                    && right.field_with_name(b).is_ok() =>
+            {
+                keys.push((a.clone(), b.clone()))
+            }
+            (Expr::Column(a), Expr::Column(b))
+                if left.field_with_name(b).is_ok()
+                    && right.field_with_name(a).is_ok() =>
+            {
+                keys.push((b.clone(), a.clone()))
+            }
+            _ => filters.push(expr.clone()),
+        },
+        _ => filters.push(expr.clone()),
+    }
+}
+
+/// How the query that contains a subquery uses its result
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SubqueryKind {
+    /// `EXISTS (subquery)`, which only depends on whether the subquery returns rows
+    Exists,
+    /// `expr IN (subquery)`, where the subquery returns a single column
+    In,
+    /// a subquery used as a value, which returns a single row and column
+    Scalar,
+}
+
+/// A subquery planned independently of the query that contains it
+struct Subquery {
+    /// the plan of the subquery, whose columns are named after the subquery
+    plan: LogicalPlan,
+    /// the number of columns of the SELECT list of the subquery, which are followed in
+    /// `plan` by the columns that correlate it with the outer query
+    num_columns: usize,
+    /// pairs of (outer query column, subquery column) that must be equal
+    keys: Vec<(String, String)>,
+    /// the other predicates that correlate the subquery with the outer query
+    filter: Option<Expr>,
+}
+
+/// Returns the name of the `index`-th subquery of a WHERE clause
+fn subquery_name(index: usize) -> String {
+    format!("__sq{}", index)
+}
+
+/// Returns the name of the `index`-th column of the subquery `name`. It is not qualified, so
+/// that it does not collide with the unqualified columns of the outer query.
+fn subquery_column(name: &str, index: usize) -> String {
+    format!("{}_{}", name, index)
+}
+
+/// Renames the columns of the plan of the subquery `name` after their position
+fn rename_subquery_columns(plan: &LogicalPlan, name: &str) -> Result<LogicalPlan> {
+    let expr = plan
+        .schema()
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(i, field)| col(field.name()).alias(&subquery_column(name, i)))
+        .collect();
+    LogicalPlanBuilder::from(plan).project(expr)?.build()
+}
+
+/// Returns whether `plan` is an aggregate without GROUP BY, which returns a single row
+fn is_single_row(plan: &LogicalPlan) -> bool {
+    match plan {
+        LogicalPlan::Projection { input, .. } => is_single_row(input),
+        LogicalPlan::Aggregate { group_expr, .. } => group_expr.is_empty(),
+        _ => false,
+    }
+}
+
+/// Returns whether the column `name` of `plan` is the value of a COUNT aggregate
+fn is_count(plan: &LogicalPlan, name: &str) -> bool {
+    let index = match plan.schema().index_of(name) {
+        Ok(index) => index,
+        Err(_) => return false,
+    };
+    let unaliased = |expr: &Expr| match expr {
+        Expr::Alias(expr, _) => expr.as_ref().clone(),
+        _ => expr.clone(),
+    };
+    match plan {
+        LogicalPlan::Projection { expr, input, .. } => match unaliased(&expr[index]) {
+            Expr::Column(column) => is_count(input, &column),
+            _ => false,
+        },
+        LogicalPlan::Aggregate {
+            group_expr,
+            aggr_expr,
+            ..
+        } if index >= group_expr.len() => {
+            match unaliased(&aggr_expr[index - group_expr.len()]) {
+                Expr::AggregateFunction { fun, .. } => {
+                    fun == aggregates::AggregateFunction::Count
                 }
-                other => Err(DataFusionError::SQL(ParserError(format!(
-                    "Unsupported expression '{:?}' in JOIN condition",
-                    other
-                )))),
-            },
-            Operator::And => {
-                extract_join_keys(left, accum)?;
-                extract_join_keys(right, accum)
+                _ => false,
             }
-            other => Err(DataFusionError::SQL(ParserError(format!(
-                "Unsupported expression '{:?}' in JOIN condition",
-                other
-            )))),
+        }
+        LogicalPlan::Sort { input, .. } => is_count(input, name),
+        _ => false,
+    }
+}
+
+/// Returns whether the column `name` of `plan` is computed from the value of a COUNT
+/// aggregate, through expressions or through the rows kept by filters
+fn depends_on_count(plan: &LogicalPlan, name: &str) -> bool {
+    let index = match plan.schema().index_of(name) {
+        Ok(index) => index,
+        Err(_) => return false,
+    };
+    match plan {
+        LogicalPlan::Projection { expr, input, .. } => {
+            let mut columns = HashSet::new();
+            match expr_to_column_names(&expr[index], &mut columns) {
+                Ok(()) => columns.iter().any(|column| depends_on_count(input, column)),
+                Err(_) => true,
+            }
+        }
+        LogicalPlan::Aggregate { group_expr, .. } if index >= group_expr.len() => {
+            is_count(plan, name)
+        }
+        LogicalPlan::Filter { input, .. }
+        | LogicalPlan::Limit { input, .. }
+        | LogicalPlan::Sort { input, .. } => depends_on_count(input, name),
+        _ => false,
+    }
+}
+
+/// Joins `plan` with a decorrelated subquery on its keys and filter
+fn join_subquery(
+    plan: &LogicalPlan,
+    subquery: &Subquery,
+    join_type: JoinType,
+) -> Result<LogicalPlan> {
+    let left_keys: Vec<_> = subquery.keys.iter().map(|(l, _)| l.as_str()).collect();
+    let right_keys: Vec<_> = subquery.keys.iter().map(|(_, r)| r.as_str()).collect();
+    LogicalPlanBuilder::from(plan)
+        .join_with_filter(
+            &subquery.plan,
+            join_type,
+            &left_keys,
+            &right_keys,
+            subquery.filter.clone(),
+        )?
+        .build()
+}
+
+/// Returns the subquery of `EXISTS (subquery)` or `NOT EXISTS (subquery)`, and whether it
+/// is negated
+fn exists_subquery(expr: &SQLExpr) -> Option<(&Query, bool)> {
+    match expr {
+        SQLExpr::Exists(subquery) => Some((subquery.as_ref(), false)),
+        SQLExpr::UnaryOp {
+            op: UnaryOperator::Not,
+            expr,
+        } => match expr.as_ref() {
+            SQLExpr::Exists(subquery) => Some((subquery.as_ref(), true)),
+            _ => None,
         },
-        other => Err(DataFusionError::SQL(ParserError(format!(
-            "Unsupported expression '{:?}' in JOIN condition",
-            other
-        )))),
+        _ => None,
+    }
+}
+
+/// Splits the WHERE clause `selection` into the predicate of the conjuncts without subqueries
+/// and the conjuncts with subqueries
+fn split_subquery_predicates(
+    selection: &Option<SQLExpr>,
+) -> (Option<SQLExpr>, Vec<&SQLExpr>) {
+    let predicate = match selection {
+        Some(predicate) => predicate,
+        None => return (None, vec![]),
+    };
+    let mut conjuncts = vec![];
+    split_conjuncts(predicate, &mut conjuncts);
+    if !conjuncts.iter().any(|conjunct| contains_subquery(conjunct)) {
+        return (selection.clone(), vec![]);
+    }
+    let (subquery_predicates, predicates): (Vec<&SQLExpr>, Vec<&SQLExpr>) = conjuncts
+        .into_iter()
+        .partition(|conjunct| contains_subquery(conjunct));
+    (
+        sql_conjunction(predicates.into_iter().cloned()),
+        subquery_predicates,
+    )
+}
+
+/// Collects the expressions of the top-level conjunction of `expr`
+fn split_conjuncts<'a>(expr: &'a SQLExpr, conjuncts: &mut Vec<&'a SQLExpr>) {
+    match expr {
+        SQLExpr::BinaryOp {
+            left,
+            op: BinaryOperator::And,
+            right,
+        } => {
+            split_conjuncts(left, conjuncts);
+            split_conjuncts(right, conjuncts);
+        }
+        _ => conjuncts.push(expr),
+    }
+}
+
+/// Returns the conjunction of `exprs`, if any
+fn sql_conjunction(exprs: impl Iterator<Item = SQLExpr>) -> Option<SQLExpr> {
+    exprs.fold(None, |acc, expr| match acc {
+        Some(acc) => Some(SQLExpr::BinaryOp {
+            left: Box::new(acc),
+            op: BinaryOperator::And,
+            right: Box::new(expr),
+        }),
+        None => Some(expr),
+    })
+}
+
+/// Whether `expr` contains a subquery
+fn contains_subquery(expr: &SQLExpr) -> bool {
+    let mut found = false;
+    // the transformation itself never fails
+    let _ = transform_sql_expr(expr, &mut |expr: &SQLExpr| match expr {
+        SQLExpr::Subquery(_) | SQLExpr::InSubquery { .. } | SQLExpr::Exists(_) => {
+            found = true;
+            Ok(Some(SQLExpr::Wildcard))
+        }
+        _ => Ok(None),
+    });
+    found
+}
+
+/// Returns the name of the field of `schema` that `expr` references, if it is a column of it
+fn sql_column(expr: &SQLExpr, schema: &QualifiedSchema) -> Option<String> {
+    match expr {
+        SQLExpr::Identifier(id) => schema.resolve(None, &id.value).ok(),
+        SQLExpr::CompoundIdentifier(ids) if ids.len() == 2 => {
+            schema.resolve(Some(&ids[0].value), &ids[1].value).ok()
+        }
+        _ => None,
+    }
+}
+
+/// Returns a copy of `expr` where the expressions for which `f` returns a replacement are
+/// replaced, visiting the parents before their children. Subqueries are not visited.
+fn transform_sql_expr<F>(expr: &SQLExpr, f: &mut F) -> Result<SQLExpr>
+where
+    F: FnMut(&SQLExpr) -> Result<Option<SQLExpr>>,
+{
+    if let Some(replacement) = f(expr)? {
+        return Ok(replacement);
     }
+    let transformed = match expr {
+        SQLExpr::BinaryOp { left, op, right } => SQLExpr::BinaryOp {
+            left: Box::new(transform_sql_expr(left, f)?),
+            op: op.clone(),
+            right: Box::new(transform_sql_expr(right, f)?),
+        },
+        SQLExpr::UnaryOp { op, expr } => SQLExpr::UnaryOp {
+            op: op.clone(),
+            expr: Box::new(transform_sql_expr(expr, f)?),
+        },
+        SQLExpr::Nested(expr) => SQLExpr::Nested(Box::new(transform_sql_expr(expr, f)?)),
+        SQLExpr::Cast { expr, data_type } => SQLExpr::Cast {
+            expr: Box::new(transform_sql_expr(expr, f)?),
+            data_type: data_type.clone(),
+        },
+        SQLExpr::Extract { field, expr } => SQLExpr::Extract {
+            field: field.clone(),
+            expr: Box::new(transform_sql_expr(expr, f)?),
+        },
+        SQLExpr::IsNull(expr) => SQLExpr::IsNull(Box::new(transform_sql_expr(expr, f)?)),
+        SQLExpr::IsNotNull(expr) => {
+            SQLExpr::IsNotNull(Box::new(transform_sql_expr(expr, f)?))
+        }
+        SQLExpr::Between {
+            expr,
+            negated,
+            low,
+            high,
+        } => SQLExpr::Between {
+            expr: Box::new(transform_sql_expr(expr, f)?),
+            negated: *negated,
+            low: Box::new(transform_sql_expr(low, f)?),
+            high: Box::new(transform_sql_expr(high, f)?),
+        },
+        SQLExpr::InList {
+            expr,
+            list,
+            negated,
+        } => SQLExpr::InList {
+            expr: Box::new(transform_sql_expr(expr, f)?),
+            list: list
+                .iter()
+                .map(|e| transform_sql_expr(e, f))
+                .collect::<Result<Vec<_>>>()?,
+            negated: *negated,
+        },
+        SQLExpr::InSubquery {
+            expr,
+            subquery,
+            negated,
+        } => SQLExpr::InSubquery {
+            expr: Box::new(transform_sql_expr(expr, f)?),
+            subquery: subquery.clone(),
+            negated: *negated,
+        },
+        SQLExpr::Function(function) => SQLExpr::Function(Function {
+            args: function
+                .args
+                .iter()
+                .map(|e| transform_sql_expr(e, f))
+                .collect::<Result<Vec<_>>>()?,
+            ..function.clone()
+        }),
+        SQLExpr::Case {
+            operand,
+            conditions,
+            results,
+            else_result,
+        } => SQLExpr::Case {
+            operand: match operand {
+                Some(e) => Some(Box::new(transform_sql_expr(e, f)?)),
+                None => None,
+            },
+            conditions: conditions
+                .iter()
+                .map(|e| transform_sql_expr(e, f))
+                .collect::<Result<Vec<_>>>()?,
+            results: results
+                .iter()
+                .map(|e| transform_sql_expr(e, f))
+                .collect::<Result<Vec<_>>>()?,
+            else_result: match else_result {
+                Some(e) => Some(Box::new(transform_sql_expr(e, f)?)),
+                None => None,
+            },
+        },
+        _ => expr.clone(),
+    };
+    Ok(transformed)
 }
 
 /// Extract join keys from a WHERE clause
@@ -885,6 +2346,83 @@ fn is_aggregate_expr(e: &Expr) -> bool {
 }
 
 /// Convert SQL data type to relational representation of data type
+/// Returns the literal of an SQL interval, such as `INTERVAL '1 day 2 hours'` or
+/// `INTERVAL '3' MONTH`, as either a number of months or of days and milliseconds
+fn parse_interval(
+    value: &str,
+    leading_field: &Option<DateTimeField>,
+    last_field: &Option<DateTimeField>,
+) -> Result<Expr> {
+    if last_field.is_some() {
+        return Err(DataFusionError::NotImplemented(format!(
+            "Intervals with a range of fields are not supported: '{}'",
+            value
+        )));
+    }
+    let value = match leading_field {
+        Some(field) => format!("{} {}", value, field),
+        None => value.to_string(),
+    };
+
+    let (mut months, mut days, mut milliseconds) = (0_i64, 0_i64, 0_i64);
+    let mut parts = value.split_whitespace();
+    while let Some(amount) = parts.next() {
+        let amount = amount.parse::<i64>().map_err(|_| {
+            DataFusionError::Plan(format!("Invalid amount '{}' of interval", amount))
+        })?;
+        let unit = parts.next().unwrap_or("second").to_lowercase();
+        match unit.trim_end_matches('s') {
+            "year" => months += amount * 12,
+            "month" => months += amount,
+            "week" => days += amount * 7,
+            "day" => days += amount,
+            "hour" => milliseconds += amount * 3_600_000,
+            "minute" => milliseconds += amount * 60_000,
+            "second" => milliseconds += amount * 1_000,
+            "millisecond" => milliseconds += amount,
+            _ => {
+                return Err(DataFusionError::Plan(format!(
+                    "Invalid unit '{}' of interval",
+                    unit
+                )))
+            }
+        }
+    }
+
+    let out_of_range =
+        || DataFusionError::Plan(format!("Interval '{}' is out of range", value));
+    if months != 0 {
+        if days != 0 || milliseconds != 0 {
+            return Err(DataFusionError::NotImplemented(format!(
+                "Intervals of both months and days or times are not supported: '{}'",
+                value
+            )));
+        }
+        let months = i32::try_from(months).map_err(|_| out_of_range())?;
+        return Ok(Expr::Literal(ScalarValue::IntervalYearMonth(Some(months))));
+    }
+    let days = i32::try_from(days).map_err(|_| out_of_range())?;
+    let milliseconds = i32::try_from(milliseconds).map_err(|_| out_of_range())?;
+    // the days are in the upper 32 bits, and the milliseconds in the lower 32 bits
+    let interval = ((days as i64) << 32) | milliseconds as u32 as i64;
+    Ok(Expr::Literal(ScalarValue::IntervalDayTime(Some(interval))))
+}
+
+/// Returns the decimal type of `DECIMAL(precision, scale)`, where the precision defaults
+/// to the largest one and the scale to 0
+fn make_decimal_type(precision: &Option<u64>, scale: &Option<u64>) -> Result<DataType> {
+    let precision = precision.unwrap_or(MAX_DECIMAL_PRECISION as u64) as usize;
+    let scale = scale.unwrap_or(0) as usize;
+    if precision == 0 || precision > MAX_DECIMAL_PRECISION || scale > precision {
+        return Err(DataFusionError::Plan(format!(
+            "Decimal precision must be between 1 and {} and the scale at most the \
+             precision, got DECIMAL({}, {})",
+            MAX_DECIMAL_PRECISION, precision, scale
+        )));
+    }
+    Ok(DataType::Decimal(precision, scale))
+}
+
 pub fn convert_data_type(sql: &SQLDataType) -> Result<DataType> {
     match sql {
         SQLDataType::Boolean => Ok(DataType::Boolean),
@@ -893,6 +2431,7 @@ pub fn convert_data_type(sql: &SQLDataType) -> Result<DataType> {
         SQLDataType::BigInt => Ok(DataType::Int64),
         SQLDataType::Float(_) | SQLDataType::Real => Ok(DataType::Float64),
         SQLDataType::Double => Ok(DataType::Float64),
+        SQLDataType::Decimal(precision, scale) => make_decimal_type(precision, scale),
         SQLDataType::Char(_) | SQLDataType::Varchar(_) => Ok(DataType::Utf8),
         SQLDataType::Timestamp => Ok(DataType::Timestamp(TimeUnit::Nanosecond, None)),
         other => Err(DataFusionError::NotImplemented(format!(
@@ -956,6 +2495,54 @@ mod tests {
         quick_test(sql, expected);
     }
 
+    #[test]
+    fn select_in_list_and_between() {
+        let sql = "SELECT id FROM person \
+                   WHERE state NOT IN ('CO', 'NY') AND age BETWEEN 21 AND 65";
+        let expected = "Projection: #id\
+            \n  Filter: #state NOT IN (Utf8(\"CO\"), Utf8(\"NY\")) And #age BETWEEN Int64(21) AND Int64(65)\
+            \n    TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn select_window_function() {
+        let sql =
+            "SELECT id, ROW_NUMBER() OVER (PARTITION BY state ORDER BY age DESC) AS rn \
+                   FROM person";
+        let expected = "Projection: #id, #ROW_NUMBER() OVER (PARTITION BY state ORDER BY age DESC NULLS FIRST) AS rn\
+            \n  Window: windowExpr=[[ROW_NUMBER() OVER (PARTITION BY #state ORDER BY #age DESC NULLS FIRST)]]\
+            \n    TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn select_window_function_with_frame() {
+        let sql = "SELECT SUM(age) OVER (ORDER BY id ROWS BETWEEN 1 PRECEDING AND CURRENT ROW) + 1 \
+                   FROM person";
+        let expected = "Projection: #SUM(age) OVER (ORDER BY id ASC NULLS FIRST ROWS BETWEEN 1 PRECEDING AND CURRENT ROW) Plus Int64(1)\
+            \n  Window: windowExpr=[[SUM(#age) OVER (ORDER BY #id ASC NULLS FIRST ROWS BETWEEN 1 PRECEDING AND CURRENT ROW)]]\
+            \n    TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn select_window_function_errors() {
+        let sql = "SELECT RANK() FROM person";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"The window function RANK requires an OVER clause\")",
+            format!("{:?}", err)
+        );
+
+        let sql = "SELECT state, RANK() OVER (ORDER BY state) FROM person GROUP BY state";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "NotImplemented(\"Window functions are not supported in aggregate queries\")",
+            format!("{:?}", err)
+        );
+    }
+
     #[test]
     fn test_timestamp_filter() {
         let sql = "SELECT state FROM person WHERE birth_date < CAST (158412331400600000 as timestamp)";
@@ -967,6 +2554,23 @@ mod tests {
         quick_test(sql, expected);
     }
 
+    #[test]
+    fn select_cast_to_decimal() {
+        let sql = "SELECT CAST(age AS DECIMAL(10, 2)), CAST(age AS DECIMAL) FROM person";
+        let expected =
+            "Projection: CAST(#age AS Decimal(10, 2)), CAST(#age AS Decimal(38, 0))\
+            \n  TableScan: person projection=None";
+        quick_test(sql, expected);
+
+        let sql = "SELECT CAST(age AS DECIMAL(40, 2)) FROM person";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"Decimal precision must be between 1 and 38 and the scale at most the \
+             precision, got DECIMAL(40, 2)\")",
+            format!("{:?}", err)
+        );
+    }
+
     #[test]
     fn select_all_boolean_operators() {
         let sql = "SELECT age, first_name, last_name \
@@ -1191,6 +2795,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn select_aggregate_with_having() {
+        let sql = "SELECT state, COUNT(*) FROM person GROUP BY state HAVING COUNT(*) > 1";
+        let expected = "Filter: #COUNT(UInt8(1)) Gt Int64(1)\
+                        \n  Aggregate: groupBy=[[#state]], aggr=[[COUNT(UInt8(1))]]\
+                        \n    TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn select_aggregate_with_having_not_in_projection() {
+        let sql =
+            "SELECT state, MIN(age) FROM person GROUP BY state HAVING MAX(age) > 50";
+        let expected = "Projection: #state, #MIN(age)\
+                        \n  Filter: #MAX(age) Gt Int64(50)\
+                        \n    Aggregate: groupBy=[[#state]], aggr=[[MIN(#age), MAX(#age)]]\
+                        \n      TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn select_aggregate_with_having_aliased() {
+        let sql = "SELECT state, MAX(age) AS max_age FROM person \
+                   GROUP BY state HAVING MAX(age) > 50 AND state <> 'CO'";
+        let expected = "Filter: #max_age Gt Int64(50) And #state NotEq Utf8(\"CO\")\
+                        \n  Aggregate: groupBy=[[#state]], aggr=[[MAX(#age) AS max_age]]\
+                        \n    TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn select_aggregate_with_having_non_aggregate() {
+        let sql = "SELECT state FROM person GROUP BY state HAVING age > 50";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"HAVING clause references non-aggregate value 'age'\")",
+            format!("{:?}", err)
+        );
+    }
+
     #[test]
     fn create_external_table_csv() {
         let sql = "CREATE EXTERNAL TABLE t(c1 int) STORED AS CSV LOCATION 'foo.csv'";
@@ -1254,6 +2898,585 @@ mod tests {
         quick_test(sql, expected);
     }
 
+    #[test]
+    fn join_with_filter() {
+        let sql = "SELECT id, order_id \
+            FROM person \
+            LEFT JOIN orders \
+            ON id = customer_id AND age > qty";
+        let expected = "Projection: #id, #order_id\
+        \n  Join: id = customer_id, filter=#age Gt #qty\
+        \n    TableScan: person projection=None\
+        \n    TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn inner_join_without_equality() {
+        let sql = "SELECT id, order_id FROM person JOIN orders ON id > customer_id";
+        let expected = "Projection: #id, #order_id\
+        \n  Filter: #id Gt #customer_id\
+        \n    CrossJoin\
+        \n      TableScan: person projection=None\
+        \n      TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn outer_join_without_equality() {
+        let sql = "SELECT id, order_id FROM person LEFT JOIN orders ON id > customer_id";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "NotImplemented(\"Left JOIN without an equality condition between columns \
+             of both sides is not supported\")",
+            format!("{:?}", err)
+        );
+    }
+
+    #[test]
+    fn full_outer_join() {
+        let sql = "SELECT id, order_id \
+            FROM person \
+            FULL OUTER JOIN orders \
+            ON id = customer_id";
+        let expected = "Projection: #id, #order_id\
+        \n  Join: id = customer_id\
+        \n    TableScan: person projection=None\
+        \n    TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn cross_join_explicit_syntax() {
+        let sql = "SELECT id, l_description FROM person CROSS JOIN lineitem";
+        let expected = "Projection: #id, #l_description\
+        \n  CrossJoin\
+        \n    TableScan: person projection=None\
+        \n    TableScan: lineitem projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn cross_join_implicit_syntax() {
+        let sql = "SELECT id, l_description FROM person, lineitem WHERE age > 20";
+        let expected = "Projection: #id, #l_description\
+        \n  Filter: #age Gt Int64(20)\
+        \n    CrossJoin\
+        \n      TableScan: person projection=None\
+        \n      TableScan: lineitem projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn join_using() {
+        let sql = "WITH o AS (SELECT order_id AS id, qty FROM orders) \
+            SELECT id, first_name, qty FROM person JOIN o USING (id)";
+        let expected = "Projection: #id, #first_name, #qty\
+        \n  Join: id = id\
+        \n    TableScan: person projection=None\
+        \n    Projection: #order_id AS id, #qty\
+        \n      TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn join_using_aliased_relations() {
+        let sql = "SELECT l_item_id, l2.l_description \
+            FROM lineitem l1 LEFT JOIN lineitem l2 USING (l_item_id)";
+        let expected = "Projection: #l1.l_item_id AS l_item_id, #l2.l_description AS l_description\
+        \n  Join: l1.l_item_id = l1.l_item_id\
+        \n    Projection: #l_item_id AS l1.l_item_id, #l_description AS l1.l_description\
+        \n      TableScan: lineitem projection=None\
+        \n    Projection: #l2.l_item_id AS l1.l_item_id, #l2.l_description\
+        \n      Projection: #l_item_id AS l2.l_item_id, #l_description AS l2.l_description\
+        \n        TableScan: lineitem projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn join_using_missing_column() {
+        let sql = "SELECT id FROM person JOIN orders USING (id)";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert!(format!("{:?}", err).starts_with("Plan(\"Invalid identifier 'id'"));
+    }
+
+    #[test]
+    fn natural_join() {
+        let sql =
+            "SELECT l_item_id, l_description FROM lineitem l1 NATURAL JOIN lineitem l2";
+        let expected = "Projection: #l1.l_item_id AS l_item_id, #l1.l_description AS l_description\
+        \n  Join: l1.l_item_id = l1.l_item_id, l1.l_description = l1.l_description\
+        \n    Projection: #l_item_id AS l1.l_item_id, #l_description AS l1.l_description\
+        \n      TableScan: lineitem projection=None\
+        \n    Projection: #l2.l_item_id AS l1.l_item_id, #l2.l_description AS l1.l_description\
+        \n      Projection: #l_item_id AS l2.l_item_id, #l_description AS l2.l_description\
+        \n        TableScan: lineitem projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn natural_join_without_common_columns() {
+        let sql = "SELECT id, l_item_id FROM person NATURAL JOIN lineitem";
+        let expected = "Projection: #id, #l_item_id\
+        \n  CrossJoin\
+        \n    TableScan: person projection=None\
+        \n    TableScan: lineitem projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn union_all() {
+        let sql = "SELECT order_id FROM orders UNION ALL SELECT order_id FROM orders";
+        let expected = "Union\
+            \n  Projection: #order_id\
+            \n    TableScan: orders projection=None\
+            \n  Projection: #order_id\
+            \n    TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn union_distinct() {
+        let sql = "SELECT id, first_name FROM person \
+            UNION SELECT customer_id, o_item_id FROM orders";
+        let expected = "Aggregate: groupBy=[[#id, #first_name]], aggr=[[]]\
+            \n  Union\
+            \n    Projection: #id, #first_name\
+            \n      TableScan: person projection=None\
+            \n    Projection: #customer_id AS id, #o_item_id AS first_name\
+            \n      Projection: #customer_id, #o_item_id\
+            \n        TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn union_with_type_coercion() {
+        let sql = "SELECT age FROM person UNION ALL SELECT order_id FROM orders";
+        let expected = "Union\
+            \n  Projection: CAST(#age AS Int64) AS age\
+            \n    Projection: #age\
+            \n      TableScan: person projection=None\
+            \n  Projection: CAST(#order_id AS Int64) AS age\
+            \n    Projection: #order_id\
+            \n      TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn union_different_number_of_columns() {
+        let sql = "SELECT id, age FROM person UNION ALL SELECT order_id FROM orders";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"Set operations require inputs with the same number of columns, \
+             but the inputs have 2 and 1 columns\")",
+            format!("{:?}", err)
+        );
+    }
+
+    #[test]
+    fn intersect() {
+        let sql = "SELECT order_id FROM orders INTERSECT SELECT id FROM person";
+        let expected = "Join: order_id = order_id\
+            \n  Aggregate: groupBy=[[#order_id]], aggr=[[]]\
+            \n    Projection: #order_id\
+            \n      TableScan: orders projection=None\
+            \n  Projection: #id AS order_id\
+            \n    Projection: #id\
+            \n      TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn except() {
+        let sql = "SELECT order_id FROM orders EXCEPT SELECT id FROM person";
+        let expected = "Join: order_id = order_id\
+            \n  Aggregate: groupBy=[[#order_id]], aggr=[[]]\
+            \n    Projection: #order_id\
+            \n      TableScan: orders projection=None\
+            \n  Projection: #id AS order_id\
+            \n    Projection: #id\
+            \n      TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn intersect_all_not_implemented() {
+        let sql = "SELECT order_id FROM orders INTERSECT ALL SELECT id FROM person";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "NotImplemented(\"INTERSECT ALL is not implemented yet\")",
+            format!("{:?}", err)
+        );
+    }
+
+    #[test]
+    fn cte() {
+        let sql = "WITH adults AS (SELECT id, first_name FROM person WHERE age > 20) \
+            SELECT first_name FROM adults";
+        let expected = "Projection: #first_name\
+            \n  Projection: #id, #first_name\
+            \n    Filter: #age Gt Int64(20)\
+            \n      TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn cte_references_earlier_cte() {
+        let sql = "WITH adults AS (SELECT id, age FROM person WHERE age > 20), \
+            seniors AS (SELECT id FROM adults WHERE age > 65) \
+            SELECT id FROM seniors";
+        let expected = "Projection: #id\
+            \n  Projection: #id\
+            \n    Filter: #age Gt Int64(65)\
+            \n      Projection: #id, #age\
+            \n        Filter: #age Gt Int64(20)\
+            \n          TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn cte_with_column_aliases() {
+        let sql = "WITH p(person_id, name) AS (SELECT id, first_name FROM person) \
+            SELECT name FROM p";
+        let expected = "Projection: #name\
+            \n  Projection: #id AS person_id, #first_name AS name\
+            \n    Projection: #id, #first_name\
+            \n      TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn cte_used_twice() {
+        let sql = "WITH ids AS (SELECT id FROM person) \
+            SELECT id FROM ids UNION ALL SELECT id FROM ids";
+        let expected = "Union\
+            \n  Projection: #id\
+            \n    Projection: #id\
+            \n      TableScan: person projection=None\
+            \n  Projection: #id\
+            \n    Projection: #id\
+            \n      TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn cte_shadows_table() {
+        let sql =
+            "WITH person AS (SELECT order_id AS id FROM orders) SELECT id FROM person";
+        let expected = "Projection: #id\
+            \n  Projection: #order_id AS id\
+            \n    TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn cte_is_scoped_to_its_query() {
+        let sql =
+            "SELECT id FROM (WITH ids AS (SELECT id FROM person) SELECT id FROM ids) \
+            UNION ALL SELECT id FROM ids";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"no schema found for table ids\")",
+            format!("{:?}", err)
+        );
+    }
+
+    #[test]
+    fn cte_duplicate_name() {
+        let sql = "WITH ids AS (SELECT id FROM person), ids AS (SELECT id FROM person) \
+            SELECT id FROM ids";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"WITH query name \\\"ids\\\" specified more than once\")",
+            format!("{:?}", err)
+        );
+    }
+
+    #[test]
+    fn cte_wrong_number_of_column_aliases() {
+        let sql = "WITH p(a, b) AS (SELECT id FROM person) SELECT a FROM p";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"Relation \\\"p\\\" has 1 columns, but 2 column names were specified\")",
+            format!("{:?}", err)
+        );
+    }
+
+    #[test]
+    fn table_alias() {
+        let sql = "SELECT l.l_item_id FROM lineitem AS l";
+        let expected = "Projection: #l.l_item_id AS l_item_id\
+            \n  Projection: #l_item_id AS l.l_item_id, #l_description AS l.l_description\
+            \n    TableScan: lineitem projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn derived_table_alias_with_columns() {
+        let sql =
+            "SELECT a FROM (SELECT l_item_id, l_description FROM lineitem) AS t(a, b)";
+        let expected = "Projection: #t.a AS a\
+            \n  Projection: #l_item_id AS t.a, #l_description AS t.b\
+            \n    Projection: #l_item_id, #l_description\
+            \n      TableScan: lineitem projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn self_join() {
+        let sql = "SELECT l1.l_description, l2.l_description \
+            FROM lineitem l1 JOIN lineitem l2 ON l2.l_item_id = l1.l_item_id";
+        let expected = "Projection: #l1.l_description, #l2.l_description\
+            \n  Join: l1.l_item_id = l2.l_item_id\
+            \n    Projection: #l_item_id AS l1.l_item_id, #l_description AS l1.l_description\
+            \n      TableScan: lineitem projection=None\
+            \n    Projection: #l_item_id AS l2.l_item_id, #l_description AS l2.l_description\
+            \n      TableScan: lineitem projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn self_join_qualified_wildcard() {
+        let sql = "SELECT l2.* FROM lineitem l1 JOIN lineitem l2 ON l1.l_item_id = l2.l_item_id";
+        let expected = "Projection: #l2.l_item_id AS l_item_id, #l2.l_description AS l_description\
+            \n  Join: l1.l_item_id = l2.l_item_id\
+            \n    Projection: #l_item_id AS l1.l_item_id, #l_description AS l1.l_description\
+            \n      TableScan: lineitem projection=None\
+            \n    Projection: #l_item_id AS l2.l_item_id, #l_description AS l2.l_description\
+            \n      TableScan: lineitem projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn self_join_ambiguous_column() {
+        let sql = "SELECT l_description \
+            FROM lineitem l1 JOIN lineitem l2 ON l1.l_item_id = l2.l_item_id";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"Ambiguous reference to column 'l_description', which could refer to \
+             any of l1.l_description, l2.l_description\")",
+            format!("{:?}", err)
+        );
+    }
+
+    #[test]
+    fn qualified_column_of_relation_without_alias() {
+        let sql = "SELECT person.id, order_id \
+            FROM person JOIN orders ON person.id = orders.customer_id";
+        let expected = "Projection: #id, #order_id\
+            \n  Join: id = customer_id\
+            \n    TableScan: person projection=None\
+            \n    TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn qualified_column_of_other_relation() {
+        let sql = "SELECT orders.id \
+            FROM person JOIN orders ON person.id = orders.customer_id";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert!(format!("{:?}", err)
+            .starts_with("Plan(\"Invalid identifier 'orders.id' for schema"));
+    }
+
+    #[test]
+    fn qualified_column_of_relation_not_in_scope() {
+        let sql = "SELECT person.id FROM person AS p";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"Invalid identifier 'person.id': there is no relation 'person' in scope\")",
+            format!("{:?}", err)
+        );
+    }
+
+    #[test]
+    fn column_name_with_dot() {
+        let sql = "SELECT x.\"a.b\" FROM (SELECT id AS \"a.b\" FROM person) AS x";
+        let expected = "Projection: #x.a.b AS a.b\
+            \n  Projection: #a.b AS x.a.b\
+            \n    Projection: #id AS a.b\
+            \n      TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn order_by_qualified_output_column() {
+        let sql = "SELECT l.l_item_id FROM lineitem AS l ORDER BY l.l_item_id";
+        let expected = "Sort: #l_item_id ASC NULLS FIRST\
+            \n  Projection: #l.l_item_id AS l_item_id\
+            \n    Projection: #l_item_id AS l.l_item_id, #l_description AS l.l_description\
+            \n      TableScan: lineitem projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn exists_subquery() {
+        let sql = "SELECT id FROM person \
+            WHERE EXISTS (SELECT * FROM orders WHERE customer_id = id)";
+        let expected = "Projection: #id\
+            \n  Join: id = __sq0_0\
+            \n    TableScan: person projection=None\
+            \n    Projection: #customer_id AS __sq0_0\
+            \n      Projection: #customer_id\
+            \n        TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn not_exists_subquery_with_filter() {
+        let sql = "SELECT id FROM person \
+            WHERE NOT EXISTS (SELECT * FROM orders WHERE customer_id = id AND qty > age)";
+        let expected = "Projection: #id\
+            \n  Join: id = __sq0_0, filter=#__sq0_1 Gt #age\
+            \n    TableScan: person projection=None\
+            \n    Projection: #customer_id AS __sq0_0, #qty AS __sq0_1\
+            \n      Projection: #customer_id, #qty\
+            \n        TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn uncorrelated_exists_subquery() {
+        let sql = "SELECT id FROM person WHERE EXISTS (SELECT * FROM orders)";
+        let expected = "Projection: #id\
+            \n  Projection: #id, #first_name, #last_name, #age, #state, #salary, #birth_date\
+            \n    Filter: #__sq0_0 Gt UInt64(0)\
+            \n      CrossJoin\
+            \n        TableScan: person projection=None\
+            \n        Projection: #COUNT(UInt8(1)) AS __sq0_0\
+            \n          Aggregate: groupBy=[[]], aggr=[[COUNT(UInt8(1))]]\
+            \n            Limit: 1\
+            \n              Projection: #order_id AS __sq0_0, #customer_id AS __sq0_1, #o_item_id AS __sq0_2, #qty AS __sq0_3, #price AS __sq0_4\
+            \n                Projection: #order_id, #customer_id, #o_item_id, #qty, #price\
+            \n                  TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn not_in_subquery() {
+        let sql = "SELECT id FROM person \
+            WHERE age > 21 AND id NOT IN (SELECT customer_id FROM orders WHERE qty > 1)";
+        let expected = "Projection: #id\
+            \n  Join: id = __sq0_0\
+            \n    Filter: #age Gt Int64(21)\
+            \n      TableScan: person projection=None\
+            \n    Projection: #customer_id AS __sq0_0\
+            \n      Projection: #customer_id\
+            \n        Filter: #qty Gt Int64(1)\
+            \n          TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn in_subquery_with_many_columns() {
+        let sql =
+            "SELECT id FROM person WHERE id IN (SELECT customer_id, qty FROM orders)";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"A subquery of IN must return a single column, but it returns 2\")",
+            format!("{:?}", err)
+        );
+    }
+
+    #[test]
+    fn scalar_subquery() {
+        let sql = "SELECT id FROM person WHERE age > (SELECT MAX(qty) FROM orders)";
+        let expected = "Projection: #id\
+            \n  Projection: #id, #first_name, #last_name, #age, #state, #salary, #birth_date\
+            \n    Filter: #age Gt #__sq0_0\
+            \n      CrossJoin\
+            \n        TableScan: person projection=None\
+            \n        Projection: #MAX(qty) AS __sq0_0\
+            \n          Aggregate: groupBy=[[]], aggr=[[MAX(#qty)]]\
+            \n            TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn correlated_scalar_subquery() {
+        let sql = "SELECT id FROM person \
+            WHERE age > (SELECT MAX(qty) FROM orders WHERE customer_id = id)";
+        let expected = "Projection: #id\
+            \n  Projection: #id, #first_name, #last_name, #age, #state, #salary, #birth_date\
+            \n    Filter: #age Gt #__sq0_0\
+            \n      Join: id = __sq0_1\
+            \n        TableScan: person projection=None\
+            \n        Projection: #MAX(qty) AS __sq0_0, #customer_id AS __sq0_1\
+            \n          Projection: #MAX(qty), #customer_id\
+            \n            Aggregate: groupBy=[[#customer_id]], aggr=[[MAX(#qty)]]\
+            \n              TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn scalar_subquery_of_many_rows() {
+        let sql = "SELECT id FROM person WHERE age > (SELECT qty FROM orders)";
+        let expected = "Projection: #id\
+            \n  Projection: #id, #first_name, #last_name, #age, #state, #salary, #birth_date\
+            \n    Filter: #age Gt #__sq0_0\
+            \n      CrossJoin\
+            \n        TableScan: person projection=None\
+            \n        Aggregate: groupBy=[[]], aggr=[[SINGLE_VALUE(#__sq0_0) AS __sq0_0]]\
+            \n          Projection: #qty AS __sq0_0\
+            \n            Projection: #qty\
+            \n              TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn correlated_count_subquery() {
+        let sql = "SELECT id FROM person \
+            WHERE age > (SELECT COUNT(*) FROM orders WHERE customer_id = id)";
+        let expected = "Projection: #id\
+            \n  Projection: #id, #first_name, #last_name, #age, #state, #salary, #birth_date\
+            \n    Filter: #age Gt #__sq0_0\
+            \n      Projection: #id, #first_name, #last_name, #age, #state, #salary, #birth_date, CASE WHEN #__sq0_0 IS NULL THEN UInt64(0) ELSE #__sq0_0 END AS __sq0_0, #__sq0_1\
+            \n        Join: id = __sq0_1\
+            \n          TableScan: person projection=None\
+            \n          Projection: #COUNT(UInt8(1)) AS __sq0_0, #customer_id AS __sq0_1\
+            \n            Projection: #COUNT(UInt8(1)), #customer_id\
+            \n              Aggregate: groupBy=[[#customer_id]], aggr=[[COUNT(UInt8(1))]]\
+            \n                TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn correlated_count_expression_subquery() {
+        let sql = "SELECT id FROM person \
+            WHERE age > (SELECT COUNT(*) + 1 FROM orders WHERE customer_id = id)";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "NotImplemented(\"Correlated scalar subqueries whose value is computed from \
+             a COUNT, other than the COUNT itself, are not supported\")",
+            format!("{:?}", err)
+        );
+    }
+
+    #[test]
+    fn correlated_count_subquery_with_having() {
+        // the group of a person without orders has a COUNT of 0, which is kept by HAVING
+        let sql = "SELECT id FROM person \
+            WHERE age > (SELECT COUNT(*) FROM orders WHERE customer_id = id \
+            HAVING COUNT(*) < 2)";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "NotImplemented(\"Correlated scalar subqueries whose value is computed from \
+             a COUNT, other than the COUNT itself, are not supported\")",
+            format!("{:?}", err)
+        );
+    }
+
+    #[test]
+    fn subquery_in_projection() {
+        let sql = "SELECT (SELECT MAX(qty) FROM orders) FROM person";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "NotImplemented(\"Subqueries are only supported in WHERE clauses, and EXISTS \
+             and IN subqueries only as conjuncts of them\")",
+            format!("{:?}", err)
+        );
+    }
+
     fn logical_plan(sql: &str) -> Result<LogicalPlan> {
         let planner = SqlToRel::new(&MockSchemaProvider {});
         let result = DFParser::parse_sql(&sql);
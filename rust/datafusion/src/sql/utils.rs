@@ -0,0 +1,329 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! SQL Utility Functions
+
+use std::collections::HashMap;
+use std::fmt;
+
+use arrow::datatypes::Schema;
+
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::Expr;
+use crate::optimizer::utils::{expr_sub_expressions, rewrite_expression};
+
+/// Collect all deeply nested `Expr::AggregateFunction` and
+/// `Expr::AggregateUDF`. They are returned in order of occurrence (depth
+/// first), with duplicates (expressions with the same name) omitted.
+pub(crate) fn find_aggregate_exprs(
+    exprs: &[Expr],
+    input_schema: &Schema,
+) -> Result<Vec<Expr>> {
+    find_exprs(exprs, input_schema, &|expr| match expr {
+        Expr::AggregateFunction { .. } | Expr::AggregateUDF { .. } => true,
+        _ => false,
+    })
+}
+
+/// Collect all deeply nested `Expr::WindowFunction`. They are returned in
+/// order of occurrence (depth first), with duplicates (expressions with the
+/// same name) omitted.
+pub(crate) fn find_window_exprs(
+    exprs: &[Expr],
+    input_schema: &Schema,
+) -> Result<Vec<Expr>> {
+    find_exprs(exprs, input_schema, &|expr| match expr {
+        Expr::WindowFunction { .. } => true,
+        _ => false,
+    })
+}
+
+fn find_exprs(
+    exprs: &[Expr],
+    input_schema: &Schema,
+    predicate: &dyn Fn(&Expr) -> bool,
+) -> Result<Vec<Expr>> {
+    let mut names = vec![];
+    let mut accum = vec![];
+    for expr in exprs {
+        collect_exprs(expr, input_schema, predicate, &mut names, &mut accum)?;
+    }
+    Ok(accum)
+}
+
+fn collect_exprs(
+    expr: &Expr,
+    input_schema: &Schema,
+    predicate: &dyn Fn(&Expr) -> bool,
+    names: &mut Vec<String>,
+    accum: &mut Vec<Expr>,
+) -> Result<()> {
+    if predicate(expr) {
+        let name = expr.name(input_schema)?;
+        if !names.contains(&name) {
+            names.push(name);
+            accum.push(expr.clone());
+        }
+        Ok(())
+    } else {
+        for e in expr_sub_expressions(expr)? {
+            collect_exprs(&e, input_schema, predicate, names, accum)?;
+        }
+        Ok(())
+    }
+}
+
+/// Rewrite `expr` so that every sub-expression which is computed by one of
+/// `base_exprs` is replaced by a column reference to that expression's
+/// output. `base_exprs` are the (possibly aliased) expressions of a node
+/// such as an aggregate, whose output columns are named after them.
+///
+/// For example, with `base_exprs = [#c1, SUM(#c2) AS total]`,
+/// `SUM(#c2) Gt Int64(10)` becomes `#total Gt Int64(10)`.
+pub(crate) fn rebase_expr(
+    expr: &Expr,
+    base_exprs: &[Expr],
+    input_schema: &Schema,
+) -> Result<Expr> {
+    let mut output_names = HashMap::new();
+    for e in base_exprs {
+        let output_name = e.name(input_schema)?;
+        let unaliased = match e {
+            Expr::Alias(inner, _) => inner.as_ref(),
+            _ => e,
+        };
+        output_names.insert(unaliased.name(input_schema)?, output_name);
+    }
+    rebase_expr_with_names(expr, &output_names, input_schema)
+}
+
+fn rebase_expr_with_names(
+    expr: &Expr,
+    output_names: &HashMap<String, String>,
+    input_schema: &Schema,
+) -> Result<Expr> {
+    if let Ok(name) = expr.name(input_schema) {
+        if let Some(output_name) = output_names.get(&name) {
+            return Ok(Expr::Column(output_name.clone()));
+        }
+    }
+    let expressions = expr_sub_expressions(expr)?
+        .iter()
+        .map(|e| rebase_expr_with_names(e, output_names, input_schema))
+        .collect::<Result<Vec<_>>>()?;
+    rewrite_expression(expr, &expressions)
+}
+
+/// Returns the unaliased name of each expression in `exprs`
+pub(crate) fn unaliased_names(
+    exprs: &[Expr],
+    input_schema: &Schema,
+) -> Result<Vec<String>> {
+    exprs
+        .iter()
+        .map(|e| match e {
+            Expr::Alias(inner, _) => inner.name(input_schema),
+            _ => e.name(input_schema),
+        })
+        .collect()
+}
+
+/// Returns the name of the field of the column `name` of the relation `relation` in the
+/// schema of a plan, e.g. `t.a`. It only keeps the fields of different relations with the
+/// same column name apart and is never parsed: the relation of a column is tracked by a
+/// [`QualifiedSchema`].
+pub(crate) fn qualified_field_name(relation: &str, name: &str) -> String {
+    format!("{}.{}", relation, name)
+}
+
+/// The name of a column in a SQL scope: the column `name`, optionally qualified by the
+/// name of the relation it belongs to
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ColumnName {
+    /// The name of the relation (table, alias or common table expression), if any
+    pub relation: Option<String>,
+    /// The name of the column in the relation
+    pub name: String,
+}
+
+impl ColumnName {
+    /// Create a column name that is not qualified by a relation
+    pub fn unqualified(name: &str) -> Self {
+        Self {
+            relation: None,
+            name: name.to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for ColumnName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.relation {
+            Some(relation) => write!(f, "{}.{}", relation, self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// The schema of a relation of a SQL query along with the name of each of its columns in
+/// the query, which SQL expressions are resolved against.
+///
+/// The name of a field only needs to be unique in the schema, while columns are
+/// referenced by their name, optionally qualified by the name of their relation. E.g.
+/// the columns `l.a` and `r.a` of a self join of `t` are the fields `l.a` and `r.a`,
+/// while the column `t.a` of a table `t` without an alias is its field `a`.
+#[derive(Debug, Clone)]
+pub(crate) struct QualifiedSchema {
+    schema: Schema,
+    columns: Vec<ColumnName>,
+}
+
+impl QualifiedSchema {
+    /// Create a scope from a schema and the names of its fields' columns
+    pub fn new(schema: Schema, columns: Vec<ColumnName>) -> Self {
+        assert_eq!(schema.fields().len(), columns.len());
+        Self { schema, columns }
+    }
+
+    /// Create a scope where the columns are named after the fields of `schema`
+    pub fn unqualified(schema: &Schema) -> Self {
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|f| ColumnName::unqualified(f.name()))
+            .collect();
+        Self::new(schema.clone(), columns)
+    }
+
+    /// The schema of the relation
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    /// The name of the column of each field of the schema
+    pub fn columns(&self) -> &[ColumnName] {
+        &self.columns
+    }
+
+    /// Returns a copy of this scope where all columns belong to `relation`
+    pub fn with_relation(&self, relation: Option<&str>) -> Self {
+        let columns = self
+            .columns
+            .iter()
+            .map(|c| ColumnName {
+                relation: relation.map(|r| r.to_owned()),
+                name: c.name.clone(),
+            })
+            .collect();
+        Self::new(self.schema.clone(), columns)
+    }
+
+    /// Returns the scope of the columns of this scope followed by the columns of `other`
+    pub fn join(&self, other: &QualifiedSchema) -> Self {
+        let mut fields = self.schema.fields().clone();
+        fields.extend_from_slice(other.schema.fields());
+        let mut columns = self.columns.clone();
+        columns.extend_from_slice(&other.columns);
+        Self::new(Schema::new(fields), columns)
+    }
+
+    /// Returns the scope of `schema`, the schema of a plan derived from this scope, where
+    /// the fields of this scope keep the name of their column and the other fields are
+    /// unqualified columns
+    pub fn qualify(&self, schema: &Schema) -> Self {
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|f| match self.column(f.name()) {
+                Some(column) => column.clone(),
+                None => ColumnName::unqualified(f.name()),
+            })
+            .collect();
+        Self::new(schema.clone(), columns)
+    }
+
+    /// Returns the name of the column of the field `field_name`, if it is in the scope
+    pub fn column(&self, field_name: &str) -> Option<&ColumnName> {
+        self.schema
+            .fields()
+            .iter()
+            .position(|f| f.name() == field_name)
+            .map(|i| &self.columns[i])
+    }
+
+    /// Whether the scope contains the columns of the relation `relation`
+    pub fn has_relation(&self, relation: &str) -> bool {
+        self.columns
+            .iter()
+            .any(|c| c.relation.as_deref() == Some(relation))
+    }
+
+    /// Returns the names of the fields of the columns of the relation `relation`
+    pub fn relation_fields(&self, relation: &str) -> Vec<&str> {
+        self.schema
+            .fields()
+            .iter()
+            .zip(&self.columns)
+            .filter(|(_, c)| c.relation.as_deref() == Some(relation))
+            .map(|(f, _)| f.name().as_str())
+            .collect()
+    }
+
+    /// Returns the name of the field of the column `name`, optionally qualified by the
+    /// name of a relation. A qualified column must belong to a relation of the scope,
+    /// while an unqualified one may belong to any relation, but must be unique.
+    pub fn resolve(&self, relation: Option<&str>, name: &str) -> Result<String> {
+        let column = ColumnName {
+            relation: relation.map(|r| r.to_owned()),
+            name: name.to_owned(),
+        };
+        if let Some(relation) = relation {
+            if !self.has_relation(relation) {
+                return Err(DataFusionError::Plan(format!(
+                    "Invalid identifier '{}': there is no relation '{}' in scope",
+                    column, relation
+                )));
+            }
+        }
+        let candidates = self
+            .schema
+            .fields()
+            .iter()
+            .zip(&self.columns)
+            .filter(|(_, c)| {
+                c.name == name && (relation.is_none() || c.relation == column.relation)
+            })
+            .collect::<Vec<_>>();
+        match candidates.len() {
+            0 => Err(DataFusionError::Plan(format!(
+                "Invalid identifier '{}' for schema {}",
+                column,
+                self.schema.to_string()
+            ))),
+            1 => Ok(candidates[0].0.name().clone()),
+            _ => Err(DataFusionError::Plan(format!(
+                "Ambiguous reference to column '{}', which could refer to any of {}",
+                column,
+                candidates
+                    .iter()
+                    .map(|(_, c)| c.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))),
+        }
+    }
+}
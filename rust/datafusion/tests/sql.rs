@@ -31,7 +31,7 @@ use arrow::{
 
 use datafusion::datasource::{csv::CsvReadOptions, MemTable};
 use datafusion::error::Result;
-use datafusion::execution::context::ExecutionContext;
+use datafusion::execution::context::{ExecutionConfig, ExecutionContext};
 use datafusion::logical_plan::LogicalPlan;
 use datafusion::prelude::create_udf;
 
@@ -624,6 +624,36 @@ async fn csv_query_group_by_string_min_max() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn csv_query_group_by_having() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    register_aggregate_csv(&mut ctx)?;
+    let sql = "SELECT c1, MIN(c12) FROM aggregate_test_100 \
+               GROUP BY c1 HAVING MAX(c12) > 0.98";
+    let mut actual = execute(&mut ctx, sql).await;
+    actual.sort();
+    let expected = vec![
+        vec!["a", "0.02182578039211991"],
+        vec!["c", "0.0494924465469434"],
+        vec!["e", "0.01479305307777301"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn csv_query_group_by_having_count() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    register_aggregate_csv(&mut ctx)?;
+    let sql = "SELECT c1, COUNT(c12) AS cnt FROM aggregate_test_100 \
+               GROUP BY c1 HAVING COUNT(c12) > 20";
+    let mut actual = execute(&mut ctx, sql).await;
+    actual.sort();
+    let expected = vec![vec!["a", "21"], vec!["c", "21"], vec!["e", "21"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
 #[tokio::test]
 async fn csv_query_cast() -> Result<()> {
     let mut ctx = ExecutionContext::new();
@@ -1132,202 +1162,833 @@ async fn equijoin_implicit_syntax_reversed() -> Result<()> {
 
 #[tokio::test]
 async fn cartesian_join() -> Result<()> {
-    let ctx = create_join_context()?;
-    let sql = "SELECT t1_id, t1_name, t2_name FROM t1, t2 ORDER BY t1_id";
-    let maybe_plan = ctx.create_logical_plan(&sql);
-    assert_eq!(
-        "This feature is not implemented: Cartesian joins are not supported",
-        &format!("{}", maybe_plan.err().unwrap())
-    );
+    let mut ctx = create_join_context()?;
+    let sql = "SELECT t1_id, t1_name, t2_name FROM t1, t2 ORDER BY t1_id, t2_name";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(16, actual.len());
+    assert_eq!(vec!["11", "a", "w"], actual[0]);
+    assert_eq!(vec!["44", "d", "z"], actual[15]);
     Ok(())
 }
 
-fn create_join_context() -> Result<ExecutionContext> {
-    let mut ctx = ExecutionContext::new();
-
-    let t1_schema = Arc::new(Schema::new(vec![
-        Field::new("t1_id", DataType::UInt32, true),
-        Field::new("t1_name", DataType::Utf8, true),
-    ]));
-    let t1_data = RecordBatch::try_new(
-        t1_schema.clone(),
-        vec![
-            Arc::new(UInt32Array::from(vec![11, 22, 33, 44])),
-            Arc::new(StringArray::from(vec![
-                Some("a"),
-                Some("b"),
-                Some("c"),
-                Some("d"),
-            ])),
-        ],
-    )?;
-    let t1_table = MemTable::new(t1_schema, vec![vec![t1_data]])?;
-    ctx.register_table("t1", Box::new(t1_table));
-
-    let t2_schema = Arc::new(Schema::new(vec![
-        Field::new("t2_id", DataType::UInt32, true),
-        Field::new("t2_name", DataType::Utf8, true),
-    ]));
-    let t2_data = RecordBatch::try_new(
-        t2_schema.clone(),
-        vec![
-            Arc::new(UInt32Array::from(vec![11, 22, 44, 55])),
-            Arc::new(StringArray::from(vec![
-                Some("z"),
-                Some("y"),
-                Some("x"),
-                Some("w"),
-            ])),
-        ],
-    )?;
-    let t2_table = MemTable::new(t2_schema, vec![vec![t2_data]])?;
-    ctx.register_table("t2", Box::new(t2_table));
-
-    Ok(ctx)
-}
-
 #[tokio::test]
-async fn csv_explain() {
-    let mut ctx = ExecutionContext::new();
-    register_aggregate_csv_by_sql(&mut ctx).await;
-    let sql = "EXPLAIN SELECT c1 FROM aggregate_test_100 where c2 > 10";
+async fn cross_join_with_filter() -> Result<()> {
+    let mut ctx = create_join_context()?;
+    let sql = "SELECT t1_id, t2_id FROM t1 CROSS JOIN t2 WHERE t1_id > t2_id ORDER BY t1_id, t2_id";
     let actual = execute(&mut ctx, sql).await;
     let expected = vec![
-        vec![
-            "logical_plan",
-            "Projection: #c1\n  Filter: #c2 Gt Int64(10)\n    TableScan: aggregate_test_100 projection=None"
-        ]
+        vec!["22", "11"],
+        vec!["33", "11"],
+        vec!["33", "22"],
+        vec!["44", "11"],
+        vec!["44", "22"],
+        vec!["44", "33"],
     ];
     assert_eq!(expected, actual);
+    Ok(())
+}
 
-    // Also, expect same result with lowercase explain
-    let sql = "explain SELECT c1 FROM aggregate_test_100 where c2 > 10";
+#[tokio::test]
+async fn full_outer_join() -> Result<()> {
+    let mut ctx = create_join_context()?;
+    let sql =
+        "SELECT t1_id, t1_name, t2_name FROM t1 FULL OUTER JOIN t2 ON t1_id = t2_id \
+               ORDER BY t1_id";
     let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["NULL", "NULL", "w"],
+        vec!["11", "a", "z"],
+        vec!["22", "b", "y"],
+        vec!["33", "c", "NULL"],
+        vec!["44", "d", "x"],
+    ];
     assert_eq!(expected, actual);
+    Ok(())
 }
 
 #[tokio::test]
-async fn csv_explain_verbose() {
-    let mut ctx = ExecutionContext::new();
-    register_aggregate_csv_by_sql(&mut ctx).await;
-    let sql = "EXPLAIN VERBOSE SELECT c1 FROM aggregate_test_100 where c2 > 10";
+async fn equijoin_with_filter() -> Result<()> {
+    let mut ctx = create_join_context()?;
+    let sql = "SELECT t1_id, t2_id FROM t1 JOIN t2 ON t1_id = t2_id AND t1_name <> 'b' \
+               ORDER BY t1_id";
     let actual = execute(&mut ctx, sql).await;
-
-    // flatten to a single string
-    let actual = actual.into_iter().map(|r| r.join("\t")).collect::<String>();
-
-    // Don't actually test the contents of the debuging output (as
-    // that may change and keeping this test updated will be a
-    // pain). Instead just check for a few key pieces.
-    assert!(actual.contains("logical_plan"), "Actual: '{}'", actual);
-    assert!(actual.contains("physical_plan"), "Actual: '{}'", actual);
-    assert!(actual.contains("#c2 Gt Int64(10)"), "Actual: '{}'", actual);
+    let expected = vec![vec!["11", "11"], vec!["44", "44"]];
+    assert_eq!(expected, actual);
+    Ok(())
 }
 
-fn aggr_test_schema() -> SchemaRef {
-    Arc::new(Schema::new(vec![
-        Field::new("c1", DataType::Utf8, false),
-        Field::new("c2", DataType::UInt32, false),
-        Field::new("c3", DataType::Int8, false),
-        Field::new("c4", DataType::Int16, false),
-        Field::new("c5", DataType::Int32, false),
-        Field::new("c6", DataType::Int64, false),
-        Field::new("c7", DataType::UInt8, false),
-        Field::new("c8", DataType::UInt16, false),
-        Field::new("c9", DataType::UInt32, false),
-        Field::new("c10", DataType::UInt64, false),
-        Field::new("c11", DataType::Float32, false),
-        Field::new("c12", DataType::Float64, false),
-        Field::new("c13", DataType::Utf8, false),
-    ]))
+#[tokio::test]
+async fn left_join_with_filter() -> Result<()> {
+    let mut ctx = create_join_context()?;
+    // the rows of t1 whose match does not satisfy the filter are padded with nulls
+    let sql = "SELECT t1_id, t1_name, t2_name FROM t1 \
+               LEFT JOIN t2 ON t1_id = t2_id AND t2_name <> 'y' \
+               ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["11", "a", "z"],
+        vec!["22", "b", "NULL"],
+        vec!["33", "c", "NULL"],
+        vec!["44", "d", "x"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
 }
 
-async fn register_aggregate_csv_by_sql(ctx: &mut ExecutionContext) {
-    let testdata = env::var("ARROW_TEST_DATA").expect("ARROW_TEST_DATA not defined");
+#[tokio::test]
+async fn join_using() -> Result<()> {
+    let mut ctx = create_join_context()?;
+    let ctes = "WITH a AS (SELECT t1_id AS id, t1_name FROM t1), \
+                b AS (SELECT t2_id AS id, t2_name FROM t2) ";
 
-    // TODO: The following c9 should be migrated to UInt32 and c10 should be UInt64 once
-    // unsigned is supported.
-    let df = ctx
-        .sql(&format!(
-            "
-    CREATE EXTERNAL TABLE aggregate_test_100 (
-        c1  VARCHAR NOT NULL,
-        c2  INT NOT NULL,
-        c3  SMALLINT NOT NULL,
-        c4  SMALLINT NOT NULL,
-        c5  INT NOT NULL,
-        c6  BIGINT NOT NULL,
-        c7  SMALLINT NOT NULL,
-        c8  INT NOT NULL,
-        c9  BIGINT NOT NULL,
-        c10 VARCHAR NOT NULL,
-        c11 FLOAT NOT NULL,
-        c12 DOUBLE NOT NULL,
-        c13 VARCHAR NOT NULL
-    )
-    STORED AS CSV
-    WITH HEADER ROW
-    LOCATION '{}/csv/aggregate_test_100.csv'
-    ",
-            testdata
-        ))
-        .expect("Creating dataframe for CREATE EXTERNAL TABLE");
+    let sql = format!(
+        "{}SELECT id, t1_name, t2_name FROM a JOIN b USING (id) ORDER BY id",
+        ctes
+    );
+    let actual = execute(&mut ctx, &sql).await;
+    let expected = vec![
+        vec!["11", "a", "z"],
+        vec!["22", "b", "y"],
+        vec!["44", "d", "x"],
+    ];
+    assert_eq!(expected, actual);
 
-    // Mimic the CLI and execute the resulting plan -- even though it
-    // is effectively a no-op (returns zero rows)
-    let results = df.collect().await.expect("Executing CREATE EXTERNAL TABLE");
-    assert!(
-        results.is_empty(),
-        "Expected no rows from executing CREATE EXTERNAL TABLE"
+    let sql = format!(
+        "{}SELECT id, t1_name, t2_name FROM a LEFT JOIN b USING (id) ORDER BY id",
+        ctes
     );
-}
+    let actual = execute(&mut ctx, &sql).await;
+    let expected = vec![
+        vec!["11", "a", "z"],
+        vec!["22", "b", "y"],
+        vec!["33", "c", "NULL"],
+        vec!["44", "d", "x"],
+    ];
+    assert_eq!(expected, actual);
 
-fn register_aggregate_csv(ctx: &mut ExecutionContext) -> Result<()> {
-    let testdata = env::var("ARROW_TEST_DATA").expect("ARROW_TEST_DATA not defined");
-    let schema = aggr_test_schema();
-    ctx.register_csv(
-        "aggregate_test_100",
-        &format!("{}/csv/aggregate_test_100.csv", testdata),
-        CsvReadOptions::new().schema(&schema),
-    )?;
+    let sql = format!(
+        "{}SELECT id, t1_name, t2_name FROM a RIGHT JOIN b USING (id) ORDER BY id",
+        ctes
+    );
+    let actual = execute(&mut ctx, &sql).await;
+    let expected = vec![
+        vec!["11", "a", "z"],
+        vec!["22", "b", "y"],
+        vec!["44", "d", "x"],
+        vec!["55", "NULL", "w"],
+    ];
+    assert_eq!(expected, actual);
     Ok(())
 }
 
-fn register_alltypes_parquet(ctx: &mut ExecutionContext) {
-    let testdata = env::var("PARQUET_TEST_DATA").expect("PARQUET_TEST_DATA not defined");
-    ctx.register_parquet(
-        "alltypes_plain",
-        &format!("{}/alltypes_plain.parquet", testdata),
-    )
-    .unwrap();
+#[tokio::test]
+async fn natural_join() -> Result<()> {
+    let mut ctx = create_join_context()?;
+    let sql = "SELECT id, t1_name, t2_name \
+               FROM (SELECT t1_id AS id, t1_name FROM t1) AS a \
+               NATURAL JOIN (SELECT t2_id AS id, t2_name FROM t2) AS b \
+               ORDER BY id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["11", "a", "z"],
+        vec!["22", "b", "y"],
+        vec!["44", "d", "x"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
 }
 
-/// Execute query and return result set as 2-d table of Vecs
-/// `result[row][column]`
-async fn execute(ctx: &mut ExecutionContext, sql: &str) -> Vec<Vec<String>> {
-    let msg = format!("Creating logical plan for '{}'", sql);
-    let plan = ctx.create_logical_plan(&sql).expect(&msg);
-    let logical_schema = plan.schema();
-
-    let msg = format!("Optimizing logical plan for '{}': {:?}", sql, plan);
-    let plan = ctx.optimize(&plan).expect(&msg);
-    let optimized_logical_schema = plan.schema();
+#[tokio::test]
+async fn in_list() -> Result<()> {
+    let mut ctx = create_join_context()?;
+    let sql = "SELECT t1_id FROM t1 WHERE t1_name IN ('a', 'c', 'e') ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["11"], vec!["33"]];
+    assert_eq!(expected, actual);
 
-    let msg = format!("Creating physical plan for '{}': {:?}", sql, plan);
-    let plan = ctx.create_physical_plan(&plan).expect(&msg);
-    let physical_schema = plan.schema();
+    let sql = "SELECT t1_id FROM t1 WHERE t1_name NOT IN ('a', 'c', 'e') ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["22"], vec!["44"]];
+    assert_eq!(expected, actual);
 
-    let msg = format!("Executing physical plan for '{}': {:?}", sql, plan);
-    let results = ctx.collect(plan).await.expect(&msg);
+    // long lists are evaluated with a hash set
+    let sql = "SELECT t1_id FROM t1 \
+               WHERE t1_id IN (1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 22, 44) ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["22"], vec!["44"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
 
-    assert_eq!(logical_schema.as_ref(), optimized_logical_schema.as_ref());
-    assert_eq!(logical_schema.as_ref(), physical_schema.as_ref());
+#[tokio::test]
+async fn between() -> Result<()> {
+    let mut ctx = create_join_context()?;
+    let sql = "SELECT t1_id FROM t1 WHERE t1_id BETWEEN 22 AND 40 ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["22"], vec!["33"]];
+    assert_eq!(expected, actual);
 
-    result_vec(&results)
+    let sql = "SELECT t1_id FROM t1 WHERE t1_id NOT BETWEEN 22 AND 40 ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["11"], vec!["44"]];
+    assert_eq!(expected, actual);
+    Ok(())
 }
 
-/// Specialised String representation
-fn col_str(column: &ArrayRef, row_index: usize) -> String {
+#[tokio::test]
+async fn exists_subquery() -> Result<()> {
+    let mut ctx = create_join_context()?;
+    let sql = "SELECT t1_id, t1_name FROM t1 \
+               WHERE EXISTS (SELECT * FROM t2 WHERE t2_id = t1_id) ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["11", "a"], vec!["22", "b"], vec!["44", "d"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT t1_id, t1_name FROM t1 \
+               WHERE NOT EXISTS (SELECT * FROM t2 WHERE t2_id = t1_id) ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["33", "c"]];
+    assert_eq!(expected, actual);
+
+    // an uncorrelated subquery keeps either all rows or none
+    let sql = "SELECT t1_id FROM t1 \
+               WHERE NOT EXISTS (SELECT * FROM t2 WHERE t2_id > 100) ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["11"], vec!["22"], vec!["33"], vec!["44"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn in_subquery() -> Result<()> {
+    let mut ctx = create_join_context()?;
+    let sql = "SELECT t1_id FROM t1 \
+               WHERE t1_id IN (SELECT t2_id FROM t2 WHERE t2_name <> 'x') ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["11"], vec!["22"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT t1_id FROM t1 \
+               WHERE t1_id NOT IN (SELECT t2_id FROM t2 WHERE t2_name <> 'x') \
+               ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["33"], vec!["44"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn scalar_subquery() -> Result<()> {
+    let mut ctx = create_join_context()?;
+    let sql = "SELECT t1_id, t1_name FROM t1 \
+               WHERE t1_id > (SELECT MIN(t2_id) FROM t2 WHERE t2_name = 'y') \
+               ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["33", "c"], vec!["44", "d"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn correlated_scalar_subquery() -> Result<()> {
+    let mut ctx = create_join_context()?;
+    // the subquery of t1_id = 33 has no rows, which is NULL
+    let sql = "SELECT t1_id FROM t1 \
+               WHERE t1_id = (SELECT MAX(t2_id) FROM t2 WHERE t2_id = t1_id) \
+               ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["11"], vec!["22"], vec!["44"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn correlated_count_subquery() -> Result<()> {
+    let mut ctx = create_join_context()?;
+    // the subquery of t1_id = 33 has no rows, whose count is 0
+    let sql = "SELECT t1_id FROM t1 \
+               WHERE (SELECT COUNT(*) FROM t2 WHERE t2_id = t1_id) = 0";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["33"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn scalar_subquery_of_many_rows() -> Result<()> {
+    let mut ctx = create_join_context()?;
+    let sql =
+        "SELECT t1_id FROM t1 WHERE t1_id > (SELECT t2_id FROM t2 WHERE t2_id > 50)";
+    let actual = execute(&mut ctx, sql).await;
+    assert!(actual.is_empty());
+
+    // the value of a subquery without rows is null
+    let sql = "SELECT t1_id FROM t1 \
+               WHERE (SELECT t2_id FROM t2 WHERE t2_id > 60) IS NULL ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["11"], vec!["22"], vec!["33"], vec!["44"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT t1_id FROM t1 WHERE t1_id > (SELECT t2_id FROM t2)";
+    let plan = ctx.create_logical_plan(sql)?;
+    let plan = ctx.optimize(&plan)?;
+    let plan = ctx.create_physical_plan(&plan)?;
+    let err = ctx
+        .collect(plan)
+        .await
+        .expect_err("query should have failed");
+    assert!(err
+        .to_string()
+        .contains("More than one row returned by a subquery used as an expression"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn not_in_subquery_with_nulls() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, true)]));
+    for (name, values) in vec![
+        ("t", vec![Some(1), Some(2), None]),
+        ("with_nulls", vec![Some(1), None]),
+        ("without_nulls", vec![Some(1), Some(3)]),
+    ] {
+        let data = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(values))],
+        )?;
+        let table = MemTable::new(schema.clone(), vec![vec![data]])?;
+        ctx.register_table(name, Box::new(table));
+    }
+
+    // 2 NOT IN (1, NULL) is null
+    let sql = "SELECT id FROM t WHERE id NOT IN (SELECT id FROM with_nulls)";
+    let actual = execute(&mut ctx, sql).await;
+    assert!(actual.is_empty());
+
+    // NULL NOT IN (1, 3) is null
+    let sql = "SELECT id FROM t WHERE id NOT IN (SELECT id FROM without_nulls)";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["2"]];
+    assert_eq!(expected, actual);
+
+    // NOT IN of no rows holds for NULL
+    let sql = "SELECT id FROM t \
+               WHERE id NOT IN (SELECT id FROM with_nulls WHERE id > 1) ORDER BY id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["NULL"], vec!["1"], vec!["2"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT id FROM t \
+               WHERE id NOT IN (SELECT w.id FROM without_nulls w WHERE w.id = t.id)";
+    let err = ctx
+        .create_logical_plan(sql)
+        .expect_err("query should have failed");
+    assert_eq!(
+        "Error during planning: NOT IN with a correlated subquery requires that neither \
+         id nor the column of the subquery is nullable",
+        err.to_string()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn union_all() -> Result<()> {
+    let mut ctx = create_join_context()?;
+    let sql = "SELECT t1_id FROM t1 UNION ALL SELECT t2_id FROM t2 ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["11"],
+        vec!["11"],
+        vec!["22"],
+        vec!["22"],
+        vec!["33"],
+        vec!["44"],
+        vec!["44"],
+        vec!["55"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn union_distinct() -> Result<()> {
+    let mut ctx = create_join_context()?;
+    let sql = "SELECT t1_id FROM t1 UNION SELECT t2_id FROM t2 ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["11"], vec!["22"], vec!["33"], vec!["44"], vec!["55"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn intersect() -> Result<()> {
+    let mut ctx = create_join_context()?;
+    let sql = "SELECT t1_id FROM t1 INTERSECT SELECT t2_id FROM t2 ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["11"], vec!["22"], vec!["44"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn except() -> Result<()> {
+    let mut ctx = create_join_context()?;
+    let sql = "SELECT t1_id FROM t1 EXCEPT SELECT t2_id FROM t2";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["33"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT t2_id, t2_name FROM t2 EXCEPT SELECT t1_id, t1_name FROM t1 \
+               ORDER BY t2_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["11", "z"],
+        vec!["22", "y"],
+        vec!["44", "x"],
+        vec!["55", "w"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn with_cte() -> Result<()> {
+    let mut ctx = create_join_context()?;
+    let sql = "WITH ids AS (SELECT t1_id AS id FROM t1), \
+               large_ids AS (SELECT id FROM ids WHERE id > 20) \
+               SELECT id FROM large_ids ORDER BY id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["22"], vec!["33"], vec!["44"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn with_cte_used_twice() -> Result<()> {
+    let mut ctx = create_join_context()?;
+    let sql = "WITH names(id, name) AS (SELECT t1_id, t1_name FROM t1) \
+               SELECT id, name FROM names WHERE id < 20 \
+               UNION ALL SELECT id, name FROM names WHERE id > 40 \
+               ORDER BY id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["11", "a"], vec!["44", "d"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn self_join_with_aliases() -> Result<()> {
+    let mut ctx = create_join_context()?;
+    let sql = "SELECT a.t1_id, b.t1_name FROM t1 AS a JOIN t1 AS b ON a.t1_id = b.t1_id \
+               ORDER BY a.t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["11", "a"],
+        vec!["22", "b"],
+        vec!["33", "c"],
+        vec!["44", "d"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn equijoin_with_qualified_columns() -> Result<()> {
+    let mut ctx = create_join_context()?;
+    let sql = "SELECT t1.t1_id, t2.t2_name FROM t1 JOIN t2 ON t2.t2_id = t1.t1_id \
+               ORDER BY t1.t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["11", "z"], vec!["22", "y"], vec!["44", "x"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn window_running_total() -> Result<()> {
+    let mut ctx = create_sales_context()?;
+    // the rows with the same amount are peers, which are summed together
+    let sql = "SELECT region, amount, \
+               SUM(amount) OVER (PARTITION BY region ORDER BY amount) AS total \
+               FROM sales ORDER BY region, amount";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["east", "10", "10"],
+        vec!["east", "30", "70"],
+        vec!["east", "30", "70"],
+        vec!["east", "60", "130"],
+        vec!["west", "20", "20"],
+        vec!["west", "50", "70"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn window_ranks() -> Result<()> {
+    let mut ctx = create_sales_context()?;
+    let sql = "SELECT region, amount, \
+               ROW_NUMBER() OVER (PARTITION BY region ORDER BY amount DESC) AS rn, \
+               RANK() OVER (PARTITION BY region ORDER BY amount DESC) AS r, \
+               DENSE_RANK() OVER (PARTITION BY region ORDER BY amount DESC) AS dr \
+               FROM sales ORDER BY region, rn";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["east", "60", "1", "1", "1"],
+        vec!["east", "30", "2", "2", "2"],
+        vec!["east", "30", "3", "2", "2"],
+        vec!["east", "10", "4", "4", "3"],
+        vec!["west", "50", "1", "1", "1"],
+        vec!["west", "20", "2", "2", "2"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn window_top_n_per_group() -> Result<()> {
+    let mut ctx = create_sales_context()?;
+    let sql = "SELECT region, amount FROM (\
+                   SELECT region, amount, \
+                   ROW_NUMBER() OVER (PARTITION BY region ORDER BY amount DESC) AS rn \
+                   FROM sales) AS s \
+               WHERE rn <= 2 ORDER BY region, amount";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["east", "30"],
+        vec!["east", "60"],
+        vec!["west", "20"],
+        vec!["west", "50"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn window_lag_and_lead() -> Result<()> {
+    let mut ctx = create_join_context()?;
+    let sql = "SELECT t1_id, LAG(t1_id) OVER (ORDER BY t1_id), \
+               LEAD(t1_id, 2, 0) OVER (ORDER BY t1_id) FROM t1 ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["11", "NULL", "33"],
+        vec!["22", "11", "44"],
+        vec!["33", "22", "0"],
+        vec!["44", "33", "0"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn window_frames() -> Result<()> {
+    let mut ctx = create_join_context()?;
+    let sql = "SELECT t1_id, \
+               FIRST_VALUE(t1_name) OVER (ORDER BY t1_id ROWS BETWEEN 1 PRECEDING AND 1 FOLLOWING), \
+               LAST_VALUE(t1_name) OVER (ORDER BY t1_id ROWS BETWEEN 1 PRECEDING AND 1 FOLLOWING), \
+               COUNT(*) OVER (), \
+               SUM(t1_id) OVER (ORDER BY t1_id RANGE BETWEEN 11 PRECEDING AND 11 FOLLOWING) \
+               FROM t1 ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["11", "a", "b", "4", "33"],
+        vec!["22", "a", "c", "4", "66"],
+        vec!["33", "b", "d", "4", "99"],
+        vec!["44", "c", "d", "4", "77"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn order_by_limit() -> Result<()> {
+    let mut ctx = create_sales_context()?;
+    let sql = "SELECT region, amount FROM sales ORDER BY amount DESC, region LIMIT 3";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["east", "60"], vec!["west", "50"], vec!["east", "30"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn order_by_partitioned() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+    let partitions = vec![vec![5, 1, 9], vec![3, 7], vec![8, 2]]
+        .into_iter()
+        .map(|values| {
+            Ok(vec![RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(Int32Array::from(values))],
+            )?])
+        })
+        .collect::<Result<Vec<_>>>()?;
+    ctx.register_table("t", Box::new(MemTable::new(schema, partitions)?));
+
+    let sql = "SELECT a FROM t ORDER BY a DESC";
+    // the partitions are sorted in parallel and then merged
+    let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+    let plan = ctx.create_physical_plan(&plan)?;
+    assert!(format!("{:?}", plan).contains("SortPreservingMergeExec"));
+
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["9"],
+        vec!["8"],
+        vec!["7"],
+        vec!["5"],
+        vec!["3"],
+        vec!["2"],
+        vec!["1"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+fn create_join_context() -> Result<ExecutionContext> {
+    let mut ctx = ExecutionContext::new();
+
+    let t1_schema = Arc::new(Schema::new(vec![
+        Field::new("t1_id", DataType::UInt32, true),
+        Field::new("t1_name", DataType::Utf8, true),
+    ]));
+    let t1_data = RecordBatch::try_new(
+        t1_schema.clone(),
+        vec![
+            Arc::new(UInt32Array::from(vec![11, 22, 33, 44])),
+            Arc::new(StringArray::from(vec![
+                Some("a"),
+                Some("b"),
+                Some("c"),
+                Some("d"),
+            ])),
+        ],
+    )?;
+    let t1_table = MemTable::new(t1_schema, vec![vec![t1_data]])?;
+    ctx.register_table("t1", Box::new(t1_table));
+
+    let t2_schema = Arc::new(Schema::new(vec![
+        Field::new("t2_id", DataType::UInt32, true),
+        Field::new("t2_name", DataType::Utf8, true),
+    ]));
+    let t2_data = RecordBatch::try_new(
+        t2_schema.clone(),
+        vec![
+            Arc::new(UInt32Array::from(vec![11, 22, 44, 55])),
+            Arc::new(StringArray::from(vec![
+                Some("z"),
+                Some("y"),
+                Some("x"),
+                Some("w"),
+            ])),
+        ],
+    )?;
+    let t2_table = MemTable::new(t2_schema, vec![vec![t2_data]])?;
+    ctx.register_table("t2", Box::new(t2_table));
+
+    Ok(ctx)
+}
+
+fn create_sales_context() -> Result<ExecutionContext> {
+    let mut ctx = ExecutionContext::new();
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("region", DataType::Utf8, false),
+        Field::new("amount", DataType::UInt32, false),
+    ]));
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(vec![
+                "east", "west", "east", "east", "west", "east",
+            ])),
+            Arc::new(UInt32Array::from(vec![10, 20, 30, 30, 50, 60])),
+        ],
+    )?;
+    let table = MemTable::new(schema, vec![vec![data]])?;
+    ctx.register_table("sales", Box::new(table));
+
+    Ok(ctx)
+}
+
+#[tokio::test]
+async fn csv_explain() {
+    let mut ctx = ExecutionContext::new();
+    register_aggregate_csv_by_sql(&mut ctx).await;
+    let sql = "EXPLAIN SELECT c1 FROM aggregate_test_100 where c2 > 10";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec![
+            "logical_plan",
+            "Projection: #c1\n  Filter: #c2 Gt Int64(10)\n    TableScan: aggregate_test_100 projection=None"
+        ]
+    ];
+    assert_eq!(expected, actual);
+
+    // Also, expect same result with lowercase explain
+    let sql = "explain SELECT c1 FROM aggregate_test_100 where c2 > 10";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(expected, actual);
+}
+
+#[tokio::test]
+async fn csv_explain_verbose() {
+    let mut ctx = ExecutionContext::new();
+    register_aggregate_csv_by_sql(&mut ctx).await;
+    let sql = "EXPLAIN VERBOSE SELECT c1 FROM aggregate_test_100 where c2 > 10";
+    let actual = execute(&mut ctx, sql).await;
+
+    // flatten to a single string
+    let actual = actual.into_iter().map(|r| r.join("\t")).collect::<String>();
+
+    // Don't actually test the contents of the debuging output (as
+    // that may change and keeping this test updated will be a
+    // pain). Instead just check for a few key pieces.
+    assert!(actual.contains("logical_plan"), "Actual: '{}'", actual);
+    assert!(actual.contains("physical_plan"), "Actual: '{}'", actual);
+    assert!(actual.contains("#c2 Gt Int64(10)"), "Actual: '{}'", actual);
+}
+
+#[tokio::test]
+async fn explain_analyze() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+    let partitions = vec![vec![5, 1, 9], vec![3, 7]]
+        .into_iter()
+        .map(|values| {
+            Ok(vec![RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(Int32Array::from(values))],
+            )?])
+        })
+        .collect::<Result<Vec<_>>>()?;
+    ctx.register_table("t", Box::new(MemTable::new(schema, partitions)?));
+
+    let sql = "EXPLAIN ANALYZE SELECT a FROM t WHERE a > 2 ORDER BY a";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(actual.len(), 2);
+    assert_eq!(actual[0][0], "logical_plan");
+    assert_eq!(actual[1][0], "physical_plan_with_metrics");
+
+    // the metrics of each plan, from the root down
+    let analyzed = &actual[1][1];
+    assert!(analyzed.contains("output_rows=4"), "Actual: '{}'", analyzed);
+    assert!(
+        analyzed.contains("FilterExec: output_rows=4"),
+        "Actual: '{}'",
+        analyzed
+    );
+    assert!(
+        analyzed.contains("MemoryExec: output_rows=5"),
+        "Actual: '{}'",
+        analyzed
+    );
+    Ok(())
+}
+
+fn aggr_test_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("c1", DataType::Utf8, false),
+        Field::new("c2", DataType::UInt32, false),
+        Field::new("c3", DataType::Int8, false),
+        Field::new("c4", DataType::Int16, false),
+        Field::new("c5", DataType::Int32, false),
+        Field::new("c6", DataType::Int64, false),
+        Field::new("c7", DataType::UInt8, false),
+        Field::new("c8", DataType::UInt16, false),
+        Field::new("c9", DataType::UInt32, false),
+        Field::new("c10", DataType::UInt64, false),
+        Field::new("c11", DataType::Float32, false),
+        Field::new("c12", DataType::Float64, false),
+        Field::new("c13", DataType::Utf8, false),
+    ]))
+}
+
+async fn register_aggregate_csv_by_sql(ctx: &mut ExecutionContext) {
+    let testdata = env::var("ARROW_TEST_DATA").expect("ARROW_TEST_DATA not defined");
+
+    // TODO: The following c9 should be migrated to UInt32 and c10 should be UInt64 once
+    // unsigned is supported.
+    let df = ctx
+        .sql(&format!(
+            "
+    CREATE EXTERNAL TABLE aggregate_test_100 (
+        c1  VARCHAR NOT NULL,
+        c2  INT NOT NULL,
+        c3  SMALLINT NOT NULL,
+        c4  SMALLINT NOT NULL,
+        c5  INT NOT NULL,
+        c6  BIGINT NOT NULL,
+        c7  SMALLINT NOT NULL,
+        c8  INT NOT NULL,
+        c9  BIGINT NOT NULL,
+        c10 VARCHAR NOT NULL,
+        c11 FLOAT NOT NULL,
+        c12 DOUBLE NOT NULL,
+        c13 VARCHAR NOT NULL
+    )
+    STORED AS CSV
+    WITH HEADER ROW
+    LOCATION '{}/csv/aggregate_test_100.csv'
+    ",
+            testdata
+        ))
+        .expect("Creating dataframe for CREATE EXTERNAL TABLE");
+
+    // Mimic the CLI and execute the resulting plan -- even though it
+    // is effectively a no-op (returns zero rows)
+    let results = df.collect().await.expect("Executing CREATE EXTERNAL TABLE");
+    assert!(
+        results.is_empty(),
+        "Expected no rows from executing CREATE EXTERNAL TABLE"
+    );
+}
+
+fn register_aggregate_csv(ctx: &mut ExecutionContext) -> Result<()> {
+    let testdata = env::var("ARROW_TEST_DATA").expect("ARROW_TEST_DATA not defined");
+    let schema = aggr_test_schema();
+    ctx.register_csv(
+        "aggregate_test_100",
+        &format!("{}/csv/aggregate_test_100.csv", testdata),
+        CsvReadOptions::new().schema(&schema),
+    )?;
+    Ok(())
+}
+
+fn register_alltypes_parquet(ctx: &mut ExecutionContext) {
+    let testdata = env::var("PARQUET_TEST_DATA").expect("PARQUET_TEST_DATA not defined");
+    ctx.register_parquet(
+        "alltypes_plain",
+        &format!("{}/alltypes_plain.parquet", testdata),
+    )
+    .unwrap();
+}
+
+/// Execute query and return result set as 2-d table of Vecs
+/// `result[row][column]`
+async fn execute(ctx: &mut ExecutionContext, sql: &str) -> Vec<Vec<String>> {
+    let msg = format!("Creating logical plan for '{}'", sql);
+    let plan = ctx.create_logical_plan(&sql).expect(&msg);
+    let logical_schema = plan.schema();
+
+    let msg = format!("Optimizing logical plan for '{}': {:?}", sql, plan);
+    let plan = ctx.optimize(&plan).expect(&msg);
+    let optimized_logical_schema = plan.schema();
+
+    let msg = format!("Creating physical plan for '{}': {:?}", sql, plan);
+    let plan = ctx.create_physical_plan(&plan).expect(&msg);
+    let physical_schema = plan.schema();
+
+    let msg = format!("Executing physical plan for '{}': {:?}", sql, plan);
+    let results = ctx.collect(plan).await.expect(&msg);
+
+    assert_eq!(logical_schema.as_ref(), optimized_logical_schema.as_ref());
+    assert_eq!(logical_schema.as_ref(), physical_schema.as_ref());
+
+    result_vec(&results)
+}
+
+/// Specialised String representation
+fn col_str(column: &ArrayRef, row_index: usize) -> String {
     if column.is_null(row_index) {
         return "NULL".to_string();
     }
@@ -1400,6 +2061,42 @@ async fn query_large_length() -> Result<()> {
     generic_query_length::<LargeStringArray>(DataType::LargeUtf8).await
 }
 
+async fn generic_query_string_functions<T: 'static + Array + From<Vec<&'static str>>>(
+    datatype: DataType,
+) -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![Field::new("c1", datatype, false)]));
+
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(T::from(vec!["a,b", "foo", ""]))],
+    )?;
+
+    let table = MemTable::new(schema, vec![vec![data]])?;
+
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("test", Box::new(table));
+    let sql = "SELECT upper(c1), lpad(c1, 4, '*'), split_part(c1, ',', 2), \
+               strpos(c1, ',') FROM test";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["A,B", "*a,b", "b", "2"],
+        vec!["FOO", "*foo", "", "0"],
+        vec!["", "****", "", "0"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_string_functions() -> Result<()> {
+    generic_query_string_functions::<StringArray>(DataType::Utf8).await
+}
+
+#[tokio::test]
+async fn query_large_string_functions() -> Result<()> {
+    generic_query_string_functions::<LargeStringArray>(DataType::LargeUtf8).await
+}
+
 #[tokio::test]
 async fn query_not() -> Result<()> {
     let schema = Arc::new(Schema::new(vec![Field::new("c1", DataType::Boolean, true)]));
@@ -1545,6 +2242,55 @@ async fn to_timstamp() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn query_date_time_functions() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("ts_data", make_timestamp_nano_table()?);
+
+    let sql = "SELECT date_trunc('hour', ts), EXTRACT(HOUR FROM ts), \
+               date_part('minute', ts), date_part('dow', ts), to_date('2021-02-03') \
+               FROM ts_data WHERE value = 1";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["1599570000000000000", "13", "42", "2", "18661"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_timestamp_interval_arithmetic() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("ts_data", make_timestamp_nano_table()?);
+
+    let sql = "SELECT ts + INTERVAL '1 day', ts - INTERVAL '1' MONTH, \
+               ts + INTERVAL '2 hours 30 minutes' FROM ts_data WHERE value = 1";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec![
+        "1599658949190855000",
+        "1596894149190855000",
+        "1599581549190855000",
+    ]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT COUNT(*) FROM ts_data \
+               WHERE ts > to_timestamp('2020-09-08T12:00:00+00:00') - INTERVAL '1 hour'";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(vec![vec!["3"]], actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_now_is_constant() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("ts_data", make_timestamp_nano_table()?);
+
+    let actual = execute(&mut ctx, "SELECT now(), now() FROM ts_data").await;
+    assert_eq!(actual.len(), 3);
+    assert!(actual
+        .iter()
+        .all(|row| row[0] == actual[0][0] && row[1] == row[0]));
+    Ok(())
+}
+
 #[tokio::test]
 async fn query_is_null() -> Result<()> {
     let schema = Arc::new(Schema::new(vec![Field::new("c1", DataType::Float64, true)]));
@@ -1620,6 +2366,291 @@ async fn query_count_distinct() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn query_statistical_aggregates() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("g", DataType::Utf8, false),
+        Field::new("x", DataType::Float64, true),
+        Field::new("y", DataType::Int32, true),
+        Field::new("b", DataType::Boolean, true),
+        Field::new("s", DataType::Utf8, true),
+    ]));
+
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(vec!["a", "a", "a", "b", "b"])),
+            Arc::new(Float64Array::from(vec![
+                Some(1.0),
+                Some(2.0),
+                Some(3.0),
+                Some(10.0),
+                None,
+            ])),
+            Arc::new(Int32Array::from(vec![2, 4, 6, 5, 7])),
+            Arc::new(BooleanArray::from(vec![
+                Some(true),
+                Some(true),
+                Some(false),
+                Some(true),
+                None,
+            ])),
+            Arc::new(StringArray::from(vec![
+                Some("p"),
+                Some("q"),
+                Some("r"),
+                Some("s"),
+                None,
+            ])),
+        ],
+    )?;
+
+    let table = MemTable::new(schema, vec![vec![data]])?;
+
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("test", Box::new(table));
+
+    let sql = "SELECT g, VARIANCE(x), STDDEV(x), COVAR(x, y), CORR(x, y), MEDIAN(x) \
+               FROM test GROUP BY g ORDER BY g";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["a", "1", "1", "2", "1", "2"],
+        vec!["b", "NULL", "NULL", "NULL", "NULL", "10"],
+    ];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT g, APPROX_DISTINCT(y), APPROX_PERCENTILE(x, 0.5), ARRAY_AGG(y), \
+               BOOL_AND(b), BOOL_OR(b), STRING_AGG(s, ',') \
+               FROM test GROUP BY g ORDER BY g";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["a", "3", "2", "[2, 4, 6]", "false", "true", "p,q,r"],
+        vec!["b", "2", "10", "[5, 7]", "true", "true", "s"],
+    ];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT VAR_POP(y), STDDEV_POP(y), COVAR_POP(y, y) FROM test";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["2.96", "1.7204650534085253", "2.96"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_decimal() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("g", DataType::Utf8, false),
+        Field::new("d", DataType::Decimal(10, 2), true),
+        Field::new("i", DataType::Int32, false),
+    ]));
+
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(vec!["a", "a", "a", "b", "b"])),
+            Arc::new(DecimalArray::from_opt_vec(
+                vec![Some(125), Some(250), None, Some(-75), Some(1000)],
+                10,
+                2,
+            )),
+            Arc::new(Int32Array::from(vec![1, 2, 3, 4, 5])),
+        ],
+    )?;
+
+    let table = MemTable::new(schema, vec![vec![data]])?;
+
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("test", Box::new(table));
+
+    let sql = "SELECT g, SUM(d), AVG(d), MIN(d), MAX(d) FROM test GROUP BY g ORDER BY g";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["a", "3.75", "1.875000", "1.25", "2.50"],
+        vec!["b", "9.25", "4.625000", "-0.75", "10.00"],
+    ];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT d, d + i, d * 2, d > 1, CAST(d AS DECIMAL(5, 1)) \
+               FROM test WHERE d IS NOT NULL ORDER BY d";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["-0.75", "3.25", "-1.50", "false", "-0.8"],
+        vec!["1.25", "2.25", "2.50", "true", "1.3"],
+        vec!["2.50", "4.50", "5.00", "true", "2.5"],
+        vec!["10.00", "15.00", "20.00", "true", "10.0"],
+    ];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT g, SUM(d) FROM test WHERE d > 2.0 GROUP BY g ORDER BY g";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["a", "2.50"], vec!["b", "10.00"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_repartitioned_many_batches() -> Result<()> {
+    // two partitions of 10 batches of 10 rows each, with the ids 0 to 199
+    let table = |id: &str, value: &str, factor: u32| {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(id, DataType::UInt32, false),
+            Field::new(value, DataType::UInt32, false),
+        ]));
+        let partitions = (0..2)
+            .map(|partition| {
+                (0..10)
+                    .map(|batch| {
+                        let ids = (0..10)
+                            .map(|row| partition * 100 + batch * 10 + row)
+                            .collect::<Vec<u32>>();
+                        let values = ids.iter().map(|id| id * factor).collect::<Vec<_>>();
+                        RecordBatch::try_new(
+                            schema.clone(),
+                            vec![
+                                Arc::new(UInt32Array::from(ids)),
+                                Arc::new(UInt32Array::from(values)),
+                            ],
+                        )
+                    })
+                    .collect::<arrow::error::Result<Vec<_>>>()
+            })
+            .collect::<arrow::error::Result<Vec<_>>>()?;
+        MemTable::new(schema, partitions)
+    };
+
+    // both sides of the join are repartitioned by hash, and their output partitions
+    // buffer many batches while the join and the sort consume them one at a time
+    let mut ctx = ExecutionContext::with_config(
+        ExecutionConfig::new()
+            .with_concurrency(4)
+            .with_hash_join_collect_left_threshold(0),
+    );
+    ctx.register_table("t1", Box::new(table("t1_id", "t1_v", 1)?));
+    ctx.register_table("t2", Box::new(table("t2_id", "t2_v", 2)?));
+
+    let sql = "SELECT t1_id, t2_v FROM t1 JOIN t2 ON t1_id = t2_id ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(actual.len(), 200);
+    assert_eq!(actual[0], vec!["0", "0"]);
+    assert_eq!(actual[199], vec!["199", "398"]);
+
+    // the output partitions are written one at a time
+    let sql = "SELECT t1_id, t2_v FROM t1 JOIN t2 ON t1_id = t2_id";
+    let plan = ctx.create_logical_plan(sql)?;
+    let plan = ctx.optimize(&plan)?;
+    let plan = ctx.create_physical_plan(&plan)?;
+    let tmp_dir = tempfile::tempdir()?;
+    let out_dir = tmp_dir.path().join("out").to_str().unwrap().to_string();
+    ctx.write_csv(plan.clone(), out_dir.clone()).await?;
+
+    let mut ctx = ExecutionContext::new();
+    ctx.register_csv(
+        "out",
+        &out_dir,
+        CsvReadOptions::new().schema(&plan.schema()),
+    )?;
+    let actual = execute(&mut ctx, "SELECT COUNT(t1_id), SUM(t2_v) FROM out").await;
+    assert_eq!(actual, vec![vec!["200", "39800"]]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_decimal_partitioned() -> Result<()> {
+    let t1_schema = Arc::new(Schema::new(vec![
+        Field::new("t1_k", DataType::Decimal(10, 2), false),
+        Field::new("t1_v", DataType::Int32, false),
+    ]));
+    let t1_batch = |keys: Vec<i128>, values: Vec<i32>| {
+        RecordBatch::try_new(
+            t1_schema.clone(),
+            vec![
+                Arc::new(DecimalArray::from_opt_vec(
+                    keys.into_iter().map(Some).collect(),
+                    10,
+                    2,
+                )),
+                Arc::new(Int32Array::from(values)),
+            ],
+        )
+    };
+    let t1 = MemTable::new(
+        t1_schema.clone(),
+        vec![
+            vec![t1_batch(vec![125, 250, 125], vec![1, 2, 3])?],
+            vec![t1_batch(vec![250, -75], vec![4, 5])?],
+        ],
+    )?;
+
+    let t2_schema = Arc::new(Schema::new(vec![
+        Field::new("t2_k", DataType::Decimal(10, 2), false),
+        Field::new("t2_name", DataType::Utf8, false),
+    ]));
+    let t2_data = RecordBatch::try_new(
+        t2_schema.clone(),
+        vec![
+            Arc::new(DecimalArray::from_opt_vec(
+                vec![Some(125), Some(250), Some(300)],
+                10,
+                2,
+            )),
+            Arc::new(StringArray::from(vec!["x", "y", "z"])),
+        ],
+    )?;
+    let t2 = MemTable::new(t2_schema, vec![vec![t2_data]])?;
+
+    // the groups and the sides of the join are partitioned by hash of their decimal keys
+    let mut ctx = ExecutionContext::with_config(
+        ExecutionConfig::new()
+            .with_concurrency(4)
+            .with_hash_join_collect_left_threshold(0),
+    );
+    ctx.register_table("t1", Box::new(t1));
+    ctx.register_table("t2", Box::new(t2));
+
+    let sql = "SELECT t1_k, COUNT(t1_v), SUM(t1_v) FROM t1 GROUP BY t1_k ORDER BY t1_k";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["-0.75", "1", "5"],
+        vec!["1.25", "2", "4"],
+        vec!["2.50", "2", "6"],
+    ];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT t1_v, t2_name FROM t1 JOIN t2 ON t1_k = t2_k ORDER BY t1_v";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["1", "x"],
+        vec!["2", "y"],
+        vec!["3", "x"],
+        vec!["4", "y"],
+    ];
+    assert_eq!(expected, actual);
+
+    // keys with another precision and scale match the keys of equal value
+    let t3_schema = Arc::new(Schema::new(vec![
+        Field::new("t3_k", DataType::Decimal(12, 3), false),
+        Field::new("t3_name", DataType::Utf8, false),
+    ]));
+    let t3_data = RecordBatch::try_new(
+        t3_schema.clone(),
+        vec![
+            Arc::new(DecimalArray::from_opt_vec(
+                vec![Some(1250), Some(2500), Some(1251)],
+                12,
+                3,
+            )),
+            Arc::new(StringArray::from(vec!["x", "y", "z"])),
+        ],
+    )?;
+    let t3 = MemTable::new(t3_schema, vec![vec![t3_data]])?;
+    ctx.register_table("t3", Box::new(t3));
+
+    let sql = "SELECT t1_v, t3_name FROM t1 JOIN t3 ON t1_k = t3_k ORDER BY t1_v";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
 #[tokio::test]
 async fn query_on_string_dictionary() -> Result<()> {
     // Test to ensure DataFusion can operate on dictionary types
@@ -237,6 +237,7 @@ impl OptimizerRule for TopKOptimizerRule {
             // here.
             LogicalPlan::Explain {
                 verbose,
+                analyze,
                 plan,
                 stringified_plans,
                 schema,
@@ -244,6 +245,7 @@ impl OptimizerRule for TopKOptimizerRule {
                 return optimize_explain(
                     self,
                     *verbose,
+                    *analyze,
                     &*plan,
                     stringified_plans,
                     &*schema,